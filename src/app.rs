@@ -1,19 +1,39 @@
-use crate::email::Email;
+use crate::config::RowDensity;
+use crate::email::{
+    DuplicateSet, Email, build_thread_ids, dedupe_emails, find_duplicates, intern_strings,
+    remove_ignored_threads, sender_matches, stale_inbox_emails,
+};
+use crate::habits::SenderHabits;
+use crate::highlight::Highlight;
+use crate::notes::SenderNotes;
+use crate::policy::{self, Policy};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
 
 /// Maximum number of undo entries to keep in history
 const MAX_UNDO_HISTORY: usize = 50;
 
 /// The grouping mode for emails
-#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+// The names are saved in the resume file, so they keep their By prefix
+#[allow(clippy::enum_variant_names)]
 pub enum GroupMode {
     #[default]
     BySenderEmail,
     ByDomain,
+    /// By Gmail label, under the first label by name for emails with several
+    ByLabel,
 }
 
+/// The group of emails without labels when grouping by label
+pub const NO_LABEL: &str = "(no label)";
+
 /// The current view state
-#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum View {
     #[default]
     GroupList,
@@ -21,10 +41,26 @@ pub enum View {
     Thread,
     UndoHistory,
     EmailBody,
+    Duplicates,
+}
+
+impl View {
+    /// The view's name as scripts and plugins see it
+    pub fn name(self) -> &'static str {
+        match self {
+            View::GroupList => "group_list",
+            View::EmailList => "email_list",
+            View::Thread => "thread",
+            View::UndoHistory => "undo_history",
+            View::EmailBody => "email_body",
+            View::Duplicates => "duplicates",
+        }
+    }
 }
 
 /// Filter for which emails/threads to display
-#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ThreadFilter {
     /// Show all emails (no filtering)
     #[default]
@@ -49,6 +85,9 @@ pub enum SelectionResult {
 pub enum UndoActionType {
     Archive,
     Delete,
+    Move,
+    /// Labelled (or moved to a folder) and archived in one step
+    File,
 }
 
 /// Context about what was affected by the action
@@ -71,43 +110,78 @@ pub struct UndoEntry {
     ///
     /// When dest_uid is available, we use fast UID-based restore; otherwise fall back to Message-ID search
     pub emails: Vec<(Option<String>, Option<u32>, String)>,
-    /// Where the emails are now: "[Gmail]/All Mail" or "[Gmail]/Trash"
+    /// Where the emails are now: "[Gmail]/All Mail", "[Gmail]/Trash" or the folder they
+    /// were moved to
     pub current_folder: String,
 }
 
+/// One email of an undo entry as the expanded entry lists it
+#[derive(Debug, Clone, PartialEq)]
+pub struct UndoEmailDetail {
+    pub subject: String,
+    pub from: String,
+    /// The folder undoing puts it back in
+    pub folder: String,
+}
+
 /// Represents a group of emails from the same sender
 #[derive(Debug, Clone, PartialEq)]
 pub struct EmailGroup {
     pub key: String,
-    pub emails: Vec<Email>,
+    /// Indices into the app's email list, sorted by date descending (newest first).
+    /// Resolve them with `App::group_emails` and `App::group_threads`.
+    pub email_indices: Vec<usize>,
 }
 
 impl EmailGroup {
     pub fn new(key: String) -> Self {
         Self {
             key,
-            emails: Vec::new(),
+            email_indices: Vec::new(),
         }
     }
 
     pub fn count(&self) -> usize {
-        self.emails.len()
+        self.email_indices.len()
     }
 
-    /// Returns the number of unique threads in this group
-    pub fn thread_count(&self) -> usize {
-        self.threads().len()
+    pub fn is_empty(&self) -> bool {
+        self.email_indices.is_empty()
     }
+}
 
-    /// Returns the newest email from each thread in this group.
-    /// Since emails are sorted by date descending, we take the first email for each thread_id.
-    pub fn threads(&self) -> Vec<&Email> {
-        let mut seen_threads = HashSet::new();
-        self.emails
-            .iter()
-            .filter(|email| seen_threads.insert(&email.thread_id))
-            .collect()
-    }
+/// Groups and threads marked to keep during an "archive everything except" sweep
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SweepMarks {
+    /// Group keys whose threads are kept
+    pub groups: HashSet<String>,
+    /// Thread IDs kept individually
+    pub threads: HashSet<String>,
+}
+
+/// The group list under the current filters, worked out once and reused by every frame
+/// until the groups or the filters change
+#[derive(Debug, Default)]
+pub struct GroupListSummary {
+    /// Indices into `App::groups` of the groups the filters show, in display order
+    pub indices: Vec<usize>,
+    /// Emails across those groups, counting whole threads
+    pub total_emails: usize,
+    /// How many of those groups have new mail
+    pub new_groups: usize,
+    /// Groups min_group_size hides that the other filters would show
+    pub hidden_small: usize,
+}
+
+/// What a GroupListSummary depends on; a cached one is reused while this is unchanged
+#[derive(Debug, Clone, PartialEq)]
+struct GroupListKey {
+    groups_version: u64,
+    new_emails: usize,
+    thread_filter: ThreadFilter,
+    text_filter: Option<String>,
+    min_group_size: usize,
+    show_small_groups: bool,
 }
 
 /// The main application state
@@ -124,6 +198,13 @@ pub struct App {
     multi_message_threads: HashSet<String>,
     /// Cache of email counts per thread_id (for calculating full thread counts)
     thread_email_counts: HashMap<String, usize>,
+    /// Position of each group key in `groups`, rebuilt by `sort_groups` so a group can be
+    /// found by key without scanning the list
+    group_positions: HashMap<String, usize>,
+    /// Bumped whenever `groups` changes, so the cached group list summary is rebuilt
+    groups_version: u64,
+    /// The last group list summary and the state it was built from
+    group_list_cache: RefCell<Option<(GroupListKey, Rc<GroupListSummary>)>>,
     /// The user's email address (used to filter out sent emails from groups)
     user_email: Option<String>,
     /// Filter for which threads to display
@@ -132,14 +213,39 @@ pub struct App {
     pub undo_history: Vec<UndoEntry>,
     /// Selected index in undo history view
     pub selected_undo: usize,
+    /// The first email shown of the selected undo entry's emails, when it's expanded
+    pub undo_detail_scroll: Option<usize>,
+    /// Sender and subject of the emails in the undo history, by Message-ID, noted as
+    /// entries are added because the emails are gone from the list by the time they're
+    /// looked at
+    undo_summaries: HashMap<String, (String, String)>,
     /// View to return to after closing undo history
     previous_view: Option<View>,
     /// The group key we're currently viewing (to preserve view after deletions)
     viewing_group_key: Option<String>,
+    /// The cursor thread of each group left this session, by group key, so going back
+    /// to a group picks up where it left off
+    group_cursors: HashMap<String, String>,
     /// Whether emails have been loaded at least once (to distinguish from inbox zero)
     emails_loaded: bool,
     /// Set of selected email IDs (for multi-select operations)
     selected_emails: HashSet<String>,
+    /// Set of selected group keys (for multi-group operations in the group list)
+    selected_groups: HashSet<String>,
+    /// Keep marks while sweeping the inbox (None when not sweeping)
+    sweep: Option<SweepMarks>,
+    /// Groups marked done or skipped for this session; they sort below the rest
+    done_groups: HashSet<String>,
+    /// Threads hidden for this session with "not now"; new mail in them stays hidden too
+    dismissed_threads: HashSet<String>,
+    /// The hidden threads' emails, kept so they can be brought back
+    dismissed: Vec<Email>,
+    /// IDs of emails that arrived with a refresh or resync after the first load
+    new_emails: HashSet<String>,
+    /// Groups with fewer emails than this are hidden (0 or 1 shows every group)
+    pub min_group_size: usize,
+    /// When true, groups under min_group_size are shown anyway
+    show_small_groups: bool,
     /// Scroll position for text view
     pub text_view_scroll: usize,
     /// ID of the email being viewed in text view (for body caching)
@@ -148,6 +254,52 @@ pub struct App {
     group_text_filter: Option<String>,
     /// Active text filter query for EmailList view (None = no filter active)
     email_text_filter: Option<String>,
+    /// Sort the email list by spam score (highest first) instead of date
+    sort_by_spam_score: bool,
+    /// When true, thread view shows every message body inline, oldest first
+    pub thread_inline_bodies: bool,
+    /// One- or two-line rows in the email list
+    pub row_density: RowDensity,
+    /// Mark emails that fail DKIM, SPF or DMARC in the email list
+    pub auth_warnings_in_list: bool,
+    /// When true, thread view shows a To/Cc recipients column
+    pub thread_recipients_column: bool,
+    /// Extra line scroll within the selected message when bodies are inline
+    pub thread_body_scroll: usize,
+    /// Email IDs collapsed to a single header line in the inline thread view
+    collapsed_thread_emails: HashSet<String>,
+    /// Duplicate sets found when the duplicates view was opened (refreshed after removals)
+    pub duplicate_sets: Vec<DuplicateSet>,
+    /// Selected index in the duplicates view
+    pub selected_duplicate: usize,
+    /// Age cutoff in days for archiving old inbox mail
+    pub archive_older_than_days: u32,
+    /// Senders or domains never archived by age
+    pub protected_senders: Vec<String>,
+    /// Rules that color matching rows in the lists
+    pub highlights: Vec<Highlight>,
+    /// The account's name and accent_color, drawn on the main view's border (default: none)
+    pub accent: Option<(String, ratatui::style::Color)>,
+    /// Older emails the next "load more" page would fetch (0 when all are loaded)
+    pub load_more_count: usize,
+    /// Whether the account has labels to group by; m skips label grouping without them
+    pub labels_available: bool,
+    /// The lowest UID fetched from each folder, where the next "load more" page starts
+    lowest_uids: HashMap<String, u32>,
+    /// Short notes the user attached to sender groups
+    pub notes: SenderNotes,
+    /// How each sender's mail has been handled, for the "do the usual" key
+    pub habits: SenderHabits,
+    /// Senders or domains whose threads are hidden entirely
+    pub ignored_senders: Vec<String>,
+    /// Senders or domains postponed to a later session; hidden like ignored ones for now
+    pub postponed_senders: Vec<String>,
+    /// Emails archived since startup (undone actions still count)
+    pub archived_count: usize,
+    /// Emails deleted since startup (undone actions still count)
+    pub deleted_count: usize,
+    /// True once another client has changed a loaded folder, until the view is resynced
+    mailbox_changed: bool,
 }
 
 impl Default for App {
@@ -168,18 +320,55 @@ impl App {
             emails: Vec::new(),
             multi_message_threads: HashSet::new(),
             thread_email_counts: HashMap::new(),
+            group_positions: HashMap::new(),
+            groups_version: 0,
+            group_list_cache: RefCell::new(None),
             user_email: None,
             thread_filter: ThreadFilter::All,
             undo_history: Vec::new(),
             selected_undo: 0,
+            undo_detail_scroll: None,
+            undo_summaries: HashMap::new(),
             previous_view: None,
             viewing_group_key: None,
+            group_cursors: HashMap::new(),
             emails_loaded: false,
             selected_emails: HashSet::new(),
+            selected_groups: HashSet::new(),
+            sweep: None,
+            done_groups: HashSet::new(),
+            dismissed_threads: HashSet::new(),
+            dismissed: Vec::new(),
+            new_emails: HashSet::new(),
+            min_group_size: 0,
+            show_small_groups: false,
             text_view_scroll: 0,
             viewing_email_id: None,
             group_text_filter: None,
             email_text_filter: None,
+            sort_by_spam_score: false,
+            thread_inline_bodies: false,
+            row_density: RowDensity::default(),
+            auth_warnings_in_list: false,
+            thread_recipients_column: false,
+            thread_body_scroll: 0,
+            collapsed_thread_emails: HashSet::new(),
+            duplicate_sets: Vec::new(),
+            selected_duplicate: 0,
+            archive_older_than_days: 365,
+            protected_senders: Vec::new(),
+            highlights: Vec::new(),
+            accent: None,
+            load_more_count: 0,
+            labels_available: true,
+            lowest_uids: HashMap::new(),
+            notes: SenderNotes::default(),
+            habits: SenderHabits::default(),
+            ignored_senders: Vec::new(),
+            postponed_senders: Vec::new(),
+            archived_count: 0,
+            deleted_count: 0,
+            mailbox_changed: false,
         }
     }
 
@@ -188,6 +377,11 @@ impl App {
         self.user_email = Some(email);
     }
 
+    /// Returns the user's email address, if known
+    pub fn user_email(&self) -> Option<&str> {
+        self.user_email.as_deref()
+    }
+
     /// Returns whether emails have been loaded at least once
     pub fn has_loaded_emails(&self) -> bool {
         self.emails_loaded
@@ -195,11 +389,179 @@ impl App {
 
     /// Sets the emails and regroups them according to current mode
     pub fn set_emails(&mut self, emails: Vec<Email>) {
-        self.emails = emails;
+        self.lowest_uids = lowest_uids(&emails);
+        (self.dismissed, self.emails) = emails
+            .into_iter()
+            .partition(|e| self.dismissed_threads.contains(&e.thread_id));
+        remove_ignored_threads(&mut self.emails, &self.ignored_senders);
+        remove_ignored_threads(&mut self.emails, &self.postponed_senders);
+        intern_strings(&mut self.emails);
+        let loaded: HashSet<&str> = self.emails.iter().map(|e| e.id.as_str()).collect();
+        self.new_emails.retain(|id| loaded.contains(id.as_str()));
         self.emails_loaded = true;
         self.regroup();
     }
 
+    /// Replaces the emails with a fresh fetch. After the first load, emails that weren't
+    /// loaded before are marked new. Returns how many were.
+    pub fn reload_emails(&mut self, emails: Vec<Email>) -> usize {
+        let known = self.emails_loaded.then(|| self.arrival_keys());
+        self.set_emails(emails);
+        known.map_or(0, |known| self.mark_new_arrivals(&known))
+    }
+
+    /// Drops the loaded emails, e.g. cached ones whose UIDs the server no longer honours,
+    /// so the next load starts over rather than marking everything new
+    pub fn clear_emails(&mut self) {
+        while self.view != View::GroupList {
+            self.exit();
+        }
+        self.set_emails(Vec::new());
+        self.emails_loaded = false;
+    }
+
+    /// Identifies the loaded emails (hidden ones too) across reloads: by Message-ID, since
+    /// an undone archive comes back with a new UID, or by ID without one
+    fn arrival_keys(&self) -> HashSet<String> {
+        self.emails
+            .iter()
+            .chain(&self.dismissed)
+            .map(arrival_key)
+            .collect()
+    }
+
+    /// Marks the loaded emails missing from `known` as new. Returns how many there were.
+    fn mark_new_arrivals(&mut self, known: &HashSet<String>) -> usize {
+        let arrived: Vec<String> = self
+            .emails
+            .iter()
+            .filter(|e| !known.contains(&arrival_key(e)))
+            .map(|e| e.id.clone())
+            .collect();
+        let count = arrived.len();
+        self.new_emails.extend(arrived);
+        count
+    }
+
+    /// Whether an email arrived after the first load
+    pub fn is_new(&self, email_id: &str) -> bool {
+        self.new_emails.contains(email_id)
+    }
+
+    /// Whether a group has mail that arrived after the first load
+    pub fn group_has_new(&self, group: &EmailGroup) -> bool {
+        !self.new_emails.is_empty() && self.group_emails(group).any(|e| self.is_new(&e.id))
+    }
+
+    /// Moves to the next shown group after the cursor whose name starts with `letter`
+    /// (ignoring case and leading quotes or symbols), wrapping around. Returns false when
+    /// there is none.
+    pub fn jump_to_group_starting_with(&mut self, letter: char) -> bool {
+        let summary = self.group_list_summary();
+        let indices = &summary.indices;
+        if indices.is_empty() {
+            return false;
+        }
+        let start = indices
+            .binary_search(&self.selected_group)
+            .map_or(0, |i| i + 1);
+        let letter = letter.to_lowercase().next().unwrap_or(letter);
+        let found = (0..indices.len())
+            .map(|i| indices[(start + i) % indices.len()])
+            .find(|&index| {
+                self.groups[index]
+                    .key
+                    .chars()
+                    .find(|c| c.is_alphanumeric())
+                    .and_then(|c| c.to_lowercase().next())
+                    == Some(letter)
+            });
+        match found {
+            Some(index) => {
+                self.selected_group = index;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Moves to the next group (group list) or thread (email list) with new mail after
+    /// the cursor, wrapping around. Returns false when there is none.
+    pub fn jump_to_new(&mut self) -> bool {
+        match self.view {
+            View::GroupList => {
+                let groups = self.filtered_groups();
+                let current = self.current_group().map(|g| g.key.as_str());
+                let start = groups
+                    .iter()
+                    .position(|g| Some(g.key.as_str()) == current)
+                    .map_or(0, |i| i + 1);
+                let Some(key) = (0..groups.len())
+                    .map(|i| groups[(start + i) % groups.len()])
+                    .find(|g| self.group_has_new(g))
+                    .map(|g| g.key.clone())
+                else {
+                    return false;
+                };
+                if let Some(index) = self.groups.iter().position(|g| g.key == key) {
+                    self.selected_group = index;
+                }
+                true
+            }
+            View::EmailList => {
+                let threads = self.filtered_threads_in_current_group();
+                let start = self.selected_email.map_or(0, |i| i + 1);
+                let Some(index) = (0..threads.len())
+                    .map(|i| (start + i) % threads.len())
+                    .find(|&i| self.is_new(&threads[i].id))
+                else {
+                    return false;
+                };
+                self.selected_email = Some(index);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Merges mail fetched from before the fetch window into the loaded emails and
+    /// rebuilds threads across both. Returns how many emails were added.
+    pub fn merge_older_emails(&mut self, older: Vec<Email>, merge_subject_threads: bool) -> usize {
+        let mut emails = std::mem::take(&mut self.emails);
+        // Dismissed mail takes part in threading; set_emails hides it again
+        emails.extend(std::mem::take(&mut self.dismissed));
+        let before = emails.len();
+        emails.extend(older);
+        dedupe_emails(&mut emails);
+        build_thread_ids(&mut emails);
+        if merge_subject_threads {
+            crate::email::merge_subject_threads(&mut emails);
+        }
+        let added = emails.len() - before;
+        let previous = std::mem::take(&mut self.lowest_uids);
+        self.set_emails(emails);
+        // Keep the floor of pages whose emails have since been archived
+        for (folder, uid) in previous {
+            let lowest = self.lowest_uids.entry(folder).or_insert(uid);
+            *lowest = (*lowest).min(uid);
+        }
+        added
+    }
+
+    /// Returns the lowest UID fetched from each folder, for paging further back
+    pub fn lowest_uids(&self) -> &HashMap<String, u32> {
+        &self.lowest_uids
+    }
+
+    /// Counts the loaded emails (hidden ones too) per folder
+    pub fn loaded_per_folder(&self) -> HashMap<String, u32> {
+        let mut counts = HashMap::new();
+        for email in self.emails.iter().chain(&self.dismissed) {
+            *counts.entry(email.source_folder.to_string()).or_default() += 1;
+        }
+        counts
+    }
+
     /// Rebuilds the cache of thread IDs with multiple messages and email counts per thread
     fn rebuild_multi_message_cache(&mut self) {
         // Count emails per thread_id
@@ -222,45 +584,74 @@ impl App {
             .collect();
     }
 
+    /// Returns the group key for an email, or None for the user's own sent emails.
+    /// Those are skipped from grouping but remain in self.emails for thread view and operations.
+    fn group_key(&self, email: &Email) -> Option<String> {
+        if let Some(ref user_email) = self.user_email
+            && email.from_email.eq_ignore_ascii_case(user_email)
+        {
+            return None;
+        }
+
+        Some(match self.group_mode {
+            GroupMode::BySenderEmail => email.from_email.to_string(),
+            GroupMode::ByDomain => email.from_domain.to_string(),
+            GroupMode::ByLabel => email
+                .labels
+                .first()
+                .cloned()
+                .unwrap_or_else(|| NO_LABEL.to_string()),
+        })
+    }
+
     /// Regroups emails according to the current group mode
     fn regroup(&mut self) {
         self.rebuild_multi_message_cache();
-        let mut group_map: HashMap<String, Vec<Email>> = HashMap::new();
+        let mut group_map: HashMap<String, Vec<usize>> = HashMap::new();
 
-        for email in &self.emails {
-            // Skip user's own sent emails from grouping
-            // They remain in self.emails for thread view and operations
-            if let Some(ref user_email) = self.user_email
-                && email.from_email.eq_ignore_ascii_case(user_email)
-            {
-                continue;
+        for (idx, email) in self.emails.iter().enumerate() {
+            if let Some(key) = self.group_key(email) {
+                group_map.entry(key).or_default().push(idx);
             }
-
-            let key = match self.group_mode {
-                GroupMode::BySenderEmail => email.from_email.clone(),
-                GroupMode::ByDomain => email.from_domain.clone(),
-            };
-            group_map.entry(key).or_default().push(email.clone());
         }
 
+        let emails = &self.emails;
         self.groups = group_map
             .into_iter()
-            .map(|(key, mut emails)| {
+            .map(|(key, mut indices)| {
                 // Sort emails by date descending (newest first)
-                emails.sort_by(|a, b| b.date.cmp(&a.date));
+                indices.sort_by_key(|&i| std::cmp::Reverse(emails[i].date));
                 let mut group = EmailGroup::new(key);
-                group.emails = emails;
+                group.email_indices = indices;
                 group
             })
             .collect();
 
-        // Sort groups by email count (descending), then alphabetically (ascending) as tie-breaker
-        self.groups
-            .sort_by_key(|g| (std::cmp::Reverse(g.count()), g.key.to_lowercase()));
+        self.sort_groups();
+    }
+
+    /// Sorts groups and refreshes the group positions and the viewed group's index
+    fn sort_groups(&mut self) {
+        // Sort groups by email count (descending), then alphabetically (ascending) as tie-breaker,
+        // with groups marked done after all the others
+        self.groups.sort_by_key(|g| {
+            (
+                self.done_groups.contains(&g.key),
+                std::cmp::Reverse(g.count()),
+                g.key.to_lowercase(),
+            )
+        });
+        self.group_positions = self
+            .groups
+            .iter()
+            .enumerate()
+            .map(|(idx, g)| (g.key.clone(), idx))
+            .collect();
+        self.groups_version += 1;
 
         // If we're viewing a specific group, find its new index after sorting
         if let Some(ref key) = self.viewing_group_key.clone() {
-            if let Some(idx) = self.groups.iter().position(|g| &g.key == key) {
+            if let Some(&idx) = self.group_positions.get(key) {
                 self.selected_group = idx;
             } else {
                 // Group no longer exists - update viewing_group_key to current selection
@@ -277,17 +668,77 @@ impl App {
         }
     }
 
-    /// Toggles between BySenderEmail and ByDomain grouping modes
+    /// Removes emails matching `predicate` and returns them. Thread counts are updated
+    /// for the removed emails only, but every group's email indices are remapped to the
+    /// compacted email list and the groups are re-sorted, which skips recomputing group
+    /// keys and re-sorting each group's emails by date.
+    fn remove_emails_where(&mut self, predicate: impl Fn(&Email) -> bool) -> Vec<Email> {
+        let mut removed = Vec::new();
+        let mut kept = Vec::with_capacity(self.emails.len());
+        // New index of each old email index (None once removed)
+        let mut new_positions: Vec<Option<usize>> = Vec::with_capacity(self.emails.len());
+        for email in std::mem::take(&mut self.emails) {
+            if predicate(&email) {
+                new_positions.push(None);
+                removed.push(email);
+            } else {
+                new_positions.push(Some(kept.len()));
+                kept.push(email);
+            }
+        }
+        self.emails = kept;
+        if removed.is_empty() {
+            return removed;
+        }
+
+        for email in &removed {
+            if let Some(count) = self.thread_email_counts.get_mut(&email.thread_id) {
+                *count -= 1;
+                if *count <= 1 {
+                    self.multi_message_threads.remove(&email.thread_id);
+                }
+                if *count == 0 {
+                    self.thread_email_counts.remove(&email.thread_id);
+                }
+            }
+        }
+
+        for group in &mut self.groups {
+            group.email_indices = group
+                .email_indices
+                .iter()
+                .filter_map(|&i| new_positions[i])
+                .collect();
+        }
+        self.groups.retain(|g| !g.is_empty());
+        self.sort_groups();
+
+        removed
+    }
+
+    /// Cycles through the BySenderEmail, ByDomain and ByLabel grouping modes, skipping
+    /// ByLabel when the account has no labels
     pub fn toggle_group_mode(&mut self) {
-        self.group_mode = match self.group_mode {
+        self.set_group_mode(match self.group_mode {
             GroupMode::BySenderEmail => GroupMode::ByDomain,
-            GroupMode::ByDomain => GroupMode::BySenderEmail,
-        };
+            GroupMode::ByDomain if self.labels_available => GroupMode::ByLabel,
+            GroupMode::ByDomain | GroupMode::ByLabel => GroupMode::BySenderEmail,
+        });
+    }
+
+    /// Regroups the emails by `mode`, starting over at the top of the group list
+    pub fn set_group_mode(&mut self, mode: GroupMode) {
+        self.group_mode = mode;
         self.regroup();
         self.selected_group = 0;
         self.selected_email = None;
         self.selected_thread_email = None;
         self.clear_selection();
+        self.clear_group_selection();
+        // Group keys change with the mode, so group keep marks no longer apply
+        if let Some(marks) = self.sweep.as_mut() {
+            marks.groups.clear();
+        }
     }
 
     /// Selects the next item based on current view
@@ -298,6 +749,11 @@ impl App {
             View::Thread => self.select_next_thread_email(),
             View::UndoHistory => self.select_next_undo(),
             View::EmailBody => self.scroll_text_view_down(1),
+            View::Duplicates => {
+                if self.selected_duplicate + 1 < self.duplicate_sets.len() {
+                    self.selected_duplicate += 1;
+                }
+            }
         }
     }
 
@@ -309,6 +765,9 @@ impl App {
             View::Thread => self.select_previous_thread_email(),
             View::UndoHistory => self.select_previous_undo(),
             View::EmailBody => self.scroll_text_view_up(1),
+            View::Duplicates => {
+                self.selected_duplicate = self.selected_duplicate.saturating_sub(1);
+            }
         }
     }
 
@@ -351,13 +810,70 @@ impl App {
                 }
             }
             View::UndoHistory => {
-                if !self.undo_history.is_empty() {
+                if self.undo_detail_scroll.is_some() {
+                    self.undo_detail_scroll = Some(0);
+                } else if !self.undo_history.is_empty() {
                     self.selected_undo = 0;
                 }
             }
             View::EmailBody => {
                 self.text_view_scroll = 0;
             }
+            View::Duplicates => {
+                self.selected_duplicate = 0;
+            }
+        }
+    }
+
+    /// Selects the visible group with the most emails (as counted in the group list),
+    /// preferring the one listed first on ties
+    pub fn select_largest_group(&mut self) {
+        let mut largest: Option<(&str, usize)> = None;
+        for group in self.filtered_groups() {
+            if self.done_groups.contains(&group.key) {
+                continue;
+            }
+            let count = self.full_thread_email_count_for_group(group);
+            if largest.is_none_or(|(_, most)| count > most) {
+                largest = Some((&group.key, count));
+            }
+        }
+        if let Some((key, _)) = largest
+            && let Some(index) = self.groups.iter().position(|g| g.key == key)
+        {
+            self.selected_group = index;
+        }
+    }
+
+    /// Opens the email list of the group at `index` in the visible (filtered) list.
+    /// Returns false when the list is shorter than that.
+    pub fn enter_group_at(&mut self, index: usize) -> bool {
+        let Some(key) = self.filtered_groups().get(index).map(|g| g.key.clone()) else {
+            return false;
+        };
+        let Some(&position) = self.group_positions.get(&key) else {
+            return false;
+        };
+        self.selected_group = position;
+        self.enter();
+        true
+    }
+
+    /// Selects a visible group picked by `roll`, a random number from the caller.
+    /// The current group is skipped when there are others, so each roll moves.
+    pub fn select_random_group(&mut self, roll: u64) {
+        let current = self.current_group().map(|g| g.key.as_str());
+        let candidates: Vec<&str> = self
+            .filtered_groups()
+            .into_iter()
+            .map(|g| g.key.as_str())
+            .filter(|&key| Some(key) != current && !self.done_groups.contains(key))
+            .collect();
+        let Some(&key) = candidates.get((roll % candidates.len().max(1) as u64) as usize) else {
+            return;
+        };
+        if let Some(index) = self.groups.iter().position(|g| g.key == key) {
+            self.selected_group = index;
         }
     }
 
@@ -387,7 +903,9 @@ impl App {
                 }
             }
             View::UndoHistory => {
-                if !self.undo_history.is_empty() {
+                if self.undo_detail_scroll.is_some() {
+                    self.undo_detail_scroll = Some(self.undo_detail_len().saturating_sub(1));
+                } else if !self.undo_history.is_empty() {
                     self.selected_undo = self.undo_history.len() - 1;
                 }
             }
@@ -395,6 +913,9 @@ impl App {
                 // Scroll to bottom - will be clamped by renderer
                 self.text_view_scroll = usize::MAX;
             }
+            View::Duplicates => {
+                self.selected_duplicate = self.duplicate_sets.len().saturating_sub(1);
+            }
         }
     }
 
@@ -454,17 +975,108 @@ impl App {
             View::EmailBody => {
                 // Scroll position is managed by the renderer
             }
+            View::Duplicates => {
+                if self.selected_duplicate >= self.duplicate_sets.len() {
+                    self.selected_duplicate = self.duplicate_sets.len().saturating_sub(1);
+                }
+            }
         }
     }
 
     /// Checks if a group matches all active filters (thread filter and text filter)
     fn group_matches_all_filters(&self, group: &EmailGroup) -> bool {
-        let matches_thread_filter = match self.thread_filter {
+        self.group_matches_thread_filter(group)
+            && self.group_matches_text_filter(group)
+            && !self.is_hidden_small_group(group)
+    }
+
+    /// Checks if a group has threads the thread filter shows
+    fn group_matches_thread_filter(&self, group: &EmailGroup) -> bool {
+        match self.thread_filter {
             ThreadFilter::All => true,
             ThreadFilter::OnlyThreads => self.group_has_multi_message_threads(group),
             ThreadFilter::NoThreads => self.group_has_single_message_threads(group),
+        }
+    }
+
+    /// Returns true when a group is hidden for having fewer emails than min_group_size
+    fn is_hidden_small_group(&self, group: &EmailGroup) -> bool {
+        !self.show_small_groups && group.count() < self.min_group_size
+    }
+
+    /// Returns how many groups min_group_size is hiding that the other filters would show
+    pub fn hidden_small_group_count(&self) -> usize {
+        self.group_list_summary().hidden_small
+    }
+
+    /// Returns the group list under the current filters. It's rebuilt only after the
+    /// groups or the filters change, so drawing a frame with thousands of groups stays cheap.
+    pub fn group_list_summary(&self) -> Rc<GroupListSummary> {
+        let key = GroupListKey {
+            groups_version: self.groups_version,
+            new_emails: self.new_emails.len(),
+            thread_filter: self.thread_filter,
+            text_filter: self.group_text_filter.clone(),
+            min_group_size: self.min_group_size,
+            show_small_groups: self.show_small_groups,
         };
-        matches_thread_filter && self.group_matches_text_filter(group)
+        if let Some((cached_key, summary)) = self.group_list_cache.borrow().as_ref()
+            && *cached_key == key
+        {
+            return Rc::clone(summary);
+        }
+        let summary = Rc::new(self.build_group_list_summary());
+        *self.group_list_cache.borrow_mut() = Some((key, Rc::clone(&summary)));
+        summary
+    }
+
+    fn build_group_list_summary(&self) -> GroupListSummary {
+        let mut summary = GroupListSummary::default();
+        for (index, group) in self.groups.iter().enumerate() {
+            if self.group_matches_all_filters(group) {
+                summary.indices.push(index);
+                summary.total_emails += self.full_thread_email_count_for_group(group);
+                if self.group_has_new(group) {
+                    summary.new_groups += 1;
+                }
+            } else if !self.show_small_groups
+                && group.count() < self.min_group_size
+                && self.group_matches_thread_filter(group)
+                && self.group_matches_text_filter(group)
+            {
+                summary.hidden_small += 1;
+            }
+        }
+        summary
+    }
+
+    /// Counts the groups in `keys` that the filters show, without walking the group list
+    fn count_shown_groups(&self, keys: &HashSet<String>) -> usize {
+        keys.iter()
+            .filter_map(|key| self.group_positions.get(key))
+            .filter(|&&index| self.group_matches_all_filters(&self.groups[index]))
+            .count()
+    }
+
+    /// Returns how many selected groups the filters show
+    pub fn shown_selected_group_count(&self) -> usize {
+        self.count_shown_groups(&self.selected_groups)
+    }
+
+    /// Returns how many groups marked done the filters show
+    pub fn shown_done_group_count(&self) -> usize {
+        self.count_shown_groups(&self.done_groups)
+    }
+
+    /// Shows or hides the groups under min_group_size. Returns whether they're now shown,
+    /// or None when no minimum is set.
+    pub fn toggle_small_groups(&mut self) -> Option<bool> {
+        if self.min_group_size <= 1 {
+            return None;
+        }
+        self.show_small_groups = !self.show_small_groups;
+        self.ensure_valid_selection();
+        Some(self.show_small_groups)
     }
 
     /// Selects the next group in the list, skipping groups hidden by any active filter
@@ -514,13 +1126,14 @@ impl App {
 
     /// Selects the next email in thread view
     fn select_next_thread_email(&mut self) {
-        let thread_emails = self.current_thread_emails();
-        if thread_emails.is_empty() {
+        let thread_len = self.current_thread_emails().len();
+        if thread_len == 0 {
             return;
         }
+        self.thread_body_scroll = 0;
 
         self.selected_thread_email = match self.selected_thread_email {
-            Some(idx) if idx < thread_emails.len() - 1 => Some(idx + 1),
+            Some(idx) if idx < thread_len - 1 => Some(idx + 1),
             Some(idx) => Some(idx),
             None => Some(0),
         };
@@ -528,6 +1141,7 @@ impl App {
 
     /// Selects the previous email in thread view
     fn select_previous_thread_email(&mut self) {
+        self.thread_body_scroll = 0;
         self.selected_thread_email = match self.selected_thread_email {
             Some(idx) if idx > 0 => Some(idx - 1),
             Some(idx) => Some(idx),
@@ -535,29 +1149,74 @@ impl App {
         };
     }
 
-    /// Selects the next item in undo history
+    /// Selects the next item in undo history, or scrolls the expanded entry's emails
     fn select_next_undo(&mut self) {
-        if !self.undo_history.is_empty() && self.selected_undo < self.undo_history.len() - 1 {
+        if let Some(scroll) = self.undo_detail_scroll {
+            if scroll + 1 < self.undo_detail_len() {
+                self.undo_detail_scroll = Some(scroll + 1);
+            }
+        } else if !self.undo_history.is_empty() && self.selected_undo < self.undo_history.len() - 1
+        {
             self.selected_undo += 1;
         }
     }
 
-    /// Selects the previous item in undo history
+    /// Selects the previous item in undo history, or scrolls the expanded entry's emails
     fn select_previous_undo(&mut self) {
-        if self.selected_undo > 0 {
+        if let Some(scroll) = self.undo_detail_scroll {
+            self.undo_detail_scroll = Some(scroll.saturating_sub(1));
+        } else if self.selected_undo > 0 {
             self.selected_undo -= 1;
         }
     }
 
+    /// Expands the selected undo entry to list its emails, or collapses it again
+    pub fn toggle_undo_detail(&mut self) {
+        self.undo_detail_scroll = match self.undo_detail_scroll {
+            None if self.current_undo_entry().is_some() => Some(0),
+            _ => None,
+        };
+    }
+
+    /// How many emails the selected undo entry holds
+    fn undo_detail_len(&self) -> usize {
+        self.current_undo_entry().map_or(0, |e| e.emails.len())
+    }
+
+    /// The emails of an undo entry, with the sender and subject they had when the
+    /// entry was added
+    pub fn undo_entry_details(&self, entry: &UndoEntry) -> Vec<UndoEmailDetail> {
+        entry
+            .emails
+            .iter()
+            .map(|(message_id, _, folder)| {
+                let (from, subject) = match message_id
+                    .as_ref()
+                    .and_then(|id| self.undo_summaries.get(id))
+                {
+                    Some((from, subject)) => (from.clone(), subject.clone()),
+                    None => (String::new(), "(unknown email)".to_string()),
+                };
+                UndoEmailDetail {
+                    subject,
+                    from,
+                    folder: folder.clone(),
+                }
+            })
+            .collect()
+    }
+
     /// Enters the undo history view
     pub fn enter_undo_history(&mut self) {
         self.previous_view = Some(self.view);
         self.view = View::UndoHistory;
         self.selected_undo = 0;
+        self.undo_detail_scroll = None;
     }
 
     /// Exits the undo history view and returns to the previous view
     pub fn exit_undo_history(&mut self) {
+        self.undo_detail_scroll = None;
         if let Some(prev) = self.previous_view.take() {
             self.view = prev;
         } else {
@@ -565,95 +1224,380 @@ impl App {
         }
     }
 
-    /// Adds an entry to the undo history (at the front, newest first)
-    pub fn push_undo(&mut self, entry: UndoEntry) {
-        self.undo_history.insert(0, entry);
-        // Trim to max size
-        if self.undo_history.len() > MAX_UNDO_HISTORY {
-            self.undo_history.truncate(MAX_UNDO_HISTORY);
-        }
+    /// Enters the duplicates view, scanning loaded emails for copies of the same message
+    pub fn enter_duplicates(&mut self) {
+        self.previous_view = Some(self.view);
+        self.view = View::Duplicates;
+        self.duplicate_sets = find_duplicates(&self.emails);
+        self.selected_duplicate = 0;
     }
 
-    /// Removes and returns the undo entry at the given index
-    pub fn pop_undo(&mut self, index: usize) -> Option<UndoEntry> {
-        if index < self.undo_history.len() {
-            let entry = self.undo_history.remove(index);
-            // Adjust selected_undo if needed
-            if self.selected_undo >= self.undo_history.len() && !self.undo_history.is_empty() {
-                self.selected_undo = self.undo_history.len() - 1;
-            }
-            Some(entry)
+    /// Exits the duplicates view and returns to the previous view
+    pub fn exit_duplicates(&mut self) {
+        self.duplicate_sets.clear();
+        if let Some(prev) = self.previous_view.take() {
+            self.view = prev;
         } else {
-            None
+            self.view = View::GroupList;
         }
     }
 
-    /// Returns the number of entries in undo history
-    #[cfg(test)]
-    pub fn undo_history_len(&self) -> usize {
-        self.undo_history.len()
+    /// Returns the redundant copies in the selected duplicate set, or in every set
+    pub fn duplicate_extras(&self, all_sets: bool) -> Vec<&Email> {
+        if all_sets {
+            self.duplicate_sets
+                .iter()
+                .flat_map(|set| set.extras())
+                .collect()
+        } else {
+            self.duplicate_sets
+                .get(self.selected_duplicate)
+                .map(|set| set.extras().iter().collect())
+                .unwrap_or_default()
+        }
     }
 
-    /// Returns the currently selected undo entry, if any
-    pub fn current_undo_entry(&self) -> Option<&UndoEntry> {
-        self.undo_history.get(self.selected_undo)
+    /// Returns inbox emails older than the age cutoff, excluding flagged mail and protected senders
+    pub fn old_inbox_emails(&self) -> Vec<&Email> {
+        let cutoff =
+            chrono::Utc::now() - chrono::Duration::days(i64::from(self.archive_older_than_days));
+        stale_inbox_emails(&self.emails, cutoff, &self.protected_senders)
     }
 
-    /// Returns the view to return to after closing undo history
-    pub fn previous_view(&self) -> Option<View> {
-        self.previous_view
+    /// Returns a group's inbox emails dated before the cutoff, excluding flagged mail and
+    /// protected senders
+    pub fn group_emails_older_than(
+        &self,
+        group_key: &str,
+        cutoff: chrono::DateTime<chrono::Utc>,
+    ) -> Vec<&Email> {
+        match self.groups.iter().find(|g| g.key == group_key) {
+            Some(group) => {
+                stale_inbox_emails(self.group_emails(group), cutoff, &self.protected_senders)
+            }
+            None => Vec::new(),
+        }
     }
 
-    /// Enters the next view level (group -> emails -> thread)
-    pub fn enter(&mut self) {
-        match self.view {
-            View::GroupList => self.enter_group(),
-            View::EmailList => self.enter_thread(),
-            View::Thread => {} // Enter handled separately in main.rs (text view)
-            View::UndoHistory => {} // Enter handled separately in main.rs
-            View::EmailBody => {} // Already viewing email
-        }
+    /// Returns emails that are alone in their thread (everything the NoThreads filter shows),
+    /// excluding flagged mail and protected senders
+    pub fn single_message_emails(&self) -> Vec<&Email> {
+        self.emails
+            .iter()
+            .filter(|e| !self.multi_message_threads.contains(&e.thread_id) && !e.flagged)
+            .filter(|e| !self.protected_senders.iter().any(|p| sender_matches(e, p)))
+            .collect()
     }
 
-    /// Exits to the previous view level (thread -> emails -> group)
-    pub fn exit(&mut self) {
-        match self.view {
-            View::GroupList => {} // Can't go back, handled by main.rs for quit
-            View::EmailList => self.exit_to_groups(),
-            View::Thread => self.exit_to_emails(),
-            View::UndoHistory => self.exit_undo_history(),
-            View::EmailBody => self.exit_text_view(),
-        }
+    /// Returns the inbox emails from one sender address, for archiving after unsubscribing.
+    /// Flagged mail is skipped.
+    pub fn sender_inbox_emails(&self, sender: &str) -> Vec<&Email> {
+        self.emails
+            .iter()
+            .filter(|e| e.from_email.eq_ignore_ascii_case(sender))
+            .filter(|e| e.source_folder == "INBOX" && !e.flagged)
+            .collect()
     }
 
-    /// Enters the email list view for the currently selected group
-    fn enter_group(&mut self) {
-        if let Some(group) = self.groups.get(self.selected_group) {
-            self.viewing_group_key = Some(group.key.clone());
-            self.view = View::EmailList;
-            self.selected_email = if group.threads().is_empty() {
-                None
-            } else {
-                Some(0)
-            };
-            self.clear_selection();
-        }
+    /// Returns read inbox emails, in one group or across the inbox, leaving unread mail as the
+    /// to-do list. Flagged mail and protected senders are skipped.
+    pub fn read_inbox_emails(&self, group_key: Option<&str>) -> Vec<&Email> {
+        let candidates: Vec<&Email> = match group_key {
+            Some(key) => match self.groups.iter().find(|g| g.key == key) {
+                Some(group) => self.group_emails(group).collect(),
+                None => Vec::new(),
+            },
+            None => self.emails.iter().collect(),
+        };
+        candidates
+            .into_iter()
+            .filter(|e| e.seen && !e.flagged && e.source_folder == "INBOX")
+            .filter(|e| !self.protected_senders.iter().any(|p| sender_matches(e, p)))
+            .collect()
     }
 
-    /// Returns to the group list view
-    fn exit_to_groups(&mut self) {
-        self.view = View::GroupList;
-        self.selected_email = None;
-        self.viewing_group_key = None;
-        self.clear_selection();
-        self.clear_email_text_filter();
+    /// Matches loaded emails against policies (each email is claimed by the first match)
+    pub fn evaluate_policies<'a>(&self, policies: &'a [Policy]) -> Vec<(&'a Policy, Vec<&Email>)> {
+        policy::evaluate(
+            policies,
+            &self.emails,
+            &self.protected_senders,
+            chrono::Utc::now(),
+        )
     }
 
-    /// Enters the thread view for the currently selected email
-    fn enter_thread(&mut self) {
-        if self.current_email().is_some() {
+    /// Removes specific emails by (uid, folder) and rescans for duplicates
+    pub fn remove_emails(&mut self, ids_and_folders: &[(String, String)]) {
+        let targets: HashSet<(&str, &str)> = ids_and_folders
+            .iter()
+            .map(|(id, folder)| (id.as_str(), folder.as_str()))
+            .collect();
+        self.remove_emails_where(|e| targets.contains(&(e.id.as_str(), e.source_folder.as_str())));
+
+        if self.selected_group >= self.groups.len() && !self.groups.is_empty() {
+            self.selected_group = self.groups.len() - 1;
+        }
+        self.selected_email = self
+            .groups
+            .get(self.selected_group)
+            .filter(|g| !g.is_empty())
+            .map(|_| 0);
+        self.selected_thread_email = None;
+
+        if self.view == View::Duplicates {
+            self.duplicate_sets = find_duplicates(&self.emails);
+            self.ensure_valid_selection();
+        }
+    }
+
+    /// Marks the view stale when another client changed the inbox or a folder mail was loaded from
+    pub fn note_mailbox_changes(&mut self, folders: &[String]) {
+        if folders.iter().any(|folder| {
+            folder == "INBOX" || self.emails.iter().any(|e| &*e.source_folder == folder)
+        }) {
+            self.mailbox_changed = true;
+        }
+    }
+
+    /// Returns true when the loaded emails may no longer match the server
+    pub fn is_mailbox_changed(&self) -> bool {
+        self.mailbox_changed
+    }
+
+    /// Marks the loaded emails as matching the server again
+    pub fn clear_mailbox_changed(&mut self) {
+        self.mailbox_changed = false;
+    }
+
+    /// Returns how many emails are loaded, i.e. still left to triage
+    pub fn email_count(&self) -> usize {
+        self.emails.len()
+    }
+
+    /// Returns every loaded email as (uid, folder), for checking against the server
+    pub fn loaded_email_ids(&self) -> Vec<(String, String)> {
+        self.emails
+            .iter()
+            .map(|e| (e.id.clone(), e.source_folder.to_string()))
+            .collect()
+    }
+
+    /// Copies every loaded email, including threads hidden for this session, for the
+    /// header cache
+    pub fn cacheable_emails(&self) -> Vec<Email> {
+        self.emails.iter().chain(&self.dismissed).cloned().collect()
+    }
+
+    /// Drops emails another client removed and adds the ones that arrived, then
+    /// marks the view current
+    pub fn apply_resync(
+        &mut self,
+        removed: &[(String, String)],
+        added: Vec<Email>,
+        merge_subject_threads: bool,
+    ) -> usize {
+        self.remove_emails(removed);
+        let known = self.arrival_keys();
+        let added = self.merge_older_emails(added, merge_subject_threads);
+        self.mark_new_arrivals(&known);
+        self.mailbox_changed = false;
+        added
+    }
+
+    /// Applies a resync made in the background as mail arrives, keeping the cursor on the
+    /// same group and thread so counts change under the user without moving them. Returns
+    /// how many emails were added.
+    pub fn apply_live_resync(
+        &mut self,
+        removed: &[(String, String)],
+        added: Vec<Email>,
+        merge_subject_threads: bool,
+    ) -> usize {
+        self.keeping_cursor(|app| app.apply_resync(removed, added, merge_subject_threads))
+    }
+
+    /// Replaces emails shown from the header cache with a fresh fetch, keeping the cursor
+    /// where the user moved it meanwhile. Emails that weren't cached are marked new;
+    /// returns how many there were.
+    pub fn reconcile_emails(&mut self, emails: Vec<Email>) -> usize {
+        self.keeping_cursor(|app| app.reload_emails(emails))
+    }
+
+    /// Runs a change to the emails, then puts the cursor back on the group, thread and
+    /// thread email it was on, or as close as the change allows
+    fn keeping_cursor<T>(&mut self, change: impl FnOnce(&mut Self) -> T) -> T {
+        let group_key = self.current_group().map(|g| g.key.clone());
+        let thread_id = self.current_email().map(|e| e.thread_id.clone());
+        let (selected_email, selected_thread_email) =
+            (self.selected_email, self.selected_thread_email);
+        let result = change(self);
+
+        if let Some(&idx) = group_key.and_then(|key| self.group_positions.get(&key)) {
+            self.selected_group = idx;
+        }
+        let threads = self.filtered_threads_in_current_group();
+        let position = thread_id
+            .and_then(|id| threads.iter().position(|e| e.thread_id == id))
+            .or_else(|| selected_email.map(|i| i.min(threads.len().saturating_sub(1))));
+        let has_threads = !threads.is_empty();
+        self.selected_email = position.filter(|_| has_threads);
+        let thread_len = self.current_thread_emails().len();
+        self.selected_thread_email = selected_thread_email
+            .filter(|_| thread_len > 0)
+            .map(|i| i.min(thread_len - 1));
+        result
+    }
+
+    /// Adds an entry to the undo history (at the front, newest first), noting its
+    /// emails' senders and subjects while they're still loaded
+    pub fn push_undo(&mut self, entry: UndoEntry) {
+        let ids: HashSet<&str> = entry
+            .emails
+            .iter()
+            .filter_map(|(message_id, _, _)| message_id.as_deref())
+            .collect();
+        for email in &self.emails {
+            if let Some(id) = email.message_id.as_deref()
+                && ids.contains(id)
+            {
+                self.undo_summaries.insert(
+                    id.to_string(),
+                    (email.from.to_string(), email.subject.clone()),
+                );
+            }
+        }
+        match entry.action_type {
+            UndoActionType::Archive | UndoActionType::File => {
+                self.archived_count += entry.emails.len()
+            }
+            UndoActionType::Delete => self.deleted_count += entry.emails.len(),
+            UndoActionType::Move => {}
+        }
+        self.undo_history.insert(0, entry);
+        // Trim to max size
+        if self.undo_history.len() > MAX_UNDO_HISTORY {
+            self.undo_history.truncate(MAX_UNDO_HISTORY);
+            self.prune_undo_summaries();
+        }
+    }
+
+    /// Forgets the senders and subjects of emails no undo entry holds any more
+    fn prune_undo_summaries(&mut self) {
+        let ids: HashSet<&str> = self
+            .undo_history
+            .iter()
+            .flat_map(|entry| &entry.emails)
+            .filter_map(|(message_id, _, _)| message_id.as_deref())
+            .collect();
+        self.undo_summaries
+            .retain(|id, _| ids.contains(id.as_str()));
+    }
+
+    /// Removes and returns the undo entry at the given index
+    pub fn pop_undo(&mut self, index: usize) -> Option<UndoEntry> {
+        if index < self.undo_history.len() {
+            let entry = self.undo_history.remove(index);
+            self.undo_detail_scroll = None;
+            self.prune_undo_summaries();
+            // Adjust selected_undo if needed
+            if self.selected_undo >= self.undo_history.len() && !self.undo_history.is_empty() {
+                self.selected_undo = self.undo_history.len() - 1;
+            }
+            Some(entry)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the number of entries in undo history
+    #[cfg(test)]
+    pub fn undo_history_len(&self) -> usize {
+        self.undo_history.len()
+    }
+
+    /// Returns the currently selected undo entry, if any
+    pub fn current_undo_entry(&self) -> Option<&UndoEntry> {
+        self.undo_history.get(self.selected_undo)
+    }
+
+    /// Returns the undo entries from the newest down to the selected one, which `a` in the
+    /// undo history undoes together
+    pub fn recent_undo_entries(&self) -> &[UndoEntry] {
+        let end = (self.selected_undo + 1).min(self.undo_history.len());
+        &self.undo_history[..end]
+    }
+
+    /// Returns the view to return to after closing undo history
+    pub fn previous_view(&self) -> Option<View> {
+        self.previous_view
+    }
+
+    /// Enters the next view level (group -> emails -> thread)
+    pub fn enter(&mut self) {
+        match self.view {
+            View::GroupList => self.enter_group(),
+            View::EmailList => self.enter_thread(),
+            View::Thread => {} // Enter handled separately in main.rs (text view)
+            View::UndoHistory => {} // Enter handled separately in main.rs
+            View::EmailBody => {} // Already viewing email
+            View::Duplicates => {} // Actions handled separately in main.rs
+        }
+    }
+
+    /// Exits to the previous view level (thread -> emails -> group)
+    pub fn exit(&mut self) {
+        match self.view {
+            View::GroupList => {} // Can't go back, handled by main.rs for quit
+            View::EmailList => self.exit_to_groups(),
+            View::Thread => self.exit_to_emails(),
+            View::UndoHistory => self.exit_undo_history(),
+            View::EmailBody => self.exit_text_view(),
+            View::Duplicates => self.exit_duplicates(),
+        }
+    }
+
+    /// Enters the email list view for the currently selected group
+    fn enter_group(&mut self) {
+        if let Some(group) = self.groups.get(self.selected_group) {
+            let key = group.key.clone();
+            self.selected_email = if group.is_empty() { None } else { Some(0) };
+            self.view = View::EmailList;
+            self.clear_selection();
+            // Back on the thread the cursor was on last time, if it's still here
+            if let Some(thread_id) = self.group_cursors.get(&key)
+                && let Some(position) = self
+                    .filtered_threads_in_current_group()
+                    .iter()
+                    .position(|e| &e.thread_id == thread_id)
+            {
+                self.selected_email = Some(position);
+            }
+            self.viewing_group_key = Some(key);
+        }
+    }
+
+    /// Returns to the group list view
+    fn exit_to_groups(&mut self) {
+        if let Some(key) = self.viewing_group_key.clone()
+            && let Some(email) = self.current_email()
+        {
+            let thread_id = email.thread_id.clone();
+            self.group_cursors.insert(key, thread_id);
+        }
+        self.view = View::GroupList;
+        self.selected_email = None;
+        self.viewing_group_key = None;
+        self.clear_selection();
+        self.clear_email_text_filter();
+    }
+
+    /// Enters the thread view for the currently selected email
+    fn enter_thread(&mut self) {
+        if self.current_email().is_some() {
             self.view = View::Thread;
             self.selected_thread_email = Some(0);
+            self.thread_body_scroll = 0;
+            self.collapsed_thread_emails.clear();
             self.clear_selection();
         }
     }
@@ -721,6 +1665,85 @@ impl App {
         }
     }
 
+    /// Marks an email as read locally, after its body was fetched with the \Seen flag set
+    pub fn mark_email_seen(&mut self, email_id: &str) {
+        if let Some(email) = self.emails.iter_mut().find(|e| e.id == email_id) {
+            email.seen = true;
+        }
+    }
+
+    /// Sets the read (\Seen) flag on emails by ID
+    pub fn set_seen(&mut self, ids: &[String], seen: bool) {
+        let ids: HashSet<&str> = ids.iter().map(String::as_str).collect();
+        for email in self
+            .emails
+            .iter_mut()
+            .filter(|e| ids.contains(e.id.as_str()))
+        {
+            email.seen = seen;
+        }
+    }
+
+    /// Adds and removes labels on the emails with the given IDs, regrouping them when
+    /// the groups are labels
+    pub fn modify_labels(&mut self, ids: &[String], add: &[String], remove: &[String]) {
+        let ids: HashSet<&str> = ids.iter().map(String::as_str).collect();
+        for email in self
+            .emails
+            .iter_mut()
+            .filter(|e| ids.contains(e.id.as_str()))
+        {
+            email.labels.retain(|label| !remove.contains(label));
+            for label in add {
+                if !email.labels.contains(label) {
+                    email.labels.push(label.clone());
+                }
+            }
+            email.labels.sort();
+        }
+        if self.group_mode == GroupMode::ByLabel {
+            self.regroup();
+        }
+    }
+
+    /// The emails M marks read or unread: every email of the selected threads (or the
+    /// thread under the cursor) in the email list, or the message under the cursor in
+    /// thread and body views
+    pub fn seen_targets(&self) -> Vec<&Email> {
+        match self.view {
+            View::EmailList => {
+                let thread_ids: HashSet<&str> = if self.has_visible_selection() {
+                    self.filtered_emails_in_current_group()
+                        .into_iter()
+                        .filter(|e| self.selected_emails.contains(&e.id))
+                        .map(|e| e.thread_id.as_str())
+                        .collect()
+                } else {
+                    self.current_email()
+                        .map(|e| e.thread_id.as_str())
+                        .into_iter()
+                        .collect()
+                };
+                self.emails
+                    .iter()
+                    .filter(|e| thread_ids.contains(e.thread_id.as_str()))
+                    .collect()
+            }
+            View::Thread => self.current_thread_email().into_iter().collect(),
+            View::EmailBody => self.viewing_email().into_iter().collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Threads with at least one unread email
+    pub fn unread_thread_ids(&self) -> HashSet<&str> {
+        self.emails
+            .iter()
+            .filter(|e| !e.seen)
+            .map(|e| e.thread_id.as_str())
+            .collect()
+    }
+
     /// Scrolls the text view down by n lines
     pub fn scroll_text_view_down(&mut self, n: usize) {
         self.text_view_scroll = self.text_view_scroll.saturating_add(n);
@@ -743,6 +1766,14 @@ impl App {
         self.groups.get(self.selected_group)
     }
 
+    /// Gets the sender or domain the current group stands for; label groups have none
+    pub fn current_sender_key(&self) -> Option<&str> {
+        if self.group_mode == GroupMode::ByLabel {
+            return None;
+        }
+        self.current_group().map(|g| g.key.as_str())
+    }
+
     /// Gets the key of the group we're currently viewing (may be empty/deleted)
     pub fn viewing_group_key(&self) -> Option<&str> {
         self.viewing_group_key.as_deref()
@@ -770,11 +1801,78 @@ impl App {
             .filter(|e| &e.thread_id == thread_id)
             .collect();
 
-        // Sort by date descending (newest first)
-        thread_emails.sort_by(|a, b| b.date.cmp(&a.date));
+        if self.thread_inline_bodies {
+            // Conversation order (oldest first) when bodies are shown inline
+            thread_emails.sort_by_key(|e| e.date);
+        } else {
+            // Sort by date descending (newest first)
+            thread_emails.sort_by_key(|e| std::cmp::Reverse(e.date));
+        }
         thread_emails
     }
 
+    /// Switches the email list between one- and two-line rows
+    pub fn toggle_row_density(&mut self) {
+        self.row_density = match self.row_density {
+            RowDensity::Compact => RowDensity::Comfortable,
+            RowDensity::Comfortable => RowDensity::Compact,
+        };
+    }
+
+    /// Toggles inline bodies in thread view, keeping the same email selected
+    pub fn toggle_thread_inline_bodies(&mut self) {
+        let len = self.current_thread_emails().len();
+        self.thread_inline_bodies = !self.thread_inline_bodies;
+        self.thread_body_scroll = 0;
+        // The sort order flips, so mirror the index to keep the cursor on the same email
+        if let Some(idx) = self.selected_thread_email
+            && idx < len
+        {
+            self.selected_thread_email = Some(len - 1 - idx);
+        }
+    }
+
+    /// Toggles whether the selected thread email is collapsed to its header line
+    pub fn toggle_current_thread_email_collapsed(&mut self) {
+        let Some(id) = self.current_thread_email().map(|e| e.id.clone()) else {
+            return;
+        };
+        if !self.collapsed_thread_emails.remove(&id) {
+            self.collapsed_thread_emails.insert(id);
+        }
+        self.thread_body_scroll = 0;
+    }
+
+    /// Expands every message in the current thread
+    pub fn expand_all_thread_emails(&mut self) {
+        self.collapsed_thread_emails.clear();
+        self.thread_body_scroll = 0;
+    }
+
+    /// Collapses every message in the current thread
+    pub fn collapse_all_thread_emails(&mut self) {
+        self.collapsed_thread_emails = self
+            .current_thread_emails()
+            .iter()
+            .map(|e| e.id.clone())
+            .collect();
+        self.thread_body_scroll = 0;
+    }
+
+    /// Checks if a thread email is collapsed in the inline thread view
+    pub fn is_thread_email_collapsed(&self, email_id: &str) -> bool {
+        self.collapsed_thread_emails.contains(email_id)
+    }
+
+    /// Returns (uid, folder) pairs for expanded emails in the current thread whose body isn't cached
+    pub fn current_thread_email_ids_missing_body(&self) -> Vec<(String, String)> {
+        self.current_thread_emails()
+            .iter()
+            .filter(|e| e.body.is_none() && !self.is_thread_email_collapsed(&e.id))
+            .map(|e| (e.id.clone(), e.source_folder.to_string()))
+            .collect()
+    }
+
     /// Checks if a thread has multiple messages (O(1) lookup using cache)
     pub fn thread_has_multiple_messages(&self, thread_id: &str) -> bool {
         self.multi_message_threads.contains(thread_id)
@@ -786,19 +1884,29 @@ impl App {
             .is_some_and(|email| self.thread_has_multiple_messages(&email.thread_id))
     }
 
+    /// Returns the emails in a group, newest first
+    pub fn group_emails<'a>(&'a self, group: &EmailGroup) -> impl Iterator<Item = &'a Email> {
+        group.email_indices.iter().map(|&i| &self.emails[i])
+    }
+
+    /// Returns the newest email from each thread in a group.
+    /// Since group emails are sorted by date descending, we take the first email for each thread_id.
+    pub fn group_threads(&self, group: &EmailGroup) -> Vec<&Email> {
+        let mut seen_threads = HashSet::new();
+        self.group_emails(group)
+            .filter(|email| seen_threads.insert(&email.thread_id))
+            .collect()
+    }
+
     /// Checks if any email in a group is part of a multi-message thread
     pub fn group_has_multi_message_threads(&self, group: &EmailGroup) -> bool {
-        group
-            .emails
-            .iter()
+        self.group_emails(group)
             .any(|email| self.thread_has_multiple_messages(&email.thread_id))
     }
 
     /// Checks if any email in a group is a single-message thread (not part of multi-message thread)
     pub fn group_has_single_message_threads(&self, group: &EmailGroup) -> bool {
-        group
-            .emails
-            .iter()
+        self.group_emails(group)
             .any(|email| !self.thread_has_multiple_messages(&email.thread_id))
     }
 
@@ -904,6 +2012,47 @@ impl App {
         }
     }
 
+    /// Returns the completion for the current view's text filter that starts with `prefix`
+    /// (ignoring case) and has the most emails: group names (and sender domains when
+    /// grouping by sender) in the group list, the viewed group's sender addresses and
+    /// domains in the email list. Runs on every filter keystroke, so the group list is
+    /// counted from the groups rather than their emails.
+    pub fn filter_completion(&self, prefix: &str) -> Option<String> {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        match self.view {
+            View::GroupList => {
+                for group in &self.groups {
+                    *counts.entry(group.key.as_str()).or_default() += group.count();
+                    // A sender's emails all share its domain
+                    if self.group_mode == GroupMode::BySenderEmail
+                        && let Some(email) = self.group_emails(group).next()
+                    {
+                        *counts.entry(email.from_domain.as_ref()).or_default() += group.count();
+                    }
+                }
+            }
+            View::EmailList => {
+                if let Some(group) = self.current_group() {
+                    for email in self.group_emails(group) {
+                        *counts.entry(email.from_email.as_ref()).or_default() += 1;
+                        *counts.entry(email.from_domain.as_ref()).or_default() += 1;
+                    }
+                }
+            }
+            _ => {}
+        }
+        counts
+            .into_iter()
+            .filter(|(candidate, _)| {
+                candidate.len() > prefix.len()
+                    && candidate
+                        .get(..prefix.len())
+                        .is_some_and(|head| head.eq_ignore_ascii_case(prefix))
+            })
+            .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(a.0)))
+            .map(|(candidate, _)| candidate.to_string())
+    }
+
     /// Sets the text filter for the current view
     pub fn set_view_text_filter(&mut self, query: Option<String>) {
         match self.view {
@@ -939,12 +2088,28 @@ impl App {
         group.key.to_lowercase().contains(&query.to_lowercase())
     }
 
-    /// Checks if an email matches the email text filter (case-insensitive)
+    /// Checks if an email matches the email text filter (case-insensitive).
+    /// "spam>N" and "spam<N" match on the spam score instead.
     fn email_matches_text_filter(&self, email: &Email) -> bool {
         let Some(ref query) = self.email_text_filter else {
             return true;
         };
         let query_lower = query.to_lowercase();
+        if let Some(rest) = query_lower.strip_prefix("spam")
+            && let Some((above, threshold)) = rest
+                .strip_prefix('>')
+                .map(|n| (true, n))
+                .or_else(|| rest.strip_prefix('<').map(|n| (false, n)))
+            && let Ok(threshold) = threshold.trim().parse::<f32>()
+        {
+            return email.spam_score.is_some_and(|score| {
+                if above {
+                    score > threshold
+                } else {
+                    score < threshold
+                }
+            });
+        }
         email.subject.to_lowercase().contains(&query_lower)
             || email.from.to_lowercase().contains(&query_lower)
             || email.from_email.to_lowercase().contains(&query_lower)
@@ -958,35 +2123,63 @@ impl App {
 
         // First apply thread filter
         let thread_filtered: Vec<&Email> = match self.thread_filter {
-            ThreadFilter::All => group.threads(),
-            ThreadFilter::OnlyThreads => group
-                .threads()
+            ThreadFilter::All => self.group_threads(group),
+            ThreadFilter::OnlyThreads => self
+                .group_threads(group)
                 .into_iter()
                 .filter(|e| self.multi_message_threads.contains(&e.thread_id))
                 .collect(),
-            ThreadFilter::NoThreads => group
-                .threads()
+            ThreadFilter::NoThreads => self
+                .group_threads(group)
                 .into_iter()
                 .filter(|e| !self.multi_message_threads.contains(&e.thread_id))
                 .collect(),
         };
 
         // Then apply email text filter if active
-        if self.email_text_filter.is_some() {
+        let mut threads: Vec<&Email> = if self.email_text_filter.is_some() {
             thread_filtered
                 .into_iter()
                 .filter(|e| self.email_matches_text_filter(e))
                 .collect()
         } else {
             thread_filtered
+        };
+        if self.sort_by_spam_score {
+            // Highest score first; unscored mail keeps its date order at the end
+            threads.sort_by(|a, b| match (a.spam_score, b.spam_score) {
+                (Some(a), Some(b)) => b.total_cmp(&a),
+                (Some(_), None) => std::cmp::Ordering::Less,
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (None, None) => std::cmp::Ordering::Equal,
+            });
         }
+        threads
+    }
+
+    /// Sorts the email list by spam score, or back by date, keeping the cursor on the same email
+    pub fn toggle_spam_score_sort(&mut self) {
+        let current = self.current_email().map(|e| e.id.clone());
+        self.sort_by_spam_score = !self.sort_by_spam_score;
+        if let Some(id) = current {
+            self.selected_email = self
+                .filtered_threads_in_current_group()
+                .iter()
+                .position(|e| e.id == id);
+        }
+    }
+
+    /// Whether the email list is sorted by spam score
+    pub fn is_sorted_by_spam_score(&self) -> bool {
+        self.sort_by_spam_score
     }
 
     /// Returns groups filtered based on thread_filter and group_text_filter settings
     pub fn filtered_groups(&self) -> Vec<&EmailGroup> {
-        self.groups
+        self.group_list_summary()
+            .indices
             .iter()
-            .filter(|g| self.group_matches_all_filters(g))
+            .map(|&i| &self.groups[i])
             .collect()
     }
 
@@ -1001,15 +2194,13 @@ impl App {
 
         // First apply thread filter
         let thread_filtered: Vec<&Email> = match self.thread_filter {
-            ThreadFilter::All => group.emails.iter().collect(),
-            ThreadFilter::OnlyThreads => group
-                .emails
-                .iter()
+            ThreadFilter::All => self.group_emails(group).collect(),
+            ThreadFilter::OnlyThreads => self
+                .group_emails(group)
                 .filter(|e| self.multi_message_threads.contains(&e.thread_id))
                 .collect(),
-            ThreadFilter::NoThreads => group
-                .emails
-                .iter()
+            ThreadFilter::NoThreads => self
+                .group_emails(group)
                 .filter(|e| !self.multi_message_threads.contains(&e.thread_id))
                 .collect(),
         };
@@ -1028,11 +2219,10 @@ impl App {
     /// Returns the filtered thread count for a specific group
     pub fn filtered_thread_count_for_group(&self, group: &EmailGroup) -> usize {
         match self.thread_filter {
-            ThreadFilter::All => group.thread_count(),
+            ThreadFilter::All => self.group_threads(group).len(),
             ThreadFilter::OnlyThreads => {
-                let thread_ids: HashSet<&str> = group
-                    .emails
-                    .iter()
+                let thread_ids: HashSet<&str> = self
+                    .group_emails(group)
                     .filter(|e| self.multi_message_threads.contains(&e.thread_id))
                     .map(|e| e.thread_id.as_str())
                     .collect();
@@ -1040,9 +2230,7 @@ impl App {
             }
             ThreadFilter::NoThreads => {
                 // In NoThreads mode, each email is its own "thread" (single messages)
-                group
-                    .emails
-                    .iter()
+                self.group_emails(group)
                     .filter(|e| !self.multi_message_threads.contains(&e.thread_id))
                     .count()
             }
@@ -1052,38 +2240,44 @@ impl App {
     /// Returns the full thread email count for a group (all emails in all threads, including
     /// emails from other senders that would be shown in thread view).
     pub fn full_thread_email_count_for_group(&self, group: &EmailGroup) -> usize {
-        // Get unique thread IDs in this group, filtered by current thread filter
-        let thread_ids: HashSet<&str> = match self.thread_filter {
-            ThreadFilter::All => group.emails.iter().map(|e| e.thread_id.as_str()).collect(),
-            ThreadFilter::OnlyThreads => group
-                .emails
-                .iter()
+        // Sum up email counts for all threads
+        self.filtered_thread_ids_for_group(group)
+            .iter()
+            .map(|tid| self.thread_email_counts.get(*tid).copied().unwrap_or(1))
+            .sum()
+    }
+
+    /// Returns the unique thread IDs in a group, filtered by the current thread filter
+    fn filtered_thread_ids_for_group(&self, group: &EmailGroup) -> HashSet<&str> {
+        match self.thread_filter {
+            ThreadFilter::All => self
+                .group_emails(group)
+                .map(|e| e.thread_id.as_str())
+                .collect(),
+            ThreadFilter::OnlyThreads => self
+                .group_emails(group)
                 .filter(|e| self.multi_message_threads.contains(&e.thread_id))
                 .map(|e| e.thread_id.as_str())
                 .collect(),
-            ThreadFilter::NoThreads => group
-                .emails
-                .iter()
+            ThreadFilter::NoThreads => self
+                .group_emails(group)
                 .filter(|e| !self.multi_message_threads.contains(&e.thread_id))
                 .map(|e| e.thread_id.as_str())
                 .collect(),
-        };
-
-        // Sum up email counts for all threads
-        thread_ids
-            .iter()
-            .map(|tid| self.thread_email_counts.get(*tid).copied().unwrap_or(1))
-            .sum()
+        }
     }
 
     /// Removes an email by ID and regroups
     pub fn remove_email(&mut self, email_id: &str) {
-        self.emails.retain(|e| e.id != email_id);
-        self.regroup();
+        self.remove_emails_where(|e| e.id == email_id);
+        self.clamp_email_selection();
+    }
 
-        // Adjust selected_email for the (possibly changed) current group
+    /// Keeps selected_email on a thread of the (possibly changed) current group after
+    /// emails were removed
+    fn clamp_email_selection(&mut self) {
         if let Some(group) = self.groups.get(self.selected_group) {
-            let threads = group.threads();
+            let threads = self.group_threads(group);
             if threads.is_empty() {
                 self.selected_email = None;
             } else if self.selected_email.is_none()
@@ -1099,25 +2293,10 @@ impl App {
 
     /// Removes all emails in a thread by thread ID
     pub fn remove_thread(&mut self, thread_id: &str) {
-        self.emails.retain(|e| e.thread_id != thread_id);
-        self.regroup();
-
-        // Adjust selected_email for the (possibly changed) current group
-        if let Some(group) = self.groups.get(self.selected_group) {
-            let threads = group.threads();
-            if threads.is_empty() {
-                self.selected_email = None;
-            } else if self.selected_email.is_none()
-                || self.selected_email.is_some_and(|idx| idx >= threads.len())
-            {
-                // Ensure a valid selection exists
-                self.selected_email = Some(threads.len().saturating_sub(1));
-            }
-        } else {
-            self.selected_email = None;
-        }
-        self.selected_thread_email = None;
-    }
+        self.remove_emails_where(|e| e.thread_id == thread_id);
+        self.clamp_email_selection();
+        self.selected_thread_email = None;
+    }
 
     /// Removes all emails in threads that contain emails from the current group.
     /// This affects ALL emails in those threads, including from other senders.
@@ -1129,8 +2308,7 @@ impl App {
             .map(|e| e.thread_id.clone())
             .collect();
 
-        self.emails.retain(|e| !thread_ids.contains(&e.thread_id));
-        self.regroup();
+        self.remove_emails_where(|e| thread_ids.contains(&e.thread_id));
         self.selected_email = None;
         self.selected_thread_email = None;
 
@@ -1142,21 +2320,152 @@ impl App {
         self.selected_email = self
             .groups
             .get(self.selected_group)
-            .filter(|g| !g.threads().is_empty())
+            .filter(|g| !g.is_empty())
             .map(|_| 0);
     }
 
+    /// Hides every thread with mail from a sender or domain, now and on later loads.
+    /// Returns how many emails were hidden.
+    pub fn ignore_sender(&mut self, pattern: &str) -> usize {
+        if !self
+            .ignored_senders
+            .iter()
+            .any(|p| p.eq_ignore_ascii_case(pattern))
+        {
+            self.ignored_senders.push(pattern.to_string());
+        }
+        self.hide_sender(pattern)
+    }
+
+    /// Hides the current group until a later session (see postpone.rs), like ignoring
+    /// its sender for now. Returns how many emails were hidden.
+    pub fn postpone_sender(&mut self, pattern: &str) -> usize {
+        if !self
+            .postponed_senders
+            .iter()
+            .any(|p| p.eq_ignore_ascii_case(pattern))
+        {
+            self.postponed_senders.push(pattern.to_string());
+        }
+        self.hide_sender(pattern)
+    }
+
+    /// Removes every thread with mail from a sender or domain. Returns how many emails
+    /// were removed.
+    fn hide_sender(&mut self, pattern: &str) -> usize {
+        let thread_ids: HashSet<String> = self
+            .emails
+            .iter()
+            .filter(|e| sender_matches(e, pattern))
+            .map(|e| e.thread_id.clone())
+            .collect();
+
+        let hidden = self
+            .remove_emails_where(|e| thread_ids.contains(&e.thread_id))
+            .len();
+        if self.selected_group >= self.groups.len() {
+            self.selected_group = self.groups.len().saturating_sub(1);
+        }
+        hidden
+    }
+
+    /// Hides the selected group's threads (group list) or the cursor thread (email list)
+    /// for this session only, without touching the server. Returns how many threads were
+    /// hidden.
+    pub fn dismiss_current(&mut self) -> usize {
+        let thread_ids: HashSet<String> = match self.view {
+            View::GroupList => self
+                .filtered_emails_in_current_group()
+                .iter()
+                .map(|e| e.thread_id.clone())
+                .collect(),
+            View::EmailList => self
+                .current_email()
+                .map(|e| e.thread_id.clone())
+                .into_iter()
+                .collect(),
+            _ => return 0,
+        };
+        let hidden = self.remove_emails_where(|e| thread_ids.contains(&e.thread_id));
+        self.dismissed.extend(hidden);
+        let count = thread_ids.len();
+        self.dismissed_threads.extend(thread_ids);
+        if self.view == View::EmailList {
+            self.clamp_email_selection();
+        }
+        self.ensure_valid_selection();
+        count
+    }
+
+    /// Returns how many loaded threads are hidden for this session
+    pub fn dismissed_count(&self) -> usize {
+        self.dismissed
+            .iter()
+            .map(|e| e.thread_id.as_str())
+            .collect::<HashSet<_>>()
+            .len()
+    }
+
+    /// Brings back everything hidden with dismiss_current. Returns how many threads came
+    /// back.
+    pub fn undismiss_all(&mut self) -> usize {
+        let count = self.dismissed_count();
+        self.dismissed_threads.clear();
+        let emails = std::mem::take(&mut self.dismissed);
+        self.restore_emails(emails);
+        self.ensure_valid_selection();
+        count
+    }
+
     /// Restores emails back into the app (for undo support)
+    /// Only the groups receiving emails have their emails re-sorted by date; the group
+    /// list itself is re-sorted as a whole.
     pub fn restore_emails(&mut self, emails: Vec<Email>) {
+        let start = self.emails.len();
         self.emails.extend(emails);
-        self.regroup();
+        intern_strings(&mut self.emails[start..]);
+
+        let mut touched: HashSet<usize> = HashSet::new();
+        for email_idx in start..self.emails.len() {
+            let email = &self.emails[email_idx];
+            let count = self
+                .thread_email_counts
+                .entry(email.thread_id.clone())
+                .or_default();
+            *count += 1;
+            if *count > 1 {
+                self.multi_message_threads.insert(email.thread_id.clone());
+            }
+
+            let Some(key) = self.group_key(email) else {
+                continue;
+            };
+            let idx = match self.group_positions.get(&key) {
+                Some(&idx) => idx,
+                None => {
+                    self.groups.push(EmailGroup::new(key.clone()));
+                    self.group_positions.insert(key, self.groups.len() - 1);
+                    self.groups.len() - 1
+                }
+            };
+            self.groups[idx].email_indices.push(email_idx);
+            touched.insert(idx);
+        }
+
+        let emails = &self.emails;
+        for idx in touched {
+            self.groups[idx]
+                .email_indices
+                .sort_by_key(|&i| std::cmp::Reverse(emails[i].date));
+        }
+        self.sort_groups();
     }
 
     /// Gets all email IDs and source folders in the current thread
     pub fn current_thread_email_ids(&self) -> Vec<(String, String)> {
         self.current_thread_emails()
             .iter()
-            .map(|e| (e.id.clone(), e.source_folder.clone()))
+            .map(|e| (e.id.clone(), e.source_folder.to_string()))
             .collect()
     }
 
@@ -1175,7 +2484,7 @@ impl App {
         self.emails
             .iter()
             .filter(|e| thread_ids.contains(&e.thread_id))
-            .map(|e| (e.id.clone(), e.source_folder.clone()))
+            .map(|e| (e.id.clone(), e.source_folder.to_string()))
             .collect()
     }
 
@@ -1194,7 +2503,13 @@ impl App {
         self.emails
             .iter()
             .filter(|e| thread_ids.contains(&e.thread_id))
-            .map(|e| (e.id.clone(), e.message_id.clone(), e.source_folder.clone()))
+            .map(|e| {
+                (
+                    e.id.clone(),
+                    e.message_id.clone(),
+                    e.source_folder.to_string(),
+                )
+            })
             .collect()
     }
 
@@ -1214,7 +2529,7 @@ impl App {
         self.emails
             .iter()
             .filter(|e| thread_ids.contains(&e.thread_id))
-            .map(|e| (e.id.clone(), e.source_folder.clone()))
+            .map(|e| (e.id.clone(), e.source_folder.to_string()))
             .collect()
     }
 
@@ -1234,7 +2549,13 @@ impl App {
         self.emails
             .iter()
             .filter(|e| thread_ids.contains(&e.thread_id))
-            .map(|e| (e.id.clone(), e.message_id.clone(), e.source_folder.clone()))
+            .map(|e| {
+                (
+                    e.id.clone(),
+                    e.message_id.clone(),
+                    e.source_folder.to_string(),
+                )
+            })
             .collect()
     }
 
@@ -1242,6 +2563,15 @@ impl App {
     /// This expands the operation to include ALL emails in affected threads, including from other senders.
     /// For use in demo mode.
     pub fn current_group_thread_emails_cloned(&self) -> Vec<Email> {
+        self.current_group_thread_emails()
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+
+    /// Gets all emails from threads that contain emails in the current group, like
+    /// current_group_thread_email_ids
+    pub fn current_group_thread_emails(&self) -> Vec<&Email> {
         // Get thread IDs from the filtered group emails
         let thread_ids: HashSet<String> = self
             .filtered_emails_in_current_group()
@@ -1253,7 +2583,6 @@ impl App {
         self.emails
             .iter()
             .filter(|e| thread_ids.contains(&e.thread_id))
-            .cloned()
             .collect()
     }
 
@@ -1261,6 +2590,12 @@ impl App {
     /// This expands the operation to include ALL emails in affected threads, including from other senders.
     /// For use in demo mode.
     pub fn selected_thread_emails_cloned(&self) -> Vec<Email> {
+        self.selected_thread_emails().into_iter().cloned().collect()
+    }
+
+    /// Gets all emails from threads that contain the selected emails, like
+    /// selected_thread_email_ids
+    pub fn selected_thread_emails(&self) -> Vec<&Email> {
         // Get thread IDs from the selected emails
         let thread_ids: HashSet<String> = self
             .filtered_emails_in_current_group()
@@ -1273,6 +2608,14 @@ impl App {
         self.emails
             .iter()
             .filter(|e| thread_ids.contains(&e.thread_id))
+            .collect()
+    }
+
+    /// Gets clones of all emails affected by acting on the selected groups.
+    /// For use in demo mode.
+    pub fn selected_groups_thread_emails_cloned(&self) -> Vec<Email> {
+        self.selected_groups_thread_emails()
+            .into_iter()
             .cloned()
             .collect()
     }
@@ -1339,14 +2682,270 @@ impl App {
             .any(|id| visible_ids.contains(id.as_str()))
     }
 
+    /// Toggles selection of the current group (only in GroupList view)
+    pub fn toggle_group_selection(&mut self) -> SelectionResult {
+        if self.view != View::GroupList {
+            return SelectionResult::NoEmail;
+        }
+        let Some(key) = self.current_group().map(|g| g.key.clone()) else {
+            return SelectionResult::NoEmail;
+        };
+        if !self.selected_groups.remove(&key) {
+            self.selected_groups.insert(key);
+        }
+        SelectionResult::Toggled
+    }
+
+    /// Marks the current group done (or skipped) for this session, or clears the mark.
+    /// Marking moves the group below the others and leaves the cursor on the next group;
+    /// clearing follows the group back up. Returns the new state (GroupList only).
+    pub fn toggle_group_done(&mut self) -> Option<bool> {
+        if self.view != View::GroupList {
+            return None;
+        }
+        let key = self.current_group()?.key.clone();
+        let done = !self.done_groups.remove(&key);
+        let position = self.selected_group;
+        if done {
+            self.done_groups.insert(key.clone());
+        }
+        self.sort_groups();
+        self.selected_group = if done {
+            position.min(self.groups.len().saturating_sub(1))
+        } else {
+            self.group_positions.get(&key).copied().unwrap_or(position)
+        };
+        self.ensure_valid_selection();
+        Some(done)
+    }
+
+    /// Returns whether a group has been marked done for this session
+    pub fn is_group_done(&self, key: &str) -> bool {
+        self.done_groups.contains(key)
+    }
+
+    /// Returns whether a specific group is selected
+    pub fn is_group_selected(&self, key: &str) -> bool {
+        self.selected_groups.contains(key)
+    }
+
+    /// Clears all selected groups
+    pub fn clear_group_selection(&mut self) {
+        self.selected_groups.clear();
+    }
+
+    /// Returns the selected groups that are visible in the group list
+    fn visible_selected_groups(&self) -> Vec<&EmailGroup> {
+        self.filtered_groups()
+            .into_iter()
+            .filter(|g| self.selected_groups.contains(&g.key))
+            .collect()
+    }
+
+    /// Returns the keys of the selected groups visible in the group list, in list order
+    pub fn selected_group_keys(&self) -> Vec<String> {
+        self.visible_selected_groups()
+            .into_iter()
+            .map(|g| g.key.clone())
+            .collect()
+    }
+
+    /// Returns all emails from threads that contain emails in the visible selected groups.
+    /// Like group actions, this includes emails from other senders in those threads
+    /// and respects the current thread filter.
+    pub fn selected_groups_thread_emails(&self) -> Vec<&Email> {
+        let thread_ids: HashSet<&str> = self
+            .visible_selected_groups()
+            .into_iter()
+            .flat_map(|g| self.filtered_thread_ids_for_group(g))
+            .collect();
+        self.emails
+            .iter()
+            .filter(|e| thread_ids.contains(e.thread_id.as_str()))
+            .collect()
+    }
+
+    /// Gets all email IDs and source folders affected by acting on the selected groups
+    pub fn selected_groups_thread_email_ids(&self) -> Vec<(String, String)> {
+        self.selected_groups_thread_emails()
+            .into_iter()
+            .map(|e| (e.id.clone(), e.source_folder.to_string()))
+            .collect()
+    }
+
+    /// Gets the data for undo support for every email affected by acting on the selected groups
+    pub fn selected_groups_thread_emails_for_undo(&self) -> Vec<(String, Option<String>, String)> {
+        self.selected_groups_thread_emails()
+            .into_iter()
+            .map(|e| {
+                (
+                    e.id.clone(),
+                    e.message_id.clone(),
+                    e.source_folder.to_string(),
+                )
+            })
+            .collect()
+    }
+
+    /// Starts a sweep with nothing marked to keep
+    pub fn start_sweep(&mut self) {
+        self.sweep = Some(SweepMarks::default());
+    }
+
+    /// Ends the sweep and forgets its keep marks
+    pub fn cancel_sweep(&mut self) {
+        self.sweep = None;
+    }
+
+    /// Returns the keep marks if a sweep is in progress
+    pub fn sweep_marks(&self) -> Option<&SweepMarks> {
+        self.sweep.as_ref()
+    }
+
+    /// Marks or unmarks the current group (GroupList) or thread (EmailList) to keep
+    pub fn toggle_keep(&mut self) -> SelectionResult {
+        let target = match self.view {
+            View::GroupList => self.current_group().map(|g| g.key.clone()),
+            View::EmailList => self.current_email().map(|e| e.thread_id.clone()),
+            _ => None,
+        };
+        let (Some(target), Some(marks)) = (target, self.sweep.as_mut()) else {
+            return SelectionResult::NoEmail;
+        };
+        let kept = if self.view == View::GroupList {
+            &mut marks.groups
+        } else {
+            &mut marks.threads
+        };
+        if !kept.remove(&target) {
+            kept.insert(target);
+        }
+        SelectionResult::Toggled
+    }
+
+    /// Returns whether a group is marked to keep in the current sweep
+    pub fn is_kept_group(&self, key: &str) -> bool {
+        self.sweep.as_ref().is_some_and(|m| m.groups.contains(key))
+    }
+
+    /// Returns the threads kept in the current sweep, on their own or through a kept group
+    pub fn kept_thread_ids(&self) -> HashSet<&str> {
+        let Some(marks) = &self.sweep else {
+            return HashSet::new();
+        };
+        let mut kept: HashSet<&str> = marks.threads.iter().map(String::as_str).collect();
+        kept.extend(
+            self.emails
+                .iter()
+                .filter(|e| {
+                    self.group_key(e)
+                        .is_some_and(|key| marks.groups.contains(&key))
+                })
+                .map(|e| e.thread_id.as_str()),
+        );
+        kept
+    }
+
+    /// Returns every email the sweep would archive: all threads not touched by a kept
+    /// group or thread, excluding flagged mail and protected senders
+    pub fn sweep_emails(&self) -> Vec<&Email> {
+        if self.sweep.is_none() {
+            return Vec::new();
+        }
+        let kept = self.kept_thread_ids();
+        self.emails
+            .iter()
+            .filter(|e| !kept.contains(e.thread_id.as_str()) && !e.flagged)
+            .filter(|e| !self.protected_senders.iter().any(|p| sender_matches(e, p)))
+            .collect()
+    }
+
     /// Returns the current thread's emails' data for undo support: (uid, message_id, source_folder)
     pub fn current_thread_emails_for_undo(&self) -> Vec<(String, Option<String>, String)> {
         self.current_thread_emails()
             .iter()
-            .map(|e| (e.id.clone(), e.message_id.clone(), e.source_folder.clone()))
+            .map(|e| {
+                (
+                    e.id.clone(),
+                    e.message_id.clone(),
+                    e.source_folder.to_string(),
+                )
+            })
             .collect()
     }
 
+    /// The emails a move from the current view takes, with the undo history's label for
+    /// them: the group's threads in the group list, the selected threads when some are
+    /// selected, otherwise the current thread
+    pub fn move_targets(&self) -> (UndoContext, Vec<&Email>) {
+        let current = self.filtered_emails_in_current_group();
+        let (context, thread_ids): (UndoContext, HashSet<&str>) = match self.view {
+            View::GroupList => {
+                let sender = self
+                    .groups
+                    .get(self.selected_group)
+                    .map(|g| g.key.clone())
+                    .unwrap_or_default();
+                let thread_ids = current.iter().map(|e| e.thread_id.as_str()).collect();
+                (UndoContext::Group { sender }, thread_ids)
+            }
+            View::EmailList if self.has_visible_selection() => {
+                let selected: Vec<&&Email> = current
+                    .iter()
+                    .filter(|e| self.selected_emails.contains(&e.id))
+                    .collect();
+                let sender = format!("{} selected", selected.len());
+                let thread_ids = selected.iter().map(|e| e.thread_id.as_str()).collect();
+                (UndoContext::Group { sender }, thread_ids)
+            }
+            View::EmailList | View::Thread | View::EmailBody => match self.current_email() {
+                Some(email) => (
+                    UndoContext::Thread {
+                        subject: email.subject.clone(),
+                    },
+                    HashSet::from([email.thread_id.as_str()]),
+                ),
+                None => {
+                    return (
+                        UndoContext::Group {
+                            sender: String::new(),
+                        },
+                        Vec::new(),
+                    );
+                }
+            },
+            View::UndoHistory | View::Duplicates => {
+                return (
+                    UndoContext::Group {
+                        sender: String::new(),
+                    },
+                    Vec::new(),
+                );
+            }
+        };
+        let emails = self
+            .emails
+            .iter()
+            .filter(|e| thread_ids.contains(e.thread_id.as_str()))
+            .collect();
+        (context, emails)
+    }
+
+    /// Removes moved emails (uid, folder) and leaves the thread or email they were
+    /// shown in
+    pub fn remove_moved_emails(&mut self, ids_and_folders: &[(String, String)]) {
+        self.remove_emails(ids_and_folders);
+        let moved: HashSet<&str> = ids_and_folders.iter().map(|(id, _)| id.as_str()).collect();
+        self.selected_emails
+            .retain(|id| !moved.contains(id.as_str()));
+        if self.view == View::EmailBody {
+            self.exit_text_view();
+        }
+        if self.view == View::Thread {
+            self.exit_to_emails();
+        }
+    }
+
     /// Removes all emails in threads that contain selected emails.
     /// This affects ALL emails in those threads, including from other senders.
     pub fn remove_selected_threads(&mut self) {
@@ -1359,20 +2958,15 @@ impl App {
             .collect();
 
         // Remove all emails from those threads, tracking which IDs are removed
-        let mut removed_ids: HashSet<String> = HashSet::new();
-        self.emails.retain(|e| {
-            if thread_ids.contains(&e.thread_id) {
-                removed_ids.insert(e.id.clone());
-                false
-            } else {
-                true
-            }
-        });
+        let removed_ids: HashSet<String> = self
+            .remove_emails_where(|e| thread_ids.contains(&e.thread_id))
+            .into_iter()
+            .map(|e| e.id)
+            .collect();
 
         // Only clear selections for emails that were actually removed
         self.selected_emails.retain(|id| !removed_ids.contains(id));
 
-        self.regroup();
         self.selected_email = None;
         self.selected_thread_email = None;
 
@@ -1384,11 +2978,28 @@ impl App {
         self.selected_email = self
             .groups
             .get(self.selected_group)
-            .filter(|g| !g.threads().is_empty())
+            .filter(|g| !g.is_empty())
             .map(|_| 0);
     }
 }
 
+/// How reloads recognize an email they already had: its Message-ID, or its ID
+fn arrival_key(email: &Email) -> String {
+    email.message_id.clone().unwrap_or_else(|| email.id.clone())
+}
+
+/// Finds the lowest UID per folder; API ids that aren't numbers are skipped
+fn lowest_uids(emails: &[Email]) -> HashMap<String, u32> {
+    let mut lowest: HashMap<String, u32> = HashMap::new();
+    for email in emails {
+        if let Ok(uid) = email.id.parse::<u32>() {
+            let entry = lowest.entry(email.source_folder.to_string()).or_insert(uid);
+            *entry = (*entry).min(uid);
+        }
+    }
+    lowest
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1439,6 +3050,61 @@ mod tests {
         assert!(app.groups.is_empty());
     }
 
+    #[test]
+    fn test_lowest_uids_survive_archiving_the_oldest_email() {
+        let mut app = App::new();
+        let email = |id: &str, folder: &str| {
+            crate::email::EmailBuilder::new()
+                .id(id)
+                .from("alice@example.com")
+                .source_folder(folder)
+                .build()
+        };
+        app.set_emails(vec![
+            email("40", "INBOX"),
+            email("30", "INBOX"),
+            email("7", "Sent"),
+            email("not-a-uid", "INBOX"),
+        ]);
+        assert_eq!(app.lowest_uids()["INBOX"], 30);
+        assert_eq!(app.lowest_uids()["Sent"], 7);
+
+        // Archiving the oldest page's emails doesn't move where the next page starts
+        app.merge_older_emails(vec![email("20", "INBOX")], false);
+        app.remove_emails(&[("20".to_string(), "INBOX".to_string())]);
+        app.merge_older_emails(Vec::new(), false);
+        assert_eq!(app.lowest_uids()["INBOX"], 20);
+        assert_eq!(app.loaded_per_folder()["INBOX"], 3);
+    }
+
+    #[test]
+    fn test_merge_older_emails_threads_across_windows() {
+        let mut app = App::new();
+        let recent = crate::email::EmailBuilder::new()
+            .id("2")
+            .from("alice@example.com")
+            .message_id("<b@x>")
+            .in_reply_to("<a@x>")
+            .build();
+        let older = crate::email::EmailBuilder::new()
+            .id("1")
+            .from("alice@example.com")
+            .message_id("<a@x>")
+            .build();
+        let mut emails = vec![recent];
+        build_thread_ids(&mut emails);
+        app.set_emails(emails);
+        assert!(!app.thread_has_multiple_messages(&app.emails[0].thread_id));
+
+        // The recent email again (already loaded) plus one older email
+        let added = app.merge_older_emails(vec![older, app.emails[0].clone()], false);
+        assert_eq!(added, 1);
+        assert_eq!(app.emails.len(), 2);
+        assert_eq!(app.emails[0].thread_id, app.emails[1].thread_id);
+        assert!(app.thread_has_multiple_messages(&app.emails[0].thread_id));
+        assert_eq!(app.groups[0].count(), 2);
+    }
+
     #[test]
     fn test_group_by_email() {
         let mut app = App::new();
@@ -1484,10 +3150,49 @@ mod tests {
         app.toggle_group_mode();
         assert_eq!(app.group_mode, GroupMode::ByDomain);
 
+        app.toggle_group_mode();
+        assert_eq!(app.group_mode, GroupMode::ByLabel);
+
+        app.toggle_group_mode();
+        assert_eq!(app.group_mode, GroupMode::BySenderEmail);
+
+        app.labels_available = false;
+        app.toggle_group_mode();
         app.toggle_group_mode();
         assert_eq!(app.group_mode, GroupMode::BySenderEmail);
     }
 
+    #[test]
+    fn test_label_groups_have_no_sender_key() {
+        let mut app = App::new();
+        app.set_emails(vec![create_test_email("1", "alice@example.com")]);
+        assert_eq!(app.current_sender_key(), Some("alice@example.com"));
+        app.set_group_mode(GroupMode::ByLabel);
+        assert!(app.current_group().is_some());
+        assert_eq!(app.current_sender_key(), None);
+    }
+
+    #[test]
+    fn test_group_by_label_follows_label_changes() {
+        let mut app = App::new();
+        let mut work = create_test_email("1", "alice@example.com");
+        work.labels = vec!["Work".to_string()];
+        app.set_emails(vec![work, create_test_email("2", "bob@example.com")]);
+        app.set_group_mode(GroupMode::ByLabel);
+        let keys = |app: &App| {
+            let mut keys: Vec<String> = app.groups.iter().map(|g| g.key.clone()).collect();
+            keys.sort();
+            keys
+        };
+        assert_eq!(keys(&app), vec![NO_LABEL, "Work"]);
+
+        app.modify_labels(&["2".to_string()], &["Receipts".to_string()], &[]);
+        app.modify_labels(&["1".to_string()], &[], &["Work".to_string()]);
+        assert_eq!(keys(&app), vec![NO_LABEL, "Receipts"]);
+        let bob = app.emails.iter().find(|e| e.id == "2").unwrap();
+        assert_eq!(bob.labels, vec!["Receipts"]);
+    }
+
     #[test]
     fn test_navigation_groups() {
         let mut app = App::new();
@@ -1562,6 +3267,47 @@ mod tests {
         assert_eq!(app.selected_group, 0);
     }
 
+    #[test]
+    fn test_select_largest_group_respects_filters() {
+        let mut app = App::new();
+        app.set_emails(vec![
+            create_test_email("1", "big@test.com"),
+            create_test_email("2", "big@test.com"),
+            create_test_email("3", "big@test.com"),
+            create_test_email_with_thread("4", "t", "chatty@test.com"),
+            create_test_email_with_thread("5", "t", "chatty@test.com"),
+            create_test_email("6", "small@test.com"),
+        ]);
+        app.selected_group = app.groups.len() - 1;
+
+        app.select_largest_group();
+        assert_eq!(app.current_group().unwrap().key, "big@test.com");
+
+        app.thread_filter = ThreadFilter::OnlyThreads;
+        app.select_largest_group();
+        assert_eq!(app.current_group().unwrap().key, "chatty@test.com");
+    }
+
+    #[test]
+    fn test_select_random_group_moves_to_a_visible_group() {
+        let mut app = App::new();
+        app.set_emails(vec![
+            create_test_email("1", "a@test.com"),
+            create_test_email("2", "b@test.com"),
+            create_test_email("3", "c@test.com"),
+        ]);
+        app.set_group_text_filter(Some("a@test.com".to_string()));
+        app.select_random_group(7);
+        assert_eq!(app.current_group().unwrap().key, "a@test.com");
+
+        app.clear_group_text_filter();
+        for roll in 0..6 {
+            let before = app.selected_group;
+            app.select_random_group(roll);
+            assert_ne!(app.selected_group, before);
+        }
+    }
+
     #[test]
     fn test_select_first_and_last_emails() {
         let mut app = App::new();
@@ -1663,10 +3409,7 @@ mod tests {
 
         // Find the email from thread_a (could be at index 0 or 1 depending on order)
         let thread_a_idx = app
-            .current_group()
-            .unwrap()
-            .emails
-            .iter()
+            .group_emails(app.current_group().unwrap())
             .position(|e| e.thread_id == "thread_a")
             .unwrap();
         app.selected_email = Some(thread_a_idx);
@@ -1833,6 +3576,54 @@ mod tests {
         assert_eq!(app.selected_email, Some(0));
     }
 
+    #[test]
+    fn test_incremental_removal_and_restore_match_full_regroup() {
+        let emails = vec![
+            create_test_email_with_thread("1", "t1", "alice@example.com"),
+            create_test_email_with_thread("2", "t1", "bob@example.com"),
+            create_test_email_with_thread("3", "t2", "alice@example.com"),
+            create_test_email_with_thread("4", "t3", "carol@example.com"),
+            create_test_email_with_thread("5", "t3", "carol@example.com"),
+        ];
+        let regrouped = |emails: &[Email]| {
+            let mut app = App::new();
+            app.set_emails(emails.to_vec());
+            app
+        };
+        let group_ids = |app: &App| -> Vec<(String, Vec<String>)> {
+            app.groups
+                .iter()
+                .map(|g| {
+                    (
+                        g.key.clone(),
+                        app.group_emails(g).map(|e| e.id.clone()).collect(),
+                    )
+                })
+                .collect()
+        };
+
+        let mut app = regrouped(&emails);
+        app.remove_thread("t1");
+        let expected = regrouped(&emails[2..]);
+        assert_eq!(group_ids(&app), group_ids(&expected));
+        assert!(!app.thread_has_multiple_messages("t1"));
+        assert!(app.thread_has_multiple_messages("t3"));
+
+        app.remove_email("4");
+        assert!(!app.thread_has_multiple_messages("t3"));
+
+        app.restore_emails(vec![
+            emails[0].clone(),
+            emails[1].clone(),
+            emails[3].clone(),
+        ]);
+        let expected = regrouped(&emails);
+        assert_eq!(group_ids(&app), group_ids(&expected));
+        assert_eq!(app.group_positions, expected.group_positions);
+        assert!(app.thread_has_multiple_messages("t1"));
+        assert!(app.thread_has_multiple_messages("t3"));
+    }
+
     #[test]
     fn test_groups_sorted_by_count() {
         let mut app = App::new();
@@ -1873,36 +3664,597 @@ mod tests {
             create_test_email_with_thread("3", "thread_b", "alice@example.com"),
         ]);
 
-        let alice_group = app
-            .groups
-            .iter()
-            .find(|g| g.key == "alice@example.com")
-            .unwrap();
-        assert_eq!(alice_group.count(), 3); // 3 emails
-        assert_eq!(alice_group.thread_count(), 2); // 2 threads
+        let alice_group = app
+            .groups
+            .iter()
+            .find(|g| g.key == "alice@example.com")
+            .unwrap();
+        assert_eq!(alice_group.count(), 3); // 3 emails
+        assert_eq!(app.group_threads(alice_group).len(), 2); // 2 threads
+    }
+
+    #[test]
+    fn test_current_thread_email() {
+        let mut app = App::new();
+        app.set_emails(vec![
+            create_test_email_with_thread("1", "thread_a", "alice@example.com"),
+            create_test_email_with_thread("2", "thread_a", "bob@example.com"),
+        ]);
+
+        // Navigate to thread view
+        app.enter(); // Enter email list
+        app.enter(); // Enter thread view
+
+        // Should return the first email in the thread
+        let email_id = app.current_thread_email().map(|e| e.id.clone());
+        assert!(email_id.is_some());
+
+        // Navigate to second email in thread
+        app.select_next();
+        let email2_id = app.current_thread_email().map(|e| e.id.clone());
+        assert!(email2_id.is_some());
+        assert_ne!(email_id, email2_id);
+    }
+
+    #[test]
+    fn test_toggle_thread_inline_bodies_orders_oldest_first_and_keeps_selection() {
+        let now = chrono::Utc::now();
+        let mut older = create_test_email_with_thread("1", "thread_a", "alice@example.com");
+        older.date = now - chrono::Duration::hours(2);
+        let mut newer = create_test_email_with_thread("2", "thread_a", "alice@example.com");
+        newer.date = now;
+
+        let mut app = App::new();
+        app.set_emails(vec![older, newer]);
+        app.enter(); // Enter email list
+        app.enter(); // Enter thread view
+
+        // Default order is newest first
+        assert_eq!(app.current_thread_email().unwrap().id, "2");
+
+        app.toggle_thread_inline_bodies();
+        assert!(app.thread_inline_bodies);
+        let ids: Vec<&str> = app
+            .current_thread_emails()
+            .iter()
+            .map(|e| e.id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["1", "2"]);
+        // Cursor stays on the same email even though the order flipped
+        assert_eq!(app.current_thread_email().unwrap().id, "2");
+
+        app.toggle_thread_inline_bodies();
+        assert!(!app.thread_inline_bodies);
+        assert_eq!(app.current_thread_email().unwrap().id, "2");
+    }
+
+    #[test]
+    fn test_current_thread_email_ids_missing_body() {
+        let mut app = App::new();
+        app.set_emails(vec![
+            create_test_email_with_thread("1", "thread_a", "alice@example.com"),
+            create_test_email_with_thread("2", "thread_a", "alice@example.com"),
+        ]);
+        app.set_email_body("1", "cached".to_string());
+        app.enter(); // Enter email list
+        app.enter(); // Enter thread view
+
+        let missing = app.current_thread_email_ids_missing_body();
+        assert_eq!(missing, vec![("2".to_string(), "INBOX".to_string())]);
+    }
+
+    #[test]
+    fn test_ignore_sender_hides_threads_now_and_on_reload() {
+        let mut app = App::new();
+        let emails = vec![
+            create_test_email_with_thread("1", "thread_a", "boss@work.com"),
+            create_test_email_with_thread("2", "thread_a", "alice@example.com"),
+            create_test_email_with_thread("3", "thread_b", "alice@example.com"),
+        ];
+        app.set_emails(emails.clone());
+
+        assert_eq!(app.ignore_sender("work.com"), 2);
+        let ids: Vec<&str> = app.emails.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, vec!["3"]);
+        assert_eq!(app.groups.len(), 1);
+
+        app.set_emails(emails);
+        assert_eq!(app.emails.len(), 1);
+    }
+
+    #[test]
+    fn test_postpone_sender_hides_until_the_list_is_cleared() {
+        let mut app = App::new();
+        let emails = vec![
+            create_test_email_with_thread("1", "thread_a", "news@example.com"),
+            create_test_email_with_thread("2", "thread_b", "alice@example.com"),
+        ];
+        app.set_emails(emails.clone());
+
+        assert_eq!(app.postpone_sender("news@example.com"), 1);
+        assert_eq!(app.postponed_senders, vec!["news@example.com"]);
+        app.set_emails(emails.clone());
+        assert_eq!(app.emails.len(), 1);
+
+        // Once the postponement runs out the group is back on the next load
+        app.postponed_senders.clear();
+        app.set_emails(emails);
+        assert_eq!(app.emails.len(), 2);
+        assert!(app.ignored_senders.is_empty());
+    }
+
+    #[test]
+    fn test_reload_marks_new_arrivals_and_jumps_to_them() {
+        let mut app = App::new();
+        let first = vec![
+            create_test_email_with_thread("1", "t1", "alice@example.com"),
+            create_test_email_with_thread("2", "t2", "bob@example.com"),
+            create_test_email_with_thread("3", "t3", "carol@example.com"),
+        ];
+        // Nothing is new on the first load
+        assert_eq!(app.reload_emails(first.clone()), 0);
+        assert!(!app.jump_to_new());
+
+        let mut second = first.clone();
+        second.push(create_test_email_with_thread(
+            "4",
+            "t4",
+            "carol@example.com",
+        ));
+        second.push(create_test_email_with_thread(
+            "5",
+            "t5",
+            "alice@example.com",
+        ));
+        assert_eq!(app.reload_emails(second.clone()), 2);
+        assert!(app.is_new("4"));
+        assert!(!app.is_new("3"));
+
+        app.selected_group = app.group_positions["bob@example.com"];
+        let mut visited = HashSet::new();
+        for _ in 0..2 {
+            assert!(app.jump_to_new());
+            visited.insert(app.current_group().unwrap().key.clone());
+        }
+        let expected: HashSet<String> = ["alice@example.com", "carol@example.com"]
+            .iter()
+            .map(|k| k.to_string())
+            .collect();
+        assert_eq!(visited, expected);
+
+        // In the email list the cursor goes to the new thread
+        app.selected_group = app.group_positions["carol@example.com"];
+        app.enter_group();
+        app.selected_email = Some(0);
+        assert!(app.jump_to_new());
+        assert_eq!(app.current_email().unwrap().id, "4");
+
+        // Marks last until the mail leaves
+        assert_eq!(app.reload_emails(second), 0);
+        assert!(app.is_new("4"));
+        assert_eq!(app.reload_emails(first), 0);
+        assert!(!app.is_new("4"));
+    }
+
+    #[test]
+    fn test_dismiss_hides_for_the_session_until_undismissed() {
+        let mut app = App::new();
+        let emails = vec![
+            create_test_email_with_thread("1", "thread_a", "news@example.com"),
+            create_test_email_with_thread("2", "thread_b", "news@example.com"),
+            create_test_email_with_thread("3", "thread_c", "alice@example.com"),
+            create_test_email_with_thread("4", "thread_c", "bob@example.com"),
+        ];
+        app.set_emails(emails.clone());
+
+        // The group list hides the whole group; the cursor moves to the next one
+        app.selected_group = app.group_positions["news@example.com"];
+        assert_eq!(app.dismiss_current(), 2);
+        assert_eq!(app.dismissed_count(), 2);
+        assert!(app.emails.iter().all(|e| e.from != "news@example.com"));
+        assert!(app.current_group().is_some());
+
+        // The email list hides only the cursor thread, even mail from other senders in it
+        app.enter();
+        assert_eq!(app.view, View::EmailList);
+        assert_eq!(app.dismiss_current(), 1);
+        assert!(app.emails.is_empty());
+
+        // A refresh keeps it all hidden
+        app.set_emails(emails);
+        assert!(app.emails.is_empty());
+        assert_eq!(app.dismissed_count(), 3);
+
+        assert_eq!(app.undismiss_all(), 3);
+        assert_eq!(app.emails.len(), 4);
+        assert_eq!(app.dismissed_count(), 0);
+        assert_eq!(app.groups.len(), 3);
+    }
+
+    #[test]
+    fn test_mark_email_seen() {
+        let mut email = create_test_email("1", "alice@example.com");
+        email.seen = false;
+        let mut app = App::new();
+        app.set_emails(vec![email]);
+
+        app.mark_email_seen("1");
+        assert!(app.emails[0].seen);
+    }
+
+    fn create_duplicate_pair() -> Vec<Email> {
+        let mut first = create_test_email_with_thread("1", "thread_a", "alice@example.com");
+        first.message_id = Some("<dup@example.com>".to_string());
+        let mut second = create_test_email_with_thread("2", "thread_a", "alice@example.com");
+        second.message_id = Some("<dup@example.com>".to_string());
+        vec![first, second]
+    }
+
+    #[test]
+    fn test_enter_and_exit_duplicates_view() {
+        let mut app = App::new();
+        app.set_emails(create_duplicate_pair());
+
+        app.enter_duplicates();
+        assert_eq!(app.view, View::Duplicates);
+        assert_eq!(app.duplicate_sets.len(), 1);
+
+        app.exit();
+        assert_eq!(app.view, View::GroupList);
+        assert!(app.duplicate_sets.is_empty());
+    }
+
+    #[test]
+    fn test_duplicate_extras_current_and_all_sets() {
+        let mut app = App::new();
+        let mut emails = create_duplicate_pair();
+        let mut third = create_test_email_with_thread("3", "thread_b", "bob@example.com");
+        third.message_id = Some("<other@example.com>".to_string());
+        let mut fourth = create_test_email_with_thread("4", "thread_b", "bob@example.com");
+        fourth.message_id = Some("<other@example.com>".to_string());
+        emails.push(third);
+        emails.push(fourth);
+        app.set_emails(emails);
+        app.enter_duplicates();
+
+        assert_eq!(app.duplicate_extras(false).len(), 1);
+        assert_eq!(app.duplicate_extras(true).len(), 2);
+    }
+
+    #[test]
+    fn test_remove_emails_rescans_duplicates() {
+        let mut app = App::new();
+        app.set_emails(create_duplicate_pair());
+        app.enter_duplicates();
+
+        let removed: Vec<(String, String)> = app
+            .duplicate_extras(true)
+            .iter()
+            .map(|e| (e.id.clone(), e.source_folder.to_string()))
+            .collect();
+        app.remove_emails(&removed);
+
+        assert_eq!(app.groups[0].count(), 1);
+        assert!(app.duplicate_sets.is_empty());
+        assert_eq!(app.selected_duplicate, 0);
+    }
+
+    #[test]
+    fn test_note_mailbox_changes_only_for_loaded_folders() {
+        let mut app = App::new();
+        app.set_emails(vec![create_test_email("1", "alice@example.com")]);
+
+        app.note_mailbox_changes(&["[Gmail]/Drafts".to_string()]);
+        assert!(!app.is_mailbox_changed());
+
+        app.note_mailbox_changes(&["INBOX".to_string()]);
+        assert!(app.is_mailbox_changed());
+    }
+
+    #[test]
+    fn test_apply_resync_removes_and_adds_emails() {
+        let mut app = App::new();
+        app.set_emails(vec![
+            create_test_email("1", "alice@example.com"),
+            create_test_email("2", "bob@example.com"),
+        ]);
+        app.note_mailbox_changes(&["INBOX".to_string()]);
+
+        let added = app.apply_resync(
+            &[("1".to_string(), "INBOX".to_string())],
+            vec![create_test_email("3", "carol@example.com")],
+            false,
+        );
+
+        assert_eq!(added, 1);
+        assert!(!app.is_mailbox_changed());
+        let mut ids: Vec<String> = app
+            .loaded_email_ids()
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+        ids.sort();
+        assert_eq!(ids, vec!["2", "3"]);
+    }
+
+    #[test]
+    fn test_live_resync_keeps_the_cursor_on_its_group() {
+        let mut app = App::new();
+        app.set_emails(vec![
+            create_test_email("1", "alice@example.com"),
+            create_test_email("2", "bob@example.com"),
+        ]);
+        let bob = app.groups.iter().position(|g| g.key == "bob@example.com");
+        app.selected_group = bob.unwrap();
+
+        // Carol's two new emails sort her group above Bob's
+        let added = app.apply_live_resync(
+            &[],
+            vec![
+                create_test_email("3", "carol@example.com"),
+                create_test_email("4", "carol@example.com"),
+            ],
+            false,
+        );
+
+        assert_eq!(added, 2);
+        assert_eq!(app.groups[0].key, "carol@example.com");
+        assert_eq!(app.current_group().unwrap().key, "bob@example.com");
+        assert!(app.is_new("3"));
+    }
+
+    #[test]
+    fn test_reconcile_replaces_cached_emails_under_the_cursor() {
+        let mut app = App::new();
+        app.set_emails(vec![
+            create_test_email("1", "alice@example.com"),
+            create_test_email("2", "bob@example.com"),
+        ]);
+        let bob = app.groups.iter().position(|g| g.key == "bob@example.com");
+        app.selected_group = bob.unwrap();
+
+        // Alice's cached email was archived elsewhere; Carol's two are new
+        let arrived = app.reconcile_emails(vec![
+            create_test_email("2", "bob@example.com"),
+            create_test_email("3", "carol@example.com"),
+            create_test_email("4", "carol@example.com"),
+        ]);
+
+        assert_eq!(arrived, 2);
+        assert_eq!(app.email_count(), 3);
+        assert_eq!(app.current_group().unwrap().key, "bob@example.com");
+        assert!(app.is_new("4"));
+    }
+
+    #[test]
+    fn test_cleared_cache_reloads_without_marking_everything_new() {
+        let mut app = App::new();
+        app.set_emails(vec![create_test_email("1", "alice@example.com")]);
+        app.enter();
+        app.clear_emails();
+        assert_eq!(app.view, View::GroupList);
+        assert_eq!(app.email_count(), 0);
+
+        let arrived = app.reload_emails(vec![
+            create_test_email("7", "alice@example.com"),
+            create_test_email("8", "bob@example.com"),
+        ]);
+        assert_eq!(arrived, 0);
+        assert!(!app.is_new("7"));
+    }
+
+    #[test]
+    fn test_read_inbox_emails_leave_unread_and_flagged() {
+        let mut read = create_test_email("1", "alice@example.com");
+        read.seen = true;
+        let unread = create_test_email("2", "alice@example.com");
+        let mut flagged = create_test_email("3", "alice@example.com");
+        flagged.seen = true;
+        flagged.flagged = true;
+        let mut other_read = create_test_email("4", "bob@example.com");
+        other_read.seen = true;
+        let mut app = App::new();
+        app.set_emails(vec![read, unread, flagged, other_read]);
+
+        let ids = |emails: Vec<&Email>| -> Vec<String> {
+            let mut ids: Vec<String> = emails.iter().map(|e| e.id.clone()).collect();
+            ids.sort();
+            ids
+        };
+        assert_eq!(ids(app.read_inbox_emails(None)), vec!["1", "4"]);
+        assert_eq!(
+            ids(app.read_inbox_emails(Some("alice@example.com"))),
+            vec!["1"]
+        );
+
+        app.protected_senders = vec!["bob@example.com".to_string()];
+        assert_eq!(ids(app.read_inbox_emails(None)), vec!["1"]);
+    }
+
+    #[test]
+    fn test_sender_inbox_emails_match_the_address_only() {
+        let mut flagged = create_test_email("2", "news@example.com");
+        flagged.flagged = true;
+        let mut archived = create_test_email("3", "news@example.com");
+        archived.source_folder = "[Gmail]/All Mail".into();
+        let mut app = App::new();
+        app.set_emails(vec![
+            create_test_email("1", "News <news@example.com>"),
+            flagged,
+            archived,
+            create_test_email("4", "other@example.com"),
+        ]);
+
+        let ids: Vec<&str> = app
+            .sender_inbox_emails("NEWS@example.com")
+            .iter()
+            .map(|e| e.id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["1"]);
+    }
+
+    #[test]
+    fn test_single_message_emails_skip_threads_flagged_and_protected() {
+        let mut app = App::new();
+        let mut flagged = create_test_email("3", "carol@example.com");
+        flagged.flagged = true;
+        app.set_emails(vec![
+            create_test_email_with_thread("1", "thread_a", "alice@example.com"),
+            create_test_email_with_thread("2", "thread_a", "bob@example.com"),
+            flagged,
+            create_test_email("4", "news@shop.com"),
+            create_test_email("5", "boss@work.com"),
+        ]);
+        app.protected_senders = vec!["work.com".to_string()];
+
+        let ids: Vec<&str> = app
+            .single_message_emails()
+            .iter()
+            .map(|e| e.id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["4"]);
+    }
+
+    #[test]
+    fn test_old_inbox_emails_uses_cutoff_and_protected_senders() {
+        let mut app = App::new();
+        let mut old = create_test_email("1", "news@example.com");
+        old.date = chrono::Utc::now() - chrono::Duration::days(400);
+        let mut protected = create_test_email("2", "boss@work.com");
+        protected.date = old.date;
+        let recent = create_test_email("3", "news@example.com");
+        app.set_emails(vec![old, protected, recent]);
+        app.protected_senders = vec!["work.com".to_string()];
+
+        let ids: Vec<&str> = app
+            .old_inbox_emails()
+            .iter()
+            .map(|e| e.id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["1"]);
+
+        app.archive_older_than_days = 500;
+        assert!(app.old_inbox_emails().is_empty());
+    }
+
+    #[test]
+    fn test_group_emails_older_than_stays_in_the_group() {
+        let mut app = App::new();
+        let mut old = create_test_email("1", "news@example.com");
+        old.date = chrono::Utc::now() - chrono::Duration::days(60);
+        let mut old_elsewhere = create_test_email("2", "alice@example.com");
+        old_elsewhere.date = old.date;
+        let mut flagged = create_test_email("3", "news@example.com");
+        flagged.date = old.date;
+        flagged.flagged = true;
+        let recent = create_test_email("4", "news@example.com");
+        app.set_emails(vec![old, old_elsewhere, flagged, recent]);
+
+        let cutoff = chrono::Utc::now() - chrono::Duration::days(30);
+        let ids: Vec<&str> = app
+            .group_emails_older_than("news@example.com", cutoff)
+            .iter()
+            .map(|e| e.id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["1"]);
+        assert!(
+            app.group_emails_older_than("nobody@example.com", cutoff)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_evaluate_policies_matches_loaded_emails() {
+        let mut app = App::new();
+        app.set_emails(vec![
+            create_test_email("1", "news@example.com"),
+            create_test_email("2", "alice@example.org"),
+        ]);
+        let policies = vec![Policy {
+            name: "news".to_string(),
+            action: crate::policy::PolicyAction::Archive,
+            older_than_days: None,
+            senders: vec!["example.com".to_string()],
+            read: None,
+            newsletter: None,
+        }];
+
+        let matches = app.evaluate_policies(&policies);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].1.len(), 1);
+        assert_eq!(matches[0].1[0].id, "1");
+    }
+
+    #[test]
+    fn test_toggle_current_thread_email_collapsed() {
+        let mut app = App::new();
+        app.set_emails(vec![
+            create_test_email_with_thread("1", "thread_a", "alice@example.com"),
+            create_test_email_with_thread("2", "thread_a", "alice@example.com"),
+        ]);
+        app.enter(); // Enter email list
+        app.enter(); // Enter thread view
+
+        let id = app.current_thread_email().unwrap().id.clone();
+        assert!(!app.is_thread_email_collapsed(&id));
+        app.toggle_current_thread_email_collapsed();
+        assert!(app.is_thread_email_collapsed(&id));
+        app.toggle_current_thread_email_collapsed();
+        assert!(!app.is_thread_email_collapsed(&id));
+    }
+
+    #[test]
+    fn test_collapse_and_expand_all_thread_emails() {
+        let mut app = App::new();
+        app.set_emails(vec![
+            create_test_email_with_thread("1", "thread_a", "alice@example.com"),
+            create_test_email_with_thread("2", "thread_a", "alice@example.com"),
+        ]);
+        app.enter(); // Enter email list
+        app.enter(); // Enter thread view
+
+        app.collapse_all_thread_emails();
+        assert!(app.is_thread_email_collapsed("1"));
+        assert!(app.is_thread_email_collapsed("2"));
+        // Collapsed messages don't need their bodies fetched
+        assert!(app.current_thread_email_ids_missing_body().is_empty());
+
+        app.expand_all_thread_emails();
+        assert!(!app.is_thread_email_collapsed("1"));
+        assert!(!app.is_thread_email_collapsed("2"));
+        assert_eq!(app.current_thread_email_ids_missing_body().len(), 2);
+    }
+
+    #[test]
+    fn test_entering_thread_resets_collapsed_state() {
+        let mut app = App::new();
+        app.set_emails(vec![create_test_email_with_thread(
+            "1",
+            "thread_a",
+            "alice@example.com",
+        )]);
+        app.enter(); // Enter email list
+        app.enter(); // Enter thread view
+        app.collapse_all_thread_emails();
+
+        app.exit();
+        app.enter();
+        assert!(!app.is_thread_email_collapsed("1"));
     }
 
     #[test]
-    fn test_current_thread_email() {
+    fn test_selecting_thread_email_resets_body_scroll() {
         let mut app = App::new();
         app.set_emails(vec![
             create_test_email_with_thread("1", "thread_a", "alice@example.com"),
-            create_test_email_with_thread("2", "thread_a", "bob@example.com"),
+            create_test_email_with_thread("2", "thread_a", "alice@example.com"),
         ]);
-
-        // Navigate to thread view
         app.enter(); // Enter email list
         app.enter(); // Enter thread view
+        app.thread_body_scroll = 5;
 
-        // Should return the first email in the thread
-        let email_id = app.current_thread_email().map(|e| e.id.clone());
-        assert!(email_id.is_some());
-
-        // Navigate to second email in thread
         app.select_next();
-        let email2_id = app.current_thread_email().map(|e| e.id.clone());
-        assert!(email2_id.is_some());
-        assert_ne!(email_id, email2_id);
+        assert_eq!(app.thread_body_scroll, 0);
     }
 
     #[test]
@@ -2195,6 +4547,35 @@ mod tests {
         assert_eq!(app.view, View::GroupList);
     }
 
+    #[test]
+    fn test_recent_undo_entries_run_from_the_newest_to_the_selected_one() {
+        let mut app = App::new();
+        assert!(app.recent_undo_entries().is_empty());
+        for i in 0..4 {
+            app.push_undo(UndoEntry {
+                action_type: UndoActionType::Archive,
+                context: UndoContext::Thread {
+                    subject: format!("Email {}", i),
+                },
+                emails: vec![(None, Some(i as u32), "INBOX".to_string())],
+                current_folder: "[Gmail]/All Mail".to_string(),
+            });
+        }
+
+        app.enter_undo_history();
+        app.select_next();
+        app.select_next();
+        let subjects: Vec<String> = app
+            .recent_undo_entries()
+            .iter()
+            .map(|entry| match &entry.context {
+                UndoContext::Thread { subject } => subject.clone(),
+                UndoContext::Group { sender } => sender.clone(),
+            })
+            .collect();
+        assert_eq!(subjects, vec!["Email 3", "Email 2", "Email 1"]);
+    }
+
     #[test]
     fn test_enter_undo_history_empty() {
         let mut app = App::new();
@@ -2208,6 +4589,68 @@ mod tests {
         assert_eq!(app.view, View::GroupList);
     }
 
+    #[test]
+    fn test_expanded_undo_entry_lists_its_emails() {
+        let mut app = App::new();
+        let mut email = create_test_email("1", "News <news@example.com>");
+        email.message_id = Some("<1@example.com>".to_string());
+        email.subject = "Weekly digest".to_string();
+        app.set_emails(vec![email]);
+        app.push_undo(UndoEntry {
+            action_type: UndoActionType::Archive,
+            context: UndoContext::Group {
+                sender: "example.com".to_string(),
+            },
+            emails: vec![
+                (
+                    Some("<1@example.com>".to_string()),
+                    None,
+                    "INBOX".to_string(),
+                ),
+                (None, None, "INBOX".to_string()),
+            ],
+            current_folder: "[Gmail]/All Mail".to_string(),
+        });
+        app.remove_emails(&[("1".to_string(), "INBOX".to_string())]);
+
+        app.enter_undo_history();
+        app.toggle_undo_detail();
+        app.select_next();
+        app.select_next();
+        assert_eq!(app.undo_detail_scroll, Some(1));
+        let details = app.undo_entry_details(app.current_undo_entry().unwrap());
+        assert_eq!(
+            details[0],
+            UndoEmailDetail {
+                subject: "Weekly digest".to_string(),
+                from: "News <news@example.com>".to_string(),
+                folder: "INBOX".to_string(),
+            }
+        );
+        assert_eq!(details[1].subject, "(unknown email)");
+
+        app.pop_undo(0);
+        assert_eq!(app.undo_detail_scroll, None);
+        assert!(app.undo_summaries.is_empty());
+    }
+
+    #[test]
+    fn test_filing_counts_as_archiving_but_moving_does_not() {
+        let mut app = App::new();
+        let entry = |action_type| UndoEntry {
+            action_type,
+            context: UndoContext::Group {
+                sender: "news@example.com".to_string(),
+            },
+            emails: vec![(None, None, "INBOX".to_string()); 2],
+            current_folder: "Receipts".to_string(),
+        };
+        app.push_undo(entry(UndoActionType::Move));
+        assert_eq!(app.archived_count, 0);
+        app.push_undo(entry(UndoActionType::File));
+        assert_eq!(app.archived_count, 2);
+    }
+
     #[test]
     fn test_undo_context_variants() {
         // Test all context variants
@@ -2359,6 +4802,227 @@ mod tests {
         assert_eq!(app.selected_email_count(), 1);
     }
 
+    #[test]
+    fn test_toggle_group_done_moves_group_to_bottom() {
+        let mut app = App::new();
+        app.set_emails(vec![
+            create_test_email("1", "alice@example.com"),
+            create_test_email("2", "alice@example.com"),
+            create_test_email("3", "alice@example.com"),
+            create_test_email("4", "bob@example.com"),
+            create_test_email("5", "bob@example.com"),
+            create_test_email("6", "carol@example.com"),
+        ]);
+        let keys =
+            |app: &App| -> Vec<String> { app.groups.iter().map(|g| g.key.clone()).collect() };
+
+        assert_eq!(app.toggle_group_done(), Some(true));
+        assert!(app.is_group_done("alice@example.com"));
+        assert_eq!(
+            keys(&app),
+            vec!["bob@example.com", "carol@example.com", "alice@example.com"]
+        );
+        // The cursor stays put, landing on the next group to process
+        assert_eq!(app.current_group().unwrap().key, "bob@example.com");
+
+        // The biggest-group jump skips groups marked done
+        app.select_largest_group();
+        assert_eq!(app.current_group().unwrap().key, "bob@example.com");
+
+        // Unmarking follows the group back to its place
+        app.selected_group = 2;
+        assert_eq!(app.toggle_group_done(), Some(false));
+        assert_eq!(app.current_group().unwrap().key, "alice@example.com");
+        assert_eq!(app.selected_group, 0);
+    }
+
+    #[test]
+    fn test_min_group_size_hides_small_groups_until_toggled() {
+        let mut app = App::new();
+        app.set_emails(vec![
+            create_test_email("1", "alice@example.com"),
+            create_test_email("2", "alice@example.com"),
+            create_test_email("3", "alice@example.com"),
+            create_test_email("4", "bob@example.com"),
+            create_test_email("5", "carol@example.com"),
+        ]);
+        assert_eq!(app.toggle_small_groups(), None);
+
+        app.min_group_size = 3;
+        app.selected_group = 2;
+        assert_eq!(app.filtered_groups().len(), 1);
+        assert_eq!(app.hidden_small_group_count(), 2);
+
+        assert_eq!(app.toggle_small_groups(), Some(true));
+        assert_eq!(app.filtered_groups().len(), 3);
+        assert_eq!(app.hidden_small_group_count(), 0);
+
+        // Hiding them again pulls the cursor back onto a visible group
+        assert_eq!(app.toggle_small_groups(), Some(false));
+        assert_eq!(app.current_group().unwrap().key, "alice@example.com");
+    }
+
+    #[test]
+    fn test_filter_completion_offers_senders_and_domains_of_the_view() {
+        let mut app = App::new();
+        app.set_emails(vec![
+            create_test_email("1", "alice@example.com"),
+            create_test_email("2", "alice@example.com"),
+            create_test_email("3", "Bob@example.com"),
+            create_test_email("4", "carol@news.org"),
+        ]);
+        assert_eq!(app.filter_completion("EX").as_deref(), Some("example.com"));
+        assert_eq!(
+            app.filter_completion("b").as_deref(),
+            Some("Bob@example.com")
+        );
+        assert_eq!(app.filter_completion("example.com"), None);
+        // The domain with the most emails wins over a sender with fewer
+        app.set_emails(vec![
+            create_test_email("1", "news@nuts.org"),
+            create_test_email("2", "nina@news.org"),
+            create_test_email("3", "nick@news.org"),
+        ]);
+        assert_eq!(app.filter_completion("n").as_deref(), Some("news.org"));
+
+        // The email list offers the open group's senders and domains only
+        assert!(app.enter_group_at(0));
+        assert_eq!(app.current_group().unwrap().key, "news@nuts.org");
+        assert_eq!(app.filter_completion("n").as_deref(), Some("news@nuts.org"));
+        assert_eq!(app.filter_completion("ni"), None);
+    }
+
+    #[test]
+    fn test_jump_to_group_starting_with_cycles_through_shown_groups() {
+        let mut app = App::new();
+        app.set_emails(vec![
+            create_test_email("1", "alice@example.com"),
+            create_test_email("2", "alice@example.com"),
+            create_test_email("3", "adam@example.com"),
+            create_test_email("4", "bob@example.com"),
+        ]);
+        assert_eq!(app.group_list_summary().total_emails, 4);
+
+        assert!(app.jump_to_group_starting_with('A'));
+        assert_eq!(app.current_group().unwrap().key, "adam@example.com");
+        assert!(app.jump_to_group_starting_with('a'));
+        assert_eq!(app.current_group().unwrap().key, "alice@example.com");
+        assert!(!app.jump_to_group_starting_with('z'));
+        assert_eq!(app.current_group().unwrap().key, "alice@example.com");
+
+        // The cached list follows the filter and the groups as they change
+        app.set_group_text_filter(Some("b".to_string()));
+        assert!(!app.jump_to_group_starting_with('a'));
+        assert_eq!(app.filtered_groups().len(), 1);
+        app.set_group_text_filter(None);
+        app.remove_email("3");
+        assert_eq!(app.group_list_summary().total_emails, 3);
+        assert!(app.jump_to_group_starting_with('a'));
+        assert_eq!(app.current_group().unwrap().key, "alice@example.com");
+    }
+
+    #[test]
+    fn test_toggle_group_selection_aggregates_threads() {
+        let mut app = App::new();
+        app.set_emails(vec![
+            create_test_email_with_thread("1", "thread_a", "alice@example.com"),
+            create_test_email_with_thread("2", "thread_a", "carol@example.com"),
+            create_test_email("3", "bob@example.com"),
+            create_test_email("4", "carol@example.com"),
+        ]);
+        let position = |app: &App, key: &str| app.groups.iter().position(|g| g.key == key);
+
+        app.selected_group = position(&app, "alice@example.com").unwrap();
+        assert_eq!(app.toggle_group_selection(), SelectionResult::Toggled);
+        app.selected_group = position(&app, "bob@example.com").unwrap();
+        assert_eq!(app.toggle_group_selection(), SelectionResult::Toggled);
+        assert!(app.is_group_selected("alice@example.com"));
+        assert!(!app.is_group_selected("carol@example.com"));
+
+        // Alice's thread brings Carol's reply along, but not Carol's own thread
+        let mut ids: Vec<String> = app
+            .selected_groups_thread_email_ids()
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+        ids.sort();
+        assert_eq!(ids, vec!["1", "2", "3"]);
+        assert_eq!(app.selected_groups_thread_emails_for_undo().len(), 3);
+
+        // Toggling again deselects
+        assert_eq!(app.toggle_group_selection(), SelectionResult::Toggled);
+        assert_eq!(app.selected_group_keys(), vec!["alice@example.com"]);
+    }
+
+    #[test]
+    fn test_group_selection_only_counts_visible_groups() {
+        let mut app = App::new();
+        app.set_emails(vec![
+            create_test_email("1", "alice@example.com"),
+            create_test_email("2", "bob@example.com"),
+        ]);
+        for index in 0..app.groups.len() {
+            app.selected_group = index;
+            app.toggle_group_selection();
+        }
+        assert_eq!(app.selected_group_keys().len(), 2);
+
+        app.set_group_text_filter(Some("alice".to_string()));
+        assert_eq!(app.selected_group_keys(), vec!["alice@example.com"]);
+        assert_eq!(app.selected_groups_thread_email_ids().len(), 1);
+
+        // Only works in the group list, and switching modes starts over
+        app.enter();
+        assert_eq!(app.toggle_group_selection(), SelectionResult::NoEmail);
+        app.exit();
+        app.toggle_group_mode();
+        assert!(app.selected_group_keys().is_empty());
+    }
+
+    #[test]
+    fn test_sweep_archives_everything_not_kept() {
+        let mut app = App::new();
+        let mut flagged = create_test_email("5", "dave@example.com");
+        flagged.flagged = true;
+        app.set_emails(vec![
+            create_test_email_with_thread("1", "thread_a", "alice@example.com"),
+            create_test_email_with_thread("2", "thread_a", "bob@example.com"),
+            create_test_email("3", "bob@example.com"),
+            create_test_email("4", "carol@example.com"),
+            flagged,
+        ]);
+        assert!(app.sweep_emails().is_empty());
+        assert_eq!(app.toggle_keep(), SelectionResult::NoEmail);
+
+        app.start_sweep();
+        // Keep Alice's group, which keeps Bob's reply in her thread too
+        app.selected_group = app
+            .groups
+            .iter()
+            .position(|g| g.key == "alice@example.com")
+            .unwrap();
+        assert_eq!(app.toggle_keep(), SelectionResult::Toggled);
+        assert!(app.is_kept_group("alice@example.com"));
+        assert!(app.kept_thread_ids().contains("thread_a"));
+
+        // Keep one of Carol's threads from the email list
+        app.selected_group = app
+            .groups
+            .iter()
+            .position(|g| g.key == "carol@example.com")
+            .unwrap();
+        app.enter();
+        assert_eq!(app.toggle_keep(), SelectionResult::Toggled);
+        app.exit();
+
+        let ids: Vec<&str> = app.sweep_emails().iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, vec!["3"]);
+
+        app.cancel_sweep();
+        assert!(app.sweep_marks().is_none());
+        assert!(!app.is_kept_group("alice@example.com"));
+    }
+
     #[test]
     fn test_selection_cleared_on_view_change() {
         let mut app = App::new();
@@ -2453,6 +5117,46 @@ mod tests {
         assert_eq!(app.filtered_threads_in_current_group().len(), 3);
     }
 
+    #[test]
+    fn test_spam_score_filter_and_sort() {
+        let mut app = App::new();
+        let scored = |id: &str, score: Option<f32>| {
+            let mut email = create_test_email_with_subject(id, "alice@example.com", id);
+            email.spam_score = score;
+            email
+        };
+        app.set_emails(vec![
+            scored("1", Some(1.5)),
+            scored("2", None),
+            scored("3", Some(8.0)),
+            scored("4", Some(4.0)),
+        ]);
+        app.enter(); // Enter email list
+
+        app.set_email_text_filter(Some("spam>3".to_string()));
+        let ids: Vec<&str> = app
+            .filtered_threads_in_current_group()
+            .iter()
+            .map(|e| e.id.as_str())
+            .collect();
+        assert_eq!(ids.len(), 2);
+        assert!(ids.contains(&"3") && ids.contains(&"4"));
+        app.set_email_text_filter(Some("spam<2".to_string()));
+        assert_eq!(app.filtered_threads_in_current_group()[0].id, "1");
+        app.clear_email_text_filter();
+
+        let current = app.current_email().unwrap().id.clone();
+        app.toggle_spam_score_sort();
+        let ids: Vec<&str> = app
+            .filtered_threads_in_current_group()
+            .iter()
+            .map(|e| e.id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["3", "4", "1", "2"]);
+        // The cursor follows the email it was on
+        assert_eq!(app.current_email().unwrap().id, current);
+    }
+
     #[test]
     fn test_text_filter_is_case_insensitive() {
         let mut app = App::new();
@@ -2717,6 +5421,110 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_enter_group_at_opens_the_numbered_group() {
+        let mut app = App::new();
+        app.set_emails(vec![
+            create_test_email("1", "alice@example.com"),
+            create_test_email("2", "bob@example.com"),
+            create_test_email("3", "bob@example.com"),
+        ]);
+
+        assert!(!app.enter_group_at(2));
+        assert_eq!(app.view, View::GroupList);
+        assert!(app.enter_group_at(1));
+        assert_eq!(app.view, View::EmailList);
+        assert_eq!(app.current_group().unwrap().key, "alice@example.com");
+    }
+
+    #[test]
+    fn test_seen_targets_cover_whole_threads_in_the_email_list() {
+        let mut app = App::new();
+        app.set_emails(vec![
+            create_test_email_with_thread("1", "thread_a", "alice@example.com"),
+            create_test_email_with_thread("2", "thread_a", "bob@example.com"),
+            create_test_email_with_thread("3", "thread_b", "alice@example.com"),
+        ]);
+        app.enter(); // Enter alice's group
+        app.selected_emails.insert("1".to_string());
+
+        let mut ids: Vec<String> = app.seen_targets().iter().map(|e| e.id.clone()).collect();
+        ids.sort();
+        assert_eq!(ids, vec!["1", "2"]);
+
+        app.set_seen(&ids, true);
+        assert!(!app.unread_thread_ids().contains("thread_a"));
+        assert!(app.unread_thread_ids().contains("thread_b"));
+    }
+
+    #[test]
+    fn test_returning_to_a_group_restores_its_cursor() {
+        let mut app = App::new();
+        app.set_emails(vec![
+            create_test_email_with_thread("1", "thread_a", "alice@example.com"),
+            create_test_email_with_thread("2", "thread_b", "alice@example.com"),
+            create_test_email_with_thread("3", "thread_c", "alice@example.com"),
+            create_test_email_with_thread("4", "thread_d", "bob@example.com"),
+        ]);
+        let alice = app.groups.iter().position(|g| g.key == "alice@example.com");
+        app.selected_group = alice.unwrap();
+        app.enter();
+        app.select_next();
+        app.select_next();
+        let thread = app.current_email().unwrap().thread_id.clone();
+        app.exit();
+
+        app.select_next();
+        app.enter(); // Bob's group starts at the top
+        assert_eq!(app.selected_email, Some(0));
+        app.exit();
+
+        app.selected_group = alice.unwrap();
+        app.enter();
+        assert_eq!(app.selected_email, Some(2));
+        assert_eq!(app.current_email().unwrap().thread_id, thread);
+    }
+
+    #[test]
+    fn test_move_targets_follow_the_view_and_moved_threads_leave_it() {
+        let mut app = App::new();
+        app.set_emails(vec![
+            create_test_email_with_thread("1", "thread_a", "alice@example.com"),
+            create_test_email_with_thread("2", "thread_a", "bob@example.com"),
+            create_test_email_with_thread("3", "thread_b", "alice@example.com"),
+        ]);
+        let ids = |app: &App| {
+            let mut ids: Vec<String> = app.move_targets().1.iter().map(|e| e.id.clone()).collect();
+            ids.sort();
+            ids
+        };
+        // The group list moves the sender's threads, including other senders' replies
+        assert_eq!(ids(&app), vec!["1", "2", "3"]);
+
+        app.enter(); // Enter alice's group
+        app.selected_emails.insert("3".to_string());
+        assert_eq!(
+            app.move_targets().0,
+            UndoContext::Group {
+                sender: "1 selected".to_string()
+            }
+        );
+        assert_eq!(ids(&app), vec!["3"]);
+
+        app.clear_selection();
+        app.enter(); // Open the cursor thread
+        let targets = ids(&app);
+        let moved: Vec<(String, String)> = app
+            .move_targets()
+            .1
+            .iter()
+            .map(|e| (e.id.clone(), e.source_folder.to_string()))
+            .collect();
+        app.remove_moved_emails(&moved);
+        assert_eq!(app.view, View::EmailList);
+        assert_eq!(app.emails.len(), 3 - targets.len());
+    }
+
     #[test]
     fn test_remove_selected_threads_preserves_hidden_selections() {
         // When filtering hides some selected emails, removing visible selections
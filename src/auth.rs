@@ -0,0 +1,139 @@
+//! Sender authentication (DKIM, SPF, DMARC) as reported by the receiving server
+
+use mailparse::{MailHeader, MailHeaderMap};
+use serde::{Deserialize, Serialize};
+
+/// The outcome of one authentication check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Verdict {
+    Pass,
+    /// fail, softfail or permerror: the message may not be from who it claims
+    Fail,
+    /// none, neutral, temperror or policy: nothing conclusive either way
+    Neutral,
+}
+
+impl Verdict {
+    fn parse(result: &str) -> Self {
+        match result.to_lowercase().as_str() {
+            "pass" => Verdict::Pass,
+            "fail" | "softfail" | "permerror" => Verdict::Fail,
+            _ => Verdict::Neutral,
+        }
+    }
+}
+
+/// DKIM, SPF and DMARC results for an email; None when the server didn't report a check
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuthResults {
+    pub dkim: Option<Verdict>,
+    pub spf: Option<Verdict>,
+    pub dmarc: Option<Verdict>,
+}
+
+impl AuthResults {
+    /// Parses raw message headers
+    pub fn parse(headers: &[u8]) -> Self {
+        mailparse::parse_headers(headers)
+            .map(|(headers, _)| Self::from_headers(&headers))
+            .unwrap_or_default()
+    }
+
+    /// Reads the topmost Authentication-Results header, which the receiving server adds.
+    /// Lower ones can come from the sender and aren't trusted. Received-SPF fills in SPF
+    /// when Authentication-Results doesn't mention it.
+    pub fn from_headers(headers: &[MailHeader]) -> Self {
+        let mut results = AuthResults::default();
+        if let Some(value) = headers.get_first_value("Authentication-Results") {
+            // The first element is the server's ID; the rest are "method=result ..." entries
+            for entry in value.split(';').skip(1) {
+                let Some((method, rest)) = entry.trim().split_once('=') else {
+                    continue;
+                };
+                let verdict = Verdict::parse(rest.split_whitespace().next().unwrap_or_default());
+                let slot = match method.trim().to_lowercase().as_str() {
+                    "dkim" => &mut results.dkim,
+                    "spf" => &mut results.spf,
+                    "dmarc" => &mut results.dmarc,
+                    _ => continue,
+                };
+                // With several DKIM signatures, one that passes is enough
+                if *slot != Some(Verdict::Pass) {
+                    *slot = Some(verdict);
+                }
+            }
+        }
+        if results.spf.is_none()
+            && let Some(value) = headers.get_first_value("Received-SPF")
+        {
+            results.spf = value.split_whitespace().next().map(Verdict::parse);
+        }
+        results
+    }
+
+    /// Whether the server reported any check at all
+    pub fn is_empty(&self) -> bool {
+        self.dkim.is_none() && self.spf.is_none() && self.dmarc.is_none()
+    }
+
+    /// Whether any reported check failed
+    pub fn has_failure(&self) -> bool {
+        [self.dkim, self.spf, self.dmarc].contains(&Some(Verdict::Fail))
+    }
+
+    /// Each reported check with its name, in display order
+    pub fn checks(&self) -> impl Iterator<Item = (&'static str, Verdict)> {
+        [
+            ("DKIM", self.dkim),
+            ("SPF", self.spf),
+            ("DMARC", self.dmarc),
+        ]
+        .into_iter()
+        .filter_map(|(name, verdict)| verdict.map(|v| (name, v)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_gmail_authentication_results() {
+        let headers = concat!(
+            "Delivered-To: me@gmail.com\r\n",
+            "Authentication-Results: mx.google.com;\r\n",
+            "       dkim=pass header.i=@example.com header.s=s1 header.b=abc;\r\n",
+            "       spf=softfail (google.com: domain of transitioning x@example.com) smtp.mailfrom=x@example.com;\r\n",
+            "       dmarc=fail (p=REJECT sp=REJECT dis=QUARANTINE) header.from=example.com\r\n",
+            "Authentication-Results: forged.example; dkim=pass; spf=pass; dmarc=pass\r\n",
+            "Subject: Hi\r\n\r\n",
+        )
+        .as_bytes();
+        let results = AuthResults::parse(headers);
+        assert_eq!(results.dkim, Some(Verdict::Pass));
+        assert_eq!(results.spf, Some(Verdict::Fail));
+        assert_eq!(results.dmarc, Some(Verdict::Fail));
+        assert!(results.has_failure());
+    }
+
+    #[test]
+    fn test_falls_back_to_received_spf() {
+        let headers =
+            b"Received-SPF: pass (google.com: domain of a@example.com designates 1.2.3.4)\r\n\
+Subject: Hi\r\n\r\n";
+        let results = AuthResults::parse(headers);
+        assert_eq!(results.spf, Some(Verdict::Pass));
+        assert_eq!(results.dkim, None);
+        assert!(!results.has_failure());
+        assert_eq!(
+            results.checks().collect::<Vec<_>>(),
+            vec![("SPF", Verdict::Pass)]
+        );
+    }
+
+    #[test]
+    fn test_no_headers_means_no_results() {
+        assert!(AuthResults::parse(b"Subject: Hi\r\n\r\n").is_empty());
+    }
+}
@@ -0,0 +1,330 @@
+//! `zeroterm bench`: loads the inbox the way the TUI does, timing each stage and each
+//! fetch connection, and prints a report to attach to performance issues
+
+use std::collections::HashSet;
+use std::fmt::Write as _;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{Result, anyhow};
+
+use crate::app::App;
+use crate::config::{AccountConfig, Config};
+use crate::email::{self, Email};
+use crate::imap_client::ImapClient;
+use crate::imap_error;
+use crate::ui::widgets::format_thousands;
+use crate::{FetchChunk, FetchPage, imap_date};
+
+/// One step of loading the inbox
+struct Stage {
+    name: &'static str,
+    elapsed: Duration,
+    /// What the step produced, e.g. "1,204 groups"
+    detail: String,
+}
+
+/// How long one parallel fetch connection took
+struct Connection {
+    folder: &'static str,
+    chunk: String,
+    emails: usize,
+    connect: Duration,
+    fetch: Duration,
+}
+
+/// Everything the report shows. Holds no addresses or mail, so it is safe to share.
+struct Report {
+    backend: String,
+    security: String,
+    parallel_connections: usize,
+    fetch_window: Option<String>,
+    fetch_limit: Option<u32>,
+    merge_subject_threads: bool,
+    stages: Vec<Stage>,
+    connections: Vec<Connection>,
+}
+
+/// Emails per second, or 0 for a step too quick to measure
+fn rate(count: usize, elapsed: Duration) -> usize {
+    let secs = elapsed.as_secs_f64();
+    if secs > 0.0 {
+        (count as f64 / secs) as usize
+    } else {
+        0
+    }
+}
+
+fn seconds(elapsed: Duration) -> String {
+    format!("{:.2}s", elapsed.as_secs_f64())
+}
+
+impl Report {
+    fn render(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "zeroterm {} bench ({} {})",
+            env!("CARGO_PKG_VERSION"),
+            std::env::consts::OS,
+            std::env::consts::ARCH
+        );
+        let _ = writeln!(
+            out,
+            "backend: {}, security: {}, parallel_connections: {}, fetch_window: {}, fetch_limit: {}, merge_subject_threads: {}",
+            self.backend,
+            self.security,
+            self.parallel_connections,
+            self.fetch_window.as_deref().unwrap_or("all"),
+            self.fetch_limit
+                .map_or_else(|| "none".to_string(), |n| n.to_string()),
+            self.merge_subject_threads
+        );
+
+        let _ = writeln!(out, "\n{:<10} {:>9}  Result", "Stage", "Time");
+        for stage in &self.stages {
+            let _ = writeln!(
+                out,
+                "{:<10} {:>9}  {}",
+                stage.name,
+                seconds(stage.elapsed),
+                stage.detail
+            );
+        }
+        let total: Duration = self.stages.iter().map(|s| s.elapsed).sum();
+        let _ = writeln!(out, "{:<10} {:>9}", "total", seconds(total));
+
+        if !self.connections.is_empty() {
+            let _ = writeln!(
+                out,
+                "\n{:>3}  {:<18} {:<20} {:>8} {:>9} {:>9} {:>9}",
+                "#", "Folder", "Range", "Emails", "Connect", "Fetch", "Emails/s"
+            );
+            for (i, conn) in self.connections.iter().enumerate() {
+                let _ = writeln!(
+                    out,
+                    "{:>3}  {:<18} {:<20} {:>8} {:>9} {:>9} {:>9}",
+                    i + 1,
+                    conn.folder,
+                    conn.chunk,
+                    format_thousands(conn.emails),
+                    seconds(conn.connect),
+                    seconds(conn.fetch),
+                    format_thousands(rate(conn.emails, conn.fetch))
+                );
+            }
+        }
+        out
+    }
+}
+
+/// Describes a chunk for the connection table
+fn chunk_label(chunk: &FetchChunk) -> String {
+    match chunk {
+        FetchChunk::Range(start, end) => format!(
+            "{}-{}",
+            format_thousands(*start as usize),
+            format_thousands(*end as usize)
+        ),
+        FetchChunk::Uids(uids) => format!("{} UIDs", format_thousands(uids.len())),
+    }
+}
+
+/// Fetches one chunk on a new connection, timing the login and the fetch separately.
+/// Unlike the TUI it doesn't retry, so a flaky connection shows up in the report.
+fn fetch_chunk(
+    account: &AccountConfig,
+    folder: &'static str,
+    chunk: &FetchChunk,
+) -> Result<(Vec<Email>, Duration, Duration)> {
+    let started = Instant::now();
+    let mut client = ImapClient::connect(account)?;
+    let connect = started.elapsed();
+    let started = Instant::now();
+    let emails = match chunk {
+        FetchChunk::Range(start, end) => client.fetch_folder_range(folder, *start, *end, None)?,
+        FetchChunk::Uids(uids) => client.fetch_folder_uids(folder, uids, None)?,
+    };
+    let fetch = started.elapsed();
+    let _ = client.logout();
+    Ok((emails, connect, fetch))
+}
+
+/// Runs the benchmark against an account and prints the report
+pub fn run(cfg: &Config, account: &AccountConfig) -> Result<()> {
+    let mut stages = Vec::new();
+
+    println!("Connecting...");
+    let started = Instant::now();
+    let mut client = ImapClient::connect(account)
+        .map_err(|e| anyhow!(imap_error::describe("Failed to connect", &e)))?;
+    stages.push(Stage {
+        name: "connect",
+        elapsed: started.elapsed(),
+        detail: String::new(),
+    });
+
+    // The same search and page as the TUI's first fetch
+    println!("Planning the fetch...");
+    let since = account.fetch_window_start(chrono::Utc::now().date_naive())?;
+    let search = since.map(|date| format!("SINCE {}", imap_date(date)));
+    let page = cfg.fetch_limit.map(FetchPage::newest);
+    // plan_fetch reports retries to the UI; nobody is listening here
+    let (resp_tx, _resp_rx) = mpsc::channel();
+    let started = Instant::now();
+    let jobs = crate::plan_fetch(
+        &mut client,
+        &resp_tx,
+        cfg.parallel_connections,
+        search.as_deref(),
+        page.as_ref(),
+    )?;
+    stages.push(Stage {
+        name: "plan",
+        elapsed: started.elapsed(),
+        detail: format!("{} connections", jobs.len()),
+    });
+    let _ = client.logout();
+
+    println!("Fetching over {} connections...", jobs.len());
+    let started = Instant::now();
+    let handles: Vec<_> = jobs
+        .iter()
+        .cloned()
+        .map(|(folder, chunk)| {
+            let account = account.clone();
+            thread::spawn(move || fetch_chunk(&account, folder, &chunk))
+        })
+        .collect();
+    let mut emails = Vec::new();
+    let mut connections = Vec::new();
+    for (handle, (folder, chunk)) in handles.into_iter().zip(&jobs) {
+        let (fetched, connect, fetch) = handle
+            .join()
+            .map_err(|_| anyhow!("Fetch thread panicked"))?
+            .map_err(|e| {
+                anyhow!(imap_error::describe(
+                    &format!("Fetching {} {} failed", folder, chunk_label(chunk)),
+                    &e
+                ))
+            })?;
+        connections.push(Connection {
+            folder,
+            chunk: chunk_label(chunk),
+            emails: fetched.len(),
+            connect,
+            fetch,
+        });
+        emails.extend(fetched);
+    }
+    let elapsed = started.elapsed();
+    stages.push(Stage {
+        name: "fetch",
+        elapsed,
+        detail: format!(
+            "{} emails ({}/s)",
+            format_thousands(emails.len()),
+            format_thousands(rate(emails.len(), elapsed))
+        ),
+    });
+
+    println!("Processing...");
+    let fetched = emails.len();
+    let started = Instant::now();
+    email::dedupe_emails(&mut emails);
+    stages.push(Stage {
+        name: "dedupe",
+        elapsed: started.elapsed(),
+        detail: format!(
+            "{} -> {} emails",
+            format_thousands(fetched),
+            format_thousands(emails.len())
+        ),
+    });
+
+    let started = Instant::now();
+    email::build_thread_ids(&mut emails);
+    if cfg.merge_subject_threads {
+        email::merge_subject_threads(&mut emails);
+    }
+    let threads: HashSet<&str> = emails.iter().map(|e| e.thread_id.as_str()).collect();
+    let thread_count = threads.len();
+    stages.push(Stage {
+        name: "thread",
+        elapsed: started.elapsed(),
+        detail: format!("{} threads", format_thousands(thread_count)),
+    });
+
+    let started = Instant::now();
+    let mut app = App::new();
+    app.set_emails(emails);
+    stages.push(Stage {
+        name: "group",
+        elapsed: started.elapsed(),
+        detail: format!("{} groups", format_thousands(app.filtered_groups().len())),
+    });
+
+    let report = Report {
+        backend: format!("{:?}", account.backend).to_lowercase(),
+        security: format!("{:?}", account.security).to_lowercase(),
+        parallel_connections: cfg.parallel_connections,
+        fetch_window: account.fetch_window.clone(),
+        fetch_limit: cfg.fetch_limit,
+        merge_subject_threads: cfg.merge_subject_threads,
+        stages,
+        connections,
+    };
+    println!("\n{}", report.render());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_lists_stages_and_connections() {
+        let report = Report {
+            backend: "gmail".to_string(),
+            security: "tls".to_string(),
+            parallel_connections: 2,
+            fetch_window: Some("6 months".to_string()),
+            fetch_limit: None,
+            merge_subject_threads: false,
+            stages: vec![
+                Stage {
+                    name: "connect",
+                    elapsed: Duration::from_millis(400),
+                    detail: String::new(),
+                },
+                Stage {
+                    name: "fetch",
+                    elapsed: Duration::from_millis(2600),
+                    detail: "12,000 emails (4,615/s)".to_string(),
+                },
+            ],
+            connections: vec![Connection {
+                folder: "INBOX",
+                chunk: chunk_label(&FetchChunk::Range(1, 12000)),
+                emails: 12000,
+                connect: Duration::from_millis(350),
+                fetch: Duration::from_secs(2),
+            }],
+        };
+        let text = report.render();
+        assert!(text.contains("fetch_window: 6 months, fetch_limit: none"));
+        assert!(text.contains("fetch          2.60s  12,000 emails (4,615/s)"));
+        assert!(text.contains("total          3.00s"));
+        let row = text.lines().last().unwrap();
+        assert!(row.contains("INBOX"));
+        assert!(row.contains("1-12,000"));
+        assert!(row.ends_with("6,000"));
+    }
+
+    #[test]
+    fn test_rate_of_an_instant_step_is_zero() {
+        assert_eq!(rate(100, Duration::ZERO), 0);
+        assert_eq!(rate(100, Duration::from_millis(500)), 200);
+    }
+}
@@ -251,7 +251,7 @@ fn main() -> Result<()> {
 
     // Sort by UID descending - higher UID = more recently added to folder
     // For Trash, this means most recently deleted first
-    emails.sort_by(|a, b| b.uid.cmp(&a.uid));
+    emails.sort_by_key(|e| std::cmp::Reverse(e.uid));
 
     println!("Parsed {} emails from [Gmail]/Trash", emails.len());
     println!();
@@ -291,7 +291,7 @@ fn main() -> Result<()> {
             }
 
             let mut counts: Vec<_> = sender_counts.into_iter().collect();
-            counts.sort_by(|a, b| b.1.cmp(&a.1));
+            counts.sort_by_key(|c| std::cmp::Reverse(c.1));
 
             println!(
                 "Top senders in [Gmail]/Trash (most recent {} emails):",
@@ -401,25 +401,53 @@ struct Account {
     password: String,
 }
 
-fn load_config() -> Result<HashMap<String, Account>> {
-    // Try to load from zeroterm config
-    let xdg_dirs = xdg::BaseDirectories::with_prefix("zeroterm")
-        .context("Failed to determine config directory")?;
-    let config_path = xdg_dirs.get_config_home().join("config.toml");
-
-    let content = std::fs::read_to_string(&config_path)
-        .with_context(|| format!("Failed to read config from {:?}", config_path))?;
+fn read_toml(path: &std::path::Path) -> Result<toml::Value> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read config from {:?}", path))?;
+    toml::from_str(&content).with_context(|| format!("Failed to parse {:?}", path))
+}
 
-    let config: toml::Value = toml::from_str(&content).context("Failed to parse config.toml")?;
+fn load_config() -> Result<HashMap<String, Account>> {
+    // Try to load from zeroterm config, or ZEROTERM_CONFIG if set
+    let config_path = match env::var_os("ZEROTERM_CONFIG") {
+        Some(path) => std::path::PathBuf::from(path),
+        None => xdg::BaseDirectories::with_prefix("zeroterm")
+            .context("Failed to determine config directory")?
+            .get_config_home()
+            .join("config.toml"),
+    };
+    let config = read_toml(&config_path)?;
+
+    // Accounts can also live in files named by `include`
+    let mut files = vec![config.clone()];
+    let dir = config_path.parent().unwrap_or(std::path::Path::new("."));
+    for pattern in config
+        .get("include")
+        .and_then(|i| i.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|p| p.as_str())
+    {
+        let pattern = dir.join(pattern);
+        let paths = glob::glob(&pattern.to_string_lossy())
+            .with_context(|| format!("Invalid include pattern {:?}", pattern))?;
+        for path in paths {
+            files.push(read_toml(&path?)?);
+        }
+    }
 
-    let accounts_table = config
-        .get("accounts")
-        .and_then(|a| a.as_table())
-        .context("No accounts in config")?;
+    let accounts_table: toml::map::Map<String, toml::Value> = files
+        .iter()
+        .filter_map(|f| f.get("accounts").and_then(|a| a.as_table()))
+        .flat_map(|t| t.clone())
+        .collect();
+    if accounts_table.is_empty() {
+        anyhow::bail!("No accounts in config");
+    }
 
     let mut accounts = HashMap::new();
 
-    for (name, account_value) in accounts_table {
+    for (name, account_value) in &accounts_table {
         let email = account_value
             .get("email")
             .and_then(|e| e.as_str())
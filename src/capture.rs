@@ -0,0 +1,216 @@
+//! Appends emails as org-mode or markdown entries to the configured capture_file
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::email::Email;
+
+/// Entry format, chosen by the capture file's extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptureFormat {
+    Org,
+    Markdown,
+}
+
+impl CaptureFormat {
+    /// Uses org-mode for .org files and markdown for everything else
+    pub fn for_path(path: &Path) -> Self {
+        if path.extension().is_some_and(|ext| ext == "org") {
+            CaptureFormat::Org
+        } else {
+            CaptureFormat::Markdown
+        }
+    }
+}
+
+/// Expands a leading ~/ to the home directory
+pub fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), std::env::var_os("HOME")) {
+        (Ok(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Returns the first `max_lines` lines of the body, skipping leading blank lines
+fn excerpt(body: &str, max_lines: usize) -> Vec<&str> {
+    body.lines()
+        .skip_while(|line| line.trim().is_empty())
+        .take(max_lines)
+        .map(str::trim_end)
+        .collect()
+}
+
+/// Formats one captured email. The excerpt comes from the loaded body, or the
+/// snippet when the body hasn't been fetched; `excerpt_lines` of 0 leaves it out.
+pub fn format_entry(
+    format: CaptureFormat,
+    email: &Email,
+    link: Option<&str>,
+    excerpt_lines: usize,
+    now: DateTime<Local>,
+) -> String {
+    let received = email.date.with_timezone(&Local);
+    let text = email.body.as_deref().unwrap_or(&email.snippet);
+    let lines = excerpt(text, excerpt_lines);
+    let mut entry = String::new();
+    match format {
+        CaptureFormat::Org => {
+            entry.push_str(&format!("* {}\n", email.subject));
+            entry.push_str(":PROPERTIES:\n");
+            entry.push_str(&format!(
+                ":CAPTURED: {}\n",
+                now.format("[%Y-%m-%d %a %H:%M]")
+            ));
+            entry.push_str(&format!(":FROM: {}\n", email.from));
+            entry.push_str(&format!(
+                ":DATE: {}\n",
+                received.format("[%Y-%m-%d %a %H:%M]")
+            ));
+            entry.push_str(":END:\n");
+            if let Some(link) = link {
+                entry.push_str(&format!("[[{}][Open in Gmail]]\n", link));
+            }
+            if !lines.is_empty() {
+                entry.push_str("#+begin_quote\n");
+                for line in lines {
+                    // A comma keeps body lines from being read as headings or block markers
+                    if line.starts_with('*') || line.starts_with("#+") {
+                        entry.push(',');
+                    }
+                    entry.push_str(line);
+                    entry.push('\n');
+                }
+                entry.push_str("#+end_quote\n");
+            }
+        }
+        CaptureFormat::Markdown => {
+            entry.push_str(&format!("## {}\n\n", email.subject));
+            entry.push_str(&format!("- Captured: {}\n", now.format("%Y-%m-%d %H:%M")));
+            entry.push_str(&format!("- From: {}\n", email.from));
+            entry.push_str(&format!("- Date: {}\n", received.format("%Y-%m-%d %H:%M")));
+            if let Some(link) = link {
+                entry.push_str(&format!("- Link: <{}>\n", link));
+            }
+            if !lines.is_empty() {
+                entry.push('\n');
+                for line in lines {
+                    if line.is_empty() {
+                        entry.push_str(">\n");
+                    } else {
+                        entry.push_str(&format!("> {}\n", line));
+                    }
+                }
+            }
+            entry.push('\n');
+        }
+    }
+    entry
+}
+
+/// Appends an email to the capture file, creating the file and its directory if needed
+pub fn capture_email(
+    path: &Path,
+    email: &Email,
+    link: Option<&str>,
+    excerpt_lines: usize,
+) -> Result<()> {
+    let entry = format_entry(
+        CaptureFormat::for_path(path),
+        email,
+        link,
+        excerpt_lines,
+        Local::now(),
+    );
+    if let Some(dir) = path.parent()
+        && !dir.as_os_str().is_empty()
+    {
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    file.write_all(entry.as_bytes())
+        .with_context(|| format!("Failed to write to {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::email::EmailBuilder;
+
+    fn email() -> Email {
+        let mut email = EmailBuilder::new()
+            .id("1")
+            .from("Alice <alice@example.com>")
+            .subject("Flight confirmation")
+            .snippet("Your flight is booked")
+            .build();
+        email.body = Some("\n\nBooking ref ABC123\n* Seat 12A\n\nSee you soon\n".to_string());
+        email
+    }
+
+    #[test]
+    fn test_format_follows_extension() {
+        assert_eq!(
+            CaptureFormat::for_path(Path::new("notes/inbox.org")),
+            CaptureFormat::Org
+        );
+        assert_eq!(
+            CaptureFormat::for_path(Path::new("notes/inbox.md")),
+            CaptureFormat::Markdown
+        );
+    }
+
+    #[test]
+    fn test_markdown_entry_quotes_excerpt() {
+        let entry = format_entry(
+            CaptureFormat::Markdown,
+            &email(),
+            Some("https://mail.example/1"),
+            3,
+            Local::now(),
+        );
+        assert!(entry.starts_with("## Flight confirmation\n\n- Captured: "));
+        assert!(entry.contains("- From: Alice <alice@example.com>\n"));
+        assert!(entry.contains("- Link: <https://mail.example/1>\n"));
+        assert!(entry.ends_with("\n> Booking ref ABC123\n> * Seat 12A\n>\n\n"));
+    }
+
+    #[test]
+    fn test_org_entry_escapes_headings_in_excerpt() {
+        let entry = format_entry(CaptureFormat::Org, &email(), None, 2, Local::now());
+        assert!(entry.starts_with("* Flight confirmation\n:PROPERTIES:\n"));
+        assert!(!entry.contains("Open in Gmail"));
+        assert!(entry.ends_with("#+begin_quote\nBooking ref ABC123\n,* Seat 12A\n#+end_quote\n"));
+    }
+
+    #[test]
+    fn test_excerpt_falls_back_to_snippet() {
+        let mut email = email();
+        email.body = None;
+        let entry = format_entry(CaptureFormat::Markdown, &email, None, 5, Local::now());
+        assert!(entry.ends_with("\n> Your flight is booked\n\n"));
+    }
+
+    #[test]
+    fn test_zero_excerpt_lines_leaves_body_out() {
+        let entry = format_entry(CaptureFormat::Org, &email(), None, 0, Local::now());
+        assert!(!entry.contains("begin_quote"));
+    }
+
+    #[test]
+    fn test_capture_appends_entries() {
+        let path =
+            std::env::temp_dir().join(format!("zeroterm-capture-{}/notes.md", std::process::id()));
+        capture_email(&path, &email(), None, 1).unwrap();
+        capture_email(&path, &email(), None, 1).unwrap();
+        let written = fs::read_to_string(&path).unwrap();
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+        assert_eq!(written.matches("## Flight confirmation").count(), 2);
+    }
+}
@@ -0,0 +1,257 @@
+//! Non-interactive commands that run without the TUI
+
+use std::io::{self, BufRead, Write};
+use std::str::FromStr;
+
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{DateTime, Duration, Utc};
+
+use crate::config::{self, AccountConfig, Config};
+use crate::email::{Email, build_thread_ids, remove_ignored_threads, stale_inbox_emails};
+use crate::ignore::{self, IgnoreList};
+use crate::imap_client::{BATCH_SIZE, EmailClient, ImapClient};
+use crate::imap_error;
+use crate::webhook::{self, BulkSummary};
+
+/// Parses `--archive-older-than [DAYS]`, returning the cutoff to use if the flag is present.
+/// DAYS is optional and falls back to the configured cutoff, but an invalid one is an error.
+pub fn parse_archive_older_than(args: &[String], default_days: u32) -> Result<Option<u32>> {
+    let flag = "--archive-older-than";
+    let Some(pos) = args.iter().position(|a| a == flag) else {
+        return Ok(None);
+    };
+    if args.get(pos + 1).is_none_or(|next| next.starts_with('-')) {
+        return Ok(Some(default_days));
+    }
+    parse_flag_value(args, flag)
+}
+
+/// Returns the value following `--account`, if given
+pub fn parse_account_name(args: &[String]) -> Option<String> {
+    args.iter()
+        .position(|a| a == "--account")
+        .and_then(|i| args.get(i + 1))
+        .cloned()
+}
+
+/// Looks up the account named by `--account`, or the default account without one
+pub fn find_account<'a>(
+    cfg: &'a Config,
+    account_name: Option<&str>,
+) -> Result<(&'a str, &'a AccountConfig)> {
+    match account_name {
+        Some(name) => cfg
+            .accounts
+            .get_key_value(name)
+            .map(|(name, account)| (name.as_str(), account))
+            .with_context(|| format!("Account '{}' not found in config", name)),
+        None => {
+            let (name, account) = config::get_default_account(cfg)?;
+            Ok((name.as_str(), account))
+        }
+    }
+}
+
+/// Parses the value following `flag`, failing if the value is missing or invalid
+pub fn parse_flag_value<T: FromStr>(args: &[String], flag: &str) -> Result<Option<T>> {
+    let Some(pos) = args.iter().position(|a| a == flag) else {
+        return Ok(None);
+    };
+    let value = args
+        .get(pos + 1)
+        .with_context(|| format!("{} needs a value", flag))?;
+    match value.parse() {
+        Ok(parsed) => Ok(Some(parsed)),
+        Err(_) => bail!("Invalid value for {}: {}", flag, value),
+    }
+}
+
+/// Archives inbox emails older than `days`, excluding flagged mail and protected senders.
+/// Prints a preview count and asks for confirmation unless `assume_yes` is set.
+pub fn run_archive_older_than(
+    cfg: &Config,
+    days: u32,
+    account_name: Option<&str>,
+    assume_yes: bool,
+) -> Result<()> {
+    let (account_name, account) = find_account(cfg, account_name)?;
+    account.require_imap("--archive-older-than")?;
+
+    println!("Connecting to {}...", account.email);
+    let mut client = ImapClient::connect(account)
+        .map_err(|e| anyhow!(imap_error::describe("Failed to connect", &e)))?;
+
+    let count = client.get_folder_count("INBOX")?;
+    println!("Scanning {} inbox emails...", count);
+    let emails = client.fetch_inbox_range(1, count, None)?;
+
+    let ignored = IgnoreList::load(&ignore::ignore_path(account_name)?)?;
+    let ignored: Vec<String> = cfg
+        .ignored_senders
+        .iter()
+        .chain(&ignored.senders)
+        .cloned()
+        .collect();
+
+    let cutoff = Utc::now() - Duration::days(i64::from(days));
+    let old_uids = archivable_uids(emails, cutoff, &cfg.protected_senders, &ignored);
+
+    println!(
+        "Found {} inbox email(s) older than {} days (excluding flagged mail and protected senders).",
+        old_uids.len(),
+        days
+    );
+    if old_uids.is_empty() {
+        return client.logout();
+    }
+
+    if !assume_yes && !confirm("Archive them? [y/N] ")? {
+        println!("Cancelled.");
+        return client.logout();
+    }
+
+    let mut archived = 0;
+    for chunk in old_uids.chunks(BATCH_SIZE) {
+        client.archive_batch(chunk, "INBOX", cfg.mark_read_on_archive)?;
+        archived += chunk.len();
+        println!("Archived {}/{} email(s)...", archived, old_uids.len());
+    }
+    println!("Archived {} email(s).", old_uids.len());
+    if let Some(url) = &cfg.webhook_url {
+        let summary = BulkSummary {
+            action: "archive",
+            scope: "old",
+            sender: None,
+            policy: None,
+            count: old_uids.len(),
+            account: account_name.to_string(),
+        };
+        if let Err(e) = webhook::send(url, &summary) {
+            eprintln!("Warning: {:#}", e);
+        }
+    }
+    client.logout()
+}
+
+/// Picks the UIDs to archive by age. Ignored senders' whole threads are left alone, as
+/// the TUI hides them, and protected senders' own emails are skipped.
+fn archivable_uids(
+    mut emails: Vec<Email>,
+    cutoff: DateTime<Utc>,
+    protected_senders: &[String],
+    ignored_senders: &[String],
+) -> Vec<String> {
+    build_thread_ids(&mut emails);
+    remove_ignored_threads(&mut emails, ignored_senders);
+    stale_inbox_emails(&emails, cutoff, protected_senders)
+        .iter()
+        .map(|e| e.id.clone())
+        .collect()
+}
+
+/// Prompts on stdin and returns true for a "y"/"yes" answer
+fn confirm(prompt: &str) -> Result<bool> {
+    print!("{}", prompt);
+    io::stdout().flush()?;
+
+    let mut answer = String::new();
+    if io::stdin().lock().read_line(&mut answer)? == 0 {
+        bail!("No answer on stdin (use --yes to skip confirmation)");
+    }
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_archive_older_than_with_days() {
+        let parsed =
+            parse_archive_older_than(&args(&["zeroterm", "--archive-older-than", "90"]), 365);
+        assert_eq!(parsed.unwrap(), Some(90));
+    }
+
+    #[test]
+    fn test_parse_archive_older_than_uses_default() {
+        let parsed =
+            parse_archive_older_than(&args(&["zeroterm", "--archive-older-than", "--yes"]), 365);
+        assert_eq!(parsed.unwrap(), Some(365));
+        let parsed = parse_archive_older_than(&args(&["zeroterm", "--archive-older-than"]), 365);
+        assert_eq!(parsed.unwrap(), Some(365));
+    }
+
+    #[test]
+    fn test_parse_archive_older_than_rejects_invalid_days() {
+        let parsed =
+            parse_archive_older_than(&args(&["zeroterm", "--archive-older-than", "90d"]), 365);
+        assert!(parsed.is_err());
+    }
+
+    #[test]
+    fn test_parse_archive_older_than_absent() {
+        assert_eq!(
+            parse_archive_older_than(&args(&["zeroterm"]), 365).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_archivable_uids_leave_ignored_threads_alone() {
+        let old = Utc::now() - Duration::days(400);
+        let email = |id: &str, from: &str, message_id: &str, in_reply_to: Option<&str>| {
+            let mut builder = crate::email::EmailBuilder::new()
+                .id(id)
+                .from(from)
+                .message_id(message_id)
+                .date(old);
+            if let Some(parent) = in_reply_to {
+                builder = builder.in_reply_to(parent);
+            }
+            builder.build()
+        };
+        let emails = vec![
+            email("1", "boss@work.com", "<a@x>", None),
+            email("2", "colleague@work.com", "<b@x>", Some("<a@x>")),
+            email("3", "shop@example.com", "<c@x>", None),
+            email("4", "bank@example.com", "<d@x>", None),
+        ];
+        let uids = archivable_uids(
+            emails,
+            Utc::now() - Duration::days(365),
+            &["bank@example.com".to_string()],
+            &["boss@work.com".to_string()],
+        );
+        assert_eq!(uids, vec!["3".to_string()]);
+    }
+
+    #[test]
+    fn test_parse_account_name() {
+        assert_eq!(
+            parse_account_name(&args(&["zeroterm", "--account", "work"])),
+            Some("work".to_string())
+        );
+        assert_eq!(parse_account_name(&args(&["zeroterm", "--account"])), None);
+    }
+
+    #[test]
+    fn test_parse_flag_value() {
+        let list = args(&["zeroterm", "--demo-size", "500"]);
+        assert_eq!(
+            parse_flag_value::<usize>(&list, "--demo-size").unwrap(),
+            Some(500)
+        );
+        assert_eq!(parse_flag_value::<u64>(&list, "--demo-seed").unwrap(), None);
+        assert!(
+            parse_flag_value::<usize>(&args(&["zeroterm", "--demo-size"]), "--demo-size").is_err()
+        );
+        assert!(
+            parse_flag_value::<usize>(&args(&["zeroterm", "--demo-size", "lots"]), "--demo-size")
+                .is_err()
+        );
+    }
+}
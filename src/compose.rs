@@ -0,0 +1,517 @@
+//! Replies and forwards: the quoted template opened in $EDITOR, reading the edited draft
+//! back, and turning it into the message smtp sends
+
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Local, Utc};
+use std::fs;
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::config;
+use crate::email::{Email, extract_email};
+use crate::task::split_args;
+
+/// A finished send: what was sent and how many recipients took it
+pub type Sent = (Outgoing, Result<usize>);
+
+/// What a draft being sent is, for the status once smtp is done with it
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outgoing {
+    Reply,
+    Forward,
+    /// A List-Unsubscribe email; the sender's remaining mail is offered for archiving
+    /// once it's sent
+    Unsubscribe {
+        sender: String,
+    },
+}
+
+/// A reply as the user left it in the editor, plus the headers that thread it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Draft {
+    pub to: Vec<String>,
+    pub cc: Vec<String>,
+    pub subject: String,
+    pub body: String,
+    pub in_reply_to: Option<String>,
+    pub references: Vec<String>,
+}
+
+impl Draft {
+    /// The bare addresses the message is delivered to
+    pub fn recipients(&self) -> Vec<String> {
+        self.to
+            .iter()
+            .chain(self.cc.iter())
+            .map(|addr| extract_email(addr))
+            .collect()
+    }
+
+    /// Short recipient list for the confirmation: the first address plus a count of the rest
+    pub fn recipients_summary(&self) -> String {
+        let recipients = self.recipients();
+        match recipients.len() {
+            0 => String::new(),
+            1 => recipients[0].clone(),
+            n => format!("{} +{}", recipients[0], n - 1),
+        }
+    }
+}
+
+/// Adds "Re: " unless the subject already has it
+fn reply_subject(subject: &str) -> String {
+    if subject.trim_start().to_lowercase().starts_with("re:") {
+        subject.to_string()
+    } else {
+        format!("Re: {}", subject)
+    }
+}
+
+/// Builds the reply to an email, to its sender or, with `all`, to everyone on it but
+/// the user. Mail the user sent is answered to its recipients instead. `body` is quoted
+/// below the space for the reply.
+pub fn reply_draft(email: &Email, body: &str, user_email: &str, all: bool) -> Draft {
+    let is_me = |addr: &String| extract_email(addr).eq_ignore_ascii_case(user_email);
+    let from_me = email.from_email.eq_ignore_ascii_case(user_email);
+
+    let mut to: Vec<String> = if from_me {
+        email.to.clone()
+    } else {
+        vec![email.from.to_string()]
+    };
+    let mut cc = Vec::new();
+    if all {
+        to.extend(email.to.iter().filter(|addr| !is_me(addr)).cloned());
+        cc.extend(email.cc.iter().filter(|addr| !is_me(addr)).cloned());
+    }
+    let mut seen = std::collections::HashSet::new();
+    to.retain(|addr| seen.insert(extract_email(addr).to_lowercase()));
+    cc.retain(|addr| seen.insert(extract_email(addr).to_lowercase()));
+
+    let date = email
+        .date
+        .with_timezone(&Local)
+        .format("%a, %-d %b %Y at %H:%M");
+    let quoted: Vec<String> = body
+        .trim_end()
+        .lines()
+        .map(|line| {
+            if line.is_empty() {
+                ">".to_string()
+            } else {
+                format!("> {}", line)
+            }
+        })
+        .collect();
+    let mut references = email.references.clone();
+    references.extend(email.message_id.clone());
+
+    Draft {
+        to,
+        cc,
+        subject: reply_subject(&email.subject),
+        body: format!(
+            "\n\nOn {}, {} wrote:\n{}\n",
+            date,
+            email.from,
+            quoted.join("\n")
+        ),
+        in_reply_to: email.message_id.clone(),
+        references,
+    }
+}
+
+/// Builds the forward of an email to the comma-separated addresses in `to`, with its
+/// headers and `body` below a "Forwarded message" line. Fails when `to` has anything
+/// that isn't an address.
+pub fn forward_draft(email: &Email, body: &str, to: &str) -> Result<Draft> {
+    let to = split_header_addresses(to);
+    if to.is_empty() {
+        bail!("No address to forward to");
+    }
+    if let Some(bad) = to.iter().find(|addr| !extract_email(addr).contains('@')) {
+        bail!("Not an email address: {}", bad);
+    }
+    let subject = if email
+        .subject
+        .trim_start()
+        .to_lowercase()
+        .starts_with("fwd:")
+    {
+        email.subject.clone()
+    } else {
+        format!("Fwd: {}", email.subject)
+    };
+    let mut headers = vec![
+        format!("From: {}", email.from),
+        format!("Date: {}", email.date.to_rfc2822()),
+        format!("Subject: {}", email.subject),
+        format!("To: {}", email.to.join(", ")),
+    ];
+    if !email.cc.is_empty() {
+        headers.push(format!("Cc: {}", email.cc.join(", ")));
+    }
+    Ok(Draft {
+        to,
+        cc: Vec::new(),
+        subject,
+        body: format!(
+            "---------- Forwarded message ---------\n{}\n\n{}\n",
+            headers.join("\n"),
+            body.trim_end()
+        ),
+        in_reply_to: None,
+        references: Vec::new(),
+    })
+}
+
+/// Builds the email a `mailto:` List-Unsubscribe link asks for, with the link's subject
+/// and body (RFC 6068). None for any other link.
+pub fn unsubscribe_draft(link: &str) -> Option<Draft> {
+    let rest = link
+        .get(..7)
+        .filter(|scheme| scheme.eq_ignore_ascii_case("mailto:"))
+        .map(|_| &link[7..])?;
+    let (addresses, query) = rest.split_once('?').unwrap_or((rest, ""));
+    let decode = |value: &str| {
+        urlencoding::decode(value)
+            .map(|v| v.into_owned())
+            .unwrap_or_else(|_| value.to_string())
+    };
+    let to = split_header_addresses(&decode(addresses));
+    if to.is_empty() {
+        return None;
+    }
+    let mut subject = "Unsubscribe".to_string();
+    let mut body = String::new();
+    for pair in query.split('&') {
+        match pair.split_once('=') {
+            Some((name, value)) if name.eq_ignore_ascii_case("subject") => subject = decode(value),
+            Some((name, value)) if name.eq_ignore_ascii_case("body") => body = decode(value),
+            _ => {}
+        }
+    }
+    Some(Draft {
+        to,
+        cc: Vec::new(),
+        subject,
+        body,
+        in_reply_to: None,
+        references: Vec::new(),
+    })
+}
+
+/// The text opened in the editor: To, Cc and Subject lines, a blank line, then the body
+pub fn draft_text(draft: &Draft) -> String {
+    format!(
+        "To: {}\nCc: {}\nSubject: {}\n\n{}",
+        draft.to.join(", "),
+        draft.cc.join(", "),
+        draft.subject,
+        draft.body
+    )
+}
+
+/// Splits an address header as typed into its addresses
+fn split_header_addresses(value: &str) -> Vec<String> {
+    match mailparse::addrparse(value) {
+        Ok(list) => list
+            .iter()
+            .flat_map(|addr| match addr {
+                mailparse::MailAddr::Single(single) => vec![single.to_string()],
+                mailparse::MailAddr::Group(group) => {
+                    group.addrs.iter().map(|a| a.to_string()).collect()
+                }
+            })
+            .collect(),
+        Err(_) => value
+            .split(',')
+            .map(str::trim)
+            .filter(|a| !a.is_empty())
+            .map(String::from)
+            .collect(),
+    }
+}
+
+/// Reads an edited draft back, keeping the threading headers of `original`
+pub fn parse_draft(text: &str, original: &Draft) -> Result<Draft> {
+    let text = text.replace("\r\n", "\n");
+    let (headers, body) = text.split_once("\n\n").unwrap_or((text.as_str(), ""));
+    let mut draft = Draft {
+        to: Vec::new(),
+        cc: Vec::new(),
+        subject: String::new(),
+        body: body.to_string(),
+        in_reply_to: original.in_reply_to.clone(),
+        references: original.references.clone(),
+    };
+    for line in headers.lines() {
+        let Some((name, value)) = line.split_once(':') else {
+            bail!("Expected a header line, found: {}", line);
+        };
+        let value = value.trim();
+        match name.trim().to_lowercase().as_str() {
+            "to" => draft.to = split_header_addresses(value),
+            "cc" => draft.cc = split_header_addresses(value),
+            "subject" => draft.subject = value.to_string(),
+            other => bail!("Unknown header in the draft: {}", other),
+        }
+    }
+    if draft.to.is_empty() && draft.cc.is_empty() {
+        bail!("The draft has no recipients");
+    }
+    Ok(draft)
+}
+
+/// Opens `path` in $VISUAL, $EDITOR or vi and waits for the editor to exit
+pub fn edit_file(path: &Path) -> Result<()> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    let args = split_args(&editor);
+    let Some((program, rest)) = args.split_first() else {
+        bail!("$EDITOR is empty");
+    };
+    let status = Command::new(program)
+        .args(rest)
+        .arg(path)
+        .status()
+        .with_context(|| format!("Failed to run {}", program))?;
+    if !status.success() {
+        bail!("{} exited with {}", program, status);
+    }
+    Ok(())
+}
+
+/// Returns ~/.cache/zeroterm/outgoing, created so only the user can open it. Drafts and
+/// outgoing mail go there rather than the shared temp directory, where another user
+/// could plant a file or symlink under the name first.
+pub fn private_temp_dir() -> Result<PathBuf> {
+    let dir = config::cache_dir()?.join("outgoing");
+    config::create_private_dir(&dir)?;
+    Ok(dir)
+}
+
+/// Where the reply being written is kept until it's sent
+pub fn draft_path() -> Result<PathBuf> {
+    Ok(private_temp_dir()?.join(format!("reply-{}.txt", std::process::id())))
+}
+
+/// Writes a file only the user can read, as drafts and outgoing mail quote private mail
+pub fn write_private(path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+    fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)
+        .and_then(|mut file| file.write_all(contents.as_ref()))
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Writes the draft to a file, has the user edit it and reads it back. None when the
+/// file was left unchanged, which cancels the reply.
+pub fn edit_draft(draft: &Draft, path: &Path) -> Result<Option<Draft>> {
+    let template = draft_text(draft);
+    write_private(path, &template)?;
+    edit_file(path)?;
+    let edited =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    if edited == template {
+        let _ = fs::remove_file(path);
+        return Ok(None);
+    }
+    parse_draft(&edited, draft).map(Some)
+}
+
+/// Encodes a header value as an RFC 2047 Q-encoded word when it isn't plain ASCII
+fn encode_word(value: &str) -> String {
+    if value.is_ascii() {
+        return value.to_string();
+    }
+    let mut out = String::from("=?UTF-8?Q?");
+    for byte in value.bytes() {
+        match byte {
+            b' ' => out.push('_'),
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'!' | b'*' | b'+' | b'-' | b'/' => {
+                out.push(byte as char)
+            }
+            _ => out.push_str(&format!("={:02X}", byte)),
+        }
+    }
+    out.push_str("?=");
+    out
+}
+
+/// Encodes the display name of a "Name <addr>" address when it isn't plain ASCII
+fn encode_address(address: &str) -> String {
+    match address.rsplit_once('<') {
+        Some((name, addr)) if !name.is_ascii() => {
+            let name = name.trim().trim_matches('"');
+            format!("{} <{}", encode_word(name), addr)
+        }
+        _ => address.to_string(),
+    }
+}
+
+/// Builds the RFC 5322 message for a draft, with CRLF line endings
+pub fn build_message(draft: &Draft, from: &str, now: DateTime<Utc>) -> String {
+    let domain = from.split('@').nth(1).unwrap_or("localhost");
+    let addresses = |list: &[String]| {
+        list.iter()
+            .map(|a| encode_address(a))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let mut headers = vec![
+        format!("From: {}", from),
+        format!("To: {}", addresses(&draft.to)),
+    ];
+    if !draft.cc.is_empty() {
+        headers.push(format!("Cc: {}", addresses(&draft.cc)));
+    }
+    headers.push(format!("Subject: {}", encode_word(&draft.subject)));
+    headers.push(format!("Date: {}", now.to_rfc2822()));
+    headers.push(format!(
+        "Message-ID: <{}.{}@{}>",
+        now.timestamp_nanos_opt().unwrap_or_default(),
+        std::process::id(),
+        domain
+    ));
+    if let Some(in_reply_to) = &draft.in_reply_to {
+        headers.push(format!("In-Reply-To: {}", in_reply_to));
+    }
+    if !draft.references.is_empty() {
+        headers.push(format!("References: {}", draft.references.join(" ")));
+    }
+    headers.push("MIME-Version: 1.0".to_string());
+    headers.push("Content-Type: text/plain; charset=utf-8".to_string());
+    headers.push("Content-Transfer-Encoding: 8bit".to_string());
+
+    let body: Vec<&str> = draft.body.lines().collect();
+    format!("{}\r\n\r\n{}\r\n", headers.join("\r\n"), body.join("\r\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::email::EmailBuilder;
+
+    fn original() -> Email {
+        EmailBuilder::new()
+            .id("1")
+            .from("Alice <alice@example.com>")
+            .subject("Lunch")
+            .message_id("<a1@example.com>")
+            .references(vec!["<a0@example.com>".to_string()])
+            .to(vec![
+                "Me <me@example.com>".to_string(),
+                "bob@example.com".to_string(),
+            ])
+            .cc(vec!["carol@example.com".to_string()])
+            .build()
+    }
+
+    #[test]
+    fn test_reply_draft_quotes_and_threads() {
+        let draft = reply_draft(&original(), "Noon?\n\nSee you", "me@example.com", false);
+        assert_eq!(draft.to, vec!["Alice <alice@example.com>"]);
+        assert!(draft.cc.is_empty());
+        assert_eq!(draft.subject, "Re: Lunch");
+        assert!(
+            draft
+                .body
+                .ends_with("Alice <alice@example.com> wrote:\n> Noon?\n>\n> See you\n")
+        );
+        assert_eq!(draft.in_reply_to.as_deref(), Some("<a1@example.com>"));
+        assert_eq!(
+            draft.references,
+            vec!["<a0@example.com>", "<a1@example.com>"]
+        );
+    }
+
+    #[test]
+    fn test_reply_all_leaves_out_the_user() {
+        let draft = reply_draft(&original(), "", "ME@example.com", true);
+        assert_eq!(
+            draft.to,
+            vec!["Alice <alice@example.com>", "bob@example.com"]
+        );
+        assert_eq!(draft.cc, vec!["carol@example.com"]);
+        assert_eq!(draft.recipients_summary(), "alice@example.com +2");
+    }
+
+    #[test]
+    fn test_forward_draft_includes_the_original_headers() {
+        let draft = forward_draft(
+            &original(),
+            "Noon?",
+            "dan@example.com, Eve <eve@example.com>",
+        )
+        .unwrap();
+        assert_eq!(
+            draft.recipients(),
+            vec!["dan@example.com", "eve@example.com"]
+        );
+        assert_eq!(draft.subject, "Fwd: Lunch");
+        assert_eq!(draft.in_reply_to, None);
+        assert!(draft.body.starts_with(
+            "---------- Forwarded message ---------\nFrom: Alice <alice@example.com>\n"
+        ));
+        assert!(draft.body.contains("\nCc: carol@example.com\n\nNoon?\n"));
+
+        assert!(forward_draft(&original(), "", "").is_err());
+        assert!(forward_draft(&original(), "", "dan").is_err());
+    }
+
+    #[test]
+    fn test_unsubscribe_draft_reads_the_mailto_link() {
+        let draft = unsubscribe_draft("MAILTO:leave-123@lists.example.com?subject=unsub%20123&x=1")
+            .unwrap();
+        assert_eq!(draft.recipients(), vec!["leave-123@lists.example.com"]);
+        assert_eq!(draft.subject, "unsub 123");
+        assert_eq!(draft.body, "");
+
+        let draft = unsubscribe_draft("mailto:off@example.com").unwrap();
+        assert_eq!(draft.subject, "Unsubscribe");
+        assert_eq!(unsubscribe_draft("https://example.com/unsub"), None);
+        assert_eq!(unsubscribe_draft("mailto:?subject=x"), None);
+    }
+
+    #[test]
+    fn test_parse_draft_reads_the_edited_headers() {
+        let draft = reply_draft(&original(), "Noon?", "me@example.com", false);
+        let edited = "To: \"Doe, Alice\" <alice@example.com>, dan@example.com\nCc:\nSubject: Re: Lunch!\n\nSure.\n";
+        let parsed = parse_draft(edited, &draft).unwrap();
+        assert_eq!(
+            parsed.recipients(),
+            vec!["alice@example.com", "dan@example.com"]
+        );
+        assert_eq!(parsed.subject, "Re: Lunch!");
+        assert_eq!(parsed.body, "Sure.\n");
+        assert_eq!(parsed.in_reply_to, draft.in_reply_to);
+
+        assert!(parse_draft("To:\nSubject: x\n\nbody", &draft).is_err());
+        assert!(parse_draft("Bcc: x@example.com\n\nbody", &draft).is_err());
+    }
+
+    #[test]
+    fn test_build_message_encodes_non_ascii_headers() {
+        let draft = Draft {
+            to: vec!["Café Owner <owner@example.com>".to_string()],
+            cc: Vec::new(),
+            subject: "Re: Café".to_string(),
+            body: "Merci\n".to_string(),
+            in_reply_to: Some("<a1@example.com>".to_string()),
+            references: vec!["<a1@example.com>".to_string()],
+        };
+        let message = build_message(&draft, "me@example.com", Utc::now());
+        assert!(message.contains("\r\nTo: =?UTF-8?Q?Caf=C3=A9_Owner?= <owner@example.com>\r\n"));
+        assert!(message.contains("\r\nSubject: =?UTF-8?Q?Re=3A_Caf=C3=A9?=\r\n"));
+        assert!(message.contains("\r\nIn-Reply-To: <a1@example.com>\r\n"));
+        assert!(!message.contains("\r\nCc:"));
+        assert!(message.ends_with("\r\n\r\nMerci\r\n"));
+    }
+}
@@ -1,22 +1,164 @@
 use anyhow::{Context, Result};
-use serde::{Deserialize, Serialize};
+use chrono::{Days, Months, NaiveDate};
+use ratatui::style::Color;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use crate::capture::expand_home;
+use crate::highlight::Highlight;
+use crate::plugin::Plugin;
+use crate::policy::Policy;
+use crate::script::CustomAction;
 
 const APP_NAME: &str = "zeroterm";
 const CONFIG_FILE: &str = "config.toml";
+/// Environment variable naming a config file to use instead of the default
+const CONFIG_ENV: &str = "ZEROTERM_CONFIG";
+const TOUR_MARKER_FILE: &str = "tour_seen";
+/// Fastmail's JMAP session resource
+const DEFAULT_JMAP_URL: &str = "https://api.fastmail.com/jmap/session";
 
 /// Supported email backends
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Backend {
     Gmail,
+    /// Outlook.com and Microsoft 365 mailboxes
+    Outlook,
+    /// JMAP servers such as Fastmail, using app_password as the API token
+    Jmap,
+    /// Gmail over the REST API with an OAuth token from oauth_token_command, falling back
+    /// to IMAP with app_password when the API can't be reached
+    #[serde(rename = "gmail-api")]
+    GmailApi,
+}
+
+/// Where a backend keeps mail outside the inbox
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Folders {
+    /// Archived mail is moved here
+    pub archive: &'static str,
+    /// Deleted mail is moved here
+    pub trash: &'static str,
+    /// Fetched alongside INBOX so threads include your replies
+    pub sent: &'static str,
+}
+
+impl Backend {
+    /// The backend's standard IMAP server
+    fn default_imap_host(&self) -> &'static str {
+        match self {
+            Backend::Gmail | Backend::GmailApi => "imap.gmail.com",
+            Backend::Outlook => "outlook.office365.com",
+            Backend::Jmap => "imap.fastmail.com",
+        }
+    }
+
+    /// The backend's standard SMTP server and port for sending replies
+    fn default_smtp_server(&self) -> (&'static str, u16) {
+        match self {
+            Backend::Gmail | Backend::GmailApi => ("smtp.gmail.com", 465),
+            Backend::Outlook => ("smtp.office365.com", 587),
+            Backend::Jmap => ("smtp.fastmail.com", 465),
+        }
+    }
+
+    /// Whether the backend's mail carries Gmail labels
+    pub fn has_labels(&self) -> bool {
+        matches!(self, Backend::Gmail | Backend::GmailApi)
+    }
+
+    /// The backend's archive, trash and sent folders
+    pub fn folders(&self) -> Folders {
+        match self {
+            Backend::Gmail | Backend::GmailApi => Folders {
+                archive: "[Gmail]/All Mail",
+                trash: "[Gmail]/Trash",
+                sent: "[Gmail]/Sent Mail",
+            },
+            Backend::Outlook => Folders {
+                archive: "Archive",
+                trash: "Deleted Items",
+                sent: "Sent Items",
+            },
+            // Mailbox roles rather than names; the JMAP client looks each one up
+            Backend::Jmap => Folders {
+                archive: "Archive",
+                trash: "Trash",
+                sent: "Sent",
+            },
+        }
+    }
+
+    /// Builds a link that finds an email by Message-ID in the backend's webmail
+    pub fn web_link(&self, message_id: &str, user_email: &str) -> String {
+        match self {
+            Backend::Gmail | Backend::GmailApi => crate::email::gmail_link(message_id, user_email),
+            Backend::Outlook => crate::email::outlook_link(message_id, user_email),
+            Backend::Jmap => crate::email::fastmail_link(message_id),
+        }
+    }
+
+    /// Builds a link that opens a whole thread in the backend's webmail. None when the
+    /// webmail has no conversation links (Outlook, Fastmail) or nothing identifies the thread.
+    pub fn thread_web_link(
+        &self,
+        thread: &[&crate::email::Email],
+        user_email: &str,
+    ) -> Option<String> {
+        match self {
+            Backend::Gmail | Backend::GmailApi => {
+                let message_ids: Vec<&str> = thread
+                    .iter()
+                    .filter_map(|email| email.message_id.as_deref())
+                    .collect();
+                let gmail_thread_id = thread.iter().find_map(|email| email.gmail_thread_id);
+                if gmail_thread_id.is_none() && message_ids.is_empty() {
+                    return None;
+                }
+                Some(crate::email::gmail_thread_link(
+                    gmail_thread_id,
+                    &message_ids,
+                    user_email,
+                ))
+            }
+            Backend::Outlook | Backend::Jmap => None,
+        }
+    }
+}
+
+/// Transport security for the IMAP connection
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Security {
+    /// Implicit TLS, typically on port 993
+    #[default]
+    Tls,
+    /// Plaintext connection upgraded with STARTTLS, typically on port 143
+    StartTls,
+    /// Unencrypted connection, only for local test servers
+    None,
+}
+
+/// How much room each row in the email list takes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RowDensity {
+    /// One line per thread: date, subject and a snippet
+    #[default]
+    Compact,
+    /// Two lines per thread: sender and date, then subject and snippet
+    Comfortable,
 }
 
 /// Configuration for a single email account
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct AccountConfig {
     /// The backend type for this account
     pub backend: Backend,
@@ -24,6 +166,127 @@ pub struct AccountConfig {
     pub email: String,
     /// App Password (not regular password)
     pub app_password: String,
+    /// IMAP server hostname (default: imap.gmail.com, or outlook.office365.com for outlook)
+    #[serde(default)]
+    pub imap_host: Option<String>,
+    /// IMAP server port (default: 993 for tls, 143 otherwise)
+    #[serde(default)]
+    pub imap_port: Option<u16>,
+    /// Connection security: "tls", "starttls" or "none" (default: tls)
+    #[serde(default)]
+    pub security: Security,
+    /// Extra PEM CA certificate bundle to trust, e.g. a self-signed bridge certificate
+    #[serde(default)]
+    pub tls_ca_file: Option<PathBuf>,
+    /// Skip TLS certificate and hostname verification (default: false)
+    #[serde(default)]
+    pub danger_accept_invalid_certs: bool,
+    /// Only fetch mail from this recent window at startup, e.g. "6 months" (default: all mail)
+    #[serde(default)]
+    pub fetch_window: Option<String>,
+    /// Color of this account's borders and titles, a color name ("magenta") or hex code
+    /// ("#ff8800"), so accounts are easy to tell apart (default: none)
+    #[serde(default)]
+    pub accent_color: Option<String>,
+    /// JMAP session URL for jmap accounts (default: Fastmail's)
+    #[serde(default)]
+    pub jmap_url: Option<String>,
+    /// Command that prints an OAuth access token with the gmail.modify scope, for
+    /// gmail-api accounts, split like task_command (default: none)
+    #[serde(default)]
+    pub oauth_token_command: Option<String>,
+    /// SMTP server replies are sent through (default: the backend's server)
+    #[serde(default)]
+    pub smtp_host: Option<String>,
+    /// SMTP port: 465 connects with TLS, any other port upgrades with STARTTLS
+    /// (default: the backend's port)
+    #[serde(default)]
+    pub smtp_port: Option<u16>,
+}
+
+impl AccountConfig {
+    /// Returns the IMAP hostname, falling back to the backend's default
+    pub fn imap_host(&self) -> &str {
+        self.imap_host
+            .as_deref()
+            .unwrap_or(self.backend.default_imap_host())
+    }
+
+    /// Returns the SMTP hostname, falling back to the backend's default
+    pub fn smtp_host(&self) -> &str {
+        self.smtp_host
+            .as_deref()
+            .unwrap_or(self.backend.default_smtp_server().0)
+    }
+
+    /// Returns the SMTP port, falling back to the backend's default
+    pub fn smtp_port(&self) -> u16 {
+        self.smtp_port
+            .unwrap_or(self.backend.default_smtp_server().1)
+    }
+
+    /// Returns the JMAP session URL, falling back to Fastmail's
+    pub fn jmap_url(&self) -> &str {
+        self.jmap_url.as_deref().unwrap_or(DEFAULT_JMAP_URL)
+    }
+
+    /// Fails for jmap accounts, for commands that only speak IMAP
+    pub fn require_imap(&self, command: &str) -> Result<()> {
+        if self.backend == Backend::Jmap {
+            anyhow::bail!("{} only works with IMAP accounts, not jmap", command);
+        }
+        Ok(())
+    }
+
+    /// The configured accent color, or None when it isn't set or can't be parsed
+    pub fn accent(&self) -> Option<Color> {
+        self.accent_color
+            .as_deref()
+            .and_then(|c| Color::from_str(c).ok())
+    }
+
+    /// Returns the IMAP port, falling back to the backend's default
+    pub fn imap_port(&self) -> u16 {
+        self.imap_port.unwrap_or(match self.security {
+            Security::Tls => 993,
+            Security::StartTls | Security::None => 143,
+        })
+    }
+
+    /// Returns the first day inside `fetch_window`, counting back from `today`
+    pub fn fetch_window_start(&self, today: NaiveDate) -> Result<Option<NaiveDate>> {
+        self.fetch_window
+            .as_deref()
+            .map(|window| window_start(window, today))
+            .transpose()
+    }
+}
+
+/// Parses a window like "30 days", "2 weeks", "6 months" or "1 year" and returns the
+/// date that far before `today`
+fn window_start(window: &str, today: NaiveDate) -> Result<NaiveDate> {
+    let invalid = || {
+        anyhow::anyhow!(
+            "Invalid fetch_window '{}' (expected e.g. \"30 days\", \"6 months\" or \"1 year\")",
+            window
+        )
+    };
+    let mut parts = window.split_whitespace();
+    let (Some(amount), Some(unit), None) = (parts.next(), parts.next(), parts.next()) else {
+        return Err(invalid());
+    };
+    let amount: u32 = amount.parse().map_err(|_| invalid())?;
+
+    let start = match unit.to_lowercase().trim_end_matches('s') {
+        "day" => today.checked_sub_days(Days::new(u64::from(amount))),
+        "week" => today.checked_sub_days(Days::new(u64::from(amount) * 7)),
+        "month" => today.checked_sub_months(Months::new(amount)),
+        "year" => amount
+            .checked_mul(12)
+            .and_then(|months| today.checked_sub_months(Months::new(months))),
+        _ => return Err(invalid()),
+    };
+    start.ok_or_else(invalid)
 }
 
 fn default_protect_threads() -> bool {
@@ -42,8 +305,93 @@ fn default_advance_on_select() -> bool {
     true
 }
 
+fn default_merge_subject_threads() -> bool {
+    false
+}
+
+fn default_thread_inline_bodies() -> bool {
+    false
+}
+
+fn default_thread_recipients_column() -> bool {
+    false
+}
+
+fn default_auth_warnings_in_list() -> bool {
+    false
+}
+
+fn default_header_cache() -> bool {
+    true
+}
+
+fn default_archive_older_than_days() -> u32 {
+    365
+}
+
+fn default_confirm_policies() -> bool {
+    false
+}
+
+pub fn default_typed_confirm_above() -> usize {
+    100
+}
+
+pub fn default_typed_count_confirm_above() -> usize {
+    1000
+}
+
+fn default_fetch_page_size() -> u32 {
+    5000
+}
+
+fn default_body_size_limit_kb() -> u32 {
+    256
+}
+
+fn default_mark_seen_on_view() -> bool {
+    false
+}
+
+fn default_mark_read_on_archive() -> bool {
+    false
+}
+
+fn default_archive_after_task() -> bool {
+    false
+}
+
+fn default_capture_excerpt_lines() -> usize {
+    10
+}
+
+fn default_print_command() -> String {
+    "lp".to_string()
+}
+
+fn default_pgp_command() -> String {
+    "gpg".to_string()
+}
+
+fn default_triage_timer_minutes() -> u32 {
+    0
+}
+
+fn default_min_group_size() -> usize {
+    0
+}
+
+fn default_postpone_days() -> u32 {
+    0
+}
+
+fn default_window_title() -> bool {
+    true
+}
+
 /// Top-level configuration containing all accounts
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Config {
     /// Named accounts, keyed by account name
     pub accounts: HashMap<String, AccountConfig>,
@@ -59,6 +407,116 @@ pub struct Config {
     /// Move to next email after toggling selection with space (default: true)
     #[serde(default = "default_advance_on_select")]
     pub advance_on_select: bool,
+    /// Merge emails with the same subject but no reply headers into one thread (default: false)
+    #[serde(default = "default_merge_subject_threads")]
+    pub merge_subject_threads: bool,
+    /// Show every message body inline in thread view, oldest first (default: false)
+    #[serde(default = "default_thread_inline_bodies")]
+    pub thread_inline_bodies: bool,
+    /// Email list rows: "compact" (one line) or "comfortable" (two lines) (default: compact)
+    #[serde(default)]
+    pub row_density: RowDensity,
+    /// Mark emails that fail DKIM, SPF or DMARC in the email list (default: false)
+    #[serde(default = "default_auth_warnings_in_list")]
+    pub auth_warnings_in_list: bool,
+    /// Show a To/Cc recipients column in thread view (default: false)
+    #[serde(default = "default_thread_recipients_column")]
+    pub thread_recipients_column: bool,
+    /// Age cutoff in days for the archive-old-mail action (default: 365)
+    #[serde(default = "default_archive_older_than_days")]
+    pub archive_older_than_days: u32,
+    /// Senders ("boss@work.com") or domains ("work.com") never auto-archived (default: none)
+    #[serde(default)]
+    pub protected_senders: Vec<String>,
+    /// Senders ("boss@work.com") or domains ("family.org") hidden from zeroterm entirely (default: none)
+    #[serde(default)]
+    pub ignored_senders: Vec<String>,
+    /// URL that receives a JSON POST summarizing each completed bulk action (default: none)
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+    /// `[[policy]]` rules applied right after emails are fetched (default: none)
+    #[serde(default, rename = "policy")]
+    pub policies: Vec<Policy>,
+    /// `[[highlight]]` rules that color matching rows in the group and email lists (default: none)
+    #[serde(default, rename = "highlight")]
+    pub highlights: Vec<Highlight>,
+    /// `[[action]]` rhai scripts bound to keys (default: none)
+    #[serde(default, rename = "action")]
+    pub actions: Vec<CustomAction>,
+    /// `[[plugin]]` external programs bound to keys (default: none)
+    #[serde(default, rename = "plugin")]
+    pub plugins: Vec<Plugin>,
+    /// Ask for confirmation with a summary before applying policies (default: false)
+    #[serde(default = "default_confirm_policies")]
+    pub confirm_policies: bool,
+    /// Deleting more emails than this asks for "delete" to be typed instead of y; 0 never
+    /// does (default: 100)
+    #[serde(default = "default_typed_confirm_above")]
+    pub typed_confirm_above: usize,
+    /// Archiving or deleting more emails than this asks for the count or the sender to be
+    /// typed instead of y; 0 never does (default: 1000)
+    #[serde(default = "default_typed_count_confirm_above")]
+    pub typed_count_confirm_above: usize,
+    /// Only fetch the newest N emails per folder at startup (default: no limit)
+    #[serde(default)]
+    pub fetch_limit: Option<u32>,
+    /// How many older emails each "load more" fetches when fetch_limit is set (default: 5000)
+    #[serde(default = "default_fetch_page_size")]
+    pub fetch_page_size: u32,
+    /// Only fetch the first N KB of a message body until L loads the rest; 0 disables (default: 256)
+    #[serde(default = "default_body_size_limit_kb")]
+    pub body_size_limit_kb: u32,
+    /// Set the server's \Seen flag when a message body is opened (default: false)
+    #[serde(default = "default_mark_seen_on_view")]
+    pub mark_seen_on_view: bool,
+    /// Mark messages read as they're archived, so they don't count as unread in All Mail (default: false)
+    #[serde(default = "default_mark_read_on_archive")]
+    pub mark_read_on_archive: bool,
+    /// Command run by T to turn an email into a task; {subject}, {from} and {link} are filled in (default: none)
+    #[serde(default)]
+    pub task_command: Option<String>,
+    /// Archive the thread once task_command succeeds (default: false)
+    #[serde(default = "default_archive_after_task")]
+    pub archive_after_task: bool,
+    /// File C appends emails to, as org-mode for .org files and markdown otherwise (default: none)
+    #[serde(default)]
+    pub capture_file: Option<PathBuf>,
+    /// Body lines quoted in each captured entry; 0 leaves the body out (default: 10)
+    #[serde(default = "default_capture_excerpt_lines")]
+    pub capture_excerpt_lines: usize,
+    /// Command P pipes the email to as plain text; {subject} is filled in (default: lp)
+    #[serde(default = "default_print_command")]
+    pub print_command: String,
+    /// gpg binary that decrypts PGP/MIME mail and checks signatures in the body view (default: gpg)
+    #[serde(default = "default_pgp_command")]
+    pub pgp_command: String,
+    /// Length of a timeboxed triage session shown as a countdown; 0 disables (default: 0)
+    #[serde(default = "default_triage_timer_minutes")]
+    pub triage_timer_minutes: u32,
+    /// Groups with fewer emails than this are hidden until H reveals them; 0 shows all (default: 0)
+    #[serde(default = "default_min_group_size")]
+    pub min_group_size: usize,
+    /// Days w postpones a group for; 0 postpones it until next Monday (default: 0)
+    #[serde(default = "default_postpone_days")]
+    pub postpone_days: u32,
+    /// Show the account and emails left in the terminal/tmux window title (default: true)
+    #[serde(default = "default_window_title")]
+    pub window_title: bool,
+    /// Show the inbox from the last session at startup while it loads from the server,
+    /// keeping its headers under ~/.cache/zeroterm (default: true)
+    #[serde(default = "default_header_cache")]
+    pub header_cache: bool,
+    /// gpg key (an address or key ID) the header cache is encrypted to with pgp_command;
+    /// reading it back needs the secret key (default: none, stored as plain JSON)
+    #[serde(default)]
+    pub header_cache_encrypt_to: Option<String>,
+}
+
+impl Config {
+    /// Returns the body fetch cap in bytes, or None when body_size_limit_kb is 0
+    pub fn body_size_limit(&self) -> Option<u32> {
+        (self.body_size_limit_kb > 0).then(|| self.body_size_limit_kb.saturating_mul(1024))
+    }
 }
 
 /// Returns the configuration directory path
@@ -68,9 +526,44 @@ pub fn config_dir() -> Result<PathBuf> {
     Ok(xdg_dirs.get_config_home())
 }
 
-/// Returns the path to the config file
+/// Returns the cache directory path: ~/.cache/zeroterm
+pub fn cache_dir() -> Result<PathBuf> {
+    let xdg_dirs = xdg::BaseDirectories::with_prefix(APP_NAME)
+        .context("Failed to determine cache directory")?;
+    Ok(xdg_dirs.get_cache_home())
+}
+
+/// Creates a directory only the user can open, for files holding mail. A directory an
+/// older version created with the default permissions is tightened too.
+pub fn create_private_dir(dir: &Path) -> Result<()> {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+
+    fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(dir)
+        .and_then(|_| fs::set_permissions(dir, fs::Permissions::from_mode(0o700)))
+        .with_context(|| format!("Failed to create {}", dir.display()))
+}
+
+/// The config file given with --config, which wins over ZEROTERM_CONFIG
+static CONFIG_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+/// Uses `path` as the config file for the rest of the run (the --config flag)
+pub fn set_config_path(path: &Path) {
+    let _ = CONFIG_OVERRIDE.set(expand_home(path));
+}
+
+/// Returns the path to the config file: --config, then $ZEROTERM_CONFIG, then
+/// config.toml in the config directory
 pub fn config_path() -> Result<PathBuf> {
-    config_dir().map(|p| p.join(CONFIG_FILE))
+    if let Some(path) = CONFIG_OVERRIDE.get() {
+        return Ok(path.clone());
+    }
+    match std::env::var_os(CONFIG_ENV).filter(|p| !p.is_empty()) {
+        Some(path) => Ok(expand_home(Path::new(&path))),
+        None => config_dir().map(|p| p.join(CONFIG_FILE)),
+    }
 }
 
 /// Ensures the config directory exists
@@ -87,6 +580,62 @@ pub fn has_config() -> bool {
     config_path().map(|p| p.exists()).unwrap_or(false)
 }
 
+/// Returns the path to the marker written once the onboarding tour has been shown
+fn tour_marker_path() -> Result<PathBuf> {
+    config_dir().map(|p| p.join(TOUR_MARKER_FILE))
+}
+
+/// Checks if the onboarding tour has been shown before
+pub fn has_seen_tour() -> bool {
+    tour_marker_path().map(|p| p.exists()).unwrap_or(true)
+}
+
+/// Records that the onboarding tour has been shown, so it isn't shown on later launches
+pub fn mark_tour_seen() -> Result<()> {
+    fs::write(tour_marker_path()?, "").context("Failed to write tour marker")
+}
+
+/// Returns the path of a per-account data file: ~/.config/zeroterm/<dir>/<account>.toml
+pub fn account_data_path(dir: &str, account: &str) -> Result<PathBuf> {
+    config_dir().map(|p| {
+        p.join(dir)
+            .join(format!("{}.toml", account_file_stem(account)))
+    })
+}
+
+/// Turns an account name into a safe file name, replacing anything unusual with `_`
+pub fn account_file_stem(account: &str) -> String {
+    account
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Reads a TOML data file, treating a missing file as empty
+pub fn load_data_file<T: DeserializeOwned + Default>(path: &Path) -> Result<T> {
+    if !path.exists() {
+        return Ok(T::default());
+    }
+    let content =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    toml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// Writes a TOML data file, creating its directory if needed
+pub fn save_data_file<T: Serialize>(path: &Path, data: &T) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    let content = toml::to_string(data).context("Failed to serialize data file")?;
+    fs::write(path, content).with_context(|| format!("Failed to write {}", path.display()))
+}
+
 /// Trait for resolving secrets, allowing for mocking in tests
 #[cfg_attr(test, mockall::automock)]
 pub trait SecretResolver {
@@ -121,17 +670,263 @@ pub fn load_config() -> Result<Config> {
     load_config_with_resolver(&OpSecretResolver)
 }
 
-/// Loads config using a provided secret resolver (for testing)
-pub fn load_config_with_resolver(resolver: &impl SecretResolver) -> Result<Config> {
-    let path = config_path()?;
-    let content = fs::read_to_string(&path)
+/// Finds the files an `include` pattern matches, in name order. Relative patterns are
+/// relative to the directory of the including file.
+fn include_paths(pattern: &str, dir: &Path) -> Result<Vec<PathBuf>> {
+    let pattern = dir.join(expand_home(Path::new(pattern)));
+    let pattern = pattern.to_string_lossy();
+    let paths = glob::glob(&pattern)
+        .with_context(|| format!("Invalid include pattern '{}'", pattern))?
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .with_context(|| format!("Failed to read files matching '{}'", pattern))?;
+    // A pattern without wildcards names one file, which must exist
+    if paths.is_empty() && !pattern.contains(['*', '?', '[']) {
+        anyhow::bail!("Included file {} not found", pattern);
+    }
+    Ok(paths)
+}
+
+/// Merges an included file into the config: tables are combined and arrays (like
+/// `[[policy]]` or protected_senders) are extended, but any other value may only be set
+/// in one file
+fn merge_included(into: &mut toml::Table, from: toml::Table, parent: &str) -> Result<()> {
+    for (key, value) in from {
+        let name = if parent.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", parent, key)
+        };
+        match (into.get_mut(&key), value) {
+            (Some(toml::Value::Table(existing)), toml::Value::Table(table)) => {
+                merge_included(existing, table, &name)?
+            }
+            (Some(toml::Value::Array(existing)), toml::Value::Array(items)) => {
+                existing.extend(items)
+            }
+            (Some(_), _) => anyhow::bail!("{} is already set", name),
+            (None, value) => {
+                into.insert(key, value);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The number of single-character edits that turn `a` into `b`
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = (above + 1)
+                .min(row[j] + 1)
+                .min(diagonal + usize::from(ca != cb));
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+/// Finds the valid key closest to the misspelt one in an "unknown field" error
+fn nearest_key(message: &str) -> Option<(&str, &str)> {
+    let rest = message.split_once("unknown field `")?.1;
+    let (unknown, expected) = rest.split_once('`')?;
+    expected
+        .split('`')
+        .skip(1)
+        .step_by(2)
+        .map(|key| (edit_distance(unknown, key), key))
+        .filter(|(distance, _)| *distance <= (unknown.len() / 3).max(2))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, key)| (unknown, key))
+}
+
+/// Adds the nearest valid key to an unknown key error, so a typo is easy to spot
+fn suggest_key(error: toml::de::Error) -> anyhow::Error {
+    let message = error.to_string();
+    match nearest_key(&message) {
+        Some((unknown, key)) => anyhow::anyhow!(
+            "{}\nUnknown key '{}': did you mean '{}'?",
+            message.trim_end(),
+            unknown,
+            key
+        ),
+        None => error.into(),
+    }
+}
+
+/// Parses a config file, merging in the files its `include` patterns match
+fn parse_config_file(path: &Path) -> Result<Config> {
+    let content = fs::read_to_string(path)
         .with_context(|| format!("Failed to read config from {:?}", path))?;
-    let config: Config = toml::from_str(&content).context("Failed to parse config.toml")?;
+    let mut table: toml::Table =
+        toml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))?;
+    let Some(include) = table.remove("include") else {
+        // Parse the text itself so errors keep their line numbers
+        return toml::from_str(&content)
+            .map_err(suggest_key)
+            .with_context(|| format!("Failed to parse {}", path.display()));
+    };
+
+    let patterns: Vec<String> = include
+        .try_into()
+        .context("include must be a list of file patterns, e.g. [\"accounts.d/*.toml\"]")?;
+    let dir = path.parent().unwrap_or(Path::new("."));
+    for pattern in &patterns {
+        for included in include_paths(pattern, dir)? {
+            let content = fs::read_to_string(&included)
+                .with_context(|| format!("Failed to read {}", included.display()))?;
+            let part: toml::Table = toml::from_str(&content)
+                .with_context(|| format!("Failed to parse {}", included.display()))?;
+            if part.contains_key("include") {
+                anyhow::bail!(
+                    "{} can't include other files; only the main config can",
+                    included.display()
+                );
+            }
+            merge_included(&mut table, part, "")
+                .with_context(|| format!("Failed to include {}", included.display()))?;
+        }
+    }
+    toml::Value::Table(table)
+        .try_into()
+        .map_err(suggest_key)
+        .with_context(|| format!("Failed to parse {} with its includes", path.display()))
+}
+
+/// Keys the main views handle in every view, so an [[action]] or [[plugin]] bound to one
+/// would never run
+const BUILT_IN_KEYS: &[char] = &[
+    'q', 'j', 'k', 'e', 'T', 'C', 'P', 'g', 'G', 'r', 'u', 'a', 'A', 'd', 'D', 'V', ' ', '?',
+];
+
+/// Lists the [[action]] and [[plugin]] keys that can never run: taken by a built-in key,
+/// or bound twice (plugins are tried first, then actions in config order)
+fn key_conflicts(config: &Config) -> Vec<String> {
+    let named = |kind: &str, description: &Option<String>| match description {
+        Some(description) => format!("{} '{}'", kind, description),
+        None => kind.to_string(),
+    };
+    let bindings: Vec<(char, String)> = config
+        .plugins
+        .iter()
+        .map(|p| (p.key, named("[[plugin]]", &p.description)))
+        .chain(
+            config
+                .actions
+                .iter()
+                .map(|a| (a.key, named("[[action]]", &a.description))),
+        )
+        .collect();
+    let mut conflicts = Vec::new();
+    for (i, (key, name)) in bindings.iter().enumerate() {
+        if BUILT_IN_KEYS.contains(key) {
+            conflicts.push(format!("{} uses the built-in key '{}'", name, key));
+        } else if let Some((_, first)) = bindings[..i].iter().find(|(k, _)| k == key) {
+            conflicts.push(format!(
+                "{} uses key '{}', which {} already has",
+                name, key, first
+            ));
+        }
+    }
+    conflicts
+}
 
+/// Checks everything in the config that serde can't, before any secret is resolved
+fn validate(config: &Config) -> Result<()> {
     if config.accounts.is_empty() {
         anyhow::bail!("No accounts configured in config.toml");
     }
 
+    if let Some(policy) = config.policies.iter().find(|p| !p.is_valid()) {
+        anyhow::bail!(
+            "Policy '{}' needs older_than_days or senders in config.toml",
+            policy.name
+        );
+    }
+
+    if config.highlights.iter().any(|h| !h.is_valid()) {
+        anyhow::bail!("Each [[highlight]] needs senders or subject in config.toml");
+    }
+
+    if let Some(color) = config
+        .highlights
+        .iter()
+        .find(|h| h.color.is_some() && h.parsed_color().is_none())
+        .and_then(|h| h.color.as_deref())
+    {
+        anyhow::bail!("Unknown highlight color '{}' in config.toml", color);
+    }
+
+    for action in &config.actions {
+        action
+            .validate()
+            .with_context(|| format!("Invalid script for [[action]] key '{}'", action.key))?;
+    }
+
+    if let Some((name, _)) = config.accounts.iter().find(|(_, a)| {
+        a.backend == Backend::GmailApi && a.oauth_token_command.as_deref().is_none_or(str::is_empty)
+    }) {
+        anyhow::bail!(
+            "Account '{}' uses the gmail-api backend and needs oauth_token_command in config.toml",
+            name
+        );
+    }
+
+    if let Some(plugin) = config.plugins.iter().find(|p| p.command.trim().is_empty()) {
+        anyhow::bail!(
+            "[[plugin]] key '{}' needs a command in config.toml",
+            plugin.key
+        );
+    }
+
+    let conflicts = key_conflicts(config);
+    if !conflicts.is_empty() {
+        anyhow::bail!(
+            "Keys in config.toml that would never run:\n  {}",
+            conflicts.join("\n  ")
+        );
+    }
+
+    for (name, account) in &config.accounts {
+        account
+            .fetch_window_start(chrono::Utc::now().date_naive())
+            .with_context(|| format!("Invalid settings for account '{}'", name))?;
+        if let Some(color) = &account.accent_color
+            && account.accent().is_none()
+        {
+            anyhow::bail!(
+                "Unknown accent_color '{}' for account '{}' in config.toml",
+                color,
+                name
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Parses and checks the config file without resolving secrets, for `zeroterm config
+/// validate`. Returns the path that was checked.
+pub fn validate_config_file() -> Result<PathBuf> {
+    let path = config_path()?;
+    validate(&parse_config_file(&path)?)?;
+    Ok(path)
+}
+
+/// Parses the config file without checking it or resolving secrets, for `zeroterm
+/// debug-bundle`
+pub fn read_config_file() -> Result<Config> {
+    parse_config_file(&config_path()?)
+}
+
+/// Loads config using a provided secret resolver (for testing)
+pub fn load_config_with_resolver(resolver: &impl SecretResolver) -> Result<Config> {
+    let config = parse_config_file(&config_path()?)?;
+    validate(&config)?;
+
     // Resolve app_password for each account
     let mut resolved_accounts = HashMap::new();
     for (name, account) in config.accounts {
@@ -142,9 +937,8 @@ pub fn load_config_with_resolver(resolver: &impl SecretResolver) -> Result<Confi
         resolved_accounts.insert(
             name,
             AccountConfig {
-                backend: account.backend,
-                email: account.email,
                 app_password: resolved_password,
+                ..account
             },
         );
     }
@@ -155,6 +949,39 @@ pub fn load_config_with_resolver(resolver: &impl SecretResolver) -> Result<Confi
         parallel_connections: config.parallel_connections,
         debug: config.debug,
         advance_on_select: config.advance_on_select,
+        merge_subject_threads: config.merge_subject_threads,
+        thread_inline_bodies: config.thread_inline_bodies,
+        row_density: config.row_density,
+        auth_warnings_in_list: config.auth_warnings_in_list,
+        thread_recipients_column: config.thread_recipients_column,
+        archive_older_than_days: config.archive_older_than_days,
+        protected_senders: config.protected_senders,
+        ignored_senders: config.ignored_senders,
+        webhook_url: config.webhook_url,
+        policies: config.policies,
+        highlights: config.highlights,
+        actions: config.actions,
+        plugins: config.plugins,
+        confirm_policies: config.confirm_policies,
+        typed_confirm_above: config.typed_confirm_above,
+        typed_count_confirm_above: config.typed_count_confirm_above,
+        fetch_limit: config.fetch_limit,
+        fetch_page_size: config.fetch_page_size,
+        body_size_limit_kb: config.body_size_limit_kb,
+        mark_seen_on_view: config.mark_seen_on_view,
+        mark_read_on_archive: config.mark_read_on_archive,
+        task_command: config.task_command,
+        archive_after_task: config.archive_after_task,
+        capture_file: config.capture_file,
+        capture_excerpt_lines: config.capture_excerpt_lines,
+        print_command: config.print_command,
+        pgp_command: config.pgp_command,
+        triage_timer_minutes: config.triage_timer_minutes,
+        min_group_size: config.min_group_size,
+        postpone_days: config.postpone_days,
+        window_title: config.window_title,
+        header_cache: config.header_cache,
+        header_cache_encrypt_to: config.header_cache_encrypt_to,
     })
 }
 
@@ -170,6 +997,7 @@ pub fn get_default_account(config: &Config) -> Result<(&String, &AccountConfig)>
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::policy::PolicyAction;
 
     #[test]
     fn test_config_dir_not_empty() {
@@ -187,6 +1015,28 @@ mod tests {
         assert!(path.ends_with(CONFIG_FILE));
     }
 
+    #[test]
+    fn test_data_file_round_trip_and_missing_file() {
+        #[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+        struct Data {
+            notes: HashMap<String, String>,
+            senders: Vec<String>,
+        }
+
+        let dir = std::env::temp_dir().join(format!("zeroterm-data-{}", std::process::id()));
+        let path = dir.join("notes/personal.toml");
+        let data = Data {
+            notes: HashMap::from([("alice@example.com".to_string(), "keep receipts".to_string())]),
+            senders: vec!["boss@work.com".to_string()],
+        };
+        let missing: Data = load_data_file(&path).unwrap();
+        save_data_file(&path, &data).unwrap();
+        let loaded: Data = load_data_file(&path).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(missing, Data::default());
+        assert_eq!(loaded, data);
+    }
+
     #[test]
     fn test_parse_single_account_config() {
         let toml_content = r#"
@@ -309,44 +1159,33 @@ app_password = "xxxx"
     }
 
     #[test]
-    fn test_parse_multiple_accounts_config() {
+    fn test_merge_subject_threads_defaults_to_false() {
         let toml_content = r#"
 [accounts.personal]
 backend = "gmail"
-email = "personal@gmail.com"
-app_password = "xxxx xxxx xxxx xxxx"
-
-[accounts.work]
-backend = "gmail"
-email = "work@company.com"
-app_password = "yyyy yyyy yyyy yyyy"
+email = "user@gmail.com"
+app_password = "xxxx"
 "#;
         let config: Config = toml::from_str(toml_content).unwrap();
-        assert_eq!(config.accounts.len(), 2);
-
-        let personal = config.accounts.get("personal").unwrap();
-        assert_eq!(personal.backend, Backend::Gmail);
-        assert_eq!(personal.email, "personal@gmail.com");
-
-        let work = config.accounts.get("work").unwrap();
-        assert_eq!(work.backend, Backend::Gmail);
-        assert_eq!(work.email, "work@company.com");
+        assert!(!config.merge_subject_threads);
     }
 
     #[test]
-    fn test_backend_requires_valid_value() {
+    fn test_merge_subject_threads_can_be_enabled() {
         let toml_content = r#"
-[accounts.test]
-backend = "outlook"
-email = "user@outlook.com"
+merge_subject_threads = true
+
+[accounts.personal]
+backend = "gmail"
+email = "user@gmail.com"
 app_password = "xxxx"
 "#;
-        let result: Result<Config, _> = toml::from_str(toml_content);
-        assert!(result.is_err());
+        let config: Config = toml::from_str(toml_content).unwrap();
+        assert!(config.merge_subject_threads);
     }
 
     #[test]
-    fn test_get_default_account() {
+    fn test_thread_inline_bodies_defaults_to_false() {
         let toml_content = r#"
 [accounts.personal]
 backend = "gmail"
@@ -354,24 +1193,751 @@ email = "user@gmail.com"
 app_password = "xxxx"
 "#;
         let config: Config = toml::from_str(toml_content).unwrap();
-        let (name, account) = get_default_account(&config).unwrap();
-        assert_eq!(name, "personal");
-        assert_eq!(account.email, "user@gmail.com");
+        assert!(!config.thread_inline_bodies);
     }
 
     #[test]
-    fn test_op_resolver_plain_text() {
-        let resolver = OpSecretResolver;
-        let result = resolver.resolve("plain-password").unwrap();
-        assert_eq!(result, "plain-password");
+    fn test_row_density() {
+        let toml_content = r#"
+[accounts.personal]
+backend = "gmail"
+email = "user@gmail.com"
+app_password = "xxxx"
+"#;
+        let config: Config = toml::from_str(toml_content).unwrap();
+        assert_eq!(config.row_density, RowDensity::Compact);
+
+        let config: Config =
+            toml::from_str(&format!("row_density = \"comfortable\"\n{}", toml_content)).unwrap();
+        assert_eq!(config.row_density, RowDensity::Comfortable);
     }
 
     #[test]
-    fn test_op_resolver_returns_value_unchanged_when_not_op_reference() {
-        let resolver = OpSecretResolver;
-        assert_eq!(resolver.resolve("my-secret").unwrap(), "my-secret");
-        assert_eq!(
-            resolver.resolve("xxxx xxxx xxxx xxxx").unwrap(),
+    fn test_auth_warnings_in_list() {
+        let toml_content = r#"
+[accounts.personal]
+backend = "gmail"
+email = "user@gmail.com"
+app_password = "xxxx"
+"#;
+        let config: Config = toml::from_str(toml_content).unwrap();
+        assert!(!config.auth_warnings_in_list);
+
+        let config: Config =
+            toml::from_str(&format!("auth_warnings_in_list = true\n{}", toml_content)).unwrap();
+        assert!(config.auth_warnings_in_list);
+    }
+
+    #[test]
+    fn test_thread_inline_bodies_can_be_enabled() {
+        let toml_content = r#"
+thread_inline_bodies = true
+
+[accounts.personal]
+backend = "gmail"
+email = "user@gmail.com"
+app_password = "xxxx"
+"#;
+        let config: Config = toml::from_str(toml_content).unwrap();
+        assert!(config.thread_inline_bodies);
+    }
+
+    #[test]
+    fn test_thread_recipients_column_defaults_to_false() {
+        let toml_content = r#"
+[accounts.personal]
+backend = "gmail"
+email = "user@gmail.com"
+app_password = "xxxx"
+"#;
+        let config: Config = toml::from_str(toml_content).unwrap();
+        assert!(!config.thread_recipients_column);
+    }
+
+    #[test]
+    fn test_thread_recipients_column_can_be_enabled() {
+        let toml_content = r#"
+thread_recipients_column = true
+
+[accounts.personal]
+backend = "gmail"
+email = "user@gmail.com"
+app_password = "xxxx"
+"#;
+        let config: Config = toml::from_str(toml_content).unwrap();
+        assert!(config.thread_recipients_column);
+    }
+
+    #[test]
+    fn test_archive_older_than_defaults() {
+        let toml_content = r#"
+[accounts.personal]
+backend = "gmail"
+email = "user@gmail.com"
+app_password = "xxxx"
+"#;
+        let config: Config = toml::from_str(toml_content).unwrap();
+        assert_eq!(config.archive_older_than_days, 365);
+        assert!(config.protected_senders.is_empty());
+        assert!(config.ignored_senders.is_empty());
+    }
+
+    #[test]
+    fn test_webhook_url_is_optional() {
+        let toml_content = r#"
+webhook_url = "https://hooks.example.com/zeroterm"
+
+[accounts.personal]
+backend = "gmail"
+email = "user@gmail.com"
+app_password = "xxxx"
+"#;
+        let config: Config = toml::from_str(toml_content).unwrap();
+        assert_eq!(
+            config.webhook_url.as_deref(),
+            Some("https://hooks.example.com/zeroterm")
+        );
+    }
+
+    #[test]
+    fn test_archive_older_than_can_be_configured() {
+        let toml_content = r#"
+archive_older_than_days = 90
+protected_senders = ["boss@work.com", "family.org"]
+
+[accounts.personal]
+backend = "gmail"
+email = "user@gmail.com"
+app_password = "xxxx"
+"#;
+        let config: Config = toml::from_str(toml_content).unwrap();
+        assert_eq!(config.archive_older_than_days, 90);
+        assert_eq!(
+            config.protected_senders,
+            vec!["boss@work.com".to_string(), "family.org".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_fetch_limit_defaults() {
+        let toml_content = r#"
+[accounts.personal]
+backend = "gmail"
+email = "user@gmail.com"
+app_password = "xxxx"
+"#;
+        let config: Config = toml::from_str(toml_content).unwrap();
+        assert_eq!(config.fetch_limit, None);
+        assert_eq!(config.fetch_page_size, 5000);
+    }
+
+    #[test]
+    fn test_fetch_limit_can_be_configured() {
+        let toml_content = r#"
+fetch_limit = 20000
+fetch_page_size = 2000
+
+[accounts.personal]
+backend = "gmail"
+email = "user@gmail.com"
+app_password = "xxxx"
+"#;
+        let config: Config = toml::from_str(toml_content).unwrap();
+        assert_eq!(config.fetch_limit, Some(20000));
+        assert_eq!(config.fetch_page_size, 2000);
+    }
+
+    #[test]
+    fn test_body_size_limit_defaults_to_256_kb() {
+        let toml_content = r#"
+[accounts.personal]
+backend = "gmail"
+email = "user@gmail.com"
+app_password = "xxxx"
+"#;
+        let config: Config = toml::from_str(toml_content).unwrap();
+        assert_eq!(config.body_size_limit_kb, 256);
+        assert_eq!(config.body_size_limit(), Some(256 * 1024));
+    }
+
+    #[test]
+    fn test_body_size_limit_zero_disables_cap() {
+        let toml_content = r#"
+body_size_limit_kb = 0
+
+[accounts.personal]
+backend = "gmail"
+email = "user@gmail.com"
+app_password = "xxxx"
+"#;
+        let config: Config = toml::from_str(toml_content).unwrap();
+        assert_eq!(config.body_size_limit(), None);
+    }
+
+    #[test]
+    fn test_mark_seen_on_view_defaults_to_false() {
+        let toml_content = r#"
+[accounts.personal]
+backend = "gmail"
+email = "user@gmail.com"
+app_password = "xxxx"
+"#;
+        let config: Config = toml::from_str(toml_content).unwrap();
+        assert!(!config.mark_seen_on_view);
+
+        let config: Config =
+            toml::from_str(&format!("mark_seen_on_view = true\n{}", toml_content)).unwrap();
+        assert!(config.mark_seen_on_view);
+    }
+
+    #[test]
+    fn test_mark_read_on_archive_defaults_to_false() {
+        let toml_content = r#"
+[accounts.personal]
+backend = "gmail"
+email = "user@gmail.com"
+app_password = "xxxx"
+"#;
+        let config: Config = toml::from_str(toml_content).unwrap();
+        assert!(!config.mark_read_on_archive);
+
+        let config: Config =
+            toml::from_str(&format!("mark_read_on_archive = true\n{}", toml_content)).unwrap();
+        assert!(config.mark_read_on_archive);
+    }
+
+    #[test]
+    fn test_task_command_options() {
+        let toml_content = r#"
+[accounts.personal]
+backend = "gmail"
+email = "user@gmail.com"
+app_password = "xxxx"
+"#;
+        let config: Config = toml::from_str(toml_content).unwrap();
+        assert_eq!(config.task_command, None);
+        assert!(!config.archive_after_task);
+
+        let config: Config = toml::from_str(&format!(
+            "task_command = \"task add {{subject}}\"\narchive_after_task = true\n{}",
+            toml_content
+        ))
+        .unwrap();
+        assert_eq!(config.task_command.as_deref(), Some("task add {subject}"));
+        assert!(config.archive_after_task);
+    }
+
+    #[test]
+    fn test_capture_options() {
+        let toml_content = r#"
+[accounts.personal]
+backend = "gmail"
+email = "user@gmail.com"
+app_password = "xxxx"
+"#;
+        let config: Config = toml::from_str(toml_content).unwrap();
+        assert_eq!(config.capture_file, None);
+        assert_eq!(config.capture_excerpt_lines, 10);
+        assert_eq!(config.print_command, "lp");
+        assert_eq!(config.pgp_command, "gpg");
+
+        let config: Config = toml::from_str(&format!(
+            "capture_file = \"~/notes/inbox.org\"\ncapture_excerpt_lines = 0\n{}",
+            toml_content
+        ))
+        .unwrap();
+        assert_eq!(
+            config.capture_file.as_deref(),
+            Some(Path::new("~/notes/inbox.org"))
+        );
+        assert_eq!(config.capture_excerpt_lines, 0);
+    }
+
+    #[test]
+    fn test_triage_timer_defaults_to_off() {
+        let toml_content = r#"
+[accounts.personal]
+backend = "gmail"
+email = "user@gmail.com"
+app_password = "xxxx"
+"#;
+        let config: Config = toml::from_str(toml_content).unwrap();
+        assert_eq!(config.triage_timer_minutes, 0);
+
+        let config: Config =
+            toml::from_str(&format!("triage_timer_minutes = 25\n{}", toml_content)).unwrap();
+        assert_eq!(config.triage_timer_minutes, 25);
+    }
+
+    #[test]
+    fn test_min_group_size_defaults_to_showing_all() {
+        let toml_content = r#"
+[accounts.personal]
+backend = "gmail"
+email = "user@gmail.com"
+app_password = "xxxx"
+"#;
+        let config: Config = toml::from_str(toml_content).unwrap();
+        assert_eq!(config.min_group_size, 0);
+
+        let config: Config =
+            toml::from_str(&format!("min_group_size = 3\n{}", toml_content)).unwrap();
+        assert_eq!(config.min_group_size, 3);
+    }
+
+    #[test]
+    fn test_typed_confirm_above_defaults_to_100() {
+        let toml_content = r#"
+[accounts.personal]
+backend = "gmail"
+email = "user@gmail.com"
+app_password = "xxxx"
+"#;
+        let config: Config = toml::from_str(toml_content).unwrap();
+        assert_eq!(config.typed_confirm_above, 100);
+        assert_eq!(config.typed_count_confirm_above, 1000);
+
+        let config: Config =
+            toml::from_str(&format!("typed_confirm_above = 0\n{}", toml_content)).unwrap();
+        assert_eq!(config.typed_confirm_above, 0);
+    }
+
+    #[test]
+    fn test_window_title_defaults_to_on() {
+        let toml_content = r#"
+[accounts.personal]
+backend = "gmail"
+email = "user@gmail.com"
+app_password = "xxxx"
+"#;
+        let config: Config = toml::from_str(toml_content).unwrap();
+        assert!(config.window_title);
+
+        let config: Config =
+            toml::from_str(&format!("window_title = false\n{}", toml_content)).unwrap();
+        assert!(!config.window_title);
+    }
+
+    #[test]
+    fn test_policies_default_to_empty() {
+        let toml_content = r#"
+[accounts.personal]
+backend = "gmail"
+email = "user@gmail.com"
+app_password = "xxxx"
+"#;
+        let config: Config = toml::from_str(toml_content).unwrap();
+        assert!(config.policies.is_empty());
+        assert!(!config.confirm_policies);
+    }
+
+    #[test]
+    fn test_parse_highlights() {
+        let toml_content = r##"
+[[highlight]]
+subject = ["invoice", "receipt"]
+color = "lightred"
+bold = true
+
+[[highlight]]
+senders = ["boss@work.com"]
+color = "#ff8800"
+
+[accounts.personal]
+backend = "gmail"
+email = "user@gmail.com"
+app_password = "xxxx"
+"##;
+        let config: Config = toml::from_str(toml_content).unwrap();
+        assert_eq!(config.highlights.len(), 2);
+        assert_eq!(config.highlights[0].subject, vec!["invoice", "receipt"]);
+        assert!(config.highlights[0].bold);
+        assert_eq!(
+            config.highlights[1].parsed_color(),
+            Some(ratatui::style::Color::Rgb(0xff, 0x88, 0x00))
+        );
+        assert!(!config.highlights[1].underline);
+    }
+
+    #[test]
+    fn test_parse_actions() {
+        let toml_content = r#"
+[[action]]
+key = "X"
+description = "Archive newsletters"
+script = 'if email.mailing_list { archive(); }'
+
+[accounts.personal]
+backend = "gmail"
+email = "user@gmail.com"
+app_password = "xxxx"
+"#;
+        let config: Config = toml::from_str(toml_content).unwrap();
+        assert_eq!(config.actions.len(), 1);
+        assert_eq!(config.actions[0].key, 'X');
+        assert!(config.actions[0].validate().is_ok());
+    }
+
+    #[test]
+    fn test_parse_plugins() {
+        let toml_content = r#"
+[[plugin]]
+key = "9"
+command = "~/bin/snooze --days 3"
+
+[accounts.personal]
+backend = "gmail"
+email = "user@gmail.com"
+app_password = "xxxx"
+"#;
+        let config: Config = toml::from_str(toml_content).unwrap();
+        assert_eq!(config.plugins.len(), 1);
+        assert_eq!(config.plugins[0].key, '9');
+        assert_eq!(config.plugins[0].label(), "plugin 9");
+    }
+
+    #[test]
+    fn test_parse_policies() {
+        let toml_content = r#"
+confirm_policies = true
+
+[[policy]]
+name = "Read newsletters"
+older_than_days = 30
+read = true
+newsletter = true
+
+[[policy]]
+name = "Receipts"
+action = "delete"
+senders = ["receipts@stripe.com"]
+
+[accounts.personal]
+backend = "gmail"
+email = "user@gmail.com"
+app_password = "xxxx"
+"#;
+        let config: Config = toml::from_str(toml_content).unwrap();
+        assert!(config.confirm_policies);
+        assert_eq!(config.policies.len(), 2);
+        assert_eq!(config.policies[0].action, PolicyAction::Archive);
+        assert_eq!(config.policies[0].older_than_days, Some(30));
+        assert_eq!(config.policies[0].read, Some(true));
+        assert_eq!(config.policies[1].action, PolicyAction::Delete);
+        assert_eq!(config.policies[1].senders, vec!["receipts@stripe.com"]);
+    }
+
+    #[test]
+    fn test_parse_multiple_accounts_config() {
+        let toml_content = r#"
+[accounts.personal]
+backend = "gmail"
+email = "personal@gmail.com"
+app_password = "xxxx xxxx xxxx xxxx"
+
+[accounts.work]
+backend = "gmail"
+email = "work@company.com"
+app_password = "yyyy yyyy yyyy yyyy"
+"#;
+        let config: Config = toml::from_str(toml_content).unwrap();
+        assert_eq!(config.accounts.len(), 2);
+
+        let personal = config.accounts.get("personal").unwrap();
+        assert_eq!(personal.backend, Backend::Gmail);
+        assert_eq!(personal.email, "personal@gmail.com");
+
+        let work = config.accounts.get("work").unwrap();
+        assert_eq!(work.backend, Backend::Gmail);
+        assert_eq!(work.email, "work@company.com");
+    }
+
+    #[test]
+    fn test_backend_requires_valid_value() {
+        let toml_content = r#"
+[accounts.test]
+backend = "yahoo"
+email = "user@yahoo.com"
+app_password = "xxxx"
+"#;
+        let result: Result<Config, _> = toml::from_str(toml_content);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_get_default_account() {
+        let toml_content = r#"
+[accounts.personal]
+backend = "gmail"
+email = "user@gmail.com"
+app_password = "xxxx"
+"#;
+        let config: Config = toml::from_str(toml_content).unwrap();
+        let (name, account) = get_default_account(&config).unwrap();
+        assert_eq!(name, "personal");
+        assert_eq!(account.email, "user@gmail.com");
+    }
+
+    #[test]
+    fn test_tls_options_default_to_gmail() {
+        let toml_content = r#"
+[accounts.personal]
+backend = "gmail"
+email = "user@gmail.com"
+app_password = "xxxx"
+"#;
+        let config: Config = toml::from_str(toml_content).unwrap();
+        let account = config.accounts.get("personal").unwrap();
+        assert_eq!(account.imap_host(), "imap.gmail.com");
+        assert_eq!(account.imap_port(), 993);
+        assert!(account.tls_ca_file.is_none());
+        assert!(!account.danger_accept_invalid_certs);
+        assert_eq!(account.security, Security::Tls);
+    }
+
+    #[test]
+    fn test_outlook_backend_preset() {
+        let toml_content = r#"
+[accounts.work]
+backend = "outlook"
+email = "me@company.com"
+app_password = "xxxx"
+"#;
+        let config: Config = toml::from_str(toml_content).unwrap();
+        let account = config.accounts.get("work").unwrap();
+        assert_eq!(account.backend, Backend::Outlook);
+        assert_eq!(account.imap_host(), "outlook.office365.com");
+        assert_eq!(account.imap_port(), 993);
+        let folders = account.backend.folders();
+        assert_eq!(folders.archive, "Archive");
+        assert_eq!(folders.trash, "Deleted Items");
+        assert_eq!(folders.sent, "Sent Items");
+        assert_eq!(
+            account.backend.web_link("<a@b.com>", "me@company.com"),
+            "https://outlook.office.com/mail/deeplink/search?query=%3Ca%40b.com%3E&login_hint=me%40company.com"
+        );
+        assert!(
+            Backend::Gmail
+                .web_link("<a@b.com>", "me@gmail.com")
+                .starts_with("https://mail.google.com/")
+        );
+    }
+
+    #[test]
+    fn test_jmap_backend_defaults_to_fastmail() {
+        let toml_content = r#"
+[accounts.fastmail]
+backend = "jmap"
+email = "me@fastmail.com"
+app_password = "fmu1-token"
+
+[accounts.selfhosted]
+backend = "jmap"
+email = "me@example.com"
+app_password = "token"
+jmap_url = "https://mail.example.com/.well-known/jmap"
+"#;
+        let config: Config = toml::from_str(toml_content).unwrap();
+        let fastmail = config.accounts.get("fastmail").unwrap();
+        assert_eq!(fastmail.backend, Backend::Jmap);
+        assert_eq!(fastmail.jmap_url(), "https://api.fastmail.com/jmap/session");
+        assert_eq!(
+            config.accounts.get("selfhosted").unwrap().jmap_url(),
+            "https://mail.example.com/.well-known/jmap"
+        );
+        assert_eq!(
+            fastmail.backend.web_link("<a@b.com>", "me@fastmail.com"),
+            "https://app.fastmail.com/mail/search:msgid%3A%3Ca%40b.com%3E"
+        );
+        let error = fastmail.require_imap("zeroterm daemon").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "zeroterm daemon only works with IMAP accounts, not jmap"
+        );
+    }
+
+    #[test]
+    fn test_gmail_api_backend_needs_a_token_command() {
+        let toml_content = r#"
+[accounts.personal]
+backend = "gmail-api"
+email = "me@gmail.com"
+app_password = "secret"
+oauth_token_command = "oauth2l fetch --scope gmail.modify"
+"#;
+        let config: Config = toml::from_str(toml_content).unwrap();
+        let account = config.accounts.get("personal").unwrap();
+        assert_eq!(account.backend, Backend::GmailApi);
+        assert_eq!(account.imap_host(), "imap.gmail.com");
+        assert_eq!(account.backend.folders(), Backend::Gmail.folders());
+        assert!(account.require_imap("zeroterm daemon").is_ok());
+        assert!(validate(&config).is_ok());
+
+        let without: Config = toml::from_str(&toml_content.replace(
+            "oauth_token_command = \"oauth2l fetch --scope gmail.modify\"",
+            "",
+        ))
+        .unwrap();
+        assert_eq!(
+            validate(&without).unwrap_err().to_string(),
+            "Account 'personal' uses the gmail-api backend and needs oauth_token_command in config.toml"
+        );
+    }
+
+    #[test]
+    fn test_tls_options_can_be_configured() {
+        let toml_content = r#"
+[accounts.proton]
+backend = "gmail"
+email = "user@proton.me"
+app_password = "xxxx"
+imap_host = "127.0.0.1"
+imap_port = 1143
+tls_ca_file = "/home/user/.config/protonmail/bridge/cert.pem"
+danger_accept_invalid_certs = true
+"#;
+        let config: Config = toml::from_str(toml_content).unwrap();
+        let account = config.accounts.get("proton").unwrap();
+        assert_eq!(account.imap_host(), "127.0.0.1");
+        assert_eq!(account.imap_port(), 1143);
+        assert_eq!(
+            account.tls_ca_file.as_deref(),
+            Some(std::path::Path::new(
+                "/home/user/.config/protonmail/bridge/cert.pem"
+            ))
+        );
+        assert!(account.danger_accept_invalid_certs);
+    }
+
+    #[test]
+    fn test_fetch_window_start() {
+        let toml_content = r#"
+[accounts.personal]
+backend = "gmail"
+email = "user@gmail.com"
+app_password = "xxxx"
+fetch_window = "6 months"
+"#;
+        let config: Config = toml::from_str(toml_content).unwrap();
+        let today = NaiveDate::from_ymd_opt(2026, 10, 15).unwrap();
+        assert_eq!(
+            config.accounts["personal"]
+                .fetch_window_start(today)
+                .unwrap(),
+            NaiveDate::from_ymd_opt(2026, 4, 15)
+        );
+
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        assert_eq!(window_start("30 days", today).unwrap(), date(2026, 9, 15));
+        assert_eq!(window_start("2 Weeks", today).unwrap(), date(2026, 10, 1));
+        assert_eq!(window_start("1 year", today).unwrap(), date(2025, 10, 15));
+        assert!(window_start("6", today).is_err());
+        assert!(window_start("six months", today).is_err());
+        assert!(window_start("6 fortnights", today).is_err());
+    }
+
+    #[test]
+    fn test_account_accent_color() {
+        let toml_content = r##"
+[accounts.personal]
+backend = "gmail"
+email = "user@gmail.com"
+app_password = "xxxx"
+
+[accounts.work]
+backend = "gmail"
+email = "user@work.com"
+app_password = "xxxx"
+accent_color = "#ff8800"
+"##;
+        let config: Config = toml::from_str(toml_content).unwrap();
+        assert_eq!(config.accounts["personal"].accent(), None);
+        assert_eq!(
+            config.accounts["work"].accent(),
+            Some(Color::Rgb(0xff, 0x88, 0x00))
+        );
+    }
+
+    #[test]
+    fn test_fetch_window_defaults_to_all_mail() {
+        let toml_content = r#"
+[accounts.personal]
+backend = "gmail"
+email = "user@gmail.com"
+app_password = "xxxx"
+"#;
+        let config: Config = toml::from_str(toml_content).unwrap();
+        let today = NaiveDate::from_ymd_opt(2026, 10, 15).unwrap();
+        assert_eq!(
+            config.accounts["personal"]
+                .fetch_window_start(today)
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_security_modes_parse() {
+        for (value, expected) in [
+            ("tls", Security::Tls),
+            ("starttls", Security::StartTls),
+            ("none", Security::None),
+        ] {
+            let toml_content = format!(
+                r#"
+[accounts.local]
+backend = "gmail"
+email = "user@localhost"
+app_password = "xxxx"
+imap_host = "localhost"
+security = "{}"
+"#,
+                value
+            );
+            let config: Config = toml::from_str(&toml_content).unwrap();
+            assert_eq!(config.accounts["local"].security, expected);
+        }
+    }
+
+    #[test]
+    fn test_default_port_follows_security() {
+        let toml_content = r#"
+[accounts.local]
+backend = "gmail"
+email = "user@localhost"
+app_password = "xxxx"
+security = "starttls"
+"#;
+        let config: Config = toml::from_str(toml_content).unwrap();
+        assert_eq!(config.accounts["local"].imap_port(), 143);
+    }
+
+    #[test]
+    fn test_invalid_security_is_rejected() {
+        let toml_content = r#"
+[accounts.local]
+backend = "gmail"
+email = "user@localhost"
+app_password = "xxxx"
+security = "ssl"
+"#;
+        let result: Result<Config, _> = toml::from_str(toml_content);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_op_resolver_plain_text() {
+        let resolver = OpSecretResolver;
+        let result = resolver.resolve("plain-password").unwrap();
+        assert_eq!(result, "plain-password");
+    }
+
+    #[test]
+    fn test_op_resolver_returns_value_unchanged_when_not_op_reference() {
+        let resolver = OpSecretResolver;
+        assert_eq!(resolver.resolve("my-secret").unwrap(), "my-secret");
+        assert_eq!(
+            resolver.resolve("xxxx xxxx xxxx xxxx").unwrap(),
             "xxxx xxxx xxxx xxxx"
         );
         assert_eq!(resolver.resolve("").unwrap(), "");
@@ -403,4 +1969,145 @@ app_password = "xxxx"
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("1Password"));
     }
+
+    #[test]
+    fn test_merge_included() {
+        let mut main: toml::Table = toml::from_str(
+            r#"
+protected_senders = ["bank.com"]
+[accounts.personal]
+email = "me@gmail.com"
+"#,
+        )
+        .unwrap();
+        let part: toml::Table = toml::from_str(
+            r#"
+protected_senders = ["school.edu"]
+[accounts.work]
+email = "me@work.com"
+"#,
+        )
+        .unwrap();
+        merge_included(&mut main, part, "").unwrap();
+        assert_eq!(
+            main["protected_senders"].as_array().unwrap().len(),
+            2,
+            "arrays are extended"
+        );
+        let accounts = main["accounts"].as_table().unwrap();
+        assert!(accounts.contains_key("personal") && accounts.contains_key("work"));
+
+        let clash: toml::Table = toml::from_str("[accounts.work]\nemail = \"x@y.com\"").unwrap();
+        let error = merge_included(&mut main, clash, "").unwrap_err();
+        assert_eq!(error.to_string(), "accounts.work.email is already set");
+    }
+
+    #[test]
+    fn test_parse_config_file_with_includes() {
+        let dir = std::env::temp_dir().join(format!("zeroterm-include-{}", std::process::id()));
+        fs::create_dir_all(dir.join("accounts.d")).unwrap();
+        fs::write(
+            dir.join("config.toml"),
+            "include = [\"accounts.d/*.toml\"]\nfetch_limit = 100\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("accounts.d/personal.toml"),
+            "[accounts.personal]\nbackend = \"gmail\"\nemail = \"me@gmail.com\"\napp_password = \"x\"\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("accounts.d/work.toml"),
+            "[accounts.work]\nbackend = \"gmail\"\nemail = \"me@work.com\"\napp_password = \"y\"\n",
+        )
+        .unwrap();
+        let config = parse_config_file(&dir.join("config.toml"));
+
+        // A file named without wildcards has to exist
+        fs::write(dir.join("config.toml"), "include = [\"missing.toml\"]\n").unwrap();
+        let missing = parse_config_file(&dir.join("config.toml"));
+        let _ = fs::remove_dir_all(&dir);
+
+        let config = config.unwrap();
+        assert_eq!(config.fetch_limit, Some(100));
+        assert_eq!(config.accounts.len(), 2);
+        assert_eq!(config.accounts["work"].email, "me@work.com");
+        assert!(
+            missing
+                .unwrap_err()
+                .to_string()
+                .contains("missing.toml not found")
+        );
+    }
+
+    #[test]
+    fn test_unknown_key_suggests_the_nearest() {
+        let error = toml::from_str::<Config>("paralel_connections = 3\n[accounts]\n")
+            .map_err(suggest_key)
+            .unwrap_err();
+        assert!(
+            error.to_string().ends_with(
+                "Unknown key 'paralel_connections': did you mean 'parallel_connections'?"
+            )
+        );
+
+        let error = toml::from_str::<Config>(
+            "[accounts.work]\nbackend = \"gmail\"\nemail = \"a@b.com\"\napp_pasword = \"x\"\n",
+        )
+        .map_err(suggest_key)
+        .unwrap_err();
+        assert!(error.to_string().contains("did you mean 'app_password'?"));
+
+        // Nothing close enough: serde's own message lists the valid keys
+        assert_eq!(
+            nearest_key("unknown field `zzz`, expected `key` or `command`"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("debug", "debug"), 0);
+        assert_eq!(edit_distance("paralel", "parallel"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn test_key_conflicts() {
+        let toml_content = r#"
+[[plugin]]
+key = "9"
+description = "Snooze"
+command = "snooze"
+
+[[action]]
+key = "9"
+script = "archive();"
+
+[[action]]
+key = "a"
+description = "Archive newsletters"
+script = "archive();"
+
+[[action]]
+key = "X"
+script = "archive();"
+
+[accounts.personal]
+backend = "gmail"
+email = "user@gmail.com"
+app_password = "xxxx"
+"#;
+        let config: Config = toml::from_str(toml_content).unwrap();
+        assert_eq!(
+            key_conflicts(&config),
+            vec![
+                "[[action]] uses key '9', which [[plugin]] 'Snooze' already has".to_string(),
+                "[[action]] 'Archive newsletters' uses the built-in key 'a'".to_string(),
+            ]
+        );
+        let error = validate(&config).unwrap_err().to_string();
+        assert!(error.starts_with("Keys in config.toml that would never run:\n  [[action]]"));
+    }
 }
@@ -0,0 +1,512 @@
+//! `zeroterm daemon`: keeps an account's IMAP connection open and its inbox loaded in the
+//! background, so later `zeroterm` launches attach over a local socket and start without
+//! refetching.
+//!
+//! The socket carries the same commands and responses as the channel between the UI and
+//! the IMAP worker, one JSON object per line: `ImapCommand`s from the UI and
+//! `RecordedResponse`s (the serializable copy of `ImapResponse`) back.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+
+use anyhow::{Context, Result, bail};
+
+use crate::config::{self, AccountConfig, Config};
+use crate::email::Email;
+use crate::recording::RecordedResponse;
+use crate::{ImapCommand, ImapResponse};
+
+/// Where the daemon for an account listens: under $XDG_RUNTIME_DIR/zeroterm when there
+/// is one, otherwise in the config directory
+pub fn socket_path(account: &str) -> Result<PathBuf> {
+    let xdg_dirs = xdg::BaseDirectories::with_prefix("zeroterm")
+        .context("Failed to determine the runtime directory")?;
+    let dir = match xdg_dirs.get_runtime_directory() {
+        Ok(runtime) => runtime.join("zeroterm"),
+        Err(_) => config::config_dir()?.join("daemon"),
+    };
+    Ok(dir.join(format!("{}.sock", config::account_file_stem(account))))
+}
+
+/// Connects to a running daemon for the account, if there is one
+pub fn attach(account: &str) -> Option<UnixStream> {
+    UnixStream::connect(socket_path(account).ok()?).ok()
+}
+
+/// Writes one JSON object and a newline
+fn write_line<T: serde::Serialize>(stream: &mut impl Write, value: &T) -> Result<()> {
+    let mut line = serde_json::to_string(value).context("Failed to encode a daemon message")?;
+    line.push('\n');
+    stream.write_all(line.as_bytes())?;
+    stream.flush()?;
+    Ok(())
+}
+
+/// Connects the UI's worker channels to an attached daemon, as `spawn_imap_worker`
+/// would to a new connection
+pub fn spawn_client(
+    stream: UnixStream,
+    cmd_rx: mpsc::Receiver<ImapCommand>,
+    resp_tx: mpsc::Sender<ImapResponse>,
+) -> Result<()> {
+    let mut writer = stream
+        .try_clone()
+        .context("Failed to open the daemon socket")?;
+    thread::spawn(move || {
+        for command in cmd_rx {
+            // The daemon stays up for the next launch; hanging up is enough
+            if matches!(command, ImapCommand::Shutdown)
+                || write_line(&mut writer, &command).is_err()
+            {
+                break;
+            }
+        }
+        let _ = writer.shutdown(std::net::Shutdown::Both);
+    });
+    thread::spawn(move || {
+        for line in BufReader::new(stream).lines() {
+            let Ok(line) = line else { break };
+            match serde_json::from_str::<RecordedResponse>(&line) {
+                Ok(response) => {
+                    if resp_tx.send(response.into()).is_err() {
+                        return;
+                    }
+                }
+                Err(e) => crate::debug_log!("Daemon sent an unreadable message: {}", e),
+            }
+        }
+        let _ = resp_tx.send(ImapResponse::Error(
+            "Lost the connection to the zeroterm daemon".to_string(),
+        ));
+    });
+    Ok(())
+}
+
+/// What the daemon's main loop reacts to
+enum Event {
+    Worker(ImapResponse),
+    WorkerGone,
+    Attach(UnixStream),
+    Command(u64, ImapCommand),
+    Detach(u64),
+}
+
+/// The UI attached right now
+struct Client {
+    id: u64,
+    stream: UnixStream,
+}
+
+/// The fetch settings the cached inbox was loaded with
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct FetchSettings {
+    merge_subject_threads: bool,
+    since: Option<chrono::NaiveDate>,
+    limit: Option<u32>,
+}
+
+impl FetchSettings {
+    fn of(command: &ImapCommand) -> Option<Self> {
+        match command {
+            ImapCommand::FetchInbox {
+                merge_subject_threads,
+                since,
+                limit,
+                ..
+            } => Some(Self {
+                merge_subject_threads: *merge_subject_threads,
+                since: *since,
+                limit: *limit,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// The daemon's view of the mailbox and the attached UI
+struct State {
+    /// The fetch a fresh UI starts with; its result is what gets cached
+    startup_fetch: ImapCommand,
+    settings: FetchSettings,
+    connected: bool,
+    cache: Option<Vec<Email>>,
+    /// A fetch with the startup settings is running, so its result refreshes the cache
+    refreshing: bool,
+    /// The attached UI archived, deleted or restored mail, so the cache is out of date
+    dirty: bool,
+    client: Option<Client>,
+    next_client_id: u64,
+}
+
+impl State {
+    fn new(startup_fetch: ImapCommand) -> Self {
+        let settings = FetchSettings::of(&startup_fetch).expect("startup fetch is a FetchInbox");
+        Self {
+            startup_fetch,
+            settings,
+            connected: false,
+            cache: None,
+            refreshing: false,
+            dirty: false,
+            client: None,
+            next_client_id: 0,
+        }
+    }
+
+    /// Sends a response to the attached UI, dropping it if it has gone away
+    fn send(&mut self, response: &ImapResponse) {
+        if let Some(client) = &mut self.client
+            && write_line(&mut client.stream, &RecordedResponse::from(response)).is_err()
+        {
+            self.client = None;
+        }
+    }
+
+    /// Reloads the inbox in the background unless that is already happening
+    fn refresh(&mut self, cmd_tx: &mpsc::Sender<ImapCommand>) -> Result<()> {
+        if !self.refreshing {
+            self.refreshing = true;
+            self.dirty = false;
+            cmd_tx.send(clone_fetch(&self.startup_fetch))?;
+        }
+        Ok(())
+    }
+
+    /// Handles a command from the attached UI: answers a startup fetch from the cache
+    /// and passes everything else to the worker
+    fn command(&mut self, command: ImapCommand, cmd_tx: &mpsc::Sender<ImapCommand>) -> Result<()> {
+        let cacheable = FetchSettings::of(&command) == Some(self.settings);
+        match command {
+            ImapCommand::Shutdown => {}
+            ImapCommand::FetchInbox { .. } if cacheable && self.refreshing => {
+                // The running refresh answers this UI too
+            }
+            ImapCommand::FetchInbox { .. } if cacheable && self.cache.is_some() && !self.dirty => {
+                let emails = self.cache.clone().unwrap_or_default();
+                self.send(&ImapResponse::Emails(Ok(emails)));
+            }
+            command => {
+                if cacheable {
+                    self.refreshing = true;
+                    self.dirty = false;
+                }
+                if matches!(
+                    command,
+                    ImapCommand::ArchiveMultiple(_)
+                        | ImapCommand::DeleteMultiple(_)
+                        | ImapCommand::MoveMultiple { .. }
+                        | ImapCommand::RestoreEmails(_)
+                        | ImapCommand::SetFlags { .. }
+                        | ImapCommand::ModifyLabels { .. }
+                ) {
+                    self.dirty = true;
+                }
+                cmd_tx.send(command)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Handles a response from the worker, keeping the cache and passing it to the UI
+    fn response(
+        &mut self,
+        response: ImapResponse,
+        cmd_tx: &mpsc::Sender<ImapCommand>,
+    ) -> Result<()> {
+        match &response {
+            ImapResponse::Connected => {
+                self.connected = true;
+                println!("Connected. Loading the inbox...");
+                self.refresh(cmd_tx)?;
+            }
+            ImapResponse::Emails(Ok(emails)) if self.refreshing => {
+                self.refreshing = false;
+                println!("Inbox loaded: {} emails", emails.len());
+                self.cache = Some(emails.clone());
+            }
+            ImapResponse::Emails(Err(_)) | ImapResponse::FetchInterrupted { .. } => {
+                self.refreshing = false;
+                self.cache = None;
+            }
+            ImapResponse::MailboxChanged(_) if self.client.is_none() => {
+                self.refresh(cmd_tx)?;
+                return Ok(());
+            }
+            ImapResponse::Error(e) if self.client.is_none() => {
+                if !self.connected {
+                    bail!("{}", e);
+                }
+                eprintln!("{}", e);
+            }
+            _ => {}
+        }
+        self.send(&response);
+        Ok(())
+    }
+}
+
+/// FetchInbox has no Clone, like the rest of ImapCommand
+fn clone_fetch(command: &ImapCommand) -> ImapCommand {
+    match command {
+        ImapCommand::FetchInbox {
+            parallel_connections,
+            merge_subject_threads,
+            since,
+            limit,
+        } => ImapCommand::FetchInbox {
+            parallel_connections: *parallel_connections,
+            merge_subject_threads: *merge_subject_threads,
+            since: *since,
+            limit: *limit,
+        },
+        _ => unreachable!("only the startup fetch is cloned"),
+    }
+}
+
+/// Removes the socket file when the daemon stops
+struct SocketFile(PathBuf);
+
+impl Drop for SocketFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+/// Creates the socket, replacing one left behind by a daemon that didn't stop cleanly
+fn bind(path: &Path) -> Result<UnixListener> {
+    if UnixStream::connect(path).is_ok() {
+        bail!("A zeroterm daemon is already running on {}", path.display());
+    }
+    if let Some(dir) = path.parent() {
+        config::create_private_dir(dir)?;
+    }
+    let _ = fs::remove_file(path);
+    let listener = UnixListener::bind(path)
+        .with_context(|| format!("Failed to listen on {}", path.display()))?;
+    // Only this user may attach; the socket hands out mail
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    Ok(listener)
+}
+
+/// Runs the daemon for an account in the foreground until it is killed or the
+/// connection to the server fails
+pub fn run(cfg: &Config, account_name: &str, account: &AccountConfig) -> Result<()> {
+    let path = socket_path(account_name)?;
+    let listener = bind(&path)?;
+    let _socket_file = SocketFile(path.clone());
+    println!(
+        "zeroterm daemon for {} listening on {}",
+        account_name,
+        path.display()
+    );
+
+    let (event_tx, event_rx) = mpsc::channel::<Event>();
+
+    let (cmd_tx, cmd_rx) = mpsc::channel::<ImapCommand>();
+    let (resp_tx, resp_rx) = mpsc::channel::<ImapResponse>();
+    crate::spawn_imap_worker(
+        cmd_rx,
+        resp_tx,
+        account.clone(),
+        cfg.mark_read_on_archive,
+        cfg.pgp_command.clone(),
+    );
+    let worker_events = event_tx.clone();
+    thread::spawn(move || {
+        for response in resp_rx {
+            if worker_events.send(Event::Worker(response)).is_err() {
+                return;
+            }
+        }
+        let _ = worker_events.send(Event::WorkerGone);
+    });
+
+    let accept_events = event_tx.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            if accept_events.send(Event::Attach(stream)).is_err() {
+                return;
+            }
+        }
+    });
+
+    // The same first fetch run_app sends, so a fresh UI is answered from the cache
+    let mut state = State::new(ImapCommand::FetchInbox {
+        parallel_connections: cfg.parallel_connections,
+        merge_subject_threads: cfg.merge_subject_threads,
+        since: account.fetch_window_start(chrono::Utc::now().date_naive())?,
+        limit: cfg.fetch_limit,
+    });
+    for event in event_rx {
+        match event {
+            Event::Worker(response) => state.response(response, &cmd_tx)?,
+            Event::WorkerGone => bail!("The connection to the server closed"),
+            Event::Attach(mut stream) => {
+                if state.client.is_some() {
+                    let busy = ImapResponse::Error(
+                        "Another zeroterm is already attached to the daemon".to_string(),
+                    );
+                    let _ = write_line(&mut stream, &RecordedResponse::from(&busy));
+                    continue;
+                }
+                state.next_client_id += 1;
+                let id = state.next_client_id;
+                let reader = stream.try_clone()?;
+                let client_events = event_tx.clone();
+                thread::spawn(move || {
+                    for line in BufReader::new(reader).lines() {
+                        let Ok(line) = line else { break };
+                        match serde_json::from_str::<ImapCommand>(&line) {
+                            Ok(command) => {
+                                if client_events.send(Event::Command(id, command)).is_err() {
+                                    return;
+                                }
+                            }
+                            Err(e) => eprintln!("Ignoring an unreadable command: {}", e),
+                        }
+                    }
+                    let _ = client_events.send(Event::Detach(id));
+                });
+                println!("zeroterm attached");
+                // Stop refusing the UIDs a UI before this one had cached under an old
+                // UIDVALIDITY; this UI checks its own
+                cmd_tx.send(ImapCommand::CheckUidValidity(HashMap::new()))?;
+                state.client = Some(Client { id, stream });
+                if state.connected {
+                    state.send(&ImapResponse::Connected);
+                }
+            }
+            Event::Command(id, command) => {
+                if state.client.as_ref().is_some_and(|c| c.id == id) {
+                    state.command(command, &cmd_tx)?;
+                }
+            }
+            Event::Detach(id) => {
+                if state.client.as_ref().is_some_and(|c| c.id == id) {
+                    println!("zeroterm detached");
+                    state.client = None;
+                    if state.dirty {
+                        state.refresh(&cmd_tx)?;
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::email::EmailBuilder;
+
+    fn fetch(limit: Option<u32>) -> ImapCommand {
+        ImapCommand::FetchInbox {
+            parallel_connections: 5,
+            merge_subject_threads: false,
+            since: None,
+            limit,
+        }
+    }
+
+    #[test]
+    fn test_startup_fetch_is_answered_from_the_cache() {
+        let (cmd_tx, cmd_rx) = mpsc::channel();
+        let mut state = State::new(fetch(None));
+
+        state.response(ImapResponse::Connected, &cmd_tx).unwrap();
+        assert!(matches!(
+            cmd_rx.try_recv(),
+            Ok(ImapCommand::FetchInbox { .. })
+        ));
+        let emails = vec![EmailBuilder::new().from("a@example.com").build()];
+        state
+            .response(ImapResponse::Emails(Ok(emails)), &cmd_tx)
+            .unwrap();
+        assert_eq!(state.cache.as_ref().map(Vec::len), Some(1));
+
+        // A UI starting with the same settings isn't sent to the server
+        state.command(fetch(None), &cmd_tx).unwrap();
+        assert!(cmd_rx.try_recv().is_err());
+
+        // Different settings are fetched for real
+        state.command(fetch(Some(100)), &cmd_tx).unwrap();
+        assert!(matches!(
+            cmd_rx.try_recv(),
+            Ok(ImapCommand::FetchInbox { .. })
+        ));
+    }
+
+    #[test]
+    fn test_changes_make_the_cache_stale() {
+        let (cmd_tx, cmd_rx) = mpsc::channel();
+        let mut state = State::new(fetch(None));
+        state.cache = Some(Vec::new());
+
+        state
+            .command(
+                ImapCommand::ArchiveMultiple(vec![("1".to_string(), "INBOX".to_string())]),
+                &cmd_tx,
+            )
+            .unwrap();
+        assert!(matches!(
+            cmd_rx.try_recv(),
+            Ok(ImapCommand::ArchiveMultiple(_))
+        ));
+        assert!(state.dirty);
+
+        // A refresh (when the UI detaches) brings it up to date
+        state.refresh(&cmd_tx).unwrap();
+        assert!(state.refreshing && !state.dirty);
+        assert!(matches!(
+            cmd_rx.try_recv(),
+            Ok(ImapCommand::FetchInbox { .. })
+        ));
+
+        // Other clients' changes are fetched while nobody is attached
+        state.refreshing = false;
+        state
+            .response(
+                ImapResponse::MailboxChanged(vec!["INBOX".to_string()]),
+                &cmd_tx,
+            )
+            .unwrap();
+        assert!(matches!(
+            cmd_rx.try_recv(),
+            Ok(ImapCommand::FetchInbox { .. })
+        ));
+    }
+
+    #[test]
+    fn test_client_speaks_json_lines() {
+        let (ui_end, mut daemon_end) = UnixStream::pair().unwrap();
+        let (cmd_tx, cmd_rx) = mpsc::channel();
+        let (resp_tx, resp_rx) = mpsc::channel();
+        spawn_client(ui_end, cmd_rx, resp_tx).unwrap();
+
+        cmd_tx.send(fetch(Some(50))).unwrap();
+        let mut line = String::new();
+        BufReader::new(daemon_end.try_clone().unwrap())
+            .read_line(&mut line)
+            .unwrap();
+        let command: ImapCommand = serde_json::from_str(&line).unwrap();
+        assert_eq!(FetchSettings::of(&command).and_then(|s| s.limit), Some(50));
+
+        write_line(
+            &mut daemon_end,
+            &RecordedResponse::from(&ImapResponse::Connected),
+        )
+        .unwrap();
+        assert!(matches!(resp_rx.recv(), Ok(ImapResponse::Connected)));
+
+        // The UI hears about it when the daemon goes away
+        drop(daemon_end);
+        assert!(matches!(resp_rx.recv(), Ok(ImapResponse::Error(_))));
+    }
+}
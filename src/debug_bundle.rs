@@ -0,0 +1,308 @@
+//! `zeroterm debug-bundle`: gathers the debug log, the config, version details and an
+//! optional session recording into one archive to attach to bug reports. Passwords,
+//! tokens, addresses, sender lists and mail contents are left out.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use anyhow::{Context, Result, bail};
+use regex::Regex;
+
+use crate::compose;
+use crate::config::{self, Config};
+use crate::recording::{self, RecordedEvent};
+
+/// Stands in for anything removed from the bundle
+const REDACTED: &str = "<redacted>";
+
+/// Only the end of a long debug log is kept
+const MAX_LOG_LINES: usize = 5000;
+
+/// Only the last events of a long recording are kept
+const MAX_TRACE_EVENTS: usize = 1000;
+
+/// External programs zeroterm can shell out to
+const TOOLS: [&str; 4] = ["curl", "gpg", "lp", "tar"];
+
+/// Replaces every email address in `text`
+fn redact_addresses(text: &str) -> String {
+    let re = Regex::new(r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}").unwrap();
+    re.replace_all(text, "<email>").into_owned()
+}
+
+/// String settings kept in the bundle: enums that say which code path ran
+const KEPT_STRINGS: [&str; 2] = ["backend", "security"];
+
+/// Replaces every string except the allowed enums, at any depth; numbers and booleans
+/// are kept
+fn redact_value(key: &str, value: &mut toml::Value) {
+    match value {
+        toml::Value::String(text) if !KEPT_STRINGS.contains(&key) => {
+            *text = REDACTED.to_string();
+        }
+        toml::Value::Array(items) => items.iter_mut().for_each(|item| redact_value(key, item)),
+        toml::Value::Table(table) => table
+            .iter_mut()
+            .for_each(|(key, item)| redact_value(key, item)),
+        _ => {}
+    }
+}
+
+/// Strips the config down to its numbers, booleans and the backend and security
+/// enums; every other string is redacted, whatever setting it belongs to. Accounts are
+/// renamed account-1, account-2, ... in name order.
+fn redact_config(mut config: Config) -> Result<toml::Value> {
+    let mut names: Vec<String> = config.accounts.keys().cloned().collect();
+    names.sort();
+    let mut accounts = HashMap::new();
+    for (i, name) in names.iter().enumerate() {
+        if let Some(account) = config.accounts.remove(name) {
+            accounts.insert(format!("account-{}", i + 1), account);
+        }
+    }
+    config.accounts = accounts;
+
+    let mut value = toml::Value::try_from(&config).context("Failed to encode the config")?;
+    redact_value("", &mut value);
+    Ok(value)
+}
+
+/// The last `max` lines of `text`
+fn tail(text: &str, max: usize) -> String {
+    let lines: Vec<&str> = text.lines().collect();
+    let start = lines.len().saturating_sub(max);
+    let mut out = lines[start..].join("\n");
+    out.push('\n');
+    out
+}
+
+/// Describes a recorded response without its contents: the variant, and the number of
+/// items or the (address-free) error it carried
+fn describe_response(value: &serde_json::Value) -> String {
+    let (name, inner) = match value {
+        serde_json::Value::String(name) => return name.clone(),
+        serde_json::Value::Object(map) => match map.iter().next() {
+            Some((name, inner)) => (name, inner),
+            None => return "?".to_string(),
+        },
+        _ => return "?".to_string(),
+    };
+    let inner = match inner.get("Ok") {
+        Some(ok) => ok,
+        None => inner,
+    };
+    match inner {
+        serde_json::Value::Array(items) => format!("{} {}", name, items.len()),
+        serde_json::Value::Object(map) if map.contains_key("Err") => {
+            let error = map["Err"].as_str().unwrap_or_default();
+            format!("{} error: {}", name, redact_addresses(error))
+        }
+        serde_json::Value::Object(map) => match map.get("error").and_then(|e| e.as_str()) {
+            Some(error) => format!("{} error: {}", name, redact_addresses(error)),
+            None => name.clone(),
+        },
+        serde_json::Value::Number(n) => format!("{} {}", name, n),
+        _ => name.clone(),
+    }
+}
+
+/// One line per recorded event: `>` for commands, `<` for responses
+fn trace(events: &[RecordedEvent]) -> String {
+    let start = events.len().saturating_sub(MAX_TRACE_EVENTS);
+    let mut out = String::new();
+    for event in &events[start..] {
+        if let Some(command) = &event.command {
+            let _ = writeln!(out, "[{:>9}ms] > {}", event.at_ms, command);
+        }
+        if let Some(response) = &event.response {
+            let description = serde_json::to_value(response)
+                .map(|value| describe_response(&value))
+                .unwrap_or_else(|_| "?".to_string());
+            let _ = writeln!(out, "[{:>9}ms] < {}", event.at_ms, description);
+        }
+    }
+    out
+}
+
+/// Version, platform, accounts and available tools
+fn info(config: Option<&Config>) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "zeroterm {}", env!("CARGO_PKG_VERSION"));
+    let _ = writeln!(
+        out,
+        "platform: {} {}",
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    );
+    for var in ["TERM", "COLORTERM", "TERM_PROGRAM"] {
+        if let Ok(value) = std::env::var(var) {
+            let _ = writeln!(out, "{}: {}", var, value);
+        }
+    }
+    if let Some(config) = config {
+        let mut accounts: Vec<_> = config.accounts.iter().collect();
+        accounts.sort_by(|a, b| a.0.cmp(b.0));
+        for (i, (_, account)) in accounts.iter().enumerate() {
+            let _ = writeln!(
+                out,
+                "account-{}: {:?}, {:?} on port {}",
+                i + 1,
+                account.backend,
+                account.security,
+                account.imap_port()
+            );
+        }
+    }
+    for tool in TOOLS {
+        let found = Command::new("sh")
+            .args(["-c", &format!("command -v {}", tool)])
+            .stdout(Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success());
+        let _ = writeln!(
+            out,
+            "{}: {}",
+            tool,
+            if found { "found" } else { "not found" }
+        );
+    }
+    out
+}
+
+/// Writes the bundle's files into `dir`
+fn write_files(dir: &Path, recording: Option<&Path>) -> Result<()> {
+    // A broken config is worth reporting too, so it doesn't stop the bundle
+    let config = config::read_config_file();
+    fs::write(dir.join("info.txt"), info(config.as_ref().ok()))?;
+    let config_text = match config {
+        Ok(config) => toml::to_string(&redact_config(config)?)?,
+        // Parse errors quote the offending line, which may hold a password
+        Err(_) => "# The config could not be read; see zeroterm config validate\n".to_string(),
+    };
+    fs::write(dir.join("config.toml"), redact_addresses(&config_text))?;
+
+    let log_path = config::config_dir()?.join("debug.log");
+    let log = match fs::read_to_string(&log_path) {
+        Ok(log) => tail(&log, MAX_LOG_LINES),
+        Err(_) => "No debug log. Run zeroterm with --debug to write one.\n".to_string(),
+    };
+    fs::write(dir.join("debug.log"), redact_addresses(&log))?;
+
+    if let Some(path) = recording {
+        let recording = recording::load(path)?;
+        fs::write(dir.join("trace.txt"), trace(&recording.event))?;
+    }
+    Ok(())
+}
+
+/// Builds zeroterm-debug-<time>.tar.gz in the current directory and returns its path
+pub fn run(recording: Option<&Path>) -> Result<PathBuf> {
+    let name = format!(
+        "zeroterm-debug-{}",
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    );
+    // Staged beside the drafts, since the bundle holds the log and config
+    let staging = compose::private_temp_dir()?.join(format!("bundle-{}", std::process::id()));
+    let dir = staging.join(&name);
+    fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+
+    let archive = std::env::current_dir()?.join(format!("{}.tar.gz", name));
+    let result = write_files(&dir, recording).and_then(|_| {
+        let output = Command::new("tar")
+            .arg("czf")
+            .arg(&archive)
+            .arg("-C")
+            .arg(&staging)
+            .arg(&name)
+            .output()
+            .context("Failed to run tar")?;
+        if !output.status.success() {
+            bail!(
+                "tar failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(())
+    });
+    let _ = fs::remove_dir_all(&staging);
+    result.map(|_| archive)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{AccountConfig, Backend};
+
+    #[test]
+    fn test_redact_addresses() {
+        assert_eq!(
+            redact_addresses("Fetched 3 from news@example.com for me+zt@mail.co.uk"),
+            "Fetched 3 from <email> for <email>"
+        );
+        assert_eq!(
+            redact_addresses("no addresses @ here"),
+            "no addresses @ here"
+        );
+    }
+
+    #[test]
+    fn test_redact_config_strips_secrets_and_senders() {
+        let mut config: Config = toml::from_str(
+            r#"
+            protected_senders = ["boss@work.com"]
+            webhook_url = "https://hooks.example.com/secret"
+            task_command = "todo add --token sk-live-1234"
+            fetch_page_size = 250
+
+            [accounts.personal]
+            backend = "gmail"
+            email = "me@gmail.com"
+            app_password = "abcd efgh"
+            "#,
+        )
+        .unwrap();
+        let account: AccountConfig = config.accounts["personal"].clone();
+        assert_eq!(account.backend, Backend::Gmail);
+        config.accounts.insert("work".to_string(), account);
+
+        let redacted = redact_config(config).unwrap();
+        let mut names: Vec<_> = redacted["accounts"].as_table().unwrap().keys().collect();
+        names.sort();
+        assert_eq!(names, vec!["account-1", "account-2"]);
+        assert_eq!(
+            redacted["accounts"]["account-1"]["backend"].as_str(),
+            Some("gmail")
+        );
+        assert_eq!(redacted["fetch_page_size"].as_integer(), Some(250));
+        let text = toml::to_string(&redacted).unwrap();
+        for secret in [
+            "me@gmail.com",
+            "abcd efgh",
+            "boss@work.com",
+            "personal",
+            "secret",
+            "sk-live-1234",
+        ] {
+            assert!(!text.contains(secret), "{} leaked", secret);
+        }
+    }
+
+    #[test]
+    fn test_describe_response_keeps_only_sizes_and_errors() {
+        let emails = serde_json::json!({"Emails": {"Ok": [{"subject": "Hi"}, {}]}});
+        assert_eq!(describe_response(&emails), "Emails 2");
+        let failed = serde_json::json!({"RestoreResult": {"Err": "No mail for a@b.com"}});
+        assert_eq!(
+            describe_response(&failed),
+            "RestoreResult error: No mail for <email>"
+        );
+        assert_eq!(
+            describe_response(&serde_json::json!({"AlreadyGone": 4})),
+            "AlreadyGone 4"
+        );
+        assert_eq!(describe_response(&serde_json::json!("NewMail")), "NewMail");
+    }
+}
@@ -1,6 +1,16 @@
-use chrono::{Duration, Utc};
+use std::fs;
+use std::path::Path;
 
-use crate::email::{Email, EmailBuilder, build_thread_ids};
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use mailparse::{MailAddr, MailHeaderMap};
+use serde::Deserialize;
+
+use crate::auth::AuthResults;
+use crate::email::{
+    Email, EmailBuilder, build_thread_ids, extract_body_text, parse_list_unsubscribe,
+};
+use crate::imap_client::{parse_message_id_list, parse_spam_score, sanitize_for_terminal};
 
 /// Creates a set of realistic demo emails for testing and screenshots
 pub fn create_demo_emails() -> Vec<Email> {
@@ -47,6 +57,8 @@ pub fn create_demo_emails() -> Vec<Email> {
             .snippet("Status changed to In Review. Alice assigned this issue to you for final review...")
             .date(now - Duration::hours(1))
             .message_id("<linear-1234@linear.app>")
+            .to(vec!["Alice <alice@example.com>".to_string()])
+            .cc(vec!["Demo User <demo@example.com>".to_string()])
             .source_folder("INBOX")
             .build(),
         EmailBuilder::new()
@@ -67,6 +79,7 @@ pub fn create_demo_emails() -> Vec<Email> {
             .snippet("That works! How about the new place on Market St? I heard they have great espresso...")
             .date(now - Duration::hours(3))
             .message_id("<alice-reply-2@example.com>")
+            .to(vec!["Demo User <demo@example.com>".to_string()])
             .in_reply_to("<demo-sent-1@example.com>")
             .references(vec![
                 "<alice-orig@example.com>".to_string(),
@@ -81,6 +94,7 @@ pub fn create_demo_emails() -> Vec<Email> {
             .snippet("Hey! It's been a while. Want to grab coffee tomorrow afternoon? I'm free after 2pm...")
             .date(yesterday - Duration::hours(2))
             .message_id("<alice-orig@example.com>")
+            .to(vec!["Demo User <demo@example.com>".to_string()])
             .source_folder("INBOX")
             .build(),
         // User's sent reply (will be in thread view)
@@ -91,6 +105,7 @@ pub fn create_demo_emails() -> Vec<Email> {
             .snippet("Sure! 3pm works for me. Any preference on location?")
             .date(yesterday)
             .message_id("<demo-sent-1@example.com>")
+            .to(vec!["Alice Chen <alice@example.com>".to_string()])
             .in_reply_to("<alice-orig@example.com>")
             .references(vec!["<alice-orig@example.com>".to_string()])
             .source_folder("[Gmail]/Sent Mail")
@@ -106,6 +121,16 @@ pub fn create_demo_emails() -> Vec<Email> {
             .message_id("<stripe-receipt-1@stripe.com>")
             .source_folder("INBOX")
             .build(),
+        // The same receipt delivered twice (shows up in the duplicates view)
+        EmailBuilder::new()
+            .id("demo_8_dup")
+            .from("Stripe <receipts@stripe.com>")
+            .subject("Your receipt from Acme Corp")
+            .snippet("Amount: $49.00. Thank you for your payment. Your subscription has been renewed...")
+            .date(two_days_ago)
+            .message_id("<stripe-receipt-1@stripe.com>")
+            .source_folder("INBOX")
+            .build(),
         EmailBuilder::new()
             .id("demo_9")
             .from("Stripe <receipts@stripe.com>")
@@ -204,6 +229,372 @@ pub fn create_demo_emails() -> Vec<Email> {
     emails
 }
 
+/// Senders for generated demo mailboxes: (display name, address, is a mailing list)
+const GENERATED_SENDERS: &[(&str, &str, bool)] = &[
+    ("GitHub", "notifications@github.com", true),
+    ("Linear", "notify@linear.app", true),
+    ("Stripe", "receipts@stripe.com", false),
+    ("Amazon", "shipment-tracking@amazon.com", false),
+    ("Figma", "no-reply@figma.com", true),
+    ("Slack", "feedback@slack.com", true),
+    ("Vercel", "notifications@vercel.com", true),
+    ("Rust Weekly", "newsletter@this-week-in-rust.org", true),
+    ("Hacker Newsletter", "kale@hackernewsletter.com", true),
+    ("Google Calendar", "calendar-notification@google.com", false),
+    ("Dropbox", "no-reply@dropbox.com", false),
+    ("LinkedIn", "messages-noreply@linkedin.com", true),
+    ("Alice Chen", "alice@example.com", false),
+    ("Bob Smith", "bob@company.com", false),
+    ("Charlie Davis", "charlie@company.com", false),
+    ("Dana Lee", "dana@company.com", false),
+    ("Evan Park", "evan@startup.io", false),
+    ("Fatima Khan", "fatima@university.edu", false),
+    ("Gabe Ortiz", "gabe@example.org", false),
+    ("Hana Sato", "hana@design.studio", false),
+];
+
+/// Subjects for generated demo mailboxes
+const GENERATED_SUBJECTS: &[&str] = &[
+    "Weekly digest",
+    "Your order has shipped",
+    "Payment receipt",
+    "New comment on your pull request",
+    "Invitation: Team sync",
+    "Quick question",
+    "Project update",
+    "Lunch on Friday?",
+    "Action required: verify your account",
+    "Release notes",
+    "Design review feedback",
+    "Monthly newsletter",
+];
+
+/// Snippets for generated demo mailboxes
+const GENERATED_SNIPPETS: &[&str] = &[
+    "Here's a summary of what happened while you were away...",
+    "Thanks for getting back to me so quickly. I had one more thought...",
+    "Your receipt is attached. If you have any questions, reply to this email...",
+    "We've made a few changes based on everyone's feedback...",
+    "Let me know if this time still works for you...",
+    "Check out what's new this week in the community...",
+];
+
+/// Small deterministic PRNG (SplitMix64) so generated mailboxes are reproducible
+struct DemoRng(u64);
+
+/// Simulated network conditions for demo mode operations
+pub struct DemoFaults {
+    /// Delay before each simulated operation step completes (milliseconds)
+    pub latency_ms: u64,
+    /// Percent chance (0-100) that an operation fails once its retries are used up
+    pub failure_rate: u8,
+    /// Retry attempts shown before each operation step completes
+    pub retries: u32,
+    rng: DemoRng,
+}
+
+impl DemoFaults {
+    /// Creates fault settings; failures are drawn from `seed` so runs are reproducible
+    pub fn new(latency_ms: u64, failure_rate: u8, retries: u32, seed: u64) -> Self {
+        Self {
+            latency_ms,
+            failure_rate: failure_rate.min(100),
+            retries,
+            rng: DemoRng(seed),
+        }
+    }
+
+    /// Decides whether the next operation fails
+    pub fn roll_failure(&mut self) -> bool {
+        self.failure_rate > 0 && self.rng.chance(usize::from(self.failure_rate))
+    }
+}
+
+impl Default for DemoFaults {
+    fn default() -> Self {
+        Self::new(300, 0, 0, 0)
+    }
+}
+
+impl DemoRng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a value in `0..n`
+    fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// Returns true with the given percent chance
+    fn chance(&mut self, percent: usize) -> bool {
+        self.below(100) < percent
+    }
+}
+
+/// Generates `size` demo emails from `seed`. The same size and seed always produce
+/// the same mailbox (dates are relative to now), so large inboxes can be reproduced.
+pub fn generate_demo_emails(size: usize, seed: u64) -> Vec<Email> {
+    let now = Utc::now();
+    let mut rng = DemoRng(seed);
+    let mut emails: Vec<Email> = Vec::with_capacity(size);
+
+    for i in 0..size {
+        // Squaring skews the distribution so a few senders dominate, like a real inbox
+        let sender_roll = rng.below(GENERATED_SENDERS.len());
+        let sender_index = sender_roll * sender_roll / GENERATED_SENDERS.len();
+        let (name, address, mailing_list) = GENERATED_SENDERS[sender_index];
+        let age = Duration::minutes(rng.below(90 * 24 * 60) as i64);
+        let snippet = GENERATED_SNIPPETS[rng.below(GENERATED_SNIPPETS.len())];
+        let domain = address.split('@').nth(1).unwrap_or("example.com");
+
+        let mut builder = EmailBuilder::new()
+            .id(format!("demo_gen_{}", i))
+            .from(format!("{} <{}>", name, address))
+            .snippet(snippet)
+            .date(now - age)
+            .message_id(format!("<gen-{}-{}@{}>", seed, i, domain))
+            .to(vec!["Demo User <demo@example.com>".to_string()])
+            .seen(rng.chance(60))
+            .mailing_list(mailing_list)
+            .unsubscribe(if mailing_list {
+                vec![format!("https://{}/unsubscribe", domain)]
+            } else {
+                Vec::new()
+            })
+            .source_folder("INBOX");
+
+        // Personal senders sometimes reply to an earlier message, forming threads
+        let parent = if !mailing_list && !emails.is_empty() && rng.chance(25) {
+            emails.get(rng.below(emails.len()))
+        } else {
+            None
+        };
+        builder = match parent.and_then(|p| p.message_id.clone().map(|id| (p, id))) {
+            Some((parent, parent_id)) => {
+                let subject = parent.subject.trim_start_matches("Re: ");
+                let mut references = parent.references.clone();
+                references.push(parent_id.clone());
+                builder
+                    .subject(format!("Re: {}", subject))
+                    .in_reply_to(parent_id)
+                    .references(references)
+            }
+            None => builder.subject(GENERATED_SUBJECTS[rng.below(GENERATED_SUBJECTS.len())]),
+        };
+
+        emails.push(builder.build());
+    }
+
+    build_thread_ids(&mut emails);
+
+    emails
+}
+
+/// A `--demo-data` fixture: a list of `[[email]]` tables in TOML, or an `email` array in
+/// JSON
+#[derive(Debug, Deserialize)]
+struct DemoFixture {
+    #[serde(default)]
+    email: Vec<FixtureEmail>,
+}
+
+/// One email in a fixture. Only `from` and `date` are required.
+#[derive(Debug, Deserialize)]
+struct FixtureEmail {
+    id: Option<String>,
+    from: String,
+    #[serde(default)]
+    subject: String,
+    #[serde(default)]
+    snippet: String,
+    date: DateTime<Utc>,
+    message_id: Option<String>,
+    in_reply_to: Option<String>,
+    #[serde(default)]
+    references: Vec<String>,
+    folder: Option<String>,
+    gmail_thread_id: Option<u64>,
+    #[serde(default)]
+    to: Vec<String>,
+    #[serde(default)]
+    cc: Vec<String>,
+    #[serde(default)]
+    flagged: bool,
+    #[serde(default)]
+    seen: bool,
+    #[serde(default)]
+    mailing_list: bool,
+}
+
+/// Loads a demo dataset from a `.toml` or `.json` fixture or an `.mbox` file, so a
+/// grouping or threading problem can be reproduced from a shared, anonymized mailbox
+pub fn load_demo_data(path: &Path) -> Result<Vec<Email>> {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+    let bytes = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+
+    let mut emails = match extension.as_str() {
+        "toml" => parse_toml_fixture(&String::from_utf8_lossy(&bytes))
+            .with_context(|| format!("Failed to parse {}", path.display()))?,
+        "json" => parse_json_fixture(&bytes)
+            .with_context(|| format!("Failed to parse {}", path.display()))?,
+        "mbox" => parse_mbox(&bytes)?,
+        _ => bail!(
+            "Unsupported demo data file {} (expected .toml, .json or .mbox)",
+            path.display()
+        ),
+    };
+
+    build_thread_ids(&mut emails);
+    Ok(emails)
+}
+
+/// Converts `[[email]]` tables into emails
+fn parse_toml_fixture(contents: &str) -> Result<Vec<Email>> {
+    Ok(fixture_emails(toml::from_str(contents)?))
+}
+
+/// Converts a JSON fixture's `email` array into emails
+fn parse_json_fixture(contents: &[u8]) -> Result<Vec<Email>> {
+    Ok(fixture_emails(serde_json::from_slice(contents)?))
+}
+
+/// Builds emails from a parsed fixture, numbering those without an id
+fn fixture_emails(fixture: DemoFixture) -> Vec<Email> {
+    fixture
+        .email
+        .into_iter()
+        .enumerate()
+        .map(|(i, e)| {
+            let mut builder = EmailBuilder::new()
+                .id(e.id.unwrap_or_else(|| format!("fixture_{}", i + 1)))
+                .from(e.from)
+                .subject(e.subject)
+                .snippet(e.snippet)
+                .date(e.date)
+                .references(e.references)
+                .source_folder(e.folder.unwrap_or_else(|| "INBOX".to_string()))
+                .to(e.to)
+                .cc(e.cc)
+                .flagged(e.flagged)
+                .seen(e.seen)
+                .mailing_list(e.mailing_list);
+            if let Some(message_id) = e.message_id {
+                builder = builder.message_id(message_id);
+            }
+            if let Some(in_reply_to) = e.in_reply_to {
+                builder = builder.in_reply_to(in_reply_to);
+            }
+            if let Some(gmail_thread_id) = e.gmail_thread_id {
+                builder = builder.gmail_thread_id(gmail_thread_id);
+            }
+            builder.build()
+        })
+        .collect()
+}
+
+/// Splits an mbox file into messages and parses each one's headers
+fn parse_mbox(bytes: &[u8]) -> Result<Vec<Email>> {
+    let mut messages: Vec<Vec<u8>> = Vec::new();
+    let mut previous_blank = true;
+
+    for line in bytes.split_inclusive(|&b| b == b'\n') {
+        // A "From " line after a blank line starts the next message
+        if previous_blank && line.starts_with(b"From ") {
+            messages.push(Vec::new());
+        } else if let Some(message) = messages.last_mut() {
+            // Undo mboxrd ">From " quoting
+            let unquoted = match line.iter().position(|&b| b != b'>') {
+                Some(pos) if pos > 0 && line[pos..].starts_with(b"From ") => &line[1..],
+                _ => line,
+            };
+            message.extend_from_slice(unquoted);
+        }
+        previous_blank = line.iter().all(|b| b.is_ascii_whitespace());
+    }
+
+    messages
+        .iter()
+        .enumerate()
+        .map(|(i, raw)| {
+            parse_mbox_message(i, raw).with_context(|| format!("Failed to parse message {}", i + 1))
+        })
+        .collect()
+}
+
+/// Builds an email from one raw mbox message
+fn parse_mbox_message(index: usize, raw: &[u8]) -> Result<Email> {
+    let parsed = mailparse::parse_mail(raw)?;
+    let headers = parsed.get_headers();
+    let header = |name: &str| headers.get_first_value(name).map(|v| v.trim().to_string());
+
+    let date = header("Date")
+        .and_then(|d| mailparse::dateparse(&d).ok())
+        .and_then(|ts| Utc.timestamp_opt(ts, 0).single())
+        .unwrap_or_else(Utc::now);
+    let snippet: String = extract_body_text(&parsed)
+        .map(|body| sanitize_for_terminal(&body))
+        .unwrap_or_default()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .take(120)
+        .collect();
+    let mailing_list = header("List-Id").is_some() || header("List-Unsubscribe").is_some();
+
+    let mut builder = EmailBuilder::new()
+        .id(format!("mbox_{}", index + 1))
+        .from(header("From").unwrap_or_else(|| "unknown".to_string()))
+        .subject(header("Subject").unwrap_or_default())
+        .snippet(snippet)
+        .date(date)
+        .references(header("References").map_or_else(Vec::new, |r| parse_message_id_list(&r)))
+        .to(header("To").map_or_else(Vec::new, |v| split_addresses(&v)))
+        .cc(header("Cc").map_or_else(Vec::new, |v| split_addresses(&v)))
+        .mailing_list(mailing_list)
+        .unsubscribe(
+            header("List-Unsubscribe").map_or_else(Vec::new, |v| parse_list_unsubscribe(&v)),
+        )
+        .auth(AuthResults::from_headers(&parsed.headers))
+        .spam_score(parse_spam_score(raw))
+        .source_folder("INBOX");
+    if let Some(message_id) = header("Message-ID") {
+        builder = builder.message_id(message_id);
+    }
+    if let Some(in_reply_to) = header("In-Reply-To") {
+        builder = builder.in_reply_to(in_reply_to);
+    }
+    Ok(builder.build())
+}
+
+/// Splits an address header into "Name <email>" entries
+fn split_addresses(value: &str) -> Vec<String> {
+    let format = |info: &mailparse::SingleInfo| match &info.display_name {
+        Some(name) => format!("{} <{}>", name, info.addr),
+        None => info.addr.clone(),
+    };
+
+    mailparse::addrparse(value)
+        .map(|list| {
+            list.iter()
+                .flat_map(|addr| match addr {
+                    MailAddr::Single(info) => vec![format(info)],
+                    MailAddr::Group(group) => group.addrs.iter().map(format).collect(),
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -252,6 +643,12 @@ mod tests {
         assert!(sent_count >= 2, "Should have at least 2 sent emails");
     }
 
+    #[test]
+    fn test_demo_emails_have_duplicates() {
+        let emails = create_demo_emails();
+        assert!(!crate::email::find_duplicates(&emails).is_empty());
+    }
+
     #[test]
     fn test_demo_emails_have_variety_of_senders() {
         let emails = create_demo_emails();
@@ -261,4 +658,144 @@ mod tests {
             "Should have at least 5 different domains"
         );
     }
+
+    #[test]
+    fn test_generate_demo_emails_size() {
+        assert_eq!(generate_demo_emails(0, 1).len(), 0);
+        assert_eq!(generate_demo_emails(1000, 1).len(), 1000);
+    }
+
+    #[test]
+    fn test_generate_demo_emails_is_reproducible() {
+        let summary = |emails: Vec<Email>| -> Vec<(String, String, String)> {
+            emails
+                .into_iter()
+                .map(|e| (e.from.to_string(), e.subject, e.thread_id))
+                .collect()
+        };
+        assert_eq!(
+            summary(generate_demo_emails(200, 42)),
+            summary(generate_demo_emails(200, 42))
+        );
+        assert_ne!(
+            summary(generate_demo_emails(200, 42)),
+            summary(generate_demo_emails(200, 43))
+        );
+    }
+
+    #[test]
+    fn test_generate_demo_emails_have_threads_and_unique_ids() {
+        let emails = generate_demo_emails(300, 7);
+        let ids: HashSet<_> = emails.iter().map(|e| &e.id).collect();
+        assert_eq!(ids.len(), emails.len());
+        let thread_ids: HashSet<_> = emails.iter().map(|e| &e.thread_id).collect();
+        assert!(thread_ids.len() < emails.len());
+    }
+
+    #[test]
+    fn test_parse_toml_fixture() {
+        let emails = parse_toml_fixture(
+            r#"
+            [[email]]
+            from = "Alice <alice@example.com>"
+            subject = "Hello"
+            date = "2024-01-02T03:04:05Z"
+            message_id = "<a@example.com>"
+
+            [[email]]
+            id = "reply"
+            from = "bob@example.com"
+            subject = "Re: Hello"
+            date = "2024-01-03T03:04:05Z"
+            in_reply_to = "<a@example.com>"
+            folder = "[Gmail]/Sent Mail"
+            seen = true
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(emails.len(), 2);
+        assert_eq!(emails[0].id, "fixture_1");
+        assert_eq!(emails[0].from_email, "alice@example.com");
+        assert_eq!(emails[1].id, "reply");
+        assert_eq!(emails[1].source_folder, "[Gmail]/Sent Mail");
+        assert!(emails[1].seen);
+        assert_eq!(emails[1].in_reply_to.as_deref(), Some("<a@example.com>"));
+    }
+
+    #[test]
+    fn test_parse_json_fixture() {
+        let emails = parse_json_fixture(
+            br#"{"email": [
+                {"from": "Alice <alice@example.com>", "date": "2024-01-02T03:04:05Z",
+                 "message_id": "<a@example.com>"},
+                {"id": "reply", "from": "bob@example.com", "date": "2024-01-03T03:04:05Z",
+                 "in_reply_to": "<a@example.com>", "flagged": true}
+            ]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(emails.len(), 2);
+        assert_eq!(emails[0].id, "fixture_1");
+        assert_eq!(emails[0].source_folder, "INBOX");
+        assert_eq!(emails[1].id, "reply");
+        assert!(emails[1].flagged);
+        assert!(parse_json_fixture(br#"{"email": [{"from": "a@b.com"}]}"#).is_err());
+    }
+
+    #[test]
+    fn test_parse_toml_fixture_requires_date() {
+        assert!(parse_toml_fixture("[[email]]\nfrom = \"a@b.com\"\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_mbox() {
+        let mbox = b"From alice@example.com Tue Jan  2 03:04:05 2024\n\
+From: Alice <alice@example.com>\n\
+To: Bob <bob@example.com>, carol@example.com\n\
+Subject: Hello\n\
+Date: Tue, 2 Jan 2024 03:04:05 +0000\n\
+Message-ID: <a@example.com>\n\
+List-Id: <news.example.com>\n\
+\n\
+First line\n\
+>From the archives\n\
+\n\
+From bob@example.com Wed Jan  3 03:04:05 2024\n\
+From: bob@example.com\n\
+Subject: Re: Hello\n\
+Date: Wed, 3 Jan 2024 03:04:05 +0000\n\
+In-Reply-To: <a@example.com>\n\
+References: <a@example.com>\n\
+\n\
+Reply body\n";
+
+        let mut emails = parse_mbox(mbox).unwrap();
+        build_thread_ids(&mut emails);
+
+        assert_eq!(emails.len(), 2);
+        assert_eq!(emails[0].subject, "Hello");
+        assert_eq!(emails[0].snippet, "First line From the archives");
+        assert_eq!(
+            emails[0].to,
+            vec!["Bob <bob@example.com>", "carol@example.com"]
+        );
+        assert!(emails[0].mailing_list);
+        assert!(!emails[1].mailing_list);
+        assert_eq!(emails[1].references, vec!["<a@example.com>"]);
+        assert_eq!(emails[0].thread_id, emails[1].thread_id);
+    }
+
+    #[test]
+    fn test_demo_faults_failure_rate() {
+        let mut never = DemoFaults::default();
+        assert!((0..100).all(|_| !never.roll_failure()));
+
+        let mut always = DemoFaults::new(0, 100, 0, 1);
+        assert!((0..100).all(|_| always.roll_failure()));
+
+        let mut sometimes = DemoFaults::new(0, 50, 0, 1);
+        let failures = (0..1000).filter(|_| sometimes.roll_failure()).count();
+        assert!((300..700).contains(&failures));
+    }
 }
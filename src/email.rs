@@ -1,15 +1,91 @@
+use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
+use mailparse::{DispositionType, MailHeaderMap, ParsedMail};
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::ops::Deref;
+use std::sync::Arc;
+
+use crate::auth::AuthResults;
+
+/// A cheaply clonable string for values repeated across many emails (senders, domains,
+/// folders). `intern_strings` makes equal values share a single allocation.
+#[derive(Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub struct SharedStr(Arc<str>);
+
+impl SharedStr {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Deref for SharedStr {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for SharedStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl fmt::Display for SharedStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&*self.0, f)
+    }
+}
+
+impl From<String> for SharedStr {
+    fn from(s: String) -> Self {
+        Self(s.into())
+    }
+}
+
+impl From<&str> for SharedStr {
+    fn from(s: &str) -> Self {
+        Self(s.into())
+    }
+}
+
+impl From<SharedStr> for String {
+    fn from(s: SharedStr) -> Self {
+        s.0.to_string()
+    }
+}
+
+impl PartialEq<str> for SharedStr {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+impl PartialEq<&str> for SharedStr {
+    fn eq(&self, other: &&str) -> bool {
+        &*self.0 == *other
+    }
+}
+
+impl PartialEq<String> for SharedStr {
+    fn eq(&self, other: &String) -> bool {
+        *self.0 == **other
+    }
+}
 
 /// Represents an email message
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Email {
     pub id: String,
     pub thread_id: String,
-    pub from: String,
-    pub from_email: String,
-    pub from_domain: String,
+    pub from: SharedStr,
+    pub from_email: SharedStr,
+    pub from_domain: SharedStr,
     pub subject: String,
     pub snippet: String,
     pub date: DateTime<Utc>,
@@ -20,9 +96,34 @@ pub struct Email {
     /// The References header (list of all Message-IDs in the conversation chain)
     pub references: Vec<String>,
     /// The IMAP folder this email came from ("INBOX" or "[Gmail]/Sent Mail")
-    pub source_folder: String,
+    pub source_folder: SharedStr,
     /// The email body content (lazy-loaded when viewed)
     pub body: Option<String>,
+    /// Gmail's X-GM-THRID conversation ID, when the server provides it. JMAP accounts
+    /// store a hash of the message's threadId here, which threads the same way.
+    pub gmail_thread_id: Option<u64>,
+    /// The To recipients ("Name <email>" or bare addresses)
+    pub to: Vec<String>,
+    /// The Cc recipients ("Name <email>" or bare addresses)
+    pub cc: Vec<String>,
+    /// Whether the email has the \Flagged (starred) flag
+    pub flagged: bool,
+    /// Whether the email has the \Seen (read) flag
+    pub seen: bool,
+    /// Whether the email came from a mailing list (List-Id or List-Unsubscribe header)
+    pub mailing_list: bool,
+    /// DKIM/SPF/DMARC results the receiving server recorded in the headers
+    #[serde(default)]
+    pub auth: AuthResults,
+    /// SpamAssassin or Rspamd score from the headers, when the server adds one
+    #[serde(default)]
+    pub spam_score: Option<f32>,
+    /// The user's Gmail labels on the email (X-GM-LABELS), without system labels
+    #[serde(default)]
+    pub labels: Vec<String>,
+    /// The http(s) and mailto links from the List-Unsubscribe header, in header order
+    #[serde(default)]
+    pub unsubscribe: Vec<String>,
 }
 
 /// Builder for creating Email instances
@@ -37,6 +138,16 @@ pub struct EmailBuilder {
     in_reply_to: Option<String>,
     references: Vec<String>,
     source_folder: String,
+    gmail_thread_id: Option<u64>,
+    to: Vec<String>,
+    cc: Vec<String>,
+    flagged: bool,
+    seen: bool,
+    mailing_list: bool,
+    auth: AuthResults,
+    spam_score: Option<f32>,
+    labels: Vec<String>,
+    unsubscribe: Vec<String>,
 }
 
 impl EmailBuilder {
@@ -89,6 +200,56 @@ impl EmailBuilder {
         self
     }
 
+    pub fn gmail_thread_id(mut self, gmail_thread_id: u64) -> Self {
+        self.gmail_thread_id = Some(gmail_thread_id);
+        self
+    }
+
+    pub fn to(mut self, to: Vec<String>) -> Self {
+        self.to = to;
+        self
+    }
+
+    pub fn cc(mut self, cc: Vec<String>) -> Self {
+        self.cc = cc;
+        self
+    }
+
+    pub fn flagged(mut self, flagged: bool) -> Self {
+        self.flagged = flagged;
+        self
+    }
+
+    pub fn seen(mut self, seen: bool) -> Self {
+        self.seen = seen;
+        self
+    }
+
+    pub fn mailing_list(mut self, mailing_list: bool) -> Self {
+        self.mailing_list = mailing_list;
+        self
+    }
+
+    pub fn auth(mut self, auth: AuthResults) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    pub fn spam_score(mut self, spam_score: Option<f32>) -> Self {
+        self.spam_score = spam_score;
+        self
+    }
+
+    pub fn labels(mut self, labels: Vec<String>) -> Self {
+        self.labels = labels;
+        self
+    }
+
+    pub fn unsubscribe(mut self, unsubscribe: Vec<String>) -> Self {
+        self.unsubscribe = unsubscribe;
+        self
+    }
+
     pub fn build(self) -> Email {
         let from_email = extract_email(&self.from);
         let from_domain = extract_domain(&from_email);
@@ -96,9 +257,9 @@ impl EmailBuilder {
         Email {
             id: self.id,
             thread_id: String::new(), // Will be set by build_thread_ids
-            from: self.from,
-            from_email,
-            from_domain,
+            from: self.from.into(),
+            from_email: from_email.into(),
+            from_domain: from_domain.into(),
             subject: self.subject,
             snippet: self.snippet,
             date: self.date.unwrap_or_else(Utc::now),
@@ -106,11 +267,21 @@ impl EmailBuilder {
             in_reply_to: self.in_reply_to,
             references: self.references,
             source_folder: if self.source_folder.is_empty() {
-                "INBOX".to_string()
+                "INBOX".into()
             } else {
-                self.source_folder
+                self.source_folder.into()
             },
             body: None,
+            gmail_thread_id: self.gmail_thread_id,
+            to: self.to,
+            cc: self.cc,
+            flagged: self.flagged,
+            seen: self.seen,
+            mailing_list: self.mailing_list,
+            auth: self.auth,
+            spam_score: self.spam_score,
+            labels: self.labels,
+            unsubscribe: self.unsubscribe,
         }
     }
 }
@@ -135,15 +306,206 @@ pub fn extract_domain(email: &str) -> String {
     email.split('@').nth(1).unwrap_or(email).to_string()
 }
 
+/// Reads the links from a List-Unsubscribe header ("<https://...>, <mailto:...>"),
+/// keeping only http(s) and mailto ones
+pub fn parse_list_unsubscribe(value: &str) -> Vec<String> {
+    let re = Regex::new(r"<([^>]+)>").unwrap();
+    re.captures_iter(value)
+        .map(|captures| captures[1].split_whitespace().collect::<String>())
+        .filter(|link| {
+            let lower = link.to_lowercase();
+            ["https://", "http://", "mailto:"]
+                .iter()
+                .any(|scheme| lower.starts_with(scheme))
+        })
+        .collect()
+}
+
+/// Extracts the readable text of a message. mailparse decodes quoted-printable, base64,
+/// charsets and RFC 2047 headers; this walks the MIME tree on top of that: the text/plain
+/// side of multipart/alternative (text/html converted when there is none), every inline
+/// part of multipart/mixed, attached messages with their headers, and a line naming each
+/// attachment instead of its contents.
+pub fn extract_body_text(mail: &ParsedMail) -> Result<String> {
+    if mail.subparts.is_empty() {
+        let content_type = mail.ctype.mimetype.to_lowercase();
+        return match part_text(mail) {
+            Some(text) => Ok(text),
+            // Fail on a body that doesn't decode rather than showing nothing
+            None if content_type.starts_with("text/") => {
+                mail.get_body().context("Failed to get email body")
+            }
+            None => Ok(format!("[Content type: {}]", content_type)),
+        };
+    }
+    Ok(part_text(mail).unwrap_or_else(|| "[No text content found]".to_string()))
+}
+
+/// The text of one MIME part, or None when it has nothing to show
+fn part_text(part: &ParsedMail) -> Option<String> {
+    let content_type = part.ctype.mimetype.to_lowercase();
+    if let Some(name) = attachment_name(part) {
+        return Some(format!("[Attachment: {} ({})]", name, content_type));
+    }
+    match content_type.as_str() {
+        "multipart/alternative" => part
+            .subparts
+            .iter()
+            .find(|p| p.ctype.mimetype.eq_ignore_ascii_case("text/plain"))
+            .and_then(part_text)
+            .or_else(|| part.subparts.iter().rev().find_map(part_text)),
+        // The first part is the message; the rest are the images and styles it refers to
+        "multipart/related" => part.subparts.first().and_then(part_text),
+        t if t.starts_with("multipart/") => {
+            let texts: Vec<String> = part.subparts.iter().filter_map(part_text).collect();
+            (!texts.is_empty()).then(|| texts.join("\n\n"))
+        }
+        "text/plain" => part.get_body().ok(),
+        "text/html" => part.get_body().ok().map(|html| html_to_text(&html)),
+        "message/rfc822" => {
+            let raw = part.get_body_raw().ok()?;
+            let inner = mailparse::parse_mail(&raw).ok()?;
+            let headers: Vec<String> = ["From", "Date", "Subject", "To"]
+                .iter()
+                .filter_map(|name| {
+                    let value = inner.headers.get_first_value(name)?;
+                    Some(format!("{}: {}", name, value))
+                })
+                .collect();
+            Some(format!(
+                "---------- Attached message ----------\n{}\n\n{}",
+                headers.join("\n"),
+                part_text(&inner).unwrap_or_default()
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// The file name of a part sent as an attachment, or None for parts meant to be read
+/// inline (an inline image without a name isn't worth a line)
+fn attachment_name(part: &ParsedMail) -> Option<String> {
+    let disposition = part.get_content_disposition();
+    let name = disposition
+        .params
+        .get("filename")
+        .or_else(|| part.ctype.params.get("name"))
+        .cloned();
+    match disposition.disposition {
+        DispositionType::Attachment => Some(name.unwrap_or_else(|| "unnamed".to_string())),
+        _ if !part.ctype.mimetype.to_lowercase().starts_with("text/")
+            && !part.ctype.mimetype.to_lowercase().starts_with("multipart/")
+            && !part.ctype.mimetype.eq_ignore_ascii_case("message/rfc822") =>
+        {
+            name
+        }
+        _ => None,
+    }
+}
+
+/// The first HTML part meant to be read, for the structured HTML view. Attachments and
+/// attached messages are skipped.
+pub fn html_part(mail: &ParsedMail) -> Option<String> {
+    if attachment_name(mail).is_some() {
+        return None;
+    }
+    let content_type = mail.ctype.mimetype.to_lowercase();
+    match content_type.as_str() {
+        "text/html" => mail.get_body().ok(),
+        t if t.starts_with("multipart/") => mail.subparts.iter().find_map(html_part),
+        _ => None,
+    }
+}
+
+/// Converts HTML to plain text using html2text
+pub fn html_to_text(html: &str) -> String {
+    html2text::from_read(html.as_bytes(), 80)
+}
+
+/// Builds a Gmail web link that finds an email by Message-ID.
+/// The user's address in the path makes Gmail open the right account.
+pub fn gmail_link(message_id: &str, user_email: &str) -> String {
+    format!(
+        "https://mail.google.com/mail/u/{}/#search/rfc822msgid:{}",
+        user_email,
+        urlencoding::encode(message_id)
+    )
+}
+
+/// Builds a Gmail web link that opens a whole conversation: by Gmail's thread id when the
+/// server reported one, otherwise a search matching any of the thread's Message-IDs
+pub fn gmail_thread_link(
+    gmail_thread_id: Option<u64>,
+    message_ids: &[&str],
+    user_email: &str,
+) -> String {
+    if let Some(thread_id) = gmail_thread_id {
+        return format!(
+            "https://mail.google.com/mail/u/{}/#all/{:x}",
+            user_email, thread_id
+        );
+    }
+    let query = message_ids
+        .iter()
+        .map(|id| format!("rfc822msgid:{}", id))
+        .collect::<Vec<_>>()
+        .join(" OR ");
+    format!(
+        "https://mail.google.com/mail/u/{}/#search/{}",
+        user_email,
+        urlencoding::encode(&query)
+    )
+}
+
+/// Builds an Outlook on the web link that searches for an email by Message-ID.
+/// login_hint makes Outlook sign in to the right account.
+pub fn outlook_link(message_id: &str, user_email: &str) -> String {
+    format!(
+        "https://outlook.office.com/mail/deeplink/search?query={}&login_hint={}",
+        urlencoding::encode(message_id),
+        urlencoding::encode(user_email)
+    )
+}
+
+/// Builds a Fastmail link that searches for an email by Message-ID
+pub fn fastmail_link(message_id: &str) -> String {
+    format!(
+        "https://app.fastmail.com/mail/search:{}",
+        urlencoding::encode(&format!("msgid:{}", message_id))
+    )
+}
+
+/// Makes emails share one allocation per distinct sender, domain and folder,
+/// which matters with 100k+ emails where a few thousand senders repeat
+pub fn intern_strings(emails: &mut [Email]) {
+    let mut pool: HashSet<SharedStr> = HashSet::new();
+    let mut intern = |value: &mut SharedStr| match pool.get(value) {
+        Some(existing) => *value = existing.clone(),
+        None => {
+            pool.insert(value.clone());
+        }
+    };
+
+    for email in emails {
+        intern(&mut email.from);
+        intern(&mut email.from_email);
+        intern(&mut email.from_domain);
+        intern(&mut email.source_folder);
+    }
+}
+
 /// Removes duplicate emails by (id, source_folder) pair, keeping the first occurrence.
 /// This handles race conditions during parallel fetching where sequence numbers may shift.
 pub fn dedupe_emails(emails: &mut Vec<Email>) {
     let mut seen: HashSet<(String, String)> = HashSet::new();
-    emails.retain(|email| seen.insert((email.id.clone(), email.source_folder.clone())));
+    emails.retain(|email| seen.insert((email.id.clone(), email.source_folder.to_string())));
 }
 
 /// Builds thread IDs for a collection of emails using Message-ID, In-Reply-To, and References headers.
 /// Uses a union-find algorithm to group connected emails into threads.
+///
+/// Emails carrying a Gmail X-GM-THRID use it as the authoritative thread ID so
+/// threads match Gmail's own conversations; only the rest are threaded by headers.
 pub fn build_thread_ids(emails: &mut [Email]) {
     if emails.is_empty() {
         return;
@@ -152,6 +514,9 @@ pub fn build_thread_ids(emails: &mut [Email]) {
     // Map Message-ID to email index (for emails in the inbox)
     let mut msg_id_to_idx: HashMap<String, usize> = HashMap::new();
     for (i, email) in emails.iter().enumerate() {
+        if email.gmail_thread_id.is_some() {
+            continue;
+        }
         if let Some(ref msg_id) = email.message_id {
             msg_id_to_idx.insert(msg_id.clone(), i);
         }
@@ -160,6 +525,9 @@ pub fn build_thread_ids(emails: &mut [Email]) {
     // Track which emails reference each Message-ID (including missing emails)
     let mut reference_to_emails: HashMap<String, Vec<usize>> = HashMap::new();
     for (i, email) in emails.iter().enumerate() {
+        if email.gmail_thread_id.is_some() {
+            continue;
+        }
         // Track In-Reply-To references
         if let Some(ref reply_to) = email.in_reply_to {
             reference_to_emails
@@ -181,6 +549,9 @@ pub fn build_thread_ids(emails: &mut [Email]) {
 
     // Union emails that are connected via In-Reply-To or References to emails in inbox
     for (i, email) in emails.iter().enumerate() {
+        if email.gmail_thread_id.is_some() {
+            continue;
+        }
         // Check In-Reply-To
         if let Some(ref reply_to) = email.in_reply_to
             && let Some(&j) = msg_id_to_idx.get(reply_to)
@@ -208,8 +579,177 @@ pub fn build_thread_ids(emails: &mut [Email]) {
 
     // Assign thread IDs based on root of each component
     for (i, email) in emails.iter_mut().enumerate() {
-        let root = find(&parent, i);
-        email.thread_id = format!("thread_{}", root);
+        email.thread_id = match email.gmail_thread_id {
+            Some(thrid) => format!("gmail_{:x}", thrid),
+            None => format!("thread_{}", find(&parent, i)),
+        };
+    }
+}
+
+/// Normalizes a subject for thread matching: strips any number of leading
+/// Re:/Fwd:/Fw: prefixes, collapses whitespace and lowercases.
+pub fn normalize_subject(subject: &str) -> String {
+    let mut rest = subject.trim();
+    loop {
+        let lower = rest.to_lowercase();
+        let stripped = ["re:", "fwd:", "fw:"]
+            .iter()
+            .find(|prefix| lower.starts_with(*prefix))
+            .map(|prefix| rest[prefix.len()..].trim_start());
+        match stripped {
+            Some(s) => rest = s,
+            None => break,
+        }
+    }
+    rest.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Checks if an email's sender matches a pattern: a full address ("boss@work.com")
+/// or a domain ("work.com" or "@work.com"), case-insensitive
+pub fn sender_matches(email: &Email, pattern: &str) -> bool {
+    let pattern = pattern.trim().to_lowercase();
+    if pattern.contains('@') && !pattern.starts_with('@') {
+        email.from_email.to_lowercase() == pattern
+    } else {
+        email.from_domain.to_lowercase() == pattern.trim_start_matches('@')
+    }
+}
+
+/// Returns INBOX emails received before the cutoff, skipping flagged mail and
+/// senders matching any of the protected patterns
+pub fn stale_inbox_emails<'a>(
+    emails: impl IntoIterator<Item = &'a Email>,
+    cutoff: DateTime<Utc>,
+    protected_senders: &[String],
+) -> Vec<&'a Email> {
+    emails
+        .into_iter()
+        .filter(|e| e.source_folder == "INBOX" && e.date < cutoff && !e.flagged)
+        .filter(|e| !protected_senders.iter().any(|p| sender_matches(e, p)))
+        .collect()
+}
+
+/// Removes every thread containing mail from a sender matching any ignored pattern
+pub fn remove_ignored_threads(emails: &mut Vec<Email>, ignored_senders: &[String]) {
+    if ignored_senders.is_empty() {
+        return;
+    }
+    let ignored_threads: HashSet<String> = emails
+        .iter()
+        .filter(|e| ignored_senders.iter().any(|p| sender_matches(e, p)))
+        .map(|e| e.thread_id.clone())
+        .collect();
+    emails.retain(|e| !ignored_threads.contains(&e.thread_id));
+}
+
+/// A set of loaded emails that are copies of the same message
+#[derive(Debug, Clone)]
+pub struct DuplicateSet {
+    /// All copies, with the one to keep first
+    pub emails: Vec<Email>,
+}
+
+impl DuplicateSet {
+    /// The copy that is kept when the set is cleaned up
+    pub fn keep(&self) -> &Email {
+        &self.emails[0]
+    }
+
+    /// The redundant copies that can be archived or deleted
+    pub fn extras(&self) -> &[Email] {
+        &self.emails[1..]
+    }
+}
+
+/// Finds emails that are copies of the same message.
+///
+/// Copies match on Message-ID, or on sender + subject + date when there is no
+/// Message-ID. On Gmail (emails with an X-GM-THRID) folders are labels, so the same
+/// message seen in two folders is one message rather than two copies; those are
+/// only matched within a folder. Within a set, the INBOX copy with the lowest UID
+/// is kept. Sets are returned newest first.
+pub fn find_duplicates(emails: &[Email]) -> Vec<DuplicateSet> {
+    let mut by_key: HashMap<String, Vec<&Email>> = HashMap::new();
+    for email in emails {
+        let mut key = match &email.message_id {
+            Some(id) => format!("id:{}", id.to_lowercase()),
+            None => format!(
+                "hdr:{}\0{}\0{}",
+                email.from_email.to_lowercase(),
+                email.subject,
+                email.date.timestamp()
+            ),
+        };
+        if email.gmail_thread_id.is_some() {
+            key.push('\0');
+            key.push_str(&email.source_folder);
+        }
+        by_key.entry(key).or_default().push(email);
+    }
+
+    let mut sets: Vec<DuplicateSet> = by_key
+        .into_values()
+        .filter(|copies| copies.len() > 1)
+        .map(|mut copies| {
+            copies.sort_by_key(|e| {
+                (
+                    e.source_folder != "INBOX",
+                    e.id.parse::<u64>().unwrap_or(u64::MAX),
+                    e.id.clone(),
+                )
+            });
+            DuplicateSet {
+                emails: copies.into_iter().cloned().collect(),
+            }
+        })
+        .collect();
+
+    sets.sort_by_key(|set| std::cmp::Reverse(set.keep().date));
+    sets
+}
+
+/// Merges threads whose header-less emails share a normalized subject.
+///
+/// Only emails without In-Reply-To or References headers are matched, so replies
+/// stay attached to whatever thread their headers point at. Must run after
+/// `build_thread_ids`.
+pub fn merge_subject_threads(emails: &mut [Email]) {
+    // Union-find over thread IDs, so threads linked through different subjects end up
+    // in one thread, under the ID of the first one seen
+    let mut thread_index: HashMap<String, usize> = HashMap::new();
+    let mut thread_ids: Vec<String> = Vec::new();
+    let mut parent: Vec<usize> = Vec::new();
+    let mut subject_to_thread: HashMap<String, usize> = HashMap::new();
+
+    for email in emails.iter() {
+        if email.in_reply_to.is_some() || !email.references.is_empty() {
+            continue;
+        }
+        let subject = normalize_subject(&email.subject);
+        if subject.is_empty() {
+            continue;
+        }
+        let idx = *thread_index
+            .entry(email.thread_id.clone())
+            .or_insert_with(|| {
+                thread_ids.push(email.thread_id.clone());
+                parent.push(parent.len());
+                parent.len() - 1
+            });
+        let first = *subject_to_thread.entry(subject).or_insert(idx);
+        union(&mut parent, first, idx);
+    }
+
+    for email in emails.iter_mut() {
+        if let Some(&idx) = thread_index.get(&email.thread_id) {
+            let root = find(&parent, idx);
+            if root != idx {
+                email.thread_id = thread_ids[root].clone();
+            }
+        }
     }
 }
 
@@ -235,6 +775,39 @@ fn union(parent: &mut [usize], i: usize, j: usize) {
     }
 }
 
+impl Email {
+    /// The link U unsubscribes with: the web page when there is one, since it doesn't
+    /// depend on a mail app, otherwise the mailto address
+    pub fn unsubscribe_link(&self) -> Option<&str> {
+        let is_web = |link: &&String| link.to_lowercase().starts_with("http");
+        self.unsubscribe
+            .iter()
+            .find(is_web)
+            .or_else(|| self.unsubscribe.first())
+            .map(String::as_str)
+    }
+
+    /// Checks if the user received this email only as a Cc recipient
+    pub fn is_only_cc(&self, user_email: &str) -> bool {
+        let matches = |addr: &String| extract_email(addr).eq_ignore_ascii_case(user_email);
+        !self.to.iter().any(matches) && self.cc.iter().any(matches)
+    }
+
+    /// Short recipient summary for list columns: the first To address plus a count of the rest
+    pub fn recipients_summary(&self) -> String {
+        let mut recipients = self.to.iter().chain(self.cc.iter());
+        let Some(first) = recipients.next() else {
+            return String::new();
+        };
+        let others = recipients.count();
+        if others == 0 {
+            extract_email(first)
+        } else {
+            format!("{} +{}", extract_email(first), others)
+        }
+    }
+}
+
 #[cfg(test)]
 impl Email {
     /// Creates a new Email, automatically extracting email and domain from the from field
@@ -252,17 +825,27 @@ impl Email {
         Self {
             id,
             thread_id,
-            from,
-            from_email,
-            from_domain,
+            from: from.into(),
+            from_email: from_email.into(),
+            from_domain: from_domain.into(),
             subject,
             snippet,
             date,
             message_id: None,
             in_reply_to: None,
             references: Vec::new(),
-            source_folder: "INBOX".to_string(),
+            source_folder: "INBOX".into(),
             body: None,
+            gmail_thread_id: None,
+            to: Vec::new(),
+            cc: Vec::new(),
+            flagged: false,
+            seen: false,
+            mailing_list: false,
+            auth: AuthResults::default(),
+            spam_score: None,
+            labels: Vec::new(),
+            unsubscribe: Vec::new(),
         }
     }
 }
@@ -271,6 +854,244 @@ impl Email {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_extract_body_text_walks_mixed_and_alternative_parts() {
+        let raw = concat!(
+            "Subject: =?UTF-8?Q?Caf=C3=A9?=\r\n",
+            "Content-Type: multipart/mixed; boundary=outer\r\n\r\n",
+            "--outer\r\n",
+            "Content-Type: multipart/alternative; boundary=inner\r\n\r\n",
+            "--inner\r\n",
+            "Content-Type: text/plain; charset=utf-8\r\n",
+            "Content-Transfer-Encoding: quoted-printable\r\n\r\n",
+            "Caf=C3=A9 at noon?\r\n",
+            "--inner\r\n",
+            "Content-Type: text/html\r\n\r\n",
+            "<p>HTML version</p>\r\n",
+            "--inner--\r\n",
+            "--outer\r\n",
+            "Content-Type: text/csv; name=\"report.csv\"\r\n",
+            "Content-Disposition: attachment; filename=\"report.csv\"\r\n",
+            "Content-Transfer-Encoding: base64\r\n\r\n",
+            "YSxiLGMK\r\n",
+            "--outer\r\n",
+            "Content-Type: text/plain\r\n",
+            "Content-Transfer-Encoding: base64\r\n\r\n",
+            "LS0gbGlzdCBmb290ZXI=\r\n",
+            "--outer--\r\n",
+        );
+        let mail = mailparse::parse_mail(raw.as_bytes()).unwrap();
+        assert_eq!(mail.headers.get_first_value("Subject").unwrap(), "Café");
+
+        let text = extract_body_text(&mail).unwrap();
+        assert!(text.starts_with("Café at noon?"), "{}", text);
+        assert!(!text.contains("HTML version"));
+        assert!(text.contains("[Attachment: report.csv (text/csv)]"));
+        assert!(!text.contains("a,b,c"));
+        assert!(text.ends_with("-- list footer"));
+        assert_eq!(html_part(&mail).unwrap().trim(), "<p>HTML version</p>");
+    }
+
+    #[test]
+    fn test_extract_body_text_converts_html_and_opens_attached_messages() {
+        let raw = concat!(
+            "Content-Type: multipart/mixed; boundary=b\r\n\r\n",
+            "--b\r\n",
+            "Content-Type: multipart/alternative; boundary=a\r\n\r\n",
+            "--a\r\n",
+            "Content-Type: text/html\r\n\r\n",
+            "<p>See <b>below</b></p>\r\n",
+            "--a--\r\n",
+            "--b\r\n",
+            "Content-Type: message/rfc822\r\n\r\n",
+            "From: Alice <alice@example.com>\r\n",
+            "Subject: Original\r\n",
+            "Content-Type: text/plain\r\n\r\n",
+            "The original text\r\n",
+            "--b--\r\n",
+        );
+        let mail = mailparse::parse_mail(raw.as_bytes()).unwrap();
+        let text = extract_body_text(&mail).unwrap();
+        assert!(text.contains("See"));
+        assert!(!text.contains("<p>"));
+        assert!(text.contains("From: Alice <alice@example.com>\nSubject: Original"));
+        assert!(text.contains("The original text"));
+        assert_eq!(html_part(&mail).unwrap().trim(), "<p>See <b>below</b></p>");
+    }
+
+    #[test]
+    fn test_html_to_text_basic() {
+        let html = "<p>Hello <b>world</b></p>";
+        let text = html_to_text(html);
+        assert!(text.contains("Hello"));
+        assert!(text.contains("world"));
+    }
+
+    #[test]
+    fn test_sender_matches_address_and_domain() {
+        let email = EmailBuilder::new()
+            .id("1")
+            .from("Boss <Boss@Work.com>")
+            .build();
+        assert!(sender_matches(&email, "boss@work.com"));
+        assert!(sender_matches(&email, "work.com"));
+        assert!(sender_matches(&email, "@WORK.com"));
+        assert!(!sender_matches(&email, "other@work.com"));
+        assert!(!sender_matches(&email, "example.com"));
+    }
+
+    #[test]
+    fn test_stale_inbox_emails_excludes_recent_flagged_protected_and_sent() {
+        let now = Utc::now();
+        let old = now - chrono::Duration::days(400);
+        let email = |id: &str, from: &str| EmailBuilder::new().id(id).from(from).date(old);
+        let emails = vec![
+            email("1", "news@example.com").build(),
+            email("2", "news@example.com").flagged(true).build(),
+            email("3", "boss@work.com").build(),
+            email("4", "me@example.com")
+                .source_folder("[Gmail]/Sent Mail")
+                .build(),
+            EmailBuilder::new()
+                .id("5")
+                .from("news@example.com")
+                .date(now)
+                .build(),
+        ];
+
+        let cutoff = now - chrono::Duration::days(365);
+        let stale = stale_inbox_emails(&emails, cutoff, &["work.com".to_string()]);
+        let ids: Vec<&str> = stale.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, vec!["1"]);
+    }
+
+    #[test]
+    fn test_remove_ignored_threads_drops_whole_threads() {
+        let email = |id: &str, from: &str, thread: &str| {
+            let mut email = EmailBuilder::new().id(id).from(from).build();
+            email.thread_id = thread.to_string();
+            email
+        };
+        let mut emails = vec![
+            email("1", "boss@work.com", "t1"),
+            email("2", "me@example.com", "t1"),
+            email("3", "news@example.com", "t2"),
+            email("4", "partner@family.org", "t3"),
+        ];
+
+        remove_ignored_threads(
+            &mut emails,
+            &["boss@work.com".to_string(), "family.org".to_string()],
+        );
+        let ids: Vec<&str> = emails.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, vec!["3"]);
+    }
+
+    #[test]
+    fn test_find_duplicates_by_message_id_keeps_inbox_copy() {
+        let emails = vec![
+            EmailBuilder::new()
+                .id("9")
+                .from("a@example.com")
+                .message_id("<dup@example.com>")
+                .source_folder("Archive")
+                .build(),
+            EmailBuilder::new()
+                .id("5")
+                .from("a@example.com")
+                .message_id("<DUP@example.com>")
+                .build(),
+            EmailBuilder::new()
+                .id("3")
+                .from("a@example.com")
+                .message_id("<unique@example.com>")
+                .build(),
+        ];
+
+        let sets = find_duplicates(&emails);
+        assert_eq!(sets.len(), 1);
+        assert_eq!(sets[0].keep().id, "5");
+        assert_eq!(sets[0].extras().len(), 1);
+        assert_eq!(sets[0].extras()[0].id, "9");
+    }
+
+    #[test]
+    fn test_find_duplicates_without_message_id_matches_sender_subject_date() {
+        let date = Utc::now();
+        let email = |id: &str, subject: &str| {
+            EmailBuilder::new()
+                .id(id)
+                .from("Reports <reports@example.com>")
+                .subject(subject)
+                .date(date)
+                .build()
+        };
+        let emails = vec![
+            email("2", "Daily"),
+            email("1", "Daily"),
+            email("3", "Other"),
+        ];
+
+        let sets = find_duplicates(&emails);
+        assert_eq!(sets.len(), 1);
+        assert_eq!(sets[0].keep().id, "1");
+        assert_eq!(sets[0].extras()[0].id, "2");
+    }
+
+    #[test]
+    fn test_find_duplicates_ignores_gmail_labels_across_folders() {
+        let emails = vec![
+            EmailBuilder::new()
+                .id("1")
+                .message_id("<self@example.com>")
+                .gmail_thread_id(42)
+                .build(),
+            EmailBuilder::new()
+                .id("1")
+                .message_id("<self@example.com>")
+                .gmail_thread_id(42)
+                .source_folder("[Gmail]/Sent Mail")
+                .build(),
+        ];
+
+        assert!(find_duplicates(&emails).is_empty());
+    }
+
+    #[test]
+    fn test_is_only_cc() {
+        let email = EmailBuilder::new()
+            .id("1")
+            .from("sender@example.com")
+            .to(vec!["Team <team@example.com>".to_string()])
+            .cc(vec!["Me <Me@Example.com>".to_string()])
+            .build();
+        assert!(email.is_only_cc("me@example.com"));
+        assert!(!email.is_only_cc("team@example.com"));
+        assert!(!email.is_only_cc("other@example.com"));
+    }
+
+    #[test]
+    fn test_recipients_summary() {
+        let none = EmailBuilder::new().id("1").build();
+        assert_eq!(none.recipients_summary(), "");
+
+        let one = EmailBuilder::new()
+            .id("2")
+            .to(vec!["Alice <alice@example.com>".to_string()])
+            .build();
+        assert_eq!(one.recipients_summary(), "alice@example.com");
+
+        let many = EmailBuilder::new()
+            .id("3")
+            .to(vec![
+                "alice@example.com".to_string(),
+                "bob@example.com".to_string(),
+            ])
+            .cc(vec!["carol@example.com".to_string()])
+            .build();
+        assert_eq!(many.recipients_summary(), "alice@example.com +2");
+    }
+
     #[test]
     fn test_extract_email_with_name_and_brackets() {
         assert_eq!(
@@ -318,6 +1139,31 @@ mod tests {
         assert_eq!(extract_domain("invalid"), "invalid");
     }
 
+    #[test]
+    fn test_parse_list_unsubscribe_prefers_the_web_link() {
+        let links = parse_list_unsubscribe(
+            "<mailto:leave@lists.example.com?subject=unsubscribe>, <https://example.com/u?id=1>, <ftp://old>",
+        );
+        assert_eq!(
+            links,
+            vec![
+                "mailto:leave@lists.example.com?subject=unsubscribe",
+                "https://example.com/u?id=1"
+            ]
+        );
+        let email = EmailBuilder::new().unsubscribe(links).build();
+        assert_eq!(email.unsubscribe_link(), Some("https://example.com/u?id=1"));
+
+        let mailto_only = EmailBuilder::new()
+            .unsubscribe(parse_list_unsubscribe("<mailto:leave@example.com>"))
+            .build();
+        assert_eq!(
+            mailto_only.unsubscribe_link(),
+            Some("mailto:leave@example.com")
+        );
+        assert_eq!(EmailBuilder::new().build().unsubscribe_link(), None);
+    }
+
     #[test]
     fn test_email_struct_creation() {
         let date = Utc::now();
@@ -562,6 +1408,234 @@ mod tests {
         assert_eq!(emails[0].thread_id, emails[1].thread_id);
     }
 
+    #[test]
+    fn test_build_thread_ids_prefers_gmail_thread_id() {
+        let date = Utc::now();
+        let mut emails = vec![
+            // Same Gmail conversation without any reference headers
+            EmailBuilder::new()
+                .id("1")
+                .from("alice@example.com")
+                .subject("Report")
+                .date(date)
+                .gmail_thread_id(0x18c2a1f3b4d5e6f7)
+                .build(),
+            EmailBuilder::new()
+                .id("2")
+                .from("alice@example.com")
+                .subject("Report (updated)")
+                .date(date)
+                .gmail_thread_id(0x18c2a1f3b4d5e6f7)
+                .build(),
+            // References the first email but Gmail split it into its own conversation
+            EmailBuilder::new()
+                .id("3")
+                .from("bob@example.com")
+                .subject("Re: Report")
+                .date(date)
+                .message_id("<msg3@example.com>")
+                .in_reply_to("<msg1@example.com>")
+                .gmail_thread_id(0x18c2a1f3b4d5e6f8)
+                .build(),
+        ];
+
+        build_thread_ids(&mut emails);
+        assert_eq!(emails[0].thread_id, "gmail_18c2a1f3b4d5e6f7");
+        assert_eq!(emails[0].thread_id, emails[1].thread_id);
+        assert_ne!(emails[0].thread_id, emails[2].thread_id);
+    }
+
+    #[test]
+    fn test_build_thread_ids_falls_back_to_headers_without_gmail_thread_id() {
+        let date = Utc::now();
+        let mut emails = vec![
+            EmailBuilder::new()
+                .id("1")
+                .from("alice@example.com")
+                .subject("Subject")
+                .date(date)
+                .message_id("<msg1@example.com>")
+                .build(),
+            EmailBuilder::new()
+                .id("2")
+                .from("bob@example.com")
+                .subject("Re: Subject")
+                .date(date)
+                .in_reply_to("<msg1@example.com>")
+                .build(),
+        ];
+
+        build_thread_ids(&mut emails);
+        assert!(emails[0].thread_id.starts_with("thread_"));
+        assert_eq!(emails[0].thread_id, emails[1].thread_id);
+    }
+
+    #[test]
+    fn test_normalize_subject() {
+        assert_eq!(normalize_subject("Daily Report"), "daily report");
+        assert_eq!(normalize_subject("Re: Daily Report"), "daily report");
+        assert_eq!(
+            normalize_subject("RE: Fwd: re:  Daily   Report "),
+            "daily report"
+        );
+        assert_eq!(normalize_subject("FW: Daily Report"), "daily report");
+        assert_eq!(normalize_subject("Reply needed"), "reply needed");
+        assert_eq!(normalize_subject("Re:"), "");
+    }
+
+    #[test]
+    fn test_merge_subject_threads_merges_headerless_emails() {
+        let date = Utc::now();
+        let mut emails = vec![
+            EmailBuilder::new()
+                .id("1")
+                .from("reports@example.com")
+                .subject("Nightly build report")
+                .date(date)
+                .message_id("<r1@example.com>")
+                .build(),
+            EmailBuilder::new()
+                .id("2")
+                .from("reports@example.com")
+                .subject("Nightly  Build Report")
+                .date(date)
+                .message_id("<r2@example.com>")
+                .build(),
+            EmailBuilder::new()
+                .id("3")
+                .from("news@example.com")
+                .subject("Weekly newsletter")
+                .date(date)
+                .build(),
+        ];
+
+        build_thread_ids(&mut emails);
+        assert_ne!(emails[0].thread_id, emails[1].thread_id);
+
+        merge_subject_threads(&mut emails);
+        assert_eq!(emails[0].thread_id, emails[1].thread_id);
+        assert_ne!(emails[0].thread_id, emails[2].thread_id);
+    }
+
+    #[test]
+    fn test_merge_subject_threads_ignores_replies() {
+        let date = Utc::now();
+        let mut emails = vec![
+            EmailBuilder::new()
+                .id("1")
+                .from("alice@example.com")
+                .subject("Lunch")
+                .date(date)
+                .message_id("<a@example.com>")
+                .build(),
+            // Reply to an email that isn't loaded; keeps its own thread
+            EmailBuilder::new()
+                .id("2")
+                .from("bob@example.com")
+                .subject("Re: Lunch")
+                .date(date)
+                .in_reply_to("<other@example.com>")
+                .build(),
+        ];
+
+        build_thread_ids(&mut emails);
+        merge_subject_threads(&mut emails);
+        assert_ne!(emails[0].thread_id, emails[1].thread_id);
+    }
+
+    #[test]
+    fn test_merge_subject_threads_carries_whole_thread() {
+        let date = Utc::now();
+        let mut emails = vec![
+            EmailBuilder::new()
+                .id("1")
+                .from("ci@example.com")
+                .subject("Deploy finished")
+                .date(date)
+                .message_id("<d1@example.com>")
+                .build(),
+            EmailBuilder::new()
+                .id("2")
+                .from("ci@example.com")
+                .subject("Deploy finished")
+                .date(date)
+                .message_id("<d2@example.com>")
+                .build(),
+            EmailBuilder::new()
+                .id("3")
+                .from("dev@example.com")
+                .subject("Re: Deploy finished")
+                .date(date)
+                .in_reply_to("<d2@example.com>")
+                .build(),
+        ];
+
+        build_thread_ids(&mut emails);
+        merge_subject_threads(&mut emails);
+        assert_eq!(emails[0].thread_id, emails[1].thread_id);
+        assert_eq!(emails[1].thread_id, emails[2].thread_id);
+    }
+
+    #[test]
+    fn test_merge_subject_threads_follows_chains_across_subjects() {
+        let date = Utc::now();
+        let email = |id: &str, thread: &str, subject: &str| {
+            let mut email = EmailBuilder::new()
+                .id(id)
+                .from("reports@example.com")
+                .subject(subject)
+                .date(date)
+                .build();
+            email.thread_id = thread.to_string();
+            email
+        };
+        // T1 joins T0 through "Backup", and T2 joins T1 through "Usage"
+        let mut emails = vec![
+            email("1", "T0", "Backup"),
+            email("2", "T1", "Usage"),
+            email("3", "T1", "Backup"),
+            email("4", "T2", "Usage"),
+            email("5", "T3", "Unrelated"),
+        ];
+
+        merge_subject_threads(&mut emails);
+        let threads: Vec<&str> = emails.iter().map(|e| e.thread_id.as_str()).collect();
+        assert_eq!(threads, vec!["T0", "T0", "T0", "T0", "T3"]);
+    }
+
+    #[test]
+    fn test_intern_strings_shares_repeated_values() {
+        let mut emails = vec![
+            EmailBuilder::new().id("1").from("a@example.com").build(),
+            EmailBuilder::new().id("2").from("a@example.com").build(),
+            EmailBuilder::new().id("3").from("b@example.com").build(),
+        ];
+        assert!(!Arc::ptr_eq(
+            &emails[0].from_email.0,
+            &emails[1].from_email.0
+        ));
+
+        intern_strings(&mut emails);
+
+        assert!(Arc::ptr_eq(
+            &emails[0].from_email.0,
+            &emails[1].from_email.0
+        ));
+        assert!(Arc::ptr_eq(
+            &emails[0].from_domain.0,
+            &emails[2].from_domain.0
+        ));
+        assert!(Arc::ptr_eq(
+            &emails[0].source_folder.0,
+            &emails[2].source_folder.0
+        ));
+        assert!(!Arc::ptr_eq(
+            &emails[0].from_email.0,
+            &emails[2].from_email.0
+        ));
+        assert_eq!(emails[2].from_email, "b@example.com");
+    }
+
     #[test]
     fn test_dedupe_emails_removes_duplicates() {
         let date = Utc::now();
@@ -624,4 +1698,17 @@ mod tests {
         // Both should remain since they're from different folders
         assert_eq!(emails.len(), 2);
     }
+
+    #[test]
+    fn test_gmail_thread_link_prefers_the_thread_id() {
+        assert_eq!(
+            gmail_thread_link(Some(0x18c2f), &["<a@x.com>"], "me@gmail.com"),
+            "https://mail.google.com/mail/u/me@gmail.com/#all/18c2f"
+        );
+        assert_eq!(
+            gmail_thread_link(None, &["<a@x.com>", "<b@x.com>"], "me@gmail.com"),
+            "https://mail.google.com/mail/u/me@gmail.com/#search/\
+             rfc822msgid%3A%3Ca%40x.com%3E%20OR%20rfc822msgid%3A%3Cb%40x.com%3E"
+        );
+    }
 }
@@ -0,0 +1,730 @@
+//! Gmail REST API client for gmail-api accounts, over curl (see `http`). Lists come from
+//! messages.list and batched metadata requests, which carry Gmail's own thread IDs and
+//! labels without a connection per folder range. Archive, delete and restore are label
+//! changes of up to 1,000 emails per request.
+
+use anyhow::{Context, Result, bail};
+use chrono::NaiveDate;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use crate::capture::expand_home;
+use crate::config::{AccountConfig, Folders};
+use crate::email::{Email, extract_body_text, html_part};
+use crate::http;
+use crate::imap_client::{
+    ApiClient, EmailClient, FetchedBody, LIST_HEADERS, SNIPPET_CHARS, email_from_headers,
+    sanitize_for_terminal,
+};
+use crate::pgp;
+use crate::task::split_args;
+
+/// The signed-in user's mailbox
+const API_URL: &str = "https://gmail.googleapis.com/gmail/v1/users/me";
+/// Where batches of API requests are posted
+const BATCH_URL: &str = "https://www.googleapis.com/batch/gmail/v1";
+/// The request path of the mailbox, as batch parts name it
+const BATCH_PATH: &str = "/gmail/v1/users/me";
+const BATCH_BOUNDARY: &str = "zeroterm_batch";
+/// Requests per batch; Google rate limits larger ones
+const BATCH_SIZE: usize = 50;
+/// Message IDs per messages.list page, the API's maximum
+const PAGE_SIZE: usize = 500;
+/// Emails per batchModify, the API's maximum
+const MODIFY_SIZE: usize = 1000;
+/// How long an access token is used before asking for a new one; Google's last an hour
+const TOKEN_LIFETIME: Duration = Duration::from_secs(45 * 60);
+
+/// Gmail API client for one account
+pub struct GmailApiClient {
+    /// oauth_token_command, run again when the token is about to expire
+    token_command: String,
+    token: String,
+    token_fetched: Instant,
+    folders: Folders,
+    /// Label IDs by name for the user's own labels, filled in by list_folders
+    labels: HashMap<String, String>,
+    /// gpg command for decrypting and verifying PGP/MIME bodies
+    pgp_command: String,
+}
+
+/// Runs oauth_token_command and returns the access token it prints
+fn fetch_token(command: &str) -> Result<String> {
+    let parts = split_args(command);
+    let Some((program, args)) = parts.split_first() else {
+        bail!("oauth_token_command is empty");
+    };
+    let output = Command::new(expand_home(Path::new(program)))
+        .args(args)
+        .output()
+        .with_context(|| format!("Failed to run {}", program))?;
+    if !output.status.success() {
+        bail!(
+            "oauth_token_command exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    let token = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if token.is_empty() {
+        bail!("oauth_token_command printed no token");
+    }
+    Ok(token)
+}
+
+/// The Gmail label holding a folder's mail; archived mail is whatever has no INBOX label,
+/// so the archive folder has none
+fn folder_label(folder: &str, folders: Folders) -> Option<&'static str> {
+    if folder == "INBOX" {
+        Some("INBOX")
+    } else if folder == folders.sent {
+        Some("SENT")
+    } else if folder == folders.trash {
+        Some("TRASH")
+    } else {
+        None
+    }
+}
+
+/// Builds the search for mail received on or after `since` and before `before`
+fn date_query(since: Option<NaiveDate>, before: Option<NaiveDate>) -> String {
+    let mut terms = Vec::new();
+    if let Some(date) = since {
+        terms.push(format!("after:{}", date.format("%Y/%m/%d")));
+    }
+    if let Some(date) = before {
+        terms.push(format!("before:{}", date.format("%Y/%m/%d")));
+    }
+    terms.join(" ")
+}
+
+/// The request path for one email's list metadata
+fn metadata_path(id: &str) -> String {
+    let headers: String = LIST_HEADERS
+        .iter()
+        .map(|name| format!("&metadataHeaders={}", name))
+        .collect();
+    format!(
+        "{}/messages/{}?format=metadata&fields=id,threadId,labelIds,snippet,payload/headers{}",
+        BATCH_PATH, id, headers
+    )
+}
+
+/// Builds a multipart/mixed batch of GET requests, numbering each part's Content-ID
+fn batch_body(paths: &[String]) -> String {
+    let mut body = String::new();
+    for (i, path) in paths.iter().enumerate() {
+        body.push_str(&format!(
+            "--{}\r\nContent-Type: application/http\r\nContent-ID: <{}>\r\n\r\nGET {}\r\n\r\n",
+            BATCH_BOUNDARY, i, path
+        ));
+    }
+    body.push_str(&format!("--{}--\r\n", BATCH_BOUNDARY));
+    body
+}
+
+/// Splits a block at its first blank line into the head and the rest
+fn split_head(block: &str) -> (&str, &str) {
+    ["\r\n\r\n", "\n\n"]
+        .iter()
+        .filter_map(|blank| block.find(blank).map(|at| (at, blank.len())))
+        .min()
+        .map_or((block, ""), |(at, len)| (&block[..at], &block[at + len..]))
+}
+
+/// Parses a batch response into each request's JSON, in request order. An email that no
+/// longer exists is None; any other failed request fails the batch.
+fn parse_batch(response: &[u8], count: usize) -> Result<Vec<Option<Value>>> {
+    let text = String::from_utf8_lossy(response);
+    let delimiter = text
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .filter(|line| line.starts_with("--"))
+        .context("The Gmail API batch response has no parts")?;
+
+    let mut results = vec![None; count];
+    let mut answered = vec![false; count];
+    for part in text.split(delimiter).map(str::trim_start) {
+        if part.is_empty() || part.starts_with("--") {
+            continue;
+        }
+        let (headers, response) = split_head(part);
+        let index = headers
+            .lines()
+            .find_map(|line| {
+                let (name, value) = line.split_once(':')?;
+                name.trim().eq_ignore_ascii_case("Content-ID").then(|| {
+                    value
+                        .trim()
+                        .trim_matches(['<', '>'])
+                        .trim_start_matches("response-")
+                        .parse::<usize>()
+                        .ok()
+                })?
+            })
+            .filter(|&i| i < count)
+            .context("A Gmail API batch part has no Content-ID")?;
+        let (head, body) = split_head(response);
+        let status = head.lines().next().unwrap_or_default();
+        answered[index] = true;
+        match status.split_whitespace().nth(1) {
+            Some("200") => {
+                results[index] = Some(
+                    serde_json::from_str(body.trim())
+                        .context("Failed to parse a Gmail API response")?,
+                );
+            }
+            Some("404") => {}
+            _ => bail!("Gmail API request failed: {}", status),
+        }
+    }
+    if answered.contains(&false) {
+        bail!("The Gmail API batch response is missing parts");
+    }
+    Ok(results)
+}
+
+/// Decodes the API's unpadded URL-safe base64
+fn decode_base64url(data: &str) -> Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(data.len() * 3 / 4);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for c in data
+        .bytes()
+        .filter(|c| !c.is_ascii_whitespace() && *c != b'=')
+    {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'-' | b'+' => 62,
+            b'_' | b'/' => 63,
+            _ => bail!("Invalid base64 in the Gmail API response"),
+        };
+        buffer = (buffer << 6) | u32::from(value);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+        }
+    }
+    Ok(out)
+}
+
+/// Undoes the HTML escaping in Gmail's snippets
+fn decode_entities(text: &str) -> String {
+    text.replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+/// Parses a metadata response into our Email struct, rebuilding the raw header block so
+/// it goes through the same parsing as IMAP mail. `label_names` maps the user's label IDs
+/// to their names.
+fn parse_message(
+    object: &Value,
+    source_folder: &str,
+    label_names: &HashMap<&str, &str>,
+) -> Option<Email> {
+    let id = object["id"].as_str()?;
+    let mut header = String::new();
+    for field in object["payload"]["headers"]
+        .as_array()
+        .into_iter()
+        .flatten()
+    {
+        if let (Some(name), Some(value)) = (field["name"].as_str(), field["value"].as_str()) {
+            header.push_str(&format!("{}: {}\r\n", name, value));
+        }
+    }
+    header.push_str("\r\n");
+
+    let snippet: String = object["snippet"]
+        .as_str()
+        .map(|snippet| {
+            sanitize_for_terminal(&decode_entities(snippet))
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .unwrap_or_default()
+        .chars()
+        .take(SNIPPET_CHARS)
+        .collect();
+    let labels: Vec<&str> = object["labelIds"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .collect();
+
+    let mut builder = email_from_headers(
+        id.to_string(),
+        header.as_bytes(),
+        (!snippet.is_empty()).then_some(snippet),
+        source_folder,
+    )?
+    .seen(!labels.contains(&"UNREAD"))
+    .flagged(labels.contains(&"STARRED"));
+    let mut user_labels: Vec<String> = labels
+        .iter()
+        .filter_map(|id| label_names.get(id))
+        .map(|name| name.to_string())
+        .collect();
+    user_labels.sort();
+    builder = builder.labels(user_labels);
+    // The API's thread ID is X-GM-THRID in hex, so threads match IMAP's
+    if let Some(thread_id) = object["threadId"]
+        .as_str()
+        .and_then(|t| u64::from_str_radix(t, 16).ok())
+    {
+        builder = builder.gmail_thread_id(thread_id);
+    }
+    Some(builder.build())
+}
+
+impl GmailApiClient {
+    /// Gets a token from oauth_token_command and checks the API accepts it
+    pub fn connect(account: &AccountConfig, pgp_command: &str) -> Result<Self> {
+        let token_command = account
+            .oauth_token_command
+            .clone()
+            .context("gmail-api accounts need oauth_token_command")?;
+        let mut client = Self {
+            token: fetch_token(&token_command)?,
+            token_command,
+            token_fetched: Instant::now(),
+            folders: account.backend.folders(),
+            labels: HashMap::new(),
+            pgp_command: pgp_command.to_string(),
+        };
+        client.get(&format!("{}/profile", API_URL))?;
+        Ok(client)
+    }
+
+    /// Returns the access token, getting a new one when it is about to expire
+    fn token(&mut self) -> Result<&str> {
+        if self.token_fetched.elapsed() >= TOKEN_LIFETIME {
+            self.token = fetch_token(&self.token_command)?;
+            self.token_fetched = Instant::now();
+        }
+        Ok(&self.token)
+    }
+
+    /// Sends a request (a GET, or a POST of a content type and body) and returns the
+    /// raw response
+    fn send(&mut self, url: &str, body: Option<(&str, &str)>) -> Result<Vec<u8>> {
+        let config = http::bearer_config(url, self.token()?, body);
+        http::send(&config, "Gmail API")
+    }
+
+    fn get(&mut self, url: &str) -> Result<Value> {
+        serde_json::from_slice(&self.send(url, None)?)
+            .context("Failed to parse the Gmail API response")
+    }
+
+    /// Sends GET requests for `paths` in batches and returns their responses in order
+    fn batch(&mut self, paths: &[String]) -> Result<Vec<Option<Value>>> {
+        let content_type = format!("multipart/mixed; boundary={}", BATCH_BOUNDARY);
+        let mut results = Vec::with_capacity(paths.len());
+        for chunk in paths.chunks(BATCH_SIZE) {
+            let response = self.send(BATCH_URL, Some((&content_type, &batch_body(chunk))))?;
+            results.extend(parse_batch(&response, chunk.len())?);
+        }
+        Ok(results)
+    }
+
+    /// The label ID of a folder, or of one of the user's labels
+    fn label(&self, folder: &str) -> Option<String> {
+        folder_label(folder, self.folders)
+            .map(str::to_string)
+            .or_else(|| self.labels.get(folder).cloned())
+    }
+
+    /// Adds and removes labels on emails, one batchModify per 1,000
+    fn modify<S: serde::Serialize>(
+        &mut self,
+        ids: &[String],
+        add: &[S],
+        remove: &[S],
+    ) -> Result<()> {
+        let url = format!("{}/messages/batchModify", API_URL);
+        for chunk in ids.chunks(MODIFY_SIZE) {
+            let body = json!({
+                "ids": chunk,
+                "addLabelIds": add,
+                "removeLabelIds": remove,
+            })
+            .to_string();
+            self.send(&url, Some(("application/json", &body)))?;
+        }
+        Ok(())
+    }
+}
+
+impl ApiClient for GmailApiClient {
+    fn folders(&self) -> Folders {
+        self.folders
+    }
+
+    /// messages.list has no offset, so the newest `skip` IDs are listed and dropped.
+    /// The number left out is Google's estimate.
+    fn fetch_folder(
+        &mut self,
+        folder: &'static str,
+        since: Option<NaiveDate>,
+        before: Option<NaiveDate>,
+        skip: u32,
+        limit: Option<u32>,
+    ) -> Result<(Vec<Email>, usize)> {
+        let label = self
+            .label(folder)
+            .with_context(|| format!("No Gmail label for {}", folder))?;
+        let query = urlencoding::encode(&date_query(since, before)).into_owned();
+        let end = limit.map(|limit| skip as usize + limit as usize);
+
+        let mut ids = Vec::new();
+        let mut page_token: Option<String> = None;
+        let mut remaining = 0;
+        loop {
+            let mut url = format!(
+                "{}/messages?labelIds={}&maxResults={}&q={}",
+                API_URL, label, PAGE_SIZE, query
+            );
+            if let Some(token) = &page_token {
+                url.push_str(&format!("&pageToken={}", urlencoding::encode(token)));
+            }
+            let page = self.get(&url)?;
+            ids.extend(
+                page["messages"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|m| m["id"].as_str())
+                    .map(str::to_string),
+            );
+            page_token = page["nextPageToken"].as_str().map(str::to_string);
+            if let Some(end) = end.filter(|&end| ids.len() >= end) {
+                if ids.len() > end || page_token.is_some() {
+                    let estimate = page["resultSizeEstimate"].as_u64().unwrap_or(0) as usize;
+                    remaining = estimate.saturating_sub(end).max(ids.len() - end).max(1);
+                }
+                ids.truncate(end);
+                break;
+            }
+            if page_token.is_none() {
+                break;
+            }
+        }
+
+        let paths: Vec<String> = ids
+            .iter()
+            .skip(skip as usize)
+            .map(|id| metadata_path(id))
+            .collect();
+        let objects = self.batch(&paths)?;
+        if self.labels.is_empty() {
+            self.list_folders()?;
+        }
+        let label_names: HashMap<&str, &str> = self
+            .labels
+            .iter()
+            .map(|(name, id)| (id.as_str(), name.as_str()))
+            .collect();
+        let emails = objects
+            .iter()
+            .flatten()
+            .filter_map(|object| parse_message(object, folder, &label_names))
+            .collect();
+        Ok((emails, remaining))
+    }
+}
+
+impl EmailClient for GmailApiClient {
+    /// Gmail message IDs don't change with labels, so there is no UID mapping to return
+    fn archive_batch(
+        &mut self,
+        uids: &[String],
+        _folder: &str,
+        mark_read: bool,
+    ) -> Result<HashMap<String, u32>> {
+        let remove: &[&str] = if mark_read {
+            &["INBOX", "UNREAD"]
+        } else {
+            &["INBOX"]
+        };
+        self.modify(uids, &[], remove)?;
+        Ok(HashMap::new())
+    }
+
+    fn delete_batch(&mut self, uids: &[String], _folder: &str) -> Result<HashMap<String, u32>> {
+        self.modify(uids, &["TRASH"], &["INBOX"])?;
+        Ok(HashMap::new())
+    }
+
+    /// Moving to a label adds it and takes the email out of the folder it was in
+    fn move_batch(
+        &mut self,
+        uids: &[String],
+        folder: &str,
+        dest: &str,
+        mark_read: bool,
+    ) -> Result<HashMap<String, u32>> {
+        let add: Vec<String> = self.label(dest).into_iter().collect();
+        let mut remove: Vec<String> = self.label(folder).into_iter().collect();
+        if add.is_empty() && remove.is_empty() {
+            bail!("No label named {}", dest);
+        }
+        if mark_read {
+            remove.push("UNREAD".to_string());
+        }
+        self.modify(uids, &add, &remove)?;
+        Ok(HashMap::new())
+    }
+
+    /// Lists the user's own labels; system labels are reached with archive and delete
+    fn list_folders(&mut self) -> Result<Vec<String>> {
+        let response = self.get(&format!("{}/labels", API_URL))?;
+        self.labels = response["labels"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|label| label["type"] == "user")
+            .filter_map(|label| {
+                Some((
+                    label["name"].as_str()?.to_string(),
+                    label["id"].as_str()?.to_string(),
+                ))
+            })
+            .collect();
+        let mut folders: Vec<String> = self.labels.keys().cloned().collect();
+        folders.sort();
+        Ok(folders)
+    }
+
+    /// Finds each email by Message-ID, a batch of searches per request, then moves its
+    /// labels back
+    fn restore_emails(
+        &mut self,
+        emails: &[(Option<String>, Option<u32>, String, String)],
+        progress_tx: Option<std::sync::mpsc::Sender<usize>>,
+    ) -> Result<()> {
+        let mut by_route: HashMap<(&str, &str), Vec<&str>> = HashMap::new();
+        for (message_id, _, current_folder, dest_folder) in emails {
+            if let Some(message_id) = message_id {
+                by_route
+                    .entry((current_folder.as_str(), dest_folder.as_str()))
+                    .or_default()
+                    .push(message_id);
+            }
+        }
+
+        for ((current_folder, dest_folder), message_ids) in by_route {
+            let add: Vec<String> = self.label(dest_folder).into_iter().collect();
+            let remove: Vec<String> = self.label(current_folder).into_iter().collect();
+            for chunk in message_ids.chunks(BATCH_SIZE) {
+                let searches: Vec<String> = chunk
+                    .iter()
+                    .map(|message_id| {
+                        let query = format!("rfc822msgid:{}", message_id.trim_matches(['<', '>']));
+                        format!(
+                            "{}/messages?q={}&maxResults=1&includeSpamTrash=true",
+                            BATCH_PATH,
+                            urlencoding::encode(&query)
+                        )
+                    })
+                    .collect();
+                // An email that isn't found was deleted for good
+                let ids: Vec<String> = self
+                    .batch(&searches)?
+                    .iter()
+                    .flatten()
+                    .filter_map(|response| response["messages"][0]["id"].as_str())
+                    .map(str::to_string)
+                    .collect();
+                self.modify(&ids, &add, &remove)?;
+                if let Some(tx) = &progress_tx {
+                    let _ = tx.send(chunk.len());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Downloads the raw message; the API can't send part of one, so it is cut at
+    /// max_bytes before parsing, like a partial IMAP fetch
+    fn fetch_email_body(
+        &mut self,
+        uid: &str,
+        folder: &str,
+        max_bytes: Option<u32>,
+        mark_seen: bool,
+    ) -> Result<FetchedBody> {
+        crate::debug_log!(
+            "fetch_email_body: fetching Gmail API email {} from {}",
+            uid,
+            folder
+        );
+
+        let message = self.get(&format!(
+            "{}/messages/{}?format=raw&fields=raw",
+            API_URL, uid
+        ))?;
+        let mut raw = decode_base64url(message["raw"].as_str().context("Email not found")?)?;
+        let truncated = max_bytes.is_some_and(|limit| raw.len() > limit as usize);
+        if let Some(limit) = max_bytes {
+            raw.truncate(limit as usize);
+        }
+        if mark_seen {
+            self.modify(&[uid.to_string()], &[], &["UNREAD"])?;
+        }
+
+        let parsed = mailparse::parse_mail(&raw).context("Failed to parse email")?;
+        let (body_text, html) = match pgp::body_text(&parsed, &self.pgp_command) {
+            Some(text) => (text, None),
+            None => (extract_body_text(&parsed)?, html_part(&parsed)),
+        };
+        Ok(FetchedBody {
+            text: sanitize_for_terminal(&body_text),
+            truncated,
+            html,
+        })
+    }
+
+    fn set_seen(&mut self, uids: &[String], _folder: &str, seen: bool) -> Result<()> {
+        if seen {
+            self.modify(uids, &[], &["UNREAD"])
+        } else {
+            self.modify(uids, &["UNREAD"], &[])
+        }
+    }
+
+    /// Labels are added and removed by ID, so the list is fetched again for a label
+    /// made since it was last read
+    fn modify_labels(
+        &mut self,
+        uids: &[String],
+        _folder: &str,
+        add: &[String],
+        remove: &[String],
+    ) -> Result<()> {
+        if add
+            .iter()
+            .chain(remove)
+            .any(|name| !self.labels.contains_key(name))
+        {
+            self.list_folders()?;
+        }
+        let ids = |names: &[String]| -> Result<Vec<String>> {
+            names
+                .iter()
+                .map(|name| {
+                    self.labels
+                        .get(name)
+                        .cloned()
+                        .with_context(|| format!("No label named {}", name))
+                })
+                .collect()
+        };
+        let (add, remove) = (ids(add)?, ids(remove)?);
+        self.modify(uids, &add, &remove)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_folders_map_to_labels() {
+        let folders = crate::config::Backend::GmailApi.folders();
+        assert_eq!(folder_label("INBOX", folders), Some("INBOX"));
+        assert_eq!(folder_label(folders.sent, folders), Some("SENT"));
+        assert_eq!(folder_label(folders.trash, folders), Some("TRASH"));
+        assert_eq!(folder_label(folders.archive, folders), None);
+        assert_eq!(
+            date_query(NaiveDate::from_ymd_opt(2026, 4, 1), None),
+            "after:2026/04/01"
+        );
+    }
+
+    #[test]
+    fn test_batch_body_numbers_parts() {
+        let body = batch_body(&["/a".to_string(), "/b".to_string()]);
+        assert!(body.starts_with("--zeroterm_batch\r\nContent-Type: application/http\r\n"));
+        assert!(body.contains("Content-ID: <1>\r\n\r\nGET /b\r\n"));
+        assert!(body.ends_with("--zeroterm_batch--\r\n"));
+        assert!(metadata_path("18c").contains("/messages/18c?format=metadata"));
+        assert!(metadata_path("18c").ends_with("&metadataHeaders=X-Spamd-Result"));
+    }
+
+    #[test]
+    fn test_parse_batch_orders_parts_by_content_id() {
+        let response = concat!(
+            "--batch_xyz\r\n",
+            "Content-Type: application/http\r\n",
+            "Content-ID: <response-1>\r\n\r\n",
+            "HTTP/1.1 404 Not Found\r\n",
+            "Content-Type: application/json\r\n\r\n",
+            "{\"error\":{}}\r\n",
+            "--batch_xyz\r\n",
+            "Content-Type: application/http\r\n",
+            "Content-ID: <response-0>\r\n\r\n",
+            "HTTP/1.1 200 OK\r\n",
+            "Content-Type: application/json\r\n\r\n",
+            "{\"id\":\"a\"}\r\n",
+            "--batch_xyz--\r\n",
+        );
+        let results = parse_batch(response.as_bytes(), 2).unwrap();
+        assert_eq!(results, vec![Some(json!({ "id": "a" })), None]);
+        assert!(parse_batch(response.as_bytes(), 3).is_err());
+        let limited = response.replace("404 Not Found", "429 Too Many Requests");
+        assert_eq!(
+            parse_batch(limited.as_bytes(), 2).unwrap_err().to_string(),
+            "Gmail API request failed: HTTP/1.1 429 Too Many Requests"
+        );
+    }
+
+    #[test]
+    fn test_parse_message() {
+        let object = json!({
+            "id": "18c2f",
+            "threadId": "18c2a",
+            "labelIds": ["INBOX", "STARRED", "UNREAD", "Label_7"],
+            "snippet": "Don&#39;t   forget &amp; bring",
+            "payload": { "headers": [
+                { "name": "From", "value": "Alice <alice@example.com>" },
+                { "name": "Subject", "value": "Lunch" },
+                { "name": "Date", "value": "Tue, 1 Apr 2025 10:00:00 +0000" },
+                { "name": "Message-ID", "value": "<a@example.com>" },
+                { "name": "List-Unsubscribe", "value": "<mailto:u@example.com>" },
+            ]},
+        });
+        let names = HashMap::from([("Label_7", "Receipts")]);
+        let email = parse_message(&object, "INBOX", &names).unwrap();
+        assert_eq!(email.id, "18c2f");
+        assert_eq!(email.from_email, "alice@example.com");
+        assert_eq!(email.subject, "Lunch");
+        assert_eq!(email.snippet, "Don't forget & bring");
+        assert_eq!(email.message_id.as_deref(), Some("<a@example.com>"));
+        assert!(!email.seen);
+        assert!(email.flagged);
+        assert!(email.mailing_list);
+        assert_eq!(email.gmail_thread_id, Some(0x18c2a));
+        assert_eq!(email.labels, vec!["Receipts"]);
+        assert!(parse_message(&json!({ "id": "1" }), "INBOX", &names).is_none());
+    }
+
+    #[test]
+    fn test_decode_base64url() {
+        assert_eq!(decode_base64url("SGk_Pz4-").unwrap(), b"Hi??>>");
+        assert_eq!(decode_base64url("SGVsbG8").unwrap(), b"Hello");
+        assert!(decode_base64url("SGV*").is_err());
+    }
+}
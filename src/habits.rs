@@ -0,0 +1,90 @@
+//! Remembers how each sender's mail is usually handled, stored locally per account
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::config;
+use crate::policy::PolicyAction;
+
+const HABITS_DIR: &str = "habits";
+
+/// Times in a row a sender must be handled the same way before it becomes the usual
+pub const USUAL_AFTER: u32 = 3;
+
+/// The last way a sender was handled and how many times in a row
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct Streak {
+    action: PolicyAction,
+    count: u32,
+}
+
+/// Handling streaks keyed by group key (a sender address or a domain)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SenderHabits(BTreeMap<String, Streak>);
+
+impl SenderHabits {
+    /// Records that a group's mail was archived or deleted
+    pub fn record(&mut self, key: &str, action: PolicyAction) {
+        let streak = self
+            .0
+            .entry(key.to_string())
+            .or_insert(Streak { action, count: 0 });
+        if streak.action != action {
+            *streak = Streak { action, count: 0 };
+        }
+        streak.count = streak.count.saturating_add(1);
+    }
+
+    /// Returns the usual action for a group once it has been repeated USUAL_AFTER times
+    pub fn usual(&self, key: &str) -> Option<PolicyAction> {
+        self.0
+            .get(key)
+            .filter(|streak| streak.count >= USUAL_AFTER)
+            .map(|streak| streak.action)
+    }
+
+    /// Loads habits from a file
+    pub fn load(path: &Path) -> Result<Self> {
+        config::load_data_file(path)
+    }
+
+    /// Writes habits to a file
+    pub fn save(&self, path: &Path) -> Result<()> {
+        config::save_data_file(path, self)
+    }
+}
+
+/// Returns the habits file for an account: ~/.config/zeroterm/habits/<account>.toml
+pub fn habits_path(account: &str) -> Result<PathBuf> {
+    config::account_data_path(HABITS_DIR, account)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_usual_after_repeated_handling() {
+        let mut habits = SenderHabits::default();
+        for _ in 0..USUAL_AFTER - 1 {
+            habits.record("news.com", PolicyAction::Archive);
+        }
+        assert_eq!(habits.usual("news.com"), None);
+
+        habits.record("news.com", PolicyAction::Archive);
+        assert_eq!(habits.usual("news.com"), Some(PolicyAction::Archive));
+    }
+
+    #[test]
+    fn test_different_action_resets_streak() {
+        let mut habits = SenderHabits::default();
+        for _ in 0..USUAL_AFTER {
+            habits.record("news.com", PolicyAction::Archive);
+        }
+        habits.record("news.com", PolicyAction::Delete);
+        assert_eq!(habits.usual("news.com"), None);
+    }
+}
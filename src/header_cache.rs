@@ -0,0 +1,198 @@
+//! The inbox headers of the last session, saved per account under ~/.cache/zeroterm so
+//! the next launch shows them at once while the real fetch runs
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::compose::write_private;
+use crate::config;
+use crate::email::Email;
+use crate::pgp;
+
+const CACHE_DIR: &str = "headers";
+
+/// Bumped when `Email` changes shape, so an old cache is ignored instead of misread
+const CACHE_VERSION: u32 = 4;
+
+/// What's written to disk
+#[derive(Serialize, Deserialize)]
+pub struct CachedInbox {
+    version: u32,
+    /// The address the headers were fetched for, in case an account is repointed
+    user_email: String,
+    /// Each folder's UIDVALIDITY when the UIDs were read; empty for API backends, whose
+    /// ids don't change. The UIDs mean nothing once the server's values differ.
+    pub uid_validity: HashMap<String, u32>,
+    pub emails: Vec<Email>,
+}
+
+/// Encrypts the cache at rest with gpg (header_cache_encrypt_to)
+#[derive(Debug, Clone)]
+pub struct Encryption {
+    /// The gpg binary and its options (pgp_command)
+    pub pgp_command: String,
+    /// The key the cache is encrypted to
+    pub recipient: String,
+}
+
+/// Returns the cache file for an account: ~/.cache/zeroterm/headers/<account>.json, or
+/// <account>.json.gpg when it's encrypted
+pub fn cache_path(account: &str, encrypted: bool) -> Result<PathBuf> {
+    let extension = if encrypted { "json.gpg" } else { "json" };
+    config::cache_dir().map(|p| {
+        p.join(CACHE_DIR).join(format!(
+            "{}.{}",
+            config::account_file_stem(account),
+            extension
+        ))
+    })
+}
+
+/// Loads the cache, or None when there is no cache for this address and version
+pub fn load(
+    path: &Path,
+    user_email: &str,
+    encryption: Option<&Encryption>,
+) -> Result<Option<CachedInbox>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let mut content =
+        fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    if let Some(encryption) = encryption {
+        content = pgp::decrypt_data(&encryption.pgp_command, &content)
+            .with_context(|| format!("Failed to decrypt {}", path.display()))?;
+    }
+    let cached: CachedInbox = serde_json::from_slice(&content)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    let current = cached.version == CACHE_VERSION && cached.user_email == user_email;
+    Ok(current.then_some(cached))
+}
+
+/// Saves the emails without their bodies, readable only by the user and encrypted if
+/// configured. Writes a temporary file first so a crash mid-write leaves the previous
+/// cache intact.
+pub fn save(
+    path: &Path,
+    user_email: &str,
+    uid_validity: &HashMap<String, u32>,
+    mut emails: Vec<Email>,
+    encryption: Option<&Encryption>,
+) -> Result<()> {
+    for email in &mut emails {
+        email.body = None;
+    }
+    let cached = CachedInbox {
+        version: CACHE_VERSION,
+        user_email: user_email.to_string(),
+        uid_validity: uid_validity.clone(),
+        emails,
+    };
+    if let Some(dir) = path.parent() {
+        config::create_private_dir(dir)?;
+    }
+    let mut content = serde_json::to_vec(&cached).context("Failed to serialize the cache")?;
+    if let Some(encryption) = encryption {
+        content = pgp::encrypt(&encryption.pgp_command, &encryption.recipient, &content)?;
+        // A plain cache from before encryption was turned on shouldn't outlive it
+        let _ = fs::remove_file(path.with_extension(""));
+    }
+    let temp = path.with_extension("tmp");
+    write_private(&temp, &content)?;
+    fs::rename(&temp, path).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Saves the emails on another thread so a large inbox doesn't stall the UI
+pub fn spawn_save(
+    path: PathBuf,
+    user_email: String,
+    uid_validity: HashMap<String, u32>,
+    emails: Vec<Email>,
+    encryption: Option<Encryption>,
+) {
+    std::thread::spawn(move || {
+        if let Err(e) = save(
+            &path,
+            &user_email,
+            &uid_validity,
+            emails,
+            encryption.as_ref(),
+        ) {
+            debug_log!("Failed to save {}: {:#}", path.display(), e);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::email::EmailBuilder;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn test_cache_round_trip_drops_bodies_and_checks_the_address() {
+        let path = std::env::temp_dir().join(format!(
+            "zeroterm-headers-{}/personal.json",
+            std::process::id()
+        ));
+        let mut email = EmailBuilder::new()
+            .id("7")
+            .from("news@example.com")
+            .subject("Digest")
+            .build();
+        email.body = Some("long body".to_string());
+        let uid_validity = HashMap::from([("INBOX".to_string(), 1700000000)]);
+        save(&path, "me@example.com", &uid_validity, vec![email], None).unwrap();
+
+        let mode = |p: &Path| fs::metadata(p).unwrap().permissions().mode() & 0o777;
+        let (file_mode, dir_mode) = (mode(&path), mode(path.parent().unwrap()));
+        let loaded = load(&path, "me@example.com", None).unwrap().unwrap();
+        let other = load(&path, "other@example.com", None).unwrap();
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+        assert_eq!((file_mode, dir_mode), (0o600, 0o700));
+        assert_eq!(loaded.uid_validity, uid_validity);
+        assert_eq!(loaded.emails.len(), 1);
+        assert_eq!(loaded.emails[0].subject, "Digest");
+        assert_eq!(loaded.emails[0].body, None);
+        assert!(other.is_none());
+        assert!(load(&path, "me@example.com", None).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_encrypted_cache_goes_through_the_pgp_command() {
+        let dir = std::env::temp_dir().join(format!("zeroterm-headers-gpg-{}", std::process::id()));
+        let path = dir.join("personal.json.gpg");
+        let plain = dir.join("personal.json");
+        // rot13 stands in for gpg: it scrambles the file and undoes itself
+        let encryption = Encryption {
+            pgp_command: "sh -c 'tr a-zA-Z n-za-mN-ZA-M'".to_string(),
+            recipient: "me@example.com".to_string(),
+        };
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&plain, "{}").unwrap();
+        let email = EmailBuilder::new()
+            .id("7")
+            .from("news@example.com")
+            .subject("Digest")
+            .build();
+        save(
+            &path,
+            "me@example.com",
+            &HashMap::new(),
+            vec![email],
+            Some(&encryption),
+        )
+        .unwrap();
+
+        let on_disk = fs::read_to_string(&path).unwrap();
+        let plain_left = plain.exists();
+        let loaded = load(&path, "me@example.com", Some(&encryption)).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+        assert!(!on_disk.contains("Digest") && !on_disk.contains("news@example.com"));
+        assert!(!plain_left);
+        assert_eq!(loaded.unwrap().emails[0].subject, "Digest");
+    }
+}
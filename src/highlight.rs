@@ -0,0 +1,163 @@
+//! `[[highlight]]` rules that make matching emails stand out in the lists
+
+use std::str::FromStr;
+
+use ratatui::style::{Color, Modifier, Style};
+use serde::{Deserialize, Serialize};
+
+use crate::email::{Email, sender_matches};
+
+/// A `[[highlight]]` rule from config. All given conditions must match.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Highlight {
+    /// Only match these senders ("billing@example.com") or domains ("example.com")
+    #[serde(default)]
+    pub senders: Vec<String>,
+    /// Only match subjects containing one of these words, ignoring case
+    #[serde(default)]
+    pub subject: Vec<String>,
+    /// A color name ("red", "lightblue") or hex code ("#ff8800") (default: unchanged)
+    pub color: Option<String>,
+    /// Draw matching rows in bold (default: false)
+    #[serde(default)]
+    pub bold: bool,
+    /// Underline matching rows (default: false)
+    #[serde(default)]
+    pub underline: bool,
+}
+
+impl Highlight {
+    /// A highlight must narrow by sender or subject so it doesn't color every row
+    pub fn is_valid(&self) -> bool {
+        !self.senders.is_empty() || !self.subject.is_empty()
+    }
+
+    /// Checks if an email matches every condition of this highlight
+    pub fn matches(&self, email: &Email) -> bool {
+        if !self.senders.is_empty() && !self.senders.iter().any(|s| sender_matches(email, s)) {
+            return false;
+        }
+        if !self.subject.is_empty() {
+            let subject = email.subject.to_lowercase();
+            if !self
+                .subject
+                .iter()
+                .any(|word| subject.contains(&word.to_lowercase()))
+            {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// The configured color, or None when it isn't set or can't be parsed
+    pub fn parsed_color(&self) -> Option<Color> {
+        self.color.as_deref().and_then(|c| Color::from_str(c).ok())
+    }
+
+    /// The style drawn over a matching row
+    pub fn style(&self) -> Style {
+        let mut style = Style::default();
+        if let Some(color) = self.parsed_color() {
+            style = style.fg(color);
+        }
+        if self.bold {
+            style = style.add_modifier(Modifier::BOLD);
+        }
+        if self.underline {
+            style = style.add_modifier(Modifier::UNDERLINED);
+        }
+        style
+    }
+}
+
+/// Returns the style of the first highlight matching any of the emails
+pub fn style_for<'a>(
+    highlights: &[Highlight],
+    mut emails: impl Iterator<Item = &'a Email>,
+) -> Option<Style> {
+    if highlights.is_empty() {
+        return None;
+    }
+    emails.find_map(|email| {
+        highlights
+            .iter()
+            .find(|h| h.matches(email))
+            .map(Highlight::style)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::email::EmailBuilder;
+
+    fn highlight() -> Highlight {
+        Highlight {
+            senders: Vec::new(),
+            subject: Vec::new(),
+            color: None,
+            bold: false,
+            underline: false,
+        }
+    }
+
+    #[test]
+    fn test_highlight_matches_subject_words_ignoring_case() {
+        let rule = Highlight {
+            subject: vec!["invoice".to_string()],
+            ..highlight()
+        };
+        let invoice = EmailBuilder::new()
+            .from("billing@example.com")
+            .subject("Your INVOICE for March")
+            .build();
+        let other = EmailBuilder::new()
+            .from("billing@example.com")
+            .subject("Welcome aboard")
+            .build();
+        assert!(rule.matches(&invoice));
+        assert!(!rule.matches(&other));
+        assert!(!highlight().is_valid());
+        assert!(rule.is_valid());
+    }
+
+    #[test]
+    fn test_highlight_requires_every_condition() {
+        let rule = Highlight {
+            senders: vec!["example.com".to_string()],
+            subject: vec!["invoice".to_string()],
+            ..highlight()
+        };
+        let from_elsewhere = EmailBuilder::new()
+            .from("billing@other.com")
+            .subject("Invoice")
+            .build();
+        assert!(!rule.matches(&from_elsewhere));
+    }
+
+    #[test]
+    fn test_style_for_uses_the_first_matching_rule() {
+        let rules = vec![
+            Highlight {
+                senders: vec!["boss@work.com".to_string()],
+                color: Some("lightred".to_string()),
+                bold: true,
+                ..highlight()
+            },
+            Highlight {
+                senders: vec!["work.com".to_string()],
+                color: Some("#00ff00".to_string()),
+                ..highlight()
+            },
+        ];
+        let boss = EmailBuilder::new().from("boss@work.com").build();
+        let style = style_for(&rules, std::iter::once(&boss)).unwrap();
+        assert_eq!(style.fg, Some(Color::LightRed));
+        assert!(style.add_modifier.contains(Modifier::BOLD));
+
+        let stranger = EmailBuilder::new().from("someone@else.com").build();
+        assert_eq!(style_for(&rules, std::iter::once(&stranger)), None);
+    }
+}
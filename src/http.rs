@@ -0,0 +1,104 @@
+//! HTTP requests for the API backends, and the SMTP requests replies are sent with. They go
+//! through curl like the webhook, with the whole request on curl's stdin so the API token
+//! or password never shows up in the process list.
+
+use anyhow::{Context, Result, bail};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Seconds curl may spend on one request
+const TIMEOUT_SECS: &str = "60";
+
+/// Quotes a value for a curl config file
+pub(crate) fn quote(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Builds the curl config for a request with a Bearer token: a GET, or a POST of a
+/// (content type, body) pair
+pub fn bearer_config(url: &str, token: &str, body: Option<(&str, &str)>) -> String {
+    let mut config = format!(
+        "url = {}\nheader = {}\n",
+        quote(url),
+        quote(&format!("Authorization: Bearer {}", token))
+    );
+    if let Some((content_type, body)) = body {
+        config.push_str(&format!(
+            "header = {}\n",
+            quote(&format!("Content-Type: {}", content_type))
+        ));
+        config.push_str(&format!("data-binary = {}\n", quote(body)));
+    }
+    config
+}
+
+/// Runs curl with the config on stdin and returns the response body. `service` names
+/// the API in errors, e.g. "JMAP".
+pub fn send(config: &str, service: &str) -> Result<Vec<u8>> {
+    let mut child = Command::new("curl")
+        .args([
+            "--silent",
+            "--show-error",
+            "--fail",
+            "--max-time",
+            TIMEOUT_SECS,
+            "--config",
+            "-",
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to run curl")?;
+    // curl reads the whole config before it starts, so this can't block on its output
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(config.as_bytes())
+            .with_context(|| format!("Failed to send the {} request to curl", service))?;
+    }
+    let output = child
+        .wait_with_output()
+        .context("Failed to wait for curl")?;
+    if !output.status.success() {
+        bail!(
+            "{} request failed: {}",
+            service,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(output.stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bearer_config_keeps_the_token_off_the_command_line() {
+        let config = bearer_config(
+            "https://api.example.com/jmap/",
+            "tok\"en",
+            Some(("application/json", "{\"a\":\"b\\\\c\"}")),
+        );
+        assert_eq!(
+            config,
+            "url = \"https://api.example.com/jmap/\"\n\
+             header = \"Authorization: Bearer tok\\\"en\"\n\
+             header = \"Content-Type: application/json\"\n\
+             data-binary = \"{\\\"a\\\":\\\"b\\\\\\\\c\\\"}\"\n"
+        );
+    }
+}
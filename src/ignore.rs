@@ -0,0 +1,55 @@
+//! Senders hidden from zeroterm with the I key, stored locally per account
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::config;
+
+const IGNORE_DIR: &str = "ignored";
+
+/// Sender addresses or domains the user chose to hide
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct IgnoreList {
+    #[serde(default)]
+    pub senders: Vec<String>,
+}
+
+impl IgnoreList {
+    /// Adds a sender or domain; returns false if it was already ignored
+    pub fn add(&mut self, pattern: &str) -> bool {
+        if self.senders.iter().any(|p| p.eq_ignore_ascii_case(pattern)) {
+            return false;
+        }
+        self.senders.push(pattern.to_string());
+        true
+    }
+
+    /// Loads the list from a file
+    pub fn load(path: &Path) -> Result<Self> {
+        config::load_data_file(path)
+    }
+
+    /// Writes the list to a file
+    pub fn save(&self, path: &Path) -> Result<()> {
+        config::save_data_file(path, self)
+    }
+}
+
+/// Returns the ignore file for an account: ~/.config/zeroterm/ignored/<account>.toml
+pub fn ignore_path(account: &str) -> Result<PathBuf> {
+    config::account_data_path(IGNORE_DIR, account)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_skips_duplicates() {
+        let mut list = IgnoreList::default();
+        assert!(list.add("boss@work.com"));
+        assert!(!list.add("Boss@Work.com"));
+        assert_eq!(list.senders, vec!["boss@work.com"]);
+    }
+}
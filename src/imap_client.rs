@@ -1,26 +1,98 @@
-use anyhow::{Context, Result};
-use chrono::{DateTime, TimeZone, Utc};
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
 use imap::{ImapConnection, Session};
+use std::io::{Read, Write};
+use std::net::TcpStream;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicUsize, Ordering};
-
-use crate::email::{Email, EmailBuilder};
-
-use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::auth::AuthResults;
+use crate::config::{AccountConfig, Folders, Security};
+use crate::email::{
+    Email, EmailBuilder, extract_body_text, html_part, html_to_text, parse_list_unsubscribe,
+};
+use crate::pgp;
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+
+/// Emails moved per IMAP command by bulk archives and deletes
+pub const BATCH_SIZE: usize = 250;
+/// Bytes of body text fetched with each message's headers to build its snippet
+const SNIPPET_FETCH_BYTES: usize = 1024;
+/// The header fields the lists are built from: addresses, subject and date, threading,
+/// mailing-list, authentication and spam headers, and the MIME fields the snippet needs.
+/// Much smaller than the full header and ENVELOPE, which carry every Received line.
+const LIST_HEADER_FIELDS: &str = "FROM TO CC SUBJECT DATE MESSAGE-ID IN-REPLY-TO REFERENCES \
+LIST-ID LIST-UNSUBSCRIBE AUTHENTICATION-RESULTS RECEIVED-SPF X-SPAM-SCORE X-SPAM-STATUS \
+X-RSPAMD-SCORE X-SPAMD-RESULT CONTENT-TYPE CONTENT-TRANSFER-ENCODING";
+/// The same headers by name, for the API backends
+pub(crate) const LIST_HEADERS: [&str; 16] = [
+    "From",
+    "To",
+    "Cc",
+    "Subject",
+    "Date",
+    "Message-ID",
+    "In-Reply-To",
+    "References",
+    "List-Id",
+    "List-Unsubscribe",
+    "Authentication-Results",
+    "Received-SPF",
+    "X-Spam-Score",
+    "X-Spam-Status",
+    "X-Rspamd-Score",
+    "X-Spamd-Result",
+];
+/// Maximum length of an email's snippet, in characters
+pub(crate) const SNIPPET_CHARS: usize = 100;
+
+/// A fetched email body, cut short when the message is larger than the fetch cap
+#[derive(Debug, Clone, PartialEq)]
+pub struct FetchedBody {
+    pub text: String,
+    /// True when only the first part of the message was fetched
+    pub truncated: bool,
+    /// The message's HTML part, when it has one, for the structured HTML view
+    pub html: Option<String>,
+}
 
 /// Trait for email operations - allows mocking in tests
 #[cfg_attr(test, mockall::automock)]
 pub trait EmailClient {
-    /// Archives a batch of emails from a single folder (moves to All Mail)
+    /// Archives a batch of emails from a single folder (moves to the archive folder)
     /// UIDs should be from the same folder for efficiency
+    /// When mark_read is set, the emails are flagged \Seen before they're moved
     /// Returns a mapping of source UID -> destination UID (empty if COPYUID not supported)
-    fn archive_batch(&mut self, uids: &[String], folder: &str) -> Result<HashMap<String, u32>>;
+    fn archive_batch(
+        &mut self,
+        uids: &[String],
+        folder: &str,
+        mark_read: bool,
+    ) -> Result<HashMap<String, u32>>;
 
-    /// Deletes a batch of emails from a single folder (moves to Trash)
+    /// Deletes a batch of emails from a single folder (moves to the trash folder)
     /// UIDs should be from the same folder for efficiency
     /// Returns a mapping of source UID -> destination UID (empty if COPYUID not supported)
     fn delete_batch(&mut self, uids: &[String], folder: &str) -> Result<HashMap<String, u32>>;
 
+    /// Moves a batch of emails from a single folder to another folder
+    /// UIDs should be from the same folder for efficiency
+    /// When mark_read is set, the emails are flagged \Seen before they're moved
+    /// Returns a mapping of source UID -> destination UID (empty if COPYUID not supported)
+    fn move_batch(
+        &mut self,
+        uids: &[String],
+        folder: &str,
+        dest: &str,
+        mark_read: bool,
+    ) -> Result<HashMap<String, u32>>;
+
+    /// Lists the folders emails can be moved to, sorted by name
+    fn list_folders(&mut self) -> Result<Vec<String>>;
+
     /// Restores emails to their original folders
     /// Takes a list of (message_id, dest_uid, current_folder, destination_folder) tuples
     /// Uses dest_uid for fast restore if available, falls back to Message-ID search otherwise
@@ -33,12 +105,67 @@ pub trait EmailClient {
 
     /// Fetches the body of an email by UID from the specified folder
     /// Returns the plain text body (or HTML converted to text if no plain text part)
-    fn fetch_email_body(&mut self, uid: &str, folder: &str) -> Result<String>;
+    /// When max_bytes is set, only that much of the raw message is downloaded
+    /// When mark_seen is set, the server marks the message as read (BODY instead of BODY.PEEK)
+    fn fetch_email_body(
+        &mut self,
+        uid: &str,
+        folder: &str,
+        max_bytes: Option<u32>,
+        mark_seen: bool,
+    ) -> Result<FetchedBody>;
+
+    /// Marks a batch of emails from a single folder read (seen) or unread
+    fn set_seen(&mut self, uids: &[String], folder: &str, seen: bool) -> Result<()>;
+
+    /// Adds and removes Gmail labels on a batch of emails from a single folder
+    fn modify_labels(
+        &mut self,
+        uids: &[String],
+        folder: &str,
+        add: &[String],
+        remove: &[String],
+    ) -> Result<()>;
+}
+
+/// A backend reached over an HTTP API rather than IMAP, which fetches a folder in a few
+/// batched requests instead of over parallel connections
+pub trait ApiClient: EmailClient {
+    /// The account backend's archive, trash and sent folders
+    fn folders(&self) -> Folders;
+
+    /// Fetches a folder's emails received on or after `since` and before `before`, newest
+    /// first, skipping the newest `skip` and stopping after `limit`. Also returns how many
+    /// older matching emails were left out.
+    fn fetch_folder(
+        &mut self,
+        folder: &'static str,
+        since: Option<NaiveDate>,
+        before: Option<NaiveDate>,
+        skip: u32,
+        limit: Option<u32>,
+    ) -> Result<(Vec<Email>, usize)>;
 }
 
 /// IMAP client for Gmail access
 pub struct ImapClient {
     session: Session<Box<dyn ImapConnection>>,
+    /// Whether the server advertises Gmail's X-GM-EXT-1 extensions
+    gmail_extensions: bool,
+    /// The account backend's archive, trash and sent folders
+    folders: Folders,
+    /// `[ALERT]` notices from the server that haven't been shown to the user yet
+    alerts: Vec<String>,
+    /// The folder currently selected on the session
+    selected: Option<String>,
+    /// How many messages the selected folder holds, as far as this session knows
+    exists: Option<u32>,
+    /// Folders another client changed since the last `take_mailbox_changes`
+    changed_folders: Vec<String>,
+    /// gpg command for decrypting and verifying PGP/MIME bodies
+    pgp_command: String,
+    /// Emails archives and deletes skipped because another client had already moved them
+    already_gone: usize,
 }
 
 /// Parses a COPYUID response to extract the mapping from source UIDs to destination UIDs
@@ -90,6 +217,151 @@ fn parse_copyuid_response(response: &[u8]) -> HashMap<String, u32> {
     uid_map
 }
 
+/// One message of a raw FETCH response
+struct FetchedMessage<'a> {
+    uid: Option<u32>,
+    seen: bool,
+    flagged: bool,
+    header: Option<Cow<'a, [u8]>>,
+    text: Option<Cow<'a, [u8]>>,
+    /// Gmail's conversation ID (X-GM-THRID)
+    gmail_thread_id: Option<u64>,
+    /// Gmail's labels (X-GM-LABELS), without system labels such as \Important
+    labels: Vec<String>,
+}
+
+/// The messages of a raw FETCH response, and what the server said about the selected
+/// folder while answering
+#[derive(Default)]
+struct FetchResponse<'a> {
+    messages: Vec<FetchedMessage<'a>>,
+    /// The last EXISTS count reported
+    exists: Option<u32>,
+    /// Whether an EXPUNGE was reported
+    expunged: bool,
+}
+
+/// Parses a raw FETCH response. The imap crate's Fetch type doesn't expose Gmail's
+/// X-GM-THRID and X-GM-LABELS, so the response is read here to get them in the same
+/// round trip.
+fn parse_fetch_response(response: &[u8]) -> FetchResponse<'_> {
+    use imap_proto::parser::parse_response;
+    use imap_proto::types::{AttributeValue, MailboxDatum, MessageSection, Response, SectionPath};
+
+    let mut parsed = FetchResponse::default();
+
+    let mut remaining = response;
+    while !remaining.is_empty() {
+        match parse_response(remaining) {
+            Ok((rest, resp)) => {
+                match resp {
+                    Response::Fetch(_, attrs) => {
+                        let mut message = FetchedMessage {
+                            uid: None,
+                            seen: false,
+                            flagged: false,
+                            header: None,
+                            text: None,
+                            gmail_thread_id: None,
+                            labels: Vec::new(),
+                        };
+                        for attr in attrs {
+                            match attr {
+                                AttributeValue::Uid(uid) => message.uid = Some(uid),
+                                AttributeValue::Flags(flags) => {
+                                    let has = |flag: &str| {
+                                        flags.iter().any(|f| f.eq_ignore_ascii_case(flag))
+                                    };
+                                    message.seen = has("\\Seen");
+                                    message.flagged = has("\\Flagged");
+                                }
+                                AttributeValue::GmailThrId(thrid) => {
+                                    message.gmail_thread_id = Some(thrid)
+                                }
+                                AttributeValue::GmailLabels(labels) => {
+                                    message.labels = user_labels(&labels)
+                                }
+                                AttributeValue::BodySection {
+                                    section: Some(SectionPath::Full(section)),
+                                    data,
+                                    ..
+                                } => match section {
+                                    MessageSection::Header => message.header = data,
+                                    MessageSection::Text => message.text = data,
+                                    _ => {}
+                                },
+                                _ => {}
+                            }
+                        }
+                        parsed.messages.push(message);
+                    }
+                    Response::MailboxData(MailboxDatum::Exists(count)) => {
+                        parsed.exists = Some(count)
+                    }
+                    Response::Expunge(_) | Response::Vanished { .. } => parsed.expunged = true,
+                    _ => {}
+                }
+                remaining = rest;
+            }
+            Err(_) => {
+                // If parsing fails, try skipping to the next line
+                if let Some(pos) = remaining.iter().position(|&b| b == b'\n') {
+                    remaining = &remaining[pos + 1..];
+                } else {
+                    break;
+                }
+            }
+        }
+    }
+
+    parsed
+}
+
+/// Collects the text of `[ALERT]` responses (untagged or tagged) from a raw server response
+fn parse_alerts(response: &[u8]) -> Vec<String> {
+    use imap_proto::parser::parse_response;
+    use imap_proto::types::{Response, ResponseCode};
+
+    let mut alerts = Vec::new();
+
+    let mut remaining = response;
+    while !remaining.is_empty() {
+        match parse_response(remaining) {
+            Ok((rest, resp)) => {
+                if let Response::Data {
+                    code: Some(ResponseCode::Alert),
+                    information,
+                    ..
+                }
+                | Response::Done {
+                    code: Some(ResponseCode::Alert),
+                    information,
+                    ..
+                } = resp
+                {
+                    alerts.push(
+                        information
+                            .map(|text| text.trim().to_string())
+                            .unwrap_or_default(),
+                    );
+                }
+                remaining = rest;
+            }
+            Err(_) => {
+                // If parsing fails, try skipping to the next line
+                if let Some(pos) = remaining.iter().position(|&b| b == b'\n') {
+                    remaining = &remaining[pos + 1..];
+                } else {
+                    break;
+                }
+            }
+        }
+    }
+
+    alerts.retain(|alert| !alert.is_empty());
+    alerts
+}
+
 /// Expands a UID set (which may contain ranges) into individual UIDs
 fn expand_uid_set(uid_set: &[imap_proto::types::UidSetMember]) -> Vec<u32> {
     use imap_proto::types::UidSetMember;
@@ -157,133 +429,334 @@ fn format_uid_sequence(ranges: &[(u32, u32)]) -> String {
         .join(",")
 }
 
-/// Sends a UID MOVE command and returns the COPYUID mapping if available
+/// Splits a PEM bundle into individual certificates
 ///
-/// This uses the IMAP MOVE extension (RFC 6851) combined with UIDPLUS (RFC 4315)
-/// to atomically move messages and get their new UIDs in the destination folder.
-fn uid_move_with_copyuid(
-    session: &mut Session<Box<dyn ImapConnection>>,
-    uids: &str,
-    dest_mailbox: &str,
-) -> Result<HashMap<String, u32>> {
-    // Escape the mailbox name if it contains special characters
-    let escaped_mailbox = if dest_mailbox.contains(' ') || dest_mailbox.contains('"') {
-        format!("\"{}\"", dest_mailbox.replace('"', "\\\""))
-    } else {
-        dest_mailbox.to_string()
-    };
+/// native-tls only parses one certificate per `from_pem` call, so bundles with
+/// several `BEGIN CERTIFICATE` blocks are split before parsing.
+fn parse_pem_certificates(pem: &[u8]) -> Result<Vec<native_tls::Certificate>> {
+    const END_MARKER: &str = "-----END CERTIFICATE-----";
+
+    let text = String::from_utf8_lossy(pem);
+    let mut certs = Vec::new();
+    let mut remaining = text.as_ref();
+    while let Some(start) = remaining.find("-----BEGIN CERTIFICATE-----") {
+        let Some(end) = remaining[start..].find(END_MARKER) else {
+            anyhow::bail!("Unterminated certificate in PEM bundle");
+        };
+        let block_end = start + end + END_MARKER.len();
+        let cert = native_tls::Certificate::from_pem(&remaining.as_bytes()[start..block_end])
+            .context("Invalid certificate in PEM bundle")?;
+        certs.push(cert);
+        remaining = &remaining[block_end..];
+    }
+
+    if certs.is_empty() {
+        anyhow::bail!("No certificates found in PEM bundle");
+    }
+    Ok(certs)
+}
+
+/// Builds a TLS connector honoring the account's CA bundle and verification settings
+fn build_tls_connector(account: &AccountConfig) -> Result<native_tls::TlsConnector> {
+    let mut builder = native_tls::TlsConnector::builder();
+
+    if let Some(ca_file) = &account.tls_ca_file {
+        let pem = std::fs::read(ca_file)
+            .with_context(|| format!("Failed to read tls_ca_file {:?}", ca_file))?;
+        for cert in parse_pem_certificates(&pem)? {
+            builder.add_root_certificate(cert);
+        }
+    }
+
+    if account.danger_accept_invalid_certs {
+        builder.danger_accept_invalid_certs(true);
+        builder.danger_accept_invalid_hostnames(true);
+    }
+
+    builder.build().context("Failed to build TLS connector")
+}
 
-    let command = format!("UID MOVE {} {}", uids, escaped_mailbox);
-    crate::debug_log!("uid_move_with_copyuid: {}", command);
+/// Reads one CRLF-terminated line without consuming anything past it
+///
+/// Reading byte-by-byte matters here: any bytes buffered beyond the line would
+/// belong to the TLS handshake that follows STARTTLS.
+fn read_line<S: Read>(stream: &mut S) -> Result<String> {
+    let mut line = Vec::new();
+    let mut byte = [0u8; 1];
+    loop {
+        let n = stream
+            .read(&mut byte)
+            .context("Failed to read from IMAP server")?;
+        if n == 0 {
+            anyhow::bail!("IMAP server closed the connection");
+        }
+        line.push(byte[0]);
+        if line.ends_with(b"\r\n") {
+            line.truncate(line.len() - 2);
+            return Ok(String::from_utf8_lossy(&line).to_string());
+        }
+    }
+}
 
-    // Use run() to get the full response including the tagged OK line with COPYUID
-    let (response, tagged_start) = session.run(&command).context("UID MOVE command failed")?;
+/// Reads the server greeting and negotiates STARTTLS on a plaintext stream
+///
+/// The imap crate's own STARTTLS support can't take a custom TLS connector,
+/// so the exchange is done by hand before handing the stream to the handshake.
+fn starttls_upgrade<S: Read + Write>(stream: &mut S) -> Result<()> {
+    let greeting = read_line(stream)?;
+    if !greeting.starts_with("* OK") {
+        anyhow::bail!("Unexpected IMAP greeting: {}", greeting);
+    }
 
-    // Parse the tagged response portion (which contains COPYUID)
-    let tagged_response = &response[tagged_start..];
-    let uid_map = parse_copyuid_response(tagged_response);
-    crate::debug_log!("uid_move_with_copyuid: got {} UID mappings", uid_map.len());
+    stream
+        .write_all(b"z0 STARTTLS\r\n")
+        .context("Failed to send STARTTLS")?;
+    loop {
+        let line = read_line(stream)?;
+        if let Some(status) = line.strip_prefix("z0 ") {
+            if status.starts_with("OK") {
+                return Ok(());
+            }
+            anyhow::bail!("Server rejected STARTTLS: {}", status);
+        }
+    }
+}
 
-    Ok(uid_map)
+/// Performs the TLS handshake over an established TCP connection
+fn tls_handshake(
+    account: &AccountConfig,
+    host: &str,
+    tcp: TcpStream,
+) -> Result<Box<dyn ImapConnection>> {
+    let tls = build_tls_connector(account)?;
+    let stream = tls
+        .connect(host, tcp)
+        .map_err(|e| anyhow::anyhow!("TLS handshake failed: {}", e))?;
+    Ok(Box::new(stream))
 }
 
 impl ImapClient {
-    /// Creates a new IMAP client and connects to Gmail
-    pub fn connect(email: &str, password: &str) -> Result<Self> {
-        crate::debug_log!("ImapClient::connect: connecting to imap.gmail.com:993");
-        let client = imap::ClientBuilder::new("imap.gmail.com", 993)
-            .connect()
-            .context("Failed to connect to IMAP server")?;
-
-        crate::debug_log!("ImapClient::connect: logging in as {}", email);
+    /// Creates a new IMAP client and connects to the account's IMAP server
+    pub fn connect(account: &AccountConfig) -> Result<Self> {
+        let host = account.imap_host();
+        let port = account.imap_port();
+        crate::debug_log!("ImapClient::connect: connecting to {}:{}", host, port);
+
+        let tcp = TcpStream::connect((host, port)).context("Failed to connect to IMAP server")?;
+        let client = match account.security {
+            Security::Tls => {
+                let stream = tls_handshake(account, host, tcp)?;
+                let mut client = imap::Client::new(stream);
+                client
+                    .read_greeting()
+                    .context("Failed to read IMAP greeting")?;
+                client
+            }
+            Security::StartTls => {
+                let mut tcp = tcp;
+                starttls_upgrade(&mut tcp)?;
+                let stream = tls_handshake(account, host, tcp)?;
+                // The greeting was already consumed before the upgrade
+                let mut client = imap::Client::new(stream);
+                client.greeting_read = true;
+                client
+            }
+            Security::None => {
+                crate::debug_log!("ImapClient::connect: using unencrypted connection");
+                let mut client = imap::Client::new(Box::new(tcp) as Box<dyn ImapConnection>);
+                client
+                    .read_greeting()
+                    .context("Failed to read IMAP greeting")?;
+                client
+            }
+        };
+
+        crate::debug_log!("ImapClient::connect: logging in as {}", account.email);
         let session = client
-            .login(email, password)
+            .login(&account.email, &account.app_password)
             .map_err(|e| anyhow::anyhow!("Login failed: {}", e.0))?;
 
         crate::debug_log!("ImapClient::connect: login successful");
-        Ok(Self { session })
+        let mut session = session;
+        let gmail_extensions = session
+            .capabilities()
+            .map(|caps| caps.has_str("X-GM-EXT-1"))
+            .unwrap_or(false);
+        crate::debug_log!(
+            "ImapClient::connect: gmail extensions {}",
+            if gmail_extensions {
+                "available"
+            } else {
+                "unavailable"
+            }
+        );
+
+        Ok(Self {
+            session,
+            gmail_extensions,
+            folders: account.backend.folders(),
+            alerts: Vec::new(),
+            selected: None,
+            exists: None,
+            changed_folders: Vec::new(),
+            already_gone: 0,
+            pgp_command: "gpg".to_string(),
+        })
+    }
+
+    /// The account backend's archive, trash and sent folders
+    pub fn folders(&self) -> Folders {
+        self.folders
     }
 
-    /// Parses an IMAP message into our Email struct
-    fn parse_message(&self, fetch: &imap::types::Fetch, source_folder: &str) -> Option<Email> {
+    /// Parses a fetched message into our Email struct
+    fn parse_message(&self, fetch: &FetchedMessage, source_folder: &str) -> Option<Email> {
         let uid = fetch.uid?;
-        let envelope = fetch.envelope()?;
-
-        // Extract From
-        let from = envelope.from.as_ref().and_then(|addrs| {
-            addrs.first().map(|addr| {
-                let name = addr
-                    .name
-                    .as_ref()
-                    .map(|n| String::from_utf8_lossy(n).to_string());
-                let mailbox = addr
-                    .mailbox
-                    .as_ref()
-                    .map(|m| String::from_utf8_lossy(m).to_string());
-                let host = addr
-                    .host
-                    .as_ref()
-                    .map(|h| String::from_utf8_lossy(h).to_string());
-
-                match (name, mailbox, host) {
-                    (Some(n), Some(m), Some(h)) => format!("{} <{}@{}>", n, m, h),
-                    (None, Some(m), Some(h)) => format!("{}@{}", m, h),
-                    _ => "unknown".to_string(),
-                }
-            })
-        })?;
+        let header = fetch.header.as_deref()?;
+        // Preview from the partial body text, falling back to the subject
+        let snippet = fetch
+            .text
+            .as_deref()
+            .and_then(|text| snippet_from_partial(header, text));
+
+        let mut builder = email_from_headers(uid.to_string(), header, snippet, source_folder)?
+            .flagged(fetch.flagged)
+            .seen(fetch.seen);
+        if let Some(thrid) = fetch.gmail_thread_id {
+            builder = builder.gmail_thread_id(thrid);
+        }
 
-        // Extract Subject
-        let subject = envelope
-            .subject
-            .as_ref()
-            .map(|s| decode_header_value(s))
-            .unwrap_or_default();
+        Some(builder.labels(fetch.labels.clone()).build())
+    }
 
-        // Extract Date
-        let date_str = envelope
-            .date
-            .as_ref()
-            .map(|d| String::from_utf8_lossy(d).to_string());
-        let date = date_str
-            .and_then(|s| parse_email_date(&s))
-            .unwrap_or_else(Utc::now);
+    /// Selects a folder, first noting any changes the server reported for the previous one
+    fn select(&mut self, folder: &str) -> Result<imap::types::Mailbox> {
+        self.collect_unsolicited();
+        let mailbox = self
+            .session
+            .select(folder)
+            .context(format!("Failed to select {}", folder))?;
+        self.selected = Some(folder.to_string());
+        self.exists = Some(mailbox.exists);
+        Ok(mailbox)
+    }
 
-        // Parse headers for Message-ID, In-Reply-To, and References
-        let (message_id, in_reply_to, references) = fetch
-            .header()
-            .map(parse_threading_headers)
-            .unwrap_or((None, None, Vec::new()));
+    /// Drops the UIDs the selected folder no longer has, counting them as already gone
+    fn existing_uids(&mut self, uids: &[u32]) -> Result<Vec<u32>> {
+        let sequence = format_uid_sequence(&extract_uid_ranges(uids));
+        let found = self
+            .session
+            .uid_search(format!("UID {}", sequence))
+            .context("Failed to check which emails still exist")?;
+        let existing = keep_found(uids, &found);
+        self.already_gone += uids.len() - existing.len();
+        Ok(existing)
+    }
 
-        // Create snippet from first part of subject for now
-        // (full body parsing would require fetching BODY[TEXT])
-        let snippet: String = subject.chars().take(100).collect();
+    /// Sends a UID MOVE command and returns the COPYUID mapping if available
+    ///
+    /// This uses the IMAP MOVE extension (RFC 6851) combined with UIDPLUS (RFC 4315)
+    /// to atomically move messages and get their new UIDs in the destination folder.
+    fn uid_move_with_copyuid(
+        &mut self,
+        uids: &str,
+        dest_mailbox: &str,
+    ) -> Result<HashMap<String, u32>> {
+        // Escape the mailbox name if it contains special characters
+        let escaped_mailbox = if dest_mailbox.contains(' ') || dest_mailbox.contains('"') {
+            format!("\"{}\"", dest_mailbox.replace('"', "\\\""))
+        } else {
+            dest_mailbox.to_string()
+        };
+
+        let command = format!("UID MOVE {} {}", uids, escaped_mailbox);
+        crate::debug_log!("uid_move_with_copyuid: {}", command);
 
-        let mut builder = EmailBuilder::new()
-            .id(uid.to_string())
-            .from(from)
-            .subject(subject)
-            .snippet(snippet)
-            .date(date)
-            .references(references)
-            .source_folder(source_folder);
+        // Use run() to get the full response including the tagged OK line with COPYUID
+        let (response, tagged_start) = self
+            .session
+            .run(&command)
+            .context("UID MOVE command failed")?;
+        self.alerts.extend(parse_alerts(&response));
+        // The moved messages' EXPUNGEs are in the raw response, so the count is unknown
+        self.exists = None;
+
+        // Parse the tagged response portion (which contains COPYUID)
+        let tagged_response = &response[tagged_start..];
+        let uid_map = parse_copyuid_response(tagged_response);
+        crate::debug_log!("uid_move_with_copyuid: got {} UID mappings", uid_map.len());
 
-        if let Some(msg_id) = message_id {
-            builder = builder.message_id(msg_id);
+        Ok(uid_map)
+    }
+
+    /// Sorts the server's untagged responses into alerts and changes made to the
+    /// selected folder by other clients
+    fn collect_unsolicited(&mut self) {
+        use imap::types::UnsolicitedResponse;
+        use imap_proto::types::ResponseCode;
+
+        let mut changed = false;
+        for response in self.session.take_all_unsolicited() {
+            match response {
+                UnsolicitedResponse::Ok {
+                    code: Some(ResponseCode::Alert),
+                    information: Some(text),
+                }
+                | UnsolicitedResponse::Bye {
+                    code: Some(ResponseCode::Alert),
+                    information: Some(text),
+                } if !text.trim().is_empty() => {
+                    self.alerts.push(text.trim().to_string());
+                }
+                UnsolicitedResponse::Exists(count) => changed |= self.note_exists(count),
+                UnsolicitedResponse::Expunge(_) | UnsolicitedResponse::Vanished { .. } => {
+                    self.exists = None;
+                    changed = true;
+                }
+                _ => {}
+            }
         }
-        if let Some(reply_to) = in_reply_to {
-            builder = builder.in_reply_to(reply_to);
+        if changed {
+            self.mark_selected_changed();
         }
+    }
 
-        Some(builder.build())
+    /// Records the selected folder's message count, returning true when it isn't the
+    /// count this session expected: mail arrived or left elsewhere
+    fn note_exists(&mut self, count: u32) -> bool {
+        let changed = self.exists.is_some_and(|known| known != count);
+        self.exists = Some(count);
+        changed
+    }
+
+    /// Notes that another client changed the selected folder
+    fn mark_selected_changed(&mut self) {
+        if let Some(folder) = &self.selected
+            && !self.changed_folders.contains(folder)
+        {
+            self.changed_folders.push(folder.clone());
+        }
+    }
+
+    /// Returns the UIDVALIDITY of INBOX and the sent folder. A server that renumbers a
+    /// folder's messages changes it, after which UIDs read before mean other messages.
+    pub fn uid_validity(&mut self) -> Result<HashMap<String, u32>> {
+        let mut values = HashMap::new();
+        for folder in ["INBOX", self.folders.sent] {
+            let status = self
+                .session
+                .status(folder, "(UIDVALIDITY)")
+                .context(format!("Failed to check {}", folder))?;
+            if let Some(value) = status.uid_validity {
+                values.insert(folder.to_string(), value);
+            }
+        }
+        Ok(values)
     }
 
     /// Gets the message count for a folder without fetching all messages
     pub fn get_folder_count(&mut self, folder: &str) -> Result<u32> {
-        let mailbox = self
-            .session
-            .select(folder)
-            .context(format!("Failed to select {}", folder))?;
+        let mailbox = self.select(folder)?;
         Ok(mailbox.exists)
     }
 
@@ -298,44 +771,101 @@ impl ImapClient {
         self.fetch_folder_range("INBOX", start, end, progress)
     }
 
-    /// Fetches sent emails within a sequence range (inclusive)
+    /// Fetches emails from a folder within a sequence range (inclusive)
     /// If a progress counter is provided, it will be incremented for each email parsed
-    pub fn fetch_sent_range(
+    pub fn fetch_folder_range(
         &mut self,
+        folder: &str,
         start: u32,
         end: u32,
         progress: Option<&Arc<AtomicUsize>>,
     ) -> Result<Vec<Email>> {
-        self.fetch_folder_range("[Gmail]/Sent Mail", start, end, progress)
+        if start > end || start == 0 {
+            return Ok(Vec::new());
+        }
+
+        self.select(folder)?;
+
+        self.fetch_messages(folder, &format!("{}:{}", start, end), false, progress)
     }
 
-    /// Fetches emails from a folder within a sequence range (inclusive)
-    fn fetch_folder_range(
+    /// Returns the UIDs in a folder matching an IMAP search such as "SINCE 15-Apr-2026"
+    pub fn search_folder_uids(&mut self, folder: &str, query: &str) -> Result<Vec<u32>> {
+        self.select(folder)?;
+
+        let mut uids: Vec<u32> = self
+            .session
+            .uid_search(query)
+            .context(format!("Failed to search {} ({})", folder, query))?
+            .into_iter()
+            .collect();
+        uids.sort_unstable();
+        Ok(uids)
+    }
+
+    /// Fetches specific emails from a folder by UID
+    /// If a progress counter is provided, it will be incremented for each email parsed
+    pub fn fetch_folder_uids(
         &mut self,
         folder: &str,
-        start: u32,
-        end: u32,
+        uids: &[u32],
         progress: Option<&Arc<AtomicUsize>>,
     ) -> Result<Vec<Email>> {
-        if start > end || start == 0 {
+        if uids.is_empty() {
             return Ok(Vec::new());
         }
 
-        self.session
-            .select(folder)
-            .context(format!("Failed to select {}", folder))?;
+        self.select(folder)?;
 
-        let sequence = format!("{}:{}", start, end);
-        let messages = self
+        let uid_set = format_uid_sequence(&extract_uid_ranges(uids));
+        self.fetch_messages(folder, &uid_set, true, progress)
+    }
+
+    /// Fetches and parses the messages in a sequence set (or UID set) of the selected folder
+    fn fetch_messages(
+        &mut self,
+        folder: &str,
+        sequence: &str,
+        by_uid: bool,
+        progress: Option<&Arc<AtomicUsize>>,
+    ) -> Result<Vec<Email>> {
+        // Only the header fields the lists use, and the first bytes of the body for the
+        // snippet. Gmail's conversation IDs and labels come in the same response, so each
+        // chunk takes one round trip.
+        let command = if by_uid { "UID FETCH" } else { "FETCH" };
+        let gmail = if self.gmail_extensions {
+            " X-GM-THRID X-GM-LABELS"
+        } else {
+            ""
+        };
+        let query = format!(
+            "(UID FLAGS{} BODY.PEEK[HEADER.FIELDS ({})] BODY.PEEK[TEXT]<0.{}>)",
+            gmail, LIST_HEADER_FIELDS, SNIPPET_FETCH_BYTES
+        );
+        self.collect_unsolicited();
+        let (response, _) = self
             .session
-            .fetch(&sequence, "(UID ENVELOPE BODY.PEEK[HEADER])")
+            .run(format!("{} {} {}", command, sequence, query))
             .context(format!(
                 "Failed to fetch messages from {} ({})",
                 folder, sequence
             ))?;
+        self.alerts.extend(parse_alerts(&response));
+
+        let fetched = parse_fetch_response(&response);
+        let mut changed = fetched.expunged;
+        if let Some(count) = fetched.exists {
+            changed |= self.note_exists(count);
+        }
+        if fetched.expunged {
+            self.exists = None;
+        }
+        if changed {
+            self.mark_selected_changed();
+        }
 
         let mut emails = Vec::new();
-        for msg in messages.iter() {
+        for msg in &fetched.messages {
             if let Some(email) = self.parse_message(msg, folder) {
                 emails.push(email);
                 if let Some(counter) = progress {
@@ -347,6 +877,68 @@ impl ImapClient {
         Ok(emails)
     }
 
+    /// Sets the gpg command used for encrypted and signed message bodies
+    pub fn set_pgp_command(&mut self, command: String) {
+        self.pgp_command = command;
+    }
+
+    /// Takes the `[ALERT]` notices the server has sent since the last call,
+    /// such as quota warnings or notice that the account is about to be disabled
+    pub fn take_alerts(&mut self) -> Vec<String> {
+        self.collect_unsolicited();
+        std::mem::take(&mut self.alerts)
+    }
+
+    /// Asks the server to report changes to the selected folder (NOOP)
+    pub fn poll(&mut self) -> Result<()> {
+        self.session
+            .noop()
+            .context("Failed to check for mailbox changes")
+    }
+
+    /// Whether the server supports IDLE (RFC 2177)
+    pub fn supports_idle(&mut self) -> bool {
+        self.session
+            .capabilities()
+            .map(|caps| caps.has_str("IDLE"))
+            .unwrap_or(false)
+    }
+
+    /// Waits in IDLE on the inbox until new mail arrives or `timeout` passes, returning
+    /// true when mail arrived. Mail leaving the inbox, such as this client's own archives,
+    /// doesn't end the wait.
+    pub fn wait_for_new_mail(&mut self, timeout: Duration) -> Result<bool> {
+        if self.selected.as_deref() != Some("INBOX") {
+            self.select("INBOX")?;
+        }
+        let mut known = self.exists.unwrap_or(0);
+        let mut arrived = false;
+        self.session
+            .idle()
+            .timeout(timeout)
+            .keepalive(false)
+            .wait_while(|response| {
+                arrived = note_idle_response(&mut known, &response);
+                !arrived
+            })
+            .context("Failed to wait for new mail")?;
+        self.exists = Some(known);
+        Ok(arrived)
+    }
+
+    /// Takes the folders another client has added mail to or removed mail from
+    /// since the last call
+    pub fn take_mailbox_changes(&mut self) -> Vec<String> {
+        self.collect_unsolicited();
+        std::mem::take(&mut self.changed_folders)
+    }
+
+    /// Takes how many emails archives and deletes skipped since the last call because
+    /// another client had already moved or deleted them
+    pub fn take_already_gone(&mut self) -> usize {
+        std::mem::take(&mut self.already_gone)
+    }
+
     /// Logs out and closes the connection
     pub fn logout(mut self) -> Result<()> {
         self.session.logout().context("Failed to logout")?;
@@ -355,7 +947,12 @@ impl ImapClient {
 }
 
 impl EmailClient for ImapClient {
-    fn archive_batch(&mut self, uids: &[String], folder: &str) -> Result<HashMap<String, u32>> {
+    fn archive_batch(
+        &mut self,
+        uids: &[String],
+        folder: &str,
+        mark_read: bool,
+    ) -> Result<HashMap<String, u32>> {
         if uids.is_empty() {
             return Ok(HashMap::new());
         }
@@ -366,12 +963,14 @@ impl EmailClient for ImapClient {
             folder
         );
 
-        self.session
-            .select(folder)
-            .context(format!("Failed to select {}", folder))?;
+        self.select(folder)?;
 
-        // Convert to u32, extract ranges, and format as sequence
+        // Convert to u32, skip the ones another client moved, and format as ranges
         let uid_values: Vec<u32> = uids.iter().filter_map(|s| s.parse().ok()).collect();
+        let uid_values = self.existing_uids(&uid_values)?;
+        if uid_values.is_empty() {
+            return Ok(HashMap::new());
+        }
         let ranges = extract_uid_ranges(&uid_values);
         let uid_sequence = format_uid_sequence(&ranges);
 
@@ -382,7 +981,14 @@ impl EmailClient for ImapClient {
             uid_sequence
         );
 
-        let uid_map = uid_move_with_copyuid(&mut self.session, &uid_sequence, "[Gmail]/All Mail")
+        if mark_read {
+            self.session
+                .uid_store(&uid_sequence, "+FLAGS.SILENT (\\Seen)")
+                .context("Failed to mark emails as read")?;
+        }
+
+        let uid_map = self
+            .uid_move_with_copyuid(&uid_sequence, self.folders.archive)
             .context("Failed to archive emails")?;
 
         crate::debug_log!("archive_batch: done, got {} UID mappings", uid_map.len());
@@ -400,12 +1006,14 @@ impl EmailClient for ImapClient {
             folder
         );
 
-        self.session
-            .select(folder)
-            .context(format!("Failed to select {}", folder))?;
+        self.select(folder)?;
 
-        // Convert to u32, extract ranges, and format as sequence
+        // Convert to u32, skip the ones another client moved, and format as ranges
         let uid_values: Vec<u32> = uids.iter().filter_map(|s| s.parse().ok()).collect();
+        let uid_values = self.existing_uids(&uid_values)?;
+        if uid_values.is_empty() {
+            return Ok(HashMap::new());
+        }
         let ranges = extract_uid_ranges(&uid_values);
         let uid_sequence = format_uid_sequence(&ranges);
 
@@ -416,13 +1024,66 @@ impl EmailClient for ImapClient {
             uid_sequence
         );
 
-        let uid_map = uid_move_with_copyuid(&mut self.session, &uid_sequence, "[Gmail]/Trash")
+        let uid_map = self
+            .uid_move_with_copyuid(&uid_sequence, self.folders.trash)
             .context("Failed to delete emails")?;
 
         crate::debug_log!("delete_batch: done, got {} UID mappings", uid_map.len());
         Ok(uid_map)
     }
 
+    fn move_batch(
+        &mut self,
+        uids: &[String],
+        folder: &str,
+        dest: &str,
+        mark_read: bool,
+    ) -> Result<HashMap<String, u32>> {
+        let uid_values: Vec<u32> = uids.iter().filter_map(|s| s.parse().ok()).collect();
+        if uid_values.is_empty() {
+            return Ok(HashMap::new());
+        }
+        self.select(folder)?;
+
+        // Skip the ones another client moved
+        let uid_values = self.existing_uids(&uid_values)?;
+        if uid_values.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let uid_sequence = format_uid_sequence(&extract_uid_ranges(&uid_values));
+        crate::debug_log!(
+            "move_batch: {} from '{}' to '{}'",
+            uid_sequence,
+            folder,
+            dest
+        );
+        if mark_read {
+            self.session
+                .uid_store(&uid_sequence, "+FLAGS.SILENT (\\Seen)")
+                .context("Failed to mark emails as read")?;
+        }
+        self.uid_move_with_copyuid(&uid_sequence, dest)
+            .with_context(|| format!("Failed to move emails to {}", dest))
+    }
+
+    /// Lists every mailbox that can be selected, so `[Gmail]` and other pure parents
+    /// are left out
+    fn list_folders(&mut self) -> Result<Vec<String>> {
+        use imap_proto::types::NameAttribute;
+
+        let names = self
+            .session
+            .list(Some(""), Some("*"))
+            .context("Failed to list folders")?;
+        let mut folders: Vec<String> = names
+            .iter()
+            .filter(|name| !name.attributes().contains(&NameAttribute::NoSelect))
+            .map(|name| name.name().to_string())
+            .collect();
+        folders.sort();
+        Ok(folders)
+    }
+
     fn restore_emails(
         &mut self,
         emails: &[(Option<String>, Option<u32>, String, String)],
@@ -452,9 +1113,7 @@ impl EmailClient for ImapClient {
 
         // Execute one UID MOVE per contiguous range for each route
         for ((current_folder, dest_folder), uids) in routes {
-            self.session
-                .select(&current_folder)
-                .context(format!("Failed to select {}", current_folder))?;
+            self.select(&current_folder)?;
 
             let ranges = extract_uid_ranges(&uids);
             crate::debug_log!(
@@ -473,9 +1132,11 @@ impl EmailClient for ImapClient {
                 };
 
                 crate::debug_log!("restore_emails: UID MOVE {} to {}", uid_range, dest_folder);
-                uid_move_with_copyuid(&mut self.session, &uid_range, &dest_folder).context(
-                    format!("Failed to restore UIDs {} to {}", uid_range, dest_folder),
-                )?;
+                self.uid_move_with_copyuid(&uid_range, &dest_folder)
+                    .context(format!(
+                        "Failed to restore UIDs {} to {}",
+                        uid_range, dest_folder
+                    ))?;
 
                 // Report progress: count emails in this range
                 let range_count = (end - start + 1) as usize;
@@ -490,9 +1151,7 @@ impl EmailClient for ImapClient {
         for (msg_id, current_folder, dest_folder) in &fallback {
             // Select the folder if needed
             if current_selected != Some(current_folder.as_str()) {
-                self.session
-                    .select(current_folder)
-                    .context(format!("Failed to select {}", current_folder))?;
+                self.select(current_folder)?;
                 current_selected = Some(current_folder.as_str());
             }
 
@@ -510,6 +1169,8 @@ impl EmailClient for ImapClient {
                         "Failed to restore email {} to {}",
                         msg_id, dest_folder
                     ))?;
+                // The EXPUNGE for this move isn't reported back, so the count is unknown
+                self.exists = None;
             }
             // If email not found, it may have been permanently deleted or already moved
             // Continue with other emails rather than failing entirely
@@ -523,61 +1184,221 @@ impl EmailClient for ImapClient {
         Ok(())
     }
 
-    fn fetch_email_body(&mut self, uid: &str, folder: &str) -> Result<String> {
+    fn fetch_email_body(
+        &mut self,
+        uid: &str,
+        folder: &str,
+        max_bytes: Option<u32>,
+        mark_seen: bool,
+    ) -> Result<FetchedBody> {
         crate::debug_log!("fetch_email_body: fetching UID {} from {}", uid, folder);
 
-        self.session
-            .select(folder)
-            .context(format!("Failed to select {}", folder))?;
+        self.select(folder)?;
 
-        // Fetch the full message body (BODY.PEEK[] to avoid marking as read)
+        let query = body_fetch_query(max_bytes, mark_seen);
         let messages = self
             .session
-            .uid_fetch(uid, "BODY.PEEK[]")
+            .uid_fetch(uid, &query)
             .context("Failed to fetch email body")?;
 
         let message = messages.iter().next().context("Email not found")?;
 
         let body_bytes = message.body().context("Email has no body")?;
+        let truncated = is_truncated(message.size, max_bytes);
 
         // Parse the email using mailparse
         let parsed = mailparse::parse_mail(body_bytes).context("Failed to parse email")?;
 
-        // Extract text content, preferring plain text over HTML
-        let body_text = extract_body_text(&parsed)?;
+        // Decrypt or verify PGP/MIME mail; otherwise extract text, preferring plain text over HTML
+        let (body_text, html) = match pgp::body_text(&parsed, &self.pgp_command) {
+            Some(text) => (text, None),
+            None => (extract_body_text(&parsed)?, html_part(&parsed)),
+        };
 
         // Sanitize for terminal display
-        Ok(sanitize_for_terminal(&body_text))
+        Ok(FetchedBody {
+            text: sanitize_for_terminal(&body_text),
+            truncated,
+            html,
+        })
+    }
+
+    fn set_seen(&mut self, uids: &[String], folder: &str, seen: bool) -> Result<()> {
+        let uid_values: Vec<u32> = uids.iter().filter_map(|s| s.parse().ok()).collect();
+        if uid_values.is_empty() {
+            return Ok(());
+        }
+        self.select(folder)?;
+        let uid_sequence = format_uid_sequence(&extract_uid_ranges(&uid_values));
+        let query = if seen {
+            "+FLAGS.SILENT (\\Seen)"
+        } else {
+            "-FLAGS.SILENT (\\Seen)"
+        };
+        crate::debug_log!("set_seen: {} {} in {}", query, uid_sequence, folder);
+        self.session
+            .uid_store(&uid_sequence, query)
+            .context("Failed to change the read flag")?;
+        Ok(())
+    }
+
+    fn modify_labels(
+        &mut self,
+        uids: &[String],
+        folder: &str,
+        add: &[String],
+        remove: &[String],
+    ) -> Result<()> {
+        if !self.gmail_extensions {
+            bail!("This server doesn't support Gmail labels");
+        }
+        let uid_values: Vec<u32> = uids.iter().filter_map(|s| s.parse().ok()).collect();
+        if uid_values.is_empty() {
+            return Ok(());
+        }
+        self.select(folder)?;
+        let uid_sequence = format_uid_sequence(&extract_uid_ranges(&uid_values));
+        for (sign, labels) in [('+', add), ('-', remove)] {
+            if labels.is_empty() {
+                continue;
+            }
+            let query = format!("{}X-GM-LABELS.SILENT {}", sign, label_list(labels));
+            crate::debug_log!("modify_labels: {} {} in {}", query, uid_sequence, folder);
+            self.session
+                .uid_store(&uid_sequence, &query)
+                .context("Failed to change the labels")?;
+        }
+        Ok(())
+    }
+}
+
+/// Drops Gmail's system labels (\Inbox, \Important, ...) and sorts the rest by name
+fn user_labels(labels: &[Cow<'_, str>]) -> Vec<String> {
+    let mut labels: Vec<String> = labels
+        .iter()
+        .filter(|label| !label.starts_with('\\'))
+        .map(|label| label.to_string())
+        .collect();
+    labels.sort();
+    labels
+}
+
+/// Formats labels as a parenthesized list of quoted strings for X-GM-LABELS
+fn label_list(labels: &[String]) -> String {
+    let quoted: Vec<String> = labels
+        .iter()
+        .map(|label| format!("\"{}\"", label.replace('\\', "\\\\").replace('"', "\\\"")))
+        .collect();
+    format!("({})", quoted.join(" "))
+}
+
+/// Builds the FETCH query for a message body
+///
+/// BODY.PEEK[] leaves the \Seen flag alone; BODY[] marks the message as read.
+/// With max_bytes, only the first part is fetched so a huge message can't stall the view.
+fn body_fetch_query(max_bytes: Option<u32>, mark_seen: bool) -> String {
+    let section = if mark_seen { "BODY[]" } else { "BODY.PEEK[]" };
+    match max_bytes {
+        Some(limit) => format!("(RFC822.SIZE {}<0.{}>)", section, limit),
+        None => section.to_string(),
     }
 }
 
-/// Extracts the text body from a parsed email, preferring text/plain over text/html
-fn extract_body_text(mail: &mailparse::ParsedMail) -> Result<String> {
-    // If it's a multipart message, search for the best text part
-    if !mail.subparts.is_empty() {
-        // First try to find text/plain
-        if let Some(text) = find_part_by_type(mail, "text/plain") {
-            return Ok(text);
+/// Whether a message of `size` bytes was cut short by a `max_bytes` partial fetch
+fn is_truncated(size: Option<u32>, max_bytes: Option<u32>) -> bool {
+    matches!((size, max_bytes), (Some(size), Some(limit)) if size > limit)
+}
+
+/// Tracks the inbox's message count through an IDLE response, returning true when it
+/// says new mail arrived
+fn note_idle_response(known: &mut u32, response: &imap::types::UnsolicitedResponse) -> bool {
+    use imap::types::UnsolicitedResponse;
+
+    match response {
+        UnsolicitedResponse::Exists(count) => {
+            let arrived = *count > *known;
+            *known = *count;
+            arrived
         }
-        // Fall back to text/html (converted to text)
-        if let Some(html) = find_part_by_type(mail, "text/html") {
-            return Ok(html_to_text(&html));
+        UnsolicitedResponse::Expunge(_) => {
+            *known = known.saturating_sub(1);
+            false
         }
-        // If no text parts found, return a message
-        return Ok("[No text content found]".to_string());
+        _ => false,
     }
+}
 
-    // Single part message
-    let content_type = mail.ctype.mimetype.to_lowercase();
-    let body = mail.get_body().context("Failed to get email body")?;
+/// The UIDs a search found, in their original order
+fn keep_found(uids: &[u32], found: &HashSet<u32>) -> Vec<u32> {
+    uids.iter()
+        .copied()
+        .filter(|uid| found.contains(uid))
+        .collect()
+}
 
-    if content_type.starts_with("text/plain") {
-        Ok(body)
-    } else if content_type.starts_with("text/html") {
-        Ok(html_to_text(&body))
-    } else {
-        Ok(format!("[Content type: {}]", content_type))
+/// Builds an email from its raw list headers (see LIST_HEADER_FIELDS), leaving the flags
+/// and the server's thread ID to the caller. Without a snippet the subject is shown.
+/// Returns None without a From address.
+pub(crate) fn email_from_headers(
+    id: String,
+    header: &[u8],
+    snippet: Option<String>,
+    source_folder: &str,
+) -> Option<EmailBuilder> {
+    let ListHeaders {
+        from,
+        to,
+        cc,
+        subject,
+        date,
+        unsubscribe,
+    } = parse_list_headers(header)?;
+    let date = date.unwrap_or_else(Utc::now);
+
+    // Parse headers for Message-ID, In-Reply-To, and References
+    let (message_id, in_reply_to, references) = parse_threading_headers(header);
+    let snippet = snippet.unwrap_or_else(|| subject.chars().take(SNIPPET_CHARS).collect());
+
+    let mut builder = EmailBuilder::new()
+        .id(id)
+        .from(from)
+        .subject(subject)
+        .snippet(snippet)
+        .date(date)
+        .references(references)
+        .to(to)
+        .cc(cc)
+        .mailing_list(has_mailing_list_headers(header))
+        .unsubscribe(unsubscribe)
+        .auth(AuthResults::parse(header))
+        .spam_score(parse_spam_score(header))
+        .source_folder(source_folder);
+
+    if let Some(msg_id) = message_id {
+        builder = builder.message_id(msg_id);
+    }
+    if let Some(reply_to) = in_reply_to {
+        builder = builder.in_reply_to(reply_to);
     }
+    Some(builder)
+}
+
+/// Builds a one-line preview from the headers and the first bytes of the body text.
+/// The text may be cut off mid-part, so parts that no longer decode are skipped.
+fn snippet_from_partial(header: &[u8], text: &[u8]) -> Option<String> {
+    let raw = [header, text].concat();
+    let parsed = mailparse::parse_mail(&raw).ok()?;
+    let body = find_part_by_type(&parsed, "text/plain")
+        .or_else(|| find_part_by_type(&parsed, "text/html").map(|html| html_to_text(&html)))?;
+
+    let snippet: String = sanitize_for_terminal(&body)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .take(SNIPPET_CHARS)
+        .collect();
+    (!snippet.is_empty()).then_some(snippet)
 }
 
 /// Recursively searches for a part with the given MIME type
@@ -597,16 +1418,11 @@ fn find_part_by_type(mail: &mailparse::ParsedMail, mime_type: &str) -> Option<St
     None
 }
 
-/// Converts HTML to plain text using html2text
-fn html_to_text(html: &str) -> String {
-    html2text::from_read(html.as_bytes(), 80)
-}
-
 /// Sanitizes text for safe terminal display
 /// - Strips ANSI escape sequences
 /// - Removes control characters except newline and tab
 /// - Preserves Unicode
-fn sanitize_for_terminal(text: &str) -> String {
+pub(crate) fn sanitize_for_terminal(text: &str) -> String {
     let mut result = String::with_capacity(text.len());
 
     let mut chars = text.chars().peekable();
@@ -637,19 +1453,65 @@ fn sanitize_for_terminal(text: &str) -> String {
     result
 }
 
-/// Decodes a potentially MIME-encoded header value
-fn decode_header_value(value: &[u8]) -> String {
-    // Try to parse as MIME encoded-word
-    let value_str = String::from_utf8_lossy(value);
+/// The fields of a message's headers shown in the lists
+struct ListHeaders {
+    from: String,
+    to: Vec<String>,
+    cc: Vec<String>,
+    subject: String,
+    date: Option<DateTime<Utc>>,
+    unsubscribe: Vec<String>,
+}
 
-    // Handle =?charset?encoding?text?= format
-    if value_str.contains("=?")
-        && let Ok(decoded) = mailparse::parse_header(format!("X: {}", value_str).as_bytes())
-    {
-        return decoded.0.get_value();
-    }
+/// Reads the addresses, subject, date and unsubscribe links from fetched header fields. Returns None
+/// without a From address, as such messages can't be grouped by sender.
+fn parse_list_headers(header: &[u8]) -> Option<ListHeaders> {
+    use mailparse::MailHeaderMap;
+
+    let (headers, _) = mailparse::parse_headers(header).ok()?;
+    let addresses = |key: &str| -> Vec<String> {
+        headers
+            .get_all_headers(key)
+            .into_iter()
+            .flat_map(format_addresses)
+            .collect()
+    };
+    Some(ListHeaders {
+        from: addresses("From").into_iter().next()?,
+        to: addresses("To"),
+        cc: addresses("Cc"),
+        subject: headers.get_first_value("Subject").unwrap_or_default(),
+        date: headers
+            .get_first_value("Date")
+            .and_then(|d| parse_email_date(d.trim())),
+        unsubscribe: headers
+            .get_first_value("List-Unsubscribe")
+            .map_or_else(Vec::new, |value| parse_list_unsubscribe(&value)),
+    })
+}
 
-    value_str.to_string()
+/// Formats the addresses in an address header as "Name <mailbox@host>" or
+/// "mailbox@host", listing the members of groups
+fn format_addresses(header: &mailparse::MailHeader) -> Vec<String> {
+    let Ok(list) = mailparse::addrparse_header(header) else {
+        // Keep a malformed address readable rather than dropping the message
+        let raw = header.get_value().trim().to_string();
+        return if raw.is_empty() {
+            Vec::new()
+        } else {
+            vec![raw]
+        };
+    };
+    let format = |info: &mailparse::SingleInfo| match &info.display_name {
+        Some(name) => format!("{} <{}>", name, info.addr),
+        None => info.addr.clone(),
+    };
+    list.iter()
+        .flat_map(|addr| match addr {
+            mailparse::MailAddr::Single(info) => vec![format(info)],
+            mailparse::MailAddr::Group(group) => group.addrs.iter().map(format).collect(),
+        })
+        .collect()
 }
 
 /// Parses Message-ID, In-Reply-To, and References headers from raw header bytes
@@ -678,8 +1540,38 @@ fn parse_threading_headers(headers: &[u8]) -> (Option<String>, Option<String>, V
     (message_id, in_reply_to, references)
 }
 
+/// Checks raw headers for List-Id or List-Unsubscribe, which mark mailing-list mail
+fn has_mailing_list_headers(headers: &[u8]) -> bool {
+    String::from_utf8_lossy(headers).lines().any(|line| {
+        let line_lower = line.to_lowercase();
+        line_lower.starts_with("list-id:") || line_lower.starts_with("list-unsubscribe:")
+    })
+}
+
+/// Reads the spam score from SpamAssassin (X-Spam-Score, X-Spam-Status "score=")
+/// or Rspamd (X-Rspamd-Score, X-Spamd-Result "[score / required]") headers
+pub(crate) fn parse_spam_score(headers: &[u8]) -> Option<f32> {
+    let (headers, _) = mailparse::parse_headers(headers).ok()?;
+    let number = |s: &str| {
+        s.trim()
+            .split(|c: char| c.is_whitespace() || c == ',' || c == ';')
+            .next()?
+            .parse::<f32>()
+            .ok()
+    };
+    headers.iter().find_map(|header| {
+        let value = header.get_value();
+        match header.get_key().to_lowercase().as_str() {
+            "x-spam-score" | "x-rspamd-score" => number(&value),
+            "x-spam-status" => number(value.split_once("score=")?.1),
+            "x-spamd-result" => number(value.split_once('[')?.1),
+            _ => None,
+        }
+    })
+}
+
 /// Parses a space-separated list of Message-IDs (used for References header)
-fn parse_message_id_list(s: &str) -> Vec<String> {
+pub(crate) fn parse_message_id_list(s: &str) -> Vec<String> {
     let mut ids = Vec::new();
     let mut current = String::new();
     let mut in_angle = false;
@@ -748,6 +1640,173 @@ mod tests {
     use super::*;
     use chrono::Datelike;
 
+    const TEST_CERT_PEM: &str = "\
+-----BEGIN CERTIFICATE-----\n\
+MIIBhzCCAS2gAwIBAgIUYEU1T81TqxQFRPbxhtlsr6/695cwCgYIKoZIzj0EAwIw\n\
+GDEWMBQGA1UEAwwNemVyb3Rlcm0tdGVzdDAgFw0yNjEwMTUwNjA2MzFaGA8yMTI2\n\
+MDkyMTA2MDYzMVowGDEWMBQGA1UEAwwNemVyb3Rlcm0tdGVzdDBZMBMGByqGSM49\n\
+AgEGCCqGSM49AwEHA0IABML+DW4R2vCZl80h/3oldNj+dfnvheoaf4DlSlmv6Kr8\n\
+j7c526DJaepTtySh0Ry2x0B2+SYV0iml/YfTwDoMVW6jUzBRMB0GA1UdDgQWBBQm\n\
+IgYw4XiIzBsrsRjc7yKE+qMN/TAfBgNVHSMEGDAWgBQmIgYw4XiIzBsrsRjc7yKE\n\
++qMN/TAPBgNVHRMBAf8EBTADAQH/MAoGCCqGSM49BAMCA0gAMEUCIQD1o37+hxTm\n\
+1WGfElJimZRjVmphxmj5xe+U+9Ra/MPz0gIgDbuo8s/f4RK8zD2HaMCBZmPUlkWp\n\
+J9jXmGW5FgXkyz8=\n\
+-----END CERTIFICATE-----\n\
+";
+
+    /// In-memory stream that replays canned server output and records writes
+    struct ScriptedStream {
+        input: std::io::Cursor<Vec<u8>>,
+        written: Vec<u8>,
+    }
+
+    impl ScriptedStream {
+        fn new(server_output: &str) -> Self {
+            Self {
+                input: std::io::Cursor::new(server_output.as_bytes().to_vec()),
+                written: Vec::new(),
+            }
+        }
+    }
+
+    impl Read for ScriptedStream {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.input.read(buf)
+        }
+    }
+
+    impl Write for ScriptedStream {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.written.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_starttls_upgrade_success() {
+        let mut stream = ScriptedStream::new(
+            "* OK Dovecot ready.\r\nz0 OK Begin TLS negotiation now.\r\nTLSDATA",
+        );
+        starttls_upgrade(&mut stream).unwrap();
+        assert_eq!(stream.written, b"z0 STARTTLS\r\n");
+        // Nothing past the tagged response may be consumed
+        assert_eq!(stream.input.position(), 55);
+    }
+
+    #[test]
+    fn test_starttls_upgrade_skips_untagged_lines() {
+        let mut stream =
+            ScriptedStream::new("* OK ready\r\n* CAPABILITY IMAP4rev1 STARTTLS\r\nz0 OK go\r\n");
+        assert!(starttls_upgrade(&mut stream).is_ok());
+    }
+
+    #[test]
+    fn test_starttls_upgrade_rejected() {
+        let mut stream = ScriptedStream::new("* OK ready\r\nz0 BAD STARTTLS unavailable\r\n");
+        let err = starttls_upgrade(&mut stream).unwrap_err();
+        assert!(err.to_string().contains("rejected STARTTLS"));
+    }
+
+    #[test]
+    fn test_starttls_upgrade_bad_greeting() {
+        let mut stream = ScriptedStream::new("* BYE go away\r\n");
+        assert!(starttls_upgrade(&mut stream).is_err());
+    }
+
+    #[test]
+    fn test_parse_fetch_response() {
+        let response = b"* 1 FETCH (X-GM-THRID 1278455344230334865 UID 101 FLAGS (\\Seen) \
+BODY[HEADER.FIELDS (FROM SUBJECT)] {36}\r\nFrom: a@example.com\r\nSubject: Hi\r\n\r\n \
+BODY[TEXT]<0> {5}\r\nHello)\r\n\
+* 3 EXISTS\r\n\
+* 2 FETCH (UID 102 FLAGS (\\Flagged) X-GM-THRID 1278455344230334866)\r\n\
+a5 OK Success\r\n";
+        let parsed = parse_fetch_response(response);
+        assert_eq!(parsed.messages.len(), 2);
+        assert_eq!(parsed.exists, Some(3));
+        assert!(!parsed.expunged);
+
+        let first = &parsed.messages[0];
+        assert_eq!(first.uid, Some(101));
+        assert_eq!(first.gmail_thread_id, Some(1278455344230334865));
+        assert!(first.seen && !first.flagged);
+        assert_eq!(
+            first.header.as_deref(),
+            Some(&b"From: a@example.com\r\nSubject: Hi\r\n\r\n"[..])
+        );
+        assert_eq!(first.text.as_deref(), Some(&b"Hello"[..]));
+
+        let second = &parsed.messages[1];
+        assert_eq!(second.gmail_thread_id, Some(1278455344230334866));
+        assert!(second.flagged && !second.seen);
+        assert!(second.header.is_none());
+    }
+
+    #[test]
+    fn test_parse_fetch_response_keeps_user_labels() {
+        let response =
+            b"* 1 FETCH (UID 101 X-GM-LABELS (\\Inbox \"Work/Q3 plans\" \\Important Receipts))\r\n\
+a5 OK Success\r\n";
+        let parsed = parse_fetch_response(response);
+        assert_eq!(parsed.messages[0].labels, vec!["Receipts", "Work/Q3 plans"]);
+        assert_eq!(
+            label_list(&["Work".to_string(), "Say \"hi\"".to_string()]),
+            "(\"Work\" \"Say \\\"hi\\\"\")"
+        );
+    }
+
+    #[test]
+    fn test_parse_alerts() {
+        let response = b"* OK [ALERT] Your mailbox is 95% full\r\n\
+* 1 FETCH (X-GM-THRID 42 UID 101)\r\n\
+* OK Still here\r\n\
+a5 OK [ALERT] Account will be disabled soon \r\n";
+        assert_eq!(
+            parse_alerts(response),
+            vec![
+                "Your mailbox is 95% full".to_string(),
+                "Account will be disabled soon".to_string()
+            ]
+        );
+        assert!(parse_alerts(b"a5 OK Success\r\n").is_empty());
+    }
+
+    #[test]
+    fn test_parse_fetch_response_notes_expunges() {
+        let response = b"* 4 EXPUNGE\r\n* 1 FETCH (X-GM-THRID 42)\r\na5 OK Success\r\n";
+        let parsed = parse_fetch_response(response);
+        assert!(parsed.expunged);
+        assert_eq!(parsed.messages[0].uid, None);
+    }
+
+    #[test]
+    fn test_parse_pem_certificates_single() {
+        let certs = parse_pem_certificates(TEST_CERT_PEM.as_bytes()).unwrap();
+        assert_eq!(certs.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_pem_certificates_bundle() {
+        let bundle = format!("# comment\n{}\n{}", TEST_CERT_PEM, TEST_CERT_PEM);
+        let certs = parse_pem_certificates(bundle.as_bytes()).unwrap();
+        assert_eq!(certs.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_pem_certificates_empty_is_error() {
+        assert!(parse_pem_certificates(b"not a certificate").is_err());
+    }
+
+    #[test]
+    fn test_parse_pem_certificates_unterminated_is_error() {
+        let truncated = &TEST_CERT_PEM[..TEST_CERT_PEM.len() / 2];
+        assert!(parse_pem_certificates(truncated.as_bytes()).is_err());
+    }
+
     #[test]
     fn test_parse_email_date_rfc2822() {
         // January 25, 2026 is a Sunday
@@ -798,6 +1857,34 @@ mod tests {
         assert_eq!(refs, vec!["<msg1@example.com>", "<msg2@example.com>"]);
     }
 
+    #[test]
+    fn test_parse_spam_score() {
+        assert_eq!(
+            parse_spam_score(b"Subject: Hi\r\nX-Spam-Score: 5.2\r\n\r\n"),
+            Some(5.2)
+        );
+        assert_eq!(
+            parse_spam_score(b"X-Spam-Status: Yes, score=7.1 required=5.0 tests=BAYES_99\r\n\r\n"),
+            Some(7.1)
+        );
+        assert_eq!(
+            parse_spam_score(b"X-Spamd-Result: default: False [-0.40 / 15.00];\r\n\r\n"),
+            Some(-0.4)
+        );
+        assert_eq!(parse_spam_score(b"X-Rspamd-Score: 3.50\r\n\r\n"), Some(3.5));
+        assert_eq!(parse_spam_score(b"Subject: Hi\r\n\r\n"), None);
+    }
+
+    #[test]
+    fn test_has_mailing_list_headers() {
+        let list = b"From: news@example.com\r\nList-Unsubscribe: <mailto:u@example.com>\r\n";
+        assert!(has_mailing_list_headers(list));
+        let list_id = b"From: a@example.com\r\nlist-id: <dev.lists.example.com>\r\n";
+        assert!(has_mailing_list_headers(list_id));
+        let personal = b"From: alice@example.com\r\nSubject: Hi\r\n";
+        assert!(!has_mailing_list_headers(personal));
+    }
+
     #[test]
     fn test_parse_message_id_list() {
         let list = "<msg1@example.com> <msg2@example.com> <msg3@example.com>";
@@ -862,6 +1949,39 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_idle_counts_only_arrivals_as_new_mail() {
+        use imap::types::UnsolicitedResponse;
+
+        let mut known = 10;
+        // This client's own archive expunges two messages and reports the new count
+        assert!(!note_idle_response(
+            &mut known,
+            &UnsolicitedResponse::Expunge(4)
+        ));
+        assert!(!note_idle_response(
+            &mut known,
+            &UnsolicitedResponse::Expunge(4)
+        ));
+        assert!(!note_idle_response(
+            &mut known,
+            &UnsolicitedResponse::Exists(8)
+        ));
+        assert_eq!(known, 8);
+        assert!(note_idle_response(
+            &mut known,
+            &UnsolicitedResponse::Exists(9)
+        ));
+        assert_eq!(known, 9);
+    }
+
+    #[test]
+    fn test_keep_found_drops_uids_already_gone() {
+        let found: HashSet<u32> = [3, 5, 9].into_iter().collect();
+        assert_eq!(keep_found(&[9, 4, 3, 5], &found), vec![9, 3, 5]);
+        assert!(keep_found(&[1, 2], &found).is_empty());
+    }
+
     #[test]
     fn test_extract_uid_ranges_empty() {
         let ranges = extract_uid_ranges(&[]);
@@ -935,6 +2055,33 @@ mod tests {
         assert_eq!(format_uid_sequence(&ranges), "1,3,5");
     }
 
+    #[test]
+    fn test_snippet_from_partial_plain_text() {
+        let header = b"Subject: Hi\r\nContent-Type: text/plain\r\n\r\n";
+        let text = b"Hello there,\r\n\r\n  see you   tomorrow.\r\n";
+        assert_eq!(
+            snippet_from_partial(header, text).as_deref(),
+            Some("Hello there, see you tomorrow.")
+        );
+    }
+
+    #[test]
+    fn test_snippet_from_partial_truncated_multipart() {
+        let header = b"Content-Type: multipart/alternative; boundary=\"b\"\r\n\r\n";
+        // Cut off inside the HTML part, before the closing boundary
+        let text = b"--b\r\nContent-Type: text/plain\r\n\r\nPlain preview\r\n--b\r\nContent-Type: text/html\r\n\r\n<p>Cut";
+        assert_eq!(
+            snippet_from_partial(header, text).as_deref(),
+            Some("Plain preview")
+        );
+    }
+
+    #[test]
+    fn test_snippet_from_partial_without_text_part() {
+        let header = b"Content-Type: image/png\r\n\r\n";
+        assert_eq!(snippet_from_partial(header, b"\x89PNG"), None);
+    }
+
     #[test]
     fn test_sanitize_strips_ansi_escape() {
         let text = "Hello \x1b[31mred\x1b[0m world";
@@ -959,14 +2106,6 @@ mod tests {
         assert_eq!(sanitize_for_terminal(text), "Hello 日本語 émojis 🎉");
     }
 
-    #[test]
-    fn test_html_to_text_basic() {
-        let html = "<p>Hello <b>world</b></p>";
-        let text = html_to_text(html);
-        assert!(text.contains("Hello"));
-        assert!(text.contains("world"));
-    }
-
     #[test]
     fn test_mock_client_fetch_body() {
         let mut mock = MockEmailClient::new();
@@ -975,11 +2114,80 @@ mod tests {
             .with(
                 mockall::predicate::eq("123"),
                 mockall::predicate::eq("INBOX"),
+                mockall::predicate::eq(None),
+                mockall::predicate::eq(false),
             )
-            .returning(|_, _| Ok("Email body content".to_string()));
-
-        let result = mock.fetch_email_body("123", "INBOX");
+            .returning(|_, _, _, _| {
+                Ok(FetchedBody {
+                    text: "Email body content".to_string(),
+                    truncated: false,
+                    html: None,
+                })
+            });
+
+        let result = mock.fetch_email_body("123", "INBOX", None, false);
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), "Email body content");
+        assert_eq!(result.unwrap().text, "Email body content");
+    }
+
+    #[test]
+    fn test_body_fetch_query_peeks_unless_marking_seen() {
+        assert_eq!(body_fetch_query(None, false), "BODY.PEEK[]");
+        assert_eq!(body_fetch_query(None, true), "BODY[]");
+        assert_eq!(
+            body_fetch_query(Some(1024), false),
+            "(RFC822.SIZE BODY.PEEK[]<0.1024>)"
+        );
+        assert_eq!(
+            body_fetch_query(Some(1024), true),
+            "(RFC822.SIZE BODY[]<0.1024>)"
+        );
+    }
+
+    #[test]
+    fn test_is_truncated_only_when_message_exceeds_cap() {
+        assert!(is_truncated(Some(300_000), Some(262_144)));
+        assert!(!is_truncated(Some(1_000), Some(262_144)));
+        assert!(!is_truncated(Some(262_144), Some(262_144)));
+        assert!(!is_truncated(Some(300_000), None));
+        assert!(!is_truncated(None, Some(262_144)));
+    }
+
+    #[test]
+    fn test_format_addresses() {
+        let (header, _) =
+            mailparse::parse_header(b"To: Alice <alice@example.com>, bob@example.com").unwrap();
+        assert_eq!(
+            format_addresses(&header),
+            vec!["Alice <alice@example.com>", "bob@example.com"]
+        );
+    }
+
+    #[test]
+    fn test_parse_list_headers() {
+        let header = concat!(
+            "From: =?UTF-8?Q?Caf=C3=A9?= <news@example.com>\r\n",
+            "To: team: alice@example.com, bob@example.com;\r\n",
+            "Subject: =?UTF-8?B?SGVsbG8gd29ybGQ=?=\r\n",
+            "Date: Tue, 1 Jul 2025 10:52:37 +0200\r\n",
+            "List-Unsubscribe: <mailto:leave@example.com>,\r\n <https://example.com/leave>\r\n",
+            "\r\n",
+        );
+        let parsed = parse_list_headers(header.as_bytes()).unwrap();
+        assert_eq!(parsed.from, "Café <news@example.com>");
+        assert_eq!(parsed.to, vec!["alice@example.com", "bob@example.com"]);
+        assert!(parsed.cc.is_empty());
+        assert_eq!(parsed.subject, "Hello world");
+        assert_eq!(
+            parsed.date.map(|d| d.to_rfc3339()),
+            Some("2025-07-01T08:52:37+00:00".to_string())
+        );
+        assert_eq!(
+            parsed.unsubscribe,
+            vec!["mailto:leave@example.com", "https://example.com/leave"]
+        );
+
+        // Without a sender the message can't be grouped
+        assert!(parse_list_headers(b"Subject: Hi\r\n\r\n").is_none());
     }
 }
@@ -0,0 +1,188 @@
+//! Recognizes common IMAP failures and explains them with a suggested fix
+
+use std::io::ErrorKind;
+
+/// A failure the user can do something about
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImapFailure {
+    /// The server rejected the email address or password
+    WrongCredentials,
+    /// Gmail wants an app password (2-Step Verification is on, or the old one was revoked)
+    AppPasswordRequired,
+    /// IMAP access is turned off for the account
+    ImapDisabled,
+    /// The server couldn't be reached at all
+    NetworkUnreachable,
+    /// The server is refusing work because of too many connections or requests
+    RateLimited,
+}
+
+impl ImapFailure {
+    /// Works out which failure an error is, from its I/O error kind or the server's response text
+    pub fn classify(error: &anyhow::Error) -> Option<Self> {
+        let network_kind = error.chain().any(|cause| {
+            cause.downcast_ref::<std::io::Error>().is_some_and(|io| {
+                matches!(
+                    io.kind(),
+                    ErrorKind::ConnectionRefused
+                        | ErrorKind::TimedOut
+                        | ErrorKind::HostUnreachable
+                        | ErrorKind::NetworkUnreachable
+                        | ErrorKind::NetworkDown
+                        | ErrorKind::AddrNotAvailable
+                )
+            })
+        });
+        if network_kind {
+            return Some(ImapFailure::NetworkUnreachable);
+        }
+
+        let text = format!("{:#}", error).to_lowercase();
+        let has = |needles: &[&str]| needles.iter().any(|n| text.contains(n));
+        if has(&["not enabled for imap", "imap access is disabled"]) {
+            Some(ImapFailure::ImapDisabled)
+        } else if has(&[
+            "application-specific password required",
+            "web login required",
+            "[webalert]",
+        ]) {
+            Some(ImapFailure::AppPasswordRequired)
+        } else if has(&[
+            "[authenticationfailed]",
+            "invalid credentials",
+            "authentication failed",
+        ]) {
+            Some(ImapFailure::WrongCredentials)
+        } else if has(&[
+            "[throttled]",
+            "[limit]",
+            "too many simultaneous connections",
+            "exceeded command or bandwidth limits",
+            "rate limit",
+        ]) {
+            Some(ImapFailure::RateLimited)
+        } else if has(&[
+            "failed to lookup address",
+            "name or service not known",
+            "nodename nor servname",
+            "network is unreachable",
+            "connection refused",
+            "timed out",
+        ]) {
+            Some(ImapFailure::NetworkUnreachable)
+        } else {
+            None
+        }
+    }
+
+    /// What went wrong and how to fix it
+    pub fn message(self) -> &'static str {
+        match self {
+            ImapFailure::WrongCredentials => {
+                "The server rejected the email or app password. Check app_password in your \
+                 config; an app password stops working if it is revoked or your Google password changes."
+            }
+            ImapFailure::AppPasswordRequired => {
+                "Gmail needs an app password for this account. Create one at \
+                 https://myaccount.google.com/apppasswords and put it in app_password."
+            }
+            ImapFailure::ImapDisabled => {
+                "IMAP is turned off for this account. Enable it in Gmail under Settings → \
+                 Forwarding and POP/IMAP, then try again."
+            }
+            ImapFailure::NetworkUnreachable => {
+                "Couldn't reach the mail server. Check your internet connection, and imap_host \
+                 and imap_port if you set them."
+            }
+            ImapFailure::RateLimited => {
+                "The server is limiting connections or requests. Wait a few minutes, or lower \
+                 parallel_connections."
+            }
+        }
+    }
+}
+
+/// Describes an error for the user: the explanation and fix when the failure is
+/// recognized, or `prefix: error` otherwise
+pub fn describe(prefix: &str, error: &anyhow::Error) -> String {
+    match ImapFailure::classify(error) {
+        Some(failure) => failure.message().to_string(),
+        None => format!("{}: {:#}", prefix, error),
+    }
+}
+
+/// Like [`describe`], for messages that already say what was being done
+pub fn explain(error: &anyhow::Error) -> String {
+    match ImapFailure::classify(error) {
+        Some(failure) => failure.message().to_string(),
+        None => format!("{:#}", error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::{Context, anyhow};
+
+    #[test]
+    fn test_classifies_gmail_responses() {
+        let cases = [
+            (
+                "Login failed: No Response: [AUTHENTICATIONFAILED] Invalid credentials (Failure)",
+                ImapFailure::WrongCredentials,
+            ),
+            (
+                "Login failed: No Response: [ALERT] Application-specific password required: \
+                 https://support.google.com/accounts/answer/185833 (Failure)",
+                ImapFailure::AppPasswordRequired,
+            ),
+            (
+                "Login failed: No Response: [ALERT] Your account is not enabled for IMAP use.",
+                ImapFailure::ImapDisabled,
+            ),
+            (
+                "No Response: [THROTTLED] Account exceeded command or bandwidth limits.",
+                ImapFailure::RateLimited,
+            ),
+            (
+                "Bad Response: Too many simultaneous connections. (Failure)",
+                ImapFailure::RateLimited,
+            ),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(
+                ImapFailure::classify(&anyhow!(text.to_string())),
+                Some(expected),
+                "{}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn test_classifies_io_errors_as_network() {
+        let error = Err::<(), _>(std::io::Error::from(ErrorKind::ConnectionRefused))
+            .context("Failed to connect to IMAP server")
+            .unwrap_err();
+        assert_eq!(
+            ImapFailure::classify(&error),
+            Some(ImapFailure::NetworkUnreachable)
+        );
+    }
+
+    #[test]
+    fn test_describe_keeps_unrecognized_errors() {
+        let error = anyhow!("Bad Response: mailbox is corrupt");
+        assert_eq!(
+            describe("Failed to connect", &error),
+            "Failed to connect: Bad Response: mailbox is corrupt"
+        );
+        assert!(
+            describe(
+                "Error",
+                &anyhow!("[AUTHENTICATIONFAILED] Invalid credentials")
+            )
+            .starts_with("The server rejected")
+        );
+    }
+}
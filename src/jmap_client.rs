@@ -0,0 +1,670 @@
+//! JMAP (RFC 8620, RFC 8621) client for servers such as Fastmail, over curl (see
+//! `http`). Archive, delete and restore are one Email/set per batch instead
+//! of a COPY per folder range.
+
+use anyhow::{Context, Result, bail};
+use chrono::NaiveDate;
+use serde_json::{Map, Value, json};
+use std::collections::HashMap;
+
+use crate::config::{AccountConfig, Folders};
+use crate::email::{Email, html_to_text};
+use crate::http;
+use crate::imap_client::{
+    ApiClient, EmailClient, FetchedBody, LIST_HEADERS, SNIPPET_CHARS, email_from_headers,
+    sanitize_for_terminal,
+};
+
+/// Capabilities every request uses
+const USING: [&str; 2] = ["urn:ietf:params:jmap:core", "urn:ietf:params:jmap:mail"];
+/// Emails per Email/get or Email/set call, well under servers' maxObjectsInGet/Set
+const BATCH_SIZE: usize = 500;
+/// JMAP client for one account
+pub struct JmapClient {
+    /// API token, sent as a Bearer token
+    token: String,
+    api_url: String,
+    account_id: String,
+    /// Mailbox IDs by folder name: "INBOX" and the backend's archive, trash and sent folders
+    mailboxes: HashMap<&'static str, String>,
+    /// Mailbox IDs by name for every mailbox, the folders emails can be moved to
+    named: HashMap<String, String>,
+    folders: Folders,
+}
+
+/// Builds a JMAP request from (method, arguments) calls, numbering their call IDs
+fn request_json(calls: Vec<(&str, Value)>) -> String {
+    let method_calls: Vec<Value> = calls
+        .into_iter()
+        .enumerate()
+        .map(|(i, (method, args))| json!([method, args, i.to_string()]))
+        .collect();
+    json!({ "using": USING, "methodCalls": method_calls }).to_string()
+}
+
+/// Returns each method's response arguments in call order, failing on the first error
+fn parse_responses(response: &[u8]) -> Result<Vec<Value>> {
+    let response: Value =
+        serde_json::from_slice(response).context("Failed to parse the JMAP response")?;
+    let Some(responses) = response["methodResponses"].as_array() else {
+        bail!("The JMAP response has no methodResponses");
+    };
+    responses
+        .iter()
+        .map(|call| {
+            if call[0] == "error" {
+                bail!(
+                    "JMAP error: {}{}",
+                    call[1]["type"].as_str().unwrap_or("unknown"),
+                    call[1]["description"]
+                        .as_str()
+                        .map(|d| format!(" ({})", d))
+                        .unwrap_or_default()
+                );
+            }
+            Ok(call[1].clone())
+        })
+        .collect()
+}
+
+/// Finds the mailbox for each folder zeroterm uses, by role or else by name
+fn map_mailboxes(list: &[Value], folders: Folders) -> HashMap<&'static str, String> {
+    let roles = [
+        ("INBOX", "inbox"),
+        (folders.archive, "archive"),
+        (folders.trash, "trash"),
+        (folders.sent, "sent"),
+    ];
+    let mut mailboxes = HashMap::new();
+    for (folder, role) in roles {
+        let found = list
+            .iter()
+            .find(|m| m["role"] == role)
+            .or_else(|| {
+                list.iter().find(|m| {
+                    m["name"]
+                        .as_str()
+                        .is_some_and(|name| name.eq_ignore_ascii_case(folder))
+                })
+            })
+            .and_then(|m| m["id"].as_str());
+        if let Some(id) = found {
+            mailboxes.insert(folder, id.to_string());
+        }
+    }
+    mailboxes
+}
+
+/// Squashes a JMAP threadId into the u64 threading uses for Gmail's thread IDs (FNV-1a)
+fn thread_key(thread_id: &str) -> u64 {
+    thread_id.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// The Email/get properties the lists need
+fn list_properties() -> Vec<String> {
+    ["id", "threadId", "keywords", "preview"]
+        .iter()
+        .map(|p| p.to_string())
+        .chain(
+            LIST_HEADERS
+                .iter()
+                .map(|name| format!("header:{}:asRaw:all", name)),
+        )
+        .collect()
+}
+
+/// Parses an Email/get object into our Email struct, rebuilding the raw header block so
+/// it goes through the same parsing as IMAP mail
+fn parse_email(object: &Value, source_folder: &str) -> Option<Email> {
+    let id = object["id"].as_str()?;
+    let mut header = String::new();
+    for name in LIST_HEADERS {
+        let values = object[format!("header:{}:asRaw:all", name).as_str()].as_array();
+        for raw in values.into_iter().flatten().filter_map(Value::as_str) {
+            header.push_str(name);
+            header.push(':');
+            header.push_str(raw);
+            header.push_str("\r\n");
+        }
+    }
+    header.push_str("\r\n");
+
+    let snippet: String = object["preview"]
+        .as_str()
+        .map(|preview| {
+            sanitize_for_terminal(preview)
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .unwrap_or_default()
+        .chars()
+        .take(SNIPPET_CHARS)
+        .collect();
+    let keyword = |name: &str| object["keywords"][name].as_bool().unwrap_or(false);
+
+    let mut builder = email_from_headers(
+        id.to_string(),
+        header.as_bytes(),
+        (!snippet.is_empty()).then_some(snippet),
+        source_folder,
+    )?
+    .seen(keyword("$seen"))
+    .flagged(keyword("$flagged"));
+    if let Some(thread_id) = object["threadId"].as_str() {
+        builder = builder.gmail_thread_id(thread_key(thread_id));
+    }
+    Some(builder.build())
+}
+
+/// Reads the text of an Email/get object with body values: the text/plain parts, or the
+/// HTML converted to text. Returns the text and whether the server cut any part short.
+fn body_text(object: &Value) -> (String, bool) {
+    let mut parts = Vec::new();
+    let mut truncated = false;
+    for part in object["textBody"].as_array().into_iter().flatten() {
+        let Some(value) = part["partId"].as_str().map(|id| &object["bodyValues"][id]) else {
+            continue;
+        };
+        let Some(text) = value["value"].as_str() else {
+            continue;
+        };
+        truncated |= value["isTruncated"].as_bool().unwrap_or(false);
+        if part["type"]
+            .as_str()
+            .is_some_and(|t| t.eq_ignore_ascii_case("text/html"))
+        {
+            parts.push(html_to_text(text));
+        } else {
+            parts.push(text.to_string());
+        }
+    }
+    if parts.is_empty() {
+        return ("[No text content found]".to_string(), false);
+    }
+    (parts.join("\n"), truncated)
+}
+
+/// Reads the HTML parts of an Email/get object's htmlBody, for the structured HTML view.
+/// None when the email has no HTML.
+fn html_body(object: &Value) -> Option<String> {
+    let parts: Vec<&str> = object["htmlBody"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|part| {
+            part["type"]
+                .as_str()
+                .is_some_and(|t| t.eq_ignore_ascii_case("text/html"))
+        })
+        .filter_map(|part| part["partId"].as_str())
+        .filter_map(|id| object["bodyValues"][id]["value"].as_str())
+        .collect();
+    (!parts.is_empty()).then(|| parts.join("\n"))
+}
+
+/// The Email/set patch that moves an email to one mailbox, marking it read if asked
+fn move_patch(mailbox_id: &str, mark_read: bool) -> Value {
+    let mut patch = json!({ "mailboxIds": { mailbox_id: true } });
+    if mark_read {
+        patch["keywords/$seen"] = json!(true);
+    }
+    patch
+}
+
+/// Fails when an Email/set response lists emails it couldn't update
+fn check_updated(response: &Value) -> Result<()> {
+    let Some(failed) = response["notUpdated"].as_object().filter(|f| !f.is_empty()) else {
+        return Ok(());
+    };
+    let reason = failed
+        .values()
+        .next()
+        .and_then(|e| e["description"].as_str().or(e["type"].as_str()))
+        .unwrap_or("unknown error");
+    bail!("{} email(s) couldn't be moved: {}", failed.len(), reason)
+}
+
+impl JmapClient {
+    /// Fetches the JMAP session and finds the account's mailboxes
+    pub fn connect(account: &AccountConfig) -> Result<Self> {
+        let token = account.app_password.clone();
+        let session: Value = serde_json::from_slice(&http::send(
+            &http::bearer_config(account.jmap_url(), &token, None),
+            "JMAP",
+        )?)
+        .context("Failed to parse the JMAP session")?;
+        let api_url = session["apiUrl"]
+            .as_str()
+            .context("The JMAP session has no apiUrl")?
+            .to_string();
+        let account_id = session["primaryAccounts"]["urn:ietf:params:jmap:mail"]
+            .as_str()
+            .context("The JMAP session has no mail account")?
+            .to_string();
+
+        let mut client = Self {
+            token,
+            api_url,
+            account_id,
+            mailboxes: HashMap::new(),
+            named: HashMap::new(),
+            folders: account.backend.folders(),
+        };
+        let list = client.fetch_mailboxes()?;
+        client.mailboxes = map_mailboxes(&list, client.folders);
+        if !client.mailboxes.contains_key("INBOX") {
+            bail!("The JMAP account has no inbox");
+        }
+        Ok(client)
+    }
+
+    /// Sends one request and returns each method's response arguments
+    fn call(&self, calls: Vec<(&str, Value)>) -> Result<Vec<Value>> {
+        let body = request_json(calls);
+        parse_responses(&http::send(
+            &http::bearer_config(
+                &self.api_url,
+                &self.token,
+                Some(("application/json", &body)),
+            ),
+            "JMAP",
+        )?)
+    }
+
+    /// Fetches every mailbox, noting their IDs by name
+    fn fetch_mailboxes(&mut self) -> Result<Vec<Value>> {
+        let responses = self.call(vec![(
+            "Mailbox/get",
+            json!({
+                "accountId": self.account_id,
+                "ids": null,
+                "properties": ["id", "name", "role"],
+            }),
+        )])?;
+        let list = responses[0]["list"].as_array().cloned().unwrap_or_default();
+        self.named = list
+            .iter()
+            .filter_map(|m| {
+                Some((
+                    m["name"].as_str()?.to_string(),
+                    m["id"].as_str()?.to_string(),
+                ))
+            })
+            .collect();
+        Ok(list)
+    }
+
+    fn mailbox_id(&self, folder: &str) -> Result<&str> {
+        self.mailboxes
+            .get(folder)
+            .or_else(|| self.named.get(folder))
+            .map(String::as_str)
+            .with_context(|| format!("No {} mailbox on the JMAP server", folder))
+    }
+
+    /// Moves emails to a folder in one Email/set per batch
+    fn move_emails(&mut self, ids: &[String], folder: &str, mark_read: bool) -> Result<()> {
+        let patch = move_patch(self.mailbox_id(folder)?, mark_read);
+        for chunk in ids.chunks(BATCH_SIZE) {
+            let update: Map<String, Value> =
+                chunk.iter().map(|id| (id.clone(), patch.clone())).collect();
+            let responses = self.call(vec![(
+                "Email/set",
+                json!({ "accountId": self.account_id, "update": update }),
+            )])?;
+            check_updated(&responses[0])?;
+        }
+        Ok(())
+    }
+}
+
+impl ApiClient for JmapClient {
+    fn folders(&self) -> Folders {
+        self.folders
+    }
+
+    fn fetch_folder(
+        &mut self,
+        folder: &'static str,
+        since: Option<NaiveDate>,
+        before: Option<NaiveDate>,
+        skip: u32,
+        limit: Option<u32>,
+    ) -> Result<(Vec<Email>, usize)> {
+        let mut filter = json!({ "inMailbox": self.mailbox_id(folder)? });
+        if let Some(date) = since {
+            filter["after"] = json!(format!("{}T00:00:00Z", date));
+        }
+        if let Some(date) = before {
+            filter["before"] = json!(format!("{}T00:00:00Z", date));
+        }
+
+        let mut emails = Vec::new();
+        let mut position = skip as usize;
+        let end = limit.map(|limit| position + limit as usize);
+        loop {
+            let count = end.map_or(BATCH_SIZE, |end| (end - position).min(BATCH_SIZE));
+            if count == 0 {
+                break;
+            }
+            let responses = self.call(vec![
+                (
+                    "Email/query",
+                    json!({
+                        "accountId": self.account_id,
+                        "filter": filter,
+                        "sort": [{ "property": "receivedAt", "isAscending": false }],
+                        "position": position,
+                        "limit": count,
+                        "calculateTotal": true,
+                    }),
+                ),
+                (
+                    "Email/get",
+                    json!({
+                        "accountId": self.account_id,
+                        "#ids": { "resultOf": "0", "name": "Email/query", "path": "/ids" },
+                        "properties": list_properties(),
+                    }),
+                ),
+            ])?;
+            let total = responses[0]["total"].as_u64().unwrap_or(0) as usize;
+            let found = responses[0]["ids"].as_array().map_or(0, Vec::len);
+            emails.extend(
+                responses[1]["list"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|object| parse_email(object, folder)),
+            );
+            position += found;
+            if found < count || position >= total {
+                return Ok((emails, total.saturating_sub(position)));
+            }
+        }
+        // Stopped at the limit; ask for the total without fetching anything
+        let responses = self.call(vec![(
+            "Email/query",
+            json!({
+                "accountId": self.account_id,
+                "filter": filter,
+                "limit": 0,
+                "calculateTotal": true,
+            }),
+        )])?;
+        let total = responses[0]["total"].as_u64().unwrap_or(0) as usize;
+        Ok((emails, total.saturating_sub(position)))
+    }
+}
+
+impl EmailClient for JmapClient {
+    /// JMAP IDs don't change when an email moves, so there is no UID mapping to return
+    fn archive_batch(
+        &mut self,
+        uids: &[String],
+        _folder: &str,
+        mark_read: bool,
+    ) -> Result<HashMap<String, u32>> {
+        self.move_emails(uids, self.folders.archive, mark_read)?;
+        Ok(HashMap::new())
+    }
+
+    fn delete_batch(&mut self, uids: &[String], _folder: &str) -> Result<HashMap<String, u32>> {
+        self.move_emails(uids, self.folders.trash, false)?;
+        Ok(HashMap::new())
+    }
+
+    fn move_batch(
+        &mut self,
+        uids: &[String],
+        _folder: &str,
+        dest: &str,
+        mark_read: bool,
+    ) -> Result<HashMap<String, u32>> {
+        self.move_emails(uids, dest, mark_read)?;
+        Ok(HashMap::new())
+    }
+
+    fn list_folders(&mut self) -> Result<Vec<String>> {
+        self.fetch_mailboxes()?;
+        let mut folders: Vec<String> = self.named.keys().cloned().collect();
+        folders.sort();
+        Ok(folders)
+    }
+
+    /// Finds each email by Message-ID in the folder it was moved to, a batch of queries
+    /// per request, then moves them back
+    fn restore_emails(
+        &mut self,
+        emails: &[(Option<String>, Option<u32>, String, String)],
+        progress_tx: Option<std::sync::mpsc::Sender<usize>>,
+    ) -> Result<()> {
+        let mut by_route: HashMap<(&str, &str), Vec<&str>> = HashMap::new();
+        for (message_id, _, current_folder, dest_folder) in emails {
+            if let Some(message_id) = message_id {
+                by_route
+                    .entry((current_folder.as_str(), dest_folder.as_str()))
+                    .or_default()
+                    .push(message_id);
+            }
+        }
+
+        for ((current_folder, dest_folder), message_ids) in by_route {
+            let current = self.mailbox_id(current_folder)?.to_string();
+            for chunk in message_ids.chunks(BATCH_SIZE) {
+                let queries = chunk
+                    .iter()
+                    .map(|message_id| {
+                        let message_id = message_id.trim_matches(['<', '>']);
+                        (
+                            "Email/query",
+                            json!({
+                                "accountId": self.account_id,
+                                "filter": {
+                                    "inMailbox": current,
+                                    "header": ["Message-ID", message_id],
+                                },
+                                "limit": 1,
+                            }),
+                        )
+                    })
+                    .collect();
+                // An email that isn't found was deleted for good or moved elsewhere
+                let ids: Vec<String> = self
+                    .call(queries)?
+                    .iter()
+                    .filter_map(|response| response["ids"][0].as_str())
+                    .map(str::to_string)
+                    .collect();
+                self.move_emails(&ids, dest_folder, false)?;
+                if let Some(tx) = &progress_tx {
+                    let _ = tx.send(chunk.len());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Fetches the text body values, which the server cuts at max_bytes
+    fn fetch_email_body(
+        &mut self,
+        uid: &str,
+        folder: &str,
+        max_bytes: Option<u32>,
+        mark_seen: bool,
+    ) -> Result<FetchedBody> {
+        crate::debug_log!(
+            "fetch_email_body: fetching JMAP email {} from {}",
+            uid,
+            folder
+        );
+
+        let mut get = json!({
+            "accountId": self.account_id,
+            "ids": [uid],
+            "properties": ["textBody", "htmlBody", "bodyValues"],
+            "fetchTextBodyValues": true,
+            "fetchHTMLBodyValues": true,
+        });
+        if let Some(limit) = max_bytes {
+            get["maxBodyValueBytes"] = json!(limit);
+        }
+        let mut calls = vec![("Email/get", get)];
+        if mark_seen {
+            calls.push((
+                "Email/set",
+                json!({
+                    "accountId": self.account_id,
+                    "update": { uid: { "keywords/$seen": true } },
+                }),
+            ));
+        }
+        let responses = self.call(calls)?;
+        let object = responses[0]["list"].get(0).context("Email not found")?;
+        let (text, truncated) = body_text(object);
+        Ok(FetchedBody {
+            text: sanitize_for_terminal(&text),
+            truncated,
+            html: html_body(object),
+        })
+    }
+
+    fn set_seen(&mut self, uids: &[String], _folder: &str, seen: bool) -> Result<()> {
+        // A null keyword patch removes $seen
+        let patch = json!({ "keywords/$seen": if seen { json!(true) } else { Value::Null } });
+        for chunk in uids.chunks(BATCH_SIZE) {
+            let update: Map<String, Value> =
+                chunk.iter().map(|id| (id.clone(), patch.clone())).collect();
+            let responses = self.call(vec![(
+                "Email/set",
+                json!({ "accountId": self.account_id, "update": update }),
+            )])?;
+            check_updated(&responses[0])?;
+        }
+        Ok(())
+    }
+
+    fn modify_labels(
+        &mut self,
+        _uids: &[String],
+        _folder: &str,
+        _add: &[String],
+        _remove: &[String],
+    ) -> Result<()> {
+        bail!("Labels are only available on Gmail accounts")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_numbers_calls_and_errors_are_reported() {
+        let request: Value = serde_json::from_str(&request_json(vec![
+            ("Mailbox/get", json!({ "ids": null })),
+            ("Email/get", json!({})),
+        ]))
+        .unwrap();
+        assert_eq!(request["using"][1], "urn:ietf:params:jmap:mail");
+        assert_eq!(request["methodCalls"][1][0], "Email/get");
+        assert_eq!(request["methodCalls"][1][2], "1");
+
+        let ok = br#"{"methodResponses":[["Mailbox/get",{"list":[]},"0"]]}"#;
+        assert_eq!(parse_responses(ok).unwrap()[0]["list"], json!([]));
+        let error = br#"{"methodResponses":[["error",{"type":"invalidArguments","description":"bad filter"},"0"]]}"#;
+        assert_eq!(
+            parse_responses(error).unwrap_err().to_string(),
+            "JMAP error: invalidArguments (bad filter)"
+        );
+    }
+
+    #[test]
+    fn test_mailboxes_are_found_by_role_then_name() {
+        let list = vec![
+            json!({ "id": "m1", "name": "Inbox", "role": "inbox" }),
+            json!({ "id": "m2", "name": "Old stuff", "role": "archive" }),
+            json!({ "id": "m3", "name": "trash", "role": null }),
+        ];
+        let folders = crate::config::Backend::Jmap.folders();
+        let mailboxes = map_mailboxes(&list, folders);
+        assert_eq!(mailboxes["INBOX"], "m1");
+        assert_eq!(mailboxes[folders.archive], "m2");
+        assert_eq!(mailboxes[folders.trash], "m3");
+        assert!(!mailboxes.contains_key(folders.sent));
+    }
+
+    #[test]
+    fn test_parse_email() {
+        let object = json!({
+            "id": "M123",
+            "threadId": "T9",
+            "keywords": { "$seen": true },
+            "preview": "  Hello\nthere  ",
+            "header:From:asRaw:all": [" Alice <alice@example.com>"],
+            "header:Subject:asRaw:all": [" Lunch"],
+            "header:Date:asRaw:all": [" Tue, 1 Apr 2025 10:00:00 +0000"],
+            "header:Message-ID:asRaw:all": [" <a@example.com>"],
+            "header:List-Id:asRaw:all": [" <news.example.com>"],
+            "header:Authentication-Results:asRaw:all": [
+                " mx.example.com; dkim=pass",
+                " forged.example; dkim=fail",
+            ],
+        });
+        let email = parse_email(&object, "INBOX").unwrap();
+        assert_eq!(email.id, "M123");
+        assert_eq!(email.from_email, "alice@example.com");
+        assert_eq!(email.subject, "Lunch");
+        assert_eq!(email.snippet, "Hello there");
+        assert_eq!(email.message_id.as_deref(), Some("<a@example.com>"));
+        assert!(email.seen);
+        assert!(!email.flagged);
+        assert!(email.mailing_list);
+        assert_eq!(email.auth.dkim, Some(crate::auth::Verdict::Pass));
+        assert_eq!(email.gmail_thread_id, Some(thread_key("T9")));
+        assert_ne!(thread_key("T9"), thread_key("T8"));
+        assert!(parse_email(&json!({ "id": "M1" }), "INBOX").is_none());
+    }
+
+    #[test]
+    fn test_body_text_prefers_text_values() {
+        let object = json!({
+            "textBody": [
+                { "partId": "1", "type": "text/plain" },
+                { "partId": "2", "type": "text/html" },
+            ],
+            "bodyValues": {
+                "1": { "value": "Hi", "isTruncated": false },
+                "2": { "value": "<p>there</p>", "isTruncated": true },
+            },
+        });
+        assert_eq!(body_text(&object), ("Hi\nthere\n".to_string(), true));
+        assert_eq!(html_body(&object), None);
+        let object = json!({
+            "htmlBody": [{ "partId": "2", "type": "text/html" }],
+            "bodyValues": { "2": { "value": "<p>there</p>" } },
+        });
+        assert_eq!(html_body(&object).as_deref(), Some("<p>there</p>"));
+        assert_eq!(
+            body_text(&json!({ "textBody": [] })),
+            ("[No text content found]".to_string(), false)
+        );
+    }
+
+    #[test]
+    fn test_move_patch_and_failed_updates() {
+        assert_eq!(
+            move_patch("m2", true),
+            json!({ "mailboxIds": { "m2": true }, "keywords/$seen": true })
+        );
+        assert!(check_updated(&json!({ "updated": { "M1": null } })).is_ok());
+        let error = check_updated(&json!({
+            "notUpdated": { "M1": { "type": "notFound" } }
+        }))
+        .unwrap_err();
+        assert_eq!(error.to_string(), "1 email(s) couldn't be moved: notFound");
+    }
+}
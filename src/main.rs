@@ -1,16 +1,44 @@
 mod app;
+mod auth;
+mod bench;
+mod capture;
+mod cli;
+mod compose;
 mod config;
+mod daemon;
 #[macro_use]
 mod debug;
+mod debug_bundle;
 mod demo;
 mod email;
+mod gmail_api_client;
+mod habits;
+mod header_cache;
+mod highlight;
+mod http;
+mod ignore;
 mod imap_client;
+mod imap_error;
+mod jmap_client;
+mod notes;
+mod pgp;
+mod plugin;
+mod policy;
+mod postpone;
+mod print;
+mod recording;
+mod resume;
+mod script;
+mod smtp;
+mod task;
 mod ui;
+mod webhook;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::io;
+use std::path::{Path, PathBuf};
 use std::process::Command;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::{Arc, mpsc};
 use std::thread;
 use std::time::{Duration, Instant};
@@ -18,59 +46,207 @@ use std::time::{Duration, Instant};
 use anyhow::Result;
 use crossterm::{
     event::{
-        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, KeyModifiers,
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
+        KeyModifiers,
     },
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
 use ratatui::{Terminal, backend::CrosstermBackend};
 
-use app::{App, UndoActionType, UndoContext, UndoEntry, View};
+use app::{App, GroupMode, UndoActionType, UndoContext, UndoEntry, View};
 use config::AccountConfig;
 use email::Email;
-use imap_client::{EmailClient, ImapClient};
+use gmail_api_client::GmailApiClient;
+use imap_client::{ApiClient, BATCH_SIZE, EmailClient, FetchedBody, ImapClient};
+use jmap_client::JmapClient;
+use policy::{Policy, PolicyAction};
 use ui::render::{render, render_account_select};
-use ui::widgets::{AccountSelection, ConfirmAction, TextViewState, UiState};
+use ui::title::WindowTitle;
+use ui::widgets::{
+    AccountSelection, ConfirmAction, ForwardInput, Picker, PickerTarget, TextViewState, Tour,
+    TriageTimer, UiState, WorkerProgress,
+};
 
 /// Commands sent to the IMAP worker thread
+#[derive(serde::Serialize, serde::Deserialize)]
 enum ImapCommand {
     FetchInbox {
         parallel_connections: usize,
+        merge_subject_threads: bool,
+        /// Only fetch mail on or after this date (the account's fetch_window)
+        since: Option<chrono::NaiveDate>,
+        /// Only fetch the newest N emails per folder (fetch_limit plus loaded pages)
+        limit: Option<u32>,
+    },
+    /// Fetch the next page of older emails, from below the oldest loaded one per folder
+    FetchPage {
+        parallel_connections: usize,
+        since: Option<chrono::NaiveDate>,
+        page: FetchPage,
+    },
+    /// Fetch the ranges an interrupted FetchInbox missed, keeping what it already got
+    RetryFetch,
+    /// Fetch the mail older than the fetch window, without threading it
+    FetchOlder {
+        parallel_connections: usize,
+        before: chrono::NaiveDate,
     },
     ArchiveMultiple(Vec<(String, String)>), // Vec<(uid, folder)>
     DeleteMultiple(Vec<(String, String)>),  // Vec<(uid, folder)>
+    /// Move emails (uid, folder) to the dest folder. A move that archives (label and
+    /// archive) marks them read first when mark_read_on_archive is set.
+    MoveMultiple {
+        emails: Vec<(String, String)>,
+        dest: String,
+        archive: bool,
+    },
+    /// List the folders emails can be moved to
+    ListFolders,
+    /// Check the loaded emails (uid, folder) against the server after another client changed it
+    Resync(Vec<(String, String)>),
     /// Vec<(message_id, dest_uid, current_folder, dest_folder)>
     /// dest_uid is used for fast restore if available, falls back to Message-ID search
     RestoreEmails(Vec<(Option<String>, Option<u32>, String, String)>),
-    /// Fetch email body (uid, folder)
+    /// Fetch email body (uid, folder), only the first max_bytes of the message when set
+    /// and marking it read on the server when mark_seen is set
     FetchBody {
         uid: String,
         folder: String,
+        max_bytes: Option<u32>,
+        mark_seen: bool,
+    },
+    /// Mark emails (uid, folder) read, or unread when seen is false
+    SetFlags {
+        emails: Vec<(String, String)>,
+        seen: bool,
+    },
+    /// Add and remove Gmail labels on emails (uid, folder)
+    ModifyLabels {
+        emails: Vec<(String, String)>,
+        add: Vec<String>,
+        remove: Vec<String>,
     },
+    /// Compare the UIDVALIDITY the UI's UIDs were read under (from the header cache)
+    /// with the server's; until they match, commands that act on UIDs are refused
+    CheckUidValidity(HashMap<String, u32>),
     Shutdown,
 }
 
+impl ImapCommand {
+    /// Short description for session recordings: the command name and how many items it touches
+    fn describe(&self) -> String {
+        match self {
+            ImapCommand::FetchInbox { .. } => "FetchInbox".to_string(),
+            ImapCommand::RetryFetch => "RetryFetch".to_string(),
+            ImapCommand::FetchOlder { .. } => "FetchOlder".to_string(),
+            ImapCommand::FetchPage { page, .. } => format!("FetchPage {}", page.count),
+            ImapCommand::ArchiveMultiple(emails) => format!("ArchiveMultiple {}", emails.len()),
+            ImapCommand::DeleteMultiple(emails) => format!("DeleteMultiple {}", emails.len()),
+            ImapCommand::MoveMultiple { emails, .. } => format!("MoveMultiple {}", emails.len()),
+            ImapCommand::ListFolders => "ListFolders".to_string(),
+            ImapCommand::Resync(emails) => format!("Resync {}", emails.len()),
+            ImapCommand::RestoreEmails(emails) => format!("RestoreEmails {}", emails.len()),
+            ImapCommand::FetchBody { uid, .. } => format!("FetchBody {}", uid),
+            ImapCommand::SetFlags { emails, .. } => format!("SetFlags {}", emails.len()),
+            ImapCommand::ModifyLabels { emails, .. } => {
+                format!("ModifyLabels {}", emails.len())
+            }
+            ImapCommand::CheckUidValidity(_) => "CheckUidValidity".to_string(),
+            ImapCommand::Shutdown => "Shutdown".to_string(),
+        }
+    }
+}
+
+/// Where the UI's IMAP responses come from
+enum Session {
+    /// Talk to the server, optionally recording the session to a file
+    Live {
+        account: AccountConfig,
+        record: Option<PathBuf>,
+    },
+    /// Talk to the server through a running `zeroterm daemon`
+    Attached {
+        account: AccountConfig,
+        stream: std::os::unix::net::UnixStream,
+    },
+    /// Feed back the events of a recorded session
+    Replay(Vec<recording::RecordedEvent>),
+}
+
+/// The difference between the loaded emails and the server, found by a Resync
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+struct MailboxResync {
+    /// Loaded emails (uid, folder) that are gone from their folder
+    removed: Vec<(String, String)>,
+    /// Emails that arrived after the newest loaded one in each folder
+    added: Vec<Email>,
+}
+
 /// Responses from the IMAP worker thread
 enum ImapResponse {
     Emails(Result<Vec<Email>>),
+    /// FetchInbox failed partway; what it got is kept so RetryFetch can fill in the rest
+    FetchInterrupted {
+        error: String,
+        fetched: usize,
+        missing_ranges: usize,
+    },
+    /// Mail from before the fetch window or the next page, to merge into the loaded emails
+    OlderEmails(Result<Vec<Email>>),
+    /// How many older emails are left to page in when fetch_limit is set
+    OlderRemaining(usize),
     /// Multi-archive result with source UID -> dest UID mapping from COPYUID
     MultiArchiveResult(Result<HashMap<String, u32>>),
     /// Multi-delete result with source UID -> dest UID mapping from COPYUID
     MultiDeleteResult(Result<HashMap<String, u32>>),
+    /// Multi-move result with source UID -> dest UID mapping from COPYUID
+    MultiMoveResult(Result<HashMap<String, u32>>),
+    /// The folders emails can be moved to, for the move picker
+    Folders(Result<Vec<String>>),
+    /// How many emails the archive or delete that follows skipped because another client
+    /// had already moved them, sent before its result
+    AlreadyGone(usize),
     RestoreResult(Result<()>),
+    /// The emails a SetFlags marked read (seen) or unread
+    FlagsSet {
+        ids: Vec<String>,
+        seen: bool,
+        result: Result<()>,
+    },
+    /// The emails a ModifyLabels changed, and the labels it added and removed
+    LabelsModified {
+        ids: Vec<String>,
+        add: Vec<String>,
+        remove: Vec<String>,
+        result: Result<()>,
+    },
     /// Email body fetch result with UID
     BodyResult {
         uid: String,
-        result: Result<String>,
+        result: Result<FetchedBody>,
     },
     /// Progress update during bulk operations (current, total, action)
     Progress(usize, usize, String),
+    /// Each parallel connection's progress while loading mail
+    WorkerProgress(Vec<WorkerProgress>),
     /// Retry status update (attempt number, max attempts, operation description)
     Retrying {
         attempt: u32,
         max_attempts: u32,
         action: String,
     },
+    /// An `[ALERT]` notice from the server, e.g. a quota warning
+    ServerAlert(String),
+    /// Folders another client added mail to or removed mail from
+    MailboxChanged(Vec<String>),
+    /// New mail reached the inbox, seen by the IDLE connection
+    NewMail,
+    /// What a Resync found changed on the server
+    Resynced(Result<MailboxResync>),
+    /// Each folder's UIDVALIDITY, sent before a FetchInbox and in answer to a
+    /// CheckUidValidity
+    UidValidity(HashMap<String, u32>),
     Connected,
     Error(String),
 }
@@ -83,41 +259,194 @@ Terminal-based email client for achieving inbox zero
 
 USAGE:
     zeroterm [OPTIONS]
+    zeroterm daemon [--account <NAME>]
+    zeroterm bench [--account <NAME>]
+    zeroterm config validate
+    zeroterm debug-bundle [--recording <FILE>]
 
 OPTIONS:
     -h, --help       Print help information
     -V, --version    Print version information
         --demo       Run in demo mode with fake data
+        --demo-size <N>
+                     Generate N fake emails for demo mode (default: 500 with --demo-seed)
+        --demo-seed <S>
+                     Seed for the generated demo mailbox (default: 0)
+        --demo-data <PATH>
+                     Load demo emails from a .toml or .json fixture or .mbox file
+        --demo-latency <MS>
+                     Simulated delay for each demo operation (default: 300)
+        --demo-failure-rate <PCT>
+                     Percent of demo operations that fail (default: 0)
+        --demo-retries <N>
+                     Simulated retries before each demo operation completes (default: 0)
+        --config <FILE>
+                     Use FILE as the config file (default: $ZEROTERM_CONFIG, then
+                     ~/.config/zeroterm/config.toml)
         --debug      Enable debug logging
+        --tour       Show the onboarding tour (shown automatically on first launch)
+        --resume     Reopen the view, selection and filters from the last session
+        --archive-older-than [DAYS]
+                     Archive inbox mail older than DAYS (default: archive_older_than_days),
+                     skipping flagged mail and protected senders, then exit
+        --account <NAME>
+                     Account to use with --archive-older-than, daemon or bench
+                     (default: first account)
+    -y, --yes        Skip the confirmation prompt for --archive-older-than
+        --record <FILE>
+                     Record the IMAP session (including email headers) to FILE
+        --replay <FILE>
+                     Replay a recorded session without connecting to a server
+        --recording <FILE>
+                     Add a redacted trace of a --record file to debug-bundle
+        --no-daemon  Connect directly even when a zeroterm daemon is running
 
 NAVIGATION:
     j/k              Move down/up in lists
     Enter            Select group or email / view email body
     Escape           Go back to previous view / clear filter
-    /                Filter groups or emails
+    /                Filter groups or emails (while typing: Tab completes a sender or domain,
+                     Up/Down recall earlier filters)
+    m                Cycle grouping by sender, domain and Gmail label (group list)
+    b                Jump to the group with the most emails (group list)
+    ' then a letter  Jump to the next group starting with that letter (group list)
+    1-9              Open the group numbered on screen (group list)
+    s                Surprise me: jump to a random group (group list)
+    J                Jump to the next group or thread with mail that arrived after the
+                     first load, marked • (group list, email list)
+    !                Show recent status messages, newest first
     q                Quit
 
 ACTIONS:
     a                Archive cursor thread, or selected threads
     d                Delete cursor thread, or selected threads
-    A                Archive all visible emails from sender
-    D                Delete all visible emails from sender
+    A                Archive all visible emails from sender, or all selected groups (group list)
+    D                Delete all visible emails from sender, or all selected groups (group list)
     e                Open email in browser (Gmail)
-    u                Undo last action
+    E                Open the whole thread in browser (Gmail)
+    M                Mark the email, thread or selected threads read/unread
+    V                Move the group's threads, the selected threads or the thread to a
+                     folder picked from the server's folders, with undo
+    F                Label and archive: file the group, selection or thread under a
+                     picked label or folder as one archive, with one undo entry
+    + / -            Add a Gmail label to, or remove one from, the group, selection or
+                     thread
+    T                Create a task from the email with task_command
+    C                Capture the email to capture_file (org-mode or markdown)
+    P                Print the email with print_command
+    v                Toggle inline message bodies in thread view, or one-/two-line rows
+                     in the email list
+    %                Sort the email list by spam score, highest first (press again for date)
+    Space            Select a group (group list) / collapse or expand a message
+                     (inline thread view)
+    c                Mark the group done or skipped for this session, moving it to the
+                     bottom of the list (group list; press again to unmark)
+    H                Show/hide groups smaller than min_group_size (group list)
+    w                Postpone the group until next week (or postpone_days), hiding it
+                     until then (group list)
+    o / O            Expand/collapse all messages (inline thread view)
+    u                Undo history: Enter undoes the selected action (i into the inbox,
+                     v into a picked folder instead), a undoes every action from the
+                     newest down to the selected one, Space lists its emails
+    f                Find duplicate emails (group list)
+    Z                Archive inbox mail older than the cutoff (group list) / the group's
+                     mail older than 30 days (email list; Z in the dialog changes the age)
+    S                Archive every single-message thread across all groups (group list)
+    R                Archive read inbox mail, leaving unread (group list: whole inbox,
+                     email list: the group)
+    W                Sweep: start marking groups/threads to keep with Space, then press W
+                     again to archive everything else (Esc cancels)
+    n                Add or edit a note for the sender (group list, email list)
+    .                Do the usual: archive or delete the sender's mail the way you
+                     did the last 3 times (group list, email list)
+    x                Not now: hide the group (group list) or thread (email list) for
+                     this session only, without touching the server
+    U                Show everything hidden with x again (group list) / unsubscribe from the
+                     sender via List-Unsubscribe, then offer to archive their mail
+    R / Ctrl+r       Reply / reply to all in $EDITOR, sent over SMTP after a confirmation
+                     (thread, email body)
+    f                Forward the email to an address typed in a prompt (email body)
+    I                Ignore the sender: hide its threads from now on (group list)
+    L                Load older mail (group list) / load a truncated message in full (email body)
+    h                Switch between plain text and the HTML rendering, with headings
+                     styled and links numbered (email body)
 
 CONFIG:
     Configuration file location: ~/.config/zeroterm/config.toml
+    (override with --config <FILE> or ZEROTERM_CONFIG)
 
     Example config:
         # Global options (all optional)
+        # include = [\"accounts.d/*.toml\"]  # Merge in more files, relative to this one (default: none)
         protect_threads = true       # Require confirmation for bulk actions (default: true)
         parallel_connections = 5     # IMAP connections for loading (default: 5)
         debug = false                # Enable debug logging (default: false)
+        merge_subject_threads = false  # Thread header-less emails by subject (default: false)
+        thread_inline_bodies = false   # Show message bodies inline in thread view (default: false)
+        row_density = \"compact\"       # Email list rows: \"compact\" or two-line \"comfortable\" (default: compact)
+        thread_recipients_column = false  # Show To/Cc column in thread view (default: false)
+        auth_warnings_in_list = false  # Mark emails failing DKIM/SPF/DMARC in the email list (default: false)
+        archive_older_than_days = 365  # Age cutoff for Z / --archive-older-than (default: 365)
+        protected_senders = []         # Senders or domains never archived by age (default: none)
+        ignored_senders = []           # Senders or domains hidden from zeroterm (default: none)
+        # webhook_url = \"https://...\"  # POST a JSON summary of each bulk action (default: none)
+        # task_command = \"task add {{subject}} {{link}}\"  # Run by T to capture an email as a task (default: none)
+        archive_after_task = false     # Archive the thread once the task is created (default: false)
+        # capture_file = \"~/notes/inbox.org\"  # C appends the email here, org or markdown (default: none)
+        capture_excerpt_lines = 10     # Body lines quoted in each capture; 0 for none (default: 10)
+        print_command = \"lp\"           # P pipes the email here as plain text (default: lp)
+        pgp_command = \"gpg\"            # Decrypts PGP/MIME mail and checks signatures (default: gpg)
+        triage_timer_minutes = 0       # Timebox triage with an on-screen countdown; 0 for off (default: 0)
+        min_group_size = 0             # Hide groups with fewer emails until H; 0 shows all (default: 0)
+        postpone_days = 0              # Days w postpones a group for; 0 means until next Monday (default: 0)
+        window_title = true            # Show \"zeroterm — account (N left)\" as the window title (default: true)
+        header_cache = true            # Show last session's inbox at startup while it loads (default: true)
+        # header_cache_encrypt_to = \"me@example.com\"  # gpg key the header cache is encrypted to (default: none)
+        confirm_policies = false       # Ask before applying archive [[policy]] rules; delete rules always ask (default: false)
+        typed_confirm_above = 100      # Deleting more emails needs \"delete\" typed; 0 for never (default: 100)
+        typed_count_confirm_above = 1000  # Archiving or deleting more needs the count or sender typed; 0 for never (default: 1000)
+        # fetch_limit = 20000          # Newest emails per folder fetched at startup (default: all)
+        # fetch_page_size = 5000       # Older emails per folder loaded by L (default: 5000)
+        body_size_limit_kb = 256       # Fetch only this much of a message body, 0 for all (default: 256)
+        mark_seen_on_view = false      # Opening a message marks it read on the server (default: false)
+        mark_read_on_archive = false   # Archiving a message also marks it read (default: false)
+
+        [[policy]]                     # Archive/delete matching inbox mail after each fetch
+        name = \"old newsletters\"
+        action = \"archive\"             # \"archive\" (default) or \"delete\"
+        older_than_days = 30           # At least one of older_than_days / senders is required
+        senders = [\"example.com\"]      # Addresses or whole domains
+        read = true                    # Only read (true) or unread (false) mail
+        newsletter = true              # Only mailing-list mail (true) or personal mail (false)
+
+        [[highlight]]                  # Make matching rows stand out in the lists
+        subject = [\"invoice\"]          # Subject words (ignoring case) and/or senders = [...]
+        color = \"lightred\"             # Color name or \"#rrggbb\"
+        bold = true                    # Also: underline = true
+
+        [[action]]                     # Run a rhai script with a key (see README: Custom Actions)
+        key = \"X\"
+        script = 'if email != () && email.seen {{ archive(); }}'
+
+        [[plugin]]                     # Run a program with a key; JSON in, commands out (see README: Plugins)
+        key = \"0\"
+        command = \"~/bin/zeroterm-snooze\"
 
         [accounts.personal]
-        backend = \"gmail\"
+        backend = \"gmail\"                    # \"gmail\", \"gmail-api\", \"outlook\" (Outlook.com / Microsoft 365) or \"jmap\"
         email = \"your.email@gmail.com\"
         app_password = \"xxxx xxxx xxxx xxxx\"
+        # imap_host = \"127.0.0.1\"            # IMAP server (default: the backend's server)
+        # imap_port = 1143                   # IMAP port (default: 993 for tls, 143 otherwise)
+        # security = \"tls\"                   # \"tls\", \"starttls\" or \"none\" (default: tls)
+        # tls_ca_file = \"/path/to/cert.pem\"  # Extra PEM CA bundle to trust
+        # danger_accept_invalid_certs = false  # Skip TLS verification (default: false)
+        # smtp_host = \"127.0.0.1\"            # SMTP server for replies (default: the backend's server)
+        # smtp_port = 465                    # SMTP port (default: 465, or 587 for outlook)
+        # fetch_window = \"6 months\"          # Only fetch recent mail at startup (L loads the rest)
+        # accent_color = \"magenta\"          # Border and title color for this account (default: none)
+        # jmap_url = \"https://...\"          # JMAP session URL for jmap (default: Fastmail's)
+        # oauth_token_command = \"oauth2l fetch --scope gmail.modify\"  # Prints an OAuth token for gmail-api
 
     The app_password can be a plain string or a 1Password reference (op://vault/item/field).
     Create an App Password at: https://myaccount.google.com/apppasswords",
@@ -141,16 +470,61 @@ fn main() -> Result<()> {
     // Check for demo mode
     let demo_mode = std::env::args().any(|arg| arg == "--demo");
     let debug_flag = std::env::args().any(|arg| arg == "--debug");
+    let tour_flag = std::env::args().any(|arg| arg == "--tour");
+    let resume_flag = std::env::args().any(|arg| arg == "--resume");
+    let no_daemon_flag = std::env::args().any(|arg| arg == "--no-daemon");
 
     if demo_mode {
         // Initialize debug logging for demo mode too
         debug::init(debug_flag);
-        return run_demo_mode();
+        let args: Vec<String> = std::env::args().collect();
+        let demo_size = cli::parse_flag_value::<usize>(&args, "--demo-size")?;
+        let demo_seed = cli::parse_flag_value::<u64>(&args, "--demo-seed")?;
+        let demo_data = cli::parse_flag_value::<PathBuf>(&args, "--demo-data")?;
+        let emails = if let Some(path) = demo_data {
+            demo::load_demo_data(&path)?
+        } else if demo_size.is_some() || demo_seed.is_some() {
+            demo::generate_demo_emails(
+                demo_size.unwrap_or(DEFAULT_DEMO_SIZE),
+                demo_seed.unwrap_or(0),
+            )
+        } else {
+            demo::create_demo_emails()
+        };
+        let defaults = demo::DemoFaults::default();
+        let faults = demo::DemoFaults::new(
+            cli::parse_flag_value(&args, "--demo-latency")?.unwrap_or(defaults.latency_ms),
+            cli::parse_flag_value(&args, "--demo-failure-rate")?.unwrap_or(defaults.failure_rate),
+            cli::parse_flag_value(&args, "--demo-retries")?.unwrap_or(defaults.retries),
+            demo_seed.unwrap_or(0),
+        );
+        return run_demo_mode(emails, faults, tour_flag);
+    }
+
+    // Replay a recorded session without a server or config
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(path) = cli::parse_flag_value::<PathBuf>(&args, "--replay")? {
+        debug::init(debug_flag);
+        return run_replay_mode(recording::load(&path)?);
+    }
+
+    // Use another config file (--config wins over ZEROTERM_CONFIG)
+    if let Some(path) = cli::parse_flag_value::<PathBuf>(&args, "--config")? {
+        config::set_config_path(&path);
     }
 
     // Initialize
     config::ensure_config_dir()?;
 
+    // Collect a redacted archive to attach to bug reports
+    if args.get(1).is_some_and(|arg| arg == "debug-bundle") {
+        let recording = cli::parse_flag_value::<PathBuf>(&args, "--recording")?;
+        let archive = debug_bundle::run(recording.as_deref())?;
+        println!("Wrote {}", archive.display());
+        println!("Look it over before attaching it to a bug report.");
+        return Ok(());
+    }
+
     // Check for config
     if !config::has_config() {
         eprintln!(
@@ -167,6 +541,15 @@ fn main() -> Result<()> {
         std::process::exit(1);
     }
 
+    // Check the config without starting (or resolving any secrets)
+    if args.get(1).is_some_and(|arg| arg == "config")
+        && args.get(2).is_some_and(|arg| arg == "validate")
+    {
+        let path = config::validate_config_file()?;
+        println!("{} is valid", path.display());
+        return Ok(());
+    }
+
     // Load config
     let cfg = config::load_config()?;
 
@@ -174,6 +557,37 @@ fn main() -> Result<()> {
     debug::init(debug_flag || cfg.debug);
     debug_log!("Zeroterm starting up");
 
+    // Archive old inbox mail without starting the TUI
+    if let Some(days) = cli::parse_archive_older_than(&args, cfg.archive_older_than_days)? {
+        let account_name = cli::parse_account_name(&args);
+        let assume_yes = args.iter().any(|arg| arg == "--yes" || arg == "-y");
+        return cli::run_archive_older_than(&cfg, days, account_name.as_deref(), assume_yes);
+    }
+
+    // Keep the connection and inbox warm for later launches
+    if args.get(1).is_some_and(|arg| arg == "daemon") {
+        let name = cli::parse_account_name(&args);
+        let (account_name, account) = cli::find_account(&cfg, name.as_deref())?;
+        account.require_imap("zeroterm daemon")?;
+        return daemon::run(&cfg, account_name, account);
+    }
+
+    // Time loading the inbox for a performance report
+    if args.get(1).is_some_and(|arg| arg == "bench") {
+        let name = cli::parse_account_name(&args);
+        let (_, account) = cli::find_account(&cfg, name.as_deref())?;
+        account.require_imap("zeroterm bench")?;
+        return bench::run(&cfg, account);
+    }
+
+    let record_path = cli::parse_flag_value::<PathBuf>(&args, "--record")?;
+
+    // Walk new users through the basics on their first launch
+    let show_tour = tour_flag || !config::has_seen_tour();
+    if show_tour && let Err(e) = config::mark_tour_seen() {
+        debug_log!("Failed to record that the tour was shown: {}", e);
+    }
+
     // Set up terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -190,12 +604,29 @@ fn main() -> Result<()> {
     };
 
     // User may have quit during account selection
-    let result = if let Some(account) = selected_account {
+    let result = if let Some((account_name, account)) = selected_account {
+        let user_email = account.email.clone();
+        // A running daemon already has the inbox loaded; recording needs a connection of its own
+        let daemon = if record_path.is_none() && !no_daemon_flag {
+            daemon::attach(&account_name)
+        } else {
+            None
+        };
+        let session = match daemon {
+            Some(stream) => Session::Attached { account, stream },
+            None => Session::Live {
+                account,
+                record: record_path,
+            },
+        };
         run_app(
             &mut terminal,
-            account,
-            cfg.parallel_connections,
-            cfg.advance_on_select,
+            account_name,
+            user_email,
+            &cfg,
+            session,
+            show_tour,
+            resume_flag,
         )
     } else {
         Ok(())
@@ -219,7 +650,35 @@ fn main() -> Result<()> {
 }
 
 /// Runs the application in demo mode with fake data
-fn run_demo_mode() -> Result<()> {
+fn run_demo_mode(emails: Vec<Email>, faults: demo::DemoFaults, show_tour: bool) -> Result<()> {
+    // Set up terminal
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend)?;
+
+    let result = run_demo_app(&mut terminal, emails, faults, show_tour);
+
+    // Restore terminal
+    disable_raw_mode()?;
+    execute!(
+        terminal.backend_mut(),
+        LeaveAlternateScreen,
+        DisableMouseCapture
+    )?;
+    terminal.show_cursor()?;
+
+    if let Err(e) = result {
+        eprintln!("Error: {e}");
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+/// Replays a recorded session in the UI, using the settings it was recorded with
+fn run_replay_mode(recording: recording::Recording) -> Result<()> {
     // Set up terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -227,7 +686,16 @@ fn run_demo_mode() -> Result<()> {
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let result = run_demo_app(&mut terminal);
+    let header = recording.header;
+    let result = run_app(
+        &mut terminal,
+        header.account,
+        header.user_email,
+        &header.config,
+        Session::Replay(recording.event),
+        false,
+        false,
+    );
 
     // Restore terminal
     disable_raw_mode()?;
@@ -254,8 +722,8 @@ struct DemoUndoStorage {
     emails: Vec<Vec<Email>>,
 }
 
-/// Simulated latency for demo mode operations (milliseconds)
-const DEMO_LATENCY_MS: u64 = 300;
+/// Number of emails generated when only --demo-seed is given
+const DEFAULT_DEMO_SIZE: usize = 500;
 
 /// Pending operations in demo mode (mirrors PendingOp for real mode)
 #[derive(Debug)]
@@ -290,9 +758,56 @@ enum DemoPendingOp {
         processed: usize,
         processed_ids: Vec<String>,
     },
+    ArchiveDuplicates {
+        emails: Vec<Email>,
+        count: usize,
+    },
+    DeleteDuplicates {
+        emails: Vec<Email>,
+        count: usize,
+    },
+    ArchiveOld {
+        emails: Vec<Email>,
+        days: u32,
+        group: Option<String>,
+    },
+    ArchiveSingles {
+        emails: Vec<Email>,
+    },
+    ArchiveRead {
+        emails: Vec<Email>,
+        group: Option<String>,
+    },
+    ArchiveSender {
+        emails: Vec<Email>,
+        sender: String,
+    },
+    ArchiveSweep {
+        emails: Vec<Email>,
+    },
+    ArchiveGroups {
+        emails: Vec<Email>,
+        senders: Vec<String>,
+    },
+    DeleteGroups {
+        emails: Vec<Email>,
+        senders: Vec<String>,
+    },
+    Move {
+        emails: Vec<Email>,
+        dest: String,
+        context: UndoContext,
+        archive: bool,
+    },
     Undo {
         index: usize,
         emails: Vec<Email>,
+        /// The folder picked to restore to, instead of the original ones
+        dest: Option<String>,
+    },
+    /// The newest undo entries' emails, newest first
+    UndoRecent {
+        emails: Vec<Vec<Email>>,
     },
 }
 
@@ -302,11 +817,32 @@ impl DemoPendingOp {
         match self {
             DemoPendingOp::ArchiveGroup { .. }
             | DemoPendingOp::ArchiveThread { .. }
-            | DemoPendingOp::ArchiveSelected { .. } => "Archiving...",
+            | DemoPendingOp::ArchiveSelected { .. }
+            | DemoPendingOp::ArchiveDuplicates { .. }
+            | DemoPendingOp::ArchiveOld { .. }
+            | DemoPendingOp::ArchiveSingles { .. }
+            | DemoPendingOp::ArchiveRead { .. }
+            | DemoPendingOp::ArchiveSender { .. }
+            | DemoPendingOp::ArchiveSweep { .. }
+            | DemoPendingOp::ArchiveGroups { .. } => "Archiving...",
             DemoPendingOp::DeleteGroup { .. }
+            | DemoPendingOp::DeleteGroups { .. }
             | DemoPendingOp::DeleteThread { .. }
-            | DemoPendingOp::DeleteSelected { .. } => "Deleting...",
-            DemoPendingOp::Undo { .. } => "Restoring...",
+            | DemoPendingOp::DeleteSelected { .. }
+            | DemoPendingOp::DeleteDuplicates { .. } => "Deleting...",
+            DemoPendingOp::Move { archive: true, .. } => "Filing...",
+            DemoPendingOp::Move { .. } => "Moving...",
+            DemoPendingOp::Undo { .. } | DemoPendingOp::UndoRecent { .. } => "Restoring...",
+        }
+    }
+
+    /// Returns true if a simulated failure can abort this operation. Selected-email
+    /// operations that are partway through only fail before their first email.
+    fn can_fail(&self) -> bool {
+        match self {
+            DemoPendingOp::ArchiveSelected { processed, .. }
+            | DemoPendingOp::DeleteSelected { processed, .. } => *processed == 0,
+            _ => true,
         }
     }
 }
@@ -336,19 +872,34 @@ impl DemoUndoStorage {
 }
 
 /// The demo app event loop - no IMAP, all actions are simulated
-fn run_demo_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result<()> {
+fn run_demo_app(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    emails: Vec<Email>,
+    mut faults: demo::DemoFaults,
+    show_tour: bool,
+) -> Result<()> {
     let mut app = App::new();
     app.set_user_email("demo@example.com".to_string());
-    app.set_emails(demo::create_demo_emails());
+    app.set_emails(emails);
     let mut ui_state = UiState::new();
+    ui_state.typed_confirm_above = config::default_typed_confirm_above();
+    ui_state.typed_count_confirm_above = config::default_typed_count_confirm_above();
+    ui_state.triage_started = Some(Instant::now());
+    if show_tour {
+        ui_state.tour = Some(Tour::new());
+    }
     let mut undo_storage = DemoUndoStorage::new();
+    let mut window_title = WindowTitle::new(true);
 
     // Track pending 'g' for gg sequence
     let mut pending_g = false;
+    let mut pending_letter_jump = false;
 
     // Pending operation for simulated network latency
     let mut pending_op: Option<DemoPendingOp> = None;
     let mut op_start_time: Option<Instant> = None;
+    // Simulated retries used so far by the pending operation step
+    let mut retry_attempt: u32 = 0;
 
     // Demo mode uses the default setting for advance_on_select
     let advance_on_select = true;
@@ -357,13 +908,30 @@ fn run_demo_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result
     loop {
         // Check if pending operation should complete
         if let (Some(op), Some(start)) = (pending_op.take(), op_start_time.take()) {
-            if start.elapsed() >= Duration::from_millis(DEMO_LATENCY_MS) {
-                // Execute the operation (may return a continuation for multi-step operations)
-                if let Some(continuation) =
-                    execute_demo_op(&mut app, &mut ui_state, &mut undo_storage, op)
-                {
-                    pending_op = Some(continuation);
+            if start.elapsed() >= Duration::from_millis(faults.latency_ms) {
+                if retry_attempt < faults.retries {
+                    // Simulate a retry storm before the step goes through
+                    retry_attempt += 1;
+                    ui_state.update_busy_message(format!(
+                        "Retrying {} ({}/{})...",
+                        op.busy_message().trim_end_matches("...").to_lowercase(),
+                        retry_attempt,
+                        faults.retries
+                    ));
+                    pending_op = Some(op);
                     op_start_time = Some(Instant::now());
+                } else if op.can_fail() && faults.roll_failure() {
+                    retry_attempt = 0;
+                    fail_demo_op(&mut app, &mut ui_state, &mut undo_storage, op);
+                } else {
+                    retry_attempt = 0;
+                    // Execute the operation (may return a continuation for multi-step operations)
+                    if let Some(continuation) =
+                        execute_demo_op(&mut app, &mut ui_state, &mut undo_storage, op)
+                    {
+                        pending_op = Some(continuation);
+                        op_start_time = Some(Instant::now());
+                    }
                 }
             } else {
                 // Not ready yet, put it back
@@ -373,8 +941,21 @@ fn run_demo_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result
         }
 
         app.ensure_valid_selection();
+        // Advance the onboarding tour as the user follows it
+        if let Some(tour) = &mut ui_state.tour
+            && !tour.update(&app)
+        {
+            ui_state.tour = None;
+        }
+        ui_state.tick_status();
+        window_title.update("demo", app.email_count())?;
         terminal.draw(|f| render(f, &app, &mut ui_state))?;
 
+        // Fill in demo bodies for the open thread when they're shown inline
+        if app.view == View::Thread && app.thread_inline_bodies {
+            load_demo_inline_bodies(&mut app);
+        }
+
         // Poll for keyboard events with timeout
         if event::poll(Duration::from_millis(50))?
             && let Event::Key(key) = event::read()?
@@ -385,12 +966,38 @@ fn run_demo_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result
 
             // Handle confirmation dialog input
             if ui_state.is_confirming() {
+                let Some(key) = typed_confirm_key(&mut ui_state, key) else {
+                    continue;
+                };
                 match key.code {
                     KeyCode::Char('y') | KeyCode::Char('Y') => {
                         if let Some(action) = ui_state.confirm_action.take() {
                             if matches!(action, ConfirmAction::Quit) {
                                 break;
                             }
+                            if let ConfirmAction::IgnoreSender { sender } = &action {
+                                // Demo mode keeps ignores for this run only
+                                ignore_sender(
+                                    &mut app,
+                                    &mut ui_state,
+                                    &mut ignore::IgnoreList::default(),
+                                    sender,
+                                    None,
+                                );
+                                continue;
+                            }
+                            if let ConfirmAction::UndoRecent { actions, .. } = action {
+                                let emails: Vec<Vec<Email>> = (0..actions)
+                                    .filter_map(|_| undo_storage.remove(0))
+                                    .collect();
+                                if !emails.is_empty() {
+                                    let op = DemoPendingOp::UndoRecent { emails };
+                                    ui_state.set_busy(op.busy_message());
+                                    pending_op = Some(op);
+                                    op_start_time = Some(Instant::now());
+                                }
+                                continue;
+                            }
                             if let Some(op) = handle_demo_confirmed_action(&app, action) {
                                 // For selected emails, record undo entry and show "1 of N" progress
                                 match &op {
@@ -406,7 +1013,7 @@ fn run_demo_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result
                                                 (
                                                     e.message_id.clone(),
                                                     None,
-                                                    e.source_folder.clone(),
+                                                    e.source_folder.to_string(),
                                                 )
                                             })
                                             .collect();
@@ -436,7 +1043,7 @@ fn run_demo_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result
                                                 (
                                                     e.message_id.clone(),
                                                     None,
-                                                    e.source_folder.clone(),
+                                                    e.source_folder.to_string(),
                                                 )
                                             })
                                             .collect();
@@ -463,6 +1070,14 @@ fn run_demo_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result
                             }
                         }
                     }
+                    KeyCode::Char('Z')
+                        if matches!(
+                            ui_state.confirm_action,
+                            Some(ConfirmAction::ArchiveGroupOld { .. })
+                        ) =>
+                    {
+                        cycle_group_age(&app, &mut ui_state);
+                    }
                     KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
                         ui_state.clear_confirm();
                     }
@@ -471,60 +1086,99 @@ fn run_demo_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result
                 continue;
             }
 
-            // Clear status message on any key press
+            // Handle the move's or the labels' picker
+            if let Some(picker) = &mut ui_state.picker {
+                match key.code {
+                    KeyCode::Char('j') | KeyCode::Down => picker.select_next(),
+                    KeyCode::Char('k') | KeyCode::Up => picker.select_previous(),
+                    KeyCode::Enter => {
+                        let picker = ui_state.picker.take();
+                        if let Some(picker) = picker
+                            && let Some(choice) = picker.current_item()
+                        {
+                            match picker.target {
+                                PickerTarget::MoveFolder { archive } => {
+                                    if let Some(op) = demo_move(&app, choice, archive) {
+                                        ui_state.set_busy(op.busy_message());
+                                        pending_op = Some(op);
+                                        op_start_time = Some(Instant::now());
+                                    }
+                                }
+                                PickerTarget::AddLabel => {
+                                    demo_label(&mut app, &mut ui_state, choice, true)
+                                }
+                                PickerTarget::RemoveLabel => {
+                                    demo_label(&mut app, &mut ui_state, choice, false)
+                                }
+                                PickerTarget::RestoreFolder => {
+                                    if let Some(op) =
+                                        handle_demo_undo(&app, &mut undo_storage, Some(choice))
+                                    {
+                                        ui_state.set_busy(op.busy_message());
+                                        pending_op = Some(op);
+                                        op_start_time = Some(Instant::now());
+                                    }
+                                }
+                                PickerTarget::Plugin(_) => {}
+                            }
+                        }
+                    }
+                    KeyCode::Esc | KeyCode::Char('q') => ui_state.picker = None,
+                    _ => {}
+                }
+                continue;
+            }
+
+            // Dismiss the status modal on any key press; toasts never take a key
             if ui_state.has_status() {
                 ui_state.clear_status();
                 continue;
             }
 
+            // Any key closes the status history
+            if ui_state.show_status_history {
+                ui_state.show_status_history = false;
+                continue;
+            }
+
             // Handle help menu
             if ui_state.is_showing_help() {
                 match key.code {
                     KeyCode::Char('?') | KeyCode::Esc | KeyCode::Char('q') => {
                         ui_state.hide_help();
                     }
+                    KeyCode::Tab => ui_state.toggle_help_global(),
                     _ => {}
                 }
                 continue;
             }
 
+            // Handle sender note input
+            if ui_state.is_note_input_active() {
+                handle_note_input(&mut app, &mut ui_state, key.code, None);
+                continue;
+            }
+
+            // Handle the forward prompt
+            if ui_state.forward_input.is_some() {
+                if let Some(draft) = handle_forward_input(&app, &mut ui_state, key.code) {
+                    ui_state.set_status(format!(
+                        "Demo mode: would forward to {}",
+                        draft.recipients_summary()
+                    ));
+                }
+                continue;
+            }
+
             // Handle filter input mode (GroupList and EmailList views)
             if ui_state.is_filter_input_active() {
-                match key.code {
-                    KeyCode::Esc => {
-                        // Revert to previous filter and exit input mode
-                        let reverted = ui_state.revert_filter();
-                        if let Some(query) = &reverted {
-                            app.set_view_text_filter(Some(query.clone()));
-                            ui_state.set_filter_query(query);
-                        } else {
-                            app.clear_view_text_filter();
-                            ui_state.clear_filter_query();
-                        }
-                        ui_state.exit_filter_input_mode();
-                    }
-                    KeyCode::Enter => {
-                        // Exit input mode, keep filter active
-                        ui_state.exit_filter_input_mode();
-                    }
-                    KeyCode::Backspace => {
-                        ui_state.backspace_filter();
-                        // Update filter in real-time
-                        let query = ui_state.filter_query().to_string();
-                        if query.is_empty() {
-                            app.clear_view_text_filter();
-                        } else {
-                            app.set_view_text_filter(Some(query));
-                        }
-                    }
-                    KeyCode::Char(c) => {
-                        ui_state.append_filter_char(c);
-                        // Update filter in real-time
-                        let query = ui_state.filter_query().to_string();
-                        app.set_view_text_filter(Some(query));
-                    }
-                    _ => {}
-                }
+                handle_filter_input(&mut app, &mut ui_state, key.code);
+                continue;
+            }
+
+            // End the onboarding tour early
+            if key.code == KeyCode::Char('x') && ui_state.tour.is_some() {
+                ui_state.tour = None;
                 continue;
             }
 
@@ -534,6 +1188,12 @@ fn run_demo_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result
                 continue;
             }
 
+            // Show recent status messages with !
+            if key.code == KeyCode::Char('!') {
+                ui_state.show_status_history = true;
+                continue;
+            }
+
             // Enter filter mode with / (GroupList and EmailList views)
             if key.code == KeyCode::Char('/')
                 && matches!(app.view, View::GroupList | View::EmailList)
@@ -558,15 +1218,32 @@ fn run_demo_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result
                 pending_g = false;
             }
 
+            // ' then a letter jumps to the next group starting with it
+            if pending_letter_jump {
+                pending_letter_jump = false;
+                if let KeyCode::Char(letter) = key.code
+                    && !app.jump_to_group_starting_with(letter)
+                {
+                    ui_state.set_status(format!("No group starting with {}", letter));
+                }
+                continue;
+            }
+
             // Handle UndoHistory view separately
             if app.view == View::UndoHistory {
                 match key.code {
                     KeyCode::Char('q') => {
                         ui_state.set_confirm(ConfirmAction::Quit);
                     }
+                    KeyCode::Esc if app.undo_detail_scroll.is_some() => {
+                        app.toggle_undo_detail();
+                    }
                     KeyCode::Esc => {
                         app.exit_undo_history();
                     }
+                    KeyCode::Char(' ') => {
+                        app.toggle_undo_detail();
+                    }
                     KeyCode::Char('j') | KeyCode::Down => {
                         app.select_next();
                     }
@@ -593,14 +1270,72 @@ fn run_demo_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result
                         let half_page = ui_state.viewport_heights.for_view(app.view) / 2;
                         app.select_previous_n(half_page.max(1));
                     }
-                    KeyCode::Enter => {
-                        // Execute undo in demo mode
-                        if let Some(op) = handle_demo_undo(&app, &mut undo_storage) {
+                    KeyCode::Enter | KeyCode::Char('i') => {
+                        // Execute undo in demo mode, into the inbox with i
+                        let dest = (key.code == KeyCode::Char('i')).then_some("INBOX");
+                        if let Some(op) = handle_demo_undo(&app, &mut undo_storage, dest) {
                             ui_state.set_busy(op.busy_message());
                             pending_op = Some(op);
                             op_start_time = Some(Instant::now());
                         }
                     }
+                    KeyCode::Char('a') => confirm_undo_recent(&app, &mut ui_state),
+                    KeyCode::Char('v') if app.current_undo_entry().is_some() => {
+                        open_folder_picker(
+                            &mut ui_state,
+                            DEMO_FOLDERS.map(String::from).to_vec(),
+                            PickerTarget::RestoreFolder,
+                        );
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+
+            // Handle Duplicates view separately
+            if app.view == View::Duplicates {
+                match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => {
+                        app.exit_duplicates();
+                    }
+                    KeyCode::Char('j') | KeyCode::Down => {
+                        app.select_next();
+                    }
+                    KeyCode::Char('k') | KeyCode::Up => {
+                        app.select_previous();
+                    }
+                    KeyCode::Char('g') => {
+                        if pending_g {
+                            app.select_first();
+                            pending_g = false;
+                        } else {
+                            pending_g = true;
+                        }
+                    }
+                    KeyCode::Char('G') => {
+                        pending_g = false;
+                        app.select_last();
+                    }
+                    KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        let half_page = ui_state.viewport_heights.for_view(app.view) / 2;
+                        app.select_next_n(half_page.max(1));
+                    }
+                    KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        let half_page = ui_state.viewport_heights.for_view(app.view) / 2;
+                        app.select_previous_n(half_page.max(1));
+                    }
+                    KeyCode::Char('a') => {
+                        confirm_duplicates_action(&app, &mut ui_state, true, false)
+                    }
+                    KeyCode::Char('d') => {
+                        confirm_duplicates_action(&app, &mut ui_state, false, false)
+                    }
+                    KeyCode::Char('A') => {
+                        confirm_duplicates_action(&app, &mut ui_state, true, true)
+                    }
+                    KeyCode::Char('D') => {
+                        confirm_duplicates_action(&app, &mut ui_state, false, true)
+                    }
                     _ => {}
                 }
                 continue;
@@ -615,6 +1350,9 @@ fn run_demo_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result
                     KeyCode::Esc => {
                         app.exit_text_view();
                     }
+                    KeyCode::Char('h') => {
+                        toggle_html_view(&mut app, &mut ui_state);
+                    }
                     KeyCode::Char('j') | KeyCode::Down => {
                         app.scroll_text_view_down(1);
                     }
@@ -644,6 +1382,45 @@ fn run_demo_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result
                     KeyCode::Char('e') => {
                         ui_state.set_status("Demo mode: would open email in browser".to_string());
                     }
+                    KeyCode::Char('U') => start_unsubscribe(&app, &mut ui_state, true, None),
+                    KeyCode::Char('f') => start_forward(&app, &mut ui_state, true),
+                    KeyCode::Char('R') => {
+                        ui_state.set_status("Demo mode: would open a reply in $EDITOR".to_string());
+                    }
+                    KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        ui_state.set_status(
+                            "Demo mode: would open a reply to all in $EDITOR".to_string(),
+                        );
+                    }
+                    KeyCode::Char('E') => {
+                        ui_state.set_status("Demo mode: would open thread in browser".to_string());
+                    }
+                    KeyCode::Char('M') => {
+                        handle_demo_toggle_seen(&mut app, &mut ui_state);
+                    }
+                    KeyCode::Char('V') => handle_demo_move(
+                        &app,
+                        &mut ui_state,
+                        PickerTarget::MoveFolder { archive: false },
+                    ),
+                    KeyCode::Char('F') => handle_demo_move(
+                        &app,
+                        &mut ui_state,
+                        PickerTarget::MoveFolder { archive: true },
+                    ),
+                    KeyCode::Char('+') => {
+                        handle_demo_move(&app, &mut ui_state, PickerTarget::AddLabel)
+                    }
+                    KeyCode::Char('-') => open_remove_label_picker(&app, &mut ui_state),
+                    KeyCode::Char('T') => {
+                        ui_state.set_status("Demo mode: would create a task".to_string());
+                    }
+                    KeyCode::Char('C') => {
+                        ui_state.set_status("Demo mode: would capture to notes file".to_string());
+                    }
+                    KeyCode::Char('P') => {
+                        ui_state.set_status("Demo mode: would print the email".to_string());
+                    }
                     KeyCode::Char('A') => {
                         handle_demo_archive_all(&app, &mut ui_state);
                     }
@@ -671,8 +1448,30 @@ fn run_demo_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result
                     } else if app.view != View::GroupList {
                         // Layer 3: exit view (EmailList → GroupList; GroupList does nothing)
                         app.exit();
+                    } else if app.sweep_marks().is_some() {
+                        app.cancel_sweep();
+                        ui_state.set_status("Sweep cancelled".to_string());
                     }
                 }
+                KeyCode::Char('d')
+                    if key.modifiers.contains(KeyModifiers::CONTROL)
+                        && app.view == View::Thread
+                        && app.thread_inline_bodies =>
+                {
+                    // Scroll within the conversation rather than jumping messages
+                    let half_page = ui_state.viewport_heights.thread_view / 2;
+                    app.thread_body_scroll =
+                        app.thread_body_scroll.saturating_add(half_page.max(1));
+                }
+                KeyCode::Char('u')
+                    if key.modifiers.contains(KeyModifiers::CONTROL)
+                        && app.view == View::Thread
+                        && app.thread_inline_bodies =>
+                {
+                    let half_page = ui_state.viewport_heights.thread_view / 2;
+                    app.thread_body_scroll =
+                        app.thread_body_scroll.saturating_sub(half_page.max(1));
+                }
                 KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
                     let half_page = ui_state.viewport_heights.for_view(app.view) / 2;
                     app.select_next_n(half_page.max(1));
@@ -681,6 +1480,26 @@ fn run_demo_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result
                     let half_page = ui_state.viewport_heights.for_view(app.view) / 2;
                     app.select_previous_n(half_page.max(1));
                 }
+                KeyCode::Char('v') if app.view == View::Thread => {
+                    app.toggle_thread_inline_bodies();
+                }
+                KeyCode::Char('v') if app.view == View::EmailList => {
+                    app.toggle_row_density();
+                }
+                KeyCode::Char('%') if app.view == View::EmailList => {
+                    app.toggle_spam_score_sort();
+                }
+                KeyCode::Enter | KeyCode::Char(' ')
+                    if app.view == View::Thread && app.thread_inline_bodies =>
+                {
+                    app.toggle_current_thread_email_collapsed();
+                }
+                KeyCode::Char('o') if app.view == View::Thread && app.thread_inline_bodies => {
+                    app.expand_all_thread_emails();
+                }
+                KeyCode::Char('O') if app.view == View::Thread && app.thread_inline_bodies => {
+                    app.collapse_all_thread_emails();
+                }
                 KeyCode::Char('j') | KeyCode::Down => {
                     app.select_next();
                 }
@@ -720,30 +1539,192 @@ fn run_demo_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result
                         ui_state.set_status("Demo mode: would open email in browser".to_string());
                     }
                 }
-                KeyCode::Char('g') => {
-                    if pending_g {
-                        app.select_first();
-                        pending_g = false;
-                    } else {
-                        pending_g = true;
+                KeyCode::Char('E') => {
+                    if matches!(app.view, View::Thread | View::EmailList) {
+                        ui_state.set_status("Demo mode: would open thread in browser".to_string());
                     }
                 }
-                KeyCode::Char('G') => {
-                    pending_g = false;
+                KeyCode::Char('M') => {
+                    if matches!(app.view, View::Thread | View::EmailList) {
+                        handle_demo_toggle_seen(&mut app, &mut ui_state);
+                    }
+                }
+                KeyCode::Char('V')
+                    if matches!(app.view, View::GroupList | View::Thread | View::EmailList) =>
+                {
+                    handle_demo_move(
+                        &app,
+                        &mut ui_state,
+                        PickerTarget::MoveFolder { archive: false },
+                    );
+                }
+                KeyCode::Char('F')
+                    if matches!(app.view, View::GroupList | View::Thread | View::EmailList) =>
+                {
+                    handle_demo_move(
+                        &app,
+                        &mut ui_state,
+                        PickerTarget::MoveFolder { archive: true },
+                    );
+                }
+                KeyCode::Char('+')
+                    if matches!(app.view, View::GroupList | View::Thread | View::EmailList) =>
+                {
+                    handle_demo_move(&app, &mut ui_state, PickerTarget::AddLabel);
+                }
+                KeyCode::Char('-')
+                    if matches!(app.view, View::GroupList | View::Thread | View::EmailList) =>
+                {
+                    open_remove_label_picker(&app, &mut ui_state);
+                }
+                KeyCode::Char('T') => {
+                    if matches!(app.view, View::Thread | View::EmailList) {
+                        ui_state.set_status("Demo mode: would create a task".to_string());
+                    }
+                }
+                KeyCode::Char('C') => {
+                    if matches!(app.view, View::Thread | View::EmailList) {
+                        ui_state.set_status("Demo mode: would capture to notes file".to_string());
+                    }
+                }
+                KeyCode::Char('P') => {
+                    if matches!(app.view, View::Thread | View::EmailList) {
+                        ui_state.set_status("Demo mode: would print the email".to_string());
+                    }
+                }
+                KeyCode::Char('g') => {
+                    if pending_g {
+                        app.select_first();
+                        pending_g = false;
+                    } else {
+                        pending_g = true;
+                    }
+                }
+                KeyCode::Char('G') => {
+                    pending_g = false;
                     app.select_last();
                 }
-                KeyCode::Char('m') => {
-                    if app.view == View::GroupList {
-                        app.toggle_group_mode();
+                KeyCode::Char('m') if app.view == View::GroupList => {
+                    app.toggle_group_mode();
+                }
+                KeyCode::Char('f') if app.view == View::GroupList => {
+                    app.enter_duplicates();
+                }
+                KeyCode::Char('Z') if app.view == View::GroupList => {
+                    confirm_archive_old(&app, &mut ui_state);
+                }
+                KeyCode::Char('S') if app.view == View::GroupList => {
+                    confirm_archive_singles(&app, &mut ui_state);
+                }
+                KeyCode::Char('R') if app.view == View::GroupList => {
+                    confirm_archive_read(&app, &mut ui_state, None);
+                }
+                KeyCode::Char('Z') if app.view == View::EmailList => {
+                    if let Some(group) = app.current_group().map(|g| g.key.clone()) {
+                        confirm_archive_group_old(&app, &mut ui_state, group, GROUP_AGE_PRESETS[1]);
+                    }
+                }
+                KeyCode::Char('R') if app.view == View::EmailList => {
+                    let group = app.current_group().map(|g| g.key.clone());
+                    if group.is_some() {
+                        confirm_archive_read(&app, &mut ui_state, group);
+                    }
+                }
+                KeyCode::Char('W')
+                    if (app.view == View::GroupList || app.view == View::EmailList) =>
+                {
+                    handle_sweep(&mut app, &mut ui_state);
+                }
+                KeyCode::Char('c') if app.view == View::GroupList => {
+                    app.toggle_group_done();
+                }
+                KeyCode::Char('x')
+                    if (app.view == View::GroupList || app.view == View::EmailList) =>
+                {
+                    handle_dismiss(&mut app, &mut ui_state);
+                }
+                KeyCode::Char('U') if matches!(app.view, View::Thread | View::EmailList) => {
+                    start_unsubscribe(&app, &mut ui_state, true, None);
+                }
+                KeyCode::Char('U') if app.view == View::GroupList => {
+                    handle_undismiss(&mut app, &mut ui_state);
+                }
+                KeyCode::Char('H') if app.view == View::GroupList => {
+                    let toggled = app.toggle_small_groups();
+                    if toggled.is_none() {
+                        ui_state.set_status(
+                            "Set min_group_size in the config to hide small groups".to_string(),
+                        );
+                    }
+                }
+                KeyCode::Char('J')
+                    if (app.view == View::GroupList || app.view == View::EmailList)
+                        && !app.jump_to_new() =>
+                {
+                    ui_state.set_status("No new mail since the first load".to_string());
+                }
+                KeyCode::Char('\'') if app.view == View::GroupList => {
+                    pending_letter_jump = true;
+                }
+                KeyCode::Char('b') if app.view == View::GroupList => {
+                    app.select_largest_group();
+                }
+                KeyCode::Char(c @ '1'..='9') if app.view == View::GroupList => {
+                    let index = ui_state.group_scroll_offset + (c as usize - '1' as usize);
+                    if !app.enter_group_at(index) {
+                        ui_state.set_status(format!("No group {} on screen", c));
+                    }
+                }
+                KeyCode::Char('s') if app.view == View::GroupList => {
+                    app.select_random_group(random_roll());
+                }
+                KeyCode::Char('w') if app.view == View::GroupList => {
+                    // Demo mode keeps postponements for this run only
+                    postpone_group(
+                        &mut app,
+                        &mut ui_state,
+                        &mut postpone::PostponeList::default(),
+                        0,
+                        None,
+                    );
+                }
+                KeyCode::Char('I') if app.view == View::GroupList => {
+                    if let Some(sender) = current_sender_key(&app, &mut ui_state) {
+                        ui_state.set_confirm(ConfirmAction::IgnoreSender { sender });
+                    }
+                }
+                KeyCode::Char('.')
+                    if (app.view == View::GroupList || app.view == View::EmailList) =>
+                {
+                    confirm_usual_action(&app, &mut ui_state);
+                }
+                KeyCode::Char('n')
+                    if (app.view == View::GroupList || app.view == View::EmailList) =>
+                {
+                    if let Some(sender) = current_sender_key(&app, &mut ui_state) {
+                        ui_state.start_note_input(&sender, app.notes.get(&sender));
                     }
                 }
+                KeyCode::Char('L') if app.view == View::GroupList => {
+                    ui_state.set_status("All mail is already loaded".to_string());
+                }
+                KeyCode::Char('R') if app.view == View::Thread => {
+                    ui_state.set_status("Demo mode: would open a reply in $EDITOR".to_string());
+                }
+                KeyCode::Char('r')
+                    if app.view == View::Thread
+                        && key.modifiers.contains(KeyModifiers::CONTROL) =>
+                {
+                    ui_state
+                        .set_status("Demo mode: would open a reply to all in $EDITOR".to_string());
+                }
                 KeyCode::Char('r') => {
                     ui_state.set_status("Demo mode: refresh simulated".to_string());
                 }
-                KeyCode::Char('t') => {
-                    if app.view == View::GroupList || app.view == View::EmailList {
-                        app.toggle_thread_filter();
-                    }
+                KeyCode::Char('t')
+                    if (app.view == View::GroupList || app.view == View::EmailList) =>
+                {
+                    app.toggle_thread_filter();
                 }
                 KeyCode::Char('u') => {
                     app.enter_undo_history();
@@ -774,11 +1755,23 @@ fn run_demo_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result
                             "Cannot select individual emails in thread view. Press Enter to open the thread."
                                 .to_string(),
                         );
+                    } else if app.sweep_marks().is_some()
+                        && (app.view == View::GroupList || app.view == View::EmailList)
+                    {
+                        // While sweeping, Space marks what to keep instead of selecting
+                        if app.toggle_keep() == app::SelectionResult::Toggled && advance_on_select {
+                            app.select_next();
+                        }
                     } else if app.view == View::EmailList
                         && let app::SelectionResult::Toggled = app.toggle_email_selection()
                         && advance_on_select
                     {
                         app.select_next();
+                    } else if app.view == View::GroupList
+                        && let app::SelectionResult::Toggled = app.toggle_group_selection()
+                        && advance_on_select
+                    {
+                        app.select_next();
                     }
                 }
                 _ => {}
@@ -800,10 +1793,11 @@ fn execute_demo_op(
     match op {
         DemoPendingOp::ArchiveGroup { emails, sender } => {
             ui_state.clear_busy();
+            remember_handling(app, &sender, PolicyAction::Archive, None);
             // Demo mode doesn't have real destination UIDs, so we use None
             let undo_emails: Vec<(Option<String>, Option<u32>, String)> = emails
                 .iter()
-                .map(|e| (e.message_id.clone(), None, e.source_folder.clone()))
+                .map(|e| (e.message_id.clone(), None, e.source_folder.to_string()))
                 .collect();
             if !undo_emails.is_empty() {
                 let undo_entry = UndoEntry {
@@ -821,10 +1815,11 @@ fn execute_demo_op(
         }
         DemoPendingOp::DeleteGroup { emails, sender } => {
             ui_state.clear_busy();
+            remember_handling(app, &sender, PolicyAction::Delete, None);
             // Demo mode doesn't have real destination UIDs, so we use None
             let undo_emails: Vec<(Option<String>, Option<u32>, String)> = emails
                 .iter()
-                .map(|e| (e.message_id.clone(), None, e.source_folder.clone()))
+                .map(|e| (e.message_id.clone(), None, e.source_folder.to_string()))
                 .collect();
             if !undo_emails.is_empty() {
                 let undo_entry = UndoEntry {
@@ -849,7 +1844,7 @@ fn execute_demo_op(
             // Demo mode doesn't have real destination UIDs, so we use None
             let undo_emails: Vec<(Option<String>, Option<u32>, String)> = thread_emails
                 .iter()
-                .map(|e| (e.message_id.clone(), None, e.source_folder.clone()))
+                .map(|e| (e.message_id.clone(), None, e.source_folder.to_string()))
                 .collect();
             if !undo_emails.is_empty() {
                 let undo_entry = UndoEntry {
@@ -885,7 +1880,7 @@ fn execute_demo_op(
             // Demo mode doesn't have real destination UIDs, so we use None
             let undo_emails: Vec<(Option<String>, Option<u32>, String)> = thread_emails
                 .iter()
-                .map(|e| (e.message_id.clone(), None, e.source_folder.clone()))
+                .map(|e| (e.message_id.clone(), None, e.source_folder.to_string()))
                 .collect();
             if !undo_emails.is_empty() {
                 let undo_entry = UndoEntry {
@@ -980,12 +1975,345 @@ fn execute_demo_op(
                 None
             }
         }
-        DemoPendingOp::Undo { index, emails } => {
+        DemoPendingOp::ArchiveDuplicates { emails, count } => {
+            ui_state.clear_busy();
+            // Demo mode doesn't have real destination UIDs, so we use None
+            let undo_emails: Vec<(Option<String>, Option<u32>, String)> = emails
+                .iter()
+                .map(|e| (e.message_id.clone(), None, e.source_folder.to_string()))
+                .collect();
+            let removed: Vec<(String, String)> = emails
+                .iter()
+                .map(|e| (e.id.clone(), e.source_folder.to_string()))
+                .collect();
+            let undo_entry = UndoEntry {
+                action_type: UndoActionType::Archive,
+                context: UndoContext::Group {
+                    sender: format!("{} duplicates", count),
+                },
+                emails: undo_emails,
+                current_folder: "[Gmail]/All Mail".to_string(),
+            };
+            undo_storage.push(emails);
+            app.push_undo(undo_entry);
+            app.remove_emails(&removed);
+            None
+        }
+        DemoPendingOp::DeleteDuplicates { emails, count } => {
+            ui_state.clear_busy();
+            // Demo mode doesn't have real destination UIDs, so we use None
+            let undo_emails: Vec<(Option<String>, Option<u32>, String)> = emails
+                .iter()
+                .map(|e| (e.message_id.clone(), None, e.source_folder.to_string()))
+                .collect();
+            let removed: Vec<(String, String)> = emails
+                .iter()
+                .map(|e| (e.id.clone(), e.source_folder.to_string()))
+                .collect();
+            let undo_entry = UndoEntry {
+                action_type: UndoActionType::Delete,
+                context: UndoContext::Group {
+                    sender: format!("{} duplicates", count),
+                },
+                emails: undo_emails,
+                current_folder: "[Gmail]/Trash".to_string(),
+            };
+            undo_storage.push(emails);
+            app.push_undo(undo_entry);
+            app.remove_emails(&removed);
+            None
+        }
+        DemoPendingOp::ArchiveOld {
+            emails,
+            days,
+            group,
+        } => {
+            ui_state.clear_busy();
+            // Demo mode doesn't have real destination UIDs, so we use None
+            let undo_emails: Vec<(Option<String>, Option<u32>, String)> = emails
+                .iter()
+                .map(|e| (e.message_id.clone(), None, e.source_folder.to_string()))
+                .collect();
+            let removed: Vec<(String, String)> = emails
+                .iter()
+                .map(|e| (e.id.clone(), e.source_folder.to_string()))
+                .collect();
+            let undo_entry = UndoEntry {
+                action_type: UndoActionType::Archive,
+                context: UndoContext::Group {
+                    sender: old_mail_label(days, group.as_deref()),
+                },
+                emails: undo_emails,
+                current_folder: "[Gmail]/All Mail".to_string(),
+            };
+            undo_storage.push(emails);
+            app.push_undo(undo_entry);
+            app.remove_emails(&removed);
+            None
+        }
+        DemoPendingOp::ArchiveSingles { emails } => {
+            ui_state.clear_busy();
+            // Demo mode doesn't have real destination UIDs, so we use None
+            let undo_emails: Vec<(Option<String>, Option<u32>, String)> = emails
+                .iter()
+                .map(|e| (e.message_id.clone(), None, e.source_folder.to_string()))
+                .collect();
+            let removed: Vec<(String, String)> = emails
+                .iter()
+                .map(|e| (e.id.clone(), e.source_folder.to_string()))
+                .collect();
+            let undo_entry = UndoEntry {
+                action_type: UndoActionType::Archive,
+                context: UndoContext::Group {
+                    sender: "single messages".to_string(),
+                },
+                emails: undo_emails,
+                current_folder: "[Gmail]/All Mail".to_string(),
+            };
+            undo_storage.push(emails);
+            app.push_undo(undo_entry);
+            app.remove_emails(&removed);
+            None
+        }
+        DemoPendingOp::ArchiveRead { emails, group } => {
+            ui_state.clear_busy();
+            // Demo mode doesn't have real destination UIDs, so we use None
+            let undo_emails: Vec<(Option<String>, Option<u32>, String)> = emails
+                .iter()
+                .map(|e| (e.message_id.clone(), None, e.source_folder.to_string()))
+                .collect();
+            let removed: Vec<(String, String)> = emails
+                .iter()
+                .map(|e| (e.id.clone(), e.source_folder.to_string()))
+                .collect();
+            let undo_entry = UndoEntry {
+                action_type: UndoActionType::Archive,
+                context: UndoContext::Group {
+                    sender: read_mail_label(group.as_deref()),
+                },
+                emails: undo_emails,
+                current_folder: "[Gmail]/All Mail".to_string(),
+            };
+            undo_storage.push(emails);
+            app.push_undo(undo_entry);
+            app.remove_emails(&removed);
+            None
+        }
+        DemoPendingOp::ArchiveSender { emails, sender } => {
+            ui_state.clear_busy();
+            // Demo mode doesn't have real destination UIDs, so we use None
+            let undo_emails: Vec<(Option<String>, Option<u32>, String)> = emails
+                .iter()
+                .map(|e| (e.message_id.clone(), None, e.source_folder.to_string()))
+                .collect();
+            let removed: Vec<(String, String)> = emails
+                .iter()
+                .map(|e| (e.id.clone(), e.source_folder.to_string()))
+                .collect();
+            let undo_entry = UndoEntry {
+                action_type: UndoActionType::Archive,
+                context: UndoContext::Group { sender },
+                emails: undo_emails,
+                current_folder: "[Gmail]/All Mail".to_string(),
+            };
+            undo_storage.push(emails);
+            app.push_undo(undo_entry);
+            app.remove_emails(&removed);
+            None
+        }
+        DemoPendingOp::ArchiveSweep { emails } => {
+            ui_state.clear_busy();
+            // Demo mode doesn't have real destination UIDs, so we use None
+            let undo_emails: Vec<(Option<String>, Option<u32>, String)> = emails
+                .iter()
+                .map(|e| (e.message_id.clone(), None, e.source_folder.to_string()))
+                .collect();
+            let removed: Vec<(String, String)> = emails
+                .iter()
+                .map(|e| (e.id.clone(), e.source_folder.to_string()))
+                .collect();
+            let undo_entry = UndoEntry {
+                action_type: UndoActionType::Archive,
+                context: UndoContext::Group {
+                    sender: "inbox sweep".to_string(),
+                },
+                emails: undo_emails,
+                current_folder: "[Gmail]/All Mail".to_string(),
+            };
+            undo_storage.push(emails);
+            app.push_undo(undo_entry);
+            app.remove_emails(&removed);
+            app.cancel_sweep();
+            None
+        }
+        DemoPendingOp::ArchiveGroups { emails, senders } => {
+            ui_state.clear_busy();
+            for sender in &senders {
+                remember_handling(app, sender, PolicyAction::Archive, None);
+            }
+            // Demo mode doesn't have real destination UIDs, so we use None
+            let undo_emails: Vec<(Option<String>, Option<u32>, String)> = emails
+                .iter()
+                .map(|e| (e.message_id.clone(), None, e.source_folder.to_string()))
+                .collect();
+            let removed: Vec<(String, String)> = emails
+                .iter()
+                .map(|e| (e.id.clone(), e.source_folder.to_string()))
+                .collect();
+            let undo_entry = UndoEntry {
+                action_type: UndoActionType::Archive,
+                context: UndoContext::Group {
+                    sender: format!("{} groups", senders.len()),
+                },
+                emails: undo_emails,
+                current_folder: "[Gmail]/All Mail".to_string(),
+            };
+            undo_storage.push(emails);
+            app.push_undo(undo_entry);
+            app.remove_emails(&removed);
+            app.clear_group_selection();
+            None
+        }
+        DemoPendingOp::DeleteGroups { emails, senders } => {
+            ui_state.clear_busy();
+            for sender in &senders {
+                remember_handling(app, sender, PolicyAction::Delete, None);
+            }
+            // Demo mode doesn't have real destination UIDs, so we use None
+            let undo_emails: Vec<(Option<String>, Option<u32>, String)> = emails
+                .iter()
+                .map(|e| (e.message_id.clone(), None, e.source_folder.to_string()))
+                .collect();
+            let removed: Vec<(String, String)> = emails
+                .iter()
+                .map(|e| (e.id.clone(), e.source_folder.to_string()))
+                .collect();
+            let undo_entry = UndoEntry {
+                action_type: UndoActionType::Delete,
+                context: UndoContext::Group {
+                    sender: format!("{} groups", senders.len()),
+                },
+                emails: undo_emails,
+                current_folder: "[Gmail]/Trash".to_string(),
+            };
+            undo_storage.push(emails);
+            app.push_undo(undo_entry);
+            app.remove_emails(&removed);
+            app.clear_group_selection();
+            None
+        }
+        DemoPendingOp::Move {
+            emails,
+            dest,
+            context,
+            archive,
+        } => {
+            ui_state.clear_busy();
+            let moved: Vec<(String, String)> = emails
+                .iter()
+                .map(|e| (e.id.clone(), e.source_folder.to_string()))
+                .collect();
+            let undo_entry = UndoEntry {
+                action_type: if archive {
+                    UndoActionType::File
+                } else {
+                    UndoActionType::Move
+                },
+                context,
+                emails: emails
+                    .iter()
+                    .map(|e| (e.message_id.clone(), None, e.source_folder.to_string()))
+                    .collect(),
+                current_folder: dest.clone(),
+            };
+            undo_storage.push(emails);
+            app.push_undo(undo_entry);
+            app.remove_moved_emails(&moved);
+            ui_state.set_status(format!(
+                "{} {} email(s) in {}",
+                if archive { "Filed" } else { "Moved" },
+                moved.len(),
+                dest
+            ));
+            None
+        }
+        DemoPendingOp::Undo {
+            index,
+            mut emails,
+            dest,
+        } => {
             ui_state.clear_busy();
-            app.restore_emails(emails);
+            match dest.as_deref() {
+                None => app.restore_emails(emails),
+                Some(dest) => {
+                    ui_state.set_status(format!(
+                        "Demo mode: restored {} email(s) to {}",
+                        emails.len(),
+                        dest
+                    ));
+                    // Only the inbox is loaded, so mail restored elsewhere stays out of view
+                    if dest == "INBOX" {
+                        for email in &mut emails {
+                            email.source_folder = dest.into();
+                        }
+                        app.restore_emails(emails);
+                    }
+                }
+            }
             app.pop_undo(index);
             None
         }
+        DemoPendingOp::UndoRecent { emails } => {
+            ui_state.clear_busy();
+            for _ in 0..emails.len() {
+                app.pop_undo(0);
+            }
+            app.restore_emails(emails.into_iter().flatten().collect());
+            None
+        }
+    }
+}
+
+/// Abandons a demo operation after a simulated failure, leaving the emails in place
+fn fail_demo_op(
+    app: &mut App,
+    ui_state: &mut UiState,
+    undo_storage: &mut DemoUndoStorage,
+    op: DemoPendingOp,
+) {
+    let action = op.busy_message().trim_end_matches("...");
+    ui_state.clear_busy();
+    ui_state.set_alert(format!("{} failed: simulated network error", action));
+
+    match op {
+        DemoPendingOp::ArchiveSelected { .. } | DemoPendingOp::DeleteSelected { .. } => {
+            // The undo entry was recorded when the operation started
+            app.pop_undo(0);
+            undo_storage.remove(0);
+        }
+        DemoPendingOp::Undo { index, emails, .. } => {
+            // Put the emails back so the undo can be retried
+            undo_storage.emails.insert(index, emails);
+        }
+        DemoPendingOp::UndoRecent { emails } => {
+            for (index, emails) in emails.into_iter().enumerate() {
+                undo_storage.emails.insert(index, emails);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Fills in placeholder bodies for the open thread in demo mode
+fn load_demo_inline_bodies(app: &mut App) {
+    let missing: Vec<(String, String, String)> = app
+        .current_thread_emails()
+        .iter()
+        .filter(|e| e.body.is_none())
+        .map(|e| (e.id.clone(), e.from.to_string(), e.subject.clone()))
+        .collect();
+    for (id, from, subject) in missing {
+        app.set_email_body(&id, demo_email_body(&from, &subject));
     }
 }
 
@@ -1005,7 +2333,7 @@ fn demo_email_body(from: &str, subject: &str) -> String {
 /// Handles 'a' key in demo mode - returns pending operation if action should proceed
 fn handle_demo_archive(app: &App, ui_state: &mut UiState) -> Option<DemoPendingOp> {
     match app.view {
-        View::GroupList | View::UndoHistory | View::EmailBody => None,
+        View::GroupList | View::UndoHistory | View::Duplicates | View::EmailBody => None,
         View::EmailList => {
             // Check if there are visible selected emails - require confirmation
             if app.has_visible_selection() {
@@ -1036,10 +2364,65 @@ fn handle_demo_archive(app: &App, ui_state: &mut UiState) -> Option<DemoPendingO
     }
 }
 
+/// Folders the demo's move picker offers
+const DEMO_FOLDERS: [&str; 4] = ["Receipts", "Travel", "Work", "[Gmail]/Spam"];
+
+/// Handles 'v', 'F' and '+' keys in demo mode
+fn handle_demo_move(app: &App, ui_state: &mut UiState, target: PickerTarget) {
+    if !app.move_targets().1.is_empty() {
+        open_folder_picker(ui_state, DEMO_FOLDERS.map(String::from).to_vec(), target);
+    }
+}
+
+/// Adds or removes the label chosen in the picker in demo mode
+fn demo_label(app: &mut App, ui_state: &mut UiState, label: &str, add: bool) {
+    let ids: Vec<String> = app.move_targets().1.iter().map(|e| e.id.clone()).collect();
+    let labels = vec![label.to_string()];
+    let (add, remove) = if add {
+        (labels, Vec::new())
+    } else {
+        (Vec::new(), labels)
+    };
+    app.modify_labels(&ids, &add, &remove);
+    ui_state.set_status(format!(
+        "Demo mode: {}",
+        labels_status(ids.len(), &add, &remove)
+    ));
+}
+
+/// Moves the current emails to `dest` in demo mode
+fn demo_move(app: &App, dest: &str, archive: bool) -> Option<DemoPendingOp> {
+    let (context, emails) = app.move_targets();
+    if emails.is_empty() {
+        return None;
+    }
+    Some(DemoPendingOp::Move {
+        emails: emails.into_iter().cloned().collect(),
+        dest: dest.to_string(),
+        context,
+        archive,
+    })
+}
+
+/// Handles 'M' key in demo mode
+fn handle_demo_toggle_seen(app: &mut App, ui_state: &mut UiState) {
+    let Some((emails, seen)) = seen_change(app) else {
+        return;
+    };
+    let ids: Vec<String> = emails.into_iter().map(|(id, _)| id).collect();
+    app.set_seen(&ids, seen);
+    ui_state.set_status(format!(
+        "Demo mode: marked {} email(s) {}",
+        ids.len(),
+        if seen { "read" } else { "unread" }
+    ));
+}
+
 /// Handles 'A' key in demo mode
 fn handle_demo_archive_all(app: &App, ui_state: &mut UiState) {
     match app.view {
-        View::GroupList | View::UndoHistory => {}
+        View::GroupList => confirm_selected_groups(app, ui_state, PolicyAction::Archive),
+        View::UndoHistory | View::Duplicates => {}
         View::EmailList => {
             // Archive all threads touched by this group's visible emails
             if let Some(group) = app.current_group() {
@@ -1064,7 +2447,7 @@ fn handle_demo_archive_all(app: &App, ui_state: &mut UiState) {
 /// Handles 'd' key in demo mode - returns pending operation if action should proceed
 fn handle_demo_delete(app: &App, ui_state: &mut UiState) -> Option<DemoPendingOp> {
     match app.view {
-        View::GroupList | View::UndoHistory | View::EmailBody => None,
+        View::GroupList | View::UndoHistory | View::Duplicates | View::EmailBody => None,
         View::EmailList => {
             // Check if there are visible selected emails - require confirmation
             if app.has_visible_selection() {
@@ -1098,7 +2481,8 @@ fn handle_demo_delete(app: &App, ui_state: &mut UiState) -> Option<DemoPendingOp
 /// Handles 'D' key in demo mode
 fn handle_demo_delete_all(app: &App, ui_state: &mut UiState) {
     match app.view {
-        View::GroupList | View::UndoHistory => {}
+        View::GroupList => confirm_selected_groups(app, ui_state, PolicyAction::Delete),
+        View::UndoHistory | View::Duplicates => {}
         View::EmailList => {
             // Delete all threads touched by this group's visible emails
             if let Some(group) = app.current_group() {
@@ -1120,14 +2504,20 @@ fn handle_demo_delete_all(app: &App, ui_state: &mut UiState) {
     }
 }
 
-/// Handles undo in demo mode - returns pending operation if action should proceed
-fn handle_demo_undo(app: &App, undo_storage: &mut DemoUndoStorage) -> Option<DemoPendingOp> {
+/// Handles undo in demo mode, into `dest` when set instead of the original folders -
+/// returns pending operation if action should proceed
+fn handle_demo_undo(
+    app: &App,
+    undo_storage: &mut DemoUndoStorage,
+    dest: Option<&str>,
+) -> Option<DemoPendingOp> {
     let selected_idx = app.selected_undo;
     undo_storage
         .remove(selected_idx)
         .map(|emails| DemoPendingOp::Undo {
             index: selected_idx,
             emails,
+            dest: dest.map(String::from),
         })
 }
 
@@ -1204,7 +2594,89 @@ fn handle_demo_confirmed_action(app: &App, action: ConfirmAction) -> Option<Demo
                 None
             }
         }
-        ConfirmAction::Quit => unreachable!(),
+        ConfirmAction::ArchiveDuplicates { count, all_sets } => {
+            let emails: Vec<Email> = app
+                .duplicate_extras(all_sets)
+                .into_iter()
+                .cloned()
+                .collect();
+            (!emails.is_empty()).then_some(DemoPendingOp::ArchiveDuplicates { emails, count })
+        }
+        ConfirmAction::DeleteDuplicates { count, all_sets } => {
+            let emails: Vec<Email> = app
+                .duplicate_extras(all_sets)
+                .into_iter()
+                .cloned()
+                .collect();
+            (!emails.is_empty()).then_some(DemoPendingOp::DeleteDuplicates { emails, count })
+        }
+        ConfirmAction::ArchiveOld { days, .. } => {
+            let emails: Vec<Email> = app.old_inbox_emails().into_iter().cloned().collect();
+            (!emails.is_empty()).then_some(DemoPendingOp::ArchiveOld {
+                emails,
+                days,
+                group: None,
+            })
+        }
+        ConfirmAction::ArchiveGroupOld {
+            group,
+            days,
+            cutoff,
+            ..
+        } => {
+            let emails: Vec<Email> = app
+                .group_emails_older_than(&group, cutoff)
+                .into_iter()
+                .cloned()
+                .collect();
+            (!emails.is_empty()).then_some(DemoPendingOp::ArchiveOld {
+                emails,
+                days,
+                group: Some(group),
+            })
+        }
+        ConfirmAction::ArchiveSingles { .. } => {
+            let emails: Vec<Email> = app.single_message_emails().into_iter().cloned().collect();
+            (!emails.is_empty()).then_some(DemoPendingOp::ArchiveSingles { emails })
+        }
+        ConfirmAction::ArchiveRead { group, .. } => {
+            let emails: Vec<Email> = app
+                .read_inbox_emails(group.as_deref())
+                .into_iter()
+                .cloned()
+                .collect();
+            (!emails.is_empty()).then_some(DemoPendingOp::ArchiveRead { emails, group })
+        }
+        ConfirmAction::ArchiveSender { sender, .. } => {
+            let emails: Vec<Email> = app
+                .sender_inbox_emails(&sender)
+                .into_iter()
+                .cloned()
+                .collect();
+            (!emails.is_empty()).then_some(DemoPendingOp::ArchiveSender { emails, sender })
+        }
+        ConfirmAction::ArchiveSweep { .. } => {
+            let emails: Vec<Email> = app.sweep_emails().into_iter().cloned().collect();
+            (!emails.is_empty()).then_some(DemoPendingOp::ArchiveSweep { emails })
+        }
+        ConfirmAction::ArchiveGroups { .. } => {
+            let emails = app.selected_groups_thread_emails_cloned();
+            let senders = app.selected_group_keys();
+            (!emails.is_empty()).then_some(DemoPendingOp::ArchiveGroups { emails, senders })
+        }
+        ConfirmAction::DeleteGroups { .. } => {
+            let emails = app.selected_groups_thread_emails_cloned();
+            let senders = app.selected_group_keys();
+            (!emails.is_empty()).then_some(DemoPendingOp::DeleteGroups { emails, senders })
+        }
+        // Demo mode has no configured policies
+        ConfirmAction::ApplyPolicies { .. } => None,
+        ConfirmAction::Quit
+        | ConfirmAction::IgnoreSender { .. }
+        | ConfirmAction::UndoRecent { .. }
+        | ConfirmAction::SendReply { .. }
+        | ConfirmAction::RetryFetch { .. }
+        | ConfirmAction::Resync { .. } => unreachable!(),
     }
 }
 
@@ -1220,7 +2692,7 @@ fn select_account(
         .collect();
 
     // Sort accounts alphabetically by name (case-insensitive)
-    accounts.sort_by(|a, b| a.0.to_lowercase().cmp(&b.0.to_lowercase()));
+    accounts.sort_by_key(|a| a.0.to_lowercase());
 
     let mut selection = AccountSelection::new(accounts);
 
@@ -1255,6 +2727,13 @@ fn select_account(
     }
 }
 
+/// How long the IMAP worker waits idle before asking the server for other clients' changes
+const MAILBOX_POLL_INTERVAL: Duration = Duration::from_secs(30);
+/// How long the IDLE connection waits before re-issuing IDLE, well inside the 29 minutes
+/// servers allow
+const IDLE_REFRESH: Duration = Duration::from_secs(10 * 60);
+/// How long the IDLE connection waits before reconnecting after an error
+const IDLE_RECONNECT_DELAY: Duration = Duration::from_secs(60);
 /// Maximum number of retry attempts for IMAP operations
 const MAX_RETRIES: u32 = 3;
 /// Initial backoff delay in milliseconds (doubles with each retry)
@@ -1316,242 +2795,514 @@ where
     Err(last_error.unwrap())
 }
 
-/// Spawns the IMAP worker thread
-fn spawn_imap_worker(
-    cmd_rx: mpsc::Receiver<ImapCommand>,
-    resp_tx: mpsc::Sender<ImapResponse>,
-    account: AccountConfig,
-) {
-    thread::spawn(move || {
-        debug_log!("IMAP worker: connecting to {}", account.email);
-        let mut client = match ImapClient::connect(&account.email, &account.app_password) {
-            Ok(c) => {
-                debug_log!("IMAP worker: connected successfully");
-                c
-            }
-            Err(e) => {
-                debug_log!("IMAP worker: connection failed: {}", e);
-                let _ = resp_tx.send(ImapResponse::Error(format!("Failed to connect: {}", e)));
-                return;
-            }
-        };
+/// Formats a date the way IMAP SEARCH expects it ("15-Apr-2026")
+fn imap_date(date: chrono::NaiveDate) -> String {
+    date.format("%d-%b-%Y").to_string()
+}
 
-        let _ = resp_tx.send(ImapResponse::Connected);
+/// Which of a folder's messages (newest first) to fetch when paging through a large mailbox
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+struct FetchPage {
+    /// The lowest UID loaded from each folder; only messages below it are fetched. Mail
+    /// archived since the last page shifts sequence numbers, but not these UIDs.
+    below_uid: HashMap<String, u32>,
+    /// How many emails are loaded from each folder, skipped by API backends, whose ids
+    /// aren't ordered. Archived mail has left the folder, so it isn't counted.
+    loaded: HashMap<String, u32>,
+    /// Messages to fetch per folder
+    count: u32,
+}
 
-        // Process commands
-        while let Ok(cmd) = cmd_rx.recv() {
-            match cmd {
-                ImapCommand::FetchInbox {
-                    parallel_connections,
-                } => {
-                    debug_log!(
-                        "FetchInbox: starting with {} parallel connections",
-                        parallel_connections
-                    );
-                    let fetch_start = Instant::now();
+impl FetchPage {
+    /// The first page: a folder's newest `count` messages
+    fn newest(count: u32) -> Self {
+        Self {
+            count,
+            ..Self::default()
+        }
+    }
 
-                    // Get message counts first (with retry)
-                    let resp_tx_retry = resp_tx.clone();
-                    let inbox_count = match retry_with_backoff(
-                        || client.get_folder_count("INBOX"),
-                        |attempt| {
-                            let _ = resp_tx_retry.send(ImapResponse::Retrying {
-                                attempt,
-                                max_attempts: MAX_RETRIES,
-                                action: "fetch".to_string(),
-                            });
-                        },
-                    ) {
-                        Ok(c) => c,
-                        Err(e) => {
-                            let _ = resp_tx.send(ImapResponse::Emails(Err(e)));
-                            continue;
-                        }
-                    };
-                    let resp_tx_retry = resp_tx.clone();
-                    let sent_count = match retry_with_backoff(
-                        || client.get_folder_count("[Gmail]/Sent Mail"),
-                        |attempt| {
-                            let _ = resp_tx_retry.send(ImapResponse::Retrying {
-                                attempt,
-                                max_attempts: MAX_RETRIES,
-                                action: "fetch".to_string(),
-                            });
-                        },
-                    ) {
-                        Ok(c) => c,
-                        Err(e) => {
-                            let _ = resp_tx.send(ImapResponse::Emails(Err(e)));
-                            continue;
-                        }
-                    };
+    /// Returns the 0-based positions (oldest first) of the newest `count` messages of a
+    /// folder with `total`, for a folder nothing was loaded from yet
+    fn positions(&self, total: u32) -> std::ops::Range<u32> {
+        total.saturating_sub(self.count)..total
+    }
+}
 
-                    let total = (inbox_count + sent_count) as usize;
-                    debug_log!(
-                        "FetchInbox: found {} inbox + {} sent = {} total emails",
-                        inbox_count,
-                        sent_count,
-                        total
-                    );
+/// A slice of a folder handled by one parallel fetch connection
+#[derive(Clone)]
+enum FetchChunk {
+    /// Sequence numbers start..=end
+    Range(u32, u32),
+    /// Specific UIDs found by a search
+    Uids(Vec<u32>),
+}
 
-                    // If mailbox is empty or small, use sequential fetch
-                    if total == 0 {
-                        debug_log!("FetchInbox: no emails to fetch");
-                        let _ = resp_tx.send(ImapResponse::Emails(Ok(Vec::new())));
-                        continue;
-                    }
+/// Fetches INBOX and Sent Mail across parallel connections, reporting progress.
+/// With a search query (e.g. "SINCE 15-Apr-2026") only matching messages are fetched,
+/// and with a page only that slice of each folder's newest messages. When paging, the
+/// number of older messages left is sent as `ImapResponse::OlderRemaining`.
+/// Returns the raw emails; the caller dedupes them and builds thread IDs.
+fn fetch_mailbox(
+    client: &mut ImapClient,
+    account: &AccountConfig,
+    resp_tx: &mpsc::Sender<ImapResponse>,
+    parallel_connections: usize,
+    search: Option<&str>,
+    page: Option<&FetchPage>,
+) -> Result<Vec<Email>> {
+    let jobs = plan_fetch(client, resp_tx, parallel_connections, search, page)?;
+    fetch_chunks(account, resp_tx, jobs).map_err(|partial| partial.error)
+}
 
-                    // Shared counter for progress reporting
-                    let fetched_count = Arc::new(AtomicUsize::new(0));
+/// Mail fetched before some connections gave up (after retries), and the chunks they missed
+struct PartialFetch {
+    emails: Vec<Email>,
+    missing: Vec<(&'static str, FetchChunk)>,
+    error: anyhow::Error,
+}
 
-                    // Calculate how many workers we actually need
-                    let num_workers = parallel_connections.min(total).max(1);
+/// A FetchInbox with some chunks still to fetch
+struct InterruptedFetch {
+    emails: Vec<Email>,
+    missing: Vec<(&'static str, FetchChunk)>,
+    merge_subject_threads: bool,
+    started: Instant,
+}
 
-                    // Spawn progress reporting thread
-                    let progress_fetched = Arc::clone(&fetched_count);
-                    let progress_tx = resp_tx.clone();
-                    let progress_handle = thread::spawn(move || {
-                        loop {
-                            let current = progress_fetched.load(Ordering::Relaxed);
-                            let _ = progress_tx.send(ImapResponse::Progress(
-                                current,
-                                total,
-                                "Loading".to_string(),
-                            ));
-                            if current >= total {
-                                break;
-                            }
-                            thread::sleep(Duration::from_millis(100));
-                        }
-                    });
+impl InterruptedFetch {
+    /// Fetches the missing chunks and sends the finished, threaded emails. If some chunks
+    /// fail again, reports the interruption and returns the fetch so it can be retried.
+    fn resume(
+        mut self,
+        account: &AccountConfig,
+        resp_tx: &mpsc::Sender<ImapResponse>,
+    ) -> Option<InterruptedFetch> {
+        match fetch_chunks(account, resp_tx, std::mem::take(&mut self.missing)) {
+            Ok(emails) => {
+                let mut all_emails = self.emails;
+                all_emails.extend(emails);
+                // Dedupe and build thread IDs
+                email::dedupe_emails(&mut all_emails);
+                email::build_thread_ids(&mut all_emails);
+                if self.merge_subject_threads {
+                    email::merge_subject_threads(&mut all_emails);
+                }
+                debug_log!(
+                    "FetchInbox: completed in {:.2}s, fetched {} emails",
+                    self.started.elapsed().as_secs_f64(),
+                    all_emails.len()
+                );
+                let _ = resp_tx.send(ImapResponse::Emails(Ok(all_emails)));
+                None
+            }
+            Err(partial) => {
+                debug_log!(
+                    "FetchInbox: {} chunk(s) failed: {}",
+                    partial.missing.len(),
+                    partial.error
+                );
+                self.emails.extend(partial.emails);
+                self.missing = partial.missing;
+                let _ = resp_tx.send(ImapResponse::FetchInterrupted {
+                    error: imap_error::explain(&partial.error),
+                    fetched: self.emails.len(),
+                    missing_ranges: self.missing.len(),
+                });
+                Some(self)
+            }
+        }
+    }
+}
 
-                    // Spawn parallel inbox fetchers
-                    let inbox_chunk_size = if inbox_count > 0 {
-                        (inbox_count as usize).div_ceil(num_workers)
-                    } else {
-                        0
-                    };
+/// Splits each folder into one chunk per parallel connection
+fn plan_fetch(
+    client: &mut ImapClient,
+    resp_tx: &mpsc::Sender<ImapResponse>,
+    parallel_connections: usize,
+    search: Option<&str>,
+    page: Option<&FetchPage>,
+) -> Result<Vec<(&'static str, FetchChunk)>> {
+    debug_log!(
+        "FetchInbox: starting with {} parallel connections (search: {:?})",
+        parallel_connections,
+        search
+    );
 
-                    let inbox_handles: Vec<_> = (0..num_workers)
-                        .filter_map(|i| {
-                            let start = (i * inbox_chunk_size + 1) as u32;
-                            let end = (((i + 1) * inbox_chunk_size) as u32).min(inbox_count);
-                            if start > inbox_count || inbox_count == 0 {
-                                return None;
-                            }
-                            let email_addr = account.email.clone();
-                            let password = account.app_password.clone();
-                            let counter = Arc::clone(&fetched_count);
-                            Some(thread::spawn(move || {
-                                retry_silent(|| {
-                                    let mut worker_client =
-                                        ImapClient::connect(&email_addr, &password)?;
-                                    let emails = worker_client.fetch_inbox_range(
-                                        start,
-                                        end,
-                                        Some(&counter),
-                                    )?;
-                                    let _ = worker_client.logout();
-                                    Ok::<_, anyhow::Error>(emails)
-                                })
-                            }))
-                        })
-                        .collect();
+    // Find what to fetch in each folder first (with retry)
+    let mut folders: Vec<(&'static str, Vec<FetchChunk>, usize)> = Vec::new();
+    let mut remaining = 0;
+    let sent = client.folders().sent;
+    for folder in ["INBOX", sent] {
+        let resp_tx_retry = resp_tx.clone();
+        let on_retry = |attempt| {
+            let _ = resp_tx_retry.send(ImapResponse::Retrying {
+                attempt,
+                max_attempts: MAX_RETRIES,
+                action: "fetch".to_string(),
+            });
+        };
+        let below_uid = page.and_then(|page| page.below_uid.get(folder).copied());
+        let (chunks, count) = match (search, below_uid) {
+            // Older pages only hold UIDs below the oldest loaded; UID 1 has none below it
+            (_, Some(lowest)) if lowest <= 1 => (Vec::new(), 0),
+            (None, None) => {
+                let total = retry_with_backoff(|| client.get_folder_count(folder), on_retry)?;
+                let positions = page.map_or(0..total, |page| page.positions(total));
+                remaining += positions.start as usize;
+                // Sequence numbers are 1-based
+                let (first, last) = (positions.start + 1, positions.end);
+                let count = positions.len();
+                let chunk_size = count.div_ceil(parallel_connections.max(1)).max(1);
+                let chunks = (first..=last)
+                    .step_by(chunk_size)
+                    .map(|start| {
+                        FetchChunk::Range(start, (start + chunk_size as u32 - 1).min(last))
+                    })
+                    .collect();
+                (chunks, count)
+            }
+            (search, below_uid) => {
+                let mut query = search.unwrap_or("ALL").to_string();
+                if let Some(lowest) = below_uid {
+                    query.push_str(&format!(" UID 1:{}", lowest - 1));
+                }
+                let mut uids =
+                    retry_with_backoff(|| client.search_folder_uids(folder, &query), on_retry)?;
+                if let Some(page) = page {
+                    let positions = page.positions(uids.len() as u32);
+                    remaining += positions.start as usize;
+                    uids = uids[positions.start as usize..positions.end as usize].to_vec();
+                }
+                let chunk_size = uids.len().div_ceil(parallel_connections.max(1)).max(1);
+                let chunks = uids
+                    .chunks(chunk_size)
+                    .map(|chunk| FetchChunk::Uids(chunk.to_vec()))
+                    .collect();
+                (chunks, uids.len())
+            }
+        };
+        folders.push((folder, chunks, count));
+    }
 
-                    // Spawn parallel sent fetchers
-                    let sent_chunk_size = if sent_count > 0 {
-                        (sent_count as usize).div_ceil(num_workers)
-                    } else {
-                        0
+    if page.is_some() {
+        let _ = resp_tx.send(ImapResponse::OlderRemaining(remaining));
+    }
+
+    let total: usize = folders.iter().map(|(_, _, count)| count).sum();
+    debug_log!("FetchInbox: found {} emails to fetch", total);
+    Ok(folders
+        .into_iter()
+        .flat_map(|(folder, chunks, _)| chunks.into_iter().map(move |chunk| (folder, chunk)))
+        .collect())
+}
+
+/// Fetches the chunks, each on its own connection. If any still fail after retries,
+/// returns what the others got along with the chunks that are missing.
+fn fetch_chunks(
+    account: &AccountConfig,
+    resp_tx: &mpsc::Sender<ImapResponse>,
+    jobs: Vec<(&'static str, FetchChunk)>,
+) -> std::result::Result<Vec<Email>, PartialFetch> {
+    // One counter per worker so each connection's progress can be shown
+    let counters: Vec<Arc<AtomicUsize>> =
+        jobs.iter().map(|_| Arc::new(AtomicUsize::new(0))).collect();
+    let workers: Vec<WorkerProgress> = jobs
+        .iter()
+        .map(|(folder, chunk)| WorkerProgress {
+            folder: folder.to_string(),
+            range: match chunk {
+                FetchChunk::Range(start, end) => Some((*start, *end)),
+                FetchChunk::Uids(_) => None,
+            },
+            fetched: 0,
+            total: match chunk {
+                FetchChunk::Range(start, end) => (end - start + 1) as usize,
+                FetchChunk::Uids(uids) => uids.len(),
+            },
+        })
+        .collect();
+    let total: usize = workers.iter().map(|w| w.total).sum();
+    if total == 0 {
+        return Ok(Vec::new());
+    }
+    let done = Arc::new(AtomicBool::new(false));
+
+    // Spawn progress reporting thread
+    let progress_counters = counters.clone();
+    let progress_done = Arc::clone(&done);
+    let progress_tx = resp_tx.clone();
+    let progress_handle = thread::spawn(move || {
+        let mut workers = workers;
+        loop {
+            for (worker, counter) in workers.iter_mut().zip(&progress_counters) {
+                worker.fetched = counter.load(Ordering::Relaxed).min(worker.total);
+            }
+            let current: usize = workers.iter().map(|w| w.fetched).sum();
+            let _ = progress_tx.send(ImapResponse::Progress(
+                current,
+                total,
+                "Loading".to_string(),
+            ));
+            if workers.len() > 1 {
+                let _ = progress_tx.send(ImapResponse::WorkerProgress(workers.clone()));
+            }
+            if current >= total || progress_done.load(Ordering::Relaxed) {
+                break;
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+    });
+
+    // Spawn one fetcher per chunk, each on its own connection
+    let handles: Vec<_> = jobs
+        .iter()
+        .cloned()
+        .zip(counters)
+        .map(|((folder, chunk), counter)| {
+            let worker_account = account.clone();
+            thread::spawn(move || {
+                retry_silent(|| {
+                    // A retry starts the chunk over, so its count does too
+                    counter.store(0, Ordering::Relaxed);
+                    let mut worker_client = ImapClient::connect(&worker_account)?;
+                    let emails = match &chunk {
+                        FetchChunk::Range(start, end) => worker_client.fetch_folder_range(
+                            folder,
+                            *start,
+                            *end,
+                            Some(&counter),
+                        )?,
+                        FetchChunk::Uids(uids) => {
+                            worker_client.fetch_folder_uids(folder, uids, Some(&counter))?
+                        }
                     };
+                    let _ = worker_client.logout();
+                    Ok::<_, anyhow::Error>(emails)
+                })
+            })
+        })
+        .collect();
 
-                    let sent_handles: Vec<_> = (0..num_workers)
-                        .filter_map(|i| {
-                            let start = (i * sent_chunk_size + 1) as u32;
-                            let end = (((i + 1) * sent_chunk_size) as u32).min(sent_count);
-                            if start > sent_count || sent_count == 0 {
-                                return None;
-                            }
-                            let email_addr = account.email.clone();
-                            let password = account.app_password.clone();
-                            let counter = Arc::clone(&fetched_count);
-                            Some(thread::spawn(move || {
-                                retry_silent(|| {
-                                    let mut worker_client =
-                                        ImapClient::connect(&email_addr, &password)?;
-                                    let emails = worker_client.fetch_sent_range(
-                                        start,
-                                        end,
-                                        Some(&counter),
-                                    )?;
-                                    let _ = worker_client.logout();
-                                    Ok::<_, anyhow::Error>(emails)
-                                })
-                            }))
-                        })
-                        .collect();
+    // Collect results, keeping track of the chunks that failed
+    let mut all_emails = Vec::new();
+    let mut missing = Vec::new();
+    let mut error: Option<anyhow::Error> = None;
+    for (handle, job) in handles.into_iter().zip(jobs) {
+        let failure = match handle.join() {
+            Ok(Ok(emails)) => {
+                all_emails.extend(emails);
+                continue;
+            }
+            Ok(Err(e)) => e,
+            Err(_) => anyhow::anyhow!("Worker thread panicked"),
+        };
+        error.get_or_insert(failure);
+        missing.push(job);
+    }
 
-                    // Collect inbox results
-                    let mut all_emails = Vec::new();
-                    let mut error: Option<anyhow::Error> = None;
+    // Signal progress thread to stop and wait for it
+    done.store(true, Ordering::Relaxed);
+    let _ = progress_handle.join();
 
-                    for handle in inbox_handles {
-                        match handle.join() {
-                            Ok(Ok(emails)) => all_emails.extend(emails),
-                            Ok(Err(e)) => {
-                                if error.is_none() {
-                                    error = Some(e);
-                                }
-                            }
-                            Err(_) => {
-                                if error.is_none() {
-                                    error = Some(anyhow::anyhow!("Worker thread panicked"));
-                                }
-                            }
+    match error {
+        Some(error) => Err(PartialFetch {
+            emails: all_emails,
+            missing,
+            error,
+        }),
+        None => Ok(all_emails),
+    }
+}
+
+/// The answer to a command that acts on UIDs read under an old UIDVALIDITY, which
+/// would now touch other messages, or None for commands that don't use UIDs
+fn refuse_stale_uids(cmd: &ImapCommand) -> Option<ImapResponse> {
+    let error = || anyhow::anyhow!("The server renumbered its messages, so this wasn't done");
+    let ids = |emails: &[(String, String)]| emails.iter().map(|(id, _)| id.clone()).collect();
+    Some(match cmd {
+        ImapCommand::ArchiveMultiple(_) => ImapResponse::MultiArchiveResult(Err(error())),
+        ImapCommand::DeleteMultiple(_) => ImapResponse::MultiDeleteResult(Err(error())),
+        ImapCommand::MoveMultiple { .. } => ImapResponse::MultiMoveResult(Err(error())),
+        ImapCommand::RestoreEmails(_) => ImapResponse::RestoreResult(Err(error())),
+        ImapCommand::Resync(_) => ImapResponse::Resynced(Err(error())),
+        ImapCommand::FetchPage { .. } => ImapResponse::OlderEmails(Err(error())),
+        ImapCommand::FetchBody { uid, .. } => ImapResponse::BodyResult {
+            uid: uid.clone(),
+            result: Err(error()),
+        },
+        ImapCommand::SetFlags { emails, seen } => ImapResponse::FlagsSet {
+            ids: ids(emails),
+            seen: *seen,
+            result: Err(error()),
+        },
+        ImapCommand::ModifyLabels {
+            emails,
+            add,
+            remove,
+        } => ImapResponse::LabelsModified {
+            ids: ids(emails),
+            add: add.clone(),
+            remove: remove.clone(),
+            result: Err(error()),
+        },
+        ImapCommand::FetchInbox { .. }
+        | ImapCommand::RetryFetch
+        | ImapCommand::FetchOlder { .. }
+        | ImapCommand::ListFolders
+        | ImapCommand::CheckUidValidity(_)
+        | ImapCommand::Shutdown => return None,
+    })
+}
+
+/// Spawns the IMAP worker thread
+fn spawn_imap_worker(
+    cmd_rx: mpsc::Receiver<ImapCommand>,
+    resp_tx: mpsc::Sender<ImapResponse>,
+    account: AccountConfig,
+    mark_read_on_archive: bool,
+    pgp_command: String,
+) {
+    thread::spawn(move || {
+        debug_log!("IMAP worker: connecting to {}", account.email);
+        let mut client = match ImapClient::connect(&account) {
+            Ok(c) => {
+                debug_log!("IMAP worker: connected successfully");
+                c
+            }
+            Err(e) => {
+                debug_log!("IMAP worker: connection failed: {}", e);
+                let _ = resp_tx.send(ImapResponse::Error(imap_error::describe(
+                    "Failed to connect",
+                    &e,
+                )));
+                return;
+            }
+        };
+
+        client.set_pgp_command(pgp_command);
+        let _ = resp_tx.send(ImapResponse::Connected);
+        send_server_notices(&mut client, &resp_tx);
+
+        // New inbox mail is pushed over a second connection, so this one stays free
+        let stop_idle = Arc::new(AtomicBool::new(false));
+        if client.supports_idle() {
+            spawn_idle_listener(account.clone(), resp_tx.clone(), Arc::clone(&stop_idle));
+        }
+
+        // A FetchInbox that failed partway, kept until it is retried or replaced
+        let mut interrupted: Option<InterruptedFetch> = None;
+        // The UI's UIDs were read under another UIDVALIDITY and point at other messages
+        let mut stale_uids = false;
+
+        // Process commands, checking for other clients' changes while idle
+        loop {
+            let cmd = match cmd_rx.recv_timeout(MAILBOX_POLL_INTERVAL) {
+                Ok(cmd) => cmd,
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    if let Err(e) = client.poll() {
+                        debug_log!("IMAP worker: poll failed: {:#}", e);
+                    }
+                    send_server_notices(&mut client, &resp_tx);
+                    continue;
+                }
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            };
+            if stale_uids && let Some(response) = refuse_stale_uids(&cmd) {
+                let _ = resp_tx.send(response);
+                continue;
+            }
+            match cmd {
+                ImapCommand::CheckUidValidity(cached) => match client.uid_validity() {
+                    Ok(current) => {
+                        // Nothing cached (a UI attaching to the daemon) clears the check
+                        stale_uids = !cached.is_empty() && current != cached;
+                        if stale_uids {
+                            debug_log!("IMAP worker: UIDVALIDITY changed since the cache");
                         }
+                        let _ = resp_tx.send(ImapResponse::UidValidity(current));
                     }
-
-                    // Collect sent results
-                    for handle in sent_handles {
-                        match handle.join() {
-                            Ok(Ok(emails)) => all_emails.extend(emails),
-                            Ok(Err(e)) => {
-                                if error.is_none() {
-                                    error = Some(e);
-                                }
-                            }
-                            Err(_) => {
-                                if error.is_none() {
-                                    error = Some(anyhow::anyhow!("Worker thread panicked"));
-                                }
-                            }
+                    Err(e) => debug_log!("IMAP worker: UIDVALIDITY check failed: {:#}", e),
+                },
+                ImapCommand::FetchInbox {
+                    parallel_connections,
+                    merge_subject_threads,
+                    since,
+                    limit,
+                } => {
+                    interrupted = None;
+                    match client.uid_validity() {
+                        Ok(current) => {
+                            let _ = resp_tx.send(ImapResponse::UidValidity(current));
                         }
+                        Err(e) => debug_log!("FetchInbox: UIDVALIDITY check failed: {:#}", e),
                     }
-
-                    // Signal progress thread to stop and wait for it
-                    fetched_count.store(total, Ordering::Relaxed);
-                    let _ = progress_handle.join();
-
-                    let result = if let Some(e) = error {
-                        debug_log!("FetchInbox: failed with error: {}", e);
-                        Err(e)
-                    } else {
-                        // Dedupe and build thread IDs
-                        email::dedupe_emails(&mut all_emails);
-                        email::build_thread_ids(&mut all_emails);
-                        debug_log!(
-                            "FetchInbox: completed in {:.2}s, fetched {} emails",
-                            fetch_start.elapsed().as_secs_f64(),
-                            all_emails.len()
-                        );
-                        Ok(all_emails)
+                    let fetch_start = Instant::now();
+                    let search = since.map(|date| format!("SINCE {}", imap_date(date)));
+                    let page = limit.map(FetchPage::newest);
+                    let jobs = match plan_fetch(
+                        &mut client,
+                        &resp_tx,
+                        parallel_connections,
+                        search.as_deref(),
+                        page.as_ref(),
+                    ) {
+                        Ok(jobs) => jobs,
+                        Err(e) => {
+                            debug_log!("FetchInbox: failed with error: {}", e);
+                            let _ = resp_tx.send(ImapResponse::Emails(Err(e)));
+                            continue;
+                        }
                     };
-
-                    let _ = resp_tx.send(ImapResponse::Emails(result));
+                    let fetch = InterruptedFetch {
+                        emails: Vec::new(),
+                        missing: jobs,
+                        merge_subject_threads,
+                        started: fetch_start,
+                    };
+                    interrupted = fetch.resume(&account, &resp_tx);
+                }
+                ImapCommand::RetryFetch => match interrupted.take() {
+                    Some(fetch) => interrupted = fetch.resume(&account, &resp_tx),
+                    None => {
+                        let _ = resp_tx.send(ImapResponse::Emails(Err(anyhow::anyhow!(
+                            "No interrupted fetch to retry"
+                        ))));
+                    }
+                },
+                ImapCommand::FetchOlder {
+                    parallel_connections,
+                    before,
+                } => {
+                    let search = format!("BEFORE {}", imap_date(before));
+                    let result = fetch_mailbox(
+                        &mut client,
+                        &account,
+                        &resp_tx,
+                        parallel_connections,
+                        Some(&search),
+                        None,
+                    );
+                    let _ = resp_tx.send(ImapResponse::OlderEmails(result));
+                }
+                ImapCommand::FetchPage {
+                    parallel_connections,
+                    since,
+                    page,
+                } => {
+                    let search = since.map(|date| format!("SINCE {}", imap_date(date)));
+                    let result = fetch_mailbox(
+                        &mut client,
+                        &account,
+                        &resp_tx,
+                        parallel_connections,
+                        search.as_deref(),
+                        Some(&page),
+                    );
+                    let _ = resp_tx.send(ImapResponse::OlderEmails(result));
                 }
                 ImapCommand::ArchiveMultiple(ids_and_folders) => {
                     use std::collections::HashMap;
-                    const BATCH_SIZE: usize = 250;
 
                     let total = ids_and_folders.len();
                     debug_log!(
@@ -1601,7 +3352,7 @@ fn spawn_imap_worker(
                             ));
                             let resp_tx_retry = resp_tx.clone();
                             let chunk_result = retry_with_backoff(
-                                || client.archive_batch(chunk, folder),
+                                || client.archive_batch(chunk, folder, mark_read_on_archive),
                                 |attempt| {
                                     let _ = resp_tx_retry.send(ImapResponse::Retrying {
                                         attempt,
@@ -1638,11 +3389,11 @@ fn spawn_imap_worker(
                         Some(e) => Err(e),
                         None => Ok(all_uid_maps),
                     };
+                    send_already_gone(&mut client, &resp_tx);
                     let _ = resp_tx.send(ImapResponse::MultiArchiveResult(result));
                 }
                 ImapCommand::DeleteMultiple(ids_and_folders) => {
                     use std::collections::HashMap;
-                    const BATCH_SIZE: usize = 250;
 
                     let total = ids_and_folders.len();
                     debug_log!(
@@ -1729,6 +3480,7 @@ fn spawn_imap_worker(
                         Some(e) => Err(e),
                         None => Ok(all_uid_maps),
                     };
+                    send_already_gone(&mut client, &resp_tx);
                     let _ = resp_tx.send(ImapResponse::MultiDeleteResult(result));
                 }
                 ImapCommand::RestoreEmails(restore_ops) => {
@@ -1770,16 +3522,61 @@ fn spawn_imap_worker(
 
                     let _ = resp_tx.send(ImapResponse::RestoreResult(result));
                 }
-                ImapCommand::FetchBody { uid, folder } => {
+                ImapCommand::Resync(emails) => {
+                    debug_log!("IMAP worker: resyncing {} emails", emails.len());
+                    let result = resync_mailbox(&mut client, &emails);
+                    let _ = resp_tx.send(ImapResponse::Resynced(result));
+                }
+                ImapCommand::FetchBody {
+                    uid,
+                    folder,
+                    max_bytes,
+                    mark_seen,
+                } => {
                     debug_log!("IMAP worker: fetching body for UID {} from {}", uid, folder);
-                    let result = client.fetch_email_body(&uid, &folder);
+                    let result = client.fetch_email_body(&uid, &folder, max_bytes, mark_seen);
                     let _ = resp_tx.send(ImapResponse::BodyResult { uid, result });
                 }
+                ImapCommand::MoveMultiple {
+                    emails,
+                    dest,
+                    archive,
+                } => {
+                    let mark_read = archive && mark_read_on_archive;
+                    let result = move_by_folder(&mut client, &emails, &dest, mark_read, &resp_tx);
+                    send_already_gone(&mut client, &resp_tx);
+                    let _ = resp_tx.send(ImapResponse::MultiMoveResult(result));
+                }
+                ImapCommand::ListFolders => {
+                    let _ = resp_tx.send(ImapResponse::Folders(client.list_folders()));
+                }
+                ImapCommand::SetFlags { emails, seen } => {
+                    let result = set_seen_by_folder(&mut client, &emails, seen, &resp_tx);
+                    let ids = emails.into_iter().map(|(id, _)| id).collect();
+                    let _ = resp_tx.send(ImapResponse::FlagsSet { ids, seen, result });
+                }
+                ImapCommand::ModifyLabels {
+                    emails,
+                    add,
+                    remove,
+                } => {
+                    let result =
+                        modify_labels_by_folder(&mut client, &emails, &add, &remove, &resp_tx);
+                    let ids = emails.into_iter().map(|(id, _)| id).collect();
+                    let _ = resp_tx.send(ImapResponse::LabelsModified {
+                        ids,
+                        add,
+                        remove,
+                        result,
+                    });
+                }
                 ImapCommand::Shutdown => {
                     debug_log!("IMAP worker: shutdown requested");
+                    stop_idle.store(true, Ordering::Relaxed);
                     break;
                 }
             }
+            send_server_notices(&mut client, &resp_tx);
         }
 
         // Properly close the IMAP session
@@ -1787,40 +3584,687 @@ fn spawn_imap_worker(
     });
 }
 
-fn run_app(
-    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
-    account: (String, AccountConfig),
-    parallel_connections: usize,
-    advance_on_select: bool,
-) -> Result<()> {
-    let (account_name, account_config) = account;
-    let user_email = account_config.email.clone();
-    let mut app = App::new();
-    app.set_user_email(user_email.clone());
-    let mut ui_state = UiState::new();
+/// Watches the inbox in IDLE on its own connection and sends NewMail as soon as mail
+/// arrives, reconnecting after errors, until `stop` is set or the UI is gone
+fn spawn_idle_listener(
+    account: AccountConfig,
+    resp_tx: mpsc::Sender<ImapResponse>,
+    stop: Arc<AtomicBool>,
+) {
+    thread::spawn(move || {
+        while !stop.load(Ordering::Relaxed) {
+            match ImapClient::connect(&account) {
+                Ok(mut client) => loop {
+                    match client.wait_for_new_mail(IDLE_REFRESH) {
+                        Ok(true) => {
+                            debug_log!("IDLE: new mail in the inbox");
+                            if resp_tx.send(ImapResponse::NewMail).is_err() {
+                                return;
+                            }
+                        }
+                        Ok(false) => {}
+                        Err(e) => {
+                            debug_log!("IDLE: {:#}", e);
+                            break;
+                        }
+                    }
+                    if stop.load(Ordering::Relaxed) {
+                        let _ = client.logout();
+                        return;
+                    }
+                },
+                Err(e) => debug_log!("IDLE: connection failed: {:#}", e),
+            }
+            thread::sleep(IDLE_RECONNECT_DELAY);
+        }
+    });
+}
 
-    // Create channels for IMAP communication
-    let (cmd_tx, cmd_rx) = mpsc::channel::<ImapCommand>();
-    let (resp_tx, resp_rx) = mpsc::channel::<ImapResponse>();
+/// Spawns the worker thread for the account's backend
+fn spawn_worker(
+    cmd_rx: mpsc::Receiver<ImapCommand>,
+    resp_tx: mpsc::Sender<ImapResponse>,
+    account: AccountConfig,
+    mark_read_on_archive: bool,
+    pgp_command: String,
+) {
+    match account.backend {
+        config::Backend::Jmap => {
+            thread::spawn(move || {
+                debug_log!("API worker: connecting to {} over JMAP", account.email);
+                match JmapClient::connect(&account) {
+                    Ok(client) => run_api_worker(client, cmd_rx, resp_tx, mark_read_on_archive),
+                    Err(e) => {
+                        debug_log!("API worker: connection failed: {:#}", e);
+                        let _ = resp_tx.send(ImapResponse::Error(imap_error::describe(
+                            "Failed to connect",
+                            &e,
+                        )));
+                    }
+                }
+            });
+        }
+        // Falls back to IMAP when the API can't be reached, e.g. it isn't enabled for the
+        // account or the token command fails
+        config::Backend::GmailApi => {
+            thread::spawn(move || {
+                debug_log!(
+                    "API worker: connecting to {} over the Gmail API",
+                    account.email
+                );
+                match GmailApiClient::connect(&account, &pgp_command) {
+                    Ok(client) => run_api_worker(client, cmd_rx, resp_tx, mark_read_on_archive),
+                    Err(e) => {
+                        debug_log!("API worker: Gmail API unavailable: {:#}", e);
+                        let _ = resp_tx.send(ImapResponse::ServerAlert(format!(
+                            "The Gmail API is unavailable, so this session uses IMAP: {:#}",
+                            e
+                        )));
+                        spawn_imap_worker(
+                            cmd_rx,
+                            resp_tx,
+                            account,
+                            mark_read_on_archive,
+                            pgp_command,
+                        );
+                    }
+                }
+            });
+        }
+        config::Backend::Gmail | config::Backend::Outlook => {
+            spawn_imap_worker(cmd_rx, resp_tx, account, mark_read_on_archive, pgp_command);
+        }
+    }
+}
 
-    // Show connecting status
-    ui_state.set_busy(format!("Connecting to {}...", account_name));
-    app.ensure_valid_selection();
-    terminal.draw(|f| render(f, &app, &mut ui_state))?;
+/// Sends a retry notice for `action` to the UI
+fn report_retry(resp_tx: &mpsc::Sender<ImapResponse>, action: &str) -> impl FnMut(u32) {
+    let resp_tx = resp_tx.clone();
+    let action = action.to_string();
+    move |attempt| {
+        let _ = resp_tx.send(ImapResponse::Retrying {
+            attempt,
+            max_attempts: MAX_RETRIES,
+            action: action.clone(),
+        });
+    }
+}
 
-    // Spawn IMAP worker thread
-    spawn_imap_worker(cmd_rx, resp_tx, account_config);
+/// Fetches the inbox and sent mailboxes of an API account, sending how many older
+/// emails are left when paging. Returns the raw emails like `fetch_mailbox`.
+fn fetch_api_mailbox(
+    client: &mut impl ApiClient,
+    resp_tx: &mpsc::Sender<ImapResponse>,
+    since: Option<chrono::NaiveDate>,
+    before: Option<chrono::NaiveDate>,
+    page: Option<&FetchPage>,
+) -> Result<Vec<Email>> {
+    let mut emails = Vec::new();
+    let mut remaining = 0;
+    let sent = client.folders().sent;
+    for folder in ["INBOX", sent] {
+        let (skip, limit) = page.map_or((0, None), |page| {
+            let loaded = page.loaded.get(folder).copied().unwrap_or(0);
+            (loaded, Some(page.count))
+        });
+        let (fetched, left) = retry_with_backoff(
+            || client.fetch_folder(folder, since, before, skip, limit),
+            report_retry(resp_tx, "fetch"),
+        )?;
+        emails.extend(fetched);
+        remaining += left;
+    }
+    if page.is_some() {
+        let _ = resp_tx.send(ImapResponse::OlderRemaining(remaining));
+    }
+    debug_log!("API worker: fetched {} emails", emails.len());
+    Ok(emails)
+}
 
-    // Wait for connection
-    loop {
-        // Check for responses
-        match resp_rx.recv_timeout(Duration::from_millis(100)) {
-            Ok(ImapResponse::Connected) => {
-                ui_state.set_busy("Loading emails...");
-                app.ensure_valid_selection();
+/// Answers the UI's commands for a JMAP or Gmail API account until shutdown. It takes
+/// the same commands as the IMAP worker, but a fetch is a few batched requests instead of
+/// parallel connections, and moving emails is one request per batch, whichever folders
+/// they are in.
+fn run_api_worker(
+    mut client: impl ApiClient,
+    cmd_rx: mpsc::Receiver<ImapCommand>,
+    resp_tx: mpsc::Sender<ImapResponse>,
+    mark_read_on_archive: bool,
+) {
+    let _ = resp_tx.send(ImapResponse::Connected);
+
+    while let Ok(cmd) = cmd_rx.recv() {
+        match cmd {
+            ImapCommand::FetchInbox {
+                merge_subject_threads,
+                since,
+                limit,
+                ..
+            } => {
+                let page = limit.map(FetchPage::newest);
+                let result = fetch_api_mailbox(&mut client, &resp_tx, since, None, page.as_ref())
+                    .map(|mut emails| {
+                        email::dedupe_emails(&mut emails);
+                        email::build_thread_ids(&mut emails);
+                        if merge_subject_threads {
+                            email::merge_subject_threads(&mut emails);
+                        }
+                        emails
+                    });
+                let _ = resp_tx.send(ImapResponse::Emails(result));
+            }
+            // An API fetch either fails as a whole or not at all, so nothing is interrupted
+            ImapCommand::RetryFetch => {
+                let _ = resp_tx.send(ImapResponse::Emails(Err(anyhow::anyhow!(
+                    "No interrupted fetch to retry"
+                ))));
+            }
+            ImapCommand::FetchOlder { before, .. } => {
+                let result = fetch_api_mailbox(&mut client, &resp_tx, None, Some(before), None);
+                let _ = resp_tx.send(ImapResponse::OlderEmails(result));
+            }
+            ImapCommand::FetchPage { since, page, .. } => {
+                let result = fetch_api_mailbox(&mut client, &resp_tx, since, None, Some(&page));
+                let _ = resp_tx.send(ImapResponse::OlderEmails(result));
+            }
+            ImapCommand::ArchiveMultiple(ids_and_folders) => {
+                let total = ids_and_folders.len();
+                let ids: Vec<String> = ids_and_folders.into_iter().map(|(id, _)| id).collect();
+                let _ = resp_tx.send(ImapResponse::Progress(
+                    total,
+                    total,
+                    "Archiving".to_string(),
+                ));
+                let result = retry_with_backoff(
+                    || client.archive_batch(&ids, "", mark_read_on_archive),
+                    report_retry(&resp_tx, "archive"),
+                );
+                let _ = resp_tx.send(ImapResponse::MultiArchiveResult(result));
+            }
+            ImapCommand::DeleteMultiple(ids_and_folders) => {
+                let total = ids_and_folders.len();
+                let ids: Vec<String> = ids_and_folders.into_iter().map(|(id, _)| id).collect();
+                let _ = resp_tx.send(ImapResponse::Progress(total, total, "Deleting".to_string()));
+                let result = retry_with_backoff(
+                    || client.delete_batch(&ids, ""),
+                    report_retry(&resp_tx, "delete"),
+                );
+                let _ = resp_tx.send(ImapResponse::MultiDeleteResult(result));
+            }
+            ImapCommand::RestoreEmails(restore_ops) => {
+                let total = restore_ops.len();
+                let (progress_tx, progress_rx) = std::sync::mpsc::channel();
+                let resp_tx_progress = resp_tx.clone();
+                let progress_thread = std::thread::spawn(move || {
+                    let mut processed = 0usize;
+                    while let Ok(delta) = progress_rx.recv() {
+                        processed += delta;
+                        let _ = resp_tx_progress.send(ImapResponse::Progress(
+                            processed,
+                            total,
+                            "Restoring".to_string(),
+                        ));
+                    }
+                });
+                let result = retry_with_backoff(
+                    || client.restore_emails(&restore_ops, Some(progress_tx.clone())),
+                    report_retry(&resp_tx, "restore"),
+                );
+                drop(progress_tx);
+                let _ = progress_thread.join();
+                let _ = resp_tx.send(ImapResponse::RestoreResult(result));
+            }
+            // Resyncs follow MailboxChanged, which only the IMAP worker reports
+            ImapCommand::Resync(_) => {
+                let _ = resp_tx.send(ImapResponse::Resynced(Ok(MailboxResync::default())));
+            }
+            ImapCommand::FetchBody {
+                uid,
+                folder,
+                max_bytes,
+                mark_seen,
+            } => {
+                debug_log!("API worker: fetching body for {} from {}", uid, folder);
+                let result = client.fetch_email_body(&uid, &folder, max_bytes, mark_seen);
+                let _ = resp_tx.send(ImapResponse::BodyResult { uid, result });
+            }
+            ImapCommand::MoveMultiple {
+                emails,
+                dest,
+                archive,
+            } => {
+                let mark_read = archive && mark_read_on_archive;
+                let result = move_by_folder(&mut client, &emails, &dest, mark_read, &resp_tx);
+                let _ = resp_tx.send(ImapResponse::MultiMoveResult(result));
+            }
+            ImapCommand::ListFolders => {
+                let _ = resp_tx.send(ImapResponse::Folders(client.list_folders()));
+            }
+            ImapCommand::SetFlags { emails, seen } => {
+                let result = set_seen_by_folder(&mut client, &emails, seen, &resp_tx);
+                let ids = emails.into_iter().map(|(id, _)| id).collect();
+                let _ = resp_tx.send(ImapResponse::FlagsSet { ids, seen, result });
+            }
+            ImapCommand::ModifyLabels {
+                emails,
+                add,
+                remove,
+            } => {
+                let result = modify_labels_by_folder(&mut client, &emails, &add, &remove, &resp_tx);
+                let ids = emails.into_iter().map(|(id, _)| id).collect();
+                let _ = resp_tx.send(ImapResponse::LabelsModified {
+                    ids,
+                    add,
+                    remove,
+                    result,
+                });
+            }
+            // API ids don't change, so there's nothing to check
+            ImapCommand::CheckUidValidity(_) => {}
+            ImapCommand::Shutdown => {
+                debug_log!("API worker: shutdown requested");
+                break;
+            }
+        }
+    }
+}
+
+/// Moves emails to `dest`, one batch per source folder, and returns the COPYUID mapping
+fn move_by_folder(
+    client: &mut impl EmailClient,
+    emails: &[(String, String)],
+    dest: &str,
+    mark_read: bool,
+    resp_tx: &mpsc::Sender<ImapResponse>,
+) -> Result<HashMap<String, u32>> {
+    let mut by_folder: HashMap<&str, Vec<String>> = HashMap::new();
+    for (uid, folder) in emails {
+        by_folder
+            .entry(folder.as_str())
+            .or_default()
+            .push(uid.clone());
+    }
+    let mut uid_map = HashMap::new();
+    let mut processed = 0;
+    for (folder, uids) in by_folder {
+        processed += uids.len();
+        let _ = resp_tx.send(ImapResponse::Progress(
+            processed,
+            emails.len(),
+            "Moving".to_string(),
+        ));
+        uid_map.extend(retry_with_backoff(
+            || client.move_batch(&uids, folder, dest, mark_read),
+            report_retry(resp_tx, "move"),
+        )?);
+    }
+    Ok(uid_map)
+}
+
+/// Marks emails read or unread, one batch per folder
+fn set_seen_by_folder(
+    client: &mut impl EmailClient,
+    emails: &[(String, String)],
+    seen: bool,
+    resp_tx: &mpsc::Sender<ImapResponse>,
+) -> Result<()> {
+    let mut by_folder: HashMap<&str, Vec<String>> = HashMap::new();
+    for (uid, folder) in emails {
+        by_folder
+            .entry(folder.as_str())
+            .or_default()
+            .push(uid.clone());
+    }
+    for (folder, uids) in by_folder {
+        retry_with_backoff(
+            || client.set_seen(&uids, folder, seen),
+            report_retry(resp_tx, "mark"),
+        )?;
+    }
+    Ok(())
+}
+
+/// Adds and removes labels on emails, one batch per folder
+fn modify_labels_by_folder(
+    client: &mut impl EmailClient,
+    emails: &[(String, String)],
+    add: &[String],
+    remove: &[String],
+    resp_tx: &mpsc::Sender<ImapResponse>,
+) -> Result<()> {
+    let mut by_folder: HashMap<&str, Vec<String>> = HashMap::new();
+    for (uid, folder) in emails {
+        by_folder
+            .entry(folder.as_str())
+            .or_default()
+            .push(uid.clone());
+    }
+    for (folder, uids) in by_folder {
+        retry_with_backoff(
+            || client.modify_labels(&uids, folder, add, remove),
+            report_retry(resp_tx, "label"),
+        )?;
+    }
+    Ok(())
+}
+
+/// Tells the UI how many emails the last archive or delete skipped as already gone
+fn send_already_gone(client: &mut ImapClient, resp_tx: &mpsc::Sender<ImapResponse>) {
+    let gone = client.take_already_gone();
+    if gone > 0 {
+        debug_log!("IMAP worker: {} emails were already gone", gone);
+        let _ = resp_tx.send(ImapResponse::AlreadyGone(gone));
+    }
+}
+
+/// Passes on any `[ALERT]` notices and other clients' changes the server reported
+fn send_server_notices(client: &mut ImapClient, resp_tx: &mpsc::Sender<ImapResponse>) {
+    for alert in client.take_alerts() {
+        debug_log!("IMAP worker: server alert: {}", alert);
+        let _ = resp_tx.send(ImapResponse::ServerAlert(alert));
+    }
+    let changed = client.take_mailbox_changes();
+    if !changed.is_empty() {
+        debug_log!("IMAP worker: changed elsewhere: {:?}", changed);
+        let _ = resp_tx.send(ImapResponse::MailboxChanged(changed));
+    }
+}
+
+/// Compares the loaded emails (uid, folder) with each folder on the server: finds the
+/// ones another client removed, and fetches any that arrived after the newest loaded one
+fn resync_mailbox(client: &mut ImapClient, emails: &[(String, String)]) -> Result<MailboxResync> {
+    let mut by_folder: HashMap<&str, HashSet<u32>> = HashMap::new();
+    for (uid, folder) in emails {
+        if let Ok(uid) = uid.parse() {
+            by_folder.entry(folder.as_str()).or_default().insert(uid);
+        }
+    }
+
+    let mut resync = MailboxResync::default();
+    for (folder, loaded) in by_folder {
+        let on_server = client.search_folder_uids(folder, "ALL")?;
+        let present: HashSet<u32> = on_server.iter().copied().collect();
+        resync.removed.extend(
+            loaded
+                .iter()
+                .filter(|uid| !present.contains(uid))
+                .map(|uid| (uid.to_string(), folder.to_string())),
+        );
+        let newest = loaded.iter().max().copied().unwrap_or(0);
+        let arrived: Vec<u32> = on_server.into_iter().filter(|&uid| uid > newest).collect();
+        resync
+            .added
+            .extend(client.fetch_folder_uids(folder, &arrived, None)?);
+    }
+    debug_log!(
+        "Resync: {} removed elsewhere, {} new",
+        resync.removed.len(),
+        resync.added.len()
+    );
+    Ok(resync)
+}
+
+/// Requests bodies for the open thread that aren't cached or already in flight
+fn request_inline_bodies(
+    app: &App,
+    cmd_tx: &mpsc::Sender<ImapCommand>,
+    ui_state: &mut UiState,
+    max_bytes: Option<u32>,
+    mark_seen: bool,
+) -> Result<()> {
+    for (uid, folder) in app.current_thread_email_ids_missing_body() {
+        if ui_state.requested_bodies.insert(uid.clone()) {
+            cmd_tx.send(ImapCommand::FetchBody {
+                uid,
+                folder,
+                max_bytes,
+                mark_seen,
+            })?;
+        }
+    }
+    Ok(())
+}
+
+fn run_app(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    account_name: String,
+    user_email: String,
+    cfg: &config::Config,
+    session: Session,
+    show_tour: bool,
+    resume: bool,
+) -> Result<()> {
+    let parallel_connections = cfg.parallel_connections;
+    let advance_on_select = cfg.advance_on_select;
+    let merge_subject_threads = cfg.merge_subject_threads;
+    let body_size_limit = cfg.body_size_limit();
+    let mark_seen_on_view = cfg.mark_seen_on_view;
+    // Replayed sessions don't post to the webhook
+    let webhook_url = match &session {
+        Session::Live { .. } | Session::Attached { .. } => cfg.webhook_url.clone(),
+        Session::Replay(_) => None,
+    };
+    // Replays don't record the backend; they were all made against Gmail
+    let backend = match &session {
+        Session::Live { account, .. } | Session::Attached { account, .. } => {
+            account.backend.clone()
+        }
+        Session::Replay(_) => config::Backend::Gmail,
+    };
+    let folders = backend.folders();
+    // First day of the account's fetch_window; cleared once older mail is loaded
+    let mut fetch_since = match &session {
+        Session::Live { account, .. } | Session::Attached { account, .. } => {
+            account.fetch_window_start(chrono::Utc::now().date_naive())?
+        }
+        Session::Replay(_) => None,
+    };
+    // Newest emails fetched per folder; grows as older pages are loaded
+    let mut fetch_limit = cfg.fetch_limit;
+    // Size of the older page being fetched, if any
+    let mut loading_page: Option<u32> = None;
+    let mut app = App::new();
+    app.set_user_email(user_email.clone());
+    app.thread_inline_bodies = cfg.thread_inline_bodies;
+    app.row_density = cfg.row_density;
+    app.auth_warnings_in_list = cfg.auth_warnings_in_list;
+    app.thread_recipients_column = cfg.thread_recipients_column;
+    app.archive_older_than_days = cfg.archive_older_than_days;
+    app.labels_available = backend.has_labels();
+    app.min_group_size = cfg.min_group_size;
+    app.protected_senders = cfg.protected_senders.clone();
+    app.highlights = cfg.highlights.clone();
+    app.accent = match &session {
+        Session::Live { account, .. } | Session::Attached { account, .. } => {
+            account.accent().map(|color| (account_name.clone(), color))
+        }
+        Session::Replay(_) => None,
+    };
+    let mut ui_state = UiState::new();
+    ui_state.typed_confirm_above = cfg.typed_confirm_above;
+    ui_state.typed_count_confirm_above = cfg.typed_count_confirm_above;
+    // Sender notes, habits and ignores persist per account
+    let mut notes_path = notes::notes_path(&account_name).ok();
+    app.notes = load_account_data(&mut notes_path, notes::SenderNotes::load);
+    let mut habits_path = habits::habits_path(&account_name).ok();
+    app.habits = load_account_data(&mut habits_path, habits::SenderHabits::load);
+    let mut ignore_path = ignore::ignore_path(&account_name).ok();
+    let mut ignore_list = load_account_data(&mut ignore_path, ignore::IgnoreList::load);
+    let mut postpone_path = postpone::postpone_path(&account_name).ok();
+    let mut postpone_list = load_account_data(&mut postpone_path, postpone::PostponeList::load);
+    if postpone_list.prune(chrono::Local::now().date_naive())
+        && let Some(path) = &postpone_path
+        && let Err(e) = postpone_list.save(path)
+    {
+        debug_log!("Failed to save {}: {:#}", path.display(), e);
+    }
+    app.postponed_senders = postpone_list.senders().cloned().collect();
+    app.ignored_senders = cfg
+        .ignored_senders
+        .iter()
+        .chain(&ignore_list.senders)
+        .cloned()
+        .collect();
+    if show_tour {
+        ui_state.tour = Some(Tour::new());
+    }
+    // Where the user left off is saved on quit; replays never overwrite it
+    let resume_path = match &session {
+        Session::Live { .. } | Session::Attached { .. } => resume::resume_path(&account_name).ok(),
+        Session::Replay(_) => None,
+    };
+    let mut window_title = WindowTitle::new(cfg.window_title);
+    // The triage timer starts once the first load is in, so loading time doesn't count
+    let mut triage_timer_minutes = Some(cfg.triage_timer_minutes).filter(|&m| m > 0);
+    let mut pending_resume = match &resume_path {
+        Some(path) if resume => match resume::ResumeState::load(path) {
+            Ok(state) => Some(state),
+            Err(e) => {
+                debug_log!("Failed to load {}: {:#}", path.display(), e);
+                None
+            }
+        },
+        _ => None,
+    };
+
+    // Create channels for IMAP communication
+    let (cmd_tx, cmd_rx) = mpsc::channel::<ImapCommand>();
+    let (resp_tx, resp_rx) = mpsc::channel::<ImapResponse>();
+
+    // Show the last session's headers right away and reconcile them with the server's
+    // once the fetch is in (recordings always start from an empty inbox)
+    let cache_encryption =
+        cfg.header_cache_encrypt_to
+            .clone()
+            .map(|recipient| header_cache::Encryption {
+                pgp_command: cfg.pgp_command.clone(),
+                recipient,
+            });
+    let cache_path = match &session {
+        Session::Live { record: None, .. } | Session::Attached { .. } if cfg.header_cache => {
+            header_cache::cache_path(&account_name, cache_encryption.is_some()).ok()
+        }
+        _ => None,
+    };
+    let mut reconciling = false;
+    // The UIDVALIDITY the loaded UIDs were read under, saved with the cache
+    let mut uid_validity: HashMap<String, u32> = HashMap::new();
+    if let Some(path) = &cache_path {
+        match header_cache::load(path, &user_email, cache_encryption.as_ref()) {
+            Ok(Some(cached)) => {
+                debug_log!("UI: showing {} cached emails", cached.emails.len());
+                app.set_emails(cached.emails);
+                reconciling = true;
+                // Checked before anything started on the cached rows runs
+                if !cached.uid_validity.is_empty() {
+                    cmd_tx.send(ImapCommand::CheckUidValidity(cached.uid_validity.clone()))?;
+                }
+                uid_validity = cached.uid_validity;
+            }
+            Ok(None) => {}
+            Err(e) => debug_log!("Failed to load {}: {:#}", path.display(), e),
+        }
+    }
+
+    if reconciling {
+        ui_state.set_status(format!(
+            "Showing mail from the last session while {} loads...",
+            account_name
+        ));
+        if let Some(state) = pending_resume.take() {
+            resume_session(
+                &mut app,
+                &mut ui_state,
+                &cmd_tx,
+                &state,
+                body_size_limit,
+                mark_seen_on_view,
+            )?;
+        }
+    } else {
+        // Show connecting status
+        ui_state.set_busy(format!("Connecting to {}...", account_name));
+    }
+    app.ensure_valid_selection();
+    terminal.draw(|f| render(f, &app, &mut ui_state))?;
+
+    // Replies go out through the account's SMTP server; replays have no account to send from
+    let smtp_server = match &session {
+        Session::Live { account, .. } | Session::Attached { account, .. } => {
+            Some(smtp::SmtpServer::for_account(account))
+        }
+        Session::Replay(_) => None,
+    };
+    match session {
+        Session::Live {
+            account,
+            record: None,
+        } => {
+            // Spawn IMAP worker thread
+            spawn_worker(
+                cmd_rx,
+                resp_tx,
+                account,
+                cfg.mark_read_on_archive,
+                cfg.pgp_command.clone(),
+            );
+        }
+        Session::Live {
+            account,
+            record: Some(path),
+        } => {
+            // Route both channels through taps that record each message
+            let mut settings = cfg.clone();
+            settings.accounts.clear();
+            let header = recording::RecordingHeader {
+                account: account_name.clone(),
+                user_email: user_email.clone(),
+                config: settings,
+            };
+            let recorder = recording::Recorder::create(&path, &header)?;
+            let (worker_cmd_tx, worker_cmd_rx) = mpsc::channel::<ImapCommand>();
+            let (worker_resp_tx, worker_resp_rx) = mpsc::channel::<ImapResponse>();
+            recording::spawn_tap(
+                cmd_rx,
+                worker_cmd_tx,
+                recorder.clone(),
+                recording::Recorder::command,
+            );
+            recording::spawn_tap(
+                worker_resp_rx,
+                resp_tx,
+                recorder,
+                recording::Recorder::response,
+            );
+            spawn_worker(
+                worker_cmd_rx,
+                worker_resp_tx,
+                account,
+                cfg.mark_read_on_archive,
+                cfg.pgp_command.clone(),
+            );
+        }
+        Session::Attached { stream, .. } => daemon::spawn_client(stream, cmd_rx, resp_tx)?,
+        Session::Replay(events) => recording::spawn_replay(cmd_rx, resp_tx, events),
+    }
+
+    // Wait for connection
+    loop {
+        // Cached mail is already on screen, so the main loop handles the connection
+        if reconciling {
+            break;
+        }
+        // Check for responses
+        match resp_rx.recv_timeout(Duration::from_millis(100)) {
+            Ok(ImapResponse::Connected) => {
+                ui_state.set_busy("Loading emails...");
+                app.ensure_valid_selection();
                 terminal.draw(|f| render(f, &app, &mut ui_state))?;
                 cmd_tx.send(ImapCommand::FetchInbox {
                     parallel_connections,
+                    merge_subject_threads,
+                    since: fetch_since,
+                    limit: fetch_limit,
                 })?;
                 break;
             }
@@ -1843,49 +4287,274 @@ fn run_app(
 
     // Track pending operations
     let mut pending_operation: Option<PendingOp> = None;
+    // The reply waiting for confirmation, and the channel sends report back on
+    let mut pending_reply: Option<compose::Draft> = None;
+    let (sent_tx, sent_rx) = mpsc::channel::<compose::Sent>();
+    // Plugins run on their own threads and answer here
+    let (plugin_tx, plugin_rx) = mpsc::channel::<plugin::Done>();
+    // The folders the move picker offers, listed the first time it opens
+    let mut move_folders: Option<Vec<String>> = None;
+    // The picker waiting for that list: move (v), label and archive (F) or add a label (+)
+    let mut folder_target = PickerTarget::MoveFolder { archive: false };
+    // Policy batches waiting to run, one IMAP operation at a time
+    let mut policy_queue: Vec<PolicyBatch> = Vec::new();
     // Track pending 'g' for gg sequence
     let mut pending_g = false;
+    let mut pending_letter_jump = false;
+    // Another client changed the mailbox and the view should catch up once the UI is idle
+    let mut live_resync_due = false;
+    // A background resync is in flight
+    let mut live_resync = false;
+    // Emails the running archive or delete found already gone
+    let mut already_gone = 0;
 
     // Main event loop
     loop {
+        // Catch up with new mail in the background, keeping the cursor where it is
+        if live_resync_due
+            && !live_resync
+            && !reconciling
+            && pending_operation.is_none()
+            && !ui_state.is_busy()
+            && !ui_state.is_confirming()
+            && policy_queue.is_empty()
+            && matches!(app.view, View::GroupList | View::EmailList | View::Thread)
+        {
+            live_resync_due = false;
+            live_resync = true;
+            cmd_tx.send(ImapCommand::Resync(app.loaded_email_ids()))?;
+        }
+        // Start the next policy batch once the previous operation has finished
+        if pending_operation.is_none() && !ui_state.is_busy() && !policy_queue.is_empty() {
+            let batch = policy_queue.remove(0);
+            start_policy_batch(batch, &cmd_tx, &mut ui_state, &mut pending_operation)?;
+        }
+
         // Tick spinner animation when busy
         if ui_state.is_busy() {
             ui_state.tick_spinner();
         }
 
         app.ensure_valid_selection();
+        // Advance the onboarding tour as the user follows it
+        if let Some(tour) = &mut ui_state.tour
+            && !tour.update(&app)
+        {
+            ui_state.tour = None;
+        }
+        // End the triage session with a summary once nothing else is on screen
+        if let Some(timer) = &ui_state.triage_timer
+            && timer.is_up()
+            && !ui_state.is_busy()
+            && !ui_state.is_confirming()
+            && !ui_state.has_status()
+        {
+            let summary = timer.summary(&app);
+            ui_state.triage_timer = None;
+            ui_state.set_alert(summary);
+        }
+        ui_state.tick_status();
+        ui_state.show_next_server_alert();
+        window_title.update(&account_name, app.email_count())?;
         terminal.draw(|f| render(f, &app, &mut ui_state))?;
 
+        // Lazily fetch bodies for the open thread when they're shown inline
+        if app.view == View::Thread && app.thread_inline_bodies {
+            request_inline_bodies(
+                &app,
+                &cmd_tx,
+                &mut ui_state,
+                body_size_limit,
+                mark_seen_on_view,
+            )?;
+        }
+
+        if let Ok((key, result)) = plugin_rx.try_recv() {
+            ui_state.clear_busy();
+            if let Some(plugin) = cfg.plugins.iter().find(|p| p.key == key) {
+                finish_plugin(
+                    &mut app,
+                    &cmd_tx,
+                    &mut ui_state,
+                    &mut pending_operation,
+                    plugin,
+                    result,
+                )?;
+            }
+        }
+
+        if let Ok((outgoing, result)) = sent_rx.try_recv() {
+            ui_state.clear_busy();
+            match (outgoing, result) {
+                (compose::Outgoing::Reply, Ok(recipients)) => {
+                    if let Ok(path) = compose::draft_path() {
+                        let _ = std::fs::remove_file(path);
+                    }
+                    ui_state.set_status(format!("Reply sent to {} recipient(s)", recipients));
+                }
+                (compose::Outgoing::Forward, Ok(recipients)) => {
+                    ui_state.set_status(format!("Forwarded to {} recipient(s)", recipients));
+                }
+                (compose::Outgoing::Reply, Err(e)) => {
+                    ui_state.set_alert(match compose::draft_path() {
+                        Ok(path) => format!(
+                            "Failed to send the reply (the draft is in {}): {:#}",
+                            path.display(),
+                            e
+                        ),
+                        Err(_) => format!("Failed to send the reply: {:#}", e),
+                    })
+                }
+                (compose::Outgoing::Forward, Err(e)) => {
+                    ui_state.set_alert(format!("Failed to forward the email: {:#}", e))
+                }
+                (compose::Outgoing::Unsubscribe { sender }, Ok(_)) => {
+                    let count = app.sender_inbox_emails(&sender).len();
+                    ui_state.set_status(format!("Sent the unsubscribe email for {}", sender));
+                    if count > 0 {
+                        ui_state.set_confirm(ConfirmAction::ArchiveSender { sender, count });
+                    }
+                }
+                (compose::Outgoing::Unsubscribe { .. }, Err(e)) => {
+                    ui_state.set_alert(format!("Failed to send the unsubscribe email: {:#}", e))
+                }
+            }
+        }
+
         // Check for IMAP responses (non-blocking)
         while let Ok(response) = resp_rx.try_recv() {
             match response {
                 ImapResponse::Emails(result) => match result {
                     Ok(emails) => {
                         let email_count = emails.len();
-                        app.set_emails(emails);
+                        let was_cached = std::mem::take(&mut reconciling);
+                        let arrived = if was_cached {
+                            app.reconcile_emails(emails)
+                        } else {
+                            app.reload_emails(emails)
+                        };
+                        app.clear_mailbox_changed();
+                        // Fresh emails have no cached bodies, so allow refetching
+                        ui_state.requested_bodies.clear();
+                        ui_state.body_errors.clear();
                         debug_log!(
                             "UI: loaded {} emails into {} groups",
                             email_count,
                             app.groups.len()
                         );
-                        ui_state.clear_busy();
+                        if let Some(path) = &cache_path {
+                            header_cache::spawn_save(
+                                path.clone(),
+                                user_email.clone(),
+                                uid_validity.clone(),
+                                app.cacheable_emails(),
+                                cache_encryption.clone(),
+                            );
+                        }
+                        // An action started on cached mail is still running
+                        if !was_cached {
+                            ui_state.clear_busy();
+                        }
+                        if arrived > 0 {
+                            ui_state.set_status(format!(
+                                "{} new email(s) since the last load, marked • (J jumps to them)",
+                                arrived
+                            ));
+                        } else if was_cached {
+                            ui_state.set_status("Up to date with the server".to_string());
+                        }
+
+                        ui_state.triage_started.get_or_insert_with(Instant::now);
+                        if let Some(minutes) = triage_timer_minutes.take() {
+                            let length = Duration::from_secs(u64::from(minutes) * 60);
+                            ui_state.triage_timer = Some(TriageTimer::new(length, &app));
+                        }
+                        // Reopen where the last session ended, once the first load is in
+                        if let Some(state) = pending_resume.take() {
+                            resume_session(
+                                &mut app,
+                                &mut ui_state,
+                                &cmd_tx,
+                                &state,
+                                body_size_limit,
+                                mark_seen_on_view,
+                            )?;
+                        }
+
+                        // Run configured policies against the fresh emails
+                        queue_policies(&app, cfg, &mut ui_state, &mut policy_queue);
                     }
                     Err(e) => {
                         debug_log!("UI: email fetch failed: {}", e);
+                        reconciling = false;
+                        ui_state.clear_busy();
+                        ui_state.set_alert(imap_error::describe("Error", &e));
+                    }
+                },
+                ImapResponse::FetchInterrupted {
+                    error,
+                    fetched,
+                    missing_ranges,
+                } => {
+                    debug_log!("UI: email fetch interrupted: {}", error);
+                    ui_state.clear_busy();
+                    ui_state.set_confirm(ConfirmAction::RetryFetch {
+                        error,
+                        fetched,
+                        missing_ranges,
+                    });
+                }
+                ImapResponse::OlderEmails(result) => match result {
+                    Ok(older) => {
+                        let added = app.merge_older_emails(older, merge_subject_threads);
+                        if let Some(count) = loading_page.take() {
+                            // Refreshes keep fetching the pages loaded so far
+                            fetch_limit = fetch_limit.map(|limit| limit + count);
+                        } else {
+                            // Everything is loaded now, so refreshes fetch all mail too
+                            fetch_since = None;
+                            fetch_limit = None;
+                        }
+                        ui_state.clear_busy();
+                        ui_state.set_status(format!("Loaded {} older emails", added));
+                        queue_policies(&app, cfg, &mut ui_state, &mut policy_queue);
+                    }
+                    Err(e) => {
+                        debug_log!("UI: older email fetch failed: {}", e);
+                        loading_page = None;
                         ui_state.clear_busy();
-                        ui_state.set_status(format!("Error: {}", e));
+                        ui_state.set_alert(imap_error::describe("Error", &e));
                     }
                 },
+                ImapResponse::OlderRemaining(remaining) => {
+                    app.load_more_count = remaining.min(cfg.fetch_page_size as usize);
+                }
+                ImapResponse::AlreadyGone(count) => already_gone = count,
                 ImapResponse::MultiArchiveResult(result) => {
                     debug_log!(
                         "UI: multi-archive result: {}",
                         if result.is_ok() { "success" } else { "failed" }
                     );
+                    if result.is_err() {
+                        // Don't keep applying policies after a failure
+                        policy_queue.clear();
+                    }
                     if let Some(op) = pending_operation.take()
                         && let Ok(uid_map) = result
                     {
+                        if let Some(url) = &webhook_url
+                            && let Some(summary) = op.bulk_summary("archive", &account_name)
+                        {
+                            webhook::notify(url, &summary);
+                        }
                         match op {
                             PendingOp::ArchiveGroup { sender, emails } => {
+                                remember_handling(
+                                    &mut app,
+                                    &sender,
+                                    PolicyAction::Archive,
+                                    habits_path.as_deref(),
+                                );
                                 // Create undo entry with destination UIDs
                                 let undo_emails: Vec<_> = emails
                                     .into_iter()
@@ -1898,7 +4567,7 @@ fn run_app(
                                     action_type: UndoActionType::Archive,
                                     context: UndoContext::Group { sender },
                                     emails: undo_emails,
-                                    current_folder: "[Gmail]/All Mail".to_string(),
+                                    current_folder: folders.archive.to_string(),
                                 };
                                 app.push_undo(undo_entry);
                                 // Remove all emails from threads touched by this group
@@ -1921,7 +4590,7 @@ fn run_app(
                                     action_type: UndoActionType::Archive,
                                     context: UndoContext::Thread { subject },
                                     emails: undo_emails,
-                                    current_folder: "[Gmail]/All Mail".to_string(),
+                                    current_folder: folders.archive.to_string(),
                                 };
                                 app.push_undo(undo_entry);
                                 let group_key =
@@ -1938,9 +4607,14 @@ fn run_app(
                                             ui_state.text_view_state = TextViewState::Loaded(body);
                                         } else {
                                             let uid = email.id.clone();
-                                            let folder = email.source_folder.clone();
+                                            let folder = email.source_folder.to_string();
                                             ui_state.text_view_state = TextViewState::Loading;
-                                            cmd_tx.send(ImapCommand::FetchBody { uid, folder })?;
+                                            cmd_tx.send(ImapCommand::FetchBody {
+                                                uid,
+                                                folder,
+                                                max_bytes: body_size_limit,
+                                                mark_seen: mark_seen_on_view,
+                                            })?;
                                         }
                                     }
                                 }
@@ -1960,28 +4634,17 @@ fn run_app(
                                         sender: format!("{} selected", count),
                                     },
                                     emails: undo_emails,
-                                    current_folder: "[Gmail]/All Mail".to_string(),
+                                    current_folder: folders.archive.to_string(),
                                 };
                                 app.push_undo(undo_entry);
                                 // Remove all emails from threads touched by selected emails
                                 app.remove_selected_threads();
                             }
-                            _ => {}
-                        }
-                    }
-                    ui_state.clear_busy();
-                }
-                ImapResponse::MultiDeleteResult(result) => {
-                    debug_log!(
-                        "UI: multi-delete result: {}",
-                        if result.is_ok() { "success" } else { "failed" }
-                    );
-                    if let Some(op) = pending_operation.take()
-                        && let Ok(uid_map) = result
-                    {
-                        match op {
-                            PendingOp::DeleteGroup { sender, emails } => {
-                                // Create undo entry with destination UIDs
+                            PendingOp::ArchiveDuplicates { count, emails } => {
+                                let removed: Vec<(String, String)> = emails
+                                    .iter()
+                                    .map(|(uid, _, folder)| (uid.clone(), folder.clone()))
+                                    .collect();
                                 let undo_emails: Vec<_> = emails
                                     .into_iter()
                                     .map(|(uid, message_id, source_folder)| {
@@ -1990,21 +4653,25 @@ fn run_app(
                                     })
                                     .collect();
                                 let undo_entry = UndoEntry {
-                                    action_type: UndoActionType::Delete,
-                                    context: UndoContext::Group { sender },
+                                    action_type: UndoActionType::Archive,
+                                    context: UndoContext::Group {
+                                        sender: format!("{} duplicates", count),
+                                    },
                                     emails: undo_emails,
-                                    current_folder: "[Gmail]/Trash".to_string(),
+                                    current_folder: folders.archive.to_string(),
                                 };
                                 app.push_undo(undo_entry);
-                                // Remove all emails from threads touched by this group
-                                app.remove_current_group_threads();
+                                app.remove_emails(&removed);
                             }
-                            PendingOp::DeleteThread {
-                                thread_id,
-                                subject,
+                            PendingOp::ArchiveOld {
+                                days,
+                                group,
                                 emails,
                             } => {
-                                // Create undo entry with destination UIDs
+                                let removed: Vec<(String, String)> = emails
+                                    .iter()
+                                    .map(|(uid, _, folder)| (uid.clone(), folder.clone()))
+                                    .collect();
                                 let undo_emails: Vec<_> = emails
                                     .into_iter()
                                     .map(|(uid, message_id, source_folder)| {
@@ -2013,35 +4680,21 @@ fn run_app(
                                     })
                                     .collect();
                                 let undo_entry = UndoEntry {
-                                    action_type: UndoActionType::Delete,
-                                    context: UndoContext::Thread { subject },
+                                    action_type: UndoActionType::Archive,
+                                    context: UndoContext::Group {
+                                        sender: old_mail_label(days, group.as_deref()),
+                                    },
                                     emails: undo_emails,
-                                    current_folder: "[Gmail]/Trash".to_string(),
+                                    current_folder: folders.archive.to_string(),
                                 };
                                 app.push_undo(undo_entry);
-                                let group_key =
-                                    app.groups.get(app.selected_group).map(|g| g.key.clone());
-                                app.remove_thread(&thread_id);
-                                if app.view == View::Thread {
-                                    app.exit();
-                                } else if let Some(key) = &group_key {
-                                    app.advance_or_exit_email_body(key);
-                                    if app.view == View::EmailBody
-                                        && let Some(email) = app.viewing_email()
-                                    {
-                                        if let Some(body) = email.body.clone() {
-                                            ui_state.text_view_state = TextViewState::Loaded(body);
-                                        } else {
-                                            let uid = email.id.clone();
-                                            let folder = email.source_folder.clone();
-                                            ui_state.text_view_state = TextViewState::Loading;
-                                            cmd_tx.send(ImapCommand::FetchBody { uid, folder })?;
-                                        }
-                                    }
-                                }
+                                app.remove_emails(&removed);
                             }
-                            PendingOp::DeleteSelected { count, emails } => {
-                                // Create undo entry with destination UIDs
+                            PendingOp::ArchiveSingles { emails } => {
+                                let removed: Vec<(String, String)> = emails
+                                    .iter()
+                                    .map(|(uid, _, folder)| (uid.clone(), folder.clone()))
+                                    .collect();
                                 let undo_emails: Vec<_> = emails
                                     .into_iter()
                                     .map(|(uid, message_id, source_folder)| {
@@ -2050,44 +4703,381 @@ fn run_app(
                                     })
                                     .collect();
                                 let undo_entry = UndoEntry {
-                                    action_type: UndoActionType::Delete,
+                                    action_type: UndoActionType::Archive,
                                     context: UndoContext::Group {
-                                        sender: format!("{} selected", count),
+                                        sender: "single messages".to_string(),
                                     },
                                     emails: undo_emails,
-                                    current_folder: "[Gmail]/Trash".to_string(),
+                                    current_folder: folders.archive.to_string(),
                                 };
                                 app.push_undo(undo_entry);
-                                // Remove all emails from threads touched by selected emails
-                                app.remove_selected_threads();
+                                app.remove_emails(&removed);
                             }
-                            _ => {}
-                        }
-                    }
-                    ui_state.clear_busy();
-                }
+                            PendingOp::ArchiveRead { group, emails } => {
+                                let removed: Vec<(String, String)> = emails
+                                    .iter()
+                                    .map(|(uid, _, folder)| (uid.clone(), folder.clone()))
+                                    .collect();
+                                let undo_emails: Vec<_> = emails
+                                    .into_iter()
+                                    .map(|(uid, message_id, source_folder)| {
+                                        let dest_uid = uid_map.get(&uid).copied();
+                                        (message_id, dest_uid, source_folder)
+                                    })
+                                    .collect();
+                                let undo_entry = UndoEntry {
+                                    action_type: UndoActionType::Archive,
+                                    context: UndoContext::Group {
+                                        sender: read_mail_label(group.as_deref()),
+                                    },
+                                    emails: undo_emails,
+                                    current_folder: folders.archive.to_string(),
+                                };
+                                app.push_undo(undo_entry);
+                                app.remove_emails(&removed);
+                            }
+                            PendingOp::ArchiveSender { sender, emails } => {
+                                let removed: Vec<(String, String)> = emails
+                                    .iter()
+                                    .map(|(uid, _, folder)| (uid.clone(), folder.clone()))
+                                    .collect();
+                                let undo_emails: Vec<_> = emails
+                                    .into_iter()
+                                    .map(|(uid, message_id, source_folder)| {
+                                        let dest_uid = uid_map.get(&uid).copied();
+                                        (message_id, dest_uid, source_folder)
+                                    })
+                                    .collect();
+                                let undo_entry = UndoEntry {
+                                    action_type: UndoActionType::Archive,
+                                    context: UndoContext::Group { sender },
+                                    emails: undo_emails,
+                                    current_folder: folders.archive.to_string(),
+                                };
+                                app.push_undo(undo_entry);
+                                app.remove_emails(&removed);
+                            }
+                            PendingOp::ArchiveSweep { emails } => {
+                                let removed: Vec<(String, String)> = emails
+                                    .iter()
+                                    .map(|(uid, _, folder)| (uid.clone(), folder.clone()))
+                                    .collect();
+                                let undo_emails: Vec<_> = emails
+                                    .into_iter()
+                                    .map(|(uid, message_id, source_folder)| {
+                                        let dest_uid = uid_map.get(&uid).copied();
+                                        (message_id, dest_uid, source_folder)
+                                    })
+                                    .collect();
+                                let undo_entry = UndoEntry {
+                                    action_type: UndoActionType::Archive,
+                                    context: UndoContext::Group {
+                                        sender: "inbox sweep".to_string(),
+                                    },
+                                    emails: undo_emails,
+                                    current_folder: folders.archive.to_string(),
+                                };
+                                app.push_undo(undo_entry);
+                                app.remove_emails(&removed);
+                                app.cancel_sweep();
+                            }
+                            PendingOp::ArchiveGroups { senders, emails } => {
+                                for sender in &senders {
+                                    remember_handling(
+                                        &mut app,
+                                        sender,
+                                        PolicyAction::Archive,
+                                        habits_path.as_deref(),
+                                    );
+                                }
+                                let removed: Vec<(String, String)> = emails
+                                    .iter()
+                                    .map(|(uid, _, folder)| (uid.clone(), folder.clone()))
+                                    .collect();
+                                let undo_emails: Vec<_> = emails
+                                    .into_iter()
+                                    .map(|(uid, message_id, source_folder)| {
+                                        let dest_uid = uid_map.get(&uid).copied();
+                                        (message_id, dest_uid, source_folder)
+                                    })
+                                    .collect();
+                                let undo_entry = UndoEntry {
+                                    action_type: UndoActionType::Archive,
+                                    context: UndoContext::Group {
+                                        sender: format!("{} groups", senders.len()),
+                                    },
+                                    emails: undo_emails,
+                                    current_folder: folders.archive.to_string(),
+                                };
+                                app.push_undo(undo_entry);
+                                app.remove_emails(&removed);
+                                app.clear_group_selection();
+                            }
+                            PendingOp::Policy { name, emails, .. } => {
+                                let removed: Vec<(String, String)> = emails
+                                    .iter()
+                                    .map(|(uid, _, folder)| (uid.clone(), folder.clone()))
+                                    .collect();
+                                let undo_emails: Vec<_> = emails
+                                    .into_iter()
+                                    .map(|(uid, message_id, source_folder)| {
+                                        let dest_uid = uid_map.get(&uid).copied();
+                                        (message_id, dest_uid, source_folder)
+                                    })
+                                    .collect();
+                                let undo_entry = UndoEntry {
+                                    action_type: UndoActionType::Archive,
+                                    context: UndoContext::Group {
+                                        sender: format!("policy \"{}\"", name),
+                                    },
+                                    emails: undo_emails,
+                                    current_folder: folders.archive.to_string(),
+                                };
+                                app.push_undo(undo_entry);
+                                app.remove_emails(&removed);
+                            }
+                            _ => {}
+                        }
+                    }
+                    ui_state.clear_busy();
+                    if already_gone > 0 {
+                        ui_state.set_status(format!(
+                            "{} message(s) were already gone",
+                            std::mem::take(&mut already_gone)
+                        ));
+                    }
+                }
+                ImapResponse::MultiDeleteResult(result) => {
+                    debug_log!(
+                        "UI: multi-delete result: {}",
+                        if result.is_ok() { "success" } else { "failed" }
+                    );
+                    if result.is_err() {
+                        // Don't keep applying policies after a failure
+                        policy_queue.clear();
+                    }
+                    if let Some(op) = pending_operation.take()
+                        && let Ok(uid_map) = result
+                    {
+                        if let Some(url) = &webhook_url
+                            && let Some(summary) = op.bulk_summary("delete", &account_name)
+                        {
+                            webhook::notify(url, &summary);
+                        }
+                        match op {
+                            PendingOp::DeleteGroup { sender, emails } => {
+                                remember_handling(
+                                    &mut app,
+                                    &sender,
+                                    PolicyAction::Delete,
+                                    habits_path.as_deref(),
+                                );
+                                // Create undo entry with destination UIDs
+                                let undo_emails: Vec<_> = emails
+                                    .into_iter()
+                                    .map(|(uid, message_id, source_folder)| {
+                                        let dest_uid = uid_map.get(&uid).copied();
+                                        (message_id, dest_uid, source_folder)
+                                    })
+                                    .collect();
+                                let undo_entry = UndoEntry {
+                                    action_type: UndoActionType::Delete,
+                                    context: UndoContext::Group { sender },
+                                    emails: undo_emails,
+                                    current_folder: folders.trash.to_string(),
+                                };
+                                app.push_undo(undo_entry);
+                                // Remove all emails from threads touched by this group
+                                app.remove_current_group_threads();
+                            }
+                            PendingOp::DeleteThread {
+                                thread_id,
+                                subject,
+                                emails,
+                            } => {
+                                // Create undo entry with destination UIDs
+                                let undo_emails: Vec<_> = emails
+                                    .into_iter()
+                                    .map(|(uid, message_id, source_folder)| {
+                                        let dest_uid = uid_map.get(&uid).copied();
+                                        (message_id, dest_uid, source_folder)
+                                    })
+                                    .collect();
+                                let undo_entry = UndoEntry {
+                                    action_type: UndoActionType::Delete,
+                                    context: UndoContext::Thread { subject },
+                                    emails: undo_emails,
+                                    current_folder: folders.trash.to_string(),
+                                };
+                                app.push_undo(undo_entry);
+                                let group_key =
+                                    app.groups.get(app.selected_group).map(|g| g.key.clone());
+                                app.remove_thread(&thread_id);
+                                if app.view == View::Thread {
+                                    app.exit();
+                                } else if let Some(key) = &group_key {
+                                    app.advance_or_exit_email_body(key);
+                                    if app.view == View::EmailBody
+                                        && let Some(email) = app.viewing_email()
+                                    {
+                                        if let Some(body) = email.body.clone() {
+                                            ui_state.text_view_state = TextViewState::Loaded(body);
+                                        } else {
+                                            let uid = email.id.clone();
+                                            let folder = email.source_folder.to_string();
+                                            ui_state.text_view_state = TextViewState::Loading;
+                                            cmd_tx.send(ImapCommand::FetchBody {
+                                                uid,
+                                                folder,
+                                                max_bytes: body_size_limit,
+                                                mark_seen: mark_seen_on_view,
+                                            })?;
+                                        }
+                                    }
+                                }
+                            }
+                            PendingOp::DeleteSelected { count, emails } => {
+                                // Create undo entry with destination UIDs
+                                let undo_emails: Vec<_> = emails
+                                    .into_iter()
+                                    .map(|(uid, message_id, source_folder)| {
+                                        let dest_uid = uid_map.get(&uid).copied();
+                                        (message_id, dest_uid, source_folder)
+                                    })
+                                    .collect();
+                                let undo_entry = UndoEntry {
+                                    action_type: UndoActionType::Delete,
+                                    context: UndoContext::Group {
+                                        sender: format!("{} selected", count),
+                                    },
+                                    emails: undo_emails,
+                                    current_folder: folders.trash.to_string(),
+                                };
+                                app.push_undo(undo_entry);
+                                // Remove all emails from threads touched by selected emails
+                                app.remove_selected_threads();
+                            }
+                            PendingOp::DeleteDuplicates { count, emails } => {
+                                let removed: Vec<(String, String)> = emails
+                                    .iter()
+                                    .map(|(uid, _, folder)| (uid.clone(), folder.clone()))
+                                    .collect();
+                                let undo_emails: Vec<_> = emails
+                                    .into_iter()
+                                    .map(|(uid, message_id, source_folder)| {
+                                        let dest_uid = uid_map.get(&uid).copied();
+                                        (message_id, dest_uid, source_folder)
+                                    })
+                                    .collect();
+                                let undo_entry = UndoEntry {
+                                    action_type: UndoActionType::Delete,
+                                    context: UndoContext::Group {
+                                        sender: format!("{} duplicates", count),
+                                    },
+                                    emails: undo_emails,
+                                    current_folder: folders.trash.to_string(),
+                                };
+                                app.push_undo(undo_entry);
+                                app.remove_emails(&removed);
+                            }
+                            PendingOp::DeleteGroups { senders, emails } => {
+                                for sender in &senders {
+                                    remember_handling(
+                                        &mut app,
+                                        sender,
+                                        PolicyAction::Delete,
+                                        habits_path.as_deref(),
+                                    );
+                                }
+                                let removed: Vec<(String, String)> = emails
+                                    .iter()
+                                    .map(|(uid, _, folder)| (uid.clone(), folder.clone()))
+                                    .collect();
+                                let undo_emails: Vec<_> = emails
+                                    .into_iter()
+                                    .map(|(uid, message_id, source_folder)| {
+                                        let dest_uid = uid_map.get(&uid).copied();
+                                        (message_id, dest_uid, source_folder)
+                                    })
+                                    .collect();
+                                let undo_entry = UndoEntry {
+                                    action_type: UndoActionType::Delete,
+                                    context: UndoContext::Group {
+                                        sender: format!("{} groups", senders.len()),
+                                    },
+                                    emails: undo_emails,
+                                    current_folder: folders.trash.to_string(),
+                                };
+                                app.push_undo(undo_entry);
+                                app.remove_emails(&removed);
+                                app.clear_group_selection();
+                            }
+                            PendingOp::Policy { name, emails, .. } => {
+                                let removed: Vec<(String, String)> = emails
+                                    .iter()
+                                    .map(|(uid, _, folder)| (uid.clone(), folder.clone()))
+                                    .collect();
+                                let undo_emails: Vec<_> = emails
+                                    .into_iter()
+                                    .map(|(uid, message_id, source_folder)| {
+                                        let dest_uid = uid_map.get(&uid).copied();
+                                        (message_id, dest_uid, source_folder)
+                                    })
+                                    .collect();
+                                let undo_entry = UndoEntry {
+                                    action_type: UndoActionType::Delete,
+                                    context: UndoContext::Group {
+                                        sender: format!("policy \"{}\"", name),
+                                    },
+                                    emails: undo_emails,
+                                    current_folder: folders.trash.to_string(),
+                                };
+                                app.push_undo(undo_entry);
+                                app.remove_emails(&removed);
+                            }
+                            _ => {}
+                        }
+                    }
+                    ui_state.clear_busy();
+                    if already_gone > 0 {
+                        ui_state.set_status(format!(
+                            "{} message(s) were already gone",
+                            std::mem::take(&mut already_gone)
+                        ));
+                    }
+                }
                 ImapResponse::RestoreResult(result) => {
                     debug_log!(
                         "UI: restore result: {}",
                         if result.is_ok() { "success" } else { "failed" }
                     );
-                    if let Some(PendingOp::Undo(index)) = pending_operation.take() {
+                    let undone = match pending_operation.take() {
+                        Some(PendingOp::Undo(index)) => Some((index, 1)),
+                        Some(PendingOp::UndoRecent(actions)) => Some((0, actions)),
+                        _ => None,
+                    };
+                    if let Some((index, actions)) = undone {
                         match result {
                             Ok(()) => {
                                 debug_log!("UI: undo successful, refreshing emails");
-                                // Remove the entry from history
-                                app.pop_undo(index);
+                                // Remove the entries from history
+                                for _ in 0..actions {
+                                    app.pop_undo(index);
+                                }
                                 // Stay in undo view - user can close it manually with Escape
                                 // Trigger refresh to update the email list
                                 ui_state.set_busy("Refreshing...");
                                 let _ = cmd_tx.send(ImapCommand::FetchInbox {
                                     parallel_connections,
+                                    merge_subject_threads,
+                                    since: fetch_since,
+                                    limit: fetch_limit,
                                 });
                             }
                             Err(e) => {
                                 debug_log!("UI: undo failed: {}", e);
                                 ui_state.clear_busy();
-                                ui_state.set_status(format!("Undo failed: {}", e));
+                                ui_state.set_alert(format!("Undo failed: {}", e));
                             }
                         }
                     } else {
@@ -2097,6 +5087,65 @@ fn run_app(
                 ImapResponse::Progress(current, total, action) => {
                     ui_state.update_busy_message(format!("{} {} of {}...", action, current, total));
                 }
+                ImapResponse::WorkerProgress(workers) => {
+                    ui_state.worker_progress = workers;
+                }
+                ImapResponse::ServerAlert(alert) => {
+                    debug_log!("UI: server alert: {}", alert);
+                    ui_state.queue_server_alert(alert);
+                }
+                ImapResponse::MailboxChanged(folders) => {
+                    debug_log!("UI: changed in another client: {:?}", folders);
+                    app.note_mailbox_changes(&folders);
+                    live_resync_due = app.is_mailbox_changed();
+                }
+                ImapResponse::NewMail => {
+                    debug_log!("UI: new mail pushed by IDLE");
+                    app.note_mailbox_changes(&["INBOX".to_string()]);
+                    live_resync_due = true;
+                }
+                // Replies come in order, so the first one after a background resync is its own
+                ImapResponse::Resynced(result) if live_resync => {
+                    live_resync = false;
+                    match result {
+                        Ok(resync) => {
+                            let added = app.apply_live_resync(
+                                &resync.removed,
+                                resync.added,
+                                merge_subject_threads,
+                            );
+                            if added > 0 {
+                                ui_state.set_status(format!(
+                                    "{} new email(s) arrived, marked • (J jumps to them)",
+                                    added
+                                ));
+                            }
+                        }
+                        // The view stays marked stale, so actions still offer a resync first
+                        Err(e) => debug_log!("UI: background resync failed: {:#}", e),
+                    }
+                }
+                ImapResponse::Resynced(result) => {
+                    ui_state.clear_busy();
+                    match result {
+                        Ok(resync) => {
+                            let removed = resync.removed.len();
+                            let added = app.apply_resync(
+                                &resync.removed,
+                                resync.added,
+                                merge_subject_threads,
+                            );
+                            ui_state.set_status(format!(
+                                "Resynced: {} removed elsewhere, {} new. Check the view and try again",
+                                removed, added
+                            ));
+                        }
+                        Err(e) => {
+                            debug_log!("UI: resync failed: {:#}", e);
+                            ui_state.set_alert(imap_error::describe("Resync failed", &e));
+                        }
+                    }
+                }
                 ImapResponse::Retrying {
                     attempt,
                     max_attempts,
@@ -2107,18 +5156,160 @@ fn run_app(
                         action, attempt, max_attempts
                     ));
                 }
+                ImapResponse::MultiMoveResult(result) => {
+                    debug_log!(
+                        "UI: multi-move result: {}",
+                        if result.is_ok() { "success" } else { "failed" }
+                    );
+                    ui_state.clear_busy();
+                    match (pending_operation.take(), result) {
+                        (
+                            Some(PendingOp::Move {
+                                dest,
+                                context,
+                                emails,
+                                archive,
+                            }),
+                            Ok(uid_map),
+                        ) => {
+                            let moved: Vec<(String, String)> = emails
+                                .iter()
+                                .map(|(uid, _, folder)| (uid.clone(), folder.clone()))
+                                .collect();
+                            let undo_emails = emails
+                                .into_iter()
+                                .map(|(uid, message_id, source_folder)| {
+                                    (message_id, uid_map.get(&uid).copied(), source_folder)
+                                })
+                                .collect();
+                            app.push_undo(UndoEntry {
+                                action_type: if archive {
+                                    UndoActionType::File
+                                } else {
+                                    UndoActionType::Move
+                                },
+                                context,
+                                emails: undo_emails,
+                                current_folder: dest.clone(),
+                            });
+                            app.remove_moved_emails(&moved);
+                            ui_state.set_status(format!(
+                                "{} {} email(s) in {}",
+                                if archive { "Filed" } else { "Moved" },
+                                moved.len(),
+                                dest
+                            ));
+                        }
+                        (_, Err(e)) => {
+                            ui_state.set_alert(imap_error::describe("Move failed", &e));
+                        }
+                        _ => {}
+                    }
+                    if already_gone > 0 {
+                        ui_state.set_status(format!(
+                            "{} message(s) were already gone",
+                            std::mem::take(&mut already_gone)
+                        ));
+                    }
+                }
+                ImapResponse::Folders(result) => {
+                    ui_state.clear_busy();
+                    match result {
+                        Ok(folders) => {
+                            open_folder_picker(&mut ui_state, folders.clone(), folder_target);
+                            move_folders = Some(folders);
+                        }
+                        Err(e) => {
+                            ui_state.set_alert(imap_error::describe("Failed to list folders", &e));
+                        }
+                    }
+                }
+                ImapResponse::FlagsSet { ids, seen, result } => {
+                    ui_state.clear_busy();
+                    match result {
+                        Ok(()) => {
+                            app.set_seen(&ids, seen);
+                            ui_state.set_status(format!(
+                                "Marked {} email(s) {}",
+                                ids.len(),
+                                if seen { "read" } else { "unread" }
+                            ));
+                        }
+                        Err(e) => ui_state.set_alert(imap_error::describe("Error", &e)),
+                    }
+                }
+                ImapResponse::LabelsModified {
+                    ids,
+                    add,
+                    remove,
+                    result,
+                } => {
+                    ui_state.clear_busy();
+                    match result {
+                        Ok(()) => {
+                            app.modify_labels(&ids, &add, &remove);
+                            ui_state.set_status(labels_status(ids.len(), &add, &remove));
+                        }
+                        Err(e) => {
+                            ui_state.set_alert(imap_error::describe("Label failed", &e));
+                        }
+                    }
+                }
+                // Cached mail is on screen, so the connection finished in the background
+                ImapResponse::Connected if reconciling => {
+                    cmd_tx.send(ImapCommand::FetchInbox {
+                        parallel_connections,
+                        merge_subject_threads,
+                        since: fetch_since,
+                        limit: fetch_limit,
+                    })?;
+                }
+                ImapResponse::Error(e) if reconciling => {
+                    return Err(anyhow::anyhow!("{}", e));
+                }
+                ImapResponse::UidValidity(values) => {
+                    // The server renumbered its messages since the cache was saved, so the
+                    // cached rows point at other mail: drop them and whatever was started
+                    // on them (the worker refuses it) and wait for the fetch
+                    if reconciling && !uid_validity.is_empty() && values != uid_validity {
+                        debug_log!("UI: UIDVALIDITY changed, discarding the header cache");
+                        reconciling = false;
+                        app.clear_emails();
+                        pending_operation = None;
+                        policy_queue.clear();
+                        if let Some(path) = &cache_path {
+                            let _ = std::fs::remove_file(path);
+                        }
+                        ui_state.set_busy("The server renumbered its mail, loading it again...");
+                        cmd_tx.send(ImapCommand::CheckUidValidity(values.clone()))?;
+                    }
+                    uid_validity = values;
+                }
                 ImapResponse::BodyResult { uid, result } => {
-                    // Check if we're still viewing this email
-                    if app.viewing_email_id() == Some(&uid) {
-                        match result {
-                            Ok(body) => {
-                                // Cache the body and update state
-                                app.set_email_body(&uid, body.clone());
-                                ui_state.text_view_state = TextViewState::Loaded(body);
+                    // Only update the text view if we're still viewing this email
+                    let viewing = app.viewing_email_id() == Some(&uid);
+                    match result {
+                        Ok(body) => {
+                            if body.truncated {
+                                ui_state.truncated_bodies.insert(uid.clone());
+                            } else {
+                                ui_state.truncated_bodies.remove(&uid);
                             }
-                            Err(e) => {
+                            ui_state.set_html_body(&uid, body.html);
+                            // Always cache the body (inline thread view shows it too)
+                            app.set_email_body(&uid, body.text.clone());
+                            if mark_seen_on_view {
+                                app.mark_email_seen(&uid);
+                            }
+                            if viewing {
+                                ui_state.text_view_state = TextViewState::Loaded(body.text);
+                            }
+                        }
+                        Err(e) => {
+                            if viewing {
                                 ui_state.text_view_state = TextViewState::Error(format!("{}", e));
                             }
+                            ui_state.body_errors.insert(uid, format!("{}", e));
                         }
                     }
                 }
@@ -2141,13 +5332,84 @@ fn run_app(
 
             // Handle confirmation dialog input
             if ui_state.is_confirming() {
+                let Some(key) = typed_confirm_key(&mut ui_state, key) else {
+                    continue;
+                };
                 match key.code {
                     KeyCode::Char('y') | KeyCode::Char('Y') => {
                         if let Some(action) = ui_state.confirm_action.take() {
                             if matches!(action, ConfirmAction::Quit) {
                                 let _ = cmd_tx.send(ImapCommand::Shutdown);
+                                if let Some(path) = &resume_path {
+                                    let state = resume::ResumeState::capture(
+                                        &app,
+                                        ui_state.group_scroll_offset,
+                                    );
+                                    if let Err(e) = state.save(path) {
+                                        debug_log!("Failed to save {}: {:#}", path.display(), e);
+                                    }
+                                }
+                                // Keep what this session archived and deleted out of the cache
+                                if let Some(path) = &cache_path
+                                    && !reconciling
+                                    && app.has_loaded_emails()
+                                    && let Err(e) = header_cache::save(
+                                        path,
+                                        &user_email,
+                                        &uid_validity,
+                                        app.cacheable_emails(),
+                                        cache_encryption.as_ref(),
+                                    )
+                                {
+                                    debug_log!("Failed to save {}: {:#}", path.display(), e);
+                                }
                                 break;
                             }
+                            if matches!(action, ConfirmAction::Resync { .. }) {
+                                ui_state.set_busy("Resyncing with the server...");
+                                cmd_tx.send(ImapCommand::Resync(app.loaded_email_ids()))?;
+                                continue;
+                            }
+                            // Don't act on emails another client may have moved
+                            if app.is_mailbox_changed() && action.changes_mailbox() {
+                                ui_state.set_confirm(ConfirmAction::Resync {
+                                    then: Box::new(action),
+                                });
+                                continue;
+                            }
+                            if matches!(action, ConfirmAction::ApplyPolicies { .. }) {
+                                policy_queue = policy_batches(&app, &cfg.policies);
+                                continue;
+                            }
+                            if matches!(action, ConfirmAction::RetryFetch { .. }) {
+                                ui_state.set_busy("Retrying remaining ranges...");
+                                cmd_tx.send(ImapCommand::RetryFetch)?;
+                                continue;
+                            }
+                            if matches!(action, ConfirmAction::SendReply { .. }) {
+                                if let (Some(draft), Some(server)) =
+                                    (pending_reply.take(), &smtp_server)
+                                {
+                                    ui_state.set_busy("Sending reply...");
+                                    send_draft(
+                                        server.clone(),
+                                        draft,
+                                        compose::Outgoing::Reply,
+                                        sent_tx.clone(),
+                                    );
+                                }
+                                continue;
+                            }
+                            if let ConfirmAction::IgnoreSender { sender } = &action {
+                                ignore_sender(
+                                    &mut app,
+                                    &mut ui_state,
+                                    &mut ignore_list,
+                                    sender,
+                                    ignore_path.as_deref(),
+                                );
+                                continue;
+                            }
                             handle_confirmed_action(
                                 &mut app,
                                 &cmd_tx,
@@ -2157,68 +5419,150 @@ fn run_app(
                             )?;
                         }
                     }
+                    KeyCode::Char('Z')
+                        if matches!(
+                            ui_state.confirm_action,
+                            Some(ConfirmAction::ArchiveGroupOld { .. })
+                        ) =>
+                    {
+                        cycle_group_age(&app, &mut ui_state);
+                    }
                     KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
-                        ui_state.clear_confirm();
+                        match ui_state.confirm_action.take() {
+                            Some(ConfirmAction::RetryFetch { error, .. }) => {
+                                ui_state.set_alert(format!("Error: {}", error));
+                            }
+                            Some(ConfirmAction::Resync { then }) if key.code != KeyCode::Esc => {
+                                // Go ahead with the loaded view
+                                app.clear_mailbox_changed();
+                                if matches!(*then, ConfirmAction::ApplyPolicies { .. }) {
+                                    policy_queue = policy_batches(&app, &cfg.policies);
+                                } else {
+                                    handle_confirmed_action(
+                                        &mut app,
+                                        &cmd_tx,
+                                        &mut ui_state,
+                                        &mut pending_operation,
+                                        *then,
+                                    )?;
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+
+            // Handle a plugin's, the move's or the labels' picker
+            if let Some(picker) = &mut ui_state.picker {
+                match key.code {
+                    KeyCode::Char('j') | KeyCode::Down => picker.select_next(),
+                    KeyCode::Char('k') | KeyCode::Up => picker.select_previous(),
+                    KeyCode::Enter => {
+                        let picker = ui_state.picker.take();
+                        if let Some(picker) = picker
+                            && let Some(choice) = picker.current_item()
+                        {
+                            match picker.target {
+                                PickerTarget::Plugin(key) => {
+                                    if let Some(plugin) = cfg.plugins.iter().find(|p| p.key == key)
+                                    {
+                                        start_plugin(
+                                            &app,
+                                            &mut ui_state,
+                                            plugin,
+                                            Some(choice),
+                                            &plugin_tx,
+                                        );
+                                    }
+                                }
+                                PickerTarget::MoveFolder { archive } => start_move(
+                                    &app,
+                                    &cmd_tx,
+                                    &mut ui_state,
+                                    &mut pending_operation,
+                                    choice,
+                                    archive,
+                                )?,
+                                PickerTarget::AddLabel => {
+                                    start_label(&app, &cmd_tx, &mut ui_state, choice, true)?
+                                }
+                                PickerTarget::RemoveLabel => {
+                                    start_label(&app, &cmd_tx, &mut ui_state, choice, false)?
+                                }
+                                PickerTarget::RestoreFolder => start_restore(
+                                    &app,
+                                    &cmd_tx,
+                                    &mut ui_state,
+                                    &mut pending_operation,
+                                    Some(choice),
+                                )?,
+                            }
+                        }
                     }
+                    KeyCode::Esc | KeyCode::Char('q') => ui_state.picker = None,
                     _ => {}
                 }
                 continue;
             }
 
-            // Clear status message on any key press
+            // Dismiss the status modal on any key press; toasts never take a key
             if ui_state.has_status() {
                 ui_state.clear_status();
                 continue; // Consume the key press
             }
 
+            // Any key closes the status history
+            if ui_state.show_status_history {
+                ui_state.show_status_history = false;
+                continue;
+            }
+
             // Handle help menu
             if ui_state.is_showing_help() {
                 match key.code {
                     KeyCode::Char('?') | KeyCode::Esc | KeyCode::Char('q') => {
                         ui_state.hide_help();
                     }
+                    KeyCode::Tab => ui_state.toggle_help_global(),
                     _ => {}
                 }
                 continue;
             }
 
+            // Handle sender note input
+            if ui_state.is_note_input_active() {
+                handle_note_input(&mut app, &mut ui_state, key.code, notes_path.as_deref());
+                continue;
+            }
+
+            // Handle the forward prompt
+            if ui_state.forward_input.is_some() {
+                if let Some(draft) = handle_forward_input(&app, &mut ui_state, key.code)
+                    && let Some(server) = &smtp_server
+                {
+                    ui_state.set_busy("Forwarding...");
+                    send_draft(
+                        server.clone(),
+                        draft,
+                        compose::Outgoing::Forward,
+                        sent_tx.clone(),
+                    );
+                }
+                continue;
+            }
+
             // Handle filter input mode (GroupList and EmailList views)
             if ui_state.is_filter_input_active() {
-                match key.code {
-                    KeyCode::Esc => {
-                        // Revert to previous filter and exit input mode
-                        let reverted = ui_state.revert_filter();
-                        if let Some(query) = &reverted {
-                            app.set_view_text_filter(Some(query.clone()));
-                            ui_state.set_filter_query(query);
-                        } else {
-                            app.clear_view_text_filter();
-                            ui_state.clear_filter_query();
-                        }
-                        ui_state.exit_filter_input_mode();
-                    }
-                    KeyCode::Enter => {
-                        // Exit input mode, keep filter active
-                        ui_state.exit_filter_input_mode();
-                    }
-                    KeyCode::Backspace => {
-                        ui_state.backspace_filter();
-                        // Update filter in real-time
-                        let query = ui_state.filter_query().to_string();
-                        if query.is_empty() {
-                            app.clear_view_text_filter();
-                        } else {
-                            app.set_view_text_filter(Some(query));
-                        }
-                    }
-                    KeyCode::Char(c) => {
-                        ui_state.append_filter_char(c);
-                        // Update filter in real-time
-                        let query = ui_state.filter_query().to_string();
-                        app.set_view_text_filter(Some(query));
-                    }
-                    _ => {}
-                }
+                handle_filter_input(&mut app, &mut ui_state, key.code);
+                continue;
+            }
+
+            // End the onboarding tour early
+            if key.code == KeyCode::Char('x') && ui_state.tour.is_some() {
+                ui_state.tour = None;
                 continue;
             }
 
@@ -2228,6 +5572,12 @@ fn run_app(
                 continue;
             }
 
+            // Show recent status messages with !
+            if key.code == KeyCode::Char('!') {
+                ui_state.show_status_history = true;
+                continue;
+            }
+
             // Enter filter mode with / (GroupList and EmailList views)
             if key.code == KeyCode::Char('/')
                 && matches!(app.view, View::GroupList | View::EmailList)
@@ -2252,15 +5602,32 @@ fn run_app(
                 pending_g = false;
             }
 
+            // ' then a letter jumps to the next group starting with it
+            if pending_letter_jump {
+                pending_letter_jump = false;
+                if let KeyCode::Char(letter) = key.code
+                    && !app.jump_to_group_starting_with(letter)
+                {
+                    ui_state.set_status(format!("No group starting with {}", letter));
+                }
+                continue;
+            }
+
             // Handle UndoHistory view separately
             if app.view == View::UndoHistory {
                 match key.code {
                     KeyCode::Char('q') => {
                         ui_state.set_confirm(ConfirmAction::Quit);
                     }
+                    KeyCode::Esc if app.undo_detail_scroll.is_some() => {
+                        app.toggle_undo_detail();
+                    }
                     KeyCode::Esc => {
                         app.exit_undo_history();
                     }
+                    KeyCode::Char(' ') => {
+                        app.toggle_undo_detail();
+                    }
                     KeyCode::Char('j') | KeyCode::Down => {
                         app.select_next();
                     }
@@ -2287,32 +5654,73 @@ fn run_app(
                         let half_page = ui_state.viewport_heights.for_view(app.view) / 2;
                         app.select_previous_n(half_page.max(1));
                     }
-                    KeyCode::Enter => {
-                        // Execute the undo action
-                        if let Some(entry) = app.current_undo_entry() {
-                            // Build restore ops: (message_id, dest_uid, current_folder, dest_folder)
-                            let restore_ops: Vec<(Option<String>, Option<u32>, String, String)> =
-                                entry
-                                    .emails
-                                    .iter()
-                                    .map(|(message_id, dest_uid, orig_folder)| {
-                                        (
-                                            message_id.clone(),
-                                            *dest_uid,
-                                            entry.current_folder.clone(),
-                                            orig_folder.clone(),
-                                        )
-                                    })
-                                    .collect();
-
-                            if !restore_ops.is_empty() {
-                                let count = restore_ops.len();
-                                ui_state.set_busy(format!("Restoring {} email(s)...", count));
-                                pending_operation = Some(PendingOp::Undo(app.selected_undo));
-                                let _ = cmd_tx.send(ImapCommand::RestoreEmails(restore_ops));
+                    KeyCode::Enter | KeyCode::Char('i') => {
+                        // Execute the undo action, into the inbox with i
+                        let dest = (key.code == KeyCode::Char('i')).then_some("INBOX");
+                        start_restore(&app, &cmd_tx, &mut ui_state, &mut pending_operation, dest)?;
+                    }
+                    KeyCode::Char('a') => confirm_undo_recent(&app, &mut ui_state),
+                    KeyCode::Char('v') if app.current_undo_entry().is_some() => {
+                        folder_target = PickerTarget::RestoreFolder;
+                        match move_folders.as_deref() {
+                            Some(folders) => {
+                                open_folder_picker(&mut ui_state, folders.to_vec(), folder_target)
                             }
+                            None => {
+                                ui_state.set_busy("Loading folders...");
+                                cmd_tx.send(ImapCommand::ListFolders)?;
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+
+            // Handle Duplicates view separately
+            if app.view == View::Duplicates {
+                match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => {
+                        app.exit_duplicates();
+                    }
+                    KeyCode::Char('j') | KeyCode::Down => {
+                        app.select_next();
+                    }
+                    KeyCode::Char('k') | KeyCode::Up => {
+                        app.select_previous();
+                    }
+                    KeyCode::Char('g') => {
+                        if pending_g {
+                            app.select_first();
+                            pending_g = false;
+                        } else {
+                            pending_g = true;
                         }
                     }
+                    KeyCode::Char('G') => {
+                        pending_g = false;
+                        app.select_last();
+                    }
+                    KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        let half_page = ui_state.viewport_heights.for_view(app.view) / 2;
+                        app.select_next_n(half_page.max(1));
+                    }
+                    KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        let half_page = ui_state.viewport_heights.for_view(app.view) / 2;
+                        app.select_previous_n(half_page.max(1));
+                    }
+                    KeyCode::Char('a') => {
+                        confirm_duplicates_action(&app, &mut ui_state, true, false)
+                    }
+                    KeyCode::Char('d') => {
+                        confirm_duplicates_action(&app, &mut ui_state, false, false)
+                    }
+                    KeyCode::Char('A') => {
+                        confirm_duplicates_action(&app, &mut ui_state, true, true)
+                    }
+                    KeyCode::Char('D') => {
+                        confirm_duplicates_action(&app, &mut ui_state, false, true)
+                    }
                     _ => {}
                 }
                 continue;
@@ -2327,6 +5735,23 @@ fn run_app(
                     KeyCode::Esc => {
                         app.exit_text_view();
                     }
+                    KeyCode::Char('h') => {
+                        toggle_html_view(&mut app, &mut ui_state);
+                    }
+                    KeyCode::Char('L') => {
+                        // Replace a truncated body with the full message
+                        if let Some(email) = app.viewing_email()
+                            && ui_state.truncated_bodies.contains(&email.id)
+                        {
+                            ui_state.text_view_state = TextViewState::Loading;
+                            cmd_tx.send(ImapCommand::FetchBody {
+                                uid: email.id.clone(),
+                                folder: email.source_folder.to_string(),
+                                max_bytes: None,
+                                mark_seen: mark_seen_on_view,
+                            })?;
+                        }
+                    }
                     KeyCode::Char('j') | KeyCode::Down => {
                         app.scroll_text_view_down(1);
                     }
@@ -2353,18 +5778,101 @@ fn run_app(
                         let half_page = ui_state.viewport_heights.text_view / 2;
                         app.scroll_text_view_up(half_page.max(1));
                     }
+                    KeyCode::Char('E') => {
+                        open_thread_in_browser(&app, &mut ui_state, &backend, &user_email);
+                    }
+                    KeyCode::Char('M') => {
+                        handle_toggle_seen(&app, &cmd_tx, &mut ui_state)?;
+                    }
+                    KeyCode::Char('V') | KeyCode::Char('F') => {
+                        folder_target = PickerTarget::MoveFolder {
+                            archive: key.code == KeyCode::Char('F'),
+                        };
+                        handle_move(
+                            &app,
+                            &cmd_tx,
+                            &mut ui_state,
+                            move_folders.as_deref(),
+                            folder_target,
+                        )?;
+                    }
+                    KeyCode::Char('+') => {
+                        folder_target = PickerTarget::AddLabel;
+                        handle_label(
+                            &app,
+                            &cmd_tx,
+                            &mut ui_state,
+                            &backend,
+                            move_folders.as_deref(),
+                            true,
+                        )?;
+                    }
+                    KeyCode::Char('-') => {
+                        handle_label(
+                            &app,
+                            &cmd_tx,
+                            &mut ui_state,
+                            &backend,
+                            move_folders.as_deref(),
+                            false,
+                        )?;
+                    }
+                    KeyCode::Char('U') => start_unsubscribe(
+                        &app,
+                        &mut ui_state,
+                        false,
+                        smtp_server.as_ref().map(|server| (server, &sent_tx)),
+                    ),
+                    KeyCode::Char('f') => start_forward(&app, &mut ui_state, smtp_server.is_some()),
+                    KeyCode::Char('R') => start_reply(
+                        terminal,
+                        &app,
+                        &mut ui_state,
+                        &mut pending_reply,
+                        smtp_server.is_some(),
+                        false,
+                    )?,
+                    KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        start_reply(
+                            terminal,
+                            &app,
+                            &mut ui_state,
+                            &mut pending_reply,
+                            smtp_server.is_some(),
+                            true,
+                        )?
+                    }
                     KeyCode::Char('e') => {
                         // Open in browser
                         if let Some(email) = app.viewing_email() {
                             if let Some(ref message_id) = email.message_id {
-                                if let Err(e) = open_email_in_browser(message_id, &user_email) {
-                                    ui_state.set_status(format!("Failed to open browser: {}", e));
+                                if let Err(e) = open_email_in_browser(
+                                    &backend.web_link(message_id, &user_email),
+                                ) {
+                                    ui_state.set_alert(format!("Failed to open browser: {}", e));
                                 }
                             } else {
                                 ui_state.set_status("Email has no Message-ID".to_string());
                             }
                         }
                     }
+                    KeyCode::Char('T') => {
+                        create_task_from_email(
+                            &mut app,
+                            &cmd_tx,
+                            &mut ui_state,
+                            &mut pending_operation,
+                            cfg,
+                            &backend,
+                            &user_email,
+                        )?;
+                    }
+                    KeyCode::Char('C') => {
+                        capture_to_notes_file(&app, &mut ui_state, cfg, &backend, &user_email);
+                    }
+                    KeyCode::Char('P') => {
+                        print_cursor_email(&app, &mut ui_state, cfg);
+                    }
                     KeyCode::Char('A') => {
                         handle_archive_all(&app, &mut ui_state);
                     }
@@ -2392,8 +5900,30 @@ fn run_app(
                     } else if app.view != View::GroupList {
                         // Layer 3: exit view (EmailList → GroupList; GroupList does nothing)
                         app.exit();
+                    } else if app.sweep_marks().is_some() {
+                        app.cancel_sweep();
+                        ui_state.set_status("Sweep cancelled".to_string());
                     }
                 }
+                KeyCode::Char('d')
+                    if key.modifiers.contains(KeyModifiers::CONTROL)
+                        && app.view == View::Thread
+                        && app.thread_inline_bodies =>
+                {
+                    // Scroll within the conversation rather than jumping messages
+                    let half_page = ui_state.viewport_heights.thread_view / 2;
+                    app.thread_body_scroll =
+                        app.thread_body_scroll.saturating_add(half_page.max(1));
+                }
+                KeyCode::Char('u')
+                    if key.modifiers.contains(KeyModifiers::CONTROL)
+                        && app.view == View::Thread
+                        && app.thread_inline_bodies =>
+                {
+                    let half_page = ui_state.viewport_heights.thread_view / 2;
+                    app.thread_body_scroll =
+                        app.thread_body_scroll.saturating_sub(half_page.max(1));
+                }
                 KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
                     let half_page = ui_state.viewport_heights.for_view(app.view) / 2;
                     app.select_next_n(half_page.max(1));
@@ -2402,6 +5932,26 @@ fn run_app(
                     let half_page = ui_state.viewport_heights.for_view(app.view) / 2;
                     app.select_previous_n(half_page.max(1));
                 }
+                KeyCode::Char('v') if app.view == View::Thread => {
+                    app.toggle_thread_inline_bodies();
+                }
+                KeyCode::Char('v') if app.view == View::EmailList => {
+                    app.toggle_row_density();
+                }
+                KeyCode::Char('%') if app.view == View::EmailList => {
+                    app.toggle_spam_score_sort();
+                }
+                KeyCode::Enter | KeyCode::Char(' ')
+                    if app.view == View::Thread && app.thread_inline_bodies =>
+                {
+                    app.toggle_current_thread_email_collapsed();
+                }
+                KeyCode::Char('o') if app.view == View::Thread && app.thread_inline_bodies => {
+                    app.expand_all_thread_emails();
+                }
+                KeyCode::Char('O') if app.view == View::Thread && app.thread_inline_bodies => {
+                    app.collapse_all_thread_emails();
+                }
                 KeyCode::Char('j') | KeyCode::Down => {
                     app.select_next();
                 }
@@ -2413,7 +5963,7 @@ fn run_app(
                         // Enter text view for the selected email in thread
                         if let Some(email) = app.current_thread_email() {
                             let email_id = email.id.clone();
-                            let folder = email.source_folder.clone();
+                            let folder = email.source_folder.to_string();
                             let has_body = email.body.is_some();
                             app.enter_text_view(&email_id);
 
@@ -2429,6 +5979,8 @@ fn run_app(
                                 cmd_tx.send(ImapCommand::FetchBody {
                                     uid: email_id,
                                     folder,
+                                    max_bytes: body_size_limit,
+                                    mark_seen: mark_seen_on_view,
                                 })?;
                             }
                         }
@@ -2438,7 +5990,7 @@ fn run_app(
                         // Single email - enter text view directly (no thread view needed)
                         if let Some(email) = app.current_email() {
                             let email_id = email.id.clone();
-                            let folder = email.source_folder.clone();
+                            let folder = email.source_folder.to_string();
                             let has_body = email.body.is_some();
                             app.enter_text_view(&email_id);
 
@@ -2454,6 +6006,8 @@ fn run_app(
                                 cmd_tx.send(ImapCommand::FetchBody {
                                     uid: email_id,
                                     folder,
+                                    max_bytes: body_size_limit,
+                                    mark_seen: mark_seen_on_view,
                                 })?;
                             }
                         }
@@ -2461,24 +6015,96 @@ fn run_app(
                         app.enter();
                     }
                 }
+                KeyCode::Char('E') => {
+                    if matches!(app.view, View::Thread | View::EmailList) {
+                        open_thread_in_browser(&app, &mut ui_state, &backend, &user_email);
+                    }
+                }
+                KeyCode::Char('M') => {
+                    if matches!(app.view, View::Thread | View::EmailList) {
+                        handle_toggle_seen(&app, &cmd_tx, &mut ui_state)?;
+                    }
+                }
+                KeyCode::Char('V')
+                    if matches!(app.view, View::GroupList | View::Thread | View::EmailList) =>
+                {
+                    folder_target = PickerTarget::MoveFolder { archive: false };
+                    handle_move(
+                        &app,
+                        &cmd_tx,
+                        &mut ui_state,
+                        move_folders.as_deref(),
+                        folder_target,
+                    )?;
+                }
+                KeyCode::Char('F')
+                    if matches!(app.view, View::GroupList | View::Thread | View::EmailList) =>
+                {
+                    folder_target = PickerTarget::MoveFolder { archive: true };
+                    handle_move(
+                        &app,
+                        &cmd_tx,
+                        &mut ui_state,
+                        move_folders.as_deref(),
+                        folder_target,
+                    )?;
+                }
+                KeyCode::Char('+')
+                    if matches!(app.view, View::GroupList | View::Thread | View::EmailList) =>
+                {
+                    folder_target = PickerTarget::AddLabel;
+                    handle_label(
+                        &app,
+                        &cmd_tx,
+                        &mut ui_state,
+                        &backend,
+                        move_folders.as_deref(),
+                        true,
+                    )?;
+                }
+                KeyCode::Char('-')
+                    if matches!(app.view, View::GroupList | View::Thread | View::EmailList) =>
+                {
+                    handle_label(
+                        &app,
+                        &cmd_tx,
+                        &mut ui_state,
+                        &backend,
+                        move_folders.as_deref(),
+                        false,
+                    )?;
+                }
                 KeyCode::Char('e') => {
                     // Open email in browser
-                    let email_to_open = match app.view {
-                        View::Thread => app.current_thread_email(),
-                        View::EmailList => app.current_email(),
-                        View::EmailBody => app.viewing_email(),
-                        _ => None,
-                    };
-                    if let Some(email) = email_to_open {
+                    if let Some(email) = cursor_email(&app) {
                         if let Some(ref message_id) = email.message_id {
-                            if let Err(e) = open_email_in_browser(message_id, &user_email) {
-                                ui_state.set_status(format!("Failed to open browser: {}", e));
+                            if let Err(e) =
+                                open_email_in_browser(&backend.web_link(message_id, &user_email))
+                            {
+                                ui_state.set_alert(format!("Failed to open browser: {}", e));
                             }
                         } else {
                             ui_state.set_status("Email has no Message-ID".to_string());
                         }
                     }
                 }
+                KeyCode::Char('T') => {
+                    create_task_from_email(
+                        &mut app,
+                        &cmd_tx,
+                        &mut ui_state,
+                        &mut pending_operation,
+                        cfg,
+                        &backend,
+                        &user_email,
+                    )?;
+                }
+                KeyCode::Char('C') => {
+                    capture_to_notes_file(&app, &mut ui_state, cfg, &backend, &user_email);
+                }
+                KeyCode::Char('P') => {
+                    print_cursor_email(&app, &mut ui_state, cfg);
+                }
                 KeyCode::Char('g') => {
                     if pending_g {
                         // gg - go to top
@@ -2493,21 +6119,170 @@ fn run_app(
                     pending_g = false;
                     app.select_last();
                 }
-                KeyCode::Char('m') => {
-                    if app.view == View::GroupList {
-                        app.toggle_group_mode();
+                KeyCode::Char('m') if app.view == View::GroupList => {
+                    app.toggle_group_mode();
+                }
+                KeyCode::Char('f') if app.view == View::GroupList => {
+                    app.enter_duplicates();
+                }
+                KeyCode::Char('Z') if app.view == View::GroupList => {
+                    confirm_archive_old(&app, &mut ui_state);
+                }
+                KeyCode::Char('S') if app.view == View::GroupList => {
+                    confirm_archive_singles(&app, &mut ui_state);
+                }
+                KeyCode::Char('R') if app.view == View::GroupList => {
+                    confirm_archive_read(&app, &mut ui_state, None);
+                }
+                KeyCode::Char('Z') if app.view == View::EmailList => {
+                    if let Some(group) = app.current_group().map(|g| g.key.clone()) {
+                        confirm_archive_group_old(&app, &mut ui_state, group, GROUP_AGE_PRESETS[1]);
+                    }
+                }
+                KeyCode::Char('R') if app.view == View::EmailList => {
+                    let group = app.current_group().map(|g| g.key.clone());
+                    if group.is_some() {
+                        confirm_archive_read(&app, &mut ui_state, group);
+                    }
+                }
+                KeyCode::Char('W')
+                    if (app.view == View::GroupList || app.view == View::EmailList) =>
+                {
+                    handle_sweep(&mut app, &mut ui_state);
+                }
+                KeyCode::Char('c') if app.view == View::GroupList => {
+                    app.toggle_group_done();
+                }
+                KeyCode::Char('x')
+                    if (app.view == View::GroupList || app.view == View::EmailList) =>
+                {
+                    handle_dismiss(&mut app, &mut ui_state);
+                }
+                KeyCode::Char('U') if matches!(app.view, View::Thread | View::EmailList) => {
+                    start_unsubscribe(
+                        &app,
+                        &mut ui_state,
+                        false,
+                        smtp_server.as_ref().map(|server| (server, &sent_tx)),
+                    );
+                }
+                KeyCode::Char('U') if app.view == View::GroupList => {
+                    handle_undismiss(&mut app, &mut ui_state);
+                }
+                KeyCode::Char('H') if app.view == View::GroupList => {
+                    let toggled = app.toggle_small_groups();
+                    if toggled.is_none() {
+                        ui_state.set_status(
+                            "Set min_group_size in the config to hide small groups".to_string(),
+                        );
+                    }
+                }
+                KeyCode::Char('J')
+                    if (app.view == View::GroupList || app.view == View::EmailList)
+                        && !app.jump_to_new() =>
+                {
+                    ui_state.set_status("No new mail since the first load".to_string());
+                }
+                KeyCode::Char('\'') if app.view == View::GroupList => {
+                    pending_letter_jump = true;
+                }
+                KeyCode::Char('b') if app.view == View::GroupList => {
+                    app.select_largest_group();
+                }
+                KeyCode::Char(c @ '1'..='9') if app.view == View::GroupList => {
+                    let index = ui_state.group_scroll_offset + (c as usize - '1' as usize);
+                    if !app.enter_group_at(index) {
+                        ui_state.set_status(format!("No group {} on screen", c));
+                    }
+                }
+                KeyCode::Char('s') if app.view == View::GroupList => {
+                    app.select_random_group(random_roll());
+                }
+                KeyCode::Char('w') if app.view == View::GroupList => {
+                    postpone_group(
+                        &mut app,
+                        &mut ui_state,
+                        &mut postpone_list,
+                        cfg.postpone_days,
+                        postpone_path.as_deref(),
+                    );
+                }
+                KeyCode::Char('I') if app.view == View::GroupList => {
+                    if let Some(sender) = current_sender_key(&app, &mut ui_state) {
+                        ui_state.set_confirm(ConfirmAction::IgnoreSender { sender });
                     }
                 }
+                KeyCode::Char('.')
+                    if (app.view == View::GroupList || app.view == View::EmailList) =>
+                {
+                    confirm_usual_action(&app, &mut ui_state);
+                }
+                KeyCode::Char('n')
+                    if (app.view == View::GroupList || app.view == View::EmailList) =>
+                {
+                    if let Some(sender) = current_sender_key(&app, &mut ui_state) {
+                        ui_state.start_note_input(&sender, app.notes.get(&sender));
+                    }
+                }
+                KeyCode::Char('L') if app.view == View::GroupList => {
+                    if fetch_limit.is_some() && app.load_more_count > 0 {
+                        ui_state.set_busy("Loading older mail...");
+                        cmd_tx.send(ImapCommand::FetchPage {
+                            parallel_connections,
+                            since: fetch_since,
+                            page: FetchPage {
+                                below_uid: app.lowest_uids().clone(),
+                                loaded: app.loaded_per_folder(),
+                                count: cfg.fetch_page_size,
+                            },
+                        })?;
+                        loading_page = Some(cfg.fetch_page_size);
+                    } else if let Some(before) = fetch_since {
+                        ui_state.set_busy("Loading older mail...");
+                        cmd_tx.send(ImapCommand::FetchOlder {
+                            parallel_connections,
+                            before,
+                        })?;
+                    } else {
+                        ui_state.set_status("All mail is already loaded".to_string());
+                    }
+                }
+                KeyCode::Char('R') if app.view == View::Thread => {
+                    start_reply(
+                        terminal,
+                        &app,
+                        &mut ui_state,
+                        &mut pending_reply,
+                        smtp_server.is_some(),
+                        false,
+                    )?;
+                }
+                KeyCode::Char('r')
+                    if app.view == View::Thread
+                        && key.modifiers.contains(KeyModifiers::CONTROL) =>
+                {
+                    start_reply(
+                        terminal,
+                        &app,
+                        &mut ui_state,
+                        &mut pending_reply,
+                        smtp_server.is_some(),
+                        true,
+                    )?;
+                }
                 KeyCode::Char('r') => {
                     ui_state.set_busy("Refreshing...");
                     cmd_tx.send(ImapCommand::FetchInbox {
                         parallel_connections,
+                        merge_subject_threads,
+                        since: fetch_since,
+                        limit: fetch_limit,
                     })?;
                 }
-                KeyCode::Char('t') => {
-                    if app.view == View::GroupList || app.view == View::EmailList {
-                        app.toggle_thread_filter();
-                    }
+                KeyCode::Char('t')
+                    if (app.view == View::GroupList || app.view == View::EmailList) =>
+                {
+                    app.toggle_thread_filter();
                 }
                 KeyCode::Char('u') => {
                     // Enter undo history view (if history is not empty)
@@ -2531,12 +6306,40 @@ fn run_app(
                             "Cannot select individual emails in thread view. Press Enter to open the thread."
                                 .to_string(),
                         );
+                    } else if app.sweep_marks().is_some()
+                        && (app.view == View::GroupList || app.view == View::EmailList)
+                    {
+                        // While sweeping, Space marks what to keep instead of selecting
+                        if app.toggle_keep() == app::SelectionResult::Toggled && advance_on_select {
+                            app.select_next();
+                        }
                     } else if app.view == View::EmailList
                         && let app::SelectionResult::Toggled = app.toggle_email_selection()
                         && advance_on_select
                     {
                         app.select_next();
-                    }
+                    } else if app.view == View::GroupList
+                        && let app::SelectionResult::Toggled = app.toggle_group_selection()
+                        && advance_on_select
+                    {
+                        app.select_next();
+                    }
+                }
+                KeyCode::Char(c) if cfg.plugins.iter().any(|p| p.key == c) => {
+                    if let Some(plugin) = cfg.plugins.iter().find(|p| p.key == c) {
+                        start_plugin(&app, &mut ui_state, plugin, None, &plugin_tx);
+                    }
+                }
+                KeyCode::Char(c) if cfg.actions.iter().any(|a| a.key == c) => {
+                    if let Some(action) = cfg.actions.iter().find(|a| a.key == c) {
+                        run_custom_action(
+                            &mut app,
+                            &cmd_tx,
+                            &mut ui_state,
+                            &mut pending_operation,
+                            action,
+                        )?;
+                    }
                 }
                 _ => {}
             }
@@ -2549,6 +6352,14 @@ fn run_app(
 /// Tracks pending operations so we know what to update when response arrives
 /// Also stores data needed to create undo entries when the result comes back
 enum PendingOp {
+    /// Move to a folder: (dest folder, undo label, Vec<(uid, message_id, source_folder)>),
+    /// filed as an archive when archive is set
+    Move {
+        dest: String,
+        context: UndoContext,
+        emails: Vec<(String, Option<String>, String)>,
+        archive: bool,
+    },
     /// Archive group: (sender, Vec<(uid, message_id, source_folder)>)
     ArchiveGroup {
         sender: String,
@@ -2581,61 +6392,848 @@ enum PendingOp {
         count: usize,
         emails: Vec<(String, Option<String>, String)>,
     },
+    /// Archive duplicates: (count, Vec<(uid, message_id, source_folder)>)
+    ArchiveDuplicates {
+        count: usize,
+        emails: Vec<(String, Option<String>, String)>,
+    },
+    /// Delete duplicates: (count, Vec<(uid, message_id, source_folder)>)
+    DeleteDuplicates {
+        count: usize,
+        emails: Vec<(String, Option<String>, String)>,
+    },
+    /// Archive old: (days, group key or None for the whole inbox, Vec<(uid, message_id, source_folder)>)
+    ArchiveOld {
+        days: u32,
+        group: Option<String>,
+        emails: Vec<(String, Option<String>, String)>,
+    },
+    /// Archive single messages: Vec<(uid, message_id, source_folder)>
+    ArchiveSingles {
+        emails: Vec<(String, Option<String>, String)>,
+    },
+    /// Archive read mail: (group key, or None for the whole inbox, Vec<(uid, message_id, source_folder)>)
+    ArchiveRead {
+        group: Option<String>,
+        emails: Vec<(String, Option<String>, String)>,
+    },
+    /// Archive a sender's mail after unsubscribing: (sender, Vec<(uid, message_id, source_folder)>)
+    ArchiveSender {
+        sender: String,
+        emails: Vec<(String, Option<String>, String)>,
+    },
+    /// Archive everything not kept in a sweep: Vec<(uid, message_id, source_folder)>
+    ArchiveSweep {
+        emails: Vec<(String, Option<String>, String)>,
+    },
+    /// Archive selected groups: (group keys, Vec<(uid, message_id, source_folder)>)
+    ArchiveGroups {
+        senders: Vec<String>,
+        emails: Vec<(String, Option<String>, String)>,
+    },
+    /// Delete selected groups: (group keys, Vec<(uid, message_id, source_folder)>)
+    DeleteGroups {
+        senders: Vec<String>,
+        emails: Vec<(String, Option<String>, String)>,
+    },
+    /// Policy: (policy name, Vec<(uid, message_id, source_folder)>)
+    Policy {
+        name: String,
+        emails: Vec<(String, Option<String>, String)>,
+    },
     /// Undo: index in undo history
     Undo(usize),
+    /// Undo the newest entries in the undo history: how many
+    UndoRecent(usize),
+}
+
+impl PendingOp {
+    /// Summarizes a completed bulk operation for the webhook (None for single threads and undo)
+    fn bulk_summary(&self, action: &'static str, account: &str) -> Option<webhook::BulkSummary> {
+        let (scope, sender, policy, count) = match self {
+            PendingOp::ArchiveGroup { sender, emails }
+            | PendingOp::DeleteGroup { sender, emails } => {
+                ("group", Some(sender.clone()), None, emails.len())
+            }
+            PendingOp::ArchiveGroups { senders, emails }
+            | PendingOp::DeleteGroups { senders, emails } => {
+                ("groups", Some(senders.join(", ")), None, emails.len())
+            }
+            PendingOp::ArchiveSelected { emails, .. }
+            | PendingOp::DeleteSelected { emails, .. } => ("selected", None, None, emails.len()),
+            PendingOp::ArchiveDuplicates { emails, .. }
+            | PendingOp::DeleteDuplicates { emails, .. } => {
+                ("duplicates", None, None, emails.len())
+            }
+            PendingOp::ArchiveOld { group, emails, .. } => {
+                ("old", group.clone(), None, emails.len())
+            }
+            PendingOp::ArchiveSingles { emails } => ("singles", None, None, emails.len()),
+            PendingOp::ArchiveRead { group, emails } => ("read", group.clone(), None, emails.len()),
+            PendingOp::ArchiveSender { sender, emails } => {
+                ("sender", Some(sender.clone()), None, emails.len())
+            }
+            PendingOp::ArchiveSweep { emails } => ("sweep", None, None, emails.len()),
+            PendingOp::Policy { name, emails } => {
+                ("policy", None, Some(name.clone()), emails.len())
+            }
+            PendingOp::ArchiveThread { .. }
+            | PendingOp::DeleteThread { .. }
+            | PendingOp::Move { .. }
+            | PendingOp::Undo(_)
+            | PendingOp::UndoRecent(_) => {
+                return None;
+            }
+        };
+        Some(webhook::BulkSummary {
+            action,
+            scope,
+            sender,
+            policy,
+            count,
+            account: account.to_string(),
+        })
+    }
+}
+
+/// A policy's matched emails waiting to be archived or deleted
+struct PolicyBatch {
+    name: String,
+    action: PolicyAction,
+    emails: Vec<(String, Option<String>, String)>,
+}
+
+/// Queues the configured policies' matches, or asks first when confirm_policies is set
+/// or any of them deletes
+fn queue_policies(
+    app: &App,
+    cfg: &config::Config,
+    ui_state: &mut UiState,
+    policy_queue: &mut Vec<PolicyBatch>,
+) {
+    let matches = app.evaluate_policies(&cfg.policies);
+    if matches.is_empty() {
+        return;
+    }
+    let deletes = matches
+        .iter()
+        .any(|(policy, _)| policy.action == PolicyAction::Delete);
+    if cfg.confirm_policies || deletes {
+        let count = matches.iter().map(|(_, emails)| emails.len()).sum();
+        let summary = policy::summarize(&matches);
+        ui_state.set_confirm(ConfirmAction::ApplyPolicies { count, summary });
+    } else {
+        *policy_queue = policy_batches(app, &cfg.policies);
+    }
+}
+
+/// Builds one batch per policy that matches any loaded emails
+fn policy_batches(app: &App, policies: &[Policy]) -> Vec<PolicyBatch> {
+    app.evaluate_policies(policies)
+        .into_iter()
+        .map(|(policy, emails)| PolicyBatch {
+            name: policy.name.clone(),
+            action: policy.action,
+            emails: emails
+                .iter()
+                .map(|e| {
+                    (
+                        e.id.clone(),
+                        e.message_id.clone(),
+                        e.source_folder.to_string(),
+                    )
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// Sends a policy batch to the IMAP worker
+fn start_policy_batch(
+    batch: PolicyBatch,
+    cmd_tx: &mpsc::Sender<ImapCommand>,
+    ui_state: &mut UiState,
+    pending_operation: &mut Option<PendingOp>,
+) -> Result<()> {
+    let email_ids: Vec<(String, String)> = batch
+        .emails
+        .iter()
+        .map(|(uid, _, folder)| (uid.clone(), folder.clone()))
+        .collect();
+    let verb = match batch.action {
+        PolicyAction::Archive => "Archiving",
+        PolicyAction::Delete => "Deleting",
+    };
+    ui_state.set_busy(format!(
+        "{} {} emails (policy \"{}\")...",
+        verb,
+        email_ids.len(),
+        batch.name
+    ));
+    *pending_operation = Some(PendingOp::Policy {
+        name: batch.name,
+        emails: batch.emails,
+    });
+    match batch.action {
+        PolicyAction::Archive => cmd_tx.send(ImapCommand::ArchiveMultiple(email_ids))?,
+        PolicyAction::Delete => cmd_tx.send(ImapCommand::DeleteMultiple(email_ids))?,
+    }
+    Ok(())
+}
+
+/// Handles the 'a' key - archive thread (not available in thread view)
+fn handle_archive(
+    app: &mut App,
+    cmd_tx: &mpsc::Sender<ImapCommand>,
+    ui_state: &mut UiState,
+    pending_operation: &mut Option<PendingOp>,
+) -> Result<()> {
+    match app.view {
+        View::GroupList | View::UndoHistory | View::Duplicates | View::EmailBody => {
+            // No action on single 'a' in group list, undo history, or text view
+        }
+        View::EmailList => {
+            // Check if there are visible selected emails - require confirmation
+            if app.has_visible_selection() {
+                let count = app.selected_thread_email_ids().len();
+                if count > 0 {
+                    ui_state.set_confirm(ConfirmAction::ArchiveSelected { count });
+                }
+                return Ok(());
+            }
+
+            // No visible selection - archive the entire thread
+            let email_ids = app.current_thread_email_ids();
+            if app.is_mailbox_changed() && !email_ids.is_empty() {
+                ui_state.set_confirm(ConfirmAction::Resync {
+                    then: Box::new(ConfirmAction::ArchiveThread {
+                        thread_email_count: email_ids.len(),
+                    }),
+                });
+                return Ok(());
+            }
+            let emails_for_undo = app.current_thread_emails_for_undo();
+            let thread_id = app.current_email().map(|e| e.thread_id.clone());
+            let subject = app.current_email().map(|e| e.subject.clone());
+            if !email_ids.is_empty()
+                && let Some(tid) = thread_id
+                && let Some(subj) = subject
+            {
+                ui_state.set_busy(format!("Archiving thread ({} emails)...", email_ids.len()));
+                *pending_operation = Some(PendingOp::ArchiveThread {
+                    thread_id: tid,
+                    subject: subj,
+                    emails: emails_for_undo,
+                });
+                cmd_tx.send(ImapCommand::ArchiveMultiple(email_ids))?;
+            }
+        }
+        View::Thread => {
+            // No lowercase 'a' in thread view - use 'A' to archive entire thread
+        }
+    }
+    Ok(())
+}
+
+/// Loads a per-account data file. An unreadable file is left alone rather than
+/// overwritten: its path is cleared and the data starts out empty.
+fn load_account_data<T: Default>(
+    path: &mut Option<PathBuf>,
+    load: impl Fn(&Path) -> Result<T>,
+) -> T {
+    let Some(file) = path.as_deref() else {
+        return T::default();
+    };
+    match load(file) {
+        Ok(data) => data,
+        Err(e) => {
+            debug_log!("Failed to load {}: {:#}", file.display(), e);
+            *path = None;
+            T::default()
+        }
+    }
+}
+
+/// Hides a sender's threads and adds it to the ignore list, saved to ignore_path if given
+fn ignore_sender(
+    app: &mut App,
+    ui_state: &mut UiState,
+    ignore_list: &mut ignore::IgnoreList,
+    sender: &str,
+    ignore_path: Option<&Path>,
+) {
+    let hidden = app.ignore_sender(sender);
+    let mut message = format!("Hid {} email(s) from {}", hidden, sender);
+    if ignore_list.add(sender)
+        && let Some(path) = ignore_path
+    {
+        match ignore_list.save(path) {
+            Ok(()) => message.push_str(&format!(
+                ". Remove it from {} to see it again.",
+                path.display()
+            )),
+            Err(e) => return ui_state.set_alert(format!("Error: {:#}", e)),
+        }
+    }
+    ui_state.set_status(message);
+}
+
+/// Hides the current group until a later session, saved to postpone_path if given
+fn postpone_group(
+    app: &mut App,
+    ui_state: &mut UiState,
+    postpone_list: &mut postpone::PostponeList,
+    days: u32,
+    postpone_path: Option<&Path>,
+) {
+    let Some(sender) = current_sender_key(app, ui_state) else {
+        return;
+    };
+    let until = postpone::postpone_until(chrono::Local::now().date_naive(), days);
+    let hidden = app.postpone_sender(&sender);
+    postpone_list.add(&sender, until);
+    let saved = match postpone_path {
+        Some(path) => postpone_list.save(path),
+        None => Ok(()),
+    };
+    ui_state.set_status(match saved {
+        Ok(()) => format!(
+            "Postponed {} email(s) from {} until {}",
+            hidden,
+            sender,
+            until.format("%a %b %-d")
+        ),
+        Err(e) => format!("Error: {:#}", e),
+    });
+}
+
+/// Returns the sender or domain of the current group. Label groups don't stand for one,
+/// so the status line says to regroup instead.
+fn current_sender_key(app: &App, ui_state: &mut UiState) -> Option<String> {
+    if app.current_group().is_some() && app.current_sender_key().is_none() {
+        ui_state.set_status("Group by sender or domain (m) to act on a sender");
+    }
+    app.current_sender_key().map(str::to_string)
+}
+
+/// Asks to confirm the current group's usual action, once one has been remembered
+fn confirm_usual_action(app: &App, ui_state: &mut UiState) {
+    let Some(sender) = current_sender_key(app, ui_state) else {
+        return;
+    };
+    let Some(usual) = app.habits.usual(&sender) else {
+        ui_state.set_status(format!("No usual action for {} yet", sender));
+        return;
+    };
+    let count = app.current_group_thread_email_ids().len();
+    let filtered = app.view == View::EmailList && app.has_view_text_filter();
+    ui_state.set_confirm(match usual {
+        PolicyAction::Archive => ConfirmAction::ArchiveEmails {
+            sender,
+            count,
+            filtered,
+        },
+        PolicyAction::Delete => ConfirmAction::DeleteEmails {
+            sender,
+            count,
+            filtered,
+        },
+    });
+}
+
+/// Records how a sender's mail was handled; saves to habits_path if given
+fn remember_handling(
+    app: &mut App,
+    sender: &str,
+    action: PolicyAction,
+    habits_path: Option<&Path>,
+) {
+    // A label group's key isn't a sender
+    if app.group_mode == GroupMode::ByLabel {
+        return;
+    }
+    app.habits.record(sender, action);
+    if let Some(path) = habits_path
+        && let Err(e) = app.habits.save(path)
+    {
+        debug_log!("Failed to save sender habits: {:#}", e);
+    }
+}
+
+/// Handles keys while a sender note is being typed; Enter saves it to notes_path if given
+fn handle_note_input(
+    app: &mut App,
+    ui_state: &mut UiState,
+    key: KeyCode,
+    notes_path: Option<&Path>,
+) {
+    let Some(input) = ui_state.note_input.as_mut() else {
+        return;
+    };
+    match key {
+        KeyCode::Esc => {
+            ui_state.note_input = None;
+        }
+        KeyCode::Enter => {
+            app.notes.set(&input.key, &input.text);
+            ui_state.note_input = None;
+            if let Some(path) = notes_path
+                && let Err(e) = app.notes.save(path)
+            {
+                ui_state.set_alert(format!("Error: {:#}", e));
+            }
+        }
+        KeyCode::Backspace => {
+            input.text.pop();
+        }
+        KeyCode::Char(c) => {
+            input.text.push(c);
+        }
+        _ => {}
+    }
+}
+
+/// Hides the selected group or thread for this session ("not now")
+fn handle_dismiss(app: &mut App, ui_state: &mut UiState) {
+    let hidden = app.dismiss_current();
+    if hidden > 0 {
+        ui_state.set_status(format!(
+            "Hid {} thread{} for this session (U in the group list shows {} hidden)",
+            hidden,
+            if hidden == 1 { "" } else { "s" },
+            app.dismissed_count()
+        ));
+    }
+}
+
+/// Brings back everything hidden with x
+fn handle_undismiss(app: &mut App, ui_state: &mut UiState) {
+    let shown = app.undismiss_all();
+    ui_state.set_status(if shown == 0 {
+        "Nothing is hidden".to_string()
+    } else {
+        format!(
+            "Showing {} hidden thread{} again",
+            shown,
+            if shown == 1 { "" } else { "s" }
+        )
+    });
+}
+
+/// Passes a key to a confirmation that wants an answer typed, returning the key the
+/// dialog should act on: y once the answer is entered, Esc to cancel, and None while it's
+/// being typed. Other confirmations get the key back unchanged.
+fn typed_confirm_key(ui_state: &mut UiState, key: KeyEvent) -> Option<KeyEvent> {
+    let Some(typed) = ui_state.confirm_input.as_mut() else {
+        return Some(key);
+    };
+    match key.code {
+        KeyCode::Esc => {
+            ui_state.confirm_input = None;
+            Some(key)
+        }
+        KeyCode::Enter if typed.is_answered() => {
+            ui_state.confirm_input = None;
+            Some(KeyEvent::new(KeyCode::Char('y'), KeyModifiers::NONE))
+        }
+        KeyCode::Enter => {
+            typed.text.clear();
+            let choices = typed.choices();
+            ui_state.set_status(format!("Type {} to confirm, or Esc to cancel", choices));
+            None
+        }
+        KeyCode::Backspace => {
+            typed.text.pop();
+            None
+        }
+        KeyCode::Char(c) => {
+            typed.text.push(c);
+            None
+        }
+        _ => None,
+    }
+}
+
+/// Hands the terminal to another program, such as the editor, while `run` runs
+fn with_terminal_suspended<T>(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    run: impl FnOnce() -> T,
+) -> Result<T> {
+    disable_raw_mode()?;
+    execute!(
+        terminal.backend_mut(),
+        LeaveAlternateScreen,
+        DisableMouseCapture
+    )?;
+    terminal.show_cursor()?;
+    let result = run();
+    enable_raw_mode()?;
+    execute!(
+        terminal.backend_mut(),
+        EnterAlternateScreen,
+        EnableMouseCapture
+    )?;
+    terminal.clear()?;
+    Ok(result)
+}
+
+/// Opens a reply to the email under the cursor in $EDITOR, to everyone on it with `all`,
+/// then asks before sending it. Like forwarding, it waits for the whole body so the quote
+/// isn't just the snippet or a cut-off message.
+fn start_reply(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    app: &App,
+    ui_state: &mut UiState,
+    pending_reply: &mut Option<compose::Draft>,
+    can_send: bool,
+    all: bool,
+) -> Result<()> {
+    if !can_send {
+        ui_state.set_status("Replies can't be sent from a replayed session".to_string());
+        return Ok(());
+    }
+    let Some(email) = cursor_email(app) else {
+        return Ok(());
+    };
+    let Some(body) = email.body.as_deref() else {
+        ui_state.set_status("Open the email and wait for it to load before replying".to_string());
+        return Ok(());
+    };
+    if ui_state.truncated_bodies.contains(&email.id) {
+        ui_state.set_status("Load the whole message with L before replying".to_string());
+        return Ok(());
+    }
+    let draft = compose::reply_draft(email, body, app.user_email().unwrap_or_default(), all);
+    let path = match compose::draft_path() {
+        Ok(path) => path,
+        Err(e) => {
+            ui_state.set_alert(format!("Reply failed: {:#}", e));
+            return Ok(());
+        }
+    };
+    match with_terminal_suspended(terminal, || compose::edit_draft(&draft, &path))? {
+        Ok(Some(draft)) => {
+            ui_state.set_confirm(ConfirmAction::SendReply {
+                to: draft.recipients_summary(),
+                subject: draft.subject.clone(),
+            });
+            *pending_reply = Some(draft);
+        }
+        Ok(None) => {
+            ui_state.set_status("Reply cancelled: the draft was left unchanged".to_string())
+        }
+        Err(e) => ui_state.set_alert(format!("Reply failed: {:#}", e)),
+    }
+    Ok(())
+}
+
+/// Handles a key typed into the `/` filter bar. Edits filter the view as they're typed,
+/// Tab takes the suggested completion and Up/Down step through earlier filters.
+fn handle_filter_input(app: &mut App, ui_state: &mut UiState, key: KeyCode) {
+    match key {
+        KeyCode::Esc => {
+            // Revert to previous filter and exit input mode
+            let reverted = ui_state.revert_filter();
+            if let Some(query) = &reverted {
+                app.set_view_text_filter(Some(query.clone()));
+                ui_state.set_filter_query(query);
+            } else {
+                app.clear_view_text_filter();
+                ui_state.clear_filter_query();
+            }
+            ui_state.exit_filter_input_mode();
+            return;
+        }
+        KeyCode::Enter => {
+            // Exit input mode, keep filter active
+            ui_state.remember_filter();
+            ui_state.exit_filter_input_mode();
+            return;
+        }
+        KeyCode::Backspace => ui_state.backspace_filter(),
+        KeyCode::Tab => match ui_state.filter_completion.take() {
+            Some(completion) => ui_state.set_filter_query(&completion),
+            None => return,
+        },
+        KeyCode::Up if !ui_state.filter_history_back() => return,
+        KeyCode::Down if !ui_state.filter_history_forward() => return,
+        KeyCode::Up | KeyCode::Down => {}
+        KeyCode::Char(c) => ui_state.append_filter_char(c),
+        _ => return,
+    }
+    // Update filter in real-time
+    let query = ui_state.filter_query().to_string();
+    ui_state.filter_completion = if query.is_empty() {
+        None
+    } else {
+        app.filter_completion(&query)
+    };
+    if query.is_empty() {
+        app.clear_view_text_filter();
+    } else {
+        app.set_view_text_filter(Some(query));
+    }
+}
+
+/// Switches the email view between the plain text and the structured HTML rendering. The
+/// choice sticks for the next emails viewed.
+fn toggle_html_view(app: &mut App, ui_state: &mut UiState) {
+    let has_html = app
+        .viewing_email_id()
+        .is_some_and(|id| ui_state.html_bodies.contains_key(id));
+    if !ui_state.html_view && !has_html {
+        ui_state.set_status("This email has no HTML to render");
+        return;
+    }
+    ui_state.html_view = !ui_state.html_view;
+    app.text_view_scroll = 0;
+    ui_state.set_status(if ui_state.html_view {
+        "Showing HTML rendering (h: plain text)"
+    } else {
+        "Showing plain text (h: HTML rendering)"
+    });
+}
+
+/// Opens the prompt for where to forward the email being viewed, once its whole body
+/// has loaded
+fn start_forward(app: &App, ui_state: &mut UiState, can_send: bool) {
+    if !can_send {
+        ui_state.set_status("Emails can't be forwarded from a replayed session".to_string());
+        return;
+    }
+    let Some(email) = cursor_email(app) else {
+        return;
+    };
+    if email.body.is_none() {
+        ui_state.set_status("Wait for the message to load before forwarding".to_string());
+    } else if ui_state.truncated_bodies.contains(&email.id) {
+        ui_state.set_status("Load the whole message with L before forwarding".to_string());
+    } else {
+        ui_state.forward_input = Some(ForwardInput {
+            subject: email.subject.clone(),
+            text: String::new(),
+        });
+    }
+}
+
+/// Handles keys while the forward address is typed; Enter returns the forward to send,
+/// or keeps the prompt open with the reason the addresses were refused
+fn handle_forward_input(app: &App, ui_state: &mut UiState, key: KeyCode) -> Option<compose::Draft> {
+    let input = ui_state.forward_input.as_mut()?;
+    match key {
+        KeyCode::Esc => {
+            ui_state.forward_input = None;
+        }
+        KeyCode::Enter => {
+            let Some(email) = cursor_email(app) else {
+                ui_state.forward_input = None;
+                return None;
+            };
+            let body = email.body.as_deref().unwrap_or_default();
+            match compose::forward_draft(email, body, &input.text) {
+                Ok(draft) => {
+                    ui_state.forward_input = None;
+                    return Some(draft);
+                }
+                Err(e) => ui_state.set_alert(format!("{:#}", e)),
+            }
+        }
+        KeyCode::Backspace => {
+            input.text.pop();
+        }
+        KeyCode::Char(c) => {
+            input.text.push(c);
+        }
+        _ => {}
+    }
+    None
+}
+
+/// Sends a reply or forward on another thread, reporting the number of recipients (or
+/// the error) on `sent_tx`
+fn send_draft(
+    server: smtp::SmtpServer,
+    draft: compose::Draft,
+    outgoing: compose::Outgoing,
+    sent_tx: mpsc::Sender<compose::Sent>,
+) {
+    thread::spawn(move || {
+        let message = compose::build_message(&draft, server.from(), chrono::Utc::now());
+        let recipients = draft.recipients();
+        let result = server.send(&recipients, &message).map(|_| recipients.len());
+        let _ = sent_tx.send((outgoing, result));
+    });
+}
+
+/// Follows the List-Unsubscribe link of the email under the cursor, then asks to archive
+/// the sender's remaining inbox mail. A `mailto:` link is sent through `smtp` when given,
+/// and the archive is offered once it's gone; other links open in the browser. Demo mode
+/// only names the link.
+fn start_unsubscribe(
+    app: &App,
+    ui_state: &mut UiState,
+    demo: bool,
+    smtp: Option<(&smtp::SmtpServer, &mpsc::Sender<compose::Sent>)>,
+) {
+    let Some(email) = cursor_email(app) else {
+        return;
+    };
+    let Some(link) = email.unsubscribe_link() else {
+        ui_state.set_status("This email has no unsubscribe link".to_string());
+        return;
+    };
+    if let Some((server, sent_tx)) = smtp
+        && let Some(draft) = compose::unsubscribe_draft(link)
+    {
+        ui_state.set_busy("Sending the unsubscribe email...");
+        let sender = email.from_email.to_string();
+        send_draft(
+            server.clone(),
+            draft,
+            compose::Outgoing::Unsubscribe { sender },
+            sent_tx.clone(),
+        );
+        return;
+    }
+    if demo {
+        ui_state.set_status(format!("Demo mode: would open {}", link));
+    } else if let Err(e) = open_email_in_browser(link) {
+        ui_state.set_alert(format!("Failed to open the unsubscribe link: {}", e));
+        return;
+    }
+    let sender = email.from_email.to_string();
+    let count = app.sender_inbox_emails(&sender).len();
+    if count > 0 {
+        ui_state.set_confirm(ConfirmAction::ArchiveSender { sender, count });
+    } else if !demo {
+        ui_state.set_status(format!("Opened the unsubscribe link for {}", sender));
+    }
+}
+
+/// Starts a sweep, or asks to archive everything not marked to keep when one is underway
+fn handle_sweep(app: &mut App, ui_state: &mut UiState) {
+    let Some(marks) = app.sweep_marks() else {
+        app.start_sweep();
+        ui_state.set_status(
+            "Sweep: Space marks groups and threads to keep, W archives the rest, Esc cancels"
+                .to_string(),
+        );
+        return;
+    };
+    let (kept_groups, kept_threads) = (marks.groups.len(), marks.threads.len());
+    let count = app.sweep_emails().len();
+    if count == 0 {
+        ui_state.set_status("Nothing left to sweep; everything is kept.".to_string());
+    } else {
+        ui_state.set_confirm(ConfirmAction::ArchiveSweep {
+            count,
+            kept_groups,
+            kept_threads,
+        });
+    }
+}
+
+/// Previews how many single-message emails would be archived and asks for confirmation
+fn confirm_archive_singles(app: &App, ui_state: &mut UiState) {
+    let count = app.single_message_emails().len();
+    if count == 0 {
+        ui_state.set_status("No single-message emails to archive.".to_string());
+    } else {
+        ui_state.set_confirm(ConfirmAction::ArchiveSingles { count });
+    }
+}
+
+/// Previews how many read emails would be archived, from one group or the whole inbox,
+/// and asks for confirmation
+fn confirm_archive_read(app: &App, ui_state: &mut UiState, group: Option<String>) {
+    let count = app.read_inbox_emails(group.as_deref()).len();
+    if count == 0 {
+        ui_state.set_status("No read inbox emails to archive.".to_string());
+    } else {
+        ui_state.set_confirm(ConfirmAction::ArchiveRead { count, group });
+    }
+}
+
+/// Undo history label for archived read mail
+fn read_mail_label(group: Option<&str>) -> String {
+    match group {
+        Some(group) => format!("read mail from {}", group),
+        None => "read mail".to_string(),
+    }
+}
+
+/// Previews how many old inbox emails would be archived and asks for confirmation
+fn confirm_archive_old(app: &App, ui_state: &mut UiState) {
+    let days = app.archive_older_than_days;
+    let count = app.old_inbox_emails().len();
+    if count == 0 {
+        ui_state.set_status(format!(
+            "No inbox emails older than {} days to archive.",
+            days
+        ));
+    } else {
+        ui_state.set_confirm(ConfirmAction::ArchiveOld { count, days });
+    }
+}
+
+/// Ages offered by Z in the email list, in days; pressing Z in the dialog moves to the next
+const GROUP_AGE_PRESETS: [u32; 4] = [7, 30, 90, 365];
+
+/// Previews how many of a group's inbox emails are older than `days` and asks for
+/// confirmation, showing the cutoff date
+fn confirm_archive_group_old(app: &App, ui_state: &mut UiState, group: String, days: u32) {
+    let cutoff = chrono::Utc::now() - chrono::Duration::days(i64::from(days));
+    let count = app.group_emails_older_than(&group, cutoff).len();
+    ui_state.set_confirm(ConfirmAction::ArchiveGroupOld {
+        group,
+        count,
+        days,
+        cutoff,
+    });
+}
+
+/// Switches an open group age confirmation to the next preset age
+fn cycle_group_age(app: &App, ui_state: &mut UiState) {
+    if let Some(ConfirmAction::ArchiveGroupOld { group, days, .. }) = ui_state.confirm_action.take()
+    {
+        let next = GROUP_AGE_PRESETS
+            .into_iter()
+            .find(|&d| d > days)
+            .unwrap_or(GROUP_AGE_PRESETS[0]);
+        confirm_archive_group_old(app, ui_state, group, next);
+    }
 }
 
-/// Handles the 'a' key - archive thread (not available in thread view)
-fn handle_archive(
-    app: &mut App,
-    cmd_tx: &mpsc::Sender<ImapCommand>,
-    ui_state: &mut UiState,
-    pending_operation: &mut Option<PendingOp>,
-) -> Result<()> {
-    match app.view {
-        View::GroupList | View::UndoHistory | View::EmailBody => {
-            // No action on single 'a' in group list, undo history, or text view
-        }
-        View::EmailList => {
-            // Check if there are visible selected emails - require confirmation
-            if app.has_visible_selection() {
-                let count = app.selected_thread_email_ids().len();
-                if count > 0 {
-                    ui_state.set_confirm(ConfirmAction::ArchiveSelected { count });
-                }
-                return Ok(());
-            }
+/// Undo history label for archived old mail
+fn old_mail_label(days: u32, group: Option<&str>) -> String {
+    match group {
+        Some(group) => format!("mail from {} older than {} days", group, days),
+        None => format!("mail older than {} days", days),
+    }
+}
 
-            // No visible selection - archive the entire thread
-            let email_ids = app.current_thread_email_ids();
-            let emails_for_undo = app.current_thread_emails_for_undo();
-            let thread_id = app.current_email().map(|e| e.thread_id.clone());
-            let subject = app.current_email().map(|e| e.subject.clone());
-            if !email_ids.is_empty()
-                && let Some(tid) = thread_id
-                && let Some(subj) = subject
-            {
-                ui_state.set_busy(format!("Archiving thread ({} emails)...", email_ids.len()));
-                *pending_operation = Some(PendingOp::ArchiveThread {
-                    thread_id: tid,
-                    subject: subj,
-                    emails: emails_for_undo,
-                });
-                cmd_tx.send(ImapCommand::ArchiveMultiple(email_ids))?;
-            }
-        }
-        View::Thread => {
-            // No lowercase 'a' in thread view - use 'A' to archive entire thread
-        }
+/// Asks to confirm archiving/deleting the extra copies in the duplicates view
+fn confirm_duplicates_action(app: &App, ui_state: &mut UiState, archive: bool, all_sets: bool) {
+    let count = app.duplicate_extras(all_sets).len();
+    if count == 0 {
+        return;
     }
-    Ok(())
+    ui_state.set_confirm(if archive {
+        ConfirmAction::ArchiveDuplicates { count, all_sets }
+    } else {
+        ConfirmAction::DeleteDuplicates { count, all_sets }
+    });
 }
 
 /// Handles the 'A' key - archive all threads in group
 fn handle_archive_all(app: &App, ui_state: &mut UiState) {
     match app.view {
-        View::GroupList | View::UndoHistory => {
-            // No 'A' in group list view or undo history to prevent accidental bulk operations
+        View::GroupList => {
+            // Only selected groups; a bare 'A' in the group list would be too easy to hit by accident
+            confirm_selected_groups(app, ui_state, PolicyAction::Archive);
+        }
+        View::UndoHistory | View::Duplicates => {
+            // No 'A' in undo history or duplicates to prevent accidental bulk operations
         }
         View::EmailList => {
             // Archive all threads touched by this group's visible emails
@@ -2667,7 +7265,7 @@ fn handle_delete(
     pending_operation: &mut Option<PendingOp>,
 ) -> Result<()> {
     match app.view {
-        View::GroupList | View::UndoHistory | View::EmailBody => {
+        View::GroupList | View::UndoHistory | View::Duplicates | View::EmailBody => {
             // No action on single 'd' in group list, undo history, or text view
         }
         View::EmailList => {
@@ -2682,6 +7280,14 @@ fn handle_delete(
 
             // No visible selection - delete the entire thread
             let email_ids = app.current_thread_email_ids();
+            if app.is_mailbox_changed() && !email_ids.is_empty() {
+                ui_state.set_confirm(ConfirmAction::Resync {
+                    then: Box::new(ConfirmAction::DeleteThread {
+                        thread_email_count: email_ids.len(),
+                    }),
+                });
+                return Ok(());
+            }
             let emails_for_undo = app.current_thread_emails_for_undo();
             let thread_id = app.current_email().map(|e| e.thread_id.clone());
             let subject = app.current_email().map(|e| e.subject.clone());
@@ -2708,62 +7314,613 @@ fn handle_delete(
 /// Handles the 'D' key - delete all threads in group
 fn handle_delete_all(app: &App, ui_state: &mut UiState) {
     match app.view {
-        View::GroupList | View::UndoHistory => {
-            // No 'D' in group list view or undo history to prevent accidental bulk operations
+        View::GroupList => {
+            // Only selected groups; a bare 'D' in the group list would be too easy to hit by accident
+            confirm_selected_groups(app, ui_state, PolicyAction::Delete);
+        }
+        View::UndoHistory | View::Duplicates => {
+            // No 'D' in undo history or duplicates to prevent accidental bulk operations
+        }
+        View::EmailList => {
+            // Delete all threads touched by this group's visible emails
+            if let Some(group) = app.current_group() {
+                ui_state.set_confirm(ConfirmAction::DeleteEmails {
+                    sender: group.key.clone(),
+                    count: app.current_group_thread_email_ids().len(),
+                    filtered: app.has_view_text_filter(),
+                });
+            }
+        }
+        View::Thread | View::EmailBody => {
+            // In thread/email body view, 'D' deletes the thread
+            let thread_count = app.current_thread_emails().len();
+            if thread_count > 0 {
+                ui_state.set_confirm(ConfirmAction::DeleteThread {
+                    thread_email_count: thread_count,
+                });
+            }
+        }
+    }
+}
+
+/// Asks to archive or delete every thread touched by the selected groups
+fn confirm_selected_groups(app: &App, ui_state: &mut UiState, action: PolicyAction) {
+    let groups = app.selected_group_keys().len();
+    if groups == 0 {
+        ui_state.set_status("Select groups with Space first".to_string());
+        return;
+    }
+    let count = app.selected_groups_thread_email_ids().len();
+    ui_state.set_confirm(match action {
+        PolicyAction::Archive => ConfirmAction::ArchiveGroups { groups, count },
+        PolicyAction::Delete => ConfirmAction::DeleteGroups { groups, count },
+    });
+}
+
+/// Opens an email's webmail link (see Backend::web_link) in the browser
+///
+/// The link searches for the email by Message-ID.
+/// This is the safest approach as it avoids rendering potentially
+/// malicious content (unicode exploits, terminal escape sequences) directly in the terminal.
+fn open_email_in_browser(url: &str) -> Result<()> {
+    // Use platform-specific command to open URL in default browser
+    #[cfg(target_os = "macos")]
+    {
+        Command::new("open").arg(url).spawn()?;
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        Command::new("xdg-open").arg(url).spawn()?;
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        Command::new("cmd").args(["/C", "start", url]).spawn()?;
+    }
+
+    Ok(())
+}
+
+/// The emails M changes and whether it marks them read: unread when every one of them
+/// is read already, read otherwise
+fn seen_change(app: &App) -> Option<(Vec<(String, String)>, bool)> {
+    let targets = app.seen_targets();
+    if targets.is_empty() {
+        return None;
+    }
+    let seen = targets.iter().any(|e| !e.seen);
+    let emails = targets
+        .iter()
+        .map(|e| (e.id.clone(), e.source_folder.to_string()))
+        .collect();
+    Some((emails, seen))
+}
+
+/// Marks the email under the cursor, its thread or the selected threads read or
+/// unread on the server (M)
+fn handle_toggle_seen(
+    app: &App,
+    cmd_tx: &mpsc::Sender<ImapCommand>,
+    ui_state: &mut UiState,
+) -> Result<()> {
+    let Some((emails, seen)) = seen_change(app) else {
+        return Ok(());
+    };
+    ui_state.set_busy(format!(
+        "Marking {} email(s) {}...",
+        emails.len(),
+        if seen { "read" } else { "unread" }
+    ));
+    cmd_tx.send(ImapCommand::SetFlags { emails, seen })?;
+    Ok(())
+}
+
+/// Opens the folder picker for moving (v), labelling and archiving (F) or labelling (+)
+/// the current emails, asking the server for its folders the first time
+fn handle_move(
+    app: &App,
+    cmd_tx: &mpsc::Sender<ImapCommand>,
+    ui_state: &mut UiState,
+    folders: Option<&[String]>,
+    target: PickerTarget,
+) -> Result<()> {
+    if app.move_targets().1.is_empty() {
+        return Ok(());
+    }
+    match folders {
+        Some(folders) => open_folder_picker(ui_state, folders.to_vec(), target),
+        None => {
+            ui_state.set_busy("Loading folders...");
+            cmd_tx.send(ImapCommand::ListFolders)?;
+        }
+    }
+    Ok(())
+}
+
+/// Builds the restore ops for an undo entry: (message_id, dest_uid, current_folder,
+/// dest_folder), with `dest` replacing the folders the emails came from when set
+fn restore_ops(
+    entry: &UndoEntry,
+    dest: Option<&str>,
+) -> Vec<(Option<String>, Option<u32>, String, String)> {
+    entry
+        .emails
+        .iter()
+        .map(|(message_id, dest_uid, orig_folder)| {
+            (
+                message_id.clone(),
+                *dest_uid,
+                entry.current_folder.clone(),
+                dest.unwrap_or(orig_folder).to_string(),
+            )
+        })
+        .collect()
+}
+
+/// Asks to undo every action from the newest down to the selected one
+fn confirm_undo_recent(app: &App, ui_state: &mut UiState) {
+    let entries = app.recent_undo_entries();
+    if entries.is_empty() {
+        return;
+    }
+    ui_state.set_confirm(ConfirmAction::UndoRecent {
+        actions: entries.len(),
+        count: entries.iter().map(|entry| entry.emails.len()).sum(),
+    });
+}
+
+/// Undoes the selected undo entry, restoring its emails to `dest` when set instead of the
+/// folders they came from
+fn start_restore(
+    app: &App,
+    cmd_tx: &mpsc::Sender<ImapCommand>,
+    ui_state: &mut UiState,
+    pending_operation: &mut Option<PendingOp>,
+    dest: Option<&str>,
+) -> Result<()> {
+    let Some(entry) = app.current_undo_entry() else {
+        return Ok(());
+    };
+    let restore_ops = restore_ops(entry, dest);
+    if restore_ops.is_empty() {
+        return Ok(());
+    }
+    ui_state.set_busy(match dest {
+        Some(dest) => format!("Restoring {} email(s) to {}...", restore_ops.len(), dest),
+        None => format!("Restoring {} email(s)...", restore_ops.len()),
+    });
+    *pending_operation = Some(PendingOp::Undo(app.selected_undo));
+    cmd_tx.send(ImapCommand::RestoreEmails(restore_ops))?;
+    Ok(())
+}
+
+/// Opens the picker of folders to move the current emails (or restore an undo entry's
+/// emails) to, or of labels to add
+fn open_folder_picker(ui_state: &mut UiState, folders: Vec<String>, target: PickerTarget) {
+    let (title, folders) = match target {
+        PickerTarget::MoveFolder { archive: true } => ("Label and archive", folders),
+        PickerTarget::AddLabel => ("Add label", label_choices(folders)),
+        PickerTarget::RestoreFolder => ("Restore to", folders),
+        _ => ("Move to", folders),
+    };
+    if folders.is_empty() {
+        ui_state.set_status("No folders to choose from");
+    } else {
+        ui_state.picker = Some(Picker::new(target, title.to_string(), folders));
+    }
+}
+
+/// The folders that can be added as labels: all but the inbox and Gmail's system folders
+fn label_choices(folders: Vec<String>) -> Vec<String> {
+    folders
+        .into_iter()
+        .filter(|f| f != "INBOX" && !f.starts_with("[Gmail]"))
+        .collect()
+}
+
+/// Opens the picker of labels to add (+) or of the current emails' labels to remove (-)
+fn handle_label(
+    app: &App,
+    cmd_tx: &mpsc::Sender<ImapCommand>,
+    ui_state: &mut UiState,
+    backend: &config::Backend,
+    folders: Option<&[String]>,
+    add: bool,
+) -> Result<()> {
+    if !backend.has_labels() {
+        ui_state.set_status("Labels are only available on Gmail accounts".to_string());
+        return Ok(());
+    }
+    if add {
+        handle_move(app, cmd_tx, ui_state, folders, PickerTarget::AddLabel)
+    } else {
+        open_remove_label_picker(app, ui_state);
+        Ok(())
+    }
+}
+
+/// Opens the picker of the labels on the current emails, to remove one (-)
+fn open_remove_label_picker(app: &App, ui_state: &mut UiState) {
+    let mut labels: Vec<String> = app
+        .move_targets()
+        .1
+        .iter()
+        .flat_map(|e| e.labels.iter().cloned())
+        .collect();
+    labels.sort();
+    labels.dedup();
+    if labels.is_empty() {
+        ui_state.set_status("No labels to remove");
+    } else {
+        ui_state.picker = Some(Picker::new(
+            PickerTarget::RemoveLabel,
+            "Remove label".to_string(),
+            labels,
+        ));
+    }
+}
+
+/// Adds the label chosen in the picker to the current emails, or removes it
+fn start_label(
+    app: &App,
+    cmd_tx: &mpsc::Sender<ImapCommand>,
+    ui_state: &mut UiState,
+    label: &str,
+    add: bool,
+) -> Result<()> {
+    let emails: Vec<(String, String)> = app
+        .move_targets()
+        .1
+        .iter()
+        .map(|e| (e.id.clone(), e.source_folder.to_string()))
+        .collect();
+    if emails.is_empty() {
+        return Ok(());
+    }
+    let labels = vec![label.to_string()];
+    let (add, remove) = if add {
+        ui_state.set_busy(format!("Labelling {} email(s) {}...", emails.len(), label));
+        (labels, Vec::new())
+    } else {
+        ui_state.set_busy(format!(
+            "Removing {} from {} email(s)...",
+            label,
+            emails.len()
+        ));
+        (Vec::new(), labels)
+    };
+    cmd_tx.send(ImapCommand::ModifyLabels {
+        emails,
+        add,
+        remove,
+    })?;
+    Ok(())
+}
+
+/// The status line for labels added to or removed from `count` emails
+fn labels_status(count: usize, add: &[String], remove: &[String]) -> String {
+    if add.is_empty() {
+        format!("Removed {} from {} email(s)", remove.join(", "), count)
+    } else {
+        format!("Labelled {} email(s) {}", count, add.join(", "))
+    }
+}
+
+/// Moves the current emails to the folder chosen in the picker, keeping them for undo.
+/// A move that archives counts toward the archived mail.
+fn start_move(
+    app: &App,
+    cmd_tx: &mpsc::Sender<ImapCommand>,
+    ui_state: &mut UiState,
+    pending_operation: &mut Option<PendingOp>,
+    dest: &str,
+    archive: bool,
+) -> Result<()> {
+    let (context, emails) = app.move_targets();
+    if emails.is_empty() {
+        return Ok(());
+    }
+    let ids: Vec<(String, String)> = emails
+        .iter()
+        .map(|e| (e.id.clone(), e.source_folder.to_string()))
+        .collect();
+    let emails = emails
+        .iter()
+        .map(|e| {
+            (
+                e.id.clone(),
+                e.message_id.clone(),
+                e.source_folder.to_string(),
+            )
+        })
+        .collect();
+    let verb = if archive { "Filing" } else { "Moving" };
+    ui_state.set_busy(format!("{} {} email(s) in {}...", verb, ids.len(), dest));
+    *pending_operation = Some(PendingOp::Move {
+        dest: dest.to_string(),
+        context,
+        emails,
+        archive,
+    });
+    cmd_tx.send(ImapCommand::MoveMultiple {
+        emails: ids,
+        dest: dest.to_string(),
+        archive,
+    })?;
+    Ok(())
+}
+
+/// Opens the whole conversation under the cursor in the backend's webmail (E)
+fn open_thread_in_browser(
+    app: &App,
+    ui_state: &mut UiState,
+    backend: &config::Backend,
+    user_email: &str,
+) {
+    let thread = app.current_thread_emails();
+    if thread.is_empty() {
+        return;
+    }
+    if matches!(backend, config::Backend::Outlook | config::Backend::Jmap) {
+        ui_state.set_status("Only Gmail opens whole threads; e opens the email".to_string());
+        return;
+    }
+    let Some(url) = backend.thread_web_link(&thread, user_email) else {
+        ui_state.set_status("Thread has no Message-IDs".to_string());
+        return;
+    };
+    if let Err(e) = open_email_in_browser(&url) {
+        ui_state.set_alert(format!("Failed to open browser: {}", e));
+    }
+}
+
+/// Restores the position saved at the end of the last session and loads the
+/// body if it reopens an email
+fn resume_session(
+    app: &mut App,
+    ui_state: &mut UiState,
+    cmd_tx: &mpsc::Sender<ImapCommand>,
+    state: &resume::ResumeState,
+    max_bytes: Option<u32>,
+    mark_seen: bool,
+) -> Result<()> {
+    if state.apply(app) {
+        ui_state.group_scroll_offset = state.group_scroll_offset;
+        ui_state.set_status("Resumed where you left off".to_string());
+    } else {
+        ui_state.set_status("Resumed as close as possible; some of that mail is gone".to_string());
+    }
+    if app.view == View::EmailBody
+        && let Some(email) = app.viewing_email()
+    {
+        if let Some(body) = &email.body {
+            ui_state.text_view_state = TextViewState::Loaded(body.clone());
+        } else {
+            ui_state.text_view_state = TextViewState::Loading;
+            cmd_tx.send(ImapCommand::FetchBody {
+                uid: email.id.clone(),
+                folder: email.source_folder.to_string(),
+                max_bytes,
+                mark_seen,
+            })?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns a random number, seeded per call by the standard library's hasher keys
+fn random_roll() -> u64 {
+    use std::hash::{BuildHasher, Hasher};
+    std::collections::hash_map::RandomState::new()
+        .build_hasher()
+        .finish()
+}
+
+/// Returns the email under the cursor in the email list, thread and email body views
+fn cursor_email(app: &App) -> Option<&Email> {
+    match app.view {
+        View::Thread => app.current_thread_email(),
+        View::EmailList => app.current_email(),
+        View::EmailBody => app.viewing_email(),
+        _ => None,
+    }
+}
+
+/// The email and group under the cursor, for `[[action]]` scripts and `[[plugin]]`s
+fn script_context(app: &App) -> script::Context<'_> {
+    script::Context {
+        view: app.view,
+        email: cursor_email(app),
+        group: app.current_group().map(|g| (g.key.as_str(), g.count())),
+    }
+}
+
+/// Carries out one operation asked for by a script or plugin. Returns false once an
+/// archive/delete has started or a confirmation is up, when the rest must wait.
+fn run_operation(
+    app: &mut App,
+    cmd_tx: &mpsc::Sender<ImapCommand>,
+    ui_state: &mut UiState,
+    pending_operation: &mut Option<PendingOp>,
+    operation: script::Operation,
+) -> Result<bool> {
+    match operation {
+        script::Operation::Archive => {
+            handle_archive(app, cmd_tx, ui_state, pending_operation)?;
+        }
+        script::Operation::Delete => {
+            handle_delete(app, cmd_tx, ui_state, pending_operation)?;
+        }
+        script::Operation::ArchiveGroup => handle_archive_all(app, ui_state),
+        script::Operation::DeleteGroup => handle_delete_all(app, ui_state),
+        script::Operation::Filter(query) => match app.view {
+            View::GroupList => app.set_group_text_filter(Some(query)),
+            View::EmailList => app.set_email_text_filter(Some(query)),
+            _ => {}
+        },
+        script::Operation::Status(message) => ui_state.set_status(message),
+    }
+    Ok(!ui_state.is_busy() && !ui_state.is_confirming())
+}
+
+/// Runs an `[[action]]` script and carries out the operations it asked for, stopping
+/// after the first one that starts an archive/delete or asks for confirmation
+fn run_custom_action(
+    app: &mut App,
+    cmd_tx: &mpsc::Sender<ImapCommand>,
+    ui_state: &mut UiState,
+    pending_operation: &mut Option<PendingOp>,
+    action: &script::CustomAction,
+) -> Result<()> {
+    let operations = match script::run(&action.script, &script_context(app)) {
+        Ok(operations) => operations,
+        Err(e) => {
+            ui_state.set_alert(format!("{} failed: {:#}", action.label(), e));
+            return Ok(());
+        }
+    };
+    for operation in operations {
+        if !run_operation(app, cmd_tx, ui_state, pending_operation, operation)? {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Starts a `[[plugin]]` (again with the user's pick when `choice` is set); its replies
+/// come back on `plugin_tx` while input waits
+fn start_plugin(
+    app: &App,
+    ui_state: &mut UiState,
+    plugin: &plugin::Plugin,
+    choice: Option<&str>,
+    plugin_tx: &mpsc::Sender<plugin::Done>,
+) {
+    ui_state.set_busy(format!("Running {}...", plugin.label()));
+    plugin::spawn(plugin, &script_context(app), choice, plugin_tx.clone());
+}
+
+/// Carries out a finished plugin's replies like an action's operations; a picker reply
+/// opens the picker and stops
+fn finish_plugin(
+    app: &mut App,
+    cmd_tx: &mpsc::Sender<ImapCommand>,
+    ui_state: &mut UiState,
+    pending_operation: &mut Option<PendingOp>,
+    plugin: &plugin::Plugin,
+    result: Result<Vec<plugin::Reply>>,
+) -> Result<()> {
+    let replies = match result {
+        Ok(replies) => replies,
+        Err(e) => {
+            ui_state.set_alert(format!("{} failed: {:#}", plugin.label(), e));
+            return Ok(());
         }
-        View::EmailList => {
-            // Delete all threads touched by this group's visible emails
-            if let Some(group) = app.current_group() {
-                ui_state.set_confirm(ConfirmAction::DeleteEmails {
-                    sender: group.key.clone(),
-                    count: app.current_group_thread_email_ids().len(),
-                    filtered: app.has_view_text_filter(),
-                });
+    };
+    for reply in replies {
+        match reply {
+            plugin::Reply::Run(operation) => {
+                if !run_operation(app, cmd_tx, ui_state, pending_operation, operation)? {
+                    break;
+                }
             }
-        }
-        View::Thread | View::EmailBody => {
-            // In thread/email body view, 'D' deletes the thread
-            let thread_count = app.current_thread_emails().len();
-            if thread_count > 0 {
-                ui_state.set_confirm(ConfirmAction::DeleteThread {
-                    thread_email_count: thread_count,
-                });
+            plugin::Reply::Picker { title, items } => {
+                if !items.is_empty() {
+                    ui_state.picker =
+                        Some(Picker::new(PickerTarget::Plugin(plugin.key), title, items));
+                }
+                break;
             }
         }
     }
+    Ok(())
 }
 
-/// Opens an email in the browser using Gmail's Message-ID search
-///
-/// Uses the rfc822msgid: search operator to find the specific email.
-/// This is the safest approach as it avoids rendering potentially
-/// malicious content (unicode exploits, terminal escape sequences) directly in the terminal.
-fn open_email_in_browser(message_id: &str, user_email: &str) -> Result<()> {
-    // URL-encode the message ID for safe inclusion in URL
-    // Message-IDs typically look like <unique-id@domain.com>
-    let encoded = urlencoding::encode(message_id);
-    // Use the user's email in the URL path so Gmail opens the correct account
-    let url = format!(
-        "https://mail.google.com/mail/u/{}/#search/rfc822msgid:{}",
-        user_email, encoded
-    );
-
-    // Use platform-specific command to open URL in default browser
-    #[cfg(target_os = "macos")]
-    {
-        Command::new("open").arg(&url).spawn()?;
+/// Handles the 'C' key - appends the email under the cursor to capture_file
+fn capture_to_notes_file(
+    app: &App,
+    ui_state: &mut UiState,
+    cfg: &config::Config,
+    backend: &config::Backend,
+    user_email: &str,
+) {
+    let Some(email) = cursor_email(app) else {
+        return;
+    };
+    let Some(path) = &cfg.capture_file else {
+        ui_state.set_status("Set capture_file in config.toml to capture emails".to_string());
+        return;
+    };
+    let path = capture::expand_home(path);
+    let link = email
+        .message_id
+        .as_deref()
+        .map(|id| backend.web_link(id, user_email));
+    match capture::capture_email(&path, email, link.as_deref(), cfg.capture_excerpt_lines) {
+        Ok(()) => ui_state.set_status(format!("Captured to {}", path.display())),
+        Err(e) => ui_state.set_alert(format!("Failed to capture email: {:#}", e)),
     }
+}
 
-    #[cfg(target_os = "linux")]
-    {
-        Command::new("xdg-open").arg(&url).spawn()?;
+/// Handles the 'P' key - prints the email under the cursor once its body is loaded
+fn print_cursor_email(app: &App, ui_state: &mut UiState, cfg: &config::Config) {
+    let Some(email) = cursor_email(app) else {
+        return;
+    };
+    let Some(body) = &email.body else {
+        ui_state.set_status("Open the email to load its body before printing".to_string());
+        return;
+    };
+    let truncated = ui_state.truncated_bodies.contains(&email.id);
+    match print::print_email(&cfg.print_command, email, body) {
+        Ok(()) if truncated => ui_state.set_status(format!(
+            "Sent to {} (truncated, press L to load the full message first)",
+            cfg.print_command
+        )),
+        Ok(()) => ui_state.set_status(format!("Sent to {}", cfg.print_command)),
+        Err(e) => ui_state.set_alert(format!("Failed to print: {:#}", e)),
     }
+}
 
-    #[cfg(target_os = "windows")]
-    {
-        Command::new("cmd").args(["/C", "start", &url]).spawn()?;
+/// Handles the 'T' key - runs task_command for the email under the cursor, then
+/// archives its thread when archive_after_task is set
+fn create_task_from_email(
+    app: &mut App,
+    cmd_tx: &mpsc::Sender<ImapCommand>,
+    ui_state: &mut UiState,
+    pending_operation: &mut Option<PendingOp>,
+    cfg: &config::Config,
+    backend: &config::Backend,
+    user_email: &str,
+) -> Result<()> {
+    let Some(email) = cursor_email(app) else {
+        return Ok(());
+    };
+    let Some(template) = &cfg.task_command else {
+        ui_state.set_status("Set task_command in config.toml to create tasks".to_string());
+        return Ok(());
+    };
+    let link = email
+        .message_id
+        .as_deref()
+        .map(|id| backend.web_link(id, user_email))
+        .unwrap_or_default();
+    if let Err(e) = task::create_task(template, email, &link) {
+        ui_state.set_alert(format!("Failed to create task: {:#}", e));
+        return Ok(());
+    }
+    ui_state.set_status(format!("Created task: {}", email.subject));
+    let thread_email_count = app.current_thread_emails().len();
+    if cfg.archive_after_task && thread_email_count > 0 {
+        handle_confirmed_action(
+            app,
+            cmd_tx,
+            ui_state,
+            pending_operation,
+            ConfirmAction::ArchiveThread { thread_email_count },
+        )?;
     }
-
     Ok(())
 }
 
@@ -2873,7 +8030,272 @@ fn handle_confirmed_action(
                 cmd_tx.send(ImapCommand::DeleteMultiple(email_ids))?;
             }
         }
-        ConfirmAction::Quit => {
+        ConfirmAction::ArchiveDuplicates { count, all_sets } => {
+            // Archive every copy except the one kept in each set
+            let extras = app.duplicate_extras(all_sets);
+            let email_ids: Vec<(String, String)> = extras
+                .iter()
+                .map(|e| (e.id.clone(), e.source_folder.to_string()))
+                .collect();
+            let emails_for_undo = extras
+                .iter()
+                .map(|e| {
+                    (
+                        e.id.clone(),
+                        e.message_id.clone(),
+                        e.source_folder.to_string(),
+                    )
+                })
+                .collect();
+            if !email_ids.is_empty() {
+                ui_state.set_busy(format!("Archiving {} duplicates...", email_ids.len()));
+                *pending_operation = Some(PendingOp::ArchiveDuplicates {
+                    count,
+                    emails: emails_for_undo,
+                });
+                cmd_tx.send(ImapCommand::ArchiveMultiple(email_ids))?;
+            }
+        }
+        ConfirmAction::DeleteDuplicates { count, all_sets } => {
+            // Delete every copy except the one kept in each set
+            let extras = app.duplicate_extras(all_sets);
+            let email_ids: Vec<(String, String)> = extras
+                .iter()
+                .map(|e| (e.id.clone(), e.source_folder.to_string()))
+                .collect();
+            let emails_for_undo = extras
+                .iter()
+                .map(|e| {
+                    (
+                        e.id.clone(),
+                        e.message_id.clone(),
+                        e.source_folder.to_string(),
+                    )
+                })
+                .collect();
+            if !email_ids.is_empty() {
+                ui_state.set_busy(format!("Deleting {} duplicates...", email_ids.len()));
+                *pending_operation = Some(PendingOp::DeleteDuplicates {
+                    count,
+                    emails: emails_for_undo,
+                });
+                cmd_tx.send(ImapCommand::DeleteMultiple(email_ids))?;
+            }
+        }
+        ConfirmAction::ArchiveOld { days, .. } => {
+            let old_emails = app.old_inbox_emails();
+            let email_ids: Vec<(String, String)> = old_emails
+                .iter()
+                .map(|e| (e.id.clone(), e.source_folder.to_string()))
+                .collect();
+            let emails_for_undo = old_emails
+                .iter()
+                .map(|e| {
+                    (
+                        e.id.clone(),
+                        e.message_id.clone(),
+                        e.source_folder.to_string(),
+                    )
+                })
+                .collect();
+            if !email_ids.is_empty() {
+                ui_state.set_busy(format!("Archiving {} old emails...", email_ids.len()));
+                *pending_operation = Some(PendingOp::ArchiveOld {
+                    days,
+                    group: None,
+                    emails: emails_for_undo,
+                });
+                cmd_tx.send(ImapCommand::ArchiveMultiple(email_ids))?;
+            }
+        }
+        ConfirmAction::ArchiveGroupOld {
+            group,
+            days,
+            cutoff,
+            ..
+        } => {
+            let old_emails = app.group_emails_older_than(&group, cutoff);
+            let email_ids: Vec<(String, String)> = old_emails
+                .iter()
+                .map(|e| (e.id.clone(), e.source_folder.to_string()))
+                .collect();
+            let emails_for_undo = old_emails
+                .iter()
+                .map(|e| {
+                    (
+                        e.id.clone(),
+                        e.message_id.clone(),
+                        e.source_folder.to_string(),
+                    )
+                })
+                .collect();
+            if !email_ids.is_empty() {
+                ui_state.set_busy(format!(
+                    "Archiving {} emails older than {} days...",
+                    email_ids.len(),
+                    days
+                ));
+                *pending_operation = Some(PendingOp::ArchiveOld {
+                    days,
+                    group: Some(group),
+                    emails: emails_for_undo,
+                });
+                cmd_tx.send(ImapCommand::ArchiveMultiple(email_ids))?;
+            }
+        }
+        ConfirmAction::ArchiveSingles { .. } => {
+            let singles = app.single_message_emails();
+            let email_ids: Vec<(String, String)> = singles
+                .iter()
+                .map(|e| (e.id.clone(), e.source_folder.to_string()))
+                .collect();
+            let emails_for_undo = singles
+                .iter()
+                .map(|e| {
+                    (
+                        e.id.clone(),
+                        e.message_id.clone(),
+                        e.source_folder.to_string(),
+                    )
+                })
+                .collect();
+            if !email_ids.is_empty() {
+                ui_state.set_busy(format!(
+                    "Archiving {} single-message emails...",
+                    email_ids.len()
+                ));
+                *pending_operation = Some(PendingOp::ArchiveSingles {
+                    emails: emails_for_undo,
+                });
+                cmd_tx.send(ImapCommand::ArchiveMultiple(email_ids))?;
+            }
+        }
+        ConfirmAction::ArchiveRead { group, .. } => {
+            let read = app.read_inbox_emails(group.as_deref());
+            let email_ids: Vec<(String, String)> = read
+                .iter()
+                .map(|e| (e.id.clone(), e.source_folder.to_string()))
+                .collect();
+            let emails_for_undo = read
+                .iter()
+                .map(|e| {
+                    (
+                        e.id.clone(),
+                        e.message_id.clone(),
+                        e.source_folder.to_string(),
+                    )
+                })
+                .collect();
+            if !email_ids.is_empty() {
+                ui_state.set_busy(format!("Archiving {} read emails...", email_ids.len()));
+                *pending_operation = Some(PendingOp::ArchiveRead {
+                    group,
+                    emails: emails_for_undo,
+                });
+                cmd_tx.send(ImapCommand::ArchiveMultiple(email_ids))?;
+            }
+        }
+        ConfirmAction::ArchiveSender { sender, .. } => {
+            let emails = app.sender_inbox_emails(&sender);
+            let email_ids: Vec<(String, String)> = emails
+                .iter()
+                .map(|e| (e.id.clone(), e.source_folder.to_string()))
+                .collect();
+            let emails_for_undo = emails
+                .iter()
+                .map(|e| {
+                    (
+                        e.id.clone(),
+                        e.message_id.clone(),
+                        e.source_folder.to_string(),
+                    )
+                })
+                .collect();
+            if !email_ids.is_empty() {
+                ui_state.set_busy(format!(
+                    "Archiving {} emails from {}...",
+                    email_ids.len(),
+                    sender
+                ));
+                *pending_operation = Some(PendingOp::ArchiveSender {
+                    sender,
+                    emails: emails_for_undo,
+                });
+                cmd_tx.send(ImapCommand::ArchiveMultiple(email_ids))?;
+            }
+        }
+        ConfirmAction::ArchiveSweep { .. } => {
+            let swept = app.sweep_emails();
+            let email_ids: Vec<(String, String)> = swept
+                .iter()
+                .map(|e| (e.id.clone(), e.source_folder.to_string()))
+                .collect();
+            let emails_for_undo = swept
+                .iter()
+                .map(|e| {
+                    (
+                        e.id.clone(),
+                        e.message_id.clone(),
+                        e.source_folder.to_string(),
+                    )
+                })
+                .collect();
+            if !email_ids.is_empty() {
+                ui_state.set_busy(format!("Sweeping {} emails...", email_ids.len()));
+                *pending_operation = Some(PendingOp::ArchiveSweep {
+                    emails: emails_for_undo,
+                });
+                cmd_tx.send(ImapCommand::ArchiveMultiple(email_ids))?;
+            }
+        }
+        ConfirmAction::ArchiveGroups { .. } => {
+            // Archive all threads touched by the selected groups' emails
+            let email_ids = app.selected_groups_thread_email_ids();
+            let emails_for_undo = app.selected_groups_thread_emails_for_undo();
+            if !email_ids.is_empty() {
+                ui_state.set_busy(format!("Archiving {} emails...", email_ids.len()));
+                *pending_operation = Some(PendingOp::ArchiveGroups {
+                    senders: app.selected_group_keys(),
+                    emails: emails_for_undo,
+                });
+                cmd_tx.send(ImapCommand::ArchiveMultiple(email_ids))?;
+            }
+        }
+        ConfirmAction::DeleteGroups { .. } => {
+            // Delete all threads touched by the selected groups' emails
+            let email_ids = app.selected_groups_thread_email_ids();
+            let emails_for_undo = app.selected_groups_thread_emails_for_undo();
+            if !email_ids.is_empty() {
+                ui_state.set_busy(format!("Deleting {} emails...", email_ids.len()));
+                *pending_operation = Some(PendingOp::DeleteGroups {
+                    senders: app.selected_group_keys(),
+                    emails: emails_for_undo,
+                });
+                cmd_tx.send(ImapCommand::DeleteMultiple(email_ids))?;
+            }
+        }
+        ConfirmAction::UndoRecent { .. } => {
+            let entries = app.recent_undo_entries();
+            let restore_ops: Vec<_> = entries
+                .iter()
+                .flat_map(|entry| restore_ops(entry, None))
+                .collect();
+            if !restore_ops.is_empty() {
+                ui_state.set_busy(format!(
+                    "Restoring {} email(s) from {} action(s)...",
+                    restore_ops.len(),
+                    entries.len()
+                ));
+                *pending_operation = Some(PendingOp::UndoRecent(entries.len()));
+                cmd_tx.send(ImapCommand::RestoreEmails(restore_ops))?;
+            }
+        }
+        ConfirmAction::Quit
+        | ConfirmAction::ApplyPolicies { .. }
+        | ConfirmAction::SendReply { .. }
+        | ConfirmAction::RetryFetch { .. }
+        | ConfirmAction::Resync { .. }
+        | ConfirmAction::IgnoreSender { .. } => {
             // Handled before calling this function
             unreachable!()
         }
@@ -2897,6 +8319,209 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_bulk_summary_covers_bulk_ops_only() {
+        let emails = vec![
+            ("1".to_string(), None, "INBOX".to_string()),
+            ("2".to_string(), None, "INBOX".to_string()),
+        ];
+        let group = PendingOp::DeleteGroup {
+            sender: "news.com".to_string(),
+            emails: emails.clone(),
+        };
+        let summary = group.bulk_summary("delete", "personal").unwrap();
+        assert_eq!(summary.scope, "group");
+        assert_eq!(summary.sender.as_deref(), Some("news.com"));
+        assert_eq!(summary.count, 2);
+        assert_eq!(summary.account, "personal");
+
+        let thread = PendingOp::ArchiveThread {
+            thread_id: "t".to_string(),
+            subject: "Hi".to_string(),
+            emails,
+        };
+        assert!(thread.bulk_summary("archive", "personal").is_none());
+    }
+
+    #[test]
+    fn test_stale_uids_refuse_only_commands_that_use_them() {
+        let archive = ImapCommand::ArchiveMultiple(vec![("7".to_string(), "INBOX".to_string())]);
+        assert!(matches!(
+            refuse_stale_uids(&archive),
+            Some(ImapResponse::MultiArchiveResult(Err(_)))
+        ));
+        let body = ImapCommand::FetchBody {
+            uid: "7".to_string(),
+            folder: "INBOX".to_string(),
+            max_bytes: None,
+            mark_seen: false,
+        };
+        assert!(matches!(
+            refuse_stale_uids(&body),
+            Some(ImapResponse::BodyResult { uid, result: Err(_) }) if uid == "7"
+        ));
+        assert!(refuse_stale_uids(&ImapCommand::ListFolders).is_none());
+        assert!(refuse_stale_uids(&ImapCommand::CheckUidValidity(HashMap::new())).is_none());
+    }
+
+    #[test]
+    fn test_usual_action_confirms_remembered_handling() {
+        let mut app = App::new();
+        app.set_emails(vec![
+            create_test_email("1", "alice@example.com"),
+            create_test_email("2", "alice@example.com"),
+        ]);
+        let mut ui_state = UiState::new();
+
+        confirm_usual_action(&app, &mut ui_state);
+        assert!(ui_state.confirm_action.is_none());
+        assert_eq!(ui_state.toasts().count(), 1);
+
+        for _ in 0..habits::USUAL_AFTER {
+            remember_handling(&mut app, "alice@example.com", PolicyAction::Delete, None);
+        }
+        let mut ui_state = UiState::new();
+        confirm_usual_action(&app, &mut ui_state);
+        match ui_state.confirm_action {
+            Some(ConfirmAction::DeleteEmails {
+                ref sender,
+                count,
+                filtered,
+            }) => {
+                assert_eq!(sender, "alice@example.com");
+                assert_eq!(count, 2);
+                assert!(!filtered);
+            }
+            other => panic!("Expected DeleteEmails, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_group_age_confirm_cycles_presets() {
+        let mut app = App::new();
+        let mut month_old = create_test_email("1", "news@example.com");
+        month_old.date = Utc::now() - chrono::Duration::days(45);
+        let mut year_old = create_test_email("2", "news@example.com");
+        year_old.date = Utc::now() - chrono::Duration::days(400);
+        app.set_emails(vec![
+            month_old,
+            year_old,
+            create_test_email("3", "news@example.com"),
+        ]);
+        let mut ui_state = UiState::new();
+
+        confirm_archive_group_old(&app, &mut ui_state, "news@example.com".to_string(), 30);
+        let counts = |ui_state: &UiState| match ui_state.confirm_action {
+            Some(ConfirmAction::ArchiveGroupOld { count, days, .. }) => (days, count),
+            ref other => panic!("Expected ArchiveGroupOld, got {:?}", other),
+        };
+        assert_eq!(counts(&ui_state), (30, 2));
+        cycle_group_age(&app, &mut ui_state);
+        assert_eq!(counts(&ui_state), (90, 1));
+        cycle_group_age(&app, &mut ui_state);
+        assert_eq!(counts(&ui_state), (365, 1));
+        cycle_group_age(&app, &mut ui_state);
+        assert_eq!(counts(&ui_state), (7, 2));
+    }
+
+    #[test]
+    fn test_large_delete_confirms_only_once_the_word_is_typed() {
+        let mut ui_state = UiState::new();
+        ui_state.typed_confirm_above = 5;
+        ui_state.set_confirm(ConfirmAction::DeleteSelected { count: 6 });
+        let press = |code| KeyEvent::new(code, KeyModifiers::NONE);
+
+        // y is just a letter of the word here
+        assert_eq!(
+            typed_confirm_key(&mut ui_state, press(KeyCode::Char('y'))),
+            None
+        );
+        assert_eq!(
+            typed_confirm_key(&mut ui_state, press(KeyCode::Enter)),
+            None
+        );
+        assert_eq!(
+            ui_state
+                .confirm_input
+                .as_ref()
+                .map(|typed| typed.text.as_str()),
+            Some("")
+        );
+        for c in "delete".chars() {
+            assert_eq!(
+                typed_confirm_key(&mut ui_state, press(KeyCode::Char(c))),
+                None
+            );
+        }
+        assert_eq!(
+            typed_confirm_key(&mut ui_state, press(KeyCode::Enter)),
+            Some(press(KeyCode::Char('y')))
+        );
+        assert_eq!(ui_state.confirm_input, None);
+
+        // Small deletes and archives keep plain y/n
+        ui_state.set_confirm(ConfirmAction::DeleteSelected { count: 5 });
+        assert_eq!(
+            typed_confirm_key(&mut ui_state, press(KeyCode::Char('y'))),
+            Some(press(KeyCode::Char('y')))
+        );
+    }
+
+    #[test]
+    fn test_forward_prompt_waits_for_the_body_and_a_valid_address() {
+        let mut app = App::new();
+        app.set_emails(vec![create_test_email("1", "alice@example.com")]);
+        app.enter_text_view("1");
+        let mut ui_state = UiState::new();
+
+        start_forward(&app, &mut ui_state, true);
+        assert!(ui_state.forward_input.is_none());
+
+        app.set_email_body("1", "Hello".to_string());
+        start_forward(&app, &mut ui_state, true);
+        for c in "bob".chars() {
+            handle_forward_input(&app, &mut ui_state, KeyCode::Char(c));
+        }
+        assert!(handle_forward_input(&app, &mut ui_state, KeyCode::Enter).is_none());
+        assert!(ui_state.forward_input.is_some());
+
+        for c in "@example.com".chars() {
+            handle_forward_input(&app, &mut ui_state, KeyCode::Char(c));
+        }
+        let draft = handle_forward_input(&app, &mut ui_state, KeyCode::Enter).unwrap();
+        assert_eq!(draft.recipients(), vec!["bob@example.com"]);
+        assert!(draft.body.ends_with("Hello\n"));
+        assert!(ui_state.forward_input.is_none());
+    }
+
+    #[test]
+    fn test_note_input_saves_on_enter_and_discards_on_esc() {
+        let mut app = App::new();
+        let mut ui_state = UiState::new();
+
+        ui_state.start_note_input("alice@example.com", None);
+        for c in "keep".chars() {
+            handle_note_input(&mut app, &mut ui_state, KeyCode::Char(c), None);
+        }
+        handle_note_input(&mut app, &mut ui_state, KeyCode::Backspace, None);
+        handle_note_input(&mut app, &mut ui_state, KeyCode::Enter, None);
+        assert!(!ui_state.is_note_input_active());
+        assert_eq!(app.notes.get("alice@example.com"), Some("kee"));
+
+        ui_state.start_note_input("alice@example.com", app.notes.get("alice@example.com"));
+        handle_note_input(&mut app, &mut ui_state, KeyCode::Char('p'), None);
+        handle_note_input(&mut app, &mut ui_state, KeyCode::Esc, None);
+        assert_eq!(app.notes.get("alice@example.com"), Some("kee"));
+    }
+
+    #[test]
+    fn test_fetch_page_positions() {
+        let first = FetchPage::newest(3);
+        assert_eq!(first.positions(10), 7..10);
+        assert_eq!(first.positions(2), 0..2);
+        assert_eq!(first.positions(0), 0..0);
+    }
+
     fn create_test_email_with_subject(id: &str, from: &str, subject: &str) -> Email {
         Email::new(
             id.to_string(),
@@ -3362,4 +8987,47 @@ mod tests {
             TextViewState::Loaded(demo_email_body(&new_email.from, &new_email.subject))
         );
     }
+
+    #[test]
+    fn test_start_restore_sends_the_emails_to_the_picked_folder() {
+        let mut app = setup_app_in_email_list(vec![create_test_email("1", "alice@example.com")]);
+        app.push_undo(UndoEntry {
+            action_type: UndoActionType::Archive,
+            context: UndoContext::Group {
+                sender: "alice@example.com".to_string(),
+            },
+            emails: vec![(
+                Some("<m1@example.com>".to_string()),
+                Some(7),
+                "Work".to_string(),
+            )],
+            current_folder: "[Gmail]/All Mail".to_string(),
+        });
+        let (cmd_tx, cmd_rx) = mpsc::channel();
+        let mut ui_state = UiState::new();
+        let mut pending_operation = None;
+
+        start_restore(
+            &app,
+            &cmd_tx,
+            &mut ui_state,
+            &mut pending_operation,
+            Some("INBOX"),
+        )
+        .unwrap();
+
+        match cmd_rx.try_recv() {
+            Ok(ImapCommand::RestoreEmails(ops)) => assert_eq!(
+                ops,
+                vec![(
+                    Some("<m1@example.com>".to_string()),
+                    Some(7),
+                    "[Gmail]/All Mail".to_string(),
+                    "INBOX".to_string()
+                )]
+            ),
+            other => panic!("Expected RestoreEmails, got {:?}", other.is_ok()),
+        }
+        assert!(matches!(pending_operation, Some(PendingOp::Undo(0))));
+    }
 }
@@ -0,0 +1,68 @@
+//! Short notes attached to sender groups, stored locally per account
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::config;
+
+const NOTES_DIR: &str = "notes";
+
+/// Notes keyed by group key (a sender address or a domain)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SenderNotes(BTreeMap<String, String>);
+
+impl SenderNotes {
+    /// Returns the note for a group key, if any
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).map(String::as_str)
+    }
+
+    /// Sets the note for a group key; a blank note removes it
+    pub fn set(&mut self, key: &str, note: &str) {
+        let note = note.trim();
+        if note.is_empty() {
+            self.0.remove(key);
+        } else {
+            self.0.insert(key.to_string(), note.to_string());
+        }
+    }
+
+    /// Loads notes from a file
+    pub fn load(path: &Path) -> Result<Self> {
+        config::load_data_file(path)
+    }
+
+    /// Writes notes to a file
+    pub fn save(&self, path: &Path) -> Result<()> {
+        config::save_data_file(path, self)
+    }
+}
+
+/// Returns the notes file for an account: ~/.config/zeroterm/notes/<account>.toml
+pub fn notes_path(account: &str) -> Result<PathBuf> {
+    config::account_data_path(NOTES_DIR, account)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blank_note_removes_entry() {
+        let mut notes = SenderNotes::default();
+        notes.set("alice@example.com", "  keep receipts ");
+        assert_eq!(notes.get("alice@example.com"), Some("keep receipts"));
+
+        notes.set("alice@example.com", "   ");
+        assert_eq!(notes.get("alice@example.com"), None);
+    }
+
+    #[test]
+    fn test_notes_path_sanitizes_account_name() {
+        let path = notes_path("work/acme co").unwrap();
+        assert!(path.ends_with("notes/work_acme_co.toml"));
+    }
+}
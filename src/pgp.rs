@@ -0,0 +1,341 @@
+//! PGP/MIME (RFC 3156): decrypts multipart/encrypted mail and checks multipart/signed
+//! signatures with gpg, so the body view shows the message instead of armored text
+
+use anyhow::{Context, Result, bail};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::email::extract_body_text;
+use crate::task::split_args;
+
+/// A signature as gpg judged it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Signature {
+    /// Verified against a key in the keyring
+    Good { signer: String },
+    /// The message was changed after it was signed, or the signature is forged
+    Bad { signer: String },
+    /// Couldn't be verified, e.g. the signer's key isn't in the keyring or has expired
+    Unchecked { reason: String },
+}
+
+impl Signature {
+    /// One line for the top of the body view
+    pub fn describe(&self) -> String {
+        match self {
+            Signature::Good { signer } => format!("✓ Good signature from {}", signer),
+            Signature::Bad { signer } => format!(
+                "✗ BAD signature from {}: the message was changed or the signature is forged",
+                signer
+            ),
+            Signature::Unchecked { reason } => format!("? Signature not verified: {}", reason),
+        }
+    }
+}
+
+/// Reads the first signature verdict from gpg's `--status-fd` lines
+fn parse_signature(status: &[String]) -> Option<Signature> {
+    status.iter().find_map(|line| {
+        let mut words = line.splitn(3, ' ');
+        let keyword = words.next()?;
+        let key_id = words.next().unwrap_or_default().to_string();
+        let signer = words.next().map_or_else(|| key_id.clone(), str::to_string);
+        match keyword {
+            "GOODSIG" => Some(Signature::Good { signer }),
+            "BADSIG" => Some(Signature::Bad { signer }),
+            "EXPSIG" => Some(Signature::Unchecked {
+                reason: format!("the signature from {} has expired", signer),
+            }),
+            "EXPKEYSIG" => Some(Signature::Unchecked {
+                reason: format!("the key of {} has expired", signer),
+            }),
+            "REVKEYSIG" => Some(Signature::Unchecked {
+                reason: format!("the key of {} was revoked", signer),
+            }),
+            "ERRSIG" => Some(Signature::Unchecked {
+                reason: format!("no public key for {} in your keyring", key_id),
+            }),
+            _ => None,
+        }
+    })
+}
+
+/// What gpg printed, split into the decrypted data, status keywords and error text
+struct GpgOutput {
+    stdout: Vec<u8>,
+    /// `[GNUPG:]` status lines without the prefix
+    status: Vec<String>,
+    /// Everything else gpg wrote to stderr
+    errors: String,
+    success: bool,
+}
+
+/// Runs the gpg command with `args`, feeding `input` on stdin
+fn run_gpg(command: &str, args: &[&str], input: &[u8]) -> Result<GpgOutput> {
+    let parts = split_args(command);
+    let Some((program, rest)) = parts.split_first() else {
+        bail!("pgp_command is empty");
+    };
+    let mut child = Command::new(program)
+        .args(rest)
+        .args(["--batch", "--status-fd", "2"])
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to run {}", program))?;
+
+    // Write from another thread so a large message can't deadlock on a full stdout pipe
+    let mut stdin = child.stdin.take().context("Failed to open gpg's stdin")?;
+    let input = input.to_vec();
+    let writer = std::thread::spawn(move || stdin.write_all(&input));
+    let output = child
+        .wait_with_output()
+        .with_context(|| format!("Failed to wait for {}", program))?;
+    let _ = writer.join();
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let (status, errors): (Vec<&str>, Vec<&str>) =
+        stderr.lines().partition(|l| l.starts_with("[GNUPG:] "));
+    Ok(GpgOutput {
+        stdout: output.stdout,
+        status: status
+            .iter()
+            .map(|l| l.trim_start_matches("[GNUPG:] ").to_string())
+            .collect(),
+        errors: errors.join("\n").trim().to_string(),
+        success: output.status.success(),
+    })
+}
+
+/// Encrypts `data` to `recipient` with `gpg --encrypt`
+pub fn encrypt(command: &str, recipient: &str, data: &[u8]) -> Result<Vec<u8>> {
+    let output = run_gpg(command, &["--encrypt", "--recipient", recipient], data)?;
+    if !output.success {
+        bail!("gpg couldn't encrypt to {}: {}", recipient, output.errors);
+    }
+    Ok(output.stdout)
+}
+
+/// Decrypts data `encrypt` wrote with `gpg --decrypt`, which asks gpg-agent for the key
+pub fn decrypt_data(command: &str, data: &[u8]) -> Result<Vec<u8>> {
+    let output = run_gpg(command, &["--decrypt"], data)?;
+    if !output.success {
+        bail!("gpg couldn't decrypt: {}", output.errors);
+    }
+    Ok(output.stdout)
+}
+
+/// The bytes a multipart/signed signature covers: the first part exactly as sent,
+/// without the line break before the boundary, with CRLF line endings
+fn signed_data(part: &mailparse::ParsedMail) -> Vec<u8> {
+    let raw = part.raw_bytes;
+    let raw = raw
+        .strip_suffix(b"\r\n")
+        .or_else(|| raw.strip_suffix(b"\n"))
+        .unwrap_or(raw);
+    let mut data = Vec::with_capacity(raw.len());
+    for (i, &byte) in raw.iter().enumerate() {
+        if byte == b'\n' && (i == 0 || raw[i - 1] != b'\r') {
+            data.push(b'\r');
+        }
+        data.push(byte);
+    }
+    data
+}
+
+/// Checks the signature of a multipart/signed message with `gpg --verify`
+fn verify(command: &str, mail: &mailparse::ParsedMail) -> Signature {
+    let (Some(content), Some(signature)) = (mail.subparts.first(), mail.subparts.get(1)) else {
+        return Signature::Unchecked {
+            reason: "the signature part is missing".to_string(),
+        };
+    };
+    let unique = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    let sig_path =
+        std::env::temp_dir().join(format!("zeroterm-{}-{}.sig", std::process::id(), unique));
+    let result = signature
+        .get_body_raw()
+        .context("Failed to read the signature")
+        .and_then(|sig| {
+            std::fs::write(&sig_path, sig).context("Failed to write the signature file")
+        })
+        .and_then(|_| {
+            let sig_arg = sig_path.to_string_lossy();
+            run_gpg(command, &["--verify", &sig_arg, "-"], &signed_data(content))
+        });
+    let _ = std::fs::remove_file(&sig_path);
+
+    match result {
+        Ok(output) => parse_signature(&output.status).unwrap_or_else(|| Signature::Unchecked {
+            reason: if output.errors.is_empty() {
+                "gpg gave no verdict".to_string()
+            } else {
+                output.errors
+            },
+        }),
+        Err(e) => Signature::Unchecked {
+            reason: format!("{:#}", e),
+        },
+    }
+}
+
+/// Renders the body of a PGP/MIME message with a line about decryption and the signature
+/// on top, or returns None for mail that is neither encrypted nor signed
+pub fn body_text(mail: &mailparse::ParsedMail, command: &str) -> Option<String> {
+    // S/MIME uses the same multipart types with a different protocol; gpg can't read it
+    let protocol = mail.ctype.params.get("protocol")?.to_lowercase();
+    if !protocol.starts_with("application/pgp-") {
+        return None;
+    }
+    match mail.ctype.mimetype.to_lowercase().as_str() {
+        "multipart/encrypted" => Some(decrypt(command, mail)),
+        "multipart/signed" => {
+            let signature = verify(command, mail);
+            let body = mail
+                .subparts
+                .first()
+                .map(|content| {
+                    body_text(content, command)
+                        .unwrap_or_else(|| extract_body_text(content).unwrap_or_default())
+                })
+                .unwrap_or_default();
+            Some(format!("{}\n\n{}", signature.describe(), body))
+        }
+        _ => None,
+    }
+}
+
+/// Decrypts a multipart/encrypted message with `gpg --decrypt`, noting any signature inside
+fn decrypt(command: &str, mail: &mailparse::ParsedMail) -> String {
+    let Some(ciphertext) = mail
+        .subparts
+        .iter()
+        .find(|p| {
+            p.ctype
+                .mimetype
+                .eq_ignore_ascii_case("application/octet-stream")
+        })
+        .and_then(|p| p.get_body_raw().ok())
+    else {
+        return "🔒 Encrypted message, but the encrypted part is missing".to_string();
+    };
+
+    let output = match run_gpg(command, &["--decrypt"], &ciphertext) {
+        Ok(output) if output.success || !output.stdout.is_empty() => output,
+        Ok(output) => {
+            return format!(
+                "🔒 Encrypted message. gpg couldn't decrypt it:\n{}",
+                output.errors
+            );
+        }
+        Err(e) => return format!("🔒 Encrypted message. {:#}", e),
+    };
+
+    let mut text = "🔒 Decrypted with gpg".to_string();
+    if let Some(signature) = parse_signature(&output.status) {
+        text.push('\n');
+        text.push_str(&signature.describe());
+    }
+    let body = match mailparse::parse_mail(&output.stdout) {
+        Ok(inner) => body_text(&inner, command)
+            .unwrap_or_else(|| extract_body_text(&inner).unwrap_or_default()),
+        Err(_) => String::from_utf8_lossy(&output.stdout).to_string(),
+    };
+    format!("{}\n\n{}", text, body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|l| l.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_signature_verdicts() {
+        assert_eq!(
+            parse_signature(&status(&[
+                "NEWSIG",
+                "GOODSIG 1234ABCD Alice <alice@example.com>",
+                "VALIDSIG 0000"
+            ])),
+            Some(Signature::Good {
+                signer: "Alice <alice@example.com>".to_string()
+            })
+        );
+        assert!(matches!(
+            parse_signature(&status(&["BADSIG 1234ABCD Mallory <m@example.com>"])),
+            Some(Signature::Bad { .. })
+        ));
+        assert_eq!(
+            parse_signature(&status(&["ERRSIG 1234ABCD 1 8 00 1700000000 9"])),
+            Some(Signature::Unchecked {
+                reason: "no public key for 1234ABCD in your keyring".to_string()
+            })
+        );
+        assert_eq!(parse_signature(&status(&["DECRYPTION_OKAY"])), None);
+    }
+
+    #[test]
+    fn test_signed_data_drops_boundary_line_break() {
+        let raw = concat!(
+            "Content-Type: multipart/signed; boundary=b; protocol=\"application/pgp-signature\"\n",
+            "\n",
+            "--b\n",
+            "Content-Type: text/plain\n",
+            "\n",
+            "Hello\n",
+            "--b\n",
+            "Content-Type: application/pgp-signature\n",
+            "\n",
+            "SIG\n",
+            "--b--\n",
+        );
+        let mail = mailparse::parse_mail(raw.as_bytes()).unwrap();
+        assert_eq!(
+            signed_data(&mail.subparts[0]),
+            b"Content-Type: text/plain\r\n\r\nHello"
+        );
+    }
+
+    #[test]
+    fn test_plain_mail_is_left_alone() {
+        let mail = mailparse::parse_mail(b"Content-Type: text/plain\r\n\r\nHi").unwrap();
+        assert_eq!(body_text(&mail, "gpg"), None);
+    }
+
+    #[test]
+    fn test_decrypts_with_the_configured_command() {
+        // A stand-in for gpg that ignores its arguments and "decrypts" to a signed text part
+        let command = "sh -c 'cat >/dev/null; printf \"Content-Type: text/plain\\r\\n\\r\\nsecret plans\"; \
+                       echo \"[GNUPG:] GOODSIG 1234ABCD Alice <alice@example.com>\" >&2'";
+        let raw = concat!(
+            "Content-Type: multipart/encrypted; boundary=b; protocol=\"application/pgp-encrypted\"\r\n",
+            "\r\n",
+            "--b\r\n",
+            "Content-Type: application/pgp-encrypted\r\n",
+            "\r\n",
+            "Version: 1\r\n",
+            "--b\r\n",
+            "Content-Type: application/octet-stream\r\n",
+            "\r\n",
+            "-----BEGIN PGP MESSAGE-----\r\n",
+            "hQEMA\r\n",
+            "-----END PGP MESSAGE-----\r\n",
+            "--b--\r\n",
+        );
+        let mail = mailparse::parse_mail(raw.as_bytes()).unwrap();
+        let text = body_text(&mail, command).unwrap();
+        assert_eq!(
+            text,
+            "🔒 Decrypted with gpg\n✓ Good signature from Alice <alice@example.com>\n\nsecret plans"
+        );
+    }
+}
@@ -0,0 +1,340 @@
+//! `[[plugin]]` external executables bound to keys. Zeroterm writes the current context
+//! to the plugin's stdin as one JSON object and reads commands from its stdout, one JSON
+//! object per line.
+
+use anyhow::{Context as _, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::capture::expand_home;
+use crate::email::Email;
+use crate::script::{Context, Operation};
+use crate::task::split_args;
+
+/// A `[[plugin]]` from config
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Plugin {
+    /// The key that runs the plugin; built-in keys take precedence
+    pub key: char,
+    /// What the plugin does, shown when it fails (default: none)
+    pub description: Option<String>,
+    /// The executable (a leading ~ is your home) and its arguments, split like task_command
+    pub command: String,
+}
+
+impl Plugin {
+    /// Names the plugin in messages: its description, or the key
+    pub fn label(&self) -> String {
+        self.description
+            .clone()
+            .unwrap_or_else(|| format!("plugin {}", self.key))
+    }
+}
+
+/// What a plugin answered with
+#[derive(Debug, Clone, PartialEq)]
+pub enum Reply {
+    /// A built-in operation, carried out like an `[[action]]` script's
+    Run(Operation),
+    /// Let the user choose one of `items`; the plugin runs again with the choice
+    Picker { title: String, items: Vec<String> },
+}
+
+/// One line of plugin output
+#[derive(Debug, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+enum PluginCommand {
+    Archive,
+    Delete,
+    ArchiveGroup,
+    DeleteGroup,
+    Filter {
+        query: String,
+    },
+    Status {
+        message: String,
+    },
+    Picker {
+        #[serde(default)]
+        title: Option<String>,
+        items: Vec<String>,
+    },
+}
+
+impl From<PluginCommand> for Reply {
+    fn from(command: PluginCommand) -> Self {
+        match command {
+            PluginCommand::Archive => Reply::Run(Operation::Archive),
+            PluginCommand::Delete => Reply::Run(Operation::Delete),
+            PluginCommand::ArchiveGroup => Reply::Run(Operation::ArchiveGroup),
+            PluginCommand::DeleteGroup => Reply::Run(Operation::DeleteGroup),
+            PluginCommand::Filter { query } => Reply::Run(Operation::Filter(query)),
+            PluginCommand::Status { message } => Reply::Run(Operation::Status(message)),
+            PluginCommand::Picker { title, items } => Reply::Picker {
+                title: title.unwrap_or_else(|| "Choose".to_string()),
+                items,
+            },
+        }
+    }
+}
+
+/// The email under the cursor, as plugins receive it
+#[derive(Serialize)]
+struct EmailInfo<'a> {
+    id: &'a str,
+    thread_id: &'a str,
+    from: &'a str,
+    from_email: &'a str,
+    from_domain: &'a str,
+    subject: &'a str,
+    snippet: &'a str,
+    date: String,
+    message_id: Option<&'a str>,
+    folder: &'a str,
+    seen: bool,
+    flagged: bool,
+    mailing_list: bool,
+    spam_score: Option<f32>,
+}
+
+impl<'a> From<&'a Email> for EmailInfo<'a> {
+    fn from(email: &'a Email) -> Self {
+        Self {
+            id: &email.id,
+            thread_id: &email.thread_id,
+            from: &email.from,
+            from_email: &email.from_email,
+            from_domain: &email.from_domain,
+            subject: &email.subject,
+            snippet: &email.snippet,
+            date: email.date.to_rfc3339(),
+            message_id: email.message_id.as_deref(),
+            folder: &email.source_folder,
+            seen: email.seen,
+            flagged: email.flagged,
+            mailing_list: email.mailing_list,
+            spam_score: email.spam_score,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct GroupInfo<'a> {
+    key: &'a str,
+    count: usize,
+}
+
+/// Everything written to the plugin's stdin
+#[derive(Serialize)]
+struct Request<'a> {
+    view: &'static str,
+    email: Option<EmailInfo<'a>>,
+    group: Option<GroupInfo<'a>>,
+    /// The item picked from the plugin's last picker, or null on a key press
+    choice: Option<&'a str>,
+}
+
+/// Builds the JSON the plugin reads on stdin
+fn request_json(context: &Context, choice: Option<&str>) -> Result<String> {
+    let request = Request {
+        view: context.view.name(),
+        email: context.email.map(EmailInfo::from),
+        group: context.group.map(|(key, count)| GroupInfo { key, count }),
+        choice,
+    };
+    serde_json::to_string(&request).context("Failed to encode the plugin request")
+}
+
+/// Parses plugin output: one command per line, blank lines ignored
+fn parse_replies(output: &str) -> Result<Vec<Reply>> {
+    output
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str::<PluginCommand>(line)
+                .map(Reply::from)
+                .with_context(|| format!("Line {} of the plugin's output", i + 1))
+        })
+        .collect()
+}
+
+/// How long a plugin may run before it's killed
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What a finished plugin sends back: its key and its replies in order
+pub type Done = (char, Result<Vec<Reply>>);
+
+/// Runs the plugin on another thread with the context (and the user's pick from its
+/// picker, if any), sending its replies on `done_tx` once it exits or is killed for
+/// taking too long
+pub fn spawn(
+    plugin: &Plugin,
+    context: &Context,
+    choice: Option<&str>,
+    done_tx: mpsc::Sender<Done>,
+) {
+    let key = plugin.key;
+    let input = match request_json(context, choice) {
+        Ok(input) => input,
+        Err(e) => {
+            let _ = done_tx.send((key, Err(e)));
+            return;
+        }
+    };
+    let command = plugin.command.clone();
+    thread::spawn(move || {
+        let _ = done_tx.send((key, run(&command, input, TIMEOUT)));
+    });
+}
+
+/// Runs `command` with `input` on its stdin and parses its replies, killing it after
+/// `timeout`
+fn run(command: &str, input: String, timeout: Duration) -> Result<Vec<Reply>> {
+    let parts = split_args(command);
+    let Some((program, args)) = parts.split_first() else {
+        bail!("The plugin's command is empty");
+    };
+    let mut child = Command::new(expand_home(Path::new(program)))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to run {}", program))?;
+
+    // Write and read from other threads so a plugin that answers before reading, or
+    // fills a pipe, can't deadlock
+    let mut stdin = child
+        .stdin
+        .take()
+        .context("Failed to open the plugin's stdin")?;
+    thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let stdout = read_in_background(child.stdout.take());
+    let stderr = read_in_background(child.stderr.take());
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child
+            .try_wait()
+            .with_context(|| format!("Failed to wait for {}", program))?
+        {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            bail!(
+                "{} didn't finish within {} seconds",
+                program,
+                timeout.as_secs()
+            );
+        }
+        thread::sleep(Duration::from_millis(20));
+    };
+    let stdout = stdout.join().unwrap_or_default();
+    let stderr = stderr.join().unwrap_or_default();
+
+    if !status.success() {
+        bail!("{} exited with {}: {}", program, status, stderr.trim());
+    }
+    parse_replies(&stdout)
+}
+
+/// Reads a child's output pipe to the end on another thread
+fn read_in_background(pipe: Option<impl Read + Send + 'static>) -> JoinHandle<String> {
+    thread::spawn(move || {
+        let mut bytes = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut bytes);
+        }
+        String::from_utf8_lossy(&bytes).into_owned()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::View;
+    use crate::email::EmailBuilder;
+
+    #[test]
+    fn test_request_describes_the_context() {
+        let email = EmailBuilder::new()
+            .from("news@example.com")
+            .subject("Digest")
+            .build();
+        let context = Context {
+            view: View::EmailList,
+            email: Some(&email),
+            group: Some(("news@example.com", 3)),
+        };
+        let json: serde_json::Value =
+            serde_json::from_str(&request_json(&context, Some("Later")).unwrap()).unwrap();
+        assert_eq!(json["view"], "email_list");
+        assert_eq!(json["email"]["subject"], "Digest");
+        assert_eq!(json["email"]["from_domain"], "example.com");
+        assert_eq!(json["group"]["count"], 3);
+        assert_eq!(json["choice"], "Later");
+    }
+
+    #[test]
+    fn test_parse_replies() {
+        let output = concat!(
+            "{\"command\":\"status\",\"message\":\"hi\"}\n",
+            "\n",
+            "{\"command\":\"picker\",\"items\":[\"a\",\"b\"]}\n",
+            "{\"command\":\"archive_group\"}\n",
+        );
+        assert_eq!(
+            parse_replies(output).unwrap(),
+            vec![
+                Reply::Run(Operation::Status("hi".to_string())),
+                Reply::Picker {
+                    title: "Choose".to_string(),
+                    items: vec!["a".to_string(), "b".to_string()]
+                },
+                Reply::Run(Operation::ArchiveGroup),
+            ]
+        );
+        let error = parse_replies("{\"command\":\"move\"}").unwrap_err();
+        assert!(format!("{:#}", error).starts_with("Line 1"));
+    }
+
+    #[test]
+    fn test_runs_the_configured_command() {
+        let plugin = Plugin {
+            key: 'X',
+            description: None,
+            command: "sh -c 'grep -q \"\\\"choice\\\":\\\"b\\\"\" && echo \"{\\\"command\\\":\\\"archive\\\"}\"'"
+                .to_string(),
+        };
+        let context = Context {
+            view: View::GroupList,
+            email: None,
+            group: None,
+        };
+        let (done_tx, done_rx) = mpsc::channel();
+        spawn(&plugin, &context, Some("b"), done_tx.clone());
+        let (key, replies) = done_rx.recv().unwrap();
+        assert_eq!(key, 'X');
+        assert_eq!(replies.unwrap(), vec![Reply::Run(Operation::Archive)]);
+        // grep finds no choice and exits 1, which is reported as a failure
+        spawn(&plugin, &context, None, done_tx);
+        assert!(done_rx.recv().unwrap().1.is_err());
+    }
+
+    #[test]
+    fn test_kills_a_plugin_that_runs_too_long() {
+        let started = Instant::now();
+        let error = run("sleep 5", String::new(), Duration::from_millis(100)).unwrap_err();
+        assert!(error.to_string().contains("didn't finish"));
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+}
@@ -0,0 +1,259 @@
+//! Declarative archive/delete rules applied right after emails are fetched
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use crate::email::{Email, sender_matches};
+
+/// What a policy does with the emails it matches
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PolicyAction {
+    /// Move to All Mail
+    #[default]
+    Archive,
+    /// Move to Trash
+    Delete,
+}
+
+/// A `[[policy]]` rule from config. All given conditions must match.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Policy {
+    /// Shown in the confirmation summary and undo history
+    pub name: String,
+    /// "archive" or "delete" (default: archive)
+    #[serde(default)]
+    pub action: PolicyAction,
+    /// Only match emails older than this many days
+    pub older_than_days: Option<u32>,
+    /// Only match these senders ("news@example.com") or domains ("example.com")
+    #[serde(default)]
+    pub senders: Vec<String>,
+    /// Only match read (true) or unread (false) emails
+    pub read: Option<bool>,
+    /// Only match mailing-list mail (true) or personal mail (false)
+    pub newsletter: Option<bool>,
+}
+
+impl Policy {
+    /// A policy must narrow by age or sender so it can't sweep the whole inbox
+    pub fn is_valid(&self) -> bool {
+        self.older_than_days.is_some() || !self.senders.is_empty()
+    }
+
+    /// Checks if an inbox email matches every condition of this policy.
+    /// Flagged mail, protected senders and other folders are never matched.
+    pub fn matches(&self, email: &Email, protected_senders: &[String], now: DateTime<Utc>) -> bool {
+        if email.source_folder != "INBOX" || email.flagged {
+            return false;
+        }
+        if protected_senders.iter().any(|p| sender_matches(email, p)) {
+            return false;
+        }
+        if let Some(days) = self.older_than_days
+            && email.date >= now - Duration::days(i64::from(days))
+        {
+            return false;
+        }
+        if !self.senders.is_empty() && !self.senders.iter().any(|s| sender_matches(email, s)) {
+            return false;
+        }
+        if self.read.is_some_and(|read| read != email.seen) {
+            return false;
+        }
+        if self
+            .newsletter
+            .is_some_and(|newsletter| newsletter != email.mailing_list)
+        {
+            return false;
+        }
+        true
+    }
+}
+
+/// Matches emails against policies in order. Each email is claimed by the first policy
+/// that matches it; policies matching nothing are left out.
+pub fn evaluate<'a, 'e>(
+    policies: &'a [Policy],
+    emails: &'e [Email],
+    protected_senders: &[String],
+    now: DateTime<Utc>,
+) -> Vec<(&'a Policy, Vec<&'e Email>)> {
+    let mut claimed: HashSet<(&str, &str)> = HashSet::new();
+    let mut results = Vec::new();
+
+    for policy in policies {
+        let matched: Vec<&Email> = emails
+            .iter()
+            .filter(|e| !claimed.contains(&(e.id.as_str(), e.source_folder.as_str())))
+            .filter(|e| policy.matches(e, protected_senders, now))
+            .collect();
+        if matched.is_empty() {
+            continue;
+        }
+        claimed.extend(
+            matched
+                .iter()
+                .map(|e| (e.id.as_str(), e.source_folder.as_str())),
+        );
+        results.push((policy, matched));
+    }
+
+    results
+}
+
+/// One-line summary of what the matched policies will do, for the confirmation prompt
+pub fn summarize(matches: &[(&Policy, Vec<&Email>)]) -> String {
+    matches
+        .iter()
+        .map(|(policy, emails)| {
+            let verb = match policy.action {
+                PolicyAction::Archive => "archive",
+                PolicyAction::Delete => "delete",
+            };
+            format!("{} {} ({})", verb, emails.len(), policy.name)
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::email::EmailBuilder;
+
+    fn policy(name: &str) -> Policy {
+        Policy {
+            name: name.to_string(),
+            action: PolicyAction::Archive,
+            older_than_days: None,
+            senders: Vec::new(),
+            read: None,
+            newsletter: None,
+        }
+    }
+
+    #[test]
+    fn test_policy_requires_age_or_senders() {
+        assert!(!policy("empty").is_valid());
+        assert!(
+            Policy {
+                older_than_days: Some(30),
+                ..policy("old")
+            }
+            .is_valid()
+        );
+        assert!(
+            Policy {
+                senders: vec!["example.com".to_string()],
+                ..policy("senders")
+            }
+            .is_valid()
+        );
+    }
+
+    #[test]
+    fn test_policy_matches_read_old_newsletters() {
+        let now = Utc::now();
+        let rule = Policy {
+            older_than_days: Some(30),
+            read: Some(true),
+            newsletter: Some(true),
+            ..policy("newsletters")
+        };
+        let old = now - Duration::days(40);
+        let base = || {
+            EmailBuilder::new()
+                .id("1")
+                .from("news@example.com")
+                .date(old)
+                .seen(true)
+                .mailing_list(true)
+        };
+
+        assert!(rule.matches(&base().build(), &[], now));
+        assert!(!rule.matches(&base().seen(false).build(), &[], now));
+        assert!(!rule.matches(&base().mailing_list(false).build(), &[], now));
+        assert!(!rule.matches(&base().flagged(true).build(), &[], now));
+        assert!(!rule.matches(&base().date(now).build(), &[], now));
+        assert!(!rule.matches(&base().source_folder("[Gmail]/Sent Mail").build(), &[], now));
+    }
+
+    #[test]
+    fn test_evaluate_first_matching_policy_claims_email() {
+        let now = Utc::now();
+        let policies = vec![
+            Policy {
+                action: PolicyAction::Delete,
+                senders: vec!["spam.com".to_string()],
+                ..policy("spam")
+            },
+            Policy {
+                older_than_days: Some(1),
+                ..policy("old")
+            },
+            Policy {
+                senders: vec!["nobody.com".to_string()],
+                ..policy("unused")
+            },
+        ];
+        let old = now - Duration::days(5);
+        let emails = vec![
+            EmailBuilder::new()
+                .id("1")
+                .from("x@spam.com")
+                .date(old)
+                .build(),
+            EmailBuilder::new()
+                .id("2")
+                .from("a@b.com")
+                .date(old)
+                .build(),
+            EmailBuilder::new()
+                .id("3")
+                .from("a@b.com")
+                .date(now)
+                .build(),
+        ];
+
+        let results = evaluate(&policies, &emails, &[], now);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].0.name, "spam");
+        assert_eq!(results[0].1.len(), 1);
+        assert_eq!(results[1].0.name, "old");
+        assert_eq!(results[1].1[0].id, "2");
+        assert_eq!(summarize(&results), "delete 1 (spam), archive 1 (old)");
+    }
+
+    #[test]
+    fn test_policies_skip_protected_senders() {
+        let now = Utc::now();
+        let rule = Policy {
+            action: PolicyAction::Delete,
+            older_than_days: Some(1),
+            ..policy("old")
+        };
+        let old = now - Duration::days(5);
+        let emails = vec![
+            EmailBuilder::new()
+                .id("1")
+                .from("boss@work.com")
+                .date(old)
+                .build(),
+            EmailBuilder::new()
+                .id("2")
+                .from("news@example.com")
+                .date(old)
+                .build(),
+        ];
+
+        let protected = vec!["work.com".to_string()];
+        assert!(!rule.matches(&emails[0], &protected, now));
+        let results = evaluate(std::slice::from_ref(&rule), &emails, &protected, now);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].1.len(), 1);
+        assert_eq!(results[0].1[0].id, "2");
+    }
+}
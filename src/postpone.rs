@@ -0,0 +1,107 @@
+//! Groups put off until a later session with the w key, stored locally per account
+
+use anyhow::Result;
+use chrono::{Datelike, Days, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::config;
+
+const POSTPONE_DIR: &str = "postponed";
+
+/// A sender or domain hidden until a day
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Postponed {
+    pub sender: String,
+    /// The first day the group shows again
+    pub until: NaiveDate,
+}
+
+/// Groups the user chose to handle in a later session
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PostponeList {
+    #[serde(default)]
+    pub groups: Vec<Postponed>,
+}
+
+impl PostponeList {
+    /// Postpones a sender or domain until a day, replacing any earlier postponement
+    pub fn add(&mut self, sender: &str, until: NaiveDate) {
+        self.groups
+            .retain(|p| !p.sender.eq_ignore_ascii_case(sender));
+        self.groups.push(Postponed {
+            sender: sender.to_string(),
+            until,
+        });
+    }
+
+    /// Drops postponements that have run out by `today`; returns true if any were dropped
+    pub fn prune(&mut self, today: NaiveDate) -> bool {
+        let before = self.groups.len();
+        self.groups.retain(|p| p.until > today);
+        self.groups.len() != before
+    }
+
+    /// The senders and domains still postponed
+    pub fn senders(&self) -> impl Iterator<Item = &String> {
+        self.groups.iter().map(|p| &p.sender)
+    }
+
+    /// Loads the list from a file
+    pub fn load(path: &Path) -> Result<Self> {
+        config::load_data_file(path)
+    }
+
+    /// Writes the list to a file
+    pub fn save(&self, path: &Path) -> Result<()> {
+        config::save_data_file(path, self)
+    }
+}
+
+/// The day a group postponed on `today` comes back: `days` later, or next Monday when
+/// days is 0
+pub fn postpone_until(today: NaiveDate, days: u32) -> NaiveDate {
+    let days = if days > 0 {
+        u64::from(days)
+    } else {
+        7 - u64::from(today.weekday().num_days_from_monday())
+    };
+    today + Days::new(days)
+}
+
+/// Returns the postpone file for an account: ~/.config/zeroterm/postponed/<account>.toml
+pub fn postpone_path(account: &str) -> Result<PathBuf> {
+    config::account_data_path(POSTPONE_DIR, account)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, d).unwrap()
+    }
+
+    #[test]
+    fn test_postpone_until_next_week_or_after_days() {
+        // 2026-10-15 is a Thursday
+        assert_eq!(postpone_until(day(15), 0), day(19));
+        assert_eq!(postpone_until(day(19), 0), day(26));
+        assert_eq!(postpone_until(day(18), 0), day(19));
+        assert_eq!(postpone_until(day(15), 3), day(18));
+    }
+
+    #[test]
+    fn test_add_replaces_and_prune_drops_expired() {
+        let mut list = PostponeList::default();
+        list.add("news@example.com", day(19));
+        list.add("shop.com", day(16));
+        list.add("News@Example.com", day(26));
+        assert_eq!(list.groups.len(), 2);
+        assert_eq!(list.groups[1].until, day(26));
+
+        assert!(!list.prune(day(15)));
+        assert!(list.prune(day(16)));
+        assert_eq!(list.senders().collect::<Vec<_>>(), vec!["News@Example.com"]);
+    }
+}
@@ -0,0 +1,110 @@
+//! Prints an email by piping it as plain text to the configured print_command
+
+use anyhow::{Context, Result, bail};
+use chrono::Local;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use crate::email::Email;
+use crate::task::split_args;
+
+/// Renders an email's headers and body as plain text for printing
+pub fn render_text(email: &Email, body: &str) -> String {
+    let mut text = String::new();
+    text.push_str(&format!("From:    {}\n", email.from));
+    if !email.to.is_empty() {
+        text.push_str(&format!("To:      {}\n", email.to.join(", ")));
+    }
+    if !email.cc.is_empty() {
+        text.push_str(&format!("Cc:      {}\n", email.cc.join(", ")));
+    }
+    text.push_str(&format!(
+        "Date:    {}\n",
+        email
+            .date
+            .with_timezone(&Local)
+            .format("%a, %d %b %Y %H:%M")
+    ));
+    text.push_str(&format!("Subject: {}\n\n", email.subject));
+    text.push_str(body.trim_end());
+    text.push('\n');
+    text
+}
+
+/// Sends the rendered email to the print command on stdin.
+/// {subject} in the command is replaced with the email's subject, e.g. for `lp -t {subject}`.
+pub fn print_email(command: &str, email: &Email, body: &str) -> Result<()> {
+    let args: Vec<String> = split_args(command)
+        .into_iter()
+        .map(|arg| arg.replace("{subject}", &email.subject))
+        .collect();
+    let Some((program, rest)) = args.split_first() else {
+        bail!("print_command is empty");
+    };
+    let mut child = Command::new(program)
+        .args(rest)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to run {}", program))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(render_text(email, body).as_bytes())
+            .with_context(|| format!("Failed to send the email to {}", program))?;
+    }
+    let output = child
+        .wait_with_output()
+        .with_context(|| format!("Failed to wait for {}", program))?;
+    if !output.status.success() {
+        bail!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::email::EmailBuilder;
+
+    #[test]
+    fn test_render_text_includes_headers_and_body() {
+        let email = EmailBuilder::new()
+            .id("1")
+            .from("Shop <orders@shop.com>")
+            .subject("Your receipt")
+            .to(vec!["me@example.com".to_string()])
+            .build();
+        let text = render_text(&email, "Total: $12\n\n\n");
+        assert!(
+            text.starts_with("From:    Shop <orders@shop.com>\nTo:      me@example.com\nDate:    ")
+        );
+        assert!(!text.contains("Cc:"));
+        assert!(text.ends_with("Subject: Your receipt\n\nTotal: $12\n"));
+    }
+
+    #[test]
+    fn test_print_email_pipes_text_to_command() {
+        let path = std::env::temp_dir().join(format!("zeroterm-print-{}.txt", std::process::id()));
+        let email = EmailBuilder::new()
+            .id("1")
+            .from("a@b.com")
+            .subject("Ticket")
+            .build();
+        let command = format!("sh -c 'cat > \"$0\"' {}", path.display());
+        print_email(&command, &email, "Seat 4B").unwrap();
+        let printed = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert!(printed.ends_with("Subject: Ticket\n\nSeat 4B\n"));
+    }
+
+    #[test]
+    fn test_failing_print_command_reports_error() {
+        let email = EmailBuilder::new().id("1").from("a@b.com").build();
+        assert!(print_email("false", &email, "body").is_err());
+    }
+}
@@ -0,0 +1,606 @@
+//! Record-and-replay of IMAP worker sessions
+//!
+//! `--record <file>` writes every command the UI sends to the worker and every response
+//! it gets back. `--replay <file>` feeds those responses back into the UI without a
+//! server, so a session can be debugged offline exactly as the user saw it.
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex, mpsc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::email::Email;
+use crate::imap_client::FetchedBody;
+use crate::ui::widgets::WorkerProgress;
+use crate::{ImapCommand, ImapResponse, MailboxResync};
+
+/// Describes the recorded session so it can be replayed with the same settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingHeader {
+    /// Account name shown while "connecting"
+    pub account: String,
+    /// The user's address, used to tell sent mail from received mail
+    pub user_email: String,
+    /// Settings in effect while recording (accounts and passwords are left out)
+    pub config: Config,
+}
+
+/// One command sent to the worker, or one response received from it
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecordedEvent {
+    /// Milliseconds since recording started
+    pub at_ms: u64,
+    /// Command name and size, e.g. "ArchiveMultiple 3"
+    pub command: Option<String>,
+    pub response: Option<RecordedResponse>,
+}
+
+/// A serializable copy of `ImapResponse` (errors are kept as their messages)
+#[derive(Debug, Serialize, Deserialize)]
+pub enum RecordedResponse {
+    Emails(Result<Vec<Email>, String>),
+    FetchInterrupted {
+        error: String,
+        fetched: usize,
+        missing_ranges: usize,
+    },
+    OlderEmails(Result<Vec<Email>, String>),
+    OlderRemaining(usize),
+    MultiArchiveResult(Result<HashMap<String, u32>, String>),
+    MultiDeleteResult(Result<HashMap<String, u32>, String>),
+    MultiMoveResult(Result<HashMap<String, u32>, String>),
+    Folders(Result<Vec<String>, String>),
+    AlreadyGone(usize),
+    RestoreResult(Result<(), String>),
+    FlagsSet {
+        ids: Vec<String>,
+        seen: bool,
+        result: Result<(), String>,
+    },
+    LabelsModified {
+        ids: Vec<String>,
+        add: Vec<String>,
+        remove: Vec<String>,
+        result: Result<(), String>,
+    },
+    BodyResult {
+        uid: String,
+        result: Result<String, String>,
+        #[serde(default)]
+        truncated: bool,
+        #[serde(default)]
+        html: Option<String>,
+    },
+    Progress(usize, usize, String),
+    WorkerProgress(Vec<WorkerProgress>),
+    Retrying {
+        attempt: u32,
+        max_attempts: u32,
+        action: String,
+    },
+    ServerAlert(String),
+    MailboxChanged(Vec<String>),
+    NewMail,
+    Resynced(Result<MailboxResync, String>),
+    UidValidity(HashMap<String, u32>),
+    Connected,
+    Error(String),
+}
+
+impl From<&ImapResponse> for RecordedResponse {
+    fn from(response: &ImapResponse) -> Self {
+        let message = |e: &anyhow::Error| format!("{:#}", e);
+        match response {
+            ImapResponse::Emails(result) => {
+                RecordedResponse::Emails(result.as_ref().map_err(message).cloned())
+            }
+            ImapResponse::FetchInterrupted {
+                error,
+                fetched,
+                missing_ranges,
+            } => RecordedResponse::FetchInterrupted {
+                error: error.clone(),
+                fetched: *fetched,
+                missing_ranges: *missing_ranges,
+            },
+            ImapResponse::OlderEmails(result) => {
+                RecordedResponse::OlderEmails(result.as_ref().map_err(message).cloned())
+            }
+            ImapResponse::OlderRemaining(count) => RecordedResponse::OlderRemaining(*count),
+            ImapResponse::MultiArchiveResult(result) => {
+                RecordedResponse::MultiArchiveResult(result.as_ref().map_err(message).cloned())
+            }
+            ImapResponse::MultiDeleteResult(result) => {
+                RecordedResponse::MultiDeleteResult(result.as_ref().map_err(message).cloned())
+            }
+            ImapResponse::MultiMoveResult(result) => {
+                RecordedResponse::MultiMoveResult(result.as_ref().map_err(message).cloned())
+            }
+            ImapResponse::Folders(result) => {
+                RecordedResponse::Folders(result.as_ref().map_err(message).cloned())
+            }
+            ImapResponse::AlreadyGone(count) => RecordedResponse::AlreadyGone(*count),
+            ImapResponse::RestoreResult(result) => {
+                RecordedResponse::RestoreResult(result.as_ref().map(|_| ()).map_err(message))
+            }
+            ImapResponse::FlagsSet { ids, seen, result } => RecordedResponse::FlagsSet {
+                ids: ids.clone(),
+                seen: *seen,
+                result: result.as_ref().map(|_| ()).map_err(message),
+            },
+            ImapResponse::LabelsModified {
+                ids,
+                add,
+                remove,
+                result,
+            } => RecordedResponse::LabelsModified {
+                ids: ids.clone(),
+                add: add.clone(),
+                remove: remove.clone(),
+                result: result.as_ref().map(|_| ()).map_err(message),
+            },
+            ImapResponse::BodyResult { uid, result } => RecordedResponse::BodyResult {
+                uid: uid.clone(),
+                result: result
+                    .as_ref()
+                    .map(|body| body.text.clone())
+                    .map_err(message),
+                truncated: result.as_ref().is_ok_and(|body| body.truncated),
+                html: result.as_ref().ok().and_then(|body| body.html.clone()),
+            },
+            ImapResponse::Progress(current, total, action) => {
+                RecordedResponse::Progress(*current, *total, action.clone())
+            }
+            ImapResponse::WorkerProgress(workers) => {
+                RecordedResponse::WorkerProgress(workers.clone())
+            }
+            ImapResponse::Retrying {
+                attempt,
+                max_attempts,
+                action,
+            } => RecordedResponse::Retrying {
+                attempt: *attempt,
+                max_attempts: *max_attempts,
+                action: action.clone(),
+            },
+            ImapResponse::ServerAlert(alert) => RecordedResponse::ServerAlert(alert.clone()),
+            ImapResponse::MailboxChanged(folders) => {
+                RecordedResponse::MailboxChanged(folders.clone())
+            }
+            ImapResponse::NewMail => RecordedResponse::NewMail,
+            ImapResponse::Resynced(result) => {
+                RecordedResponse::Resynced(result.as_ref().map_err(message).cloned())
+            }
+            ImapResponse::UidValidity(values) => RecordedResponse::UidValidity(values.clone()),
+            ImapResponse::Connected => RecordedResponse::Connected,
+            ImapResponse::Error(e) => RecordedResponse::Error(e.clone()),
+        }
+    }
+}
+
+impl From<RecordedResponse> for ImapResponse {
+    fn from(response: RecordedResponse) -> Self {
+        let error = |e: String| anyhow::anyhow!(e);
+        match response {
+            RecordedResponse::Emails(result) => ImapResponse::Emails(result.map_err(error)),
+            RecordedResponse::FetchInterrupted {
+                error,
+                fetched,
+                missing_ranges,
+            } => ImapResponse::FetchInterrupted {
+                error,
+                fetched,
+                missing_ranges,
+            },
+            RecordedResponse::OlderEmails(result) => {
+                ImapResponse::OlderEmails(result.map_err(error))
+            }
+            RecordedResponse::OlderRemaining(count) => ImapResponse::OlderRemaining(count),
+            RecordedResponse::MultiArchiveResult(result) => {
+                ImapResponse::MultiArchiveResult(result.map_err(error))
+            }
+            RecordedResponse::MultiDeleteResult(result) => {
+                ImapResponse::MultiDeleteResult(result.map_err(error))
+            }
+            RecordedResponse::MultiMoveResult(result) => {
+                ImapResponse::MultiMoveResult(result.map_err(error))
+            }
+            RecordedResponse::Folders(result) => ImapResponse::Folders(result.map_err(error)),
+            RecordedResponse::AlreadyGone(count) => ImapResponse::AlreadyGone(count),
+            RecordedResponse::RestoreResult(result) => {
+                ImapResponse::RestoreResult(result.map_err(error))
+            }
+            RecordedResponse::FlagsSet { ids, seen, result } => ImapResponse::FlagsSet {
+                ids,
+                seen,
+                result: result.map_err(error),
+            },
+            RecordedResponse::LabelsModified {
+                ids,
+                add,
+                remove,
+                result,
+            } => ImapResponse::LabelsModified {
+                ids,
+                add,
+                remove,
+                result: result.map_err(error),
+            },
+            RecordedResponse::BodyResult {
+                uid,
+                result,
+                truncated,
+                html,
+            } => ImapResponse::BodyResult {
+                uid,
+                result: result
+                    .map(|text| FetchedBody {
+                        text,
+                        truncated,
+                        html,
+                    })
+                    .map_err(error),
+            },
+            RecordedResponse::Progress(current, total, action) => {
+                ImapResponse::Progress(current, total, action)
+            }
+            RecordedResponse::WorkerProgress(workers) => ImapResponse::WorkerProgress(workers),
+            RecordedResponse::Retrying {
+                attempt,
+                max_attempts,
+                action,
+            } => ImapResponse::Retrying {
+                attempt,
+                max_attempts,
+                action,
+            },
+            RecordedResponse::ServerAlert(alert) => ImapResponse::ServerAlert(alert),
+            RecordedResponse::MailboxChanged(folders) => ImapResponse::MailboxChanged(folders),
+            RecordedResponse::NewMail => ImapResponse::NewMail,
+            RecordedResponse::Resynced(result) => ImapResponse::Resynced(result.map_err(error)),
+            RecordedResponse::UidValidity(values) => ImapResponse::UidValidity(values),
+            RecordedResponse::Connected => ImapResponse::Connected,
+            RecordedResponse::Error(e) => ImapResponse::Error(e),
+        }
+    }
+}
+
+/// A recording loaded from disk
+#[derive(Debug, Deserialize)]
+pub struct Recording {
+    pub header: RecordingHeader,
+    #[serde(default)]
+    pub event: Vec<RecordedEvent>,
+}
+
+/// Events are appended one `[[event]]` table at a time so a crash keeps what came before
+#[derive(Serialize)]
+struct EventChunk<'a> {
+    event: [&'a RecordedEvent; 1],
+}
+
+#[derive(Serialize)]
+struct HeaderChunk<'a> {
+    header: &'a RecordingHeader,
+}
+
+/// Appends session events to a recording file (shared by the command and response taps)
+#[derive(Clone)]
+pub struct Recorder {
+    file: Arc<Mutex<File>>,
+    start: Instant,
+}
+
+impl Recorder {
+    /// Creates the recording file and writes the header
+    pub fn create(path: &Path, header: &RecordingHeader) -> Result<Self> {
+        let mut file = File::create(path)
+            .with_context(|| format!("Failed to create recording {}", path.display()))?;
+        let header = toml::to_string(&HeaderChunk { header })?;
+        file.write_all(header.as_bytes())?;
+
+        Ok(Self {
+            file: Arc::new(Mutex::new(file)),
+            start: Instant::now(),
+        })
+    }
+
+    /// Records a command sent to the worker
+    pub fn command(&self, command: &ImapCommand) {
+        self.write(RecordedEvent {
+            at_ms: self.elapsed_ms(),
+            command: Some(command.describe()),
+            response: None,
+        });
+    }
+
+    /// Records a response sent back to the UI
+    pub fn response(&self, response: &ImapResponse) {
+        self.write(RecordedEvent {
+            at_ms: self.elapsed_ms(),
+            command: None,
+            response: Some(response.into()),
+        });
+    }
+
+    fn elapsed_ms(&self) -> u64 {
+        self.start.elapsed().as_millis() as u64
+    }
+
+    fn write(&self, event: RecordedEvent) {
+        let chunk = match toml::to_string(&EventChunk { event: [&event] }) {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                debug_log!("Recording: failed to serialize event: {}", e);
+                return;
+            }
+        };
+        if let Ok(mut file) = self.file.lock()
+            && let Err(e) = file
+                .write_all(format!("\n{}", chunk).as_bytes())
+                .and_then(|_| file.flush())
+        {
+            debug_log!("Recording: failed to write event: {}", e);
+        }
+    }
+}
+
+/// Loads a recording written by `Recorder`
+pub fn load(path: &Path) -> Result<Recording> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read recording {}", path.display()))?;
+    toml::from_str(&contents)
+        .with_context(|| format!("Failed to parse recording {}", path.display()))
+}
+
+/// Forwards messages from `rx` to `tx`, recording each one on the way through
+pub fn spawn_tap<T: Send + 'static>(
+    rx: mpsc::Receiver<T>,
+    tx: mpsc::Sender<T>,
+    recorder: Recorder,
+    record: fn(&Recorder, &T),
+) {
+    thread::spawn(move || {
+        for message in rx {
+            record(&recorder, &message);
+            if tx.send(message).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+/// Stands in for the IMAP worker, replaying recorded responses with their original timing.
+/// Where the user issued a command, replay waits for the UI to send one, so the person
+/// debugging drives the session with the same keys.
+pub fn spawn_replay(
+    cmd_rx: mpsc::Receiver<ImapCommand>,
+    resp_tx: mpsc::Sender<ImapResponse>,
+    events: Vec<RecordedEvent>,
+) {
+    thread::spawn(move || {
+        let mut last_at_ms = 0;
+        for event in events {
+            if let Some(expected) = event.command {
+                match cmd_rx.recv() {
+                    Ok(ImapCommand::Shutdown) | Err(_) => return,
+                    Ok(command) => {
+                        let actual = command.describe();
+                        if command_name(&actual) != command_name(&expected) {
+                            debug_log!("Replay: expected {}, got {}", expected, actual);
+                        }
+                    }
+                }
+                last_at_ms = event.at_ms;
+            } else if let Some(response) = event.response {
+                thread::sleep(Duration::from_millis(
+                    event.at_ms.saturating_sub(last_at_ms),
+                ));
+                last_at_ms = event.at_ms;
+                if resp_tx.send(response.into()).is_err() {
+                    return;
+                }
+            }
+        }
+
+        debug_log!("Replay: end of recording");
+        // Nothing left to replay; ignore further commands until shutdown
+        for command in cmd_rx {
+            if matches!(command, ImapCommand::Shutdown) {
+                break;
+            }
+        }
+    });
+}
+
+/// Returns the command name from a description like "ArchiveMultiple 3"
+fn command_name(description: &str) -> &str {
+    description.split_whitespace().next().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::email::EmailBuilder;
+
+    fn header() -> RecordingHeader {
+        let config: Config = toml::from_str("[accounts]\n").unwrap();
+        RecordingHeader {
+            account: "personal".to_string(),
+            user_email: "me@example.com".to_string(),
+            config,
+        }
+    }
+
+    #[test]
+    fn test_recording_round_trip() {
+        let path = std::env::temp_dir().join(format!(
+            "zeroterm-recording-{}-round-trip.toml",
+            std::process::id()
+        ));
+        let recorder = Recorder::create(&path, &header()).unwrap();
+
+        let email = EmailBuilder::new()
+            .id("1")
+            .from("Alice <alice@example.com>")
+            .subject("Hello")
+            .gmail_thread_id(42)
+            .build();
+        recorder.response(&ImapResponse::Connected);
+        recorder.command(&ImapCommand::FetchInbox {
+            parallel_connections: 5,
+            merge_subject_threads: false,
+            since: None,
+            limit: None,
+        });
+        recorder.response(&ImapResponse::Emails(Ok(vec![email.clone()])));
+        recorder.command(&ImapCommand::ArchiveMultiple(vec![(
+            "1".to_string(),
+            "INBOX".to_string(),
+        )]));
+        recorder.response(&ImapResponse::MultiArchiveResult(Err(anyhow::anyhow!(
+            "connection reset"
+        ))));
+
+        let recording = load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(recording.header.user_email, "me@example.com");
+        assert_eq!(recording.event.len(), 5);
+        assert_eq!(recording.event[1].command.as_deref(), Some("FetchInbox"));
+        assert_eq!(
+            recording.event[3].command.as_deref(),
+            Some("ArchiveMultiple 1")
+        );
+
+        let responses: Vec<ImapResponse> = recording
+            .event
+            .into_iter()
+            .filter_map(|e| e.response.map(ImapResponse::from))
+            .collect();
+        assert!(matches!(responses[0], ImapResponse::Connected));
+        match &responses[1] {
+            ImapResponse::Emails(Ok(emails)) => assert_eq!(emails, &vec![email]),
+            _ => panic!("expected emails"),
+        }
+        match &responses[2] {
+            ImapResponse::MultiArchiveResult(Err(e)) => {
+                assert_eq!(e.to_string(), "connection reset")
+            }
+            _ => panic!("expected archive error"),
+        }
+    }
+
+    #[test]
+    fn test_resync_round_trip() {
+        let resync = MailboxResync {
+            removed: vec![("7".to_string(), "INBOX".to_string())],
+            added: vec![
+                EmailBuilder::new()
+                    .id("9")
+                    .from("Alice <alice@example.com>")
+                    .build(),
+            ],
+        };
+        let recorded = RecordedResponse::from(&ImapResponse::Resynced(Ok(resync.clone())));
+        let text = toml::to_string(&RecordedEvent {
+            at_ms: 0,
+            command: None,
+            response: Some(recorded),
+        })
+        .unwrap();
+        let event: RecordedEvent = toml::from_str(&text).unwrap();
+        match event.response.map(ImapResponse::from) {
+            Some(ImapResponse::Resynced(Ok(replayed))) => {
+                assert_eq!(replayed.removed, resync.removed);
+                assert_eq!(replayed.added.len(), 1);
+                assert_eq!(replayed.added[0].id, "9");
+            }
+            _ => panic!("expected a resync result"),
+        }
+    }
+
+    #[test]
+    fn test_interrupted_fetch_round_trip() {
+        let path = std::env::temp_dir().join(format!(
+            "zeroterm-recording-{}-interrupted.toml",
+            std::process::id()
+        ));
+        let recorder = Recorder::create(&path, &header()).unwrap();
+        recorder.response(&ImapResponse::FetchInterrupted {
+            error: "connection reset".to_string(),
+            fetched: 8000,
+            missing_ranges: 2,
+        });
+        recorder.command(&ImapCommand::RetryFetch);
+
+        let mut recording = load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(recording.event[1].command.as_deref(), Some("RetryFetch"));
+        let response = recording.event[0].response.take().map(ImapResponse::from);
+        match response {
+            Some(ImapResponse::FetchInterrupted {
+                error,
+                fetched,
+                missing_ranges,
+            }) => {
+                assert_eq!(error, "connection reset");
+                assert_eq!((fetched, missing_ranges), (8000, 2));
+            }
+            _ => panic!("expected an interrupted fetch"),
+        }
+    }
+
+    #[test]
+    fn test_replay_waits_for_commands() {
+        let events = vec![
+            RecordedEvent {
+                at_ms: 0,
+                command: None,
+                response: Some(RecordedResponse::Connected),
+            },
+            RecordedEvent {
+                at_ms: 5,
+                command: Some("FetchInbox".to_string()),
+                response: None,
+            },
+            RecordedEvent {
+                at_ms: 10,
+                command: None,
+                response: Some(RecordedResponse::Emails(Ok(Vec::new()))),
+            },
+        ];
+        let (cmd_tx, cmd_rx) = mpsc::channel();
+        let (resp_tx, resp_rx) = mpsc::channel();
+        spawn_replay(cmd_rx, resp_tx, events);
+
+        let timeout = Duration::from_secs(1);
+        assert!(matches!(
+            resp_rx.recv_timeout(timeout),
+            Ok(ImapResponse::Connected)
+        ));
+        // Emails only arrive after the UI asks for them
+        assert!(resp_rx.recv_timeout(Duration::from_millis(50)).is_err());
+        cmd_tx
+            .send(ImapCommand::FetchInbox {
+                parallel_connections: 1,
+                merge_subject_threads: false,
+                since: None,
+                limit: None,
+            })
+            .unwrap();
+        assert!(matches!(
+            resp_rx.recv_timeout(timeout),
+            Ok(ImapResponse::Emails(Ok(_)))
+        ));
+        cmd_tx.send(ImapCommand::Shutdown).unwrap();
+    }
+}
@@ -0,0 +1,221 @@
+//! Saves where the user was on quit so `--resume` can reopen there, stored locally per account
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::app::{App, GroupMode, ThreadFilter, View};
+use crate::config;
+
+const RESUME_DIR: &str = "resume";
+
+/// The view, selection, filters and scroll positions at quit.
+/// Groups, threads and emails are saved by key rather than index so they can be
+/// found again in freshly fetched mail.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResumeState {
+    pub view: View,
+    /// View the email body was opened from
+    pub body_from: Option<View>,
+    pub group_mode: GroupMode,
+    pub thread_filter: ThreadFilter,
+    pub thread_inline_bodies: bool,
+    pub group_filter: Option<String>,
+    pub email_filter: Option<String>,
+    pub group_key: Option<String>,
+    pub thread_id: Option<String>,
+    pub thread_email_id: Option<String>,
+    pub viewing_email_id: Option<String>,
+    pub group_scroll_offset: usize,
+    pub thread_body_scroll: usize,
+    pub text_view_scroll: usize,
+}
+
+impl ResumeState {
+    /// Captures the current position; undo history and duplicates resume at the group list
+    pub fn capture(app: &App, group_scroll_offset: usize) -> Self {
+        let view = match app.view {
+            View::UndoHistory | View::Duplicates => View::GroupList,
+            view => view,
+        };
+        Self {
+            view,
+            body_from: (view == View::EmailBody)
+                .then(|| app.previous_view())
+                .flatten(),
+            group_mode: app.group_mode,
+            thread_filter: app.thread_filter,
+            thread_inline_bodies: app.thread_inline_bodies,
+            group_filter: app.group_text_filter().map(String::from),
+            email_filter: app.email_text_filter().map(String::from),
+            group_key: app.current_group().map(|g| g.key.clone()),
+            thread_id: app.current_email().map(|e| e.thread_id.clone()),
+            thread_email_id: app.current_thread_email().map(|e| e.id.clone()),
+            viewing_email_id: app.viewing_email_id().map(String::from),
+            group_scroll_offset,
+            thread_body_scroll: app.thread_body_scroll,
+            text_view_scroll: app.text_view_scroll,
+        }
+    }
+
+    /// Reapplies the saved position to freshly loaded mail, going as deep as the
+    /// saved group, thread and email still exist. Returns true if it got all the way.
+    pub fn apply(&self, app: &mut App) -> bool {
+        if app.group_mode != self.group_mode {
+            app.set_group_mode(self.group_mode);
+        }
+        app.thread_filter = self.thread_filter;
+        app.thread_inline_bodies = self.thread_inline_bodies;
+        app.set_group_text_filter(self.group_filter.clone());
+
+        let Some(key) = &self.group_key else {
+            return true;
+        };
+        let Some(index) = app.groups.iter().position(|g| &g.key == key) else {
+            return false;
+        };
+        app.selected_group = index;
+        app.ensure_valid_selection();
+        if app.current_group().is_none_or(|g| &g.key != key) {
+            return false;
+        }
+        if self.view == View::GroupList {
+            return true;
+        }
+
+        app.enter();
+        app.set_email_text_filter(self.email_filter.clone());
+        let Some(thread_id) = &self.thread_id else {
+            return true;
+        };
+        let Some(position) = app
+            .filtered_threads_in_current_group()
+            .iter()
+            .position(|e| &e.thread_id == thread_id)
+        else {
+            return false;
+        };
+        app.selected_email = Some(position);
+        let wants_thread = self.view == View::Thread || self.body_from == Some(View::Thread);
+        if self.view == View::EmailList {
+            return true;
+        }
+
+        if wants_thread {
+            app.enter();
+            if let Some(id) = &self.thread_email_id {
+                let Some(position) = app.current_thread_emails().iter().position(|e| &e.id == id)
+                else {
+                    return false;
+                };
+                app.selected_thread_email = Some(position);
+            }
+            app.thread_body_scroll = self.thread_body_scroll;
+        }
+        if self.view == View::Thread {
+            return true;
+        }
+
+        let Some(id) = &self.viewing_email_id else {
+            return true;
+        };
+        if !app.current_thread_emails().iter().any(|e| &e.id == id) {
+            return false;
+        }
+        app.enter_text_view(id);
+        app.text_view_scroll = self.text_view_scroll;
+        true
+    }
+
+    /// Loads the saved state from a file
+    pub fn load(path: &Path) -> Result<Self> {
+        config::load_data_file(path)
+    }
+
+    /// Writes the state to a file
+    pub fn save(&self, path: &Path) -> Result<()> {
+        config::save_data_file(path, self)
+    }
+}
+
+/// Returns the resume file for an account: ~/.config/zeroterm/resume/<account>.toml
+pub fn resume_path(account: &str) -> Result<PathBuf> {
+    config::account_data_path(RESUME_DIR, account)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::email::{EmailBuilder, build_thread_ids};
+
+    fn app() -> App {
+        let mut emails = vec![
+            EmailBuilder::new()
+                .id("1")
+                .from("alice@example.com")
+                .subject("Lunch")
+                .build(),
+            EmailBuilder::new()
+                .id("2")
+                .from("bob@example.com")
+                .subject("Report")
+                .build(),
+            EmailBuilder::new()
+                .id("3")
+                .from("bob@example.com")
+                .subject("Invoice")
+                .build(),
+        ];
+        build_thread_ids(&mut emails);
+        let mut app = App::new();
+        app.set_emails(emails);
+        app
+    }
+
+    fn open_bob_invoice(app: &mut App) {
+        app.selected_group = app
+            .groups
+            .iter()
+            .position(|g| g.key == "bob@example.com")
+            .unwrap();
+        app.enter();
+        app.selected_email = app
+            .filtered_threads_in_current_group()
+            .iter()
+            .position(|e| e.id == "3");
+        app.enter();
+        app.enter_text_view("3");
+        app.text_view_scroll = 7;
+    }
+
+    #[test]
+    fn test_resume_reopens_email_body() {
+        let mut before = app();
+        open_bob_invoice(&mut before);
+        let state = ResumeState::capture(&before, 2);
+        assert_eq!(state.view, View::EmailBody);
+        assert_eq!(state.body_from, Some(View::Thread));
+
+        let mut after = app();
+        assert!(state.apply(&mut after));
+        assert_eq!(after.view, View::EmailBody);
+        assert_eq!(after.viewing_email_id(), Some("3"));
+        assert_eq!(after.current_group().unwrap().key, "bob@example.com");
+        assert_eq!(after.text_view_scroll, 7);
+        assert_eq!(after.previous_view(), Some(View::Thread));
+    }
+
+    #[test]
+    fn test_resume_stops_where_mail_is_gone() {
+        let mut before = app();
+        open_bob_invoice(&mut before);
+        let state = ResumeState::capture(&before, 0);
+
+        let mut after = app();
+        after.remove_email("3");
+        assert!(!state.apply(&mut after));
+        assert_eq!(after.view, View::EmailList);
+        assert_eq!(after.current_group().unwrap().key, "bob@example.com");
+    }
+}
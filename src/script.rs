@@ -0,0 +1,237 @@
+//! `[[action]]` rules: rhai scripts bound to keys. A script sees the email and group
+//! under the cursor and asks for built-in operations, which zeroterm then carries out
+//! the same way as the matching keys.
+
+use anyhow::{Result, anyhow};
+use rhai::{Dynamic, Engine, Map, Scope};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::app::View;
+use crate::email::Email;
+
+/// Stops scripts that loop forever instead of freezing the UI
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// A `[[action]]` from config
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CustomAction {
+    /// The key that runs the script; built-in keys take precedence
+    pub key: char,
+    /// What the action does, shown when the script fails (default: none)
+    pub description: Option<String>,
+    /// The rhai source
+    pub script: String,
+}
+
+impl CustomAction {
+    /// Checks that the script parses, so mistakes show up at startup
+    pub fn validate(&self) -> Result<()> {
+        Engine::new()
+            .compile(&self.script)
+            .map(|_| ())
+            .map_err(|e| anyhow!("{}", e))
+    }
+
+    /// Names the action in messages: its description, or the key
+    pub fn label(&self) -> String {
+        self.description
+            .clone()
+            .unwrap_or_else(|| format!("action {}", self.key))
+    }
+}
+
+/// A built-in operation a script asked for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Operation {
+    /// Archive the cursor thread, or the selected threads (like `a`)
+    Archive,
+    /// Delete the cursor thread, or the selected threads (like `d`)
+    Delete,
+    /// Archive all visible mail from the sender, with confirmation (like `A`)
+    ArchiveGroup,
+    /// Delete all visible mail from the sender, with confirmation (like `D`)
+    DeleteGroup,
+    /// Filter the current list (like `/`)
+    Filter(String),
+    /// Show a message in the status line
+    Status(String),
+}
+
+/// What the script can see
+pub struct Context<'a> {
+    pub view: View,
+    /// The email under the cursor
+    pub email: Option<&'a Email>,
+    /// The group under the cursor: (key, email count)
+    pub group: Option<(&'a str, usize)>,
+}
+
+fn email_map(email: &Email) -> Map {
+    let mut map = Map::new();
+    map.insert("id".into(), email.id.clone().into());
+    map.insert("thread_id".into(), email.thread_id.clone().into());
+    map.insert("from".into(), email.from.to_string().into());
+    map.insert("from_email".into(), email.from_email.to_string().into());
+    map.insert("from_domain".into(), email.from_domain.to_string().into());
+    map.insert("subject".into(), email.subject.clone().into());
+    map.insert("snippet".into(), email.snippet.clone().into());
+    map.insert("date".into(), email.date.to_rfc3339().into());
+    map.insert("folder".into(), email.source_folder.to_string().into());
+    map.insert("seen".into(), email.seen.into());
+    map.insert("flagged".into(), email.flagged.into());
+    map.insert("mailing_list".into(), email.mailing_list.into());
+    map.insert(
+        "spam_score".into(),
+        email
+            .spam_score
+            .map_or(Dynamic::UNIT, |s| Dynamic::from_float(f64::from(s))),
+    );
+    map
+}
+
+/// Runs a script and returns the operations it asked for, in order
+pub fn run(script: &str, context: &Context) -> Result<Vec<Operation>> {
+    let operations = Rc::new(RefCell::new(Vec::new()));
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    // Scripts must not write over the UI
+    engine.on_print(|_| {});
+    engine.on_debug(|_, _, _| {});
+
+    let record = |op: Operation| {
+        let operations = Rc::clone(&operations);
+        move || operations.borrow_mut().push(op.clone())
+    };
+    engine.register_fn("archive", record(Operation::Archive));
+    engine.register_fn("delete", record(Operation::Delete));
+    engine.register_fn("archive_group", record(Operation::ArchiveGroup));
+    engine.register_fn("delete_group", record(Operation::DeleteGroup));
+    let ops = Rc::clone(&operations);
+    engine.register_fn("filter", move |query: &str| {
+        ops.borrow_mut().push(Operation::Filter(query.to_string()))
+    });
+    let ops = Rc::clone(&operations);
+    engine.register_fn("status", move |message: &str| {
+        ops.borrow_mut()
+            .push(Operation::Status(message.to_string()))
+    });
+
+    let mut scope = Scope::new();
+    scope.push_constant("view", context.view.name());
+    scope.push_constant(
+        "email",
+        context
+            .email
+            .map_or(Dynamic::UNIT, |e| Dynamic::from_map(email_map(e))),
+    );
+    scope.push_constant(
+        "group",
+        context.group.map_or(Dynamic::UNIT, |(key, count)| {
+            let mut map = Map::new();
+            map.insert("key".into(), key.to_string().into());
+            map.insert("count".into(), (count as i64).into());
+            Dynamic::from_map(map)
+        }),
+    );
+
+    engine
+        .run_with_scope(&mut scope, script)
+        .map_err(|e| anyhow!("{}", e))?;
+    drop(engine);
+    Ok(Rc::try_unwrap(operations)
+        .map(RefCell::into_inner)
+        .unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::email::EmailBuilder;
+
+    fn context(email: &Email) -> Context<'_> {
+        Context {
+            view: View::EmailList,
+            email: Some(email),
+            group: Some(("news@example.com", 12)),
+        }
+    }
+
+    #[test]
+    fn test_script_sees_the_email_and_asks_for_operations() {
+        let email = EmailBuilder::new()
+            .from("news@example.com")
+            .subject("Weekly digest")
+            .build();
+        let script = r#"
+            if email.subject.contains("digest") && group.count > 10 {
+                archive_group();
+            } else {
+                archive();
+            }
+            status(`${view}: ${email.from_domain}`);
+        "#;
+        assert_eq!(
+            run(script, &context(&email)).unwrap(),
+            vec![
+                Operation::ArchiveGroup,
+                Operation::Status("email_list: example.com".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_readme_example_keeps_unread_mail() {
+        let script = r#"
+            if email == () { return; }
+            if email.mailing_list && group.count > 20 {
+                archive_group();
+            } else if email.seen {
+                archive();
+            } else {
+                status(`${email.from}: still unread, keeping it`);
+            }
+        "#;
+        let mut email = EmailBuilder::new().from("alice@example.com").build();
+        assert_eq!(
+            run(script, &context(&email)).unwrap(),
+            vec![Operation::Status(
+                "alice@example.com: still unread, keeping it".to_string()
+            )]
+        );
+        email.seen = true;
+        assert_eq!(
+            run(script, &context(&email)).unwrap(),
+            vec![Operation::Archive]
+        );
+    }
+
+    #[test]
+    fn test_script_without_an_email_sees_unit() {
+        let ctx = Context {
+            view: View::GroupList,
+            email: None,
+            group: None,
+        };
+        assert_eq!(
+            run(r#"if email == () { filter("unread"); }"#, &ctx).unwrap(),
+            vec![Operation::Filter("unread".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_runaway_and_broken_scripts_are_errors() {
+        let email = EmailBuilder::new().from("a@example.com").build();
+        assert!(run("loop { }", &context(&email)).is_err());
+        assert!(run("no_such_function()", &context(&email)).is_err());
+
+        let broken = CustomAction {
+            key: 'X',
+            description: None,
+            script: "if {".to_string(),
+        };
+        assert!(broken.validate().is_err());
+    }
+}
@@ -0,0 +1,128 @@
+//! Sends replies through the account's SMTP server with curl, signing in with the
+//! account's address and app_password
+
+use anyhow::Result;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::compose::{private_temp_dir, write_private};
+use crate::config::AccountConfig;
+use crate::http::{self, quote};
+
+/// Port that connects with TLS straight away; every other port upgrades with STARTTLS
+const IMPLICIT_TLS_PORT: u16 = 465;
+
+/// Where and how to send mail for an account
+#[derive(Clone)]
+pub struct SmtpServer {
+    host: String,
+    port: u16,
+    user: String,
+    password: String,
+    ca_file: Option<PathBuf>,
+    accept_invalid_certs: bool,
+}
+
+impl SmtpServer {
+    pub fn for_account(account: &AccountConfig) -> Self {
+        Self {
+            host: account.smtp_host().to_string(),
+            port: account.smtp_port(),
+            user: account.email.clone(),
+            password: account.app_password.clone(),
+            ca_file: account.tls_ca_file.clone(),
+            accept_invalid_certs: account.danger_accept_invalid_certs,
+        }
+    }
+
+    /// The address mail is sent from
+    pub fn from(&self) -> &str {
+        &self.user
+    }
+
+    /// Builds the curl config that uploads the message in `message_path` to `recipients`
+    fn curl_config(&self, recipients: &[String], message_path: &Path) -> String {
+        let scheme = if self.port == IMPLICIT_TLS_PORT {
+            "smtps"
+        } else {
+            "smtp"
+        };
+        let mut config = format!(
+            "url = {}\nuser = {}\nmail-from = {}\n",
+            quote(&format!("{}://{}:{}", scheme, self.host, self.port)),
+            quote(&format!("{}:{}", self.user, self.password)),
+            quote(&self.user)
+        );
+        for recipient in recipients {
+            config.push_str(&format!("mail-rcpt = {}\n", quote(recipient)));
+        }
+        config.push_str(&format!(
+            "upload-file = {}\n",
+            quote(&message_path.to_string_lossy())
+        ));
+        if scheme == "smtp" {
+            config.push_str("ssl-reqd\n");
+        }
+        if let Some(ca_file) = &self.ca_file {
+            config.push_str(&format!("cacert = {}\n", quote(&ca_file.to_string_lossy())));
+        }
+        if self.accept_invalid_certs {
+            config.push_str("insecure\n");
+        }
+        config
+    }
+
+    /// Sends an RFC 5322 message to the recipients. The message goes through a file only
+    /// the user can read, as curl's stdin carries the config with the password.
+    pub fn send(&self, recipients: &[String], message: &str) -> Result<()> {
+        let path = private_temp_dir()?.join(format!("outgoing-{}.eml", std::process::id()));
+        write_private(&path, message)?;
+        let result = http::send(&self.curl_config(recipients, &path), "SMTP");
+        let _ = fs::remove_file(&path);
+        result.map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(toml: &str) -> AccountConfig {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn test_gmail_sends_over_tls_with_the_app_password() {
+        let server = SmtpServer::for_account(&account(
+            "backend = \"gmail\"\nemail = \"me@gmail.com\"\napp_password = \"abcd efgh\"",
+        ));
+        let config = server.curl_config(
+            &[
+                "alice@example.com".to_string(),
+                "bob@example.com".to_string(),
+            ],
+            Path::new("/tmp/out.eml"),
+        );
+        assert_eq!(
+            config,
+            "url = \"smtps://smtp.gmail.com:465\"\n\
+             user = \"me@gmail.com:abcd efgh\"\n\
+             mail-from = \"me@gmail.com\"\n\
+             mail-rcpt = \"alice@example.com\"\n\
+             mail-rcpt = \"bob@example.com\"\n\
+             upload-file = \"/tmp/out.eml\"\n"
+        );
+    }
+
+    #[test]
+    fn test_other_ports_require_starttls() {
+        let server = SmtpServer::for_account(&account(
+            "backend = \"outlook\"\nemail = \"me@outlook.com\"\napp_password = \"pw\"\n\
+             smtp_host = \"mail.example.com\"\ndanger_accept_invalid_certs = true",
+        ));
+        let config = server.curl_config(&[], Path::new("/tmp/out.eml"));
+        assert!(config.starts_with("url = \"smtp://mail.example.com:587\"\n"));
+        assert!(config.contains("\nssl-reqd\n"));
+        assert!(config.ends_with("\ninsecure\n"));
+    }
+}
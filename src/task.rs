@@ -0,0 +1,107 @@
+//! Turns an email into a task by running the configured task_command
+
+use anyhow::{Context, Result, bail};
+use std::process::{Command, Stdio};
+
+use crate::email::Email;
+
+/// Splits a command template into arguments, honoring single and double quotes
+pub fn split_args(template: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut quote: Option<char> = None;
+    for c in template.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => current.push(c),
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                in_arg = true;
+            }
+            None if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            None => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if in_arg {
+        args.push(current);
+    }
+    args
+}
+
+/// Builds the task command's arguments for an email, with `link` its webmail link.
+/// {subject}, {from} and {link} are filled in after splitting, so the email's
+/// text always stays inside its argument and never reaches a shell.
+pub fn task_args(template: &str, email: &Email, link: &str) -> Vec<String> {
+    split_args(template)
+        .into_iter()
+        .map(|arg| {
+            arg.replace("{subject}", &email.subject)
+                .replace("{from}", &email.from)
+                .replace("{link}", link)
+        })
+        .collect()
+}
+
+/// Runs the task command for an email and waits for it to finish
+pub fn create_task(template: &str, email: &Email, link: &str) -> Result<()> {
+    let args = task_args(template, email, link);
+    let Some((program, rest)) = args.split_first() else {
+        bail!("task_command is empty");
+    };
+    let output = Command::new(program)
+        .args(rest)
+        .stdin(Stdio::null())
+        .output()
+        .with_context(|| format!("Failed to run {}", program))?;
+    if !output.status.success() {
+        bail!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::email::EmailBuilder;
+
+    #[test]
+    fn test_split_args_honors_quotes() {
+        assert_eq!(
+            split_args(r#"todoist add "{subject} ({from})" --label 'e mail'"#),
+            vec!["todoist", "add", "{subject} ({from})", "--label", "e mail"]
+        );
+        assert_eq!(split_args(r#"cmd "" x"#), vec!["cmd", "", "x"]);
+    }
+
+    #[test]
+    fn test_task_args_keep_email_text_in_one_argument() {
+        let email = EmailBuilder::new()
+            .id("1")
+            .from("Alice <alice@example.com>")
+            .subject("Pay invoice; rm -rf ~")
+            .message_id("<inv@example.com>")
+            .build();
+        let link = crate::email::gmail_link("<inv@example.com>", "me@gmail.com");
+        let args = task_args("task add {subject} +email", &email, &link);
+        assert_eq!(args, vec!["task", "add", "Pay invoice; rm -rf ~", "+email"]);
+
+        let args = task_args("todo '{from}: {link}'", &email, &link);
+        assert_eq!(
+            args[1],
+            "Alice <alice@example.com>: https://mail.google.com/mail/u/me@gmail.com/#search/rfc822msgid:%3Cinv%40example.com%3E"
+        );
+    }
+}
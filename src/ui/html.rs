@@ -0,0 +1,230 @@
+//! Structured rendering of HTML email bodies for the email view: headings and emphasis
+//! styled, and links numbered in the text with their addresses listed at the end
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use html2text::render::text_renderer::{RichAnnotation, TaggedLine, TextDecorator};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+
+use crate::imap_client::sanitize_for_terminal;
+
+/// Links in the order they first appear, shared by the decorators of nested blocks
+/// (table cells, list items) so the numbering runs through the whole email
+#[derive(Debug, Default)]
+struct LinkList {
+    urls: Vec<String>,
+    /// Number of the link being rendered, written after its text
+    current: usize,
+}
+
+/// Marks links with their number and keeps the rest of the markup as annotations
+#[derive(Clone, Default)]
+struct NumberedLinks {
+    links: Rc<RefCell<LinkList>>,
+}
+
+impl TextDecorator for NumberedLinks {
+    type Annotation = RichAnnotation;
+
+    fn decorate_link_start(&mut self, url: &str) -> (String, Self::Annotation) {
+        let mut links = self.links.borrow_mut();
+        // A link repeated later (a logo and a "read more" to the same page) keeps its number
+        links.current = match links.urls.iter().position(|u| u == url) {
+            Some(index) => index + 1,
+            None => {
+                links.urls.push(url.to_string());
+                links.urls.len()
+            }
+        };
+        (String::new(), RichAnnotation::Link(url.to_string()))
+    }
+
+    fn decorate_link_end(&mut self) -> String {
+        format!("[{}]", self.links.borrow().current)
+    }
+
+    fn decorate_em_start(&self) -> (String, Self::Annotation) {
+        (String::new(), RichAnnotation::Emphasis)
+    }
+
+    fn decorate_em_end(&self) -> String {
+        String::new()
+    }
+
+    fn decorate_strong_start(&self) -> (String, Self::Annotation) {
+        (String::new(), RichAnnotation::Strong)
+    }
+
+    fn decorate_strong_end(&self) -> String {
+        String::new()
+    }
+
+    fn decorate_strikeout_start(&self) -> (String, Self::Annotation) {
+        (String::new(), RichAnnotation::Strikeout)
+    }
+
+    fn decorate_strikeout_end(&self) -> String {
+        String::new()
+    }
+
+    fn decorate_code_start(&self) -> (String, Self::Annotation) {
+        (String::new(), RichAnnotation::Code)
+    }
+
+    fn decorate_code_end(&self) -> String {
+        String::new()
+    }
+
+    fn decorate_preformat_first(&self) -> Self::Annotation {
+        RichAnnotation::Preformat(false)
+    }
+
+    fn decorate_preformat_cont(&self) -> Self::Annotation {
+        RichAnnotation::Preformat(true)
+    }
+
+    fn decorate_image(&mut self, src: &str, title: &str) -> (String, Self::Annotation) {
+        let text = if title.is_empty() {
+            String::new()
+        } else {
+            format!("[image: {}]", title)
+        };
+        (text, RichAnnotation::Image(src.to_string()))
+    }
+
+    fn header_prefix(&self, level: usize) -> String {
+        "#".repeat(level) + " "
+    }
+
+    fn quote_prefix(&self) -> String {
+        "> ".to_string()
+    }
+
+    fn unordered_item_prefix(&self) -> String {
+        "• ".to_string()
+    }
+
+    fn ordered_item_prefix(&self, i: i64) -> String {
+        format!("{}. ", i)
+    }
+
+    fn make_subblock_decorator(&self) -> Self {
+        self.clone()
+    }
+
+    fn finalise(&mut self, _links: Vec<String>) -> Vec<TaggedLine<Self::Annotation>> {
+        Vec::new()
+    }
+}
+
+/// The style for a run of text, from its annotations outermost first
+fn annotation_style(annotations: &[RichAnnotation]) -> Style {
+    annotations
+        .iter()
+        .fold(Style::default(), |style, annotation| match annotation {
+            RichAnnotation::Link(_) => style.fg(Color::Cyan).add_modifier(Modifier::UNDERLINED),
+            RichAnnotation::Image(_) => style.fg(Color::DarkGray),
+            RichAnnotation::Emphasis => style.add_modifier(Modifier::ITALIC),
+            RichAnnotation::Strong => style.add_modifier(Modifier::BOLD),
+            RichAnnotation::Strikeout => style.add_modifier(Modifier::CROSSED_OUT),
+            RichAnnotation::Code | RichAnnotation::Preformat(_) => style.fg(Color::Yellow),
+            _ => style,
+        })
+}
+
+/// Renders an HTML body wrapped to `width` columns, with a numbered list of its links
+/// after the text. Returns None when the HTML can't be laid out at that width.
+pub fn render(html: &str, width: usize) -> Option<Vec<Line<'static>>> {
+    let decorator = NumberedLinks::default();
+    let links = Rc::clone(&decorator.links);
+    let tagged = html2text::config::with_decorator(decorator)
+        .lines_from_read(html.as_bytes(), width.max(1))
+        .ok()?;
+
+    let mut lines: Vec<Line<'static>> = tagged
+        .iter()
+        .map(|line| {
+            let spans: Vec<Span<'static>> = line
+                .tagged_strings()
+                .map(|run| Span::styled(sanitize_for_terminal(&run.s), annotation_style(&run.tag)))
+                .collect();
+            let heading = spans
+                .first()
+                .is_some_and(|span| span.content.starts_with('#'));
+            let line = Line::from(spans);
+            if heading {
+                line.style(
+                    Style::default()
+                        .fg(Color::Magenta)
+                        .add_modifier(Modifier::BOLD),
+                )
+            } else {
+                line
+            }
+        })
+        .collect();
+
+    let urls = std::mem::take(&mut links.borrow_mut().urls);
+    if !urls.is_empty() {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "Links:",
+            Style::default().add_modifier(Modifier::BOLD),
+        )));
+        for (i, url) in urls.iter().enumerate() {
+            lines.push(Line::from(vec![
+                Span::styled(format!("[{}] ", i + 1), Style::default().fg(Color::Cyan)),
+                Span::styled(
+                    sanitize_for_terminal(url),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ]));
+        }
+    }
+    Some(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plain(lines: &[Line]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn test_render_numbers_links_and_styles_headings() {
+        let html = concat!(
+            "<h1>Weekly digest</h1>",
+            "<p>Read <a href=\"https://example.com/a\">the post</a> or ",
+            "<a href=\"https://example.com/b\">the <b>other</b> one</a>.</p>",
+            "<p><a href=\"https://example.com/a\">Again</a></p>",
+        );
+        let lines = render(html, 60).unwrap();
+        let text = plain(&lines);
+
+        assert_eq!(text[0], "# Weekly digest");
+        assert!(lines[0].style.add_modifier.contains(Modifier::BOLD));
+        assert!(
+            text.iter()
+                .any(|l| l == "Read the post[1] or the other one[2].")
+        );
+        assert!(text.iter().any(|l| l == "Again[1]"));
+        assert_eq!(
+            &text[text.len() - 3..],
+            [
+                "Links:",
+                "[1] https://example.com/a",
+                "[2] https://example.com/b"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_strips_terminal_escapes() {
+        let lines = render("<p>safe\x1b[31m red</p>", 40).unwrap();
+        assert!(plain(&lines).iter().all(|l| !l.contains('\x1b')));
+    }
+}
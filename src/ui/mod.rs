@@ -1,2 +1,4 @@
+pub mod html;
 pub mod render;
+pub mod title;
 pub mod widgets;
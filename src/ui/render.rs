@@ -1,15 +1,17 @@
 use ratatui::{
     Frame,
-    layout::{Constraint, Direction, Layout},
+    layout::{Constraint, Direction, Layout, Rect},
     widgets::{Block, Borders, TableState},
 };
 
 use crate::app::{App, View};
 use crate::ui::widgets::{
-    AccountSelectWidget, AccountSelection, BusyModalWidget, ConfirmDialogWidget, EmailListWidget,
-    FilterBarWidget, GroupListWidget, HelpBarWidget, HelpMenuWidget, InboxZeroWidget,
-    PassiveFilterBarWidget, StatusModalWidget, TextViewWidget, ThreadViewWidget, UiState,
-    UndoHistoryWidget, help_text_for_app,
+    AccountSelectWidget, AccountSelection, BusyModalWidget, ConfirmDialogWidget, DuplicatesWidget,
+    EmailListWidget, FilterBarWidget, ForwardPromptWidget, GroupListWidget, HelpBarWidget,
+    HelpMenuWidget, InboxZeroWidget, NoteBarWidget, PassiveFilterBarWidget, PickerWidget,
+    ProcessingRateWidget, StatusHistoryWidget, StatusModalWidget, TextViewWidget, ThreadViewWidget,
+    ToastWidget, TourWidget, TriageTimerWidget, UiState, UndoHistoryWidget, affected_emails,
+    confirm_note, help_text_for_app,
 };
 
 /// Renders the entire application UI
@@ -40,14 +42,11 @@ pub fn render(frame: &mut Frame, app: &App, ui_state: &mut UiState) {
 
                 // Calculate scroll offset using filtered-list position (not unfiltered index),
                 // since GroupListWidget applies scroll_offset to the filtered list.
+                // The shown indices are ascending, so finding the cursor's row is a binary search
                 let selected = app
-                    .groups
-                    .get(app.selected_group)
-                    .and_then(|selected_group| {
-                        app.filtered_groups()
-                            .iter()
-                            .position(|g| g.key == selected_group.key)
-                    })
+                    .group_list_summary()
+                    .indices
+                    .binary_search(&app.selected_group)
                     .unwrap_or(0);
                 let height = inner_height;
                 let offset = &mut ui_state.group_scroll_offset;
@@ -65,14 +64,12 @@ pub fn render(frame: &mut Frame, app: &App, ui_state: &mut UiState) {
         View::EmailList => {
             ui_state.viewport_heights.email_list = inner_height;
 
-            let widget = EmailListWidget::new(app);
-            let mut table_state = TableState::default().with_selected(app.selected_email);
-            frame.render_stateful_widget(widget, chunks[0], &mut table_state);
+            render_email_list(frame, chunks[0], app, ui_state);
         }
         View::Thread => {
             ui_state.viewport_heights.thread_view = inner_height;
 
-            let widget = ThreadViewWidget::new(app);
+            let widget = ThreadViewWidget::new(app, &ui_state.body_errors);
             let mut table_state = TableState::default().with_selected(app.selected_thread_email);
             frame.render_stateful_widget(widget, chunks[0], &mut table_state);
         }
@@ -81,13 +78,11 @@ pub fn render(frame: &mut Frame, app: &App, ui_state: &mut UiState) {
             match app.previous_view() {
                 Some(View::EmailList) => {
                     ui_state.viewport_heights.email_list = inner_height;
-                    let widget = EmailListWidget::new(app);
-                    let mut table_state = TableState::default().with_selected(app.selected_email);
-                    frame.render_stateful_widget(widget, chunks[0], &mut table_state);
+                    render_email_list(frame, chunks[0], app, ui_state);
                 }
                 Some(View::Thread) => {
                     ui_state.viewport_heights.thread_view = inner_height;
-                    let widget = ThreadViewWidget::new(app);
+                    let widget = ThreadViewWidget::new(app, &ui_state.body_errors);
                     let mut table_state =
                         TableState::default().with_selected(app.selected_thread_email);
                     frame.render_stateful_widget(widget, chunks[0], &mut table_state);
@@ -126,14 +121,32 @@ pub fn render(frame: &mut Frame, app: &App, ui_state: &mut UiState) {
             // (we don't know exact line count but prevent going too far)
             let scroll = app.text_view_scroll.min(10000);
 
-            let widget = TextViewWidget::new(app, scroll, &ui_state.text_view_state);
+            let truncated = app
+                .viewing_email_id()
+                .is_some_and(|id| ui_state.truncated_bodies.contains(id));
+            let width = block.inner(chunks[0]).width;
+            let html = app
+                .viewing_email_id()
+                .and_then(|id| ui_state.html_rendering(id, width));
+            let widget = TextViewWidget::new(app, scroll, &ui_state.text_view_state, truncated)
+                .html(html.as_deref());
             frame.render_widget(widget, chunks[0]);
         }
+        View::Duplicates => {
+            ui_state.viewport_heights.duplicates = inner_height;
+
+            let widget = DuplicatesWidget::new(app);
+            let mut table_state = TableState::default().with_selected(app.selected_duplicate);
+            frame.render_stateful_widget(widget, chunks[0], &mut table_state);
+        }
     }
 
-    // Render help bar or filter bar
-    if ui_state.is_filter_input_active() {
-        let filter = FilterBarWidget::new(ui_state.filter_query());
+    // Render help bar, note input or filter bar
+    if let Some(input) = &ui_state.note_input {
+        frame.render_widget(NoteBarWidget::new(input), chunks[1]);
+    } else if ui_state.is_filter_input_active() {
+        let filter = FilterBarWidget::new(ui_state.filter_query())
+            .completion(ui_state.filter_completion.as_deref());
         frame.render_widget(filter, chunks[1]);
     } else if let Some(query) = app.view_text_filter() {
         let help_text = help_text_for_app(app);
@@ -142,17 +155,45 @@ pub fn render(frame: &mut Frame, app: &App, ui_state: &mut UiState) {
     } else {
         let help = HelpBarWidget::new(app);
         frame.render_widget(help, chunks[1]);
+        if let Some(started) = ui_state.triage_started {
+            frame.render_widget(ProcessingRateWidget::new(app, started), chunks[1]);
+        }
+    }
+
+    // Render the triage countdown on the main border
+    if let Some(timer) = &ui_state.triage_timer {
+        frame.render_widget(TriageTimerWidget::new(timer), chunks[0]);
+    }
+
+    // Render onboarding tour under any dialogs
+    if let Some(tour) = &ui_state.tour {
+        frame.render_widget(TourWidget::new(tour), chunks[0]);
     }
 
+    // Render toasts under any dialogs
+    frame.render_widget(ToastWidget::new(ui_state.toasts()), chunks[0]);
+
     // Render confirmation dialog if active
     if let Some(action) = &ui_state.confirm_action {
-        let dialog = ConfirmDialogWidget::new(action);
+        let dialog = ConfirmDialogWidget::new(action, confirm_note(app, action))
+            .affected(affected_emails(app, action))
+            .typed(ui_state.confirm_input.as_ref());
         frame.render_widget(dialog, frame.area());
     }
 
+    // Render the forward prompt if an address is being typed
+    if let Some(input) = &ui_state.forward_input {
+        frame.render_widget(ForwardPromptWidget::new(input), frame.area());
+    }
+
+    // Render a plugin's picker if one is open
+    if let Some(picker) = &ui_state.picker {
+        frame.render_widget(PickerWidget::new(picker), frame.area());
+    }
+
     // Render help menu if active
     if ui_state.is_showing_help() {
-        let help_menu = HelpMenuWidget::new(app.view);
+        let help_menu = HelpMenuWidget::new(app.view, ui_state.help_global);
         frame.render_widget(help_menu, frame.area());
     }
 
@@ -160,7 +201,8 @@ pub fn render(frame: &mut Frame, app: &App, ui_state: &mut UiState) {
     if ui_state.is_busy()
         && let Some(msg) = &ui_state.status_message
     {
-        let modal = BusyModalWidget::new(msg, ui_state.spinner_char());
+        let modal =
+            BusyModalWidget::new(msg, ui_state.spinner_char()).workers(&ui_state.worker_progress);
         frame.render_widget(modal, frame.area());
     }
 
@@ -169,9 +211,24 @@ pub fn render(frame: &mut Frame, app: &App, ui_state: &mut UiState) {
         && !ui_state.is_confirming()
         && let Some(msg) = &ui_state.status_message
     {
-        let modal = StatusModalWidget::new(msg);
+        let modal = StatusModalWidget::new(msg).queued(ui_state.queued_status_count());
         frame.render_widget(modal, frame.area());
     }
+
+    if ui_state.show_status_history {
+        let history = StatusHistoryWidget::new(ui_state.status_history());
+        frame.render_widget(history, frame.area());
+    }
+}
+
+/// Renders the email list, scrolled where the group's list was last left
+fn render_email_list(frame: &mut Frame, area: Rect, app: &App, ui_state: &mut UiState) {
+    ui_state.switch_email_scroll_group(app.viewing_group_key());
+    let mut table_state = TableState::default()
+        .with_offset(ui_state.email_scroll_offset())
+        .with_selected(app.selected_email);
+    frame.render_stateful_widget(EmailListWidget::new(app), area, &mut table_state);
+    ui_state.set_email_scroll_offset(table_state.offset());
 }
 
 /// Renders the account selection UI
@@ -0,0 +1,80 @@
+//! Shows the account and how much mail is left in the terminal (and tmux) window title
+
+use std::io::{self, Write};
+
+use crossterm::{execute, terminal::SetTitle};
+
+use crate::ui::widgets::format_thousands;
+
+/// xterm sequences that save the current title on the terminal's title stack and restore it
+const PUSH_TITLE: &[u8] = b"\x1b[22;0t";
+const POP_TITLE: &[u8] = b"\x1b[23;0t";
+
+/// The window title, e.g. "zeroterm — personal (1,204 left)"
+pub fn title_text(account: &str, left: usize) -> String {
+    format!("zeroterm — {} ({} left)", account, format_thousands(left))
+}
+
+/// Keeps the window title current, writing only when it changes, and puts the
+/// terminal's own title back when dropped
+pub struct WindowTitle {
+    enabled: bool,
+    current: Option<String>,
+}
+
+impl WindowTitle {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            current: None,
+        }
+    }
+
+    /// Sets the title for the account and the number of emails still loaded
+    pub fn update(&mut self, account: &str, left: usize) -> io::Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        let text = title_text(account, left);
+        if self.current.as_deref() == Some(text.as_str()) {
+            return Ok(());
+        }
+        let mut stdout = io::stdout();
+        if self.current.is_none() {
+            stdout.write_all(PUSH_TITLE)?;
+        }
+        execute!(stdout, SetTitle(&text))?;
+        self.current = Some(text);
+        Ok(())
+    }
+}
+
+impl Drop for WindowTitle {
+    fn drop(&mut self) {
+        if self.current.is_some() {
+            let mut stdout = io::stdout();
+            let _ = stdout.write_all(POP_TITLE).and_then(|_| stdout.flush());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_title_text_shows_account_and_count() {
+        assert_eq!(
+            title_text("personal", 1204),
+            "zeroterm — personal (1,204 left)"
+        );
+        assert_eq!(title_text("work", 0), "zeroterm — work (0 left)");
+    }
+
+    #[test]
+    fn test_disabled_title_writes_nothing() {
+        let mut title = WindowTitle::new(false);
+        title.update("personal", 3).unwrap();
+        assert!(title.current.is_none());
+    }
+}
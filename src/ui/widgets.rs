@@ -1,14 +1,24 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
 use chrono::{DateTime, Datelike, Local, Utc};
 use ratatui::{
     buffer::Buffer,
     layout::{Constraint, Rect},
     style::{Color, Modifier, Style},
-    text::{Line, Span},
-    widgets::{Block, Borders, Paragraph, Row, StatefulWidget, Table, TableState, Widget, Wrap},
+    text::{Line, Span, Text},
+    widgets::{
+        Block, Borders, Cell, Paragraph, Row, StatefulWidget, Table, TableState, Widget, Wrap,
+    },
 };
 
 use crate::app::{App, GroupMode, UndoActionType, UndoContext, View};
-use crate::config::AccountConfig;
+use crate::auth::Verdict;
+use crate::config::{AccountConfig, RowDensity};
+use crate::email::Email;
+use crate::highlight;
+use serde::{Deserialize, Serialize};
 
 /// Warning indicator character for messages
 pub const WARNING_CHAR: char = '⚠';
@@ -28,6 +38,70 @@ fn format_date(date: &DateTime<Utc>) -> String {
     }
 }
 
+/// A colored "DKIM ✓" style badge for one authentication check
+fn auth_badge(name: &str, verdict: Verdict) -> Span<'static> {
+    let (mark, color) = match verdict {
+        Verdict::Pass => ("✓", Color::Green),
+        Verdict::Fail => ("✗", Color::Red),
+        Verdict::Neutral => ("?", Color::DarkGray),
+    };
+    Span::styled(format!("{} {}", name, mark), Style::default().fg(color))
+}
+
+/// The bordered block around the main view. With an account accent_color, the border
+/// and title take that color and the account's name sits on the right of the top border.
+fn main_block<'a>(app: &App, title: impl Into<Line<'a>>) -> Block<'a> {
+    let block = Block::default().borders(Borders::ALL).title(title);
+    match &app.accent {
+        Some((account, color)) => {
+            let style = Style::default().fg(*color);
+            block.border_style(style).title_style(style).title(
+                Line::from(format!(" {} ", account))
+                    .right_aligned()
+                    .style(style.add_modifier(Modifier::BOLD)),
+            )
+        }
+        None => block,
+    }
+}
+
+/// How old a message is, used to color list rows so stale backlog stands out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Age {
+    /// Less than a week old
+    Fresh,
+    /// Less than a month old
+    Weeks,
+    /// Less than a year old
+    Months,
+    /// A year or older
+    Years,
+}
+
+impl Age {
+    pub fn of(date: &DateTime<Utc>, now: DateTime<Utc>) -> Self {
+        let days = (now - *date).num_days();
+        if days < 7 {
+            Age::Fresh
+        } else if days < 30 {
+            Age::Weeks
+        } else if days < 365 {
+            Age::Months
+        } else {
+            Age::Years
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            Age::Fresh => Color::Green,
+            Age::Weeks => Color::Reset,
+            Age::Months => Color::Magenta,
+            Age::Years => Color::Red,
+        }
+    }
+}
+
 /// State for the confirmation dialog
 #[derive(Debug, Clone, PartialEq)]
 pub enum ConfirmAction {
@@ -51,6 +125,51 @@ pub enum ConfirmAction {
     ArchiveSelected { count: usize },
     /// Delete all emails in threads touched by selected emails
     DeleteSelected { count: usize },
+    /// Archive all emails in threads touched by the selected groups
+    ArchiveGroups { groups: usize, count: usize },
+    /// Delete all emails in threads touched by the selected groups
+    DeleteGroups { groups: usize, count: usize },
+    /// Archive redundant copies in the selected duplicate set (or all sets)
+    ArchiveDuplicates { count: usize, all_sets: bool },
+    /// Delete redundant copies in the selected duplicate set (or all sets)
+    DeleteDuplicates { count: usize, all_sets: bool },
+    /// Archive inbox emails older than the age cutoff
+    ArchiveOld { count: usize, days: u32 },
+    /// Archive a group's inbox emails dated before the cutoff
+    ArchiveGroupOld {
+        group: String,
+        count: usize,
+        days: u32,
+        cutoff: DateTime<Utc>,
+    },
+    /// Archive every email that isn't part of a multi-message thread
+    ArchiveSingles { count: usize },
+    /// Archive read inbox mail from one group (or across the inbox when group is None)
+    ArchiveRead { count: usize, group: Option<String> },
+    /// Archive everything in the inbox except the groups and threads marked to keep
+    ArchiveSweep {
+        count: usize,
+        kept_groups: usize,
+        kept_threads: usize,
+    },
+    /// Undo the newest undo history entries down to the selected one in one go
+    UndoRecent { actions: usize, count: usize },
+    /// Send the reply just written in the editor
+    SendReply { to: String, subject: String },
+    /// Apply configured policies to freshly loaded emails
+    ApplyPolicies { count: usize, summary: String },
+    /// Archive a sender's remaining inbox mail after opening their unsubscribe link
+    ArchiveSender { sender: String, count: usize },
+    /// Hide a sender or domain's threads from zeroterm for good
+    IgnoreSender { sender: String },
+    /// Loading failed partway; fetch the missing ranges and keep what already arrived
+    RetryFetch {
+        error: String,
+        fetched: usize,
+        missing_ranges: usize,
+    },
+    /// Another client changed the mailbox; resync before running `then`, or run it anyway
+    Resync { then: Box<ConfirmAction> },
     /// Quit the application
     Quit,
 }
@@ -79,11 +198,11 @@ impl ConfirmAction {
             } => {
                 if *filtered {
                     format!(
-                        "🗑  Delete {} filtered email(s) from {}? (y/n)",
+                        "🗑  Delete {} filtered email(s) from {}? They move to Trash. (y/n)",
                         count, sender
                     )
                 } else {
-                    format!("🗑  Delete {} email(s) from {}? (y/n)", count, sender)
+                    format!("🗑  Delete {} email(s) from {}? They move to Trash. (y/n)", count, sender)
                 }
             }
             ConfirmAction::ArchiveThread { thread_email_count } => {
@@ -94,7 +213,7 @@ impl ConfirmAction {
             }
             ConfirmAction::DeleteThread { thread_email_count } => {
                 format!(
-                    "🗑  Delete entire thread ({} email(s))? (y/n)",
+                    "🗑  Delete entire thread ({} email(s))? They move to Trash. (y/n)",
                     thread_email_count
                 )
             }
@@ -102,11 +221,211 @@ impl ConfirmAction {
                 format!("📥 Archive {} selected email(s)? (y/n)", count)
             }
             ConfirmAction::DeleteSelected { count } => {
-                format!("🗑  Delete {} selected email(s)? (y/n)", count)
+                format!("🗑  Delete {} selected email(s)? They move to Trash. (y/n)", count)
+            }
+            ConfirmAction::ArchiveGroups { groups, count } => {
+                format!(
+                    "📥 Archive {} email(s) from {} selected groups? (y/n)",
+                    count, groups
+                )
+            }
+            ConfirmAction::DeleteGroups { groups, count } => {
+                format!(
+                    "🗑  Delete {} email(s) from {} selected groups? They move to Trash. (y/n)",
+                    count, groups
+                )
+            }
+            ConfirmAction::ArchiveDuplicates { count, all_sets } => {
+                if *all_sets {
+                    format!(
+                        "📥 Archive {} duplicate copies across all sets? (y/n)",
+                        count
+                    )
+                } else {
+                    format!("📥 Archive {} duplicate copies in this set? (y/n)", count)
+                }
+            }
+            ConfirmAction::DeleteDuplicates { count, all_sets } => {
+                if *all_sets {
+                    format!(
+                        "🗑  Delete {} duplicate copies across all sets? They move to Trash. (y/n)",
+                        count
+                    )
+                } else {
+                    format!("🗑  Delete {} duplicate copies in this set? They move to Trash. (y/n)", count)
+                }
+            }
+            ConfirmAction::ArchiveOld { count, days } => {
+                format!(
+                    "📥 Archive {} inbox email(s) older than {} days? (y/n)",
+                    count, days
+                )
+            }
+            ConfirmAction::ArchiveGroupOld {
+                group,
+                count,
+                days,
+                cutoff,
+            } => {
+                let before = cutoff.with_timezone(&Local).format("%Y-%m-%d");
+                if *count == 0 {
+                    format!(
+                        "No inbox mail from {} older than {} days (before {}). Z: change age, n: cancel",
+                        group, days, before
+                    )
+                } else {
+                    format!(
+                        "📥 Archive {} email(s) from {} older than {} days (before {})? Z: change age (y/n)",
+                        count, group, days, before
+                    )
+                }
+            }
+            ConfirmAction::ArchiveSingles { count } => {
+                format!(
+                    "📥 Archive {} single-message email(s) across all groups? (y/n)",
+                    count
+                )
+            }
+            ConfirmAction::ArchiveRead { count, group } => match group {
+                Some(group) => format!(
+                    "📥 Archive {} read email(s) from {}? Unread mail stays. (y/n)",
+                    count, group
+                ),
+                None => format!(
+                    "📥 Archive {} read email(s) across the inbox? Unread mail stays. (y/n)",
+                    count
+                ),
+            },
+            ConfirmAction::ArchiveSweep {
+                count,
+                kept_groups,
+                kept_threads,
+            } => {
+                format!(
+                    "📥 Archive {} email(s), keeping {} group(s) and {} thread(s)? (y/n)",
+                    count, kept_groups, kept_threads
+                )
+            }
+            ConfirmAction::UndoRecent { actions, count } => {
+                format!(
+                    "↩ Undo the last {} action(s), restoring {} email(s)? (y/n)",
+                    actions, count
+                )
+            }
+            ConfirmAction::SendReply { to, subject } => {
+                format!("✉ Send \"{}\" to {}? (y/n)", subject, to)
+            }
+            ConfirmAction::ApplyPolicies { count, summary } => {
+                format!(
+                    "📋 Apply policies to {} email(s): {}? (y/n)",
+                    count, summary
+                )
+            }
+            ConfirmAction::ArchiveSender { sender, count } => {
+                format!(
+                    "📭 Unsubscribe link opened. Archive the {} remaining email(s) from {}? (y/n)",
+                    count, sender
+                )
+            }
+            ConfirmAction::IgnoreSender { sender } => {
+                format!("🙈 Hide all threads from {} from now on? (y/n)", sender)
+            }
+            ConfirmAction::RetryFetch {
+                error,
+                fetched,
+                missing_ranges,
+            } => {
+                format!(
+                    "⚠ Loading stopped after {} email(s): {}. Retry {} remaining range(s)? (y/n)",
+                    format_thousands(*fetched),
+                    error,
+                    missing_ranges
+                )
+            }
+            ConfirmAction::Resync { .. } => {
+                "⚠ The mailbox changed in another client. Resync first? (y: resync, n: continue anyway)"
+                    .to_string()
             }
             ConfirmAction::Quit => "🚪 Quit zeroterm? (y/n)".to_string(),
         }
     }
+
+    /// Returns how many emails a delete confirmation is about, or None for other actions
+    pub fn delete_count(&self) -> Option<usize> {
+        match self {
+            ConfirmAction::DeleteEmails { count, .. }
+            | ConfirmAction::DeleteSelected { count }
+            | ConfirmAction::DeleteGroups { count, .. }
+            | ConfirmAction::DeleteDuplicates { count, .. } => Some(*count),
+            ConfirmAction::DeleteThread { thread_email_count } => Some(*thread_email_count),
+            _ => None,
+        }
+    }
+
+    /// Returns how many emails an archive or delete confirmation is about, or None for
+    /// actions that don't take mail out of the inbox
+    pub fn email_count(&self) -> Option<usize> {
+        match self {
+            ConfirmAction::ArchiveEmails { count, .. }
+            | ConfirmAction::ArchiveSelected { count }
+            | ConfirmAction::ArchiveGroups { count, .. }
+            | ConfirmAction::ArchiveDuplicates { count, .. }
+            | ConfirmAction::ArchiveOld { count, .. }
+            | ConfirmAction::ArchiveGroupOld { count, .. }
+            | ConfirmAction::ArchiveSingles { count }
+            | ConfirmAction::ArchiveRead { count, .. }
+            | ConfirmAction::ArchiveSweep { count, .. }
+            | ConfirmAction::ArchiveSender { count, .. }
+            | ConfirmAction::ApplyPolicies { count, .. } => Some(*count),
+            ConfirmAction::ArchiveThread { thread_email_count } => Some(*thread_email_count),
+            _ => self.delete_count(),
+        }
+    }
+
+    /// Returns the sender or group an archive or delete confirmation is limited to
+    pub fn sender(&self) -> Option<&str> {
+        match self {
+            ConfirmAction::ArchiveEmails { sender, .. }
+            | ConfirmAction::DeleteEmails { sender, .. }
+            | ConfirmAction::ArchiveSender { sender, .. } => Some(sender),
+            ConfirmAction::ArchiveGroupOld { group, .. } => Some(group),
+            _ => None,
+        }
+    }
+
+    /// Returns true for actions that archive or delete mail on the server
+    pub fn changes_mailbox(&self) -> bool {
+        !matches!(
+            self,
+            ConfirmAction::IgnoreSender { .. }
+                | ConfirmAction::SendReply { .. }
+                | ConfirmAction::RetryFetch { .. }
+                | ConfirmAction::Resync { .. }
+                | ConfirmAction::Quit
+        )
+    }
+}
+
+/// Most status messages kept waiting behind the one on screen; older ones are dropped
+const STATUS_QUEUE_LEN: usize = 5;
+
+/// Status messages kept for the `!` history
+const STATUS_HISTORY_LEN: usize = 100;
+
+/// Most toasts stacked in the corner at once; the oldest goes first
+const MAX_TOASTS: usize = 3;
+
+/// How long a toast stays up: longer for longer messages
+fn toast_duration(message: &str) -> Duration {
+    let reading = Duration::from_millis(60) * message.chars().count() as u32;
+    (Duration::from_secs(4) + reading).min(Duration::from_secs(15))
+}
+
+/// An informational message shown in the corner until it times out
+#[derive(Debug, Clone)]
+pub struct Toast {
+    pub message: String,
+    shown_at: Instant,
 }
 
 /// Spinner frames for animated busy indicator
@@ -142,6 +461,7 @@ pub struct ViewportHeights {
     pub thread_view: usize,
     pub undo_history: usize,
     pub text_view: usize,
+    pub duplicates: usize,
 }
 
 impl ViewportHeights {
@@ -153,14 +473,79 @@ impl ViewportHeights {
             View::Thread => self.thread_view,
             View::UndoHistory => self.undo_history,
             View::EmailBody => self.text_view,
+            View::Duplicates => self.duplicates,
+        }
+    }
+}
+
+/// Steps of the onboarding tour, in order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TourStep {
+    OpenGroup,
+    Archive,
+    OpenUndo,
+    Restore,
+}
+
+/// Onboarding tour that walks through groups → email list → archive → undo.
+/// Each step advances when the user does what it asks.
+#[derive(Debug)]
+pub struct Tour {
+    step: TourStep,
+    /// Undo history length when the archive step began, to notice the archive
+    undo_count: usize,
+}
+
+impl Tour {
+    pub fn new() -> Self {
+        Self {
+            step: TourStep::OpenGroup,
+            undo_count: 0,
         }
     }
+
+    pub fn step(&self) -> TourStep {
+        self.step
+    }
+
+    /// Advances to the next step once the current one is done.
+    /// Returns false when the tour is finished.
+    pub fn update(&mut self, app: &App) -> bool {
+        match self.step {
+            TourStep::OpenGroup if app.view == View::EmailList => {
+                self.step = TourStep::Archive;
+                self.undo_count = app.undo_history.len();
+            }
+            TourStep::Archive if app.undo_history.len() > self.undo_count => {
+                self.step = TourStep::OpenUndo;
+            }
+            TourStep::OpenUndo if app.view == View::UndoHistory => {
+                self.step = TourStep::Restore;
+            }
+            TourStep::Restore if app.view != View::UndoHistory => return false,
+            _ => {}
+        }
+        true
+    }
+}
+
+impl Default for Tour {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 /// UI state that supplements App state
 #[derive(Debug, Default)]
 pub struct UiState {
     pub confirm_action: Option<ConfirmAction>,
+    /// What the confirmation asks to be typed instead of y, and what's been typed so far
+    pub confirm_input: Option<TypedConfirm>,
+    /// Deletes of more emails than this need "delete" typed; 0 never does
+    pub typed_confirm_above: usize,
+    /// Archives and deletes of more emails than this need the count or sender typed; 0
+    /// never does
+    pub typed_count_confirm_above: usize,
     pub status_message: Option<String>,
     /// When true, the UI is busy with an IMAP operation and input is blocked
     pub busy: bool,
@@ -174,16 +559,174 @@ pub struct UiState {
     pub group_scroll_offset: usize,
     /// Scroll offset for undo history list
     pub undo_scroll_offset: usize,
+    /// Scroll offset for the email list, kept between frames so the view only scrolls
+    /// when the cursor reaches an edge
+    email_scroll_offset: usize,
+    /// The group whose email list `email_scroll_offset` belongs to
+    email_scroll_group: Option<String>,
+    /// Email list scroll offsets of the groups left this session, by group key
+    email_scroll_offsets: HashMap<String, usize>,
     /// When true, the help menu is displayed
     pub show_help: bool,
+    /// When true, the help menu lists bindings that work in every view
+    pub help_global: bool,
     /// When true, the user is typing in the filter input bar
     pub filter_input_mode: bool,
     /// The current filter query being typed
     pub filter_query: String,
     /// Snapshot of the filter query when entering input mode, for Esc-to-revert
     filter_revert_query: Option<String>,
+    /// The best completion of the query being typed, taken with Tab
+    pub filter_completion: Option<String>,
+    /// Filters applied with Enter this session, oldest first
+    filter_history: Vec<String>,
+    /// Position in filter_history while stepping through it with Up and Down
+    filter_history_position: Option<usize>,
+    /// The query typed before stepping into the history, brought back past the newest entry
+    filter_history_draft: String,
     /// State of the text view (loading, loaded, error)
     pub text_view_state: TextViewState,
+    /// Onboarding tour shown on first launch or with --tour
+    pub tour: Option<Tour>,
+    /// Email IDs whose bodies have been requested for inline thread display
+    pub requested_bodies: HashSet<String>,
+    /// Errors from body fetches, keyed by email ID (shown inline in thread view)
+    pub body_errors: HashMap<String, String>,
+    /// Email IDs whose cached bodies were cut short by body_size_limit_kb
+    pub truncated_bodies: HashSet<String>,
+    /// HTML parts of fetched bodies, keyed by email ID, for the HTML view
+    pub html_bodies: HashMap<String, String>,
+    /// When true, the email view shows the structured HTML rendering of emails with HTML
+    pub html_view: bool,
+    /// The last HTML rendering with the email ID and width it was made for
+    html_rendering: Option<(String, u16, Rc<[Line<'static>]>)>,
+    /// Sender note being typed, if any
+    pub note_input: Option<NoteInput>,
+    /// Address the viewed email is being forwarded to, while it's typed
+    pub forward_input: Option<ForwardInput>,
+    /// Choices offered by a `[[plugin]]`, if one asked
+    pub picker: Option<Picker>,
+    /// Timebox for the triage session, from triage_timer_minutes
+    pub triage_timer: Option<TriageTimer>,
+    /// When mail was first loaded, for the processing rate in the help bar
+    pub triage_started: Option<Instant>,
+    /// Per-connection progress while mail loads in parallel
+    pub worker_progress: Vec<WorkerProgress>,
+    /// Server `[ALERT]` notices waiting to be shown
+    server_alerts: VecDeque<String>,
+    /// Every server alert received this session, so repeats aren't shown again
+    seen_server_alerts: HashSet<String>,
+    /// Status messages waiting for the one on screen to be dismissed
+    status_queue: VecDeque<String>,
+    /// Informational messages in the corner, oldest first
+    toasts: VecDeque<Toast>,
+    /// Recent status messages with when they were set, oldest first
+    status_history: VecDeque<(DateTime<Local>, String)>,
+    /// When true, the status history is displayed
+    pub show_status_history: bool,
+}
+
+/// How far one parallel fetch connection has got through its share of a folder
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkerProgress {
+    pub folder: String,
+    /// Sequence numbers being fetched, or None when fetching UIDs found by a search
+    pub range: Option<(u32, u32)>,
+    pub fetched: usize,
+    pub total: usize,
+}
+
+impl WorkerProgress {
+    /// One line for the loading screen, e.g. "worker 3/5  INBOX 8,001-12,000: 2,140/4,000"
+    pub fn line(&self, number: usize, workers: usize) -> String {
+        let folder = self.folder.trim_start_matches("[Gmail]/");
+        let range = match self.range {
+            Some((start, end)) => format!(
+                " {}-{}",
+                format_thousands(start as usize),
+                format_thousands(end as usize)
+            ),
+            None => String::new(),
+        };
+        format!(
+            "worker {}/{}  {}{}: {}/{}",
+            number,
+            workers,
+            folder,
+            range,
+            format_thousands(self.fetched),
+            format_thousands(self.total)
+        )
+    }
+}
+
+/// What a picker's choice is for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PickerTarget {
+    /// The `[[plugin]]` with this key, which gets the choice
+    Plugin(char),
+    /// The folder to move the current emails to; with archive set, the label to file
+    /// them under as they're archived
+    MoveFolder { archive: bool },
+    /// The Gmail label to add to the current emails
+    AddLabel,
+    /// The Gmail label to remove from the current emails
+    RemoveLabel,
+    /// The folder to restore the selected undo entry's emails to, instead of where they
+    /// came from
+    RestoreFolder,
+}
+
+/// A list the user chooses from: a `[[plugin]]`'s choices or the folders to move to
+#[derive(Debug, Clone, PartialEq)]
+pub struct Picker {
+    pub target: PickerTarget,
+    pub title: String,
+    pub items: Vec<String>,
+    pub selected: usize,
+}
+
+impl Picker {
+    pub fn new(target: PickerTarget, title: String, items: Vec<String>) -> Self {
+        Self {
+            target,
+            title,
+            items,
+            selected: 0,
+        }
+    }
+
+    pub fn select_next(&mut self) {
+        if self.selected < self.items.len().saturating_sub(1) {
+            self.selected += 1;
+        }
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn current_item(&self) -> Option<&str> {
+        self.items.get(self.selected).map(String::as_str)
+    }
+}
+
+/// The address an email is being forwarded to, typed in a prompt over the email
+#[derive(Debug, Clone, PartialEq)]
+pub struct ForwardInput {
+    /// Subject of the email being forwarded
+    pub subject: String,
+    /// Addresses typed so far
+    pub text: String,
+}
+
+/// A sender note being edited in the bottom bar
+#[derive(Debug, Clone, PartialEq)]
+pub struct NoteInput {
+    /// Key of the group the note belongs to
+    pub key: String,
+    /// Note text typed so far
+    pub text: String,
 }
 
 impl UiState {
@@ -191,27 +734,178 @@ impl UiState {
         Self::default()
     }
 
+    /// Makes `group` the one the email list's scroll offset belongs to, saving the offset
+    /// of the group being left and restoring the one `group` had last time
+    pub fn switch_email_scroll_group(&mut self, group: Option<&str>) {
+        if self.email_scroll_group.as_deref() == group {
+            return;
+        }
+        if let Some(previous) = self.email_scroll_group.take() {
+            self.email_scroll_offsets
+                .insert(previous, self.email_scroll_offset);
+        }
+        self.email_scroll_offset = group
+            .and_then(|key| self.email_scroll_offsets.get(key))
+            .copied()
+            .unwrap_or(0);
+        self.email_scroll_group = group.map(str::to_string);
+    }
+
+    /// The email list's scroll offset for the current group
+    pub fn email_scroll_offset(&self) -> usize {
+        self.email_scroll_offset
+    }
+
+    /// Keeps the email list's scroll offset after a frame scrolled it
+    pub fn set_email_scroll_offset(&mut self, offset: usize) {
+        self.email_scroll_offset = offset;
+    }
+
+    /// Keeps a fetched body's HTML part for the HTML view, replacing any older rendering
+    pub fn set_html_body(&mut self, email_id: &str, html: Option<String>) {
+        if self
+            .html_rendering
+            .as_ref()
+            .is_some_and(|(id, _, _)| id == email_id)
+        {
+            self.html_rendering = None;
+        }
+        match html {
+            Some(html) => self.html_bodies.insert(email_id.to_string(), html),
+            None => self.html_bodies.remove(email_id),
+        };
+    }
+
+    /// Returns the HTML view's rendering of an email at `width` columns, or None when the
+    /// view is off or the email has no HTML part. The last rendering is reused until the
+    /// email or the width changes.
+    pub fn html_rendering(&mut self, email_id: &str, width: u16) -> Option<Rc<[Line<'static>]>> {
+        if !self.html_view {
+            return None;
+        }
+        if let Some((id, rendered_width, lines)) = &self.html_rendering
+            && id == email_id
+            && *rendered_width == width
+        {
+            return Some(Rc::clone(lines));
+        }
+        let lines: Rc<[Line<'static>]> =
+            super::html::render(self.html_bodies.get(email_id)?, width as usize)?.into();
+        self.html_rendering = Some((email_id.to_string(), width, Rc::clone(&lines)));
+        Some(lines)
+    }
+
     pub fn set_confirm(&mut self, action: ConfirmAction) {
+        let above = |count: Option<usize>, threshold: usize| {
+            threshold > 0 && count.is_some_and(|count| count > threshold)
+        };
+        self.confirm_input = if above(action.email_count(), self.typed_count_confirm_above) {
+            let count = action.email_count().unwrap_or_default();
+            let answers = std::iter::once(count.to_string())
+                .chain(action.sender().map(str::to_string))
+                .collect();
+            Some(TypedConfirm::new(answers))
+        } else if above(action.delete_count(), self.typed_confirm_above) {
+            Some(TypedConfirm::new(vec![TYPED_CONFIRM_WORD.to_string()]))
+        } else {
+            None
+        };
         self.confirm_action = Some(action);
     }
 
     pub fn clear_confirm(&mut self) {
         self.confirm_action = None;
+        self.confirm_input = None;
     }
 
     pub fn is_confirming(&self) -> bool {
         self.confirm_action.is_some()
     }
 
+    fn record_status(&mut self, msg: &str) {
+        if self.status_history.len() == STATUS_HISTORY_LEN {
+            self.status_history.pop_front();
+        }
+        self.status_history
+            .push_back((Local::now(), msg.to_string()));
+    }
+
+    /// Shows an informational message as a toast in the corner, which times out on
+    /// its own and doesn't take a key press. Warnings still go to the modal.
     pub fn set_status(&mut self, msg: impl Into<String>) {
-        self.status_message = Some(msg.into());
+        let msg = msg.into();
+        if msg.starts_with(WARNING_CHAR) {
+            return self.set_alert(msg);
+        }
+        self.record_status(&msg);
+        // The same message again (another "Copied") restarts the one on screen
+        if let Some(last) = self.toasts.back_mut()
+            && last.message == msg
+        {
+            last.shown_at = Instant::now();
+            return;
+        }
+        if self.toasts.len() == MAX_TOASTS {
+            self.toasts.pop_front();
+        }
+        self.toasts.push_back(Toast {
+            message: msg,
+            shown_at: Instant::now(),
+        });
+    }
+
+    /// Shows an error, warning or anything else that shouldn't go unread in a modal
+    /// that stays until a key is pressed, or
+    /// queues it behind the one on screen (or the busy message) so quick successive
+    /// messages don't replace each other unseen
+    pub fn set_alert(&mut self, msg: impl Into<String>) {
+        let msg = msg.into();
+        self.record_status(&msg);
+        if self.status_message.is_none() {
+            self.status_message = Some(msg);
+        } else if self.status_message.as_ref() != Some(&msg)
+            && self.status_queue.back() != Some(&msg)
+        {
+            if self.status_queue.len() == STATUS_QUEUE_LEN {
+                self.status_queue.pop_front();
+            }
+            self.status_queue.push_back(msg);
+        }
+    }
+
+    /// Drops toasts that have been up long enough
+    pub fn tick_status(&mut self) {
+        self.toasts
+            .retain(|toast| toast.shown_at.elapsed() < toast_duration(&toast.message));
+    }
+
+    /// Toasts on screen, oldest first
+    pub fn toasts(&self) -> impl Iterator<Item = &Toast> {
+        self.toasts.iter()
+    }
+
+    /// Returns how many status messages wait behind the one on screen
+    pub fn queued_status_count(&self) -> usize {
+        self.status_queue.len()
+    }
+
+    /// Recent status messages with when they were set, newest first
+    pub fn status_history(&self) -> impl Iterator<Item = &(DateTime<Local>, String)> {
+        self.status_history.iter().rev()
     }
 
     /// Set busy state with a status message (blocks input)
     pub fn set_busy(&mut self, msg: impl Into<String>) {
+        // A status message on screen comes back once the operation finishes
+        if !self.busy
+            && let Some(shown) = self.status_message.take()
+        {
+            self.status_queue.push_front(shown);
+        }
         self.busy = true;
         self.status_message = Some(msg.into());
         self.spinner_frame = 0;
+        self.worker_progress.clear();
     }
 
     /// Update the busy message without resetting the spinner
@@ -219,10 +913,11 @@ impl UiState {
         self.status_message = Some(msg.into());
     }
 
-    /// Clear busy state
+    /// Clear busy state, showing the next queued status message
     pub fn clear_busy(&mut self) {
         self.busy = false;
-        self.status_message = None;
+        self.worker_progress.clear();
+        self.clear_status();
     }
 
     /// Returns true if the UI is busy and input should be blocked
@@ -245,9 +940,9 @@ impl UiState {
         SPINNER_FRAMES[self.spinner_frame % SPINNER_FRAMES.len()]
     }
 
-    /// Clear the status message
+    /// Clear the status message, showing the next queued one
     pub fn clear_status(&mut self) {
-        self.status_message = None;
+        self.status_message = self.status_queue.pop_front();
     }
 
     /// Returns true if there's a status message to display
@@ -255,9 +950,33 @@ impl UiState {
         self.status_message.is_some()
     }
 
-    /// Show the help menu
+    /// Queues a server `[ALERT]` notice to be shown; alerts already received
+    /// this session are ignored, since servers often repeat them on every command
+    pub fn queue_server_alert(&mut self, alert: String) {
+        if self.seen_server_alerts.insert(alert.clone()) {
+            self.server_alerts.push_back(alert);
+        }
+    }
+
+    /// Shows the next queued server alert once nothing else is on screen
+    pub fn show_next_server_alert(&mut self) {
+        if self.is_busy() || self.is_confirming() || self.has_status() {
+            return;
+        }
+        if let Some(alert) = self.server_alerts.pop_front() {
+            self.set_alert(format!("{} Server alert: {}", WARNING_CHAR, alert));
+        }
+    }
+
+    /// Show the help menu, starting with the current view's bindings
     pub fn show_help(&mut self) {
         self.show_help = true;
+        self.help_global = false;
+    }
+
+    /// Switch the help menu between the current view's and every view's bindings
+    pub fn toggle_help_global(&mut self) {
+        self.help_global = !self.help_global;
     }
 
     /// Hide the help menu
@@ -270,12 +989,27 @@ impl UiState {
         self.show_help
     }
 
+    /// Starts editing the note for a group, pre-filled with its current note
+    pub fn start_note_input(&mut self, key: &str, current: Option<&str>) {
+        self.note_input = Some(NoteInput {
+            key: key.to_string(),
+            text: current.unwrap_or_default().to_string(),
+        });
+    }
+
+    /// Returns true while a sender note is being typed
+    pub fn is_note_input_active(&self) -> bool {
+        self.note_input.is_some()
+    }
+
     /// Enter filter input mode (user is typing filter query)
     /// Snapshots the current filter so Esc can revert to it.
     pub fn enter_filter_input_mode(&mut self, current_filter: Option<&str>) {
         self.filter_input_mode = true;
         self.filter_revert_query = current_filter.map(|s| s.to_string());
         self.filter_query.clear();
+        self.filter_completion = None;
+        self.filter_history_position = None;
     }
 
     /// Enter filter input mode with existing query (for editing active filter)
@@ -288,11 +1022,14 @@ impl UiState {
         self.filter_input_mode = true;
         self.filter_revert_query = current_filter.map(|s| s.to_string());
         self.filter_query = query.to_string();
+        self.filter_completion = None;
+        self.filter_history_position = None;
     }
 
     /// Exit filter input mode
     pub fn exit_filter_input_mode(&mut self) {
         self.filter_input_mode = false;
+        self.filter_completion = None;
     }
 
     /// Returns true if in filter input mode
@@ -303,11 +1040,56 @@ impl UiState {
     /// Append a character to the filter query
     pub fn append_filter_char(&mut self, c: char) {
         self.filter_query.push(c);
+        self.filter_history_position = None;
     }
 
     /// Remove the last character from the filter query
     pub fn backspace_filter(&mut self) {
         self.filter_query.pop();
+        self.filter_history_position = None;
+    }
+
+    /// Adds the query to the filter history, moving it to the newest entry if it's there
+    pub fn remember_filter(&mut self) {
+        self.filter_history_position = None;
+        if self.filter_query.is_empty() {
+            return;
+        }
+        self.filter_history.retain(|q| q != &self.filter_query);
+        self.filter_history.push(self.filter_query.clone());
+    }
+
+    /// Replaces the query with the next older filter from the history. Returns false at
+    /// the oldest entry or when there is no history.
+    pub fn filter_history_back(&mut self) -> bool {
+        let position = match self.filter_history_position {
+            None if self.filter_history.is_empty() => return false,
+            None => {
+                self.filter_history_draft = self.filter_query.clone();
+                self.filter_history.len() - 1
+            }
+            Some(0) => return false,
+            Some(position) => position - 1,
+        };
+        self.filter_history_position = Some(position);
+        self.filter_query = self.filter_history[position].clone();
+        true
+    }
+
+    /// Replaces the query with the next newer filter from the history, or what was typed
+    /// before stepping into it. Returns false when not in the history.
+    pub fn filter_history_forward(&mut self) -> bool {
+        let Some(position) = self.filter_history_position else {
+            return false;
+        };
+        if position + 1 < self.filter_history.len() {
+            self.filter_history_position = Some(position + 1);
+            self.filter_query = self.filter_history[position + 1].clone();
+        } else {
+            self.filter_history_position = None;
+            self.filter_query = std::mem::take(&mut self.filter_history_draft);
+        }
+        true
     }
 
     /// Get the current filter query
@@ -336,23 +1118,54 @@ impl UiState {
 pub struct BusyModalWidget<'a> {
     message: &'a str,
     spinner: char,
+    workers: &'a [WorkerProgress],
 }
 
 impl<'a> BusyModalWidget<'a> {
     pub fn new(message: &'a str, spinner: char) -> Self {
-        Self { message, spinner }
+        Self {
+            message,
+            spinner,
+            workers: &[],
+        }
+    }
+
+    /// Lists each parallel connection's progress under the message
+    pub fn workers(mut self, workers: &'a [WorkerProgress]) -> Self {
+        self.workers = workers;
+        self
     }
 }
 
 impl Widget for BusyModalWidget<'_> {
     fn render(self, area: Rect, buf: &mut Buffer) {
+        use unicode_width::UnicodeWidthStr;
+
         // Format message with spinner
         let display_msg = format!("{} {}", self.spinner, self.message);
+        let worker_lines: Vec<String> = self
+            .workers
+            .iter()
+            .enumerate()
+            .map(|(i, w)| w.line(i + 1, self.workers.len()))
+            .collect();
 
         // Calculate centered box size
-        let msg_width = display_msg.len() as u16 + 4;
+        let msg_width = worker_lines
+            .iter()
+            .map(|line| line.width())
+            .max()
+            .unwrap_or(0)
+            .max(display_msg.width()) as u16
+            + 4;
         let box_width = msg_width.max(20).min(area.width.saturating_sub(4));
-        let box_height = 3;
+        // A blank line separates the workers from the message
+        let worker_rows = if worker_lines.is_empty() {
+            0
+        } else {
+            worker_lines.len() as u16 + 1
+        };
+        let box_height = (3 + worker_rows).min(area.height);
 
         let x = area.x + (area.width.saturating_sub(box_width)) / 2;
         let y = area.y + (area.height.saturating_sub(box_height)) / 2;
@@ -388,26 +1201,55 @@ impl Widget for BusyModalWidget<'_> {
             )),
             inner.width,
         );
-    }
-}
 
-/// Widget for status message modal overlay (used for warnings)
-pub struct StatusModalWidget<'a> {
-    message: &'a str,
-}
-
-impl<'a> StatusModalWidget<'a> {
-    pub fn new(message: &'a str) -> Self {
-        Self { message }
-    }
+        // Finished connections are dimmed so the ones still working (or stuck) stand out
+        for (row, (line, worker)) in worker_lines.iter().zip(self.workers).enumerate() {
+            let y = inner.y + 2 + row as u16;
+            if y >= inner.y + inner.height {
+                break;
+            }
+            let style = if worker.fetched >= worker.total {
+                Style::default().fg(Color::DarkGray)
+            } else {
+                Style::default()
+            };
+            buf.set_line(
+                inner.x + 1,
+                y,
+                &Line::from(Span::styled(line.as_str(), style)),
+                inner.width.saturating_sub(1),
+            );
+        }
+    }
+}
+
+/// Widget for status message modal overlay (used for warnings)
+pub struct StatusModalWidget<'a> {
+    message: &'a str,
+    /// Messages waiting behind this one
+    queued: usize,
+}
+
+impl<'a> StatusModalWidget<'a> {
+    pub fn new(message: &'a str) -> Self {
+        Self { message, queued: 0 }
+    }
+
+    pub fn queued(mut self, queued: usize) -> Self {
+        self.queued = queued;
+        self
+    }
 }
 
 impl Widget for StatusModalWidget<'_> {
     fn render(self, area: Rect, buf: &mut Buffer) {
-        // Calculate centered box size
-        let msg_width = self.message.len() as u16 + 4;
+        use unicode_width::UnicodeWidthStr;
+
+        // Calculate centered box size, wrapping messages wider than the screen
+        let msg_width = self.message.width() as u16 + 4;
         let box_width = msg_width.max(20).min(area.width.saturating_sub(4));
-        let box_height = 3;
+        let lines = wrap_text(self.message, box_width.saturating_sub(2) as usize);
+        let box_height = (lines.len() as u16 + 2).min(area.height);
 
         let x = area.x + (area.width.saturating_sub(box_width)) / 2;
         let y = area.y + (area.height.saturating_sub(box_height)) / 2;
@@ -429,17 +1271,18 @@ impl Widget for StatusModalWidget<'_> {
             Color::White
         };
 
-        let block = Block::default()
+        let mut block = Block::default()
             .borders(Borders::ALL)
             .border_style(Style::default().fg(border_color))
             .style(Style::default());
+        if self.queued > 0 {
+            block =
+                block.title_bottom(Line::from(format!(" {} more ", self.queued)).right_aligned());
+        }
 
         let inner = block.inner(modal_area);
         block.render(modal_area, buf);
 
-        // Center the message
-        let msg_x = inner.x + (inner.width.saturating_sub(self.message.len() as u16)) / 2;
-
         // Use yellow text for warnings
         let text_color = if self.message.starts_with(WARNING_CHAR) {
             Color::Yellow
@@ -447,15 +1290,156 @@ impl Widget for StatusModalWidget<'_> {
             Color::White
         };
 
-        buf.set_line(
-            msg_x,
-            inner.y,
-            &Line::from(Span::styled(
-                self.message,
-                Style::default().fg(text_color).add_modifier(Modifier::BOLD),
-            )),
-            inner.width,
-        );
+        // Center each line of the message
+        for (row, line) in lines.iter().enumerate().take(inner.height as usize) {
+            let line_x = inner.x + (inner.width.saturating_sub(line.width() as u16)) / 2;
+            buf.set_line(
+                line_x,
+                inner.y + row as u16,
+                &Line::from(Span::styled(
+                    line.as_str(),
+                    Style::default().fg(text_color).add_modifier(Modifier::BOLD),
+                )),
+                inner.width,
+            );
+        }
+    }
+}
+
+/// Widget for the toasts, stacked in the top-right corner with the newest at the bottom
+pub struct ToastWidget<'a> {
+    toasts: Vec<&'a Toast>,
+}
+
+impl<'a> ToastWidget<'a> {
+    pub fn new(toasts: impl Iterator<Item = &'a Toast>) -> Self {
+        Self {
+            toasts: toasts.collect(),
+        }
+    }
+}
+
+impl Widget for ToastWidget<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        use unicode_width::UnicodeWidthStr;
+
+        let max_width = (area.width / 2)
+            .clamp(20, 60)
+            .min(area.width.saturating_sub(2));
+        // Sit inside the main border, below its title
+        let x_end = area.x + area.width.saturating_sub(1);
+        let mut y = area.y + 1;
+        let bottom = area.y + area.height.saturating_sub(1);
+
+        for toast in self.toasts {
+            let box_width = (toast.message.width() as u16 + 4).min(max_width);
+            let lines = wrap_text(&toast.message, box_width.saturating_sub(4) as usize);
+            let box_height = lines.len() as u16 + 2;
+            if y + box_height > bottom {
+                break;
+            }
+            let toast_area = Rect::new(x_end.saturating_sub(box_width), y, box_width, box_height);
+
+            // Clear the area behind the toast
+            for row in toast_area.y..toast_area.y + toast_area.height {
+                for col in toast_area.x..toast_area.x + toast_area.width {
+                    buf[(col, row)].set_char(' ');
+                    buf[(col, row)].set_style(Style::default());
+                }
+            }
+
+            let block = Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Green));
+            let inner = block.inner(toast_area);
+            block.render(toast_area, buf);
+
+            for (row, line) in lines.iter().enumerate() {
+                buf.set_line(
+                    inner.x + 1,
+                    inner.y + row as u16,
+                    &Line::from(Span::styled(
+                        line.as_str(),
+                        Style::default().fg(Color::White),
+                    )),
+                    inner.width.saturating_sub(1),
+                );
+            }
+            y += box_height;
+        }
+    }
+}
+
+/// Widget for the status message history, newest first
+pub struct StatusHistoryWidget<'a> {
+    entries: Vec<&'a (DateTime<Local>, String)>,
+}
+
+impl<'a> StatusHistoryWidget<'a> {
+    pub fn new(entries: impl Iterator<Item = &'a (DateTime<Local>, String)>) -> Self {
+        Self {
+            entries: entries.collect(),
+        }
+    }
+}
+
+impl Widget for StatusHistoryWidget<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let box_width = 80.min(area.width.saturating_sub(4));
+        let box_height = (self.entries.len().max(1) as u16 + 2).min(area.height.saturating_sub(2));
+        let x = area.x + (area.width.saturating_sub(box_width)) / 2;
+        let y = area.y + (area.height.saturating_sub(box_height)) / 2;
+        let modal_area = Rect::new(x, y, box_width, box_height);
+
+        // Clear the area behind the modal
+        for row in modal_area.y..modal_area.y + modal_area.height {
+            for col in modal_area.x..modal_area.x + modal_area.width {
+                buf[(col, row)].set_char(' ');
+                buf[(col, row)].set_style(Style::default());
+            }
+        }
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(" Messages (newest first) ")
+            .title_bottom(Line::from(" any key: close ").right_aligned())
+            .border_style(Style::default().fg(Color::Cyan));
+        let inner = block.inner(modal_area);
+        block.render(modal_area, buf);
+
+        if self.entries.is_empty() {
+            buf.set_line(
+                inner.x + 1,
+                inner.y,
+                &Line::from(Span::styled(
+                    "No messages yet",
+                    Style::default().fg(Color::DarkGray),
+                )),
+                inner.width.saturating_sub(1),
+            );
+            return;
+        }
+        for (row, (time, message)) in self.entries.iter().take(inner.height as usize).enumerate() {
+            let color = if message.starts_with(WARNING_CHAR) {
+                Color::Yellow
+            } else {
+                Color::White
+            };
+            // Multi-line messages are shown on one row
+            let message = message.replace('\n', " ");
+            buf.set_line(
+                inner.x + 1,
+                inner.y + row as u16,
+                &Line::from(vec![
+                    Span::styled(
+                        time.format("%H:%M:%S  ").to_string(),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                    Span::styled(message, Style::default().fg(color)),
+                ]),
+                inner.width.saturating_sub(1),
+            );
+        }
     }
 }
 
@@ -605,6 +1589,19 @@ impl Widget for InboxZeroWidget {
     }
 }
 
+/// Formats a count with thousands separators ("5,000")
+pub fn format_thousands(n: usize) -> String {
+    let digits = n.to_string();
+    let mut out = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
 /// Widget for rendering the group list
 pub struct GroupListWidget<'a> {
     app: &'a App,
@@ -622,31 +1619,84 @@ impl Widget for GroupListWidget<'_> {
         let mode_str = match self.app.group_mode {
             GroupMode::BySenderEmail => "email",
             GroupMode::ByDomain => "domain",
+            GroupMode::ByLabel => "label",
         };
         let filter_indicator = match self.app.thread_filter {
             crate::app::ThreadFilter::All => "",
             crate::app::ThreadFilter::OnlyThreads => " [Threads]",
             crate::app::ThreadFilter::NoThreads => " [No Threads]",
         };
-        let filtered_groups = self.app.filtered_groups();
-        let total_emails: usize = filtered_groups
-            .iter()
-            .map(|g| self.app.full_thread_email_count_for_group(g))
-            .sum();
+        // Only the counts in the title and the rows on screen are worked out per frame;
+        // the filtered list itself is cached by the app
+        let summary = self.app.group_list_summary();
+        let selected_groups = self.app.shown_selected_group_count();
+        let selection_indicator = if selected_groups > 0 {
+            format!(" ({} selected)", selected_groups)
+        } else {
+            String::new()
+        };
+        let done_groups = self.app.shown_done_group_count();
+        let done_indicator = if done_groups > 0 {
+            format!(" ({} done)", done_groups)
+        } else {
+            String::new()
+        };
+        let hidden_groups = self.app.hidden_small_group_count();
+        let hidden_indicator = if hidden_groups > 0 {
+            format!(
+                " [{} under {} hidden, H to show]",
+                hidden_groups, self.app.min_group_size
+            )
+        } else {
+            String::new()
+        };
+        let dismissed = self.app.dismissed_count();
+        let dismissed_indicator = if dismissed > 0 {
+            format!(" [{} hidden for now, U to show]", dismissed)
+        } else {
+            String::new()
+        };
+        let new_groups = summary.new_groups;
+        let new_indicator = if new_groups > 0 {
+            format!(" [• {} with new mail, J to jump]", new_groups)
+        } else {
+            String::new()
+        };
+        let sweep_indicator = match self.app.sweep_marks() {
+            Some(marks) => format!(
+                " [Sweep: keeping {} groups, {} threads]",
+                marks.groups.len(),
+                marks.threads.len()
+            ),
+            None => String::new(),
+        };
         let title = format!(
-            " Senders (by {}){} — {} emails in {} groups ",
+            " Senders (by {}){} — {} emails in {} groups{}{}{}{}{}{} ",
             mode_str,
             filter_indicator,
-            total_emails,
-            filtered_groups.len()
+            summary.total_emails,
+            summary.indices.len(),
+            new_indicator,
+            hidden_indicator,
+            dismissed_indicator,
+            done_indicator,
+            selection_indicator,
+            sweep_indicator
         );
-        let block = Block::default().borders(Borders::ALL).title(title);
+        let mut block = main_block(self.app, title);
+        if self.app.load_more_count > 0 {
+            let hint = format!(
+                " L: load next {} older emails ",
+                format_thousands(self.app.load_more_count)
+            );
+            block = block.title_bottom(Line::from(hint).style(Style::default().fg(Color::Cyan)));
+        }
 
         let inner = block.inner(area);
         block.render(area, buf);
 
         // Show message if text filter is active but no groups match
-        if filtered_groups.is_empty() && self.app.has_group_text_filter() {
+        if summary.indices.is_empty() && self.app.has_group_text_filter() {
             let msg = "No matching groups (Esc: clear filter)";
             let x = inner.x + (inner.width.saturating_sub(msg.len() as u16)) / 2;
             let y = inner.y + inner.height / 2;
@@ -660,7 +1710,7 @@ impl Widget for GroupListWidget<'_> {
         }
 
         // Show message if thread filter is active but no groups match
-        if filtered_groups.is_empty() && self.app.thread_filter != crate::app::ThreadFilter::All {
+        if summary.indices.is_empty() && self.app.thread_filter != crate::app::ThreadFilter::All {
             let msg = match self.app.thread_filter {
                 crate::app::ThreadFilter::OnlyThreads => {
                     "No senders with threads (t: cycle filter)"
@@ -683,20 +1733,34 @@ impl Widget for GroupListWidget<'_> {
 
         // Get the currently selected group to match by key
         let selected_key = self.app.groups.get(self.app.selected_group).map(|g| &g.key);
+        let now = Utc::now();
 
-        for (i, group) in filtered_groups.iter().enumerate().skip(self.scroll_offset) {
-            let row_index = i - self.scroll_offset;
-            if row_index >= inner.height as usize {
-                break;
-            }
+        let visible = summary
+            .indices
+            .iter()
+            .skip(self.scroll_offset)
+            .take(inner.height as usize);
+        for (row_index, &group_index) in visible.enumerate() {
+            let group = &self.app.groups[group_index];
 
             let is_selected = selected_key.is_some_and(|k| k == &group.key);
+            let is_done = self.app.is_group_done(&group.key);
             let style = if is_selected {
                 Style::default()
                     .fg(Color::Yellow)
                     .add_modifier(Modifier::BOLD)
+            } else if is_done {
+                Style::default().fg(Color::DarkGray)
             } else {
-                Style::default()
+                // Colored by the newest email, so piles nobody has touched in a while stand out
+                let age = match self.app.group_emails(group).next() {
+                    Some(newest) => Style::default().fg(Age::of(&newest.date, now).color()),
+                    None => Style::default(),
+                };
+                match highlight::style_for(&self.app.highlights, self.app.group_emails(group)) {
+                    Some(highlight) => age.patch(highlight),
+                    None => age,
+                }
             };
 
             // Don't show thread indicator in NoThreads mode (we're only showing single messages)
@@ -708,23 +1772,73 @@ impl Widget for GroupListWidget<'_> {
                 "  "
             };
 
+            // Selection markers only take up room once a group is selected or kept
+            let selection_marker = if self.app.is_kept_group(&group.key) {
+                "✓ "
+            } else if self.app.is_group_selected(&group.key) {
+                "● "
+            } else if selected_groups > 0 || self.app.sweep_marks().is_some() {
+                "  "
+            } else {
+                ""
+            };
+
             let thread_count = self.app.filtered_thread_count_for_group(group);
             let email_count = self.app.full_thread_email_count_for_group(group);
             let line = if thread_count == email_count {
                 // Each email is its own thread (or in NoThreads mode)
-                format!("{}{} ({} emails)", thread_indicator, group.key, email_count)
+                format!(
+                    "{}{}{} ({} emails)",
+                    selection_marker, thread_indicator, group.key, email_count
+                )
             } else {
                 format!(
-                    "{}{} ({} emails in {} threads)",
-                    thread_indicator, group.key, email_count, thread_count
+                    "{}{}{} ({} emails in {} threads)",
+                    selection_marker, thread_indicator, group.key, email_count, thread_count
                 )
             };
-            let span = Span::styled(line, style);
+            // The first nine rows on screen are numbered for 1-9
+            let mut spans = vec![Span::styled(
+                match row_index {
+                    0..=8 => format!("{} ", row_index + 1),
+                    _ => "  ".to_string(),
+                },
+                Style::default().fg(Color::DarkGray),
+            )];
+            // The new-mail badge only takes up room once some group has new mail
+            if self.app.group_has_new(group) {
+                spans.push(Span::styled("• ", new_mail_style()));
+            } else if new_groups > 0 {
+                spans.push(Span::raw("  "));
+            }
+            spans.push(Span::styled(line, style));
+            if is_done {
+                spans.push(Span::styled(
+                    "  — done",
+                    Style::default().fg(Color::DarkGray),
+                ));
+            }
+            if let Some(usual) = self.app.habits.usual(&group.key) {
+                let label = match usual {
+                    crate::policy::PolicyAction::Archive => "archive",
+                    crate::policy::PolicyAction::Delete => "delete",
+                };
+                spans.push(Span::styled(
+                    format!("  ↻ usual: {}", label),
+                    Style::default().fg(Color::Cyan),
+                ));
+            }
+            if let Some(note) = self.app.notes.get(&group.key) {
+                spans.push(Span::styled(
+                    format!("  📝 {}", note),
+                    Style::default().fg(Color::DarkGray),
+                ));
+            }
 
             buf.set_line(
                 inner.x,
                 inner.y + row_index as u16,
-                &Line::from(span),
+                &Line::from(spans),
                 inner.width,
             );
         }
@@ -753,11 +1867,14 @@ impl StatefulWidget for EmailListWidget<'_> {
         };
 
         // Build text filter indicator
-        let text_filter_indicator = if let Some(query) = self.app.email_text_filter() {
+        let mut text_filter_indicator = if let Some(query) = self.app.email_text_filter() {
             format!(" [filter: {}]", query)
         } else {
             String::new()
         };
+        if self.app.is_sorted_by_spam_score() {
+            text_filter_indicator.push_str(" [by spam score]");
+        }
 
         // Get the title - use current group if available, otherwise use viewing_group_key
         let title = if let Some(g) = self.app.current_group() {
@@ -787,7 +1904,7 @@ impl StatefulWidget for EmailListWidget<'_> {
             " Threads ".to_string()
         };
 
-        let block = Block::default().borders(Borders::ALL).title(title);
+        let block = main_block(self.app, title);
 
         let inner = block.inner(area);
         block.render(area, buf);
@@ -846,22 +1963,97 @@ impl StatefulWidget for EmailListWidget<'_> {
         }
 
         // Display one row per thread (newest email in each thread)
+        let kept_threads = self.app.kept_thread_ids();
+        let unread_threads = self.app.unread_thread_ids();
+        let now = Utc::now();
         let rows: Vec<Row> = filtered_threads
             .iter()
             .map(|email| {
                 let has_multiple_messages = self.app.thread_has_multiple_messages(&email.thread_id);
                 let is_selected = self.app.is_email_selected(&email.id);
 
-                let selection_indicator = if is_selected { "●" } else { " " };
+                let selection_indicator = if kept_threads.contains(email.thread_id.as_str()) {
+                    "✓"
+                } else if is_selected {
+                    "●"
+                } else {
+                    " "
+                };
                 let thread_indicator = if has_multiple_messages { "◈" } else { " " };
-                let date_str = format_date(&email.date);
+                let date = Span::styled(
+                    format_date(&email.date),
+                    Style::default().fg(Age::of(&email.date, now).color()),
+                );
 
-                Row::new(vec![
-                    selection_indicator.to_string(),
-                    date_str,
-                    thread_indicator.to_string(),
-                    email.subject.clone(),
-                ])
+                // Subject followed by a dimmed body preview (snippets fall back to the subject)
+                let mut subject = Vec::new();
+                if self.app.is_new(&email.id) {
+                    subject.push(Span::styled("• ", new_mail_style()));
+                }
+                if self.app.auth_warnings_in_list && email.auth.has_failure() {
+                    subject.push(Span::styled(
+                        format!("{} auth ", WARNING_CHAR),
+                        Style::default().fg(Color::Red),
+                    ));
+                }
+                if let Some(score) = email.spam_score {
+                    let color = if score >= 5.0 {
+                        Color::Red
+                    } else {
+                        Color::DarkGray
+                    };
+                    subject.push(Span::styled(
+                        format!("[{:.1}] ", score),
+                        Style::default().fg(color),
+                    ));
+                }
+                for label in &email.labels {
+                    subject.push(Span::styled(
+                        format!("[{}] ", label),
+                        Style::default().fg(Color::Cyan),
+                    ));
+                }
+                // Threads with unread mail have bold subjects
+                let subject_style = if unread_threads.contains(email.thread_id.as_str()) {
+                    Style::default().add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default()
+                };
+                subject.push(Span::styled(email.subject.clone(), subject_style));
+                if !email.snippet.is_empty() && !email.subject.starts_with(email.snippet.as_str()) {
+                    subject.push(Span::raw("  "));
+                    subject.push(Span::styled(
+                        email.snippet.clone(),
+                        Style::default().fg(Color::DarkGray),
+                    ));
+                }
+
+                let row = match self.app.row_density {
+                    RowDensity::Compact => Row::new(vec![
+                        Cell::from(selection_indicator),
+                        Cell::from(date),
+                        Cell::from(thread_indicator),
+                        Cell::from(Line::from(subject)),
+                    ]),
+                    // Sender and date on the first line, subject and snippet below
+                    RowDensity::Comfortable => Row::new(vec![
+                        Cell::from(selection_indicator),
+                        Cell::from(date),
+                        Cell::from(thread_indicator),
+                        Cell::from(Text::from(vec![
+                            Line::from(Span::styled(
+                                email.from.to_string(),
+                                Style::default().add_modifier(Modifier::BOLD),
+                            )),
+                            Line::from(subject),
+                        ])),
+                    ])
+                    .height(2),
+                };
+                match highlight::style_for(&self.app.highlights, std::iter::once(*email)) {
+                    Some(style) => row.style(style),
+                    None => row,
+                }
             })
             .collect();
 
@@ -884,15 +2076,130 @@ impl StatefulWidget for EmailListWidget<'_> {
     }
 }
 
+/// The badge on groups and threads with mail that arrived after the first load
+fn new_mail_style() -> Style {
+    Style::default()
+        .fg(Color::Green)
+        .add_modifier(Modifier::BOLD)
+}
+
 /// Widget for rendering the thread view (all emails in a thread)
 pub struct ThreadViewWidget<'a> {
     app: &'a App,
+    body_errors: &'a HashMap<String, String>,
 }
 
 impl<'a> ThreadViewWidget<'a> {
-    pub fn new(app: &'a App) -> Self {
-        Self { app }
+    pub fn new(app: &'a App, body_errors: &'a HashMap<String, String>) -> Self {
+        Self { app, body_errors }
+    }
+
+    /// Renders the thread as a conversation with each body inline, oldest first
+    fn render_inline_bodies(&self, area: Rect, buf: &mut Buffer) {
+        let thread_emails = self.app.current_thread_emails();
+        let current_sender = self.app.current_email().map(|e| &e.from_email);
+        let width = area.width as usize;
+
+        let mut lines: Vec<Line> = Vec::new();
+        let mut selected_start = 0;
+
+        for (idx, email) in thread_emails.iter().enumerate() {
+            let is_selected = self.app.selected_thread_email == Some(idx);
+            if is_selected {
+                selected_start = lines.len();
+            }
+
+            let is_other_sender = current_sender.is_some_and(|s| s != &email.from_email);
+            let header_style = if is_selected {
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD)
+            } else if is_other_sender {
+                Style::default().fg(Color::Cyan)
+            } else {
+                Style::default().add_modifier(Modifier::BOLD)
+            };
+
+            if self.app.is_thread_email_collapsed(&email.id) {
+                // Collapsed: a single header line with a snippet preview
+                let header = format!("▸ {}  {}", format_date(&email.date), email.from);
+                let header_width = unicode_width::UnicodeWidthStr::width(header.as_str());
+                let preview: String = email
+                    .snippet
+                    .chars()
+                    .take(width.saturating_sub(header_width + 2))
+                    .collect();
+                lines.push(Line::from(vec![
+                    Span::styled(header, header_style),
+                    Span::raw("  "),
+                    Span::styled(preview, Style::default().fg(Color::DarkGray)),
+                ]));
+                continue;
+            }
+
+            lines.push(Line::from(Span::styled(
+                format!("▾ {}  {}", format_date(&email.date), email.from),
+                header_style,
+            )));
+            lines.push(Line::from(Span::styled(
+                format!("  {}", email.subject),
+                Style::default().fg(Color::DarkGray),
+            )));
+            lines.push(Line::from(""));
+
+            match (&email.body, self.body_errors.get(&email.id)) {
+                (Some(body), _) => {
+                    for line in body.lines() {
+                        lines.extend(wrap_text(line, width).into_iter().map(Line::from));
+                    }
+                }
+                (None, Some(err)) => lines.push(Line::from(Span::styled(
+                    format!("Error: {}", err),
+                    Style::default().fg(Color::Red),
+                ))),
+                (None, None) => lines.push(Line::from(Span::styled(
+                    "Loading...",
+                    Style::default().fg(Color::DarkGray),
+                ))),
+            }
+
+            lines.push(Line::from(Span::styled(
+                "─".repeat(width),
+                Style::default().fg(Color::DarkGray),
+            )));
+        }
+
+        // Pin the selected message's header to the top, then apply any extra scroll
+        let offset =
+            (selected_start + self.app.thread_body_scroll).min(lines.len().saturating_sub(1));
+        Paragraph::new(lines)
+            .scroll((offset as u16, 0))
+            .render(area, buf);
+    }
+}
+
+/// Wraps a line of text to the given display width, breaking on character boundaries
+fn wrap_text(text: &str, width: usize) -> Vec<String> {
+    use unicode_width::UnicodeWidthChar;
+
+    if width == 0 || text.is_empty() {
+        return vec![text.to_string()];
+    }
+
+    let mut wrapped = Vec::new();
+    let mut current = String::new();
+    let mut current_width = 0;
+    for c in text.chars() {
+        let char_width = c.width().unwrap_or(0);
+        if current_width + char_width > width && !current.is_empty() {
+            wrapped.push(std::mem::take(&mut current));
+            current_width = 0;
+        }
+        current.push(c);
+        current_width += char_width;
     }
+    wrapped.push(current);
+    wrapped
 }
 
 impl StatefulWidget for ThreadViewWidget<'_> {
@@ -913,14 +2220,18 @@ impl StatefulWidget for ThreadViewWidget<'_> {
             })
             .unwrap_or_else(|| " Thread ".to_string());
 
-        let block = Block::default().borders(Borders::ALL).title(title);
+        let block = main_block(self.app, title);
 
         let inner = block.inner(area);
         block.render(area, buf);
 
+        if self.app.thread_inline_bodies {
+            self.render_inline_bodies(inner, buf);
+            return;
+        }
+
         // thread_emails already sorted by date descending from above
         let current_sender = self.app.current_email().map(|e| &e.from_email);
-
         let rows: Vec<Row> = thread_emails
             .iter()
             .map(|email| {
@@ -934,24 +2245,35 @@ impl StatefulWidget for ThreadViewWidget<'_> {
 
                 let date_str = format_date(&email.date);
 
-                Row::new(vec![
-                    date_str,
-                    email.from_email.clone(),
-                    email.subject.clone(),
-                ])
-                .style(style)
+                let mut cells = vec![date_str, email.from_email.to_string()];
+                if self.app.thread_recipients_column {
+                    let only_cc = self
+                        .app
+                        .user_email()
+                        .is_some_and(|user| email.is_only_cc(user));
+                    let recipients = email.recipients_summary();
+                    cells.push(if only_cc {
+                        format!("(cc) {}", recipients)
+                    } else {
+                        recipients
+                    });
+                }
+                cells.push(email.subject.clone());
+
+                Row::new(cells).style(style)
             })
             .collect();
 
-        let table = Table::new(
-            rows,
-            [
-                Constraint::Length(12), // Date column
-                Constraint::Length(30), // Sender email
-                Constraint::Min(20),    // Subject
-            ],
-        )
-        .row_highlight_style(
+        let mut widths = vec![
+            Constraint::Length(12), // Date column
+            Constraint::Length(30), // Sender email
+        ];
+        if self.app.thread_recipients_column {
+            widths.push(Constraint::Length(30)); // Recipients
+        }
+        widths.push(Constraint::Min(20)); // Subject
+
+        let table = Table::new(rows, widths).row_highlight_style(
             Style::default()
                 .fg(Color::Yellow)
                 .add_modifier(Modifier::BOLD),
@@ -978,16 +2300,32 @@ pub struct TextViewWidget<'a> {
     app: &'a App,
     scroll_offset: usize,
     state: &'a TextViewState,
+    /// Whether the loaded body is only the first part of the message
+    truncated: bool,
+    /// The HTML view's rendering, shown instead of the plain text when set
+    html: Option<&'a [Line<'static>]>,
 }
 
 impl<'a> TextViewWidget<'a> {
-    pub fn new(app: &'a App, scroll_offset: usize, state: &'a TextViewState) -> Self {
+    pub fn new(
+        app: &'a App,
+        scroll_offset: usize,
+        state: &'a TextViewState,
+        truncated: bool,
+    ) -> Self {
         Self {
             app,
             scroll_offset,
             state,
+            truncated,
+            html: None,
         }
     }
+
+    pub fn html(mut self, html: Option<&'a [Line<'static>]>) -> Self {
+        self.html = html;
+        self
+    }
 }
 
 impl Widget for TextViewWidget<'_> {
@@ -995,14 +2333,16 @@ impl Widget for TextViewWidget<'_> {
         let email = self.app.viewing_email();
 
         // Build title
-        let title = email
-            .map(|e| format!(" {} ", e.subject))
-            .unwrap_or_else(|| " Email ".to_string());
+        let title = match (email, self.html) {
+            (Some(e), Some(_)) => format!(" {} [HTML] ", e.subject),
+            (Some(e), None) => format!(" {} ", e.subject),
+            (None, _) => " Email ".to_string(),
+        };
 
-        let block = Block::default()
-            .borders(Borders::ALL)
-            .title(title)
-            .border_style(Style::default().fg(Color::Cyan));
+        let mut block = main_block(self.app, title);
+        if self.app.accent.is_none() {
+            block = block.border_style(Style::default().fg(Color::Cyan));
+        }
 
         let inner = block.inner(area);
         block.render(area, buf);
@@ -1012,8 +2352,20 @@ impl Widget for TextViewWidget<'_> {
         if let Some(email) = email {
             header_lines.push(Line::from(vec![
                 Span::styled("From: ", Style::default().fg(Color::Yellow)),
-                Span::raw(&email.from),
+                Span::raw(email.from.as_str()),
             ]));
+            if !email.to.is_empty() {
+                header_lines.push(Line::from(vec![
+                    Span::styled("To: ", Style::default().fg(Color::Yellow)),
+                    Span::raw(email.to.join(", ")),
+                ]));
+            }
+            if !email.cc.is_empty() {
+                header_lines.push(Line::from(vec![
+                    Span::styled("Cc: ", Style::default().fg(Color::Yellow)),
+                    Span::raw(email.cc.join(", ")),
+                ]));
+            }
             header_lines.push(Line::from(vec![
                 Span::styled("Subject: ", Style::default().fg(Color::Yellow)),
                 Span::raw(&email.subject),
@@ -1022,6 +2374,14 @@ impl Widget for TextViewWidget<'_> {
                 Span::styled("Date: ", Style::default().fg(Color::Yellow)),
                 Span::raw(format_date(&email.date)),
             ]));
+            if !email.auth.is_empty() {
+                let mut spans = vec![Span::styled("Auth: ", Style::default().fg(Color::Yellow))];
+                for (name, verdict) in email.auth.checks() {
+                    spans.push(auth_badge(name, verdict));
+                    spans.push(Span::raw(" "));
+                }
+                header_lines.push(Line::from(spans));
+            }
             header_lines.push(Line::from(""));
         }
 
@@ -1040,7 +2400,18 @@ impl Widget for TextViewWidget<'_> {
                 ))]
             }
             TextViewState::Loaded(body) => {
-                body.lines().map(|l| Line::from(l.to_string())).collect()
+                let mut lines: Vec<Line> = match self.html {
+                    Some(html) => html.to_vec(),
+                    None => body.lines().map(|l| Line::from(l.to_string())).collect(),
+                };
+                if self.truncated {
+                    lines.push(Line::from(""));
+                    lines.push(Line::from(Span::styled(
+                        "… (truncated, press L to load full message)",
+                        Style::default().fg(Color::DarkGray),
+                    )));
+                }
+                lines
             }
         };
 
@@ -1055,8 +2426,85 @@ impl Widget for TextViewWidget<'_> {
     }
 }
 
-/// Widget for rendering the undo history list
-pub struct UndoHistoryWidget<'a> {
+/// Widget for rendering the duplicate sets found in loaded emails
+pub struct DuplicatesWidget<'a> {
+    app: &'a App,
+}
+
+impl<'a> DuplicatesWidget<'a> {
+    pub fn new(app: &'a App) -> Self {
+        Self { app }
+    }
+}
+
+impl StatefulWidget for DuplicatesWidget<'_> {
+    type State = TableState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let sets = &self.app.duplicate_sets;
+        let extra_count: usize = sets.iter().map(|set| set.extras().len()).sum();
+        let title = format!(
+            " Duplicates — {} set(s), {} extra copies ",
+            sets.len(),
+            extra_count
+        );
+        let block = main_block(self.app, title);
+
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        if sets.is_empty() {
+            let msg = "No duplicates found";
+            let x = inner.x + (inner.width.saturating_sub(msg.len() as u16)) / 2;
+            let y = inner.y + inner.height / 2;
+            buf.set_line(
+                x,
+                y,
+                &Line::from(Span::styled(msg, Style::default().fg(Color::DarkGray))),
+                inner.width,
+            );
+            return;
+        }
+
+        let rows: Vec<Row> = sets
+            .iter()
+            .map(|set| {
+                let keep = set.keep();
+                let folders: Vec<&str> = set
+                    .emails
+                    .iter()
+                    .map(|e| e.source_folder.as_str())
+                    .collect();
+                Row::new(vec![
+                    format_date(&keep.date),
+                    keep.from_email.to_string(),
+                    keep.subject.clone(),
+                    format!("{} copies ({})", set.emails.len(), folders.join(", ")),
+                ])
+            })
+            .collect();
+
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(12), // Date column
+                Constraint::Length(30), // Sender email
+                Constraint::Min(20),    // Subject
+                Constraint::Length(36), // Copies and folders
+            ],
+        )
+        .row_highlight_style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        );
+
+        StatefulWidget::render(table, inner, buf, state);
+    }
+}
+
+/// Widget for rendering the undo history list
+pub struct UndoHistoryWidget<'a> {
     app: &'a App,
     scroll_offset: usize,
 }
@@ -1071,11 +2519,20 @@ impl<'a> UndoHistoryWidget<'a> {
         let action_icon = match entry.action_type {
             UndoActionType::Archive => "📦",
             UndoActionType::Delete => "🗑️",
+            UndoActionType::Move => "📁",
+            UndoActionType::File => "🏷️",
         };
 
         let action_verb = match entry.action_type {
             UndoActionType::Archive => "archived",
             UndoActionType::Delete => "deleted",
+            UndoActionType::Move => "moved",
+            UndoActionType::File => "filed",
+        };
+        let destination = match entry.action_type {
+            UndoActionType::Move => format!(" to {}", entry.current_folder),
+            UndoActionType::File => format!(" in {}", entry.current_folder),
+            UndoActionType::Archive | UndoActionType::Delete => String::new(),
         };
 
         let email_count = entry.emails.len();
@@ -1084,8 +2541,8 @@ impl<'a> UndoHistoryWidget<'a> {
         match &entry.context {
             UndoContext::Group { sender } => {
                 format!(
-                    "{} {} {} {} from {}",
-                    action_icon, action_verb, email_count, email_word, sender
+                    "{} {} {} {} from {}{}",
+                    action_icon, action_verb, email_count, email_word, sender, destination
                 )
             }
             UndoContext::Thread { subject } => {
@@ -1095,14 +2552,59 @@ impl<'a> UndoHistoryWidget<'a> {
                     subject.clone()
                 };
                 format!(
-                    "{} {} thread '{}' ({} {})",
-                    action_icon, action_verb, truncated, email_count, email_word
+                    "{} {} thread '{}' ({} {}){}",
+                    action_icon, action_verb, truncated, email_count, email_word, destination
                 )
             }
         }
     }
 }
 
+impl UndoHistoryWidget<'_> {
+    /// Renders an expanded entry: its summary, then one line per email from `scroll` on
+    fn render_detail(
+        &self,
+        entry: &crate::app::UndoEntry,
+        scroll: usize,
+        inner: Rect,
+        buf: &mut Buffer,
+    ) {
+        let header = Span::styled(
+            Self::format_entry(entry),
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        );
+        buf.set_line(inner.x, inner.y, &Line::from(header), inner.width);
+
+        let details = self.app.undo_entry_details(entry);
+        for (row, detail) in details
+            .iter()
+            .skip(scroll)
+            .take(inner.height.saturating_sub(1) as usize)
+            .enumerate()
+        {
+            let mut spans = vec![Span::raw(format!("  {}", detail.subject))];
+            if !detail.from.is_empty() {
+                spans.push(Span::styled(
+                    format!("  {}", detail.from),
+                    Style::default().fg(Color::DarkGray),
+                ));
+            }
+            spans.push(Span::styled(
+                format!("  → {}", detail.folder),
+                Style::default().fg(Color::Cyan),
+            ));
+            buf.set_line(
+                inner.x,
+                inner.y + 1 + row as u16,
+                &Line::from(spans),
+                inner.width,
+            );
+        }
+    }
+}
+
 impl Widget for UndoHistoryWidget<'_> {
     fn render(self, area: Rect, buf: &mut Buffer) {
         // Calculate centered modal area (80% width, 60% height)
@@ -1143,6 +2645,13 @@ impl Widget for UndoHistoryWidget<'_> {
             return;
         }
 
+        if let Some(scroll) = self.app.undo_detail_scroll
+            && let Some(entry) = self.app.current_undo_entry()
+        {
+            self.render_detail(entry, scroll, inner, buf);
+            return;
+        }
+
         for (i, entry) in self
             .app
             .undo_history
@@ -1180,17 +2689,59 @@ impl Widget for UndoHistoryWidget<'_> {
 /// Widget for the filter bar at the bottom (when typing filter query)
 pub struct FilterBarWidget<'a> {
     query: &'a str,
+    /// Completion of the query, its untyped rest shown dimmed after it
+    completion: Option<&'a str>,
 }
 
 impl<'a> FilterBarWidget<'a> {
     pub fn new(query: &'a str) -> Self {
-        Self { query }
+        Self {
+            query,
+            completion: None,
+        }
+    }
+
+    pub fn completion(mut self, completion: Option<&'a str>) -> Self {
+        self.completion = completion;
+        self
     }
 }
 
 impl Widget for FilterBarWidget<'_> {
     fn render(self, area: Rect, buf: &mut Buffer) {
-        let text = format!("filter: {}", self.query);
+        let mut spans = vec![Span::styled(
+            format!("filter: {}", self.query),
+            Style::default().fg(Color::Yellow),
+        )];
+        if let Some(completion) = self.completion {
+            let rest: String = completion
+                .chars()
+                .skip(self.query.chars().count())
+                .collect();
+            spans.push(Span::styled(rest, Style::default().fg(Color::DarkGray)));
+            spans.push(Span::styled(
+                "  Tab: complete",
+                Style::default().fg(Color::DarkGray),
+            ));
+        }
+        Paragraph::new(Line::from(spans)).render(area, buf);
+    }
+}
+
+/// Widget for the sender note input bar
+pub struct NoteBarWidget<'a> {
+    input: &'a NoteInput,
+}
+
+impl<'a> NoteBarWidget<'a> {
+    pub fn new(input: &'a NoteInput) -> Self {
+        Self { input }
+    }
+}
+
+impl Widget for NoteBarWidget<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let text = format!("note for {}: {}", self.input.key, self.input.text);
         let paragraph = Paragraph::new(text).style(Style::default().fg(Color::Yellow));
         paragraph.render(area, buf);
     }
@@ -1297,10 +2848,17 @@ pub fn help_text_for_app(app: &App) -> &'static str {
             }
         }
         View::Thread => {
-            "j/k: navigate  Enter: view body  e: browser  A/D: archive/delete  q: back  ?: more"
+            if app.thread_inline_bodies {
+                "j/k: message  Space: collapse  o/O: expand/collapse all  v: list  A/D: archive/delete  ?: more"
+            } else {
+                "j/k: navigate  Enter: view body  v: inline  e: browser  A/D: archive/delete  ?: more"
+            }
+        }
+        View::UndoHistory => {
+            "j/k: navigate  Enter: undo  a: undo all to here  Space: emails  q: back  ?: more"
         }
-        View::UndoHistory => "j/k: navigate  Enter: undo  q: back  ?: more",
         View::EmailBody => "j/k: scroll  e: browser  A/D: archive/delete  Esc: back  ?: more",
+        View::Duplicates => "j/k: navigate  a/d: clean set  A/D: clean all sets  q: back  ?: more",
     }
 }
 
@@ -1323,133 +2881,657 @@ impl Widget for HelpBarWidget<'_> {
     }
 }
 
-/// Widget for the full help menu modal
+/// Emails processed per minute, counting a session shorter than a minute as one minute
+pub fn processing_rate(processed: usize, elapsed: Duration) -> usize {
+    let minutes = elapsed.as_secs_f64().max(60.0) / 60.0;
+    (processed as f64 / minutes).round() as usize
+}
+
+/// Widget for the "processed: N (R/min)" counter at the right end of the help bar
+pub struct ProcessingRateWidget {
+    processed: usize,
+    elapsed: Duration,
+}
+
+impl ProcessingRateWidget {
+    pub fn new(app: &App, started: Instant) -> Self {
+        Self {
+            processed: app.archived_count + app.deleted_count,
+            elapsed: started.elapsed(),
+        }
+    }
+}
+
+impl Widget for ProcessingRateWidget {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if self.processed == 0 {
+            return;
+        }
+        let text = format!(
+            " processed: {} ({}/min)",
+            self.processed,
+            processing_rate(self.processed, self.elapsed)
+        );
+        let width = (text.len() as u16).min(area.width);
+        let x = area.x + area.width - width;
+        Paragraph::new(text)
+            .style(Style::default().fg(Color::Green))
+            .render(Rect::new(x, area.y, width, 1), buf);
+    }
+}
+
+/// Widget for the onboarding tour box in the bottom-right corner
+pub struct TourWidget<'a> {
+    tour: &'a Tour,
+}
+
+impl<'a> TourWidget<'a> {
+    pub fn new(tour: &'a Tour) -> Self {
+        Self { tour }
+    }
+
+    /// Returns (step number, instructions) for the current step
+    fn content(&self) -> (usize, &'static str) {
+        match self.tour.step() {
+            TourStep::OpenGroup => (
+                1,
+                "Welcome to zeroterm! Emails are grouped by sender. \
+                 Use j/k to pick a group, then press Enter to open it.",
+            ),
+            TourStep::Archive => (
+                2,
+                "These are the sender's emails. Press a to archive the selected one, \
+                 or A to archive everything from this sender.",
+            ),
+            TourStep::OpenUndo => (
+                3,
+                "Archived! Changed your mind? Press u to open undo history.",
+            ),
+            TourStep::Restore => (
+                4,
+                "Press Enter to undo the selected action, or Esc to go back. \
+                 Press ? any time to see every shortcut.",
+            ),
+        }
+    }
+}
+
+impl Widget for TourWidget<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let (number, text) = self.content();
+
+        let box_width = 48_u16.min(area.width);
+        let box_height = 8_u16.min(area.height);
+        // Sit above the help bar in the bottom-right corner
+        let x = area.x + area.width.saturating_sub(box_width + 1);
+        let y = area.y + area.height.saturating_sub(box_height + 1);
+        let modal_area = Rect::new(x, y, box_width, box_height);
+
+        // Clear the area behind the modal
+        for row in modal_area.y..modal_area.y + modal_area.height {
+            for col in modal_area.x..modal_area.x + modal_area.width {
+                buf[(col, row)].set_char(' ');
+                buf[(col, row)].set_style(Style::default());
+            }
+        }
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(format!(" Tour {}/4 ", number))
+            .border_style(Style::default().fg(Color::Green));
+        let inner = block.inner(modal_area);
+        block.render(modal_area, buf);
+
+        let lines = vec![
+            Line::from(Span::styled(text, Style::default().fg(Color::White))),
+            Line::from(""),
+            Line::from(Span::styled(
+                "x: end tour",
+                Style::default().fg(Color::DarkGray),
+            )),
+        ];
+        Paragraph::new(lines)
+            .wrap(Wrap { trim: true })
+            .render(inner, buf);
+    }
+}
+
+/// Countdown for a timeboxed triage session, with counts taken when it started
+#[derive(Debug)]
+pub struct TriageTimer {
+    started: Instant,
+    length: Duration,
+    archived_at_start: usize,
+    deleted_at_start: usize,
+}
+
+impl TriageTimer {
+    pub fn new(length: Duration, app: &App) -> Self {
+        Self {
+            started: Instant::now(),
+            length,
+            archived_at_start: app.archived_count,
+            deleted_at_start: app.deleted_count,
+        }
+    }
+
+    /// Time left in the session, zero once it's up
+    pub fn remaining(&self) -> Duration {
+        self.length.saturating_sub(self.started.elapsed())
+    }
+
+    pub fn is_up(&self) -> bool {
+        self.remaining().is_zero()
+    }
+
+    /// One-line end-of-session banner with what was cleared during the session
+    pub fn summary(&self, app: &App) -> String {
+        format!(
+            "⏰ Time's up after {} min: {} archived, {} deleted. Nice work, take a break!",
+            self.length.as_secs().div_ceil(60),
+            app.archived_count.saturating_sub(self.archived_at_start),
+            app.deleted_count.saturating_sub(self.deleted_at_start)
+        )
+    }
+}
+
+/// Widget for the triage timer countdown in the top-right corner of the main border
+pub struct TriageTimerWidget<'a> {
+    timer: &'a TriageTimer,
+}
+
+impl<'a> TriageTimerWidget<'a> {
+    pub fn new(timer: &'a TriageTimer) -> Self {
+        Self { timer }
+    }
+}
+
+impl Widget for TriageTimerWidget<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let secs = self.timer.remaining().as_secs();
+        let text = format!(" ⏱ {:02}:{:02} ", secs / 60, secs % 60);
+        let width = (unicode_width::UnicodeWidthStr::width(text.as_str()) as u16).min(area.width);
+        // Sits on the top border, clear of the title on the left
+        let x = area.x + area.width.saturating_sub(width + 2);
+        // The last minute turns yellow as a gentle heads-up
+        let color = if secs < 60 {
+            Color::Yellow
+        } else {
+            Color::Cyan
+        };
+        Paragraph::new(Span::styled(text, Style::default().fg(color)))
+            .render(Rect::new(x, area.y, width, 1), buf);
+    }
+}
+
+/// Views a key binding applies to
+#[derive(Debug, Clone, Copy)]
+enum BindingViews {
+    /// Works the same in every view
+    All,
+    Only(&'static [View]),
+}
+
+/// One entry in the keymap shown by the help menu
+#[derive(Debug)]
+struct KeyBinding {
+    section: &'static str,
+    keys: &'static str,
+    description: &'static str,
+    views: BindingViews,
+}
+
+impl KeyBinding {
+    const fn new(
+        section: &'static str,
+        keys: &'static str,
+        description: &'static str,
+        views: BindingViews,
+    ) -> Self {
+        Self {
+            section,
+            keys,
+            description,
+            views,
+        }
+    }
+
+    fn applies_to(&self, view: View) -> bool {
+        match self.views {
+            BindingViews::All => true,
+            BindingViews::Only(views) => views.contains(&view),
+        }
+    }
+}
+
+const LIST_VIEWS: &[View] = &[
+    View::GroupList,
+    View::EmailList,
+    View::Thread,
+    View::UndoHistory,
+    View::Duplicates,
+];
+
+/// Every key binding, in the order the help menu lists them
+const KEYMAP: &[KeyBinding] = {
+    use BindingViews::{All, Only};
+    const NAV: &str = "Navigation";
+    const ACTIONS: &str = "Actions";
+    const GENERAL: &str = "General";
+    &[
+        KeyBinding::new(NAV, "j / ↓", "Move down", Only(LIST_VIEWS)),
+        KeyBinding::new(NAV, "k / ↑", "Move up", Only(LIST_VIEWS)),
+        KeyBinding::new(NAV, "j / ↓", "Scroll down", Only(&[View::EmailBody])),
+        KeyBinding::new(NAV, "k / ↑", "Scroll up", Only(&[View::EmailBody])),
+        KeyBinding::new(NAV, "g g", "Go to top", All),
+        KeyBinding::new(NAV, "G", "Go to bottom", All),
+        KeyBinding::new(NAV, "Ctrl+d", "Half page down", All),
+        KeyBinding::new(NAV, "Ctrl+u", "Half page up", All),
+        KeyBinding::new(NAV, "/", "Filter groups", Only(&[View::GroupList])),
+        KeyBinding::new(NAV, "b", "Jump to biggest group", Only(&[View::GroupList])),
+        KeyBinding::new(
+            NAV,
+            "' a-z",
+            "Jump to group by letter",
+            Only(&[View::GroupList]),
+        ),
+        KeyBinding::new(NAV, "1-9", "Open numbered group", Only(&[View::GroupList])),
+        KeyBinding::new(
+            NAV,
+            "J",
+            "Jump to new mail",
+            Only(&[View::GroupList, View::EmailList]),
+        ),
+        KeyBinding::new(
+            NAV,
+            "s",
+            "Surprise me (random group)",
+            Only(&[View::GroupList]),
+        ),
+        KeyBinding::new(NAV, "/", "Filter emails", Only(&[View::EmailList])),
+        KeyBinding::new(
+            NAV,
+            "Esc",
+            "Clear filter",
+            Only(&[View::GroupList, View::EmailList]),
+        ),
+        KeyBinding::new(ACTIONS, "Enter", "Open group", Only(&[View::GroupList])),
+        KeyBinding::new(ACTIONS, "Enter", "View thread", Only(&[View::EmailList])),
+        KeyBinding::new(ACTIONS, "Enter", "View email body", Only(&[View::Thread])),
+        KeyBinding::new(
+            ACTIONS,
+            "Enter",
+            "Undo selected action",
+            Only(&[View::UndoHistory]),
+        ),
+        KeyBinding::new(
+            ACTIONS,
+            "Space",
+            "List the action's emails",
+            Only(&[View::UndoHistory]),
+        ),
+        KeyBinding::new(
+            ACTIONS,
+            "a",
+            "Undo everything down to the selected action",
+            Only(&[View::UndoHistory]),
+        ),
+        KeyBinding::new(
+            ACTIONS,
+            "i",
+            "Undo into the inbox",
+            Only(&[View::UndoHistory]),
+        ),
+        KeyBinding::new(
+            ACTIONS,
+            "v",
+            "Undo into a picked folder",
+            Only(&[View::UndoHistory]),
+        ),
+        KeyBinding::new(
+            ACTIONS,
+            "m",
+            "Cycle email/domain/label grouping",
+            Only(&[View::GroupList]),
+        ),
+        KeyBinding::new(ACTIONS, "Space", "Select group", Only(&[View::GroupList])),
+        KeyBinding::new(
+            ACTIONS,
+            "c",
+            "Mark group done for now",
+            Only(&[View::GroupList]),
+        ),
+        KeyBinding::new(NAV, "H", "Show/hide small groups", Only(&[View::GroupList])),
+        KeyBinding::new(
+            NAV,
+            "x",
+            "Hide for this session",
+            Only(&[View::GroupList, View::EmailList]),
+        ),
+        KeyBinding::new(NAV, "U", "Show hidden again", Only(&[View::GroupList])),
+        KeyBinding::new(
+            ACTIONS,
+            "U",
+            "Unsubscribe from the sender",
+            Only(&[View::EmailList, View::Thread, View::EmailBody]),
+        ),
+        KeyBinding::new(
+            ACTIONS,
+            "R",
+            "Reply",
+            Only(&[View::Thread, View::EmailBody]),
+        ),
+        KeyBinding::new(ACTIONS, "f", "Forward", Only(&[View::EmailBody])),
+        KeyBinding::new(
+            ACTIONS,
+            "Ctrl+r",
+            "Reply to all",
+            Only(&[View::Thread, View::EmailBody]),
+        ),
+        KeyBinding::new(
+            ACTIONS,
+            "A",
+            "Archive selected groups",
+            Only(&[View::GroupList]),
+        ),
+        KeyBinding::new(
+            ACTIONS,
+            "D",
+            "Delete selected groups",
+            Only(&[View::GroupList]),
+        ),
+        KeyBinding::new(ACTIONS, "a", "Archive email", Only(&[View::EmailList])),
+        KeyBinding::new(
+            ACTIONS,
+            "A",
+            "Archive all in group",
+            Only(&[View::EmailList]),
+        ),
+        KeyBinding::new(ACTIONS, "d", "Delete email", Only(&[View::EmailList])),
+        KeyBinding::new(
+            ACTIONS,
+            "D",
+            "Delete all in group",
+            Only(&[View::EmailList]),
+        ),
+        KeyBinding::new(
+            ACTIONS,
+            "Space",
+            "Select/deselect",
+            Only(&[View::EmailList]),
+        ),
+        KeyBinding::new(
+            ACTIONS,
+            "a",
+            "Archive extra copies in set",
+            Only(&[View::Duplicates]),
+        ),
+        KeyBinding::new(
+            ACTIONS,
+            "d",
+            "Delete extra copies in set",
+            Only(&[View::Duplicates]),
+        ),
+        KeyBinding::new(
+            ACTIONS,
+            "A",
+            "Archive extra copies in all sets",
+            Only(&[View::Duplicates]),
+        ),
+        KeyBinding::new(
+            ACTIONS,
+            "D",
+            "Delete extra copies in all sets",
+            Only(&[View::Duplicates]),
+        ),
+        KeyBinding::new(ACTIONS, "v", "Toggle inline bodies", Only(&[View::Thread])),
+        KeyBinding::new(
+            ACTIONS,
+            "v",
+            "Toggle one-/two-line rows",
+            Only(&[View::EmailList]),
+        ),
+        KeyBinding::new(NAV, "%", "Sort by spam score", Only(&[View::EmailList])),
+        KeyBinding::new(
+            ACTIONS,
+            "Space",
+            "Collapse/expand (inline)",
+            Only(&[View::Thread]),
+        ),
+        KeyBinding::new(
+            ACTIONS,
+            "o / O",
+            "Expand/collapse all",
+            Only(&[View::Thread]),
+        ),
+        KeyBinding::new(
+            ACTIONS,
+            "e",
+            "Open in browser",
+            Only(&[View::Thread, View::EmailBody]),
+        ),
+        KeyBinding::new(
+            ACTIONS,
+            "E",
+            "Open thread in browser",
+            Only(&[View::Thread, View::EmailBody]),
+        ),
+        KeyBinding::new(
+            ACTIONS,
+            "M",
+            "Mark read/unread",
+            Only(&[View::EmailList, View::Thread, View::EmailBody]),
+        ),
+        KeyBinding::new(
+            ACTIONS,
+            "V",
+            "Move to folder",
+            Only(&[
+                View::GroupList,
+                View::EmailList,
+                View::Thread,
+                View::EmailBody,
+            ]),
+        ),
+        KeyBinding::new(
+            ACTIONS,
+            "+ / -",
+            "Add / remove a Gmail label",
+            Only(&[
+                View::GroupList,
+                View::EmailList,
+                View::Thread,
+                View::EmailBody,
+            ]),
+        ),
+        KeyBinding::new(
+            ACTIONS,
+            "F",
+            "Label and archive",
+            Only(&[
+                View::GroupList,
+                View::EmailList,
+                View::Thread,
+                View::EmailBody,
+            ]),
+        ),
+        KeyBinding::new(
+            ACTIONS,
+            "A",
+            "Archive thread",
+            Only(&[View::Thread, View::EmailBody]),
+        ),
+        KeyBinding::new(
+            ACTIONS,
+            "D",
+            "Delete thread",
+            Only(&[View::Thread, View::EmailBody]),
+        ),
+        KeyBinding::new(
+            ACTIONS,
+            "t",
+            "Toggle threads only",
+            Only(&[View::GroupList, View::EmailList]),
+        ),
+        KeyBinding::new(ACTIONS, "r", "Refresh", Only(&[View::GroupList])),
+        KeyBinding::new(ACTIONS, "f", "Find duplicates", Only(&[View::GroupList])),
+        KeyBinding::new(
+            ACTIONS,
+            "Z",
+            "Archive old inbox mail",
+            Only(&[View::GroupList]),
+        ),
+        KeyBinding::new(
+            ACTIONS,
+            "Z",
+            "Archive the group's mail older than 30 days (Z again: 7/90/365)",
+            Only(&[View::EmailList]),
+        ),
+        KeyBinding::new(
+            ACTIONS,
+            "S",
+            "Archive all single messages",
+            Only(&[View::GroupList]),
+        ),
+        KeyBinding::new(
+            ACTIONS,
+            "R",
+            "Archive read mail in the inbox",
+            Only(&[View::GroupList]),
+        ),
+        KeyBinding::new(
+            ACTIONS,
+            "R",
+            "Archive the group's read mail",
+            Only(&[View::EmailList]),
+        ),
+        KeyBinding::new(
+            ACTIONS,
+            "W",
+            "Sweep: archive all but kept",
+            Only(&[View::GroupList, View::EmailList]),
+        ),
+        KeyBinding::new(ACTIONS, "L", "Load older mail", Only(&[View::GroupList])),
+        KeyBinding::new(
+            ACTIONS,
+            "n",
+            "Edit sender note",
+            Only(&[View::GroupList, View::EmailList]),
+        ),
+        KeyBinding::new(
+            ACTIONS,
+            ".",
+            "Do the usual for sender",
+            Only(&[View::GroupList, View::EmailList]),
+        ),
+        KeyBinding::new(ACTIONS, "I", "Ignore sender", Only(&[View::GroupList])),
+        KeyBinding::new(
+            ACTIONS,
+            "w",
+            "Postpone group until later",
+            Only(&[View::GroupList]),
+        ),
+        KeyBinding::new(ACTIONS, "L", "Load full message", Only(&[View::EmailBody])),
+        KeyBinding::new(
+            ACTIONS,
+            "h",
+            "Toggle HTML rendering",
+            Only(&[View::EmailBody]),
+        ),
+        KeyBinding::new(
+            ACTIONS,
+            "T",
+            "Create task",
+            Only(&[View::EmailList, View::Thread, View::EmailBody]),
+        ),
+        KeyBinding::new(
+            ACTIONS,
+            "C",
+            "Capture to notes",
+            Only(&[View::EmailList, View::Thread, View::EmailBody]),
+        ),
+        KeyBinding::new(
+            ACTIONS,
+            "P",
+            "Print email",
+            Only(&[View::EmailList, View::Thread, View::EmailBody]),
+        ),
+        KeyBinding::new(
+            ACTIONS,
+            "u",
+            "Undo history",
+            Only(&[View::GroupList, View::EmailList, View::Thread]),
+        ),
+        KeyBinding::new(GENERAL, "Esc", "Back", Only(&[View::EmailBody])),
+        KeyBinding::new(
+            GENERAL,
+            "q",
+            "Quit",
+            Only(&[View::GroupList, View::EmailBody]),
+        ),
+        KeyBinding::new(
+            GENERAL,
+            "q",
+            "Back",
+            Only(&[
+                View::EmailList,
+                View::Thread,
+                View::UndoHistory,
+                View::Duplicates,
+            ]),
+        ),
+        KeyBinding::new(GENERAL, "?", "Toggle this help", All),
+        KeyBinding::new(GENERAL, "!", "Recent messages", All),
+    ]
+};
+
+/// Widget for the help menu modal: the current view's bindings, or
+/// (after Tab) the bindings that work in every view
 pub struct HelpMenuWidget {
     view: View,
+    global: bool,
 }
 
 impl HelpMenuWidget {
-    pub fn new(view: View) -> Self {
-        Self { view }
+    pub fn new(view: View, global: bool) -> Self {
+        Self { view, global }
     }
 
     fn help_sections(&self) -> Vec<(&'static str, Vec<(&'static str, &'static str)>)> {
-        let nav = (
-            "Navigation",
-            vec![
-                ("j / ↓", "Move down"),
-                ("k / ↑", "Move up"),
-                ("g g", "Go to top"),
-                ("G", "Go to bottom"),
-                ("Ctrl+d", "Half page down"),
-                ("Ctrl+u", "Half page up"),
-            ],
-        );
+        let mut sections: Vec<(&'static str, Vec<(&'static str, &'static str)>)> = Vec::new();
+        let bindings = KEYMAP.iter().filter(|b| {
+            if self.global {
+                matches!(b.views, BindingViews::All)
+            } else {
+                b.applies_to(self.view)
+            }
+        });
 
-        let nav_with_group_filter = (
-            "Navigation",
-            vec![
-                ("j / ↓", "Move down"),
-                ("k / ↑", "Move up"),
-                ("g g", "Go to top"),
-                ("G", "Go to bottom"),
-                ("Ctrl+d", "Half page down"),
-                ("Ctrl+u", "Half page up"),
-                ("/", "Filter groups"),
-                ("Esc", "Clear filter"),
-            ],
-        );
+        for binding in bindings {
+            let entry = (binding.keys, binding.description);
+            match sections
+                .iter_mut()
+                .find(|(title, _)| *title == binding.section)
+            {
+                Some((_, entries)) => entries.push(entry),
+                None => sections.push((binding.section, vec![entry])),
+            }
+        }
 
-        let nav_with_filter = (
-            "Navigation",
-            vec![
-                ("j / ↓", "Move down"),
-                ("k / ↑", "Move up"),
-                ("g g", "Go to top"),
-                ("G", "Go to bottom"),
-                ("Ctrl+d", "Half page down"),
-                ("Ctrl+u", "Half page up"),
-                ("/", "Filter emails"),
-                ("Esc", "Clear filter"),
-            ],
-        );
+        sections
+    }
 
+    fn title(&self) -> &'static str {
+        if self.global {
+            return " Help: all views ";
+        }
         match self.view {
-            View::GroupList => vec![
-                nav_with_group_filter,
-                (
-                    "Actions",
-                    vec![
-                        ("Enter", "Open group"),
-                        ("m", "Toggle email/domain mode"),
-                        ("t", "Toggle threads only"),
-                        ("r", "Refresh"),
-                        ("u", "Undo history"),
-                    ],
-                ),
-                ("General", vec![("q", "Quit"), ("?", "Toggle this help")]),
-            ],
-            View::EmailList => vec![
-                nav_with_filter,
-                (
-                    "Actions",
-                    vec![
-                        ("Enter", "View thread"),
-                        ("a", "Archive email"),
-                        ("A", "Archive all in group"),
-                        ("d", "Delete email"),
-                        ("D", "Delete all in group"),
-                        ("Space", "Select/deselect"),
-                        ("t", "Toggle threads only"),
-                        ("u", "Undo history"),
-                    ],
-                ),
-                ("General", vec![("q", "Back"), ("?", "Toggle this help")]),
-            ],
-            View::Thread => vec![
-                nav,
-                (
-                    "Actions",
-                    vec![
-                        ("Enter", "View email body"),
-                        ("e", "Open in browser"),
-                        ("A", "Archive thread"),
-                        ("D", "Delete thread"),
-                        ("u", "Undo history"),
-                    ],
-                ),
-                ("General", vec![("q", "Back"), ("?", "Toggle this help")]),
-            ],
-            View::UndoHistory => vec![
-                nav,
-                ("Actions", vec![("Enter", "Undo selected action")]),
-                ("General", vec![("q", "Back"), ("?", "Toggle this help")]),
-            ],
-            View::EmailBody => vec![
-                (
-                    "Navigation",
-                    vec![
-                        ("j / ↓", "Scroll down"),
-                        ("k / ↑", "Scroll up"),
-                        ("g g", "Go to top"),
-                        ("G", "Go to bottom"),
-                        ("Ctrl+d", "Half page down"),
-                        ("Ctrl+u", "Half page up"),
-                    ],
-                ),
-                (
-                    "Actions",
-                    vec![
-                        ("e", "Open in browser"),
-                        ("A", "Archive thread"),
-                        ("D", "Delete thread"),
-                    ],
-                ),
-                (
-                    "General",
-                    vec![("Esc", "Back"), ("q", "Quit"), ("?", "Toggle this help")],
-                ),
-            ],
+            View::GroupList => " Help: groups ",
+            View::EmailList => " Help: emails ",
+            View::Thread => " Help: thread ",
+            View::UndoHistory => " Help: undo history ",
+            View::EmailBody => " Help: email ",
+            View::Duplicates => " Help: duplicates ",
         }
     }
 }
@@ -1483,7 +3565,7 @@ impl Widget for HelpMenuWidget {
 
         let block = Block::default()
             .borders(Borders::ALL)
-            .title(" Help ")
+            .title(self.title())
             .border_style(Style::default().fg(Color::Cyan));
 
         let inner = block.inner(modal_area);
@@ -1528,7 +3610,11 @@ impl Widget for HelpMenuWidget {
 
         // Footer hint
         let footer_y = modal_area.y + modal_area.height - 2;
-        let footer = "Press ? or Esc to close";
+        let footer = if self.global {
+            "Tab: this view  ?/Esc: close"
+        } else {
+            "Tab: all views  ?/Esc: close"
+        };
         let footer_x = inner.x + (inner.width.saturating_sub(footer.len() as u16)) / 2;
         buf.set_line(
             footer_x,
@@ -1539,32 +3625,185 @@ impl Widget for HelpMenuWidget {
     }
 }
 
+/// The word a large delete asks to be typed in place of y
+pub const TYPED_CONFIRM_WORD: &str = "delete";
+
+/// An answer a large archive or delete wants typed in place of y
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypedConfirm {
+    /// Any of these confirms: "delete", or the email count and the sender
+    pub answers: Vec<String>,
+    /// What's been typed so far
+    pub text: String,
+}
+
+impl TypedConfirm {
+    pub fn new(answers: Vec<String>) -> Self {
+        Self {
+            answers,
+            text: String::new(),
+        }
+    }
+
+    /// Returns true when what's been typed is one of the answers, ignoring case and the
+    /// thousands separators in a count
+    pub fn is_answered(&self) -> bool {
+        let typed = self.text.trim();
+        let digits = typed.replace(',', "");
+        self.answers
+            .iter()
+            .any(|answer| answer.eq_ignore_ascii_case(typed) || *answer == digits)
+    }
+
+    /// The answers as offered in the dialog: "1200" or "news@example.com"
+    pub fn choices(&self) -> String {
+        self.answers
+            .iter()
+            .map(|answer| format!("\"{}\"", answer))
+            .collect::<Vec<_>>()
+            .join(" or ")
+    }
+}
+
+/// Subjects listed under a bulk confirmation before "and N more"
+const CONFIRM_SAMPLE_SIZE: usize = 5;
+
 /// Widget for the confirmation dialog
 pub struct ConfirmDialogWidget<'a> {
     action: &'a ConfirmAction,
+    /// Note for the sender the action targets, shown under the question
+    note: Option<&'a str>,
+    /// The emails the action archives or deletes, newest first, to sample under the question
+    affected: Vec<&'a Email>,
+    /// The answer to type, when the action needs one instead of y
+    typed: Option<&'a TypedConfirm>,
 }
 
 impl<'a> ConfirmDialogWidget<'a> {
-    pub fn new(action: &'a ConfirmAction) -> Self {
-        Self { action }
+    pub fn new(action: &'a ConfirmAction, note: Option<&'a str>) -> Self {
+        Self {
+            action,
+            note,
+            affected: Vec::new(),
+            typed: None,
+        }
+    }
+
+    /// Lists the first few of the emails the action takes out of the inbox
+    pub fn affected(mut self, affected: Vec<&'a Email>) -> Self {
+        self.affected = affected;
+        self
+    }
+
+    /// Asks for a typed answer instead of y, showing what's been typed so far
+    pub fn typed(mut self, typed: Option<&'a TypedConfirm>) -> Self {
+        self.typed = typed;
+        self
     }
 }
 
+/// Returns the emails an archive or delete confirmation is about, newest first, so the
+/// dialog can show what's going before the group is opened
+pub fn affected_emails<'a>(app: &'a App, action: &ConfirmAction) -> Vec<&'a Email> {
+    let mut emails = match action {
+        ConfirmAction::ArchiveEmails { .. } | ConfirmAction::DeleteEmails { .. } => {
+            app.current_group_thread_emails()
+        }
+        ConfirmAction::ArchiveThread { .. } | ConfirmAction::DeleteThread { .. } => {
+            app.current_thread_emails()
+        }
+        ConfirmAction::ArchiveSelected { .. } | ConfirmAction::DeleteSelected { .. } => {
+            app.selected_thread_emails()
+        }
+        ConfirmAction::ArchiveGroups { .. } | ConfirmAction::DeleteGroups { .. } => {
+            app.selected_groups_thread_emails()
+        }
+        ConfirmAction::ArchiveDuplicates { all_sets, .. }
+        | ConfirmAction::DeleteDuplicates { all_sets, .. } => app.duplicate_extras(*all_sets),
+        ConfirmAction::ArchiveOld { .. } => app.old_inbox_emails(),
+        ConfirmAction::ArchiveGroupOld { group, cutoff, .. } => {
+            app.group_emails_older_than(group, *cutoff)
+        }
+        ConfirmAction::ArchiveSingles { .. } => app.single_message_emails(),
+        ConfirmAction::ArchiveRead { group, .. } => app.read_inbox_emails(group.as_deref()),
+        ConfirmAction::ArchiveSweep { .. } => app.sweep_emails(),
+        ConfirmAction::ArchiveSender { sender, .. } => app.sender_inbox_emails(sender),
+        _ => Vec::new(),
+    };
+    emails.sort_by_key(|e| std::cmp::Reverse(e.date));
+    emails
+}
+
+/// Returns the sender note to show when confirming an action on one group
+pub fn confirm_note<'a>(app: &'a App, action: &ConfirmAction) -> Option<&'a str> {
+    let key = match action {
+        ConfirmAction::ArchiveEmails { sender, .. }
+        | ConfirmAction::DeleteEmails { sender, .. }
+        | ConfirmAction::ArchiveSender { sender, .. }
+        | ConfirmAction::IgnoreSender { sender } => sender.as_str(),
+        ConfirmAction::ArchiveThread { .. }
+        | ConfirmAction::DeleteThread { .. }
+        | ConfirmAction::ArchiveSelected { .. }
+        | ConfirmAction::DeleteSelected { .. } => app.current_group()?.key.as_str(),
+        _ => return None,
+    };
+    app.notes.get(key)
+}
+
 impl Widget for ConfirmDialogWidget<'_> {
     fn render(self, area: Rect, buf: &mut Buffer) {
         use unicode_width::UnicodeWidthStr;
 
-        let message = self.action.message();
+        let mut message = self.action.message();
+        let prompt = self.typed.map(|typed| {
+            message = message.trim_end_matches(" (y/n)").to_string();
+            format!(
+                "Type {} and press Enter (Esc cancels): {}▏",
+                typed.choices(),
+                typed.text
+            )
+        });
         let msg_width = message.width() as u16;
-
-        // Calculate box size based on content (message + horizontal and vertical padding)
-        let horizontal_padding = 4_u16; // 2 chars on each side
-        let vertical_padding = 2_u16; // 1 line above and below
-        let box_width = (msg_width + horizontal_padding + 2)
-            .max(20)
-            .min(area.width.saturating_sub(4));
-        let box_height = 3 + vertical_padding; // border + padding + content + padding + border
-
+        let note = self.note.map(|n| format!("📝 {}", n));
+        let note_width = note.as_ref().map_or(0, |n| n.width() as u16);
+        let prompt_width = prompt.as_ref().map_or(0, |p| p.width() as u16);
+        // A few of the affected emails, then how many more there are
+        let mut sample: Vec<String> = self
+            .affected
+            .iter()
+            .take(CONFIRM_SAMPLE_SIZE)
+            .map(|e| format!("{}  {}", format_date(&e.date), e.subject))
+            .collect();
+        if self.affected.len() > CONFIRM_SAMPLE_SIZE {
+            sample.push(format!(
+                "and {} more",
+                self.affected.len() - CONFIRM_SAMPLE_SIZE
+            ));
+        }
+        let sample_width = sample.iter().map(|l| l.width() as u16).max().unwrap_or(0);
+        let content_height = 1
+            + u16::from(note.is_some())
+            + u16::from(prompt.is_some())
+            + if sample.is_empty() {
+                0
+            } else {
+                sample.len() as u16 + 1
+            };
+
+        // Calculate box size based on content (message + horizontal and vertical padding)
+        let horizontal_padding = 4_u16; // 2 chars on each side
+        let vertical_padding = 2_u16; // 1 line above and below
+        let box_width = (msg_width
+            .max(note_width)
+            .max(prompt_width)
+            .max(sample_width.min(72))
+            + horizontal_padding
+            + 2)
+        .max(20)
+        .min(area.width.saturating_sub(4));
+        // border + padding + content + padding + border
+        let box_height = 2 + content_height + vertical_padding;
+
         // Center the box
         let x = area.x + (area.width.saturating_sub(box_width)) / 2;
         let y = area.y + (area.height.saturating_sub(box_height)) / 2;
@@ -1579,17 +3818,26 @@ impl Widget for ConfirmDialogWidget<'_> {
             }
         }
 
+        // Deletes get a red frame of their own so they never look like an archive
+        let (title, border_style) = if self.action.delete_count().is_some() {
+            (
+                " ⚠ Delete ",
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            )
+        } else {
+            (" Confirm ", Style::default().fg(Color::Yellow))
+        };
         let block = Block::default()
             .borders(Borders::ALL)
-            .title(" Confirm ")
-            .border_style(Style::default().fg(Color::Red));
+            .title(title)
+            .border_style(border_style);
 
         let inner = block.inner(modal_area);
         block.render(modal_area, buf);
 
         // Center the message horizontally and vertically within the inner area
         let msg_x = inner.x + inner.width.saturating_sub(msg_width) / 2;
-        let msg_y = inner.y + inner.height / 2;
+        let msg_y = inner.y + inner.height.saturating_sub(content_height) / 2;
 
         buf.set_line(
             msg_x,
@@ -1597,6 +3845,106 @@ impl Widget for ConfirmDialogWidget<'_> {
             &Line::from(Span::styled(message, Style::default().fg(Color::White))),
             inner.width,
         );
+
+        let mut line_y = msg_y + 1;
+        if let Some(note) = note {
+            let note_x = inner.x + inner.width.saturating_sub(note_width) / 2;
+            buf.set_line(
+                note_x,
+                line_y,
+                &Line::from(Span::styled(note, Style::default().fg(Color::Yellow))),
+                inner.width,
+            );
+            line_y += 1;
+        }
+        if !sample.is_empty() {
+            // Left-aligned under a blank line, cut at the dialog's edge
+            let sample_x = inner.x + 2;
+            let sample_width = inner.width.saturating_sub(4);
+            let sample_rows = sample.len() as u16;
+            for (i, line) in sample.into_iter().enumerate() {
+                buf.set_line(
+                    sample_x,
+                    line_y + 1 + i as u16,
+                    &Line::from(Span::styled(line, Style::default().fg(Color::Gray))),
+                    sample_width,
+                );
+            }
+            line_y += 1 + sample_rows;
+        }
+        if let Some(prompt) = prompt {
+            let prompt_x = inner.x + inner.width.saturating_sub(prompt_width) / 2;
+            buf.set_line(
+                prompt_x,
+                line_y,
+                &Line::from(Span::styled(prompt, Style::default().fg(Color::Red))),
+                inner.width,
+            );
+        }
+    }
+}
+
+/// Widget for the prompt asking where to forward an email
+pub struct ForwardPromptWidget<'a> {
+    input: &'a ForwardInput,
+}
+
+impl<'a> ForwardPromptWidget<'a> {
+    pub fn new(input: &'a ForwardInput) -> Self {
+        Self { input }
+    }
+}
+
+impl Widget for ForwardPromptWidget<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let question = format!("Forward \"{}\" to:", self.input.subject);
+        let field = format!("> {}▏", self.input.text);
+        let help_text = "Enter: send | Esc: cancel | commas separate addresses";
+        let box_width = 60.min(area.width.saturating_sub(4));
+        // Question, field, a blank line and the help text, inside the borders
+        let box_height = 6;
+
+        let x = area.x + (area.width.saturating_sub(box_width)) / 2;
+        let y = area.y + (area.height.saturating_sub(box_height)) / 2;
+        let modal_area = Rect::new(x, y, box_width, box_height);
+
+        // Clear the area behind the modal
+        for row in modal_area.y..modal_area.y + modal_area.height {
+            for col in modal_area.x..modal_area.x + modal_area.width {
+                buf[(col, row)].set_char(' ');
+                buf[(col, row)].set_style(Style::default());
+            }
+        }
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(" Forward ")
+            .border_style(Style::default().fg(Color::Cyan));
+        let inner = block.inner(modal_area);
+        block.render(modal_area, buf);
+
+        let width = inner.width.saturating_sub(1);
+        buf.set_line(
+            inner.x + 1,
+            inner.y,
+            &Line::from(Span::styled(question, Style::default().fg(Color::White))),
+            width,
+        );
+        buf.set_line(
+            inner.x + 1,
+            inner.y + 1,
+            &Line::from(Span::styled(field, Style::default().fg(Color::Yellow))),
+            width,
+        );
+        buf.set_line(
+            inner.x + 1,
+            inner.y + 3,
+            &Line::from(Span::styled(
+                help_text,
+                Style::default().fg(Color::DarkGray),
+            )),
+            width,
+        );
     }
 }
 
@@ -1634,6 +3982,92 @@ impl AccountSelection {
     }
 }
 
+/// Modal list for a picker
+pub struct PickerWidget<'a> {
+    picker: &'a Picker,
+}
+
+impl<'a> PickerWidget<'a> {
+    pub fn new(picker: &'a Picker) -> Self {
+        Self { picker }
+    }
+}
+
+impl Widget for PickerWidget<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        use unicode_width::UnicodeWidthStr;
+
+        let help_text = "j/↓: next | k/↑: prev | Enter: choose | Esc: cancel";
+        let widest = self
+            .picker
+            .items
+            .iter()
+            .map(|item| item.width() + 2)
+            .chain([help_text.width(), self.picker.title.width() + 2])
+            .max()
+            .unwrap_or_default() as u16;
+        let box_width = (widest + 4).min(area.width.saturating_sub(4));
+        // Items, a blank line and the help text, inside the borders
+        let box_height = (self.picker.items.len() as u16 + 4).min(area.height.saturating_sub(2));
+
+        let x = area.x + (area.width.saturating_sub(box_width)) / 2;
+        let y = area.y + (area.height.saturating_sub(box_height)) / 2;
+        let modal_area = Rect::new(x, y, box_width, box_height);
+
+        // Clear the area behind the modal
+        for row in modal_area.y..modal_area.y + modal_area.height {
+            for col in modal_area.x..modal_area.x + modal_area.width {
+                buf[(col, row)].set_char(' ');
+                buf[(col, row)].set_style(Style::default());
+            }
+        }
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(format!(" {} ", self.picker.title))
+            .border_style(Style::default().fg(Color::Cyan));
+        let inner = block.inner(modal_area);
+        block.render(modal_area, buf);
+
+        // Keep the selection in view when there are more items than rows
+        let rows = inner.height.saturating_sub(2) as usize;
+        let first = (self.picker.selected + 1).saturating_sub(rows);
+        for (row, (i, item)) in self
+            .picker
+            .items
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(rows)
+            .enumerate()
+        {
+            let style = if i == self.picker.selected {
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+            buf.set_line(
+                inner.x + 1,
+                inner.y + row as u16,
+                &Line::from(Span::styled(item.as_str(), style)),
+                inner.width.saturating_sub(1),
+            );
+        }
+
+        buf.set_line(
+            inner.x + 1,
+            inner.y + inner.height.saturating_sub(1),
+            &Line::from(Span::styled(
+                help_text,
+                Style::default().fg(Color::DarkGray),
+            )),
+            inner.width.saturating_sub(1),
+        );
+    }
+}
+
 /// Widget for account selection
 pub struct AccountSelectWidget<'a> {
     selection: &'a AccountSelection,
@@ -1667,10 +4101,14 @@ impl Widget for AccountSelectWidget<'_> {
                 Style::default()
             };
 
-            let line = format!("{} ({})", name, account.email);
-            let span = Span::styled(line, style);
+            // The name takes the account's accent color, as its views will
+            let name_style = account.accent().map_or(style, |color| style.fg(color));
+            let line = Line::from(vec![
+                Span::styled(name.as_str(), name_style),
+                Span::styled(format!(" ({})", account.email), style),
+            ]);
 
-            buf.set_line(inner.x, inner.y + i as u16, &Line::from(span), inner.width);
+            buf.set_line(inner.x, inner.y + i as u16, &line, inner.width);
         }
 
         // Help text at the bottom
@@ -1692,6 +4130,33 @@ impl Widget for AccountSelectWidget<'_> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_picker_shows_items_and_keeps_selection_in_view() {
+        let mut picker = Picker::new(
+            PickerTarget::Plugin('X'),
+            "Snooze until".to_string(),
+            (1..=20).map(|i| format!("item {}", i)).collect(),
+        );
+        for _ in 0..15 {
+            picker.select_next();
+        }
+        let area = Rect::new(0, 0, 60, 12);
+        let mut buf = Buffer::empty(area);
+        PickerWidget::new(&picker).render(area, &mut buf);
+        let text: String = (0..area.height)
+            .map(|y| {
+                (0..area.width)
+                    .map(|x| buf[(x, y)].symbol())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert!(text.contains("Snooze until"));
+        assert!(text.contains("item 16"));
+        assert!(!text.contains("item 1 "));
+        assert_eq!(picker.current_item(), Some("item 16"));
+    }
+
     #[test]
     fn test_confirm_action_archive_emails() {
         let action = ConfirmAction::ArchiveEmails {
@@ -1750,6 +4215,69 @@ mod tests {
         let msg = action.message();
         assert!(msg.contains("Delete entire thread"));
         assert!(msg.contains("3 email(s)"));
+        assert!(msg.contains("move to Trash"));
+    }
+
+    #[test]
+    fn test_large_delete_asks_for_the_word_to_be_typed() {
+        let mut state = UiState::new();
+        state.typed_confirm_above = 10;
+        state.set_confirm(ConfirmAction::DeleteSelected { count: 10 });
+        assert_eq!(state.confirm_input, None);
+        state.set_confirm(ConfirmAction::ArchiveSelected { count: 50 });
+        assert_eq!(state.confirm_input, None);
+        state.set_confirm(ConfirmAction::DeleteSelected { count: 11 });
+        assert_eq!(
+            state.confirm_input,
+            Some(TypedConfirm::new(vec!["delete".to_string()]))
+        );
+        state.clear_confirm();
+        assert_eq!(state.confirm_input, None);
+
+        let action = ConfirmAction::DeleteSelected { count: 11 };
+        let mut typed = TypedConfirm::new(vec!["delete".to_string()]);
+        typed.text = "del".to_string();
+        let area = Rect::new(0, 0, 100, 20);
+        let mut buf = Buffer::empty(area);
+        ConfirmDialogWidget::new(&action, None)
+            .typed(Some(&typed))
+            .render(area, &mut buf);
+        let text = buffer_text(&buf);
+        assert!(text.contains("⚠ Delete"));
+        assert!(text.contains("Type \"delete\" and press Enter (Esc cancels): del"));
+        assert!(!text.contains("(y/n)"));
+    }
+
+    #[test]
+    fn test_very_large_operation_asks_for_the_count_or_sender() {
+        let mut state = UiState::new();
+        state.typed_confirm_above = 10;
+        state.typed_count_confirm_above = 1000;
+        state.set_confirm(ConfirmAction::ArchiveEmails {
+            sender: "news@example.com".to_string(),
+            count: 1200,
+            filtered: false,
+        });
+        let mut typed = state.confirm_input.clone().unwrap();
+        assert_eq!(typed.choices(), "\"1200\" or \"news@example.com\"");
+        for answer in ["1200", "1,200", " News@Example.com "] {
+            typed.text = answer.to_string();
+            assert!(typed.is_answered(), "{}", answer);
+        }
+        typed.text = "y".to_string();
+        assert!(!typed.is_answered());
+
+        // Past both thresholds a delete wants the count rather than the word
+        state.set_confirm(ConfirmAction::DeleteSelected { count: 1001 });
+        assert_eq!(
+            state
+                .confirm_input
+                .as_ref()
+                .map(|typed| typed.answers.clone()),
+            Some(vec!["1001".to_string()])
+        );
+        state.set_confirm(ConfirmAction::ArchiveSelected { count: 1000 });
+        assert_eq!(state.confirm_input, None);
     }
 
     #[test]
@@ -1778,13 +4306,73 @@ mod tests {
         let mut state = UiState::new();
         assert!(state.status_message.is_none());
 
-        state.set_status("Loading...");
-        assert_eq!(state.status_message, Some("Loading...".to_string()));
+        state.set_alert("Error: offline");
+        assert_eq!(state.status_message, Some("Error: offline".to_string()));
 
         state.clear_status();
         assert!(state.status_message.is_none());
     }
 
+    #[test]
+    fn test_status_messages_queue_behind_the_shown_one() {
+        let mut state = UiState::new();
+        state.set_alert("Error: timeout");
+        state.set_alert("Error: timeout");
+        state.set_alert("Error: offline");
+        assert_eq!(state.status_message.as_deref(), Some("Error: timeout"));
+        assert_eq!(state.queued_status_count(), 1);
+
+        // Messages set while busy wait for the operation to finish
+        state.clear_status();
+        state.set_busy("Archiving...");
+        state.set_alert("Error: refused");
+        assert_eq!(state.status_message.as_deref(), Some("Archiving..."));
+        state.clear_busy();
+        assert_eq!(state.status_message.as_deref(), Some("Error: offline"));
+        state.clear_status();
+        assert_eq!(state.status_message.as_deref(), Some("Error: refused"));
+        state.clear_status();
+        assert!(!state.has_status());
+
+        let history: Vec<&str> = state.status_history().map(|(_, m)| m.as_str()).collect();
+        assert_eq!(
+            history,
+            [
+                "Error: refused",
+                "Error: offline",
+                "Error: timeout",
+                "Error: timeout"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_info_messages_show_as_toasts_and_warnings_as_modals() {
+        let mut state = UiState::new();
+        state.set_status("Copied to clipboard");
+        state.set_status("Copied to clipboard");
+        assert!(!state.has_status());
+        let toasts: Vec<&str> = state.toasts().map(|t| t.message.as_str()).collect();
+        assert_eq!(toasts, ["Copied to clipboard"]);
+
+        for n in 0..MAX_TOASTS {
+            state.set_status(format!("Archived {} emails", n));
+        }
+        assert_eq!(state.toasts().count(), MAX_TOASTS);
+        assert_eq!(state.toasts().next().unwrap().message, "Archived 0 emails");
+
+        // Toasts time out on their own
+        for toast in &mut state.toasts {
+            toast.shown_at -= Duration::from_secs(20);
+        }
+        state.tick_status();
+        assert_eq!(state.toasts().count(), 0);
+
+        state.set_status(format!("{} Sync failed", WARNING_CHAR));
+        assert!(state.has_status());
+        assert_eq!(state.toasts().count(), 0);
+    }
+
     #[test]
     fn test_enter_filter_input_mode_with_active_filter_snapshots_it() {
         let mut state = UiState::new();
@@ -1815,6 +4403,28 @@ mod tests {
         assert_eq!(state.revert_filter(), Some("original".to_string()));
     }
 
+    #[test]
+    fn test_filter_history_steps_back_and_restores_the_typed_query() {
+        let mut state = UiState::new();
+        for query in ["alice", "news", "alice"] {
+            state.enter_filter_input_mode(None);
+            state.set_filter_query(query);
+            state.remember_filter();
+        }
+        state.enter_filter_input_mode(None);
+        state.append_filter_char('b');
+        assert!(!state.filter_history_forward());
+
+        assert!(state.filter_history_back());
+        assert_eq!(state.filter_query(), "alice");
+        assert!(state.filter_history_back());
+        assert_eq!(state.filter_query(), "news");
+        assert!(!state.filter_history_back());
+        assert!(state.filter_history_forward());
+        assert!(state.filter_history_forward());
+        assert_eq!(state.filter_query(), "b");
+    }
+
     fn buffer_text(buf: &Buffer) -> String {
         let area = buf.area();
         let mut text = String::new();
@@ -1837,6 +4447,403 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_group_list_shows_load_more_hint() {
+        let mut app = App::new();
+        app.set_emails(vec![create_test_email("1", "alice@example.com")]);
+        let area = Rect::new(0, 0, 60, 10);
+
+        let mut buf = Buffer::empty(area);
+        GroupListWidget::new(&app, 0).render(area, &mut buf);
+        assert!(!buffer_text(&buf).contains("older emails"));
+
+        app.load_more_count = 5000;
+        let mut buf = Buffer::empty(area);
+        GroupListWidget::new(&app, 0).render(area, &mut buf);
+        assert!(buffer_text(&buf).contains("L: load next 5,000 older emails"));
+    }
+
+    #[test]
+    fn test_main_view_uses_account_accent() {
+        let mut app = App::new();
+        app.set_emails(vec![create_test_email("1", "alice@example.com")]);
+        let area = Rect::new(0, 0, 80, 10);
+
+        let mut buf = Buffer::empty(area);
+        GroupListWidget::new(&app, 0).render(area, &mut buf);
+        assert!(!buffer_text(&buf).contains(" work "));
+        assert_eq!(buf[(0, 5)].fg, Color::Reset);
+
+        app.accent = Some(("work".to_string(), Color::Magenta));
+        let mut buf = Buffer::empty(area);
+        GroupListWidget::new(&app, 0).render(area, &mut buf);
+        let top: String = (0..area.width).map(|x| buf[(x, 0)].symbol()).collect();
+        assert!(top.trim_end_matches('┐').ends_with(" work "));
+        assert_eq!(buf[(0, 5)].fg, Color::Magenta);
+        assert_eq!(buf[(2, 0)].fg, Color::Magenta);
+    }
+
+    #[test]
+    fn test_group_list_shows_sender_note() {
+        let mut app = App::new();
+        app.set_emails(vec![
+            create_test_email("1", "alice@example.com"),
+            create_test_email("2", "bob@example.com"),
+        ]);
+        app.notes.set("alice@example.com", "keep receipts");
+        let area = Rect::new(0, 0, 70, 10);
+
+        let mut buf = Buffer::empty(area);
+        GroupListWidget::new(&app, 0).render(area, &mut buf);
+        let text = buffer_text(&buf);
+        assert!(text.contains("keep receipts"));
+        assert_eq!(text.matches("📝").count(), 1);
+    }
+
+    #[test]
+    fn test_group_list_marks_selected_groups() {
+        let mut app = App::new();
+        app.set_emails(vec![
+            create_test_email("1", "alice@example.com"),
+            create_test_email("2", "bob@example.com"),
+        ]);
+        app.toggle_group_selection();
+        let area = Rect::new(0, 0, 80, 10);
+
+        let mut buf = Buffer::empty(area);
+        GroupListWidget::new(&app, 0).render(area, &mut buf);
+        let text = buffer_text(&buf);
+        assert!(text.contains("(1 selected)"));
+        assert_eq!(text.matches("●").count(), 1);
+    }
+
+    #[test]
+    fn test_group_list_shows_done_groups() {
+        let mut app = App::new();
+        app.set_emails(vec![
+            create_test_email("1", "alice@example.com"),
+            create_test_email("2", "bob@example.com"),
+        ]);
+        app.toggle_group_done();
+        let area = Rect::new(0, 0, 80, 10);
+
+        let mut buf = Buffer::empty(area);
+        GroupListWidget::new(&app, 0).render(area, &mut buf);
+        let text = buffer_text(&buf);
+        assert!(text.contains("(1 done)"));
+        assert_eq!(text.matches("— done").count(), 1);
+    }
+
+    #[test]
+    fn test_age_buckets() {
+        let now = chrono::Utc::now();
+        let ago = |days| now - chrono::Duration::days(days);
+        assert_eq!(Age::of(&ago(0), now), Age::Fresh);
+        assert_eq!(Age::of(&ago(6), now), Age::Fresh);
+        assert_eq!(Age::of(&ago(7), now), Age::Weeks);
+        assert_eq!(Age::of(&ago(45), now), Age::Months);
+        assert_eq!(Age::of(&ago(400), now), Age::Years);
+    }
+
+    #[test]
+    fn test_group_list_colors_rows_by_newest_email_age() {
+        let mut app = App::new();
+        let mut old = create_test_email("1", "old@example.com");
+        old.date = chrono::Utc::now() - chrono::Duration::days(800);
+        app.set_emails(vec![
+            old,
+            create_test_email("2", "new@example.com"),
+            create_test_email("3", "new@example.com"),
+        ]);
+        // Move the cursor off the old group so its highlight doesn't hide the color
+        app.selected_group = 0;
+        assert_eq!(app.current_group().unwrap().key, "new@example.com");
+        let area = Rect::new(0, 0, 80, 10);
+
+        let mut buf = Buffer::empty(area);
+        GroupListWidget::new(&app, 0).render(area, &mut buf);
+        // Rows start inside the border: the selected group, then the stale one
+        let old_row = (0..area.width)
+            .find(|&x| buf[(x, 2)].symbol() == "o")
+            .unwrap();
+        assert_eq!(buf[(old_row, 2)].fg, Color::Red);
+    }
+
+    #[test]
+    fn test_group_list_shows_hidden_small_groups() {
+        let mut app = App::new();
+        app.set_emails(vec![
+            create_test_email("1", "alice@example.com"),
+            create_test_email("2", "alice@example.com"),
+            create_test_email("3", "bob@example.com"),
+        ]);
+        app.min_group_size = 2;
+        let area = Rect::new(0, 0, 100, 10);
+
+        let mut buf = Buffer::empty(area);
+        GroupListWidget::new(&app, 0).render(area, &mut buf);
+        let text = buffer_text(&buf);
+        assert!(text.contains("[1 under 2 hidden, H to show]"));
+        assert!(!text.contains("bob@example.com"));
+    }
+
+    #[test]
+    fn test_group_list_marks_kept_groups_while_sweeping() {
+        let mut app = App::new();
+        app.set_emails(vec![
+            create_test_email("1", "alice@example.com"),
+            create_test_email("2", "bob@example.com"),
+        ]);
+        app.start_sweep();
+        app.toggle_keep();
+        let area = Rect::new(0, 0, 100, 10);
+
+        let mut buf = Buffer::empty(area);
+        GroupListWidget::new(&app, 0).render(area, &mut buf);
+        let text = buffer_text(&buf);
+        assert!(text.contains("[Sweep: keeping 1 groups, 0 threads]"));
+        assert_eq!(text.matches("✓").count(), 1);
+    }
+
+    #[test]
+    fn test_group_list_shows_usual_action() {
+        let mut app = App::new();
+        app.set_emails(vec![create_test_email("1", "alice@example.com")]);
+        let area = Rect::new(0, 0, 70, 10);
+
+        let mut buf = Buffer::empty(area);
+        GroupListWidget::new(&app, 0).render(area, &mut buf);
+        assert!(!buffer_text(&buf).contains("usual"));
+
+        for _ in 0..crate::habits::USUAL_AFTER {
+            app.habits
+                .record("alice@example.com", crate::policy::PolicyAction::Delete);
+        }
+        let mut buf = Buffer::empty(area);
+        GroupListWidget::new(&app, 0).render(area, &mut buf);
+        assert!(buffer_text(&buf).contains("usual: delete"));
+    }
+
+    #[test]
+    fn test_confirm_dialog_shows_note_for_targeted_sender() {
+        let mut app = App::new();
+        app.set_emails(vec![create_test_email("1", "alice@example.com")]);
+        app.notes.set("alice@example.com", "keep receipts");
+        app.enter(); // Enter email list
+
+        let archive = ConfirmAction::ArchiveEmails {
+            sender: "alice@example.com".to_string(),
+            count: 1,
+            filtered: false,
+        };
+        assert_eq!(confirm_note(&app, &archive), Some("keep receipts"));
+        let thread = ConfirmAction::ArchiveThread {
+            thread_email_count: 1,
+        };
+        assert_eq!(confirm_note(&app, &thread), Some("keep receipts"));
+        assert_eq!(confirm_note(&app, &ConfirmAction::Quit), None);
+
+        let area = Rect::new(0, 0, 80, 20);
+        let mut buf = Buffer::empty(area);
+        ConfirmDialogWidget::new(&archive, confirm_note(&app, &archive)).render(area, &mut buf);
+        let text = buffer_text(&buf);
+        assert!(text.contains("Archive 1 email(s) from alice@example.com?"));
+        assert!(text.contains("keep receipts"));
+    }
+
+    #[test]
+    fn test_confirm_dialog_samples_the_affected_subjects() {
+        let mut app = App::new();
+        let emails: Vec<_> = (0..7)
+            .map(|i| {
+                let mut email = create_test_email(&i.to_string(), "news@example.com");
+                email.subject = format!("Issue {}", i);
+                email.date = chrono::Utc::now() - chrono::Duration::days(i);
+                email
+            })
+            .collect();
+        app.set_emails(emails);
+        app.enter(); // Enter email list
+
+        let action = ConfirmAction::ArchiveEmails {
+            sender: "news@example.com".to_string(),
+            count: 7,
+            filtered: false,
+        };
+        let affected = affected_emails(&app, &action);
+        assert_eq!(affected.len(), 7);
+        assert_eq!(affected[0].subject, "Issue 0");
+        assert!(affected_emails(&app, &ConfirmAction::Quit).is_empty());
+
+        let area = Rect::new(0, 0, 80, 20);
+        let mut buf = Buffer::empty(area);
+        ConfirmDialogWidget::new(&action, None)
+            .affected(affected)
+            .render(area, &mut buf);
+        let text = buffer_text(&buf);
+        assert!(text.contains("Issue 0"));
+        assert!(text.contains("Issue 4"));
+        assert!(!text.contains("Issue 5"));
+        assert!(text.contains("and 2 more"));
+    }
+
+    #[test]
+    fn test_email_list_shows_snippet_after_subject() {
+        let mut with_preview = create_test_email("1", "alice@example.com");
+        with_preview.snippet = "Lunch on Friday?".to_string();
+        let mut app = App::new();
+        // Without a body preview the snippet falls back to the subject
+        let mut without_preview = create_test_email("2", "alice@example.com");
+        without_preview.snippet = "Subject".to_string();
+        app.set_emails(vec![with_preview, without_preview]);
+        app.enter(); // Enter email list
+
+        let area = Rect::new(0, 0, 60, 10);
+        let mut buf = Buffer::empty(area);
+        let mut state = TableState::default();
+        EmailListWidget::new(&app).render(area, &mut buf, &mut state);
+
+        let text = buffer_text(&buf);
+        assert!(text.contains("Subject  Lunch on Friday?"));
+        assert!(!text.contains("Subject  Subject"));
+    }
+
+    #[test]
+    fn test_email_list_comfortable_rows_span_two_lines() {
+        let mut email = create_test_email("1", "alice@example.com");
+        email.snippet = "Lunch on Friday?".to_string();
+        let mut app = App::new();
+        app.set_emails(vec![email]);
+        app.enter(); // Enter email list
+        app.toggle_row_density();
+        assert_eq!(app.row_density, RowDensity::Comfortable);
+
+        let area = Rect::new(0, 0, 60, 10);
+        let mut buf = Buffer::empty(area);
+        let mut state = TableState::default();
+        EmailListWidget::new(&app).render(area, &mut buf, &mut state);
+
+        let row = |y: u16| -> String { (0..area.width).map(|x| buf[(x, y)].symbol()).collect() };
+        assert!(row(1).contains("alice@example.com"));
+        assert!(row(2).contains("Subject  Lunch on Friday?"));
+    }
+
+    #[test]
+    fn test_text_view_shows_auth_badges() {
+        let mut app = App::new();
+        let mut email = create_test_email("1", "alice@example.com");
+        email.auth = crate::auth::AuthResults {
+            dkim: Some(Verdict::Pass),
+            spf: None,
+            dmarc: Some(Verdict::Fail),
+        };
+        app.set_emails(vec![email]);
+        app.enter_text_view("1");
+
+        let state = TextViewState::Loaded("Body".to_string());
+        let area = Rect::new(0, 0, 60, 12);
+        let mut buf = Buffer::empty(area);
+        TextViewWidget::new(&app, 0, &state, false).render(area, &mut buf);
+        assert!(buffer_text(&buf).contains("Auth: DKIM ✓ DMARC ✗"));
+    }
+
+    #[test]
+    fn test_email_list_shows_spam_score() {
+        let mut email = create_test_email("1", "alice@example.com");
+        email.spam_score = Some(6.25);
+        let mut app = App::new();
+        app.set_emails(vec![email]);
+        app.enter(); // Enter email list
+        app.toggle_spam_score_sort();
+
+        let area = Rect::new(0, 0, 80, 10);
+        let mut buf = Buffer::empty(area);
+        let mut state = TableState::default();
+        EmailListWidget::new(&app).render(area, &mut buf, &mut state);
+
+        let text = buffer_text(&buf);
+        assert!(text.contains("[6.2] Subject"));
+        assert!(text.contains("[by spam score]"));
+    }
+
+    #[test]
+    fn test_format_thousands() {
+        assert_eq!(format_thousands(0), "0");
+        assert_eq!(format_thousands(999), "999");
+        assert_eq!(format_thousands(5000), "5,000");
+        assert_eq!(format_thousands(1234567), "1,234,567");
+    }
+
+    #[test]
+    fn test_busy_modal_lists_worker_progress() {
+        let workers = vec![
+            WorkerProgress {
+                folder: "INBOX".to_string(),
+                range: Some((8001, 12000)),
+                fetched: 2140,
+                total: 4000,
+            },
+            WorkerProgress {
+                folder: "[Gmail]/Sent Mail".to_string(),
+                range: None,
+                fetched: 12,
+                total: 12,
+            },
+        ];
+        assert_eq!(
+            workers[0].line(1, 2),
+            "worker 1/2  INBOX 8,001-12,000: 2,140/4,000"
+        );
+        assert_eq!(workers[1].line(2, 2), "worker 2/2  Sent Mail: 12/12");
+
+        let area = Rect::new(0, 0, 80, 12);
+        let mut buf = Buffer::empty(area);
+        BusyModalWidget::new("Loading 2152 of 4012...", '⠋')
+            .workers(&workers)
+            .render(area, &mut buf);
+        let text = buffer_text(&buf);
+        assert!(text.contains("Loading 2152 of 4012..."));
+        assert!(text.contains("worker 1/2  INBOX 8,001-12,000: 2,140/4,000"));
+        assert!(text.contains("worker 2/2  Sent Mail: 12/12"));
+    }
+
+    #[test]
+    fn test_server_alerts_wait_until_nothing_else_is_shown() {
+        let mut ui_state = UiState::new();
+        ui_state.set_busy("Loading emails...");
+        ui_state.queue_server_alert("Your mailbox is 95% full".to_string());
+        ui_state.queue_server_alert("Your mailbox is 95% full".to_string());
+        ui_state.show_next_server_alert();
+        assert_eq!(
+            ui_state.status_message.as_deref(),
+            Some("Loading emails...")
+        );
+
+        ui_state.clear_busy();
+        ui_state.show_next_server_alert();
+        assert_eq!(
+            ui_state.status_message.as_deref(),
+            Some("⚠ Server alert: Your mailbox is 95% full")
+        );
+
+        // The repeat was dropped, and a later repeat isn't shown again
+        ui_state.clear_status();
+        ui_state.queue_server_alert("Your mailbox is 95% full".to_string());
+        ui_state.show_next_server_alert();
+        assert!(!ui_state.has_status());
+    }
+
+    #[test]
+    fn test_status_modal_wraps_long_messages() {
+        let message = "⚠ Server alert: Please log in via your web browser, then try again";
+        let area = Rect::new(0, 0, 30, 10);
+        let mut buf = Buffer::empty(area);
+        StatusModalWidget::new(message).render(area, &mut buf);
+        let text = buffer_text(&buf);
+        assert!(text.contains("⚠ Server alert: Pleas"));
+        assert!(text.contains("then try again"));
+    }
+
     #[test]
     fn test_group_list_text_filter_no_matches_shows_empty_message() {
         let mut app = App::new();
@@ -1985,7 +4992,7 @@ mod tests {
         // Widget: 20 wide (inner 18 after borders), 10 tall (inner 8 after borders)
         let area = Rect::new(0, 0, 20, 10);
         let mut buf = Buffer::empty(area);
-        let widget = TextViewWidget::new(&app, 0, &state);
+        let widget = TextViewWidget::new(&app, 0, &state, false);
         widget.render(area, &mut buf);
 
         // With wrapping enabled, the 35-char body should span two visual rows.
@@ -2028,7 +5035,7 @@ mod tests {
 
         // Render with no scroll — MARKER should be visible
         let mut buf_no_scroll = Buffer::empty(area);
-        let widget = TextViewWidget::new(&app, 0, &state);
+        let widget = TextViewWidget::new(&app, 0, &state, false);
         widget.render(area, &mut buf_no_scroll);
         let text_no_scroll = buffer_text(&buf_no_scroll);
         assert!(
@@ -2040,7 +5047,7 @@ mod tests {
         // The "From:" header line scrolls off, so we should still see MARKER
         // but "From:" should no longer be visible.
         let mut buf_scrolled = Buffer::empty(area);
-        let widget = TextViewWidget::new(&app, 1, &state);
+        let widget = TextViewWidget::new(&app, 1, &state, false);
         widget.render(area, &mut buf_scrolled);
         let text_scrolled = buffer_text(&buf_scrolled);
 
@@ -2054,4 +5061,332 @@ mod tests {
             "MARKER should still be visible after scrolling 1 row"
         );
     }
+
+    #[test]
+    fn test_wrap_text_breaks_at_width() {
+        assert_eq!(wrap_text("abcdef", 4), vec!["abcd", "ef"]);
+        assert_eq!(wrap_text("abc", 10), vec!["abc"]);
+        assert_eq!(wrap_text("", 10), vec![""]);
+    }
+
+    #[test]
+    fn test_wrap_text_counts_wide_characters() {
+        // Each CJK character is two columns wide
+        assert_eq!(wrap_text("日本語", 4), vec!["日本", "語"]);
+    }
+
+    #[test]
+    fn test_thread_view_inline_bodies_shows_bodies_and_loading() {
+        let now = chrono::Utc::now();
+        let mut first = create_test_email("1", "alice@example.com");
+        first.thread_id = "thread_a".to_string();
+        first.date = now - chrono::Duration::hours(1);
+        let mut second = create_test_email("2", "alice@example.com");
+        second.thread_id = "thread_a".to_string();
+        second.date = now;
+
+        let mut app = App::new();
+        app.set_emails(vec![first, second]);
+        app.enter(); // Enter email list
+        app.enter(); // Enter thread view
+        app.toggle_thread_inline_bodies();
+        app.set_email_body("1", "FIRST BODY".to_string());
+
+        let area = Rect::new(0, 0, 60, 20);
+        let mut buf = Buffer::empty(area);
+        let errors = HashMap::new();
+        let mut state = TableState::default();
+        // Select the oldest message so the whole conversation is in view
+        app.selected_thread_email = Some(0);
+        ThreadViewWidget::new(&app, &errors).render(area, &mut buf, &mut state);
+
+        let text = buffer_text(&buf);
+        assert!(text.contains("FIRST BODY"));
+        assert!(text.contains("Loading..."));
+    }
+
+    #[test]
+    fn test_thread_view_inline_bodies_shows_fetch_errors() {
+        let mut app = App::new();
+        app.set_emails(vec![create_test_email("1", "alice@example.com")]);
+        app.enter(); // Enter email list
+        app.enter(); // Enter thread view
+        app.toggle_thread_inline_bodies();
+
+        let area = Rect::new(0, 0, 60, 10);
+        let mut buf = Buffer::empty(area);
+        let mut errors = HashMap::new();
+        errors.insert("1".to_string(), "timed out".to_string());
+        let mut state = TableState::default();
+        ThreadViewWidget::new(&app, &errors).render(area, &mut buf, &mut state);
+
+        assert!(buffer_text(&buf).contains("Error: timed out"));
+    }
+
+    #[test]
+    fn test_thread_view_inline_collapsed_message_hides_body() {
+        let mut app = App::new();
+        app.set_emails(vec![create_test_email("1", "alice@example.com")]);
+        app.enter(); // Enter email list
+        app.enter(); // Enter thread view
+        app.toggle_thread_inline_bodies();
+        app.set_email_body("1", "HIDDEN BODY".to_string());
+        app.toggle_current_thread_email_collapsed();
+
+        let area = Rect::new(0, 0, 80, 10);
+        let mut buf = Buffer::empty(area);
+        let errors = HashMap::new();
+        let mut state = TableState::default();
+        ThreadViewWidget::new(&app, &errors).render(area, &mut buf, &mut state);
+
+        let text = buffer_text(&buf);
+        assert!(!text.contains("HIDDEN BODY"));
+        assert!(text.contains("▸"));
+        assert!(text.contains("Snippet"));
+    }
+
+    #[test]
+    fn test_text_view_shows_to_and_cc_headers() {
+        let mut app = App::new();
+        let mut email = create_test_email("1", "alice@example.com");
+        email.to = vec!["Bob <bob@example.com>".to_string()];
+        email.cc = vec!["carol@example.com".to_string()];
+        app.set_emails(vec![email]);
+        app.enter_text_view("1");
+
+        let state = TextViewState::Loaded("Body".to_string());
+        let area = Rect::new(0, 0, 60, 12);
+        let mut buf = Buffer::empty(area);
+        TextViewWidget::new(&app, 0, &state, false).render(area, &mut buf);
+
+        let text = buffer_text(&buf);
+        assert!(text.contains("To: Bob <bob@example.com>"));
+        assert!(text.contains("Cc: carol@example.com"));
+    }
+
+    #[test]
+    fn test_text_view_marks_truncated_body() {
+        let mut app = App::new();
+        app.set_emails(vec![create_test_email("1", "alice@example.com")]);
+        app.enter_text_view("1");
+
+        let state = TextViewState::Loaded("Body".to_string());
+        let area = Rect::new(0, 0, 60, 12);
+
+        let mut buf = Buffer::empty(area);
+        TextViewWidget::new(&app, 0, &state, false).render(area, &mut buf);
+        assert!(!buffer_text(&buf).contains("truncated"));
+
+        let mut buf = Buffer::empty(area);
+        TextViewWidget::new(&app, 0, &state, true).render(area, &mut buf);
+        assert!(buffer_text(&buf).contains("(truncated, press L to load full message)"));
+    }
+
+    #[test]
+    fn test_thread_view_recipients_column_marks_cc_only() {
+        let mut app = App::new();
+        app.set_user_email("me@example.com".to_string());
+        app.thread_recipients_column = true;
+        let mut email = create_test_email("1", "alice@example.com");
+        email.to = vec!["team@example.com".to_string()];
+        email.cc = vec!["me@example.com".to_string()];
+        app.set_emails(vec![email]);
+        app.enter(); // Enter email list
+        app.enter(); // Enter thread view
+
+        let area = Rect::new(0, 0, 120, 6);
+        let mut buf = Buffer::empty(area);
+        let errors = HashMap::new();
+        let mut state = TableState::default();
+        ThreadViewWidget::new(&app, &errors).render(area, &mut buf, &mut state);
+
+        assert!(buffer_text(&buf).contains("(cc) team@example.com +1"));
+    }
+
+    #[test]
+    fn test_duplicates_widget_lists_sets() {
+        let mut app = App::new();
+        let mut first = create_test_email("1", "alice@example.com");
+        first.message_id = Some("<dup@example.com>".to_string());
+        let mut second = create_test_email("2", "alice@example.com");
+        second.message_id = Some("<dup@example.com>".to_string());
+        second.source_folder = "Archive".into();
+        app.set_emails(vec![first, second]);
+        app.enter_duplicates();
+
+        let area = Rect::new(0, 0, 120, 6);
+        let mut buf = Buffer::empty(area);
+        let mut state = TableState::default();
+        DuplicatesWidget::new(&app).render(area, &mut buf, &mut state);
+
+        let text = buffer_text(&buf);
+        assert!(text.contains("1 set(s), 1 extra copies"));
+        assert!(text.contains("2 copies (INBOX, Archive)"));
+    }
+
+    #[test]
+    fn test_duplicates_widget_empty_state() {
+        let mut app = App::new();
+        app.set_emails(vec![create_test_email("1", "alice@example.com")]);
+        app.enter_duplicates();
+
+        let area = Rect::new(0, 0, 60, 6);
+        let mut buf = Buffer::empty(area);
+        let mut state = TableState::default();
+        DuplicatesWidget::new(&app).render(area, &mut buf, &mut state);
+
+        assert!(buffer_text(&buf).contains("No duplicates found"));
+    }
+
+    #[test]
+    fn test_tour_advances_through_steps() {
+        let mut app = App::new();
+        app.set_emails(vec![
+            crate::email::EmailBuilder::new()
+                .id("1")
+                .from("a@example.com")
+                .build(),
+        ]);
+        let mut tour = Tour::new();
+
+        assert!(tour.update(&app));
+        assert_eq!(tour.step(), TourStep::OpenGroup);
+
+        app.enter();
+        assert!(tour.update(&app));
+        assert_eq!(tour.step(), TourStep::Archive);
+
+        app.push_undo(crate::app::UndoEntry {
+            action_type: UndoActionType::Archive,
+            context: UndoContext::Group {
+                sender: "a@example.com".to_string(),
+            },
+            emails: Vec::new(),
+            current_folder: "[Gmail]/All Mail".to_string(),
+        });
+        assert!(tour.update(&app));
+        assert_eq!(tour.step(), TourStep::OpenUndo);
+
+        app.enter_undo_history();
+        assert!(tour.update(&app));
+        assert_eq!(tour.step(), TourStep::Restore);
+
+        app.exit();
+        assert!(!tour.update(&app));
+    }
+
+    #[test]
+    fn test_email_scroll_offset_is_kept_per_group() {
+        let mut ui_state = UiState::new();
+        ui_state.switch_email_scroll_group(Some("alice"));
+        assert_eq!(ui_state.email_scroll_offset(), 0);
+        ui_state.set_email_scroll_offset(12);
+        ui_state.switch_email_scroll_group(Some("alice"));
+        assert_eq!(ui_state.email_scroll_offset(), 12);
+
+        // Another group starts at the top, and alice's offset comes back after it
+        ui_state.switch_email_scroll_group(Some("bob"));
+        assert_eq!(ui_state.email_scroll_offset(), 0);
+        ui_state.set_email_scroll_offset(3);
+        ui_state.switch_email_scroll_group(Some("alice"));
+        assert_eq!(ui_state.email_scroll_offset(), 12);
+        ui_state.switch_email_scroll_group(Some("bob"));
+        assert_eq!(ui_state.email_scroll_offset(), 3);
+    }
+
+    #[test]
+    fn test_undo_entry_names_the_folder_a_move_went_to() {
+        let entry = crate::app::UndoEntry {
+            action_type: UndoActionType::Move,
+            context: UndoContext::Group {
+                sender: "news@example.com".to_string(),
+            },
+            emails: vec![(None, None, "INBOX".to_string()); 2],
+            current_folder: "Receipts".to_string(),
+        };
+        assert_eq!(
+            UndoHistoryWidget::format_entry(&entry),
+            "📁 moved 2 emails from news@example.com to Receipts"
+        );
+        let filed = crate::app::UndoEntry {
+            action_type: UndoActionType::File,
+            ..entry
+        };
+        assert_eq!(
+            UndoHistoryWidget::format_entry(&filed),
+            "🏷️ filed 2 emails from news@example.com in Receipts"
+        );
+    }
+
+    #[test]
+    fn test_processing_rate_per_minute() {
+        assert_eq!(processing_rate(312, Duration::from_secs(7 * 60 + 36)), 41);
+        // The first minute counts as a whole minute
+        assert_eq!(processing_rate(12, Duration::from_secs(5)), 12);
+        assert_eq!(processing_rate(0, Duration::from_secs(600)), 0);
+    }
+
+    #[test]
+    fn test_triage_timer_summarizes_session_only() {
+        let mut app = App::new();
+        let entry = |action_type, count| crate::app::UndoEntry {
+            action_type,
+            context: UndoContext::Group {
+                sender: "a@example.com".to_string(),
+            },
+            emails: vec![(None, None, "INBOX".to_string()); count],
+            current_folder: "[Gmail]/All Mail".to_string(),
+        };
+        app.push_undo(entry(UndoActionType::Archive, 4));
+
+        let timer = TriageTimer::new(Duration::ZERO, &app);
+        app.push_undo(entry(UndoActionType::Archive, 3));
+        app.push_undo(entry(UndoActionType::Delete, 2));
+
+        assert!(timer.is_up());
+        assert_eq!(
+            timer.summary(&app),
+            "⏰ Time's up after 0 min: 3 archived, 2 deleted. Nice work, take a break!"
+        );
+        assert!(!TriageTimer::new(Duration::from_secs(25 * 60), &app).is_up());
+    }
+
+    fn help_keys(view: View, global: bool) -> Vec<(&'static str, &'static str)> {
+        HelpMenuWidget::new(view, global)
+            .help_sections()
+            .into_iter()
+            .flat_map(|(_, entries)| entries)
+            .collect()
+    }
+
+    #[test]
+    fn test_help_lists_only_current_view_bindings() {
+        let group_keys = help_keys(View::GroupList, false);
+        assert!(group_keys.contains(&("Enter", "Open group")));
+        assert!(group_keys.contains(&("Z", "Archive old inbox mail")));
+        assert!(!group_keys.contains(&("Space", "Select/deselect")));
+
+        let body_keys = help_keys(View::EmailBody, false);
+        assert!(body_keys.contains(&("j / ↓", "Scroll down")));
+        assert!(!body_keys.contains(&("j / ↓", "Move down")));
+        assert!(body_keys.contains(&("?", "Toggle this help")));
+    }
+
+    #[test]
+    fn test_help_global_tab_lists_bindings_for_every_view() {
+        let global_keys = help_keys(View::Thread, true);
+        assert!(global_keys.contains(&("G", "Go to bottom")));
+        assert!(global_keys.contains(&("?", "Toggle this help")));
+        assert!(!global_keys.contains(&("v", "Toggle inline bodies")));
+    }
+
+    #[test]
+    fn test_help_sections_keep_section_order() {
+        let titles: Vec<&str> = HelpMenuWidget::new(View::EmailList, false)
+            .help_sections()
+            .into_iter()
+            .map(|(title, _)| title)
+            .collect();
+        assert_eq!(titles, vec!["Navigation", "Actions", "General"]);
+    }
 }
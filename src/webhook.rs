@@ -0,0 +1,161 @@
+//! Posts a JSON summary of each completed bulk action to the configured webhook_url
+
+use anyhow::Result;
+use std::thread;
+
+use crate::http::{self, quote};
+
+/// Seconds curl may spend on one webhook request
+const TIMEOUT_SECS: &str = "10";
+
+/// What a completed bulk action did
+#[derive(Debug, Clone, PartialEq)]
+pub struct BulkSummary {
+    /// "archive" or "delete"
+    pub action: &'static str,
+    /// What the action covered: "group", "groups", "selected", "duplicates",
+    /// "old", "singles", "read", "sweep" or "policy"
+    pub scope: &'static str,
+    /// Sender or domain of the group, for group actions (comma-separated for "groups")
+    pub sender: Option<String>,
+    /// Policy name, for policy actions
+    pub policy: Option<String>,
+    /// Number of emails moved
+    pub count: usize,
+    /// Account the action ran on
+    pub account: String,
+}
+
+impl BulkSummary {
+    /// One-line description, also sent as `text` so Slack incoming webhooks can show it
+    pub fn text(&self) -> String {
+        let verb = if self.action == "delete" {
+            "Deleted"
+        } else {
+            "Archived"
+        };
+        let target = match (&self.sender, &self.policy) {
+            (Some(sender), _) => format!(" from {}", sender),
+            (None, Some(policy)) => format!(" with policy \"{}\"", policy),
+            (None, None) => match self.scope {
+                "duplicates" => " duplicate copies".to_string(),
+                "old" => " old inbox emails".to_string(),
+                "singles" => " single-message emails".to_string(),
+                "read" => " read inbox emails".to_string(),
+                "sweep" => " emails in an inbox sweep".to_string(),
+                _ => " selected emails".to_string(),
+            },
+        };
+        format!(
+            "zeroterm: {} {} email(s){} ({})",
+            verb, self.count, target, self.account
+        )
+    }
+
+    /// Renders the summary as a JSON object
+    pub fn to_json(&self) -> String {
+        serde_json::json!({
+            "text": self.text(),
+            "action": self.action,
+            "scope": self.scope,
+            "sender": self.sender,
+            "policy": self.policy,
+            "count": self.count,
+            "account": self.account,
+        })
+        .to_string()
+    }
+}
+
+/// Sends the summary in the background; failures are only logged so triage never waits on it
+pub fn notify(url: &str, summary: &BulkSummary) {
+    let url = url.to_string();
+    let body = summary.to_json();
+    thread::spawn(move || {
+        if let Err(e) = post_json(&url, &body) {
+            crate::debug_log!("webhook: {:#}", e);
+        }
+    });
+}
+
+/// Sends the summary and waits for the response
+pub fn send(url: &str, summary: &BulkSummary) -> Result<()> {
+    post_json(url, &summary.to_json())
+}
+
+/// Builds the curl config that POSTs a JSON body. The URL goes on curl's stdin with the
+/// rest, as webhook URLs often carry a secret token.
+fn curl_config(url: &str, body: &str) -> String {
+    format!(
+        "url = {}\nheader = {}\ndata-binary = {}\nmax-time = {}\n",
+        quote(url),
+        quote("Content-Type: application/json"),
+        quote(body),
+        TIMEOUT_SECS
+    )
+}
+
+/// POSTs a JSON body with curl
+fn post_json(url: &str, body: &str) -> Result<()> {
+    http::send(&curl_config(url, body), "Webhook").map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary() -> BulkSummary {
+        BulkSummary {
+            action: "archive",
+            scope: "group",
+            sender: Some("news@example.com".to_string()),
+            policy: None,
+            count: 12,
+            account: "personal".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_summary_json() {
+        let json: serde_json::Value = serde_json::from_str(&summary().to_json()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "text": "zeroterm: Archived 12 email(s) from news@example.com (personal)",
+                "action": "archive",
+                "scope": "group",
+                "sender": "news@example.com",
+                "policy": null,
+                "count": 12,
+                "account": "personal",
+            })
+        );
+    }
+
+    #[test]
+    fn test_policy_summary_text() {
+        let policy = BulkSummary {
+            action: "delete",
+            scope: "policy",
+            sender: None,
+            policy: Some("receipts".to_string()),
+            ..summary()
+        };
+        assert_eq!(
+            policy.text(),
+            "zeroterm: Deleted 12 email(s) with policy \"receipts\" (personal)"
+        );
+    }
+
+    #[test]
+    fn test_curl_config_keeps_the_url_off_the_command_line() {
+        let config = curl_config("https://hooks.example.com/T0/secret", "{\"a\":\"b\"}");
+        assert_eq!(
+            config,
+            "url = \"https://hooks.example.com/T0/secret\"\n\
+             header = \"Content-Type: application/json\"\n\
+             data-binary = \"{\\\"a\\\":\\\"b\\\"}\"\n\
+             max-time = 10\n"
+        );
+    }
+}